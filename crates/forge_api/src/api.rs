@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
@@ -23,6 +24,54 @@ pub trait API: Sync + Send {
     /// Executes a chat request and returns a stream of responses
     async fn chat(&self, chat: ChatRequest) -> Result<MpscStream<Result<ChatResponse>>>;
 
+    /// Sends an instruction to the turn running on the conversation, cutting
+    /// the response the model is streaming short to take it
+    async fn steer(&self, conversation_id: &ConversationId, message: String) -> Result<()>;
+
+    /// Subscribes to the lifecycle of the turns of all the conversations:
+    /// their start and end, tool calls, compactions, retries and errors. A
+    /// subscriber that falls behind is told how many events it missed.
+    fn lifecycle_events(&self) -> MpscStream<LifecycleUpdate>;
+
+    /// Starts the task with the agent on a fork of the conversation, in a git
+    /// worktree of its own, and returns without waiting for it. A notification
+    /// is sent once it's done.
+    async fn chat_in_background(
+        &self,
+        conversation_id: &ConversationId,
+        agent: AgentId,
+        task: String,
+    ) -> Result<BackgroundTask>;
+
+    /// Runs the schedules of the workflow without a user, on their cron
+    /// expressions or when the files they watch change, until the stream is
+    /// dropped. Each run is sent once it's done.
+    async fn run_schedules(&self) -> Result<MpscStream<Result<ScheduledRun>>>;
+
+    /// Runs the schedule with the name once, right away
+    async fn run_schedule(&self, name: &str) -> Result<ScheduledRun>;
+
+    /// Runs the steps of the pipeline with the name one after the other. Each
+    /// step is sent once it's done, along with the approvals and questions of
+    /// its agent, and the pipeline stops at the first failed step not allowed
+    /// to fail.
+    async fn run_pipeline(
+        &self,
+        name: &str,
+        inputs: BTreeMap<String, String>,
+    ) -> Result<MpscStream<Result<PipelineEvent>>>;
+
+    /// Returns the background tasks that are still running
+    fn background_tasks(&self) -> Vec<BackgroundTask>;
+
+    /// Takes the approvals and questions of the background tasks waiting for
+    /// the user
+    fn background_requests(&self) -> Vec<BackgroundRequest>;
+
+    /// Waits for the next notification of the background tasks, sent as soon
+    /// as one is done or waits for the user
+    async fn next_notification(&self) -> Option<Notification>;
+
     /// Returns the current environment
     fn environment(&self) -> Environment;
 
@@ -71,6 +120,49 @@ pub trait API: Sync + Send {
         conversation_id: &ConversationId,
     ) -> Result<CompactionResult>;
 
+    /// Summarizes the conversation into the decisions, open tasks and files
+    /// touched, replacing its history with the summary when `replace` is set.
+    /// Returns `None` when there's nothing to summarize yet.
+    async fn summarize_conversation(
+        &self,
+        conversation_id: &ConversationId,
+        replace: bool,
+    ) -> Result<Option<String>>;
+
+    /// Generates the repo map of the conversation again from the workspace as
+    /// it is now. Returns `None` when there's no source file to map.
+    async fn refresh_repo_map(&self, conversation_id: &ConversationId) -> Result<Option<String>>;
+
+    /// Exports the context of the conversation as a markdown transcript or a
+    /// standalone HTML page, with the tool outputs collapsed, or as JSON
+    async fn export_context(
+        &self,
+        conversation_id: &ConversationId,
+        format: ExportFormat,
+    ) -> Result<String>;
+
+    /// Shows which messages were added, compacted, truncated or removed
+    /// between the last two requests of the conversation
+    async fn context_diff(&self, conversation_id: &ConversationId) -> Result<Option<ContextDiff>>;
+
+    /// Returns the tokens, cost, tool calls and time spent over the turns of
+    /// the conversation
+    async fn session_stats(&self, conversation_id: &ConversationId) -> Result<SessionStats>;
+
+    /// Removes the sessions, request dumps and snapshots beyond the limits of
+    /// the retention, except the session kept, or only reports them on a dry
+    /// run
+    async fn prune(
+        &self,
+        retention: &Retention,
+        keep: Option<ConversationId>,
+        dry_run: bool,
+    ) -> Result<PruneReport>;
+
+    /// Sends a recorded request again, possibly to another model, and returns
+    /// the answer. The tools called in the answer aren't run.
+    async fn replay(&self, model: &ModelId, context: Context) -> Result<ChatCompletionMessageFull>;
+
     /// Executes a shell command using the shell tool infrastructure
     async fn execute_shell_command(
         &self,
@@ -100,4 +192,116 @@ pub trait API: Sync + Send {
     async fn app_config(&self) -> anyhow::Result<AppConfig>;
     async fn user_info(&self) -> anyhow::Result<Option<User>>;
     async fn user_usage(&self) -> anyhow::Result<Option<UserUsage>>;
+
+    /// Activates the named profile from the workflow, switching the provider
+    /// used for subsequent requests
+    async fn activate_profile(&self, name: &str) -> anyhow::Result<Profile>;
+
+    /// Lists the snapshots taken before files were changed, from the oldest
+    /// to the most recent
+    async fn snapshots(&self) -> Result<Vec<Snapshot>>;
+
+    /// Reads the content a file had when the snapshot was taken, `None` when
+    /// the file didn't exist yet
+    async fn snapshot_content(&self, snapshot: &Snapshot) -> Result<Option<Vec<u8>>>;
+
+    /// Undoes the most recent file change by restoring its snapshot
+    async fn undo_last(&self) -> Result<Snapshot>;
+
+    /// Restores a file to the content it had when the snapshot was taken,
+    /// discarding the more recent snapshots of the file
+    async fn restore(&self, snapshot_id: &SnapshotId) -> Result<Snapshot>;
+
+    /// Returns the tool calls recorded in the audit log that match the query,
+    /// from the oldest to the most recent
+    async fn tool_calls(&self, query: ToolCallQuery) -> Result<Vec<ToolCallRecord>>;
+
+    /// Returns the latency, failure rate and output size of each tool, from
+    /// the calls recorded in the audit log
+    async fn tool_stats(&self) -> Result<Vec<ToolStats>>;
+
+    /// Returns the task list of the workspace
+    async fn tasks(&self) -> Result<TaskList>;
+
+    /// Counts the files and lines of the workspace by language and finds its
+    /// largest files, counting at most `max_files` files
+    async fn workspace_stats(&self, max_files: usize) -> Result<WorkspaceStats>;
+
+    /// Saves the conversation as a session, to list and resume it later
+    async fn save_session(&self, conversation: &Conversation) -> Result<()>;
+
+    /// Lists the saved sessions meeting the criteria of the filter, the most
+    /// recently updated first
+    async fn sessions(&self, filter: SessionFilter) -> Result<Vec<Session>>;
+
+    /// Reads the conversation of the session found by its id or its name, or
+    /// of the most recent session of the workspace for `last`, including the
+    /// steps of a turn interrupted by a crash
+    async fn resume_session(&self, session: &str) -> Result<Conversation>;
+
+    /// Names the session found by its id or its current name
+    async fn rename_session(&self, session: &str, name: &str) -> Result<Session>;
+
+    /// Adds the tags to the session found by its id or its name
+    async fn tag_session(&self, session: &str, tags: &[String]) -> Result<Session>;
+
+    /// Removes the tags from the session found by its id or its name
+    async fn untag_session(&self, session: &str, tags: &[String]) -> Result<Session>;
+
+    /// Deletes the session found by its id or its name
+    async fn delete_session(&self, session: &str) -> Result<Session>;
+
+    /// Facts remembered for the project of the working directory, in the
+    /// order they were remembered
+    async fn memories(&self) -> Result<Vec<Memory>>;
+
+    /// Forgets the memory of the project of the working directory with the id
+    async fn forget_memory(&self, id: u32) -> Result<Memory>;
+
+    /// Replaces the fact of the memory of the project of the working directory
+    /// with the id
+    async fn edit_memory(&self, id: u32, fact: &str) -> Result<Memory>;
+
+    /// Lets other frontends attach to the conversation, through a socket of
+    /// which the path is returned. Its later turns are shown to them, and in
+    /// collaborative mode they can also send prompts.
+    async fn share_session(
+        &self,
+        conversation_id: &ConversationId,
+        mode: ShareMode,
+    ) -> Result<PathBuf>;
+
+    /// Attaches to the conversation shared by another forge process, found by
+    /// the id or the name of its session, or to the most recently shared one
+    async fn attach_session(&self, session: Option<&str>) -> Result<LiveClient>;
+
+    /// Returns the prompts advertised by the MCP servers
+    async fn mcp_prompts(&self) -> Result<Vec<McpPrompt>>;
+
+    /// Fills in a prompt of an MCP server with the arguments and returns its
+    /// text
+    async fn mcp_prompt(
+        &self,
+        prompt: &McpPrompt,
+        arguments: BTreeMap<String, String>,
+    ) -> Result<String>;
+
+    /// Applies the changes of the MCP configuration without restarting forge,
+    /// returning the servers that were started, stopped or restarted
+    async fn reload_mcp(&self) -> Result<McpReload>;
+
+    /// Starts the authorization of forge by the remote MCP server, returning
+    /// the page on which the user approves it
+    async fn mcp_authorize(&self, server: &str) -> Result<McpAuthorization>;
+
+    /// Waits for the user to approve the authorization and stores the token
+    /// of the server
+    async fn mcp_await_authorization(&self, authorization: McpAuthorization) -> Result<()>;
+
+    /// Forgets the token issued by the remote MCP server
+    async fn mcp_deauthorize(&self, server: &str) -> Result<()>;
+
+    /// Serves the read, search, patch and shell tools to MCP clients over the
+    /// transport, until the client disconnects or the process is stopped
+    async fn serve_mcp(&self, transport: McpServeTransport) -> Result<()>;
 }
@@ -0,0 +1,260 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use forge_app::{ForgeApp, Services};
+use forge_domain::{
+    BackgroundRequest, BackgroundTask, ChatRequest, ChatResponse, Event, InterruptionReason,
+    Notification, Tools,
+};
+use futures::StreamExt;
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+/// Agent tasks of this process running in the background, their approvals and
+/// questions waiting for the user, and the notifications not taken yet
+pub(crate) struct BackgroundTasks {
+    running: Arc<Mutex<Vec<BackgroundTask>>>,
+    requests: Arc<Mutex<Vec<BackgroundRequest>>>,
+    tx: mpsc::UnboundedSender<Notification>,
+    rx: tokio::sync::Mutex<mpsc::UnboundedReceiver<Notification>>,
+}
+
+impl Default for BackgroundTasks {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            running: Default::default(),
+            requests: Default::default(),
+            tx,
+            rx: tokio::sync::Mutex::new(rx),
+        }
+    }
+}
+
+impl BackgroundTasks {
+    /// Runs the task on its conversation until the agent is done, then
+    /// notifies whether it completed or failed
+    pub(crate) fn spawn<S: Services>(&self, services: Arc<S>, task: BackgroundTask) {
+        self.running.lock().unwrap().push(task.clone());
+
+        let running = self.running.clone();
+        let requests = self.requests.clone();
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            // The agent isn't told about the worktree otherwise, as the
+            // environment of the system prompt is the one of the workspace
+            let prompt = format!(
+                "{}\n\nWork in {}, a git worktree of the workspace of its own, and leave the files of the workspace as they are.",
+                task.task,
+                task.worktree.display()
+            );
+            let event = Event::new(format!("{}/user_task_init", task.agent), Some(prompt));
+            let request = ChatRequest::new(event, task.conversation_id);
+            let ask = |request| wait_for_user(&requests, &tx, &task, request);
+            let notification = match run_headless(services, request, ask).await {
+                Ok(summary) => Notification::TaskCompleted { task: task.clone(), summary },
+                Err(error) => {
+                    Notification::TaskFailed { task: task.clone(), error: format!("{error:#}") }
+                }
+            };
+            running.lock().unwrap().retain(|running| *running != task);
+            tx.send(notification).ok();
+        });
+    }
+
+    pub(crate) fn running(&self) -> Vec<BackgroundTask> {
+        self.running.lock().unwrap().clone()
+    }
+
+    /// Takes the approvals and questions waiting for the user
+    pub(crate) fn requests(&self) -> Vec<BackgroundRequest> {
+        std::mem::take(&mut *self.requests.lock().unwrap())
+    }
+
+    /// Waits for the next notification, as soon as it's sent
+    pub(crate) async fn next_notification(&self) -> Option<Notification> {
+        self.rx.lock().await.recv().await
+    }
+}
+
+/// Keeps the approval or question of the task until the user answers it,
+/// notifying them right away
+fn wait_for_user(
+    requests: &Mutex<Vec<BackgroundRequest>>,
+    tx: &mpsc::UnboundedSender<Notification>,
+    task: &BackgroundTask,
+    request: ChatResponse,
+) {
+    let message = match &request {
+        ChatResponse::ApprovalRequest { message, .. } => message.clone(),
+        ChatResponse::Question { question, .. } => question.clone(),
+        _ => return,
+    };
+    requests
+        .lock()
+        .unwrap()
+        .push(BackgroundRequest { task: task.clone(), request });
+    tx.send(Notification::TaskWaiting { task: task.clone(), message })
+        .ok();
+}
+
+/// Refuses the approvals and questions of a run no one follows, which the
+/// agent sees as rejected or unanswered
+pub(crate) fn refuse(request: ChatResponse) {
+    if let ChatResponse::ApprovalRequest { message, .. }
+    | ChatResponse::Question { question: message, .. } = &request
+    {
+        tracing::warn!(request = %message, "No one to answer, refused");
+    }
+}
+
+/// Adds a detached git worktree of the commit checked out in the workspace,
+/// for a background task to work in
+pub(crate) async fn add_worktree(cwd: &Path, path: &Path) -> anyhow::Result<()> {
+    let output = Command::new("git")
+        .args(["worktree", "add", "--detach"])
+        .arg(path)
+        .arg("HEAD")
+        .current_dir(cwd)
+        .output()
+        .await
+        .context("Failed to run git")?;
+    anyhow::ensure!(
+        output.status.success(),
+        "Background tasks work in a git worktree of their own, which couldn't be added: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(())
+}
+
+/// Runs the turn of the agent without a user, returning its last answer.
+/// Approvals and questions of the agent are passed to `ask`, the reply they
+/// hold being answered or dropped, which rejects them.
+pub(crate) async fn run_headless<S: Services>(
+    services: Arc<S>,
+    request: ChatRequest,
+    mut ask: impl FnMut(ChatResponse) + Send,
+) -> anyhow::Result<Option<String>> {
+    let mut stream = ForgeApp::new(services).chat(request).await?;
+
+    let mut summary = None;
+    while let Some(message) = stream.next().await {
+        match message? {
+            ChatResponse::Text { text, is_complete: true, is_md: true }
+                if !text.trim().is_empty() =>
+            {
+                summary = Some(text);
+            }
+            ChatResponse::ToolCallStart(call) => {
+                if let Ok(Tools::ForgeToolAttemptCompletion(input)) = Tools::try_from(call) {
+                    summary = Some(input.result);
+                }
+            }
+            request @ (ChatResponse::ApprovalRequest { .. } | ChatResponse::Question { .. }) => {
+                ask(request)
+            }
+            ChatResponse::Interrupt { reason } => {
+                let reason = match reason {
+                    InterruptionReason::MaxRequestPerTurnLimitReached { limit } => {
+                        format!("maximum of {limit} requests per turn reached")
+                    }
+                    InterruptionReason::MaxToolFailurePerTurnLimitReached { limit } => {
+                        format!("maximum of {limit} tool failures per turn reached")
+                    }
+                    InterruptionReason::TurnLimitReached { limit, partial } => {
+                        format!("limit of {limit} reached after {partial}")
+                    }
+                };
+                anyhow::bail!("Interrupted, {reason}");
+            }
+            _ => {}
+        }
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use forge_domain::{AgentId, ConversationId, Reply};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn task() -> BackgroundTask {
+        BackgroundTask {
+            conversation_id: ConversationId::generate(),
+            agent: AgentId::new("forge"),
+            task: "Update the changelog".to_string(),
+            worktree: PathBuf::from("/home/user/forge/worktrees/task"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_questions_wait_for_the_user() {
+        let fixture = BackgroundTasks::default();
+        let (reply, mut answer) = Reply::channel();
+        let question = ChatResponse::Question {
+            question: "Which version?".to_string(),
+            options: Vec::new(),
+            multiple: false,
+            reply,
+        };
+
+        wait_for_user(&fixture.requests, &fixture.tx, &task(), question);
+        let notification = fixture.next_notification().await.unwrap().to_string();
+        let requests = fixture.requests();
+        if let ChatResponse::Question { reply, .. } = &requests[0].request {
+            reply.send("1.2.0".to_string());
+        }
+
+        let actual = (notification, requests.len(), answer.try_recv().unwrap());
+        let expected = (
+            "Background task of forge is waiting for an answer: Which version?".to_string(),
+            1,
+            "1.2.0".to_string(),
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_worktree_is_added_at_head() {
+        let fixture = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .args([
+                    "-c",
+                    "user.name=forge",
+                    "-c",
+                    "user.email=forge@example.com",
+                ])
+                .args(args)
+                .current_dir(fixture.path())
+                .output()
+                .unwrap()
+        };
+        git(&["init", "-q"]);
+        std::fs::write(fixture.path().join("CHANGELOG.md"), "# 1.1.0").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "Release"]);
+        std::fs::write(fixture.path().join("CHANGELOG.md"), "# 1.2.0").unwrap();
+        let worktree = fixture.path().join("worktrees").join("task");
+
+        add_worktree(fixture.path(), &worktree).await.unwrap();
+
+        let actual = std::fs::read_to_string(worktree.join("CHANGELOG.md")).unwrap();
+        assert_eq!(actual, "# 1.1.0");
+    }
+
+    #[tokio::test]
+    async fn test_worktree_needs_a_git_repository() {
+        let fixture = tempfile::tempdir().unwrap();
+
+        let actual = add_worktree(fixture.path(), &fixture.path().join("task")).await;
+
+        assert!(actual.is_err());
+    }
+}
@@ -1,28 +1,44 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
-use forge_app::dto::{AppConfig, InitAuth};
+use forge_app::dto::{AppConfig, InitAuth, McpAuthorization};
 use forge_app::{
     AppConfigService, AuthService, ConversationService, EnvironmentService, FileDiscoveryService,
-    ForgeApp, McpConfigManager, ProviderRegistry, ProviderService, Services, User, UserUsage,
-    Walker, WorkflowService,
+    ForgeApp, LifecycleService, McpConfigManager, McpService, MemoryService, ProviderRegistry,
+    ProviderService, Services, SessionService, SteeringService, TaskService, ToolAuditService,
+    User, UserUsage, Walker, WorkflowService, WorkspaceStatsService,
 };
 use forge_domain::*;
 use forge_infra::ForgeInfra;
-use forge_services::{CommandInfra, ForgeServices};
+use forge_services::{CommandInfra, FileReaderInfra, ForgeServices, SnapshotInfra, WalkerInfra};
+use forge_snaps::{Snapshot, SnapshotId};
 use forge_stream::MpscStream;
+use tokio::sync::broadcast;
 
-use crate::API;
+use crate::background::{BackgroundTasks, add_worktree};
+use crate::live::LiveSessions;
+use crate::mcp_server::serve_mcp;
+use crate::pipeline::PipelineRunner;
+use crate::scheduler::Scheduler;
+use crate::{API, LiveClient, McpServeTransport};
 
 pub struct ForgeAPI<S, F> {
     services: Arc<S>,
     infra: Arc<F>,
+    live: Arc<LiveSessions>,
+    background: Arc<BackgroundTasks>,
 }
 
 impl<A, F> ForgeAPI<A, F> {
     pub fn new(services: Arc<A>, infra: Arc<F>) -> Self {
-        Self { services, infra }
+        Self {
+            services,
+            infra,
+            live: Default::default(),
+            background: Default::default(),
+        }
     }
 }
 
@@ -32,13 +48,35 @@ impl ForgeAPI<ForgeServices<ForgeInfra>, ForgeInfra> {
         let app = Arc::new(ForgeServices::new(infra.clone()));
         ForgeAPI::new(app, infra)
     }
+
+    /// Creates the API for frontends that draw the whole terminal, such as the
+    /// TUI. The output of the shell commands isn't written to the terminal,
+    /// the frontend shows it from `ChatResponse::CommandOutput` instead.
+    pub fn init_without_echo(restricted: bool, cwd: PathBuf) -> Self {
+        let infra = Arc::new(ForgeInfra::new(restricted, cwd).without_echo());
+        let app = Arc::new(ForgeServices::new(infra.clone()));
+        ForgeAPI::new(app, infra)
+    }
+}
+
+impl<A: Services, F> ForgeAPI<A, F> {
+    /// Project the memories of the working directory are kept under
+    async fn memory_project(&self) -> PathBuf {
+        let cwd = self.services.get_environment().cwd;
+        self.services.memory_project(&cwd).await
+    }
 }
 
 #[async_trait::async_trait]
-impl<A: Services, F: CommandInfra> API for ForgeAPI<A, F> {
+impl<A: Services, F: CommandInfra + SnapshotInfra + WalkerInfra + FileReaderInfra + 'static> API
+    for ForgeAPI<A, F>
+{
     async fn discover(&self) -> Result<Vec<File>> {
         let environment = self.services.get_environment();
-        let config = Walker::unlimited().cwd(environment.cwd);
+        let filter = self.services.read_merged(None).await?.walker;
+        let config = Walker::unlimited()
+            .cwd(environment.cwd)
+            .filter(&filter.unwrap_or_default());
         self.services.collect_files(config).await
     }
 
@@ -58,11 +96,39 @@ impl<A: Services, F: CommandInfra> API for ForgeAPI<A, F> {
         &self,
         chat: ChatRequest,
     ) -> anyhow::Result<MpscStream<Result<ChatResponse, anyhow::Error>>> {
+        // The turns of a shared conversation are also shown to the attached
+        // frontends
+        if let Some(shared) = self.live.get(&chat.conversation_id) {
+            return shared.chat(self.services.clone(), chat).await;
+        }
+
         // Create a ForgeApp instance and delegate the chat logic to it
         let forge_app = ForgeApp::new(self.services.clone());
         forge_app.chat(chat).await
     }
 
+    async fn steer(&self, conversation_id: &ConversationId, message: String) -> Result<()> {
+        self.services.steer(conversation_id, message).await
+    }
+
+    fn lifecycle_events(&self) -> MpscStream<LifecycleUpdate> {
+        let mut events = self.services.lifecycle_events();
+        MpscStream::spawn(|tx| async move {
+            loop {
+                let update = match events.recv().await {
+                    Ok(event) => LifecycleUpdate::Event(event),
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        LifecycleUpdate::Lagged { missed }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if tx.send(update).await.is_err() {
+                    break;
+                }
+            }
+        })
+    }
+
     async fn init_conversation<W: Into<Workflow> + Send + Sync>(
         &self,
         workflow: W,
@@ -82,6 +148,134 @@ impl<A: Services, F: CommandInfra> API for ForgeAPI<A, F> {
         forge_app.compact_conversation(conversation_id).await
     }
 
+    async fn summarize_conversation(
+        &self,
+        conversation_id: &ConversationId,
+        replace: bool,
+    ) -> anyhow::Result<Option<String>> {
+        let forge_app = ForgeApp::new(self.services.clone());
+        forge_app
+            .summarize_conversation(conversation_id, replace)
+            .await
+    }
+
+    async fn refresh_repo_map(
+        &self,
+        conversation_id: &ConversationId,
+    ) -> anyhow::Result<Option<String>> {
+        let forge_app = ForgeApp::new(self.services.clone());
+        forge_app.refresh_repo_map(conversation_id).await
+    }
+
+    async fn export_context(
+        &self,
+        conversation_id: &ConversationId,
+        format: ExportFormat,
+    ) -> anyhow::Result<String> {
+        let forge_app = ForgeApp::new(self.services.clone());
+        forge_app.export_context(conversation_id, format).await
+    }
+
+    async fn context_diff(
+        &self,
+        conversation_id: &ConversationId,
+    ) -> anyhow::Result<Option<ContextDiff>> {
+        let forge_app = ForgeApp::new(self.services.clone());
+        forge_app.context_diff(conversation_id).await
+    }
+
+    async fn session_stats(
+        &self,
+        conversation_id: &ConversationId,
+    ) -> anyhow::Result<SessionStats> {
+        let forge_app = ForgeApp::new(self.services.clone());
+        forge_app.session_stats(conversation_id).await
+    }
+
+    async fn prune(
+        &self,
+        retention: &Retention,
+        keep: Option<ConversationId>,
+        dry_run: bool,
+    ) -> anyhow::Result<PruneReport> {
+        let forge_app = ForgeApp::new(self.services.clone());
+        forge_app.prune(retention, keep, dry_run).await
+    }
+
+    async fn replay(
+        &self,
+        model: &ModelId,
+        context: forge_domain::Context,
+    ) -> anyhow::Result<ChatCompletionMessageFull> {
+        let forge_app = ForgeApp::new(self.services.clone());
+        forge_app.replay(model, context).await
+    }
+
+    async fn chat_in_background(
+        &self,
+        conversation_id: &ConversationId,
+        agent: AgentId,
+        task: String,
+    ) -> anyhow::Result<BackgroundTask> {
+        let parent = self
+            .services
+            .find(conversation_id)
+            .await?
+            .with_context(|| format!("Conversation {conversation_id} not found"))?;
+        // The task keeps exchanging messages with the agents of the conversation
+        let mut conversation = parent.fork().root(Some(parent.root_id()));
+        let env = self.services.get_environment();
+        let worktree = env.worktrees_path().join(conversation.id.into_string());
+        add_worktree(&env.cwd, &worktree).await?;
+        conversation.cwd = Some(worktree.clone());
+        let task = BackgroundTask { conversation_id: conversation.id, agent, task, worktree };
+        self.services.upsert(conversation).await?;
+
+        self.background.spawn(self.services.clone(), task.clone());
+        Ok(task)
+    }
+
+    async fn run_schedules(&self) -> anyhow::Result<MpscStream<Result<ScheduledRun>>> {
+        let scheduler = Scheduler::new(self.services.clone(), self.infra.clone());
+        Ok(MpscStream::spawn(|tx| async move {
+            if let Err(error) = scheduler.run(&tx).await {
+                tx.send(Err(error)).await.ok();
+            }
+        }))
+    }
+
+    async fn run_schedule(&self, name: &str) -> anyhow::Result<ScheduledRun> {
+        Scheduler::new(self.services.clone(), self.infra.clone())
+            .run_now(name)
+            .await
+    }
+
+    async fn run_pipeline(
+        &self,
+        name: &str,
+        inputs: BTreeMap<String, String>,
+    ) -> anyhow::Result<MpscStream<Result<PipelineEvent>>> {
+        let runner = PipelineRunner::new(self.services.clone());
+        let name = name.to_string();
+        Ok(MpscStream::spawn(|tx| async move {
+            if let Err(error) = runner.run(&name, inputs, &tx).await {
+                tx.send(Err(error)).await.ok();
+            }
+        }))
+    }
+
+    fn background_tasks(&self) -> Vec<BackgroundTask> {
+        self.background.running()
+    }
+
+    fn background_requests(&self) -> Vec<BackgroundRequest> {
+        self.background.requests()
+    }
+
+    async fn next_notification(&self) -> Option<Notification> {
+        self.background.next_notification().await
+    }
+
     fn environment(&self) -> Environment {
         self.services.get_environment().clone()
     }
@@ -120,8 +314,10 @@ impl<A: Services, F: CommandInfra> API for ForgeAPI<A, F> {
         command: &str,
         working_dir: PathBuf,
     ) -> anyhow::Result<CommandOutput> {
+        let env = self.services.read_merged(None).await?.env;
+        let settings = CommandSettings::default().env(env.unwrap_or_default());
         self.infra
-            .execute_command(command.to_string(), working_dir)
+            .execute_command(command.to_string(), working_dir, None, None, &settings)
             .await
     }
     async fn read_mcp_config(&self) -> Result<McpConfig> {
@@ -143,7 +339,10 @@ impl<A: Services, F: CommandInfra> API for ForgeAPI<A, F> {
         command: &str,
     ) -> anyhow::Result<std::process::ExitStatus> {
         let cwd = self.environment().cwd;
-        self.infra.execute_command_raw(command, cwd).await
+        let env = self.services.read_merged(None).await?.env;
+        self.infra
+            .execute_command_raw(command, cwd, &env.unwrap_or_default())
+            .await
     }
 
     async fn init_login(&self) -> Result<InitAuth> {
@@ -186,4 +385,175 @@ impl<A: Services, F: CommandInfra> API for ForgeAPI<A, F> {
         }
         Ok(None)
     }
+
+    async fn activate_profile(&self, name: &str) -> Result<Profile> {
+        let forge_app = ForgeApp::new(self.services.clone());
+        forge_app.activate_profile(name).await
+    }
+
+    async fn snapshots(&self) -> Result<Vec<Snapshot>> {
+        self.infra.list_snapshots().await
+    }
+
+    async fn snapshot_content(&self, snapshot: &Snapshot) -> Result<Option<Vec<u8>>> {
+        self.infra.snapshot_content(snapshot).await
+    }
+
+    async fn undo_last(&self) -> Result<Snapshot> {
+        self.infra.undo_last_snapshot().await
+    }
+
+    async fn restore(&self, snapshot_id: &SnapshotId) -> Result<Snapshot> {
+        self.infra.restore_snapshot(snapshot_id).await
+    }
+
+    async fn tool_calls(&self, query: ToolCallQuery) -> Result<Vec<ToolCallRecord>> {
+        self.services.tool_calls(query).await
+    }
+
+    async fn tool_stats(&self) -> Result<Vec<ToolStats>> {
+        let records = self.services.tool_calls(ToolCallQuery::default()).await?;
+        Ok(ToolStats::aggregate(&records))
+    }
+
+    async fn tasks(&self) -> Result<TaskList> {
+        self.services.load_tasks().await
+    }
+
+    async fn workspace_stats(&self, max_files: usize) -> Result<WorkspaceStats> {
+        let workflow = self.services.read_merged(None).await?;
+        let filter = workflow.walker.unwrap_or_default();
+        let generated = GeneratedFiles::new(&workflow.generated_files);
+        self.services
+            .workspace_stats(&filter, &generated, max_files)
+            .await
+    }
+
+    async fn save_session(&self, conversation: &Conversation) -> Result<()> {
+        self.services.save_session(conversation).await
+    }
+
+    async fn sessions(&self, filter: SessionFilter) -> Result<Vec<Session>> {
+        let index = self.services.load_sessions().await?;
+        Ok(index.filter(&filter))
+    }
+
+    async fn resume_session(&self, session: &str) -> Result<Conversation> {
+        self.services.load_session(session).await
+    }
+
+    async fn rename_session(&self, session: &str, name: &str) -> Result<Session> {
+        self.services.rename_session(session, name).await
+    }
+
+    async fn tag_session(&self, session: &str, tags: &[String]) -> Result<Session> {
+        self.services.tag_session(session, tags).await
+    }
+
+    async fn untag_session(&self, session: &str, tags: &[String]) -> Result<Session> {
+        self.services.untag_session(session, tags).await
+    }
+
+    async fn delete_session(&self, session: &str) -> Result<Session> {
+        self.services.delete_session(session).await
+    }
+
+    async fn memories(&self) -> Result<Vec<Memory>> {
+        let project = self.memory_project().await;
+        let memories = self.services.load_memories().await?;
+        Ok(memories.memories(&project).to_vec())
+    }
+
+    async fn forget_memory(&self, id: u32) -> Result<Memory> {
+        let project = self.memory_project().await;
+        self.services
+            .update_memories(Box::new(move |memories| memories.forget(&project, id)))
+            .await?
+            .with_context(|| format!("No memory with the id {id}"))
+    }
+
+    async fn edit_memory(&self, id: u32, fact: &str) -> Result<Memory> {
+        let project = self.memory_project().await;
+        let fact = fact.to_string();
+        self.services
+            .update_memories(Box::new(move |memories| memories.edit(&project, id, fact)))
+            .await?
+            .with_context(|| format!("No memory with the id {id}"))
+    }
+
+    async fn share_session(
+        &self,
+        conversation_id: &ConversationId,
+        mode: ShareMode,
+    ) -> Result<PathBuf> {
+        self.live
+            .share(self.services.clone(), *conversation_id, mode)
+    }
+
+    async fn attach_session(&self, session: Option<&str>) -> Result<LiveClient> {
+        let dir = self.services.get_environment().live_sessions_path();
+        let path = match session {
+            Some(session) => {
+                // Sessions are only indexed once saved, so a new conversation
+                // is found by its id
+                let id = match self.services.load_sessions().await?.find(session) {
+                    Some(found) => found.id,
+                    None => ConversationId::parse(session)
+                        .with_context(|| format!("No session named {session}"))?,
+                };
+                dir.join(format!("{id}.sock"))
+            }
+            None => {
+                let mut latest = None;
+                if let Ok(mut entries) = tokio::fs::read_dir(&dir).await {
+                    while let Some(entry) = entries.next_entry().await? {
+                        let path = entry.path();
+                        if path.extension().is_none_or(|ext| ext != "sock") {
+                            continue;
+                        }
+                        let modified = entry.metadata().await?.modified()?;
+                        if latest.as_ref().is_none_or(|(time, _)| modified > *time) {
+                            latest = Some((modified, path));
+                        }
+                    }
+                }
+                latest
+                    .map(|(_, path)| path)
+                    .context("No session is shared")?
+            }
+        };
+        LiveClient::connect(&path).await
+    }
+
+    async fn mcp_prompts(&self) -> Result<Vec<McpPrompt>> {
+        self.services.prompts().await
+    }
+
+    async fn mcp_prompt(
+        &self,
+        prompt: &McpPrompt,
+        arguments: BTreeMap<String, String>,
+    ) -> Result<String> {
+        self.services.prompt(prompt, arguments).await
+    }
+
+    async fn reload_mcp(&self) -> Result<McpReload> {
+        self.services.reload().await
+    }
+
+    async fn mcp_authorize(&self, server: &str) -> Result<McpAuthorization> {
+        self.services.authorize(server).await
+    }
+
+    async fn mcp_await_authorization(&self, authorization: McpAuthorization) -> Result<()> {
+        self.services.await_authorization(authorization).await
+    }
+
+    async fn mcp_deauthorize(&self, server: &str) -> Result<()> {
+        self.services.deauthorize(server).await
+    }
+
+    async fn serve_mcp(&self, transport: McpServeTransport) -> Result<()> {
+        serve_mcp(self.services.clone(), transport).await
+    }
 }
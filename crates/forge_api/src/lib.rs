@@ -1,8 +1,16 @@
 mod api;
+mod background;
 mod forge_api;
+mod live;
+mod mcp_server;
+mod pipeline;
+mod scheduler;
 
 pub use api::*;
 pub use forge_api::*;
 pub use forge_app::dto::*;
 pub use forge_app::{Plan, UsageInfo, UserUsage};
 pub use forge_domain::*;
+pub use forge_snaps::{Snapshot, SnapshotId};
+pub use live::LiveClient;
+pub use mcp_server::McpServeTransport;
@@ -0,0 +1,427 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use forge_app::{EnvironmentService, ForgeApp, Services};
+use forge_domain::{
+    AgentId, Approval, ChatRequest, ChatResponse, ConversationId, Event, LiveCommand, LiveEvent,
+    ShareMode,
+};
+use forge_stream::MpscStream;
+use futures::StreamExt;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, Lines};
+use tokio::sync::{OwnedMutexGuard, broadcast, mpsc};
+use tokio::task::JoinHandle;
+
+/// Conversations of this process shared with other frontends
+#[derive(Default)]
+pub(crate) struct LiveSessions {
+    shared: Mutex<HashMap<ConversationId, Arc<SharedSession>>>,
+}
+
+impl LiveSessions {
+    pub(crate) fn get(&self, id: &ConversationId) -> Option<Arc<SharedSession>> {
+        self.shared.lock().unwrap().get(id).cloned()
+    }
+
+    /// Listens for frontends attaching to the conversation, returning the path
+    /// of the socket they connect to
+    pub(crate) fn share<S: Services>(
+        &self,
+        services: Arc<S>,
+        id: ConversationId,
+        mode: ShareMode,
+    ) -> Result<PathBuf> {
+        if let Some(session) = self.get(&id) {
+            return Ok(session.path.clone());
+        }
+
+        let dir = services.get_environment().live_sessions_path();
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let path = dir.join(format!("{id}.sock"));
+
+        let channel = Channel {
+            conversation_id: id,
+            mode,
+            events: broadcast::channel(256).0,
+            turn: Default::default(),
+            agent: Default::default(),
+        };
+        let accept = listen(channel.clone(), services, &path)?;
+        let session = Arc::new(SharedSession { channel, path: path.clone(), accept });
+        self.shared.lock().unwrap().insert(id, session);
+        Ok(path)
+    }
+}
+
+/// Conversation shared over a local socket. The socket is removed once the
+/// session is dropped.
+pub(crate) struct SharedSession {
+    channel: Channel,
+    path: PathBuf,
+    accept: JoinHandle<()>,
+}
+
+impl SharedSession {
+    /// Runs the turn of the host, showing it to the attached frontends
+    pub(crate) async fn chat<S: Services>(
+        &self,
+        services: Arc<S>,
+        request: ChatRequest,
+    ) -> Result<MpscStream<Result<ChatResponse>>> {
+        self.channel.chat(services, request, false).await
+    }
+}
+
+impl Drop for SharedSession {
+    fn drop(&mut self) {
+        self.accept.abort();
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// State shared by the host and the connections of a shared conversation
+#[derive(Clone)]
+struct Channel {
+    conversation_id: ConversationId,
+    mode: ShareMode,
+    events: broadcast::Sender<LiveEvent>,
+    /// Held while a turn runs, so that the turns of the host and of the
+    /// collaborators run one after the other
+    turn: Arc<tokio::sync::Mutex<()>>,
+    /// Agent the host last talked to, which the prompts of the collaborators
+    /// are sent to
+    agent: Arc<Mutex<Option<String>>>,
+}
+
+impl Channel {
+    async fn chat<S: Services>(
+        &self,
+        services: Arc<S>,
+        request: ChatRequest,
+        remote: bool,
+    ) -> Result<MpscStream<Result<ChatResponse>>> {
+        let turn = TurnGuard {
+            events: self.events.clone(),
+            _turn: self.turn.clone().lock_owned().await,
+        };
+
+        if !remote && let Some((agent, _)) = request.event.name.split_once('/') {
+            *self.agent.lock().unwrap() = Some(agent.to_string());
+        }
+        let prompt = request
+            .event
+            .value
+            .as_ref()
+            .and_then(|value| value.as_str())
+            .map(str::to_string);
+
+        let mut stream = ForgeApp::new(services).chat(request).await?;
+        let events = self.events.clone();
+        let agent = self.agent.clone();
+        Ok(MpscStream::spawn(move |tx| async move {
+            let _turn = turn;
+            if let Some(content) = prompt {
+                let _ = events.send(LiveEvent::Prompt { content });
+            }
+
+            while let Some(response) = stream.next().await {
+                if let Ok(ChatResponse::Handoff { to, .. }) = &response {
+                    *agent.lock().unwrap() = Some(to.to_string());
+                }
+                let event = match &response {
+                    Ok(response) => LiveEvent::from_response(response),
+                    Err(error) => Some(LiveEvent::Error { message: format!("{error:?}") }),
+                };
+                if let Some(event) = event {
+                    let _ = events.send(event);
+                }
+                if tx.send(response).await.is_err() {
+                    break;
+                }
+            }
+        }))
+    }
+
+    /// First event sent to a frontend once attached
+    fn attached(&self) -> LiveEvent {
+        LiveEvent::Attached {
+            conversation_id: self.conversation_id,
+            mode: self.mode,
+            busy: self.turn.try_lock().is_err(),
+        }
+    }
+
+    /// Relays the events to a frontend and runs the prompts it sends
+    async fn serve<S: Services>(
+        self,
+        services: Arc<S>,
+        reader: impl AsyncRead + Unpin + Send + 'static,
+        writer: impl AsyncWrite + Unpin + Send + 'static,
+    ) {
+        let (direct_tx, direct) = mpsc::unbounded_channel();
+        let relay = relay(self.attached(), self.events.subscribe(), direct, writer);
+
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let Ok(LiveCommand::Prompt { content }) = serde_json::from_str(&line) else {
+                continue;
+            };
+            if self.mode == ShareMode::ReadOnly {
+                let message = "The session is shared read-only".to_string();
+                let _ = direct_tx.send(LiveEvent::Error { message });
+                continue;
+            }
+
+            let agent = self.agent.lock().unwrap().clone();
+            let event = match agent {
+                Some(agent) => format!("{agent}/user_task_update"),
+                None => format!("{}/user_task_init", AgentId::default()),
+            };
+            let request = ChatRequest::new(Event::new(event, Some(content)), self.conversation_id);
+            match self.chat(services.clone(), request, true).await {
+                Ok(mut stream) => {
+                    // Only the host can answer, so the operations needing
+                    // an answer are refused in the turns of collaborators
+                    while let Some(response) = stream.next().await {
+                        match response {
+                            Ok(ChatResponse::ApprovalRequest { reply, .. }) => {
+                                reply.send(Approval::Reject);
+                            }
+                            Ok(ChatResponse::Question { reply, .. }) => reply.cancel(),
+                            _ => {}
+                        }
+                    }
+                }
+                Err(error) => {
+                    let _ = self
+                        .events
+                        .send(LiveEvent::Error { message: format!("{error:?}") });
+                }
+            }
+        }
+
+        relay.abort();
+    }
+}
+
+/// Writes the events of the conversation to a frontend, starting with the
+/// given one, along with those meant for this frontend only
+fn relay(
+    attached: LiveEvent,
+    mut events: broadcast::Receiver<LiveEvent>,
+    mut direct: mpsc::UnboundedReceiver<LiveEvent>,
+    mut writer: impl AsyncWrite + Unpin + Send + 'static,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut next = Some(attached);
+        loop {
+            let event = match next.take() {
+                Some(event) => event,
+                None => tokio::select! {
+                    event = events.recv() => match event {
+                        Ok(event) => event,
+                        // A frontend too slow to keep up misses events
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    Some(event) = direct.recv() => event,
+                },
+            };
+            if write_line(&mut writer, &event).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Ends the turn, telling the frontends even when the host interrupts it
+struct TurnGuard {
+    events: broadcast::Sender<LiveEvent>,
+    _turn: OwnedMutexGuard<()>,
+}
+
+impl Drop for TurnGuard {
+    fn drop(&mut self) {
+        let _ = self.events.send(LiveEvent::TurnEnd);
+    }
+}
+
+async fn write_line(
+    writer: &mut (impl AsyncWrite + Unpin),
+    message: &impl serde::Serialize,
+) -> Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn listen<S: Services>(channel: Channel, services: Arc<S>, path: &Path) -> Result<JoinHandle<()>> {
+    // Left behind by a process that didn't exit cleanly
+    let _ = std::fs::remove_file(path);
+    let listener = tokio::net::UnixListener::bind(path)
+        .with_context(|| format!("Failed to listen on {}", path.display()))?;
+
+    Ok(tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let (reader, writer) = stream.into_split();
+            tokio::spawn(channel.clone().serve(services.clone(), reader, writer));
+        }
+    }))
+}
+
+#[cfg(not(unix))]
+fn listen<S: Services>(
+    _channel: Channel,
+    _services: Arc<S>,
+    _path: &Path,
+) -> Result<JoinHandle<()>> {
+    anyhow::bail!("Sharing a session is only supported on Unix")
+}
+
+/// Frontend attached to a conversation shared by another forge process
+pub struct LiveClient {
+    lines: Lines<BufReader<Box<dyn AsyncRead + Send + Unpin>>>,
+    writer: Box<dyn AsyncWrite + Send + Unpin>,
+}
+
+impl LiveClient {
+    #[cfg(unix)]
+    pub async fn connect(path: &Path) -> Result<Self> {
+        let stream = tokio::net::UnixStream::connect(path)
+            .await
+            .with_context(|| format!("Failed to attach to {}", path.display()))?;
+        let (reader, writer) = stream.into_split();
+        Ok(Self::new(reader, writer))
+    }
+
+    fn new(
+        reader: impl AsyncRead + Send + Unpin + 'static,
+        writer: impl AsyncWrite + Send + Unpin + 'static,
+    ) -> Self {
+        let reader: Box<dyn AsyncRead + Send + Unpin> = Box::new(reader);
+        Self {
+            lines: BufReader::new(reader).lines(),
+            writer: Box::new(writer),
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub async fn connect(_path: &Path) -> Result<Self> {
+        anyhow::bail!("Attaching to a session is only supported on Unix")
+    }
+
+    /// Next event of the conversation, none once the host is gone
+    pub async fn next_event(&mut self) -> Result<Option<LiveEvent>> {
+        match self.lines.next_line().await? {
+            Some(line) => Ok(Some(serde_json::from_str(&line)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Sends a prompt to run once the current turn is over
+    pub async fn prompt(&mut self, content: impl ToString) -> Result<()> {
+        write_line(
+            &mut self.writer,
+            &LiveCommand::Prompt { content: content.to_string() },
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn channel(mode: ShareMode) -> Channel {
+        Channel {
+            conversation_id: ConversationId::generate(),
+            mode,
+            events: broadcast::channel(16).0,
+            turn: Default::default(),
+            agent: Default::default(),
+        }
+    }
+
+    /// Frontend attached to the channel, relayed the events without a socket
+    fn attach(channel: &Channel) -> (LiveClient, mpsc::UnboundedSender<LiveEvent>) {
+        let (client, host) = tokio::io::duplex(1024);
+        let (direct_tx, direct) = mpsc::unbounded_channel();
+        let (_, writer) = tokio::io::split(host);
+        relay(
+            channel.attached(),
+            channel.events.subscribe(),
+            direct,
+            writer,
+        );
+        let (reader, writer) = tokio::io::split(client);
+        (LiveClient::new(reader, writer), direct_tx)
+    }
+
+    #[tokio::test]
+    async fn test_attached_frontend_receives_the_events() {
+        let fixture = channel(ShareMode::ReadOnly);
+        let (mut client, direct) = attach(&fixture);
+
+        fixture
+            .events
+            .send(LiveEvent::Prompt { content: "Fix the build".to_string() })
+            .unwrap();
+        direct
+            .send(LiveEvent::Error { message: "The session is shared read-only".to_string() })
+            .unwrap();
+        let mut actual = Vec::new();
+        for _ in 0..3 {
+            actual.push(client.next_event().await.unwrap().unwrap());
+        }
+        // Which of the shared and the direct events comes first isn't set
+        actual[1..].sort_by_key(|event| matches!(event, LiveEvent::Error { .. }));
+
+        let expected = vec![
+            LiveEvent::Attached {
+                conversation_id: fixture.conversation_id,
+                mode: ShareMode::ReadOnly,
+                busy: false,
+            },
+            LiveEvent::Prompt { content: "Fix the build".to_string() },
+            LiveEvent::Error { message: "The session is shared read-only".to_string() },
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_turn_end_is_sent_once_the_turn_is_over() {
+        let fixture = channel(ShareMode::Collaborative);
+        let mut events = fixture.events.subscribe();
+        let turn = TurnGuard {
+            events: fixture.events.clone(),
+            _turn: fixture.turn.clone().lock_owned().await,
+        };
+        let busy = fixture.attached();
+
+        drop(turn);
+
+        let actual = (busy, events.recv().await.unwrap(), fixture.attached());
+        let expected = (
+            LiveEvent::Attached {
+                conversation_id: fixture.conversation_id,
+                mode: ShareMode::Collaborative,
+                busy: true,
+            },
+            LiveEvent::TurnEnd,
+            LiveEvent::Attached {
+                conversation_id: fixture.conversation_id,
+                mode: ShareMode::Collaborative,
+                busy: false,
+            },
+        );
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,414 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Context;
+use forge_app::{EnvironmentService, ForgeApp, Services};
+use forge_domain::{
+    Agent, AgentId, FileVersions, GeneratedFiles, Guard, Redactor, TaskList, ToolCallContext,
+    ToolCallFull, ToolDefinition, ToolOutput, ToolValue, ToolsDiscriminants,
+};
+use rmcp::model::{
+    CallToolRequestParam, CallToolResult, Content, Implementation, ListToolsResult,
+    PaginatedRequestParam, ServerCapabilities, ServerInfo, Tool,
+};
+use rmcp::service::RequestContext;
+use rmcp::transport::SseServer;
+use rmcp::{Error as McpError, RoleServer, ServerHandler, ServiceExt};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+
+/// Tools of forge that are made available to MCP clients
+const EXPOSED_TOOLS: &[ToolsDiscriminants] = &[
+    ToolsDiscriminants::ForgeToolFsRead,
+    ToolsDiscriminants::ForgeToolFsSearch,
+    ToolsDiscriminants::ForgeToolFsPatch,
+    ToolsDiscriminants::ForgeToolProcessShell,
+];
+
+/// Transport over which forge is served to MCP clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum McpServeTransport {
+    /// JSON-RPC messages on the standard input and output
+    Stdio,
+    /// Server-sent events on `/sse`, with the messages of the clients posted
+    /// to `/message`. The clients have to send the token, as a `token` query
+    /// parameter or a bearer token, to open the event stream.
+    Sse { address: SocketAddr, token: String },
+}
+
+impl McpServeTransport {
+    /// SSE transport on the address, with a random token
+    pub fn sse(address: SocketAddr) -> Self {
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        Self::Sse { address, token }
+    }
+
+    /// URL the clients connect to, with the token
+    pub fn url(&self) -> Option<String> {
+        match self {
+            McpServeTransport::Stdio => None,
+            McpServeTransport::Sse { address, token } => {
+                Some(format!("http://{address}/sse?token={token}"))
+            }
+        }
+    }
+}
+
+/// Serves the tools of forge to MCP clients. Operations that need the
+/// approval of the user are rejected, as there is no one to ask.
+#[derive(Clone)]
+struct ForgeMcpServer<S> {
+    services: Arc<S>,
+    agent: Agent,
+    // Versions of the files seen by the client, so that a patch of a file that
+    // changed since it was read is refused, as it is in a chat
+    file_versions: Arc<Mutex<FileVersions>>,
+    // Directory the shell commands of the client moved to with `cd`
+    cwd: Arc<Mutex<Option<PathBuf>>>,
+}
+
+impl<S: Services> ForgeMcpServer<S> {
+    fn new(services: Arc<S>) -> Self {
+        let tools = EXPOSED_TOOLS.iter().map(|tool| tool.name()).collect();
+        Self {
+            services,
+            agent: Agent::new(AgentId::new("mcp")).tools(tools),
+            file_versions: Default::default(),
+            cwd: Default::default(),
+        }
+    }
+}
+
+impl<S: Services> ServerHandler for ForgeMcpServer<S> {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            server_info: Implementation {
+                name: "forge".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            instructions: Some(
+                "Reads, searches and patches the files of the workspace and runs shell commands in it."
+                    .to_string(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    async fn list_tools(
+        &self,
+        _request: PaginatedRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        let tools = EXPOSED_TOOLS
+            .iter()
+            .map(|tool| to_mcp_tool(tool.definition()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ListToolsResult { next_cursor: None, tools })
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let call = ToolCallFull::new(request.name.to_string()).arguments(
+            request
+                .arguments
+                .map(serde_json::Value::Object)
+                .unwrap_or_default(),
+        );
+
+        let mut file_versions = self.file_versions.lock().await;
+        let mut cwd = self.cwd.lock().await;
+        let app = ForgeApp::new(self.services.clone());
+        let workflow = app.read_workflow_merged(None).await.unwrap_or_default();
+        let guard = Guard::new(
+            &workflow.guardrails.clone().unwrap_or_default(),
+            self.services.get_environment().home.as_deref(),
+        );
+        let redactor = Redactor::new(
+            &workflow.redaction.clone().unwrap_or_default(),
+            std::env::vars(),
+        )
+        .map_err(|error| McpError::internal_error(error.to_string(), None))?;
+        let mut context = ToolCallContext::new(TaskList::new())
+            .file_versions(file_versions.clone())
+            .cwd(cwd.clone())
+            .generated(Arc::new(GeneratedFiles::new(&workflow.generated_files)))
+            .workflow(Arc::new(workflow))
+            .guard(Arc::new(guard))
+            .redactor(Arc::new(redactor));
+        let result = app.call_tool(&self.agent, &mut context, call).await;
+        *file_versions = context.file_versions;
+        *cwd = context.cwd;
+
+        Ok(to_mcp_result(result.output))
+    }
+}
+
+fn to_mcp_tool(definition: ToolDefinition) -> Result<Tool, McpError> {
+    let schema = serde_json::to_value(&definition.input_schema)
+        .map_err(|error| McpError::internal_error(error.to_string(), None))?;
+    let schema = match schema {
+        serde_json::Value::Object(schema) => schema,
+        _ => Default::default(),
+    };
+    Ok(Tool::new(
+        definition.name.to_string(),
+        definition.description,
+        Arc::new(schema),
+    ))
+}
+
+fn to_mcp_result(output: ToolOutput) -> CallToolResult {
+    let content = output
+        .values
+        .into_iter()
+        .filter_map(|value| match value {
+            ToolValue::Text(text) => Some(Content::text(text)),
+            ToolValue::Image(image) => {
+                // Images are kept as data URLs, MCP expects the bare base64 data
+                let data = image
+                    .url()
+                    .split_once(";base64,")
+                    .map(|(_, data)| data)
+                    .unwrap_or(image.url().as_str());
+                Some(Content::image(data, image.mime_type()))
+            }
+            ToolValue::Empty => None,
+        })
+        .collect();
+
+    if output.is_error {
+        CallToolResult::error(content)
+    } else {
+        CallToolResult::success(content)
+    }
+}
+
+/// Serves the tools of forge over the transport until the client disconnects
+/// or, for SSE, until the process is stopped
+pub async fn serve_mcp<S: Services>(
+    services: Arc<S>,
+    transport: McpServeTransport,
+) -> anyhow::Result<()> {
+    let server = ForgeMcpServer::new(services);
+    match transport {
+        McpServeTransport::Stdio => {
+            let service = server
+                .serve(rmcp::transport::stdio())
+                .await
+                .context("Failed to start the MCP server")?;
+            service.waiting().await?;
+        }
+        McpServeTransport::Sse { address, token } => {
+            // The server only listens on a free loopback port of its own, the
+            // connections reach it once they passed the gate
+            let inner = TcpListener::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0))
+                .await?
+                .local_addr()?;
+            let sse = SseServer::serve(inner)
+                .await
+                .context("Failed to start the MCP server")?;
+            let listener = TcpListener::bind(address)
+                .await
+                .with_context(|| format!("Failed to listen on {address}"))?;
+            tracing::info!(%address, "Serving MCP over SSE");
+            let ct = sse.with_service(move || server.clone());
+            tokio::select! {
+                result = gate(listener, inner, token) => result?,
+                result = tokio::signal::ctrl_c() => result?,
+            }
+            ct.cancel();
+        }
+    }
+    Ok(())
+}
+
+/// Headers of a request are refused past this size
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+
+/// Forwards the connections to the server at `inner`, once their first request
+/// passed [`admits`]. A web page could otherwise reach the local server through
+/// DNS rebinding and run commands with the shell tool.
+async fn gate(listener: TcpListener, inner: SocketAddr, token: String) -> anyhow::Result<()> {
+    let token = Arc::new(token);
+    loop {
+        let (client, _) = listener.accept().await?;
+        let token = token.clone();
+        tokio::spawn(async move {
+            if let Err(error) = forward(client, inner, &token).await {
+                tracing::debug!(%error, "MCP connection closed");
+            }
+        });
+    }
+}
+
+async fn forward(mut client: TcpStream, inner: SocketAddr, token: &str) -> anyhow::Result<()> {
+    let mut head = Vec::new();
+    let mut buffer = [0; 4096];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        let n = client.read(&mut buffer).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        head.extend_from_slice(&buffer[..n]);
+        anyhow::ensure!(
+            head.len() <= MAX_HEAD_BYTES,
+            "The request headers are too large"
+        );
+    }
+
+    if let Err(status) = admits(&String::from_utf8_lossy(&head), token) {
+        client
+            .write_all(
+                format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                    .as_bytes(),
+            )
+            .await?;
+        return Ok(());
+    }
+
+    let mut server = TcpStream::connect(inner).await?;
+    server.write_all(&head).await?;
+    tokio::io::copy_bidirectional(&mut client, &mut server).await?;
+    Ok(())
+}
+
+/// Checks the head of a request: its `Host` and `Origin` have to be local, so
+/// that a page served from another domain can't use the server, and the event
+/// stream needs the token. The messages are posted with the session id the
+/// event stream gave, which stands for the token. Returns the status of the
+/// refusal otherwise.
+fn admits(head: &str, token: &str) -> Result<(), &'static str> {
+    let mut lines = head.lines();
+    let target = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .ok_or("400 Bad Request")?;
+    let headers = lines
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim()))
+        .collect::<Vec<_>>();
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| *value)
+    };
+
+    if !header("host").is_some_and(is_local_host) {
+        return Err("403 Forbidden");
+    }
+    if let Some(origin) = header("origin") {
+        let host = origin.split_once("://").map_or(origin, |(_, host)| host);
+        if !is_local_host(host) {
+            return Err("403 Forbidden");
+        }
+    }
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    if path == "/sse" {
+        let bearer = header("authorization").and_then(|value| value.strip_prefix("Bearer "));
+        let query = query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("token="));
+        if bearer != Some(token) && query != Some(token) {
+            return Err("401 Unauthorized");
+        }
+    }
+    Ok(())
+}
+
+/// Whether the host, with or without a port, is `localhost` or an IP address.
+/// A host name resolving to a local address is what DNS rebinding relies on.
+fn is_local_host(host: &str) -> bool {
+    let name = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => host.rsplit_once(':').map_or(host, |(name, _)| name),
+    };
+    name.eq_ignore_ascii_case("localhost") || name.parse::<IpAddr>().is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn head(target: &str, headers: &[&str]) -> String {
+        format!("GET {target} HTTP/1.1\r\n{}\r\n\r\n", headers.join("\r\n"))
+    }
+
+    #[test]
+    fn test_admits_local_clients_with_the_token() {
+        let fixture = [
+            head("/sse?token=secret", &["Host: 127.0.0.1:8811"]),
+            head(
+                "/sse",
+                &["Host: localhost:8811", "Authorization: Bearer secret"],
+            ),
+            head(
+                "/message?sessionId=1",
+                &["host: [::1]:8811", "Origin: http://localhost:3000"],
+            ),
+        ];
+
+        let actual = fixture.map(|head| admits(&head, "secret"));
+
+        let expected = [Ok(()), Ok(()), Ok(())];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_admits_refuses_rebinding_and_missing_token() {
+        let fixture = [
+            head("/sse?token=secret", &["Host: attacker.example:8811"]),
+            head(
+                "/message?sessionId=1",
+                &["Host: 127.0.0.1:8811", "Origin: http://attacker.example"],
+            ),
+            head("/message?sessionId=1", &[]),
+            head("/sse", &["Host: 127.0.0.1:8811"]),
+            head("/sse?token=wrong", &["Host: 127.0.0.1:8811"]),
+        ];
+
+        let actual = fixture.map(|head| admits(&head, "secret"));
+
+        let expected = [
+            Err("403 Forbidden"),
+            Err("403 Forbidden"),
+            Err("403 Forbidden"),
+            Err("401 Unauthorized"),
+            Err("401 Unauthorized"),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_gate_refuses_before_forwarding() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        // Nothing listens on the inner address, a forwarded request would fail
+        // to connect instead of being answered
+        let inner = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let inner_address = inner.local_addr().unwrap();
+        drop(inner);
+        tokio::spawn(gate(listener, inner_address, "secret".to_string()));
+
+        let mut client = TcpStream::connect(address).await.unwrap();
+        client
+            .write_all(head("/sse", &["Host: attacker.example"]).as_bytes())
+            .await
+            .unwrap();
+        let mut actual = String::new();
+        client.read_to_string(&mut actual).await.unwrap();
+
+        let expected = "HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use anyhow::Context;
+use forge_app::{ConversationService, Services, TemplateService, WorkflowService};
+use forge_domain::{
+    ChatRequest, ChatResponse, Event, Pipeline, PipelineContext, PipelineEvent, PipelineStep,
+    StepRun, StepStatus,
+};
+use tokio::sync::mpsc::Sender;
+
+use crate::background::run_headless;
+
+/// Tag of the sessions of the steps, along with the name of the pipeline
+const PIPELINE_TAG: &str = "pipeline";
+
+/// Runs a step of a pipeline, with the values of the steps before it
+#[async_trait::async_trait]
+trait StepRunner: Send + Sync {
+    async fn run_step(
+        &self,
+        pipeline: &Pipeline,
+        step: &PipelineStep,
+        context: &PipelineContext,
+        tx: &Sender<anyhow::Result<PipelineEvent>>,
+    ) -> anyhow::Result<StepRun>;
+}
+
+/// Runs the steps of a pipeline of the workflow one after the other, the
+/// approvals and questions of their agents being sent to the user
+pub(crate) struct PipelineRunner<S> {
+    services: Arc<S>,
+}
+
+impl<S: Services> PipelineRunner<S> {
+    pub(crate) fn new(services: Arc<S>) -> Self {
+        Self { services }
+    }
+
+    /// Runs the pipeline with the name, sending each step once it's done. A
+    /// failed step stops the pipeline, unless it's allowed to fail.
+    pub(crate) async fn run(
+        &self,
+        name: &str,
+        inputs: BTreeMap<String, String>,
+        tx: &Sender<anyhow::Result<PipelineEvent>>,
+    ) -> anyhow::Result<()> {
+        let pipelines = self.services.read_merged(None).await?.pipelines;
+        let pipeline = Pipeline::find(pipelines, name)?;
+        run_steps(self, &pipeline, inputs, tx).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: Services> StepRunner for PipelineRunner<S> {
+    /// Runs the prompt of the step on a new conversation, saved as a session
+    /// tagged with the name of the pipeline, unless its condition doesn't hold
+    async fn run_step(
+        &self,
+        pipeline: &Pipeline,
+        step: &PipelineStep,
+        context: &PipelineContext,
+        tx: &Sender<anyhow::Result<PipelineEvent>>,
+    ) -> anyhow::Result<StepRun> {
+        if let Some(when) = &step.when {
+            let condition = self
+                .services
+                .render_template(when, context)
+                .await
+                .with_context(|| format!("Invalid condition of step {}", step.name))?;
+            if !PipelineStep::holds(&condition) {
+                return Ok(StepRun::skipped(&step.name));
+            }
+        }
+        let prompt = self
+            .services
+            .render_template(&step.prompt, context)
+            .await
+            .with_context(|| format!("Invalid prompt of step {}", step.name))?;
+
+        tracing::info!(pipeline = %pipeline.name, step = %step.name, "Running pipeline step");
+
+        let workflow = self.services.read_merged(None).await?;
+        let mut conversation = self.services.create_conversation(workflow).await?;
+        conversation.tags = vec![PIPELINE_TAG.to_string(), pipeline.name.clone()];
+        let conversation_id = conversation.id;
+        self.services.upsert(conversation).await?;
+
+        let agent = step.agent.clone().unwrap_or_default();
+        let event = Event::new(format!("{agent}/user_task_init"), Some(prompt));
+        let outcome = run_headless(
+            self.services.clone(),
+            ChatRequest::new(event, conversation_id),
+            |request| ask(tx, request),
+        )
+        .await;
+        if let Err(error) = &outcome {
+            tracing::warn!(step = %step.name, error = ?error, "Pipeline step failed");
+        }
+
+        Ok(StepRun {
+            step: step.name.clone(),
+            status: if outcome.is_ok() {
+                StepStatus::Completed
+            } else {
+                StepStatus::Failed
+            },
+            conversation_id: Some(conversation_id),
+            output: outcome.as_ref().ok().cloned().flatten(),
+            error: outcome.err().map(|error| format!("{error:#}")),
+        })
+    }
+}
+
+/// Runs the steps of the pipeline in order, each with the outputs of the ones
+/// before it, and stops at the first failed step not allowed to fail
+async fn run_steps(
+    runner: &impl StepRunner,
+    pipeline: &Pipeline,
+    inputs: BTreeMap<String, String>,
+    tx: &Sender<anyhow::Result<PipelineEvent>>,
+) -> anyhow::Result<()> {
+    pipeline.validate(&inputs)?;
+
+    let mut context = PipelineContext::new(inputs);
+    for step in &pipeline.steps {
+        let run = runner.run_step(pipeline, step, &context, tx).await?;
+        context.record(&run);
+
+        let stop = run.status == StepStatus::Failed && !step.continue_on_error.unwrap_or_default();
+        if tx.send(Ok(PipelineEvent::Step(run))).await.is_err() {
+            return Ok(());
+        }
+        if stop {
+            anyhow::bail!(
+                "Pipeline {} stopped as step {} failed",
+                pipeline.name,
+                step.name
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Sends the approval or question of the agent of a step to the user. It's
+/// rejected or left unanswered once they're gone.
+fn ask(tx: &Sender<anyhow::Result<PipelineEvent>>, request: ChatResponse) {
+    let tx = tx.clone();
+    tokio::spawn(async move { tx.send(Ok(PipelineEvent::Request(request))).await });
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_domain::Reply;
+    use pretty_assertions::assert_eq;
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    /// Runs the steps by answering with the names of the steps before,
+    /// failing the ones named
+    #[derive(Default)]
+    struct Fixture {
+        failing: Vec<String>,
+    }
+
+    #[async_trait::async_trait]
+    impl StepRunner for Fixture {
+        async fn run_step(
+            &self,
+            _pipeline: &Pipeline,
+            step: &PipelineStep,
+            context: &PipelineContext,
+            _tx: &Sender<anyhow::Result<PipelineEvent>>,
+        ) -> anyhow::Result<StepRun> {
+            let failed = self.failing.contains(&step.name);
+            let before = serde_json::to_value(context)?["steps"]
+                .as_object()
+                .map(|steps| steps.keys().cloned().collect::<Vec<_>>().join(", "));
+            Ok(StepRun {
+                step: step.name.clone(),
+                status: if failed {
+                    StepStatus::Failed
+                } else {
+                    StepStatus::Completed
+                },
+                conversation_id: None,
+                output: before,
+                error: failed.then(|| "Tests failed".to_string()),
+            })
+        }
+    }
+
+    fn pipeline() -> Pipeline {
+        Pipeline::new("release").steps(vec![
+            PipelineStep::new("test", "Run the tests"),
+            PipelineStep::new("fix", "Fix {{steps.test.output}}").continue_on_error(true),
+            PipelineStep::new("bump", "Bump the version"),
+        ])
+    }
+
+    /// Runs the pipeline, returning the steps sent and the outcome
+    async fn run(fixture: &Fixture, pipeline: &Pipeline) -> (Vec<StepRun>, Result<(), String>) {
+        let (tx, mut rx) = mpsc::channel(8);
+        let outcome = run_steps(fixture, pipeline, BTreeMap::new(), &tx).await;
+        drop(tx);
+
+        let mut sent = Vec::new();
+        while let Some(event) = rx.recv().await {
+            if let Ok(PipelineEvent::Step(run)) = event {
+                sent.push(run);
+            }
+        }
+        (sent, outcome.map_err(|error| error.to_string()))
+    }
+
+    fn statuses(runs: &[StepRun]) -> Vec<(&str, StepStatus)> {
+        runs.iter()
+            .map(|run| (run.step.as_str(), run.status))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_steps_run_after_the_ones_before() {
+        let fixture = Fixture::default();
+
+        let (runs, _) = run(&fixture, &pipeline()).await;
+
+        let actual = runs
+            .into_iter()
+            .map(|run| run.output.unwrap())
+            .collect::<Vec<_>>();
+        let expected = vec!["".to_string(), "test".to_string(), "fix, test".to_string()];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_failed_step_stops_the_pipeline() {
+        let fixture = Fixture { failing: vec!["test".to_string()], ..Default::default() };
+
+        let (runs, outcome) = run(&fixture, &pipeline()).await;
+
+        let actual = (statuses(&runs), outcome);
+        let expected = (
+            vec![("test", StepStatus::Failed)],
+            Err("Pipeline release stopped as step test failed".to_string()),
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_step_allowed_to_fail_doesnt_stop_the_pipeline() {
+        let fixture = Fixture { failing: vec!["fix".to_string()], ..Default::default() };
+
+        let (runs, outcome) = run(&fixture, &pipeline()).await;
+
+        let actual = (statuses(&runs), outcome);
+        let expected = (
+            vec![
+                ("test", StepStatus::Completed),
+                ("fix", StepStatus::Failed),
+                ("bump", StepStatus::Completed),
+            ],
+            Ok(()),
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_inputs_run_no_step() {
+        let fixture = Fixture::default();
+        let pipeline = pipeline().inputs(vec!["version".to_string()]);
+
+        let (runs, outcome) = run(&fixture, &pipeline).await;
+
+        let actual = (statuses(&runs), outcome);
+        let expected = (
+            Vec::new(),
+            Err("Missing input version of pipeline release".to_string()),
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_questions_are_sent_to_the_user() {
+        let (tx, mut rx) = mpsc::channel(8);
+        let (reply, mut answer) = Reply::channel();
+        let question = ChatResponse::Question {
+            question: "Which version?".to_string(),
+            options: Vec::new(),
+            multiple: false,
+            reply,
+        };
+
+        ask(&tx, question);
+        if let Some(Ok(PipelineEvent::Request(ChatResponse::Question { reply, .. }))) =
+            rx.recv().await
+        {
+            reply.send("1.2.0".to_string());
+        }
+
+        let actual = answer.try_recv().unwrap();
+        assert_eq!(actual, "1.2.0".to_string());
+    }
+}
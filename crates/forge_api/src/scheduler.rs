@@ -0,0 +1,281 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use chrono::Local;
+use forge_app::{
+    ConversationService, EnvironmentService, Services, WalkedFile, Walker, WorkflowService,
+};
+use forge_domain::{ChatRequest, Event, Schedule, ScheduleTrigger, ScheduledRun, WalkerFilter};
+use forge_services::WalkerInfra;
+use globset::GlobSet;
+use tokio::sync::mpsc::Sender;
+
+use crate::background::{refuse, run_headless};
+
+/// How often the files watched by the schedules are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Tag of the sessions of the scheduled runs, along with the name of the
+/// schedule
+const SCHEDULE_TAG: &str = "schedule";
+
+/// Size and time of the last modification of the files watched by a
+/// schedule, by path
+type Fingerprint = BTreeMap<String, (u64, Option<SystemTime>)>;
+
+/// Runs the schedules of the workflow without a user. Runs are made one at a
+/// time, so that they don't change the same files at once.
+pub(crate) struct Scheduler<S, F> {
+    services: Arc<S>,
+    infra: Arc<F>,
+}
+
+impl<S: Services, F: WalkerInfra> Scheduler<S, F> {
+    pub(crate) fn new(services: Arc<S>, infra: Arc<F>) -> Self {
+        Self { services, infra }
+    }
+
+    /// Runs the schedules when their cron expression fires or the files they
+    /// watch change, sending each run once it's done
+    pub(crate) async fn run(
+        &self,
+        tx: &Sender<anyhow::Result<ScheduledRun>>,
+    ) -> anyhow::Result<()> {
+        let workflow = self.services.read_merged(None).await?;
+        let schedules = workflow.schedules;
+        let filter = workflow.walker.unwrap_or_default();
+        anyhow::ensure!(!schedules.is_empty(), "No schedules are configured");
+
+        let mut next_runs = schedules
+            .iter()
+            .map(|schedule| schedule.next_run(&Local::now()))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let watchers = schedules
+            .iter()
+            .map(Schedule::watcher)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let mut fingerprints = self.fingerprints(&watchers, &filter).await?;
+
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            // The workspace is walked once for all the schedules
+            let latest = self.fingerprints(&watchers, &filter).await?;
+            let now = Local::now();
+            let mut triggers = Vec::new();
+            for (index, schedule) in schedules.iter().enumerate() {
+                let mut trigger = None;
+                if next_runs[index].as_ref().is_some_and(|next| *next <= now) {
+                    next_runs[index] = schedule.next_run(&now)?;
+                    trigger = Some(ScheduleTrigger::Cron);
+                }
+                let changes = changed_paths(&fingerprints[index], &latest[index]);
+                if !changes.is_empty() {
+                    trigger = trigger.or(Some(ScheduleTrigger::Changes(changes)));
+                }
+                if let Some(trigger) = trigger {
+                    triggers.push((schedule, trigger));
+                }
+            }
+            fingerprints = latest;
+
+            if triggers.is_empty() {
+                continue;
+            }
+            for (schedule, trigger) in triggers {
+                let run = self.run_schedule(schedule, trigger).await;
+                if tx.send(run).await.is_err() {
+                    return Ok(());
+                }
+            }
+            // The changes made by the runs themselves don't trigger other ones
+            fingerprints = self.fingerprints(&watchers, &filter).await?;
+        }
+    }
+
+    /// Runs the schedule with the name right away
+    pub(crate) async fn run_now(&self, name: &str) -> anyhow::Result<ScheduledRun> {
+        let schedule = self
+            .services
+            .read_merged(None)
+            .await?
+            .schedules
+            .into_iter()
+            .find(|schedule| schedule.name == name)
+            .with_context(|| format!("No schedule named {name}"))?;
+        self.run_schedule(&schedule, ScheduleTrigger::Manual).await
+    }
+
+    /// Runs the prompt of the schedule on a new conversation, saved as a
+    /// session tagged with the name of the schedule
+    async fn run_schedule(
+        &self,
+        schedule: &Schedule,
+        trigger: ScheduleTrigger,
+    ) -> anyhow::Result<ScheduledRun> {
+        tracing::info!(schedule = %schedule.name, trigger = %trigger, "Running schedule");
+
+        let workflow = self
+            .services
+            .read_merged(schedule.workflow.as_deref())
+            .await?;
+        let mut conversation = self.services.create_conversation(workflow).await?;
+        conversation.tags = vec![SCHEDULE_TAG.to_string(), schedule.name.clone()];
+        let conversation_id = conversation.id;
+        self.services.upsert(conversation).await?;
+
+        // The agent is told which files changed, as the prompt can't know it
+        let prompt = match &trigger {
+            ScheduleTrigger::Changes(paths) => {
+                format!(
+                    "{}\n\nChanged files:\n- {}",
+                    schedule.prompt,
+                    paths.join("\n- ")
+                )
+            }
+            _ => schedule.prompt.clone(),
+        };
+        let agent = schedule.agent.clone().unwrap_or_default();
+        let event = Event::new(format!("{agent}/user_task_init"), Some(prompt));
+
+        let outcome = run_headless(
+            self.services.clone(),
+            ChatRequest::new(event, conversation_id),
+            refuse,
+        )
+        .await;
+        if let Err(error) = &outcome {
+            tracing::warn!(schedule = %schedule.name, error = ?error, "Scheduled run failed");
+        }
+
+        Ok(ScheduledRun {
+            schedule: schedule.name.clone(),
+            trigger,
+            conversation_id,
+            summary: outcome.as_ref().ok().cloned().flatten(),
+            error: outcome.err().map(|error| format!("{error:#}")),
+        })
+    }
+
+    /// Fingerprints of the files each of the watchers matches, from a single
+    /// walk of the workspace with the filter
+    async fn fingerprints(
+        &self,
+        watchers: &[Option<GlobSet>],
+        filter: &WalkerFilter,
+    ) -> anyhow::Result<Vec<Fingerprint>> {
+        if watchers.iter().all(Option::is_none) {
+            return Ok(vec![Fingerprint::new(); watchers.len()]);
+        }
+
+        let cwd = self.services.get_environment().cwd;
+        let files = self
+            .infra
+            .walk(Walker::unlimited().cwd(cwd).filter(filter))
+            .await?;
+        Ok(watchers
+            .iter()
+            .map(|watcher| fingerprint(&files, watcher.as_ref()))
+            .collect())
+    }
+}
+
+/// Size and modification time of the files the watcher matches
+fn fingerprint(files: &[WalkedFile], watcher: Option<&GlobSet>) -> Fingerprint {
+    let Some(watcher) = watcher else {
+        return Fingerprint::new();
+    };
+    files
+        .iter()
+        .filter(|file| !file.is_dir() && watcher.is_match(&file.path))
+        .map(|file| (file.path.clone(), (file.size, file.modified)))
+        .collect()
+}
+
+/// Paths of the files added, changed or removed between the fingerprints
+fn changed_paths(before: &Fingerprint, after: &Fingerprint) -> Vec<String> {
+    let changed = after
+        .iter()
+        .filter(|(path, hash)| before.get(*path) != Some(*hash))
+        .map(|(path, _)| path.clone());
+    let removed = before
+        .keys()
+        .filter(|path| !after.contains_key(*path))
+        .cloned();
+    let mut paths = changed.chain(removed).collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn file(path: &str, size: u64, modified: u64) -> WalkedFile {
+        WalkedFile {
+            path: path.to_string(),
+            file_name: path.rsplit('/').next().map(str::to_string),
+            size,
+            modified: Some(UNIX_EPOCH + Duration::from_secs(modified)),
+            generated: None,
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_keeps_the_watched_files() {
+        let watcher = Schedule::new("docs", "Regenerate the docs")
+            .watch(vec!["src/**.rs".to_string()])
+            .watcher()
+            .unwrap();
+        let files = [
+            file("src/", 0, 0),
+            file("src/main.rs", 10, 1),
+            file("README.md", 20, 2),
+        ];
+
+        let actual = fingerprint(&files, watcher.as_ref());
+
+        let expected = Fingerprint::from([(
+            "src/main.rs".to_string(),
+            (10, Some(UNIX_EPOCH + Duration::from_secs(1))),
+        )]);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_changed_paths() {
+        let watcher = Schedule::new("docs", "Regenerate the docs")
+            .watch(vec!["**.rs".to_string()])
+            .watcher()
+            .unwrap();
+        let before = fingerprint(
+            &[
+                file("lib.rs", 10, 1),
+                file("main.rs", 10, 1),
+                file("old.rs", 10, 1),
+                file("same.rs", 10, 1),
+            ],
+            watcher.as_ref(),
+        );
+        let after = fingerprint(
+            &[
+                file("lib.rs", 12, 1),
+                file("main.rs", 10, 2),
+                file("new.rs", 10, 1),
+                file("same.rs", 10, 1),
+            ],
+            watcher.as_ref(),
+        );
+
+        let actual = changed_paths(&before, &after);
+
+        let expected = vec!["lib.rs", "main.rs", "new.rs", "old.rs"];
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,25 +1,32 @@
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use forge_domain::{
-    Agent, ChatCompletionMessage, Context, Conversation, ModelId, ResultStream, ToolCallContext,
-    ToolCallFull, ToolResult,
+    Agent, AgentId, AgentMessage, ChatCompletionMessage, Context, Conversation, ConversationId,
+    Error, HookOutcome, HookPayload, LifecycleEvent, ModelId, ProviderDetails, ResultStream,
+    SessionEvent, ToolCallContext, ToolCallFull, ToolResult, Workflow,
 };
+use futures::StreamExt;
 
+use crate::hook_runner::HookRunner;
 use crate::tool_registry::ToolRegistry;
 use crate::{
-    AppConfigService, ConversationService, ProviderRegistry, ProviderService, Services,
-    TemplateService,
+    AppConfigService, ConversationService, LifecycleService, MailboxService, ProviderRegistry,
+    ProviderService, RequestRecorderService, Services, SessionService, SteeringService,
+    TemplateService, WorkflowService,
 };
 
 /// Agent service trait that provides core chat and tool call functionality.
 /// This trait abstracts the essential operations needed by the Orchestrator.
 #[async_trait::async_trait]
 pub trait AgentService: Send + Sync + 'static {
-    /// Execute a chat completion request
+    /// Execute a chat completion request, with the provider configured on the
+    /// agent if any, otherwise with the active provider
     async fn chat_agent(
         &self,
         id: &ModelId,
         context: Context,
+        provider: Option<&ProviderDetails>,
     ) -> ResultStream<ChatCompletionMessage, anyhow::Error>;
 
     /// Execute a tool call
@@ -39,6 +46,32 @@ pub trait AgentService: Send + Sync + 'static {
 
     /// Synchronize the on-going conversation
     async fn update(&self, conversation: Conversation) -> anyhow::Result<()>;
+
+    /// Runs the hooks of the workflow for the action described by the payload
+    async fn run_hooks(&self, workflow: &Workflow, payload: &HookPayload) -> HookOutcome;
+
+    /// Journals a step of the ongoing turn, before the conversation is
+    /// synchronized at the end of the turn
+    async fn checkpoint(&self, id: &ConversationId, event: SessionEvent) -> anyhow::Result<()>;
+
+    /// Takes the messages other agents of the conversation sent to the agent
+    async fn receive_messages(
+        &self,
+        conversation: &ConversationId,
+        agent: &AgentId,
+    ) -> anyhow::Result<Vec<AgentMessage>>;
+
+    /// Takes the instructions the user sent during the turn running on the
+    /// conversation. When none has arrived yet and `wait` is set, waits for
+    /// one.
+    async fn receive_steering(
+        &self,
+        id: &ConversationId,
+        wait: bool,
+    ) -> anyhow::Result<Vec<String>>;
+
+    /// Publishes an event of the lifecycle of the turn to its subscribers
+    fn publish_lifecycle(&self, event: LifecycleEvent);
 }
 
 /// Blanket implementation of AgentService for any type that implements Services
@@ -48,10 +81,42 @@ impl<T: Services> AgentService for T {
         &self,
         id: &ModelId,
         context: Context,
+        provider: Option<&ProviderDetails>,
     ) -> ResultStream<ChatCompletionMessage, anyhow::Error> {
         let config = self.read_app_config().await.unwrap_or_default();
-        let provider = self.get_provider(config).await?;
-        self.chat(id, context, provider).await
+        let details = provider.cloned();
+        let provider = match &details {
+            Some(details) => self.get_agent_provider(config, details).await?,
+            None => self.get_provider(config).await?,
+        };
+        let dump = dump_request(self, id, &context).await;
+        let stream = match self.chat(id, context, provider).await {
+            Ok(stream) => stream,
+            Err(error) => {
+                let error = rotate_on_rejection(self, details.as_ref(), error).await;
+                record_response(self, dump.as_deref(), Err(&error)).await;
+                return Err(error);
+            }
+        };
+
+        // Responses are written to the dump as they are streamed, rather than
+        // once the stream is collected
+        let services = self.clone();
+        Ok(Box::pin(stream.then(move |item| {
+            let services = services.clone();
+            let dump = dump.clone();
+            let details = details.clone();
+            async move {
+                let item = match item {
+                    Ok(message) => Ok(message),
+                    Err(error) => {
+                        Err(rotate_on_rejection(&services, details.as_ref(), error).await)
+                    }
+                };
+                record_response(&services, dump.as_deref(), item.as_ref()).await;
+                item
+            }
+        })))
     }
 
     async fn call(
@@ -73,6 +138,112 @@ impl<T: Services> AgentService for T {
     }
 
     async fn update(&self, conversation: Conversation) -> anyhow::Result<()> {
+        // A session failing to save shouldn't interrupt the conversation
+        if conversation.persist
+            && let Err(error) = self.save_session(&conversation).await
+        {
+            tracing::warn!(error = ?error, "Failed to save the session");
+        }
         self.upsert(conversation).await
     }
+
+    async fn run_hooks(&self, workflow: &Workflow, payload: &HookPayload) -> HookOutcome {
+        HookRunner::new(Arc::new(self.clone()))
+            .run(workflow, payload)
+            .await
+    }
+
+    async fn checkpoint(&self, id: &ConversationId, event: SessionEvent) -> anyhow::Result<()> {
+        if let Err(error) = self.record_session_event(id, &event).await {
+            tracing::warn!(error = ?error, "Failed to journal the session");
+        }
+        Ok(())
+    }
+
+    async fn receive_messages(
+        &self,
+        conversation: &ConversationId,
+        agent: &AgentId,
+    ) -> anyhow::Result<Vec<AgentMessage>> {
+        self.take_messages(conversation, agent, None).await
+    }
+
+    async fn receive_steering(
+        &self,
+        id: &ConversationId,
+        wait: bool,
+    ) -> anyhow::Result<Vec<String>> {
+        self.take_steering(id, wait).await
+    }
+
+    fn publish_lifecycle(&self, event: LifecycleEvent) {
+        self.publish(event)
+    }
+}
+
+/// Dumps the request when enabled in the workflow, returning the path of the
+/// dump. Failing to dump is logged rather than failing the request.
+async fn dump_request<T: Services>(
+    services: &T,
+    model: &ModelId,
+    context: &Context,
+) -> Option<PathBuf> {
+    let workflow = services.read_merged(None).await.ok()?;
+    let dump = workflow.request_dump.unwrap_or_default();
+    if !dump.is_enabled() {
+        return None;
+    }
+
+    services
+        .record_request(model, context, dump.keep())
+        .await
+        .inspect_err(|error| tracing::warn!(error = ?error, "Failed to dump the request"))
+        .ok()
+}
+
+async fn record_response<T: Services>(
+    services: &T,
+    dump: Option<&Path>,
+    response: Result<&ChatCompletionMessage, &anyhow::Error>,
+) {
+    let Some(dump) = dump else {
+        return;
+    };
+    if let Err(error) = services.record_response(dump, response).await {
+        tracing::warn!(error = ?error, "Failed to dump the response");
+    }
+}
+
+/// Switches to the next API key when the provider rejected the current one,
+/// making the error retryable so the request is re-issued with the new key.
+/// Without another key to rotate to, the original error is returned.
+async fn rotate_on_rejection<T: Services>(
+    services: &T,
+    details: Option<&ProviderDetails>,
+    error: anyhow::Error,
+) -> anyhow::Error {
+    if !matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::KeyRejected { .. })
+    ) {
+        return error;
+    }
+
+    let (status, error) = match error.downcast::<Error>() {
+        Ok(Error::KeyRejected { status, error }) => (status, error),
+        Ok(error) => return error.into(),
+        Err(error) => return error,
+    };
+
+    let mut config = services.read_app_config().await.unwrap_or_default();
+    match services.rotate_key(&mut config, details).await {
+        Ok(true) => {
+            if let Err(e) = services.write_app_config(&config).await {
+                tracing::warn!(error = ?e, "Failed to persist the active API key");
+            }
+            tracing::info!(status, "API key rejected, rotated to the next key");
+            Error::Retryable(error).into()
+        }
+        _ => error,
+    }
 }
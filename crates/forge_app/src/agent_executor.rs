@@ -1,15 +1,28 @@
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use convert_case::{Case, Casing};
+use derive_more::Display;
 use forge_display::TitleFormat;
 use forge_domain::{
-    ChatRequest, ChatResponse, Event, ToolCallContext, ToolDefinition, ToolName, ToolOutput,
+    Agent, ChatRequest, ChatResponse, Event, FanOut, InterruptionReason, SpawnAgent,
+    ToolCallContext, ToolDefinition, ToolName, ToolOutput, Tools, ToolsDiscriminants,
+    WorkItemStatus, WorkItemUpdate,
 };
-use futures::StreamExt;
+use forge_template::Element;
+use futures::{StreamExt, TryStreamExt};
 use tokio::sync::RwLock;
 
 use crate::error::Error;
-use crate::{ConversationService, Services, WorkflowService};
+use crate::fmt::content::FormatContent;
+use crate::{ConversationService, EnvironmentService, Services, WorkflowService};
+
+/// Sub-agents working at the same time in a fan-out, when the call doesn't say
+const DEFAULT_FAN_OUT_PARALLELISM: usize = 4;
+
+/// Sub-agents working at the same time in a fan-out at most, whatever the call
+/// asks for, to stay within the rate limits of the provider
+const MAX_FAN_OUT_PARALLELISM: usize = 8;
 
 pub struct AgentExecutor<S> {
     services: Arc<S>,
@@ -54,24 +67,33 @@ impl<S: Services> AgentExecutor<S> {
         // Create a new conversation for agent execution
         let workflow = self.services.read_merged(None).await?;
         let conversation =
-            ConversationService::create_conversation(self.services.as_ref(), workflow).await?;
+            ConversationService::create_conversation(self.services.as_ref(), workflow)
+                .await?
+                .delegated(context.root_conversation_id);
+        let conversation_id = conversation.id;
+        ConversationService::upsert(self.services.as_ref(), conversation).await?;
 
         // Execute the request through the ForgeApp
         let app = crate::ForgeApp::new(self.services.clone());
         let mut response_stream = app
             .chat(ChatRequest::new(
                 Event::new(format!("{agent_id}/user_task_init"), Some(task)),
-                conversation.id,
+                conversation_id,
             ))
             .await?;
 
         // Collect responses from the agent
         while let Some(message) = response_stream.next().await {
             let message = message?;
+            if let ChatResponse::Usage(usage) = &message {
+                context.spending.record(usage.cost);
+            }
             match &message {
                 ChatResponse::Summary { content } => {
                     return Ok(ToolOutput::text(content));
                 }
+                // The context of the agent isn't the one of the conversation it reports to
+                ChatResponse::TokenBudget(_) => {}
                 _ => {
                     context.send(message).await?;
                 }
@@ -80,8 +102,386 @@ impl<S: Services> AgentExecutor<S> {
         Err(Error::EmptyToolResponse.into())
     }
 
+    /// Runs the task in a new conversation with the sub-agent, within the
+    /// budget of requests, and reports back how its turn ended along with its
+    /// summary
+    pub async fn spawn(
+        &self,
+        agent: Agent,
+        input: SpawnAgent,
+        context: &mut ToolCallContext,
+    ) -> anyhow::Result<ToolOutput> {
+        let env = self.services.get_environment();
+        if let Some(content) = Tools::ForgeToolSpawnAgent(input.clone()).to_content(&env) {
+            context.send(content).await?;
+        }
+
+        let report = self
+            .run_sub_agent(agent, input.task, input.max_requests, None, context)
+            .await?;
+        Ok(ToolOutput::text(report.to_element()))
+    }
+
+    /// Runs the task over each item with a copy of the sub-agent, several of
+    /// them at the same time and within a budget of requests they share,
+    /// sending the status of the items as they are started and done. Reports
+    /// back the results of all the items, in the order of the items.
+    pub async fn fan_out(
+        &self,
+        agent: Agent,
+        input: FanOut,
+        context: &mut ToolCallContext,
+    ) -> anyhow::Result<ToolOutput> {
+        let env = self.services.get_environment();
+        if let Some(content) = Tools::ForgeToolFanOut(input.clone()).to_content(&env) {
+            context.send(content).await?;
+        }
+
+        let context = &*context;
+        let total = input.items.len();
+        // Without a limit, the budget is never used up
+        let budget = AtomicUsize::new(input.max_requests.unwrap_or(usize::MAX));
+        let parallelism = fan_out_parallelism(input.parallelism);
+        let results = futures::stream::iter(input.items.iter().enumerate())
+            .map(|(index, item)| {
+                let agent = agent.clone();
+                let task = format!("{}\n\n{}", input.task, Element::new("item").cdata(item));
+                let budget = &budget;
+                async move {
+                    let update = |status| {
+                        ChatResponse::WorkItem(WorkItemUpdate {
+                            index: index + 1,
+                            total,
+                            item: item.clone(),
+                            status,
+                        })
+                    };
+
+                    if budget.load(Ordering::SeqCst) == 0 {
+                        context.send(update(WorkItemStatus::Skipped)).await?;
+                        return Ok((item, None));
+                    }
+
+                    context.send(update(WorkItemStatus::Running)).await?;
+                    let report = self
+                        .run_sub_agent(agent, task, None, Some(budget), context)
+                        .await
+                        .unwrap_or_else(|error| SubAgentReport {
+                            status: SubAgentStatus::Failed,
+                            tool_calls: 0,
+                            summary: Some(format!("{error:#}")),
+                        });
+                    context.send(update(report.status.into())).await?;
+                    anyhow::Ok((item, Some(report)))
+                }
+            })
+            .buffered(parallelism)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        Ok(ToolOutput::text(fan_out_element(&results)))
+    }
+
+    /// Runs the task in a new conversation with the sub-agent until its turn
+    /// ends. A sub-agent sharing a budget with others is stopped once one of
+    /// its requests goes over it, and only its questions are passed on to the
+    /// user, as the work of the sub-agents running along would be interleaved.
+    async fn run_sub_agent(
+        &self,
+        agent: Agent,
+        task: String,
+        max_requests: Option<usize>,
+        budget: Option<&AtomicUsize>,
+        context: &ToolCallContext,
+    ) -> anyhow::Result<SubAgentReport> {
+        // The sub-agent replaces the agent it's a copy of in a conversation of
+        // its own, which is all the context it gets
+        let workflow = self.services.read_merged(None).await?;
+        let mut conversation =
+            ConversationService::create_conversation(self.services.as_ref(), workflow)
+                .await?
+                .delegated(context.root_conversation_id);
+        let event = Event::new(format!("{}/user_task_init", agent.id), Some(task));
+        let left = budget.map(|budget| budget.load(Ordering::SeqCst));
+        conversation.max_requests_per_turn =
+            lowest_limit([conversation.max_requests_per_turn, max_requests, left]);
+        conversation
+            .agents
+            .retain(|existing| existing.id != agent.id);
+        conversation.agents.push(agent);
+        let conversation_id = conversation.id;
+        ConversationService::upsert(self.services.as_ref(), conversation).await?;
+
+        let app = crate::ForgeApp::new(self.services.clone());
+        let mut response_stream = app.chat(ChatRequest::new(event, conversation_id)).await?;
+
+        let completion = ToolsDiscriminants::ForgeToolAttemptCompletion.name();
+        let mut report = SubAgentReport::default();
+        while let Some(message) = response_stream.next().await {
+            match message? {
+                // The interruption is reported to the agent rather than to the
+                // user, who would otherwise be asked whether to continue
+                ChatResponse::Interrupt { reason } => {
+                    report.status = match reason {
+                        InterruptionReason::MaxRequestPerTurnLimitReached { .. } => {
+                            SubAgentStatus::BudgetExhausted
+                        }
+                        InterruptionReason::MaxToolFailurePerTurnLimitReached { .. } => {
+                            SubAgentStatus::Failed
+                        }
+                        InterruptionReason::TurnLimitReached { partial, .. } => {
+                            report.summary = report.summary.or(partial.last_message);
+                            SubAgentStatus::BudgetExhausted
+                        }
+                    };
+                }
+                // The context of the agent isn't the one of the conversation it reports to
+                ChatResponse::TokenBudget(_) => {}
+                message => {
+                    match &message {
+                        ChatResponse::Text { text, is_complete: true, .. }
+                            if !text.trim().is_empty() =>
+                        {
+                            report.summary = Some(text.clone());
+                        }
+                        ChatResponse::ToolCallStart(call) => {
+                            if let Ok(Tools::ForgeToolAttemptCompletion(input)) =
+                                Tools::try_from(call.clone())
+                            {
+                                report.status = SubAgentStatus::Completed;
+                                report.summary = Some(input.result);
+                            }
+                        }
+                        ChatResponse::ToolCallEnd(result) if result.name != completion => {
+                            report.tool_calls += 1
+                        }
+                        ChatResponse::Usage(usage) => {
+                            context.spending.record(usage.cost);
+                            // Dropping the stream stops the sub-agent
+                            if let Some(budget) = budget
+                                && !take_request(budget)
+                            {
+                                report.status = SubAgentStatus::BudgetExhausted;
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    let asks = matches!(
+                        message,
+                        ChatResponse::ApprovalRequest { .. } | ChatResponse::Question { .. }
+                    );
+                    if budget.is_none() || asks {
+                        context.send(message).await?;
+                    }
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
     pub async fn contains_tool(&self, tool_name: &ToolName) -> anyhow::Result<bool> {
         let agent_tools = self.tool_agents().await?;
         Ok(agent_tools.iter().any(|tool| tool.name == *tool_name))
     }
 }
+
+/// Sub-agents working at the same time for the parallelism the call asks for
+fn fan_out_parallelism(requested: Option<usize>) -> usize {
+    requested
+        .unwrap_or(DEFAULT_FAN_OUT_PARALLELISM)
+        .clamp(1, MAX_FAN_OUT_PARALLELISM)
+}
+
+/// The lowest of the limits of requests that apply to a sub-agent, `None`
+/// when none does
+fn lowest_limit(limits: [Option<usize>; 3]) -> Option<usize> {
+    limits.into_iter().flatten().min()
+}
+
+/// Takes a request from the budget shared by the sub-agents of a fan-out,
+/// `false` once it's used up
+fn take_request(budget: &AtomicUsize) -> bool {
+    budget
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+            left.checked_sub(1)
+        })
+        .is_ok()
+}
+
+/// How the turn of a sub-agent ended
+#[derive(Debug, Default, Clone, Copy, PartialEq, Display)]
+enum SubAgentStatus {
+    #[display("completed")]
+    Completed,
+    #[display("budget_exhausted")]
+    BudgetExhausted,
+    #[display("failed")]
+    Failed,
+    /// The turn ended without the sub-agent completing the task
+    #[default]
+    #[display("incomplete")]
+    Incomplete,
+}
+
+impl From<SubAgentStatus> for WorkItemStatus {
+    fn from(status: SubAgentStatus) -> Self {
+        match status {
+            SubAgentStatus::Completed => WorkItemStatus::Completed,
+            SubAgentStatus::BudgetExhausted => WorkItemStatus::BudgetExhausted,
+            SubAgentStatus::Failed => WorkItemStatus::Failed,
+            SubAgentStatus::Incomplete => WorkItemStatus::Incomplete,
+        }
+    }
+}
+
+/// Result of a sub-agent given back to the agent that spawned it. Unless the
+/// task was completed, the summary is the last answer of the sub-agent.
+#[derive(Debug, Default)]
+struct SubAgentReport {
+    status: SubAgentStatus,
+    tool_calls: usize,
+    summary: Option<String>,
+}
+
+impl SubAgentReport {
+    fn to_element(&self) -> Element {
+        Element::new("sub_agent_result")
+            .attr("status", self.status)
+            .attr("tool_calls", self.tool_calls)
+            .append(
+                self.summary
+                    .as_ref()
+                    .map(|summary| Element::new("summary").cdata(summary)),
+            )
+    }
+}
+
+/// Results of the items of a fan-out given back to the agent, `None` for the
+/// items skipped once the budget was used up
+fn fan_out_element(results: &[(&String, Option<SubAgentReport>)]) -> Element {
+    let completed = results
+        .iter()
+        .filter(|(_, report)| {
+            report
+                .as_ref()
+                .is_some_and(|report| report.status == SubAgentStatus::Completed)
+        })
+        .count();
+    Element::new("fan_out_result")
+        .attr("total", results.len())
+        .attr("completed", completed)
+        .append(results.iter().map(|(item, report)| {
+            let status = report
+                .as_ref()
+                .map_or(WorkItemStatus::Skipped, |report| report.status.into());
+            Element::new("item")
+                .attr("status", status)
+                .attr(
+                    "tool_calls",
+                    report.as_ref().map_or(0, |report| report.tool_calls),
+                )
+                .append(Element::new("value").cdata(item))
+                .append(
+                    report
+                        .as_ref()
+                        .and_then(|report| report.summary.as_ref())
+                        .map(|summary| Element::new("summary").cdata(summary)),
+                )
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_sub_agent_report() {
+        let fixture = SubAgentReport {
+            status: SubAgentStatus::BudgetExhausted,
+            tool_calls: 12,
+            summary: Some("Found the parser in src/parse.rs".to_string()),
+        };
+
+        let actual = fixture.to_element().render();
+
+        let expected = r#"<sub_agent_result
+  status="budget_exhausted"
+  tool_calls="12"
+>
+<summary><![CDATA[Found the parser in src/parse.rs]]></summary>
+</sub_agent_result>"#;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_fan_out_result() {
+        let items = ["tests::parse".to_string(), "tests::lex".to_string()];
+        let fixture = [
+            (
+                &items[0],
+                Some(SubAgentReport {
+                    status: SubAgentStatus::Completed,
+                    tool_calls: 3,
+                    summary: Some("Fixed the off-by-one in the parser".to_string()),
+                }),
+            ),
+            (&items[1], None),
+        ];
+
+        let actual = fan_out_element(&fixture).render();
+
+        let expected = r#"<fan_out_result
+  total="2"
+  completed="1"
+>
+<item
+  status="completed"
+  tool_calls="3"
+>
+<value><![CDATA[tests::parse]]></value>
+<summary><![CDATA[Fixed the off-by-one in the parser]]></summary>
+</item>
+<item
+  status="skipped"
+  tool_calls="0"
+>
+<value><![CDATA[tests::lex]]></value>
+</item>
+</fan_out_result>"#;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_fan_out_parallelism() {
+        let actual = [None, Some(0), Some(2), Some(100)].map(fan_out_parallelism);
+        let expected = [DEFAULT_FAN_OUT_PARALLELISM, 1, 2, MAX_FAN_OUT_PARALLELISM];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_lowest_limit() {
+        let actual = [
+            lowest_limit([Some(50), Some(10), Some(30)]),
+            lowest_limit([None, None, Some(30)]),
+            lowest_limit([None, None, None]),
+        ];
+        let expected = [Some(10), Some(30), None];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_take_request_until_the_budget_is_used_up() {
+        let fixture = AtomicUsize::new(2);
+
+        let actual = [(); 3].map(|_| take_request(&fixture));
+
+        let expected = [true, true, false];
+        assert_eq!(actual, expected);
+        // The items not started yet are then skipped
+        assert_eq!(fixture.load(Ordering::SeqCst), 0);
+    }
+}
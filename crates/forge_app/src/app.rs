@@ -2,9 +2,10 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
-use chrono::Local;
+use chrono::{Local, Utc};
 use forge_domain::*;
 use forge_stream::MpscStream;
+use tokio::sync::broadcast;
 
 use crate::authenticator::Authenticator;
 use crate::dto::InitAuth;
@@ -14,9 +15,13 @@ use crate::tool_registry::ToolRegistry;
 use crate::workflow_manager::WorkflowManager;
 use crate::{
     AppConfigService, AttachmentService, ConversationService, EnvironmentService,
-    FileDiscoveryService, ProviderRegistry, ProviderService, Services, Walker,
+    FileDiscoveryService, McpService, MemoryService, ProviderRegistry, ProviderService, Services,
+    StorageService, SymbolIndexService, Walker, WorkspaceIndexService,
 };
 
+/// Remembered facts given to a new conversation at most
+const RECALLED_MEMORIES: usize = 5;
+
 /// ForgeApp handles the core chat functionality by orchestrating various
 /// services. It encapsulates the complex logic previously contained in the
 /// ForgeAPI chat method.
@@ -47,7 +52,7 @@ impl<S: Services> ForgeApp<S> {
         let services = self.services.clone();
 
         // Get the conversation for the chat request
-        let conversation = services
+        let mut conversation = services
             .find(&chat.conversation_id)
             .await
             .unwrap_or_default()
@@ -55,12 +60,56 @@ impl<S: Services> ForgeApp<S> {
 
         // Get tool definitions and models
         let tool_definitions = self.tool_registry.list().await?;
+        // Servers going down or coming back during the chat are reported as they do
+        let mut mcp_events = services.mcp_events();
         let config = services.read_app_config().await.unwrap_or_default();
         let provider = services
-            .get_provider(config)
+            .get_provider(config.clone())
             .await
             .context("Failed to get provider")?;
-        let models = services.models(provider).await?;
+        let retry = provider.retry().cloned();
+        let mut models = services.models(provider.clone()).await?;
+
+        // Models of the providers configured on agents, so that their
+        // capabilities are known when the agents run
+        let mut agent_providers: Vec<&ProviderDetails> = Vec::new();
+        for details in conversation
+            .agents
+            .iter()
+            .filter_map(|agent| agent.provider.as_ref())
+        {
+            if agent_providers.contains(&details) {
+                continue;
+            }
+            agent_providers.push(details);
+
+            let agent_models = match services.get_agent_provider(config.clone(), details).await {
+                Ok(provider) => services.models(provider).await,
+                Err(error) => Err(error),
+            };
+            match agent_models {
+                Ok(agent_models) => models.extend(agent_models),
+                Err(error) => {
+                    tracing::warn!(
+                        error = ?error,
+                        provider = %details.provider_type,
+                        "Failed to list the models of an agent provider"
+                    )
+                }
+            }
+        }
+
+        // Report problems with the provider configuration once per conversation
+        let provider_warnings = if conversation.context.is_none() {
+            services.validate_providers().await.unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let mcp_failures = if conversation.context.is_none() {
+            services.mcp_failures().await
+        } else {
+            Default::default()
+        };
 
         // Discover files using the discovery service
         let workflow = self
@@ -69,13 +118,19 @@ impl<S: Services> ForgeApp<S> {
             .await
             .unwrap_or_default();
         let max_depth = workflow.max_walker_depth;
-        let environment = services.get_environment();
+        let walker_filter = workflow.walker.clone().unwrap_or_default();
+        let generated = Arc::new(GeneratedFiles::new(&workflow.generated_files));
+        let mut environment = services.get_environment();
+        if let Some(retry) = retry {
+            environment.retry_config = retry.apply(&environment.retry_config);
+        }
 
         let mut walker = Walker::conservative().cwd(environment.cwd.clone());
 
         if let Some(depth) = max_depth {
             walker = walker.max_depth(depth);
         };
+        walker = walker.filter(&walker_filter);
 
         let files = services
             .collect_files(walker)
@@ -83,13 +138,40 @@ impl<S: Services> ForgeApp<S> {
             .into_iter()
             .map(|f| f.path)
             .collect::<Vec<_>>();
+        let git_branch = services.git_branch(&environment.cwd).await;
+        let project_memory = services.project_memory(&environment.cwd).await;
+
+        // Facts remembered in earlier sessions are recalled once, for the first
+        // message of the conversation
+        let memories = match chat.event.value.as_ref() {
+            Some(value) if conversation.context.is_none() => match services.load_memories().await {
+                Ok(memories) => {
+                    let project = services.memory_project(&environment.cwd).await;
+                    memories.recall(&project, &value.to_string(), RECALLED_MEMORIES)
+                }
+                Err(error) => {
+                    tracing::warn!(error = ?error, "Failed to recall memories");
+                    Vec::new()
+                }
+            },
+            _ => Vec::new(),
+        };
+
+        // The repo map is generated in the background so that the first message
+        // doesn't wait for the workspace to be outlined, and is added to the
+        // first message after it's ready. It's kept until it's refreshed.
+        if conversation.repo_map.is_none()
+            && let Some(config) = &workflow.repo_map
+            && let Some(files) = services.try_map_files(&walker_filter, &generated).await
+        {
+            conversation.repo_map = config.render(files);
+        }
 
         // Register templates using workflow path or environment fallback
         let template_path = workflow
             .templates
-            .map_or(environment.templates(), |templates| {
-                PathBuf::from(templates)
-            });
+            .as_ref()
+            .map_or(environment.templates(), PathBuf::from);
 
         services.register_template(template_path).await?;
 
@@ -99,8 +181,71 @@ impl<S: Services> ForgeApp<S> {
             chat.event = chat.event.attachments(attachments);
         }
 
+        // Pinned files are attached once, compaction keeps them in the context
+        // afterwards
+        for path in &conversation.pins.files {
+            let in_context = conversation
+                .context
+                .iter()
+                .flat_map(|context| &context.messages)
+                .any(|message| Pins::holds_file(path, message));
+            if in_context
+                || chat
+                    .event
+                    .attachments
+                    .iter()
+                    .any(|attached| &attached.path == path)
+            {
+                continue;
+            }
+            match services.attachments(&format!("@[{path}]")).await {
+                Ok(attachments) => chat.event.attachments.extend(attachments),
+                Err(error) => {
+                    tracing::warn!(error = ?error, path = %path, "Failed to attach pinned file")
+                }
+            }
+        }
+
+        // Attach the parts of the workspace most relevant to the message
+        if let Some(retrieval) = workflow
+            .retrieval
+            .as_ref()
+            .filter(|r| r.context_chunks() > 0)
+            && let Some(value) = chat.event.value.as_ref()
+        {
+            match services
+                .semantic_search(
+                    provider,
+                    retrieval.model.clone(),
+                    value.to_string(),
+                    retrieval.context_chunks(),
+                    &walker_filter,
+                    &generated,
+                )
+                .await
+            {
+                Ok(matches) => {
+                    for matched in matches {
+                        let attachment = matched.chunk.into_attachment(&environment.cwd);
+                        if !chat
+                            .event
+                            .attachments
+                            .iter()
+                            .any(|attached| attached.path == attachment.path)
+                        {
+                            chat.event.attachments.push(attachment);
+                        }
+                    }
+                }
+                // Retrieval only adds context, the message is sent without it
+                Err(error) => {
+                    tracing::warn!(error = ?error, "Failed to retrieve workspace context")
+                }
+            }
+        }
+
         // Create the orchestrator with all necessary dependencies
-        let orch = Orchestrator::new(
+        let mut orch = Orchestrator::new(
             services.clone(),
             environment.clone(),
             conversation,
@@ -108,7 +253,22 @@ impl<S: Services> ForgeApp<S> {
         )
         .tool_definitions(tool_definitions)
         .models(models)
-        .files(files);
+        .files(files)
+        .project_memory(project_memory)
+        .memories(memories)
+        .guard(Guard::new(
+            &workflow.guardrails.clone().unwrap_or_default(),
+            environment.home.as_deref(),
+        ))
+        .redactor(Redactor::new(
+            &workflow.redaction.clone().unwrap_or_default(),
+            std::env::vars(),
+        )?)
+        .generated(generated)
+        .workflow(workflow);
+        if let Some(git_branch) = git_branch {
+            orch = orch.git_branch(git_branch);
+        }
 
         // Create and return the stream
         let stream = MpscStream::spawn(
@@ -116,9 +276,38 @@ impl<S: Services> ForgeApp<S> {
                 async move {
                     let tx = Arc::new(tx);
 
+                    for warning in provider_warnings {
+                        tx.send(Ok(ChatResponse::ProviderWarning(warning)))
+                            .await
+                            .ok();
+                    }
+                    for (server, reason) in mcp_failures {
+                        tx.send(Ok(ChatResponse::McpServer(McpServerEvent::Unavailable {
+                            server,
+                            reason,
+                        })))
+                        .await
+                        .ok();
+                    }
+                    let mcp_notices = tokio::spawn({
+                        let tx = tx.clone();
+                        async move {
+                            loop {
+                                match mcp_events.recv().await {
+                                    Ok(event) => {
+                                        tx.send(Ok(ChatResponse::McpServer(event))).await.ok();
+                                    }
+                                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                                    Err(broadcast::error::RecvError::Closed) => break,
+                                }
+                            }
+                        }
+                    });
+
                     // Execute dispatch and always save conversation afterwards
                     let mut orch = orch.sender(tx.clone());
                     let dispatch_result = orch.chat(chat.event).await;
+                    mcp_notices.abort();
 
                     // Always save conversation using get_conversation()
                     let conversation = orch.get_conversation().clone();
@@ -178,7 +367,9 @@ impl<S: Services> ForgeApp<S> {
         // Apply compaction using the Compactor
         let compactor = Compactor::new(self.services.clone());
 
-        let compacted_context = compactor.compact(&agent, context, true).await?;
+        let compacted_context = compactor
+            .compact(&agent, context, true, &conversation.pins)
+            .await?;
 
         // Calculate compacted metrics
         let compacted_messages = compacted_context.messages.len();
@@ -199,9 +390,166 @@ impl<S: Services> ForgeApp<S> {
         ))
     }
 
+    /// Summarizes the conversation with the model of its main agent into the
+    /// decisions, open tasks and files touched. When `replace` is set the
+    /// summary takes the place of the history. Returns `None` when there's
+    /// nothing to summarize yet.
+    pub async fn summarize_conversation(
+        &self,
+        conversation_id: &ConversationId,
+        replace: bool,
+    ) -> Result<Option<String>> {
+        use crate::summarize::Summarizer;
+
+        let mut conversation = self
+            .services
+            .find(conversation_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Conversation not found: {}", conversation_id))?;
+        let Some(context) = conversation.context.clone() else {
+            return Ok(None);
+        };
+
+        let summarizer = Summarizer::new(self.services.clone());
+        let model = conversation.main_model()?;
+        let provider = conversation
+            .get_agent(&AgentId::default())?
+            .provider
+            .clone();
+        let Some(summary) = summarizer
+            .summarize(&model, provider.as_ref(), &context)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        if replace {
+            let context = summarizer
+                .replace_history(context, &summary, &conversation.pins)
+                .await?;
+            conversation.context = Some(context);
+            self.services.upsert(conversation).await?;
+        }
+
+        Ok(Some(summary))
+    }
+
+    /// Generates the repo map of the conversation again from the workspace as
+    /// it is now, with the default settings when none are configured. Returns
+    /// `None` when there's no source file to map.
+    pub async fn refresh_repo_map(
+        &self,
+        conversation_id: &ConversationId,
+    ) -> Result<Option<String>> {
+        let mut conversation = self
+            .services
+            .find(conversation_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Conversation not found: {}", conversation_id))?;
+        let workflow = self.workflow_manager.read_merged(None).await?;
+        let filter = workflow.walker.clone().unwrap_or_default();
+        let generated = GeneratedFiles::new(&workflow.generated_files);
+        let files = self.services.map_files(&filter, &generated).await?;
+        conversation.repo_map = workflow.repo_map.unwrap_or_default().render(files);
+
+        let repo_map = conversation.repo_map.clone();
+        self.services.upsert(conversation).await?;
+        Ok(repo_map)
+    }
+
+    /// Exports the context of the conversation as a markdown transcript, an
+    /// HTML page or JSON
+    pub async fn export_context(
+        &self,
+        conversation_id: &ConversationId,
+        format: ExportFormat,
+    ) -> Result<String> {
+        let conversation = self
+            .services
+            .find(conversation_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Conversation not found: {}", conversation_id))?;
+        Ok(conversation.export(format))
+    }
+
+    /// What changed in the context of the conversation between its last two
+    /// requests, `None` until two requests were sent
+    pub async fn context_diff(
+        &self,
+        conversation_id: &ConversationId,
+    ) -> Result<Option<ContextDiff>> {
+        let conversation = self
+            .services
+            .find(conversation_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Conversation not found: {}", conversation_id))?;
+        Ok(conversation.context_diff())
+    }
+
+    /// Tokens, cost, tool calls and time spent over the turns of the
+    /// conversation
+    pub async fn session_stats(&self, conversation_id: &ConversationId) -> Result<SessionStats> {
+        let conversation = self
+            .services
+            .find(conversation_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Conversation not found: {}", conversation_id))?;
+        Ok(conversation.stats())
+    }
+
+    /// Removes the sessions, request dumps and snapshots beyond the limits of
+    /// the retention, or only reports them on a dry run. The session kept, such
+    /// as the one in use, is never removed.
+    pub async fn prune(
+        &self,
+        retention: &Retention,
+        keep: Option<ConversationId>,
+        dry_run: bool,
+    ) -> Result<PruneReport> {
+        let keep = keep.map(ConversationId::into_string);
+        let items = self
+            .services
+            .stored_items()
+            .await?
+            .into_iter()
+            .filter(|item| item.kind != StoredKind::Session || Some(&item.id) != keep.as_ref())
+            .collect();
+        let mut items = retention.select(items, Utc::now());
+        if !dry_run {
+            items = self.services.remove_stored(&items).await?;
+        }
+        Ok(PruneReport::new(&items, dry_run))
+    }
+
+    /// Sends a recorded request again to the model, without running the
+    /// tools it calls, and collects its answer
+    pub async fn replay(
+        &self,
+        model: &ModelId,
+        context: forge_domain::Context,
+    ) -> Result<ChatCompletionMessageFull> {
+        use crate::agent::AgentService;
+
+        self.services
+            .chat_agent(model, context, None)
+            .await?
+            .into_full(false)
+            .await
+    }
+
     pub async fn list_tools(&self) -> Result<Vec<ToolDefinition>> {
         self.tool_registry.list().await
     }
+
+    /// Executes a single tool call on behalf of the agent, outside of a chat
+    pub async fn call_tool(
+        &self,
+        agent: &Agent,
+        context: &mut ToolCallContext,
+        call: ToolCallFull,
+    ) -> ToolResult {
+        self.tool_registry.call(agent, context, call).await
+    }
     pub async fn login(&self, init_auth: &InitAuth) -> Result<()> {
         self.authenticator.login(init_auth).await
     }
@@ -221,4 +569,21 @@ impl<S: Services> ForgeApp<S> {
     pub async fn write_workflow(&self, path: Option<&Path>, workflow: &Workflow) -> Result<()> {
         self.workflow_manager.write_workflow(path, workflow).await
     }
+
+    /// Makes the named profile from the workflow the active one and persists
+    /// the choice in the app config.
+    pub async fn activate_profile(&self, name: &str) -> Result<Profile> {
+        let workflow = self.workflow_manager.read_merged(None).await?;
+        let profile = workflow
+            .profiles
+            .get(name)
+            .cloned()
+            .ok_or_else(|| crate::error::Error::ProfileNotFound(name.to_string()))?;
+
+        let mut config = self.services.read_app_config().await.unwrap_or_default();
+        config.active_profile = Some(name.to_string());
+        self.services.write_app_config(&config).await?;
+
+        Ok(profile)
+    }
 }
@@ -20,10 +20,13 @@ impl<S: AppConfigService + AuthService> Authenticator<S> {
         self.service.init_auth().await
     }
     pub async fn login(&self, init_auth: &InitAuth) -> anyhow::Result<()> {
+        // Poll at the interval requested by the server until the device code expires
+        let interval = init_auth.interval.max(1);
         self.poll(
             RetryConfig::default()
-                .max_retry_attempts(300usize)
-                .max_delay(2)
+                .max_retry_attempts((init_auth.expires_in / interval) as usize)
+                .min_delay_ms(interval * 1000)
+                .max_delay(interval)
                 .backoff_factor(1u64),
             || self.login_inner(init_auth),
         )
@@ -58,6 +61,7 @@ impl<S: AppConfigService + AuthService> Authenticator<S> {
         let mut builder = ExponentialBuilder::default()
             .with_factor(1.0)
             .with_factor(config.backoff_factor as f32)
+            .with_min_delay(Duration::from_millis(config.min_delay_ms))
             .with_max_times(config.max_retry_attempts)
             .with_jitter();
         if let Some(max_delay) = config.max_delay {
@@ -66,7 +70,7 @@ impl<S: AppConfigService + AuthService> Authenticator<S> {
 
         call.retry(builder)
             .when(|e| {
-                // Only retry on Error::AuthInProgress (202 status)
+                // Only retry while the authorization is pending
                 e.downcast_ref::<Error>()
                     .map(|v| matches!(v, Error::AuthInProgress))
                     .unwrap_or(false)
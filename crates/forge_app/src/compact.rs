@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use forge_domain::{
     Agent, ChatCompletionMessage, ChatCompletionMessageFull, Compact, CompactionStrategy, Context,
-    ContextMessage, ResultStreamExt, extract_tag_content,
+    ContextMessage, Pins, ProviderDetails, ResultStreamExt, extract_tag_content,
 };
 use futures::Stream;
 use tracing::{debug, info};
@@ -19,12 +19,14 @@ impl<S: AgentService> Compactor<S> {
         Self { services }
     }
 
-    /// Apply compaction to the context if requested.
+    /// Apply compaction to the context if requested. Pinned messages are kept
+    /// as they are.
     pub async fn compact(
         &self,
         agent: &Agent,
         context: Context,
         max: bool,
+        pins: &Pins,
     ) -> anyhow::Result<Context> {
         if let Some(ref compact) = agent.compact {
             debug!(agent_id = %agent.id, "Context compaction triggered");
@@ -41,8 +43,14 @@ impl<S: AgentService> Compactor<S> {
             match strategy.eviction_range(&context) {
                 Some(sequence) => {
                     debug!(agent_id = %agent.id, "Compressing sequence");
-                    self.compress_single_sequence(compact, context, sequence)
-                        .await
+                    self.compress_single_sequence(
+                        compact,
+                        agent.provider.as_ref(),
+                        context,
+                        sequence,
+                        pins,
+                    )
+                    .await
                 }
                 None => {
                     debug!(agent_id = %agent.id, "No compressible sequences found");
@@ -58,15 +66,25 @@ impl<S: AgentService> Compactor<S> {
     async fn compress_single_sequence(
         &self,
         compact: &Compact,
+        provider: Option<&ProviderDetails>,
         mut context: Context,
         sequence: (usize, usize),
+        pins: &Pins,
     ) -> anyhow::Result<Context> {
         let (start, end) = sequence;
 
-        let sequence_messages = &context.messages[start..=end].to_vec();
+        let (pinned, sequence_messages): (Vec<_>, Vec<_>) = context.messages[start..=end]
+            .iter()
+            .cloned()
+            .partition(|message| pins.keeps(message));
+        if sequence_messages.is_empty() {
+            debug!("Every message of the sequence is pinned");
+            return Ok(context);
+        }
+        let sequence_messages = &sequence_messages;
 
         let summary = self
-            .generate_summary_for_sequence(compact, sequence_messages)
+            .generate_summary_for_sequence(compact, provider, sequence_messages)
             .await?;
 
         info!(
@@ -85,9 +103,10 @@ impl<S: AgentService> Compactor<S> {
             )
             .await?;
 
+        // Pinned messages follow the summary of the messages around them
         context.messages.splice(
             start..=end,
-            std::iter::once(ContextMessage::user(summary, None)),
+            std::iter::once(ContextMessage::user(summary, None)).chain(pinned),
         );
 
         Ok(context)
@@ -97,6 +116,7 @@ impl<S: AgentService> Compactor<S> {
     async fn generate_summary_for_sequence(
         &self,
         compact: &Compact,
+        provider: Option<&ProviderDetails>,
         messages: &[ContextMessage],
     ) -> anyhow::Result<String> {
         let sequence_context = messages
@@ -137,7 +157,7 @@ impl<S: AgentService> Compactor<S> {
             context = context.max_tokens(max_token);
         }
 
-        let response = self.services.chat_agent(model, context).await?;
+        let response = self.services.chat_agent(model, context, provider).await?;
 
         self.collect_completion_stream_content(compact, response)
             .await
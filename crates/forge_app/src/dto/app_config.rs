@@ -1,18 +1,92 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Utc};
 use derive_more::From;
+use forge_domain::OperationClass;
 use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+use url::Url;
 
+/// Device authorization response used to start an OAuth device-code login
 #[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
 pub struct InitAuth {
-    pub session_id: String,
-    pub auth_url: String,
-    pub token: String,
+    pub device_code: String,
+    /// Code the user enters on the verification page
+    pub user_code: String,
+    pub verification_uri: String,
+    /// Verification page with the user code already filled in
+    #[serde(default)]
+    pub verification_uri_complete: Option<String>,
+    /// Seconds until the device code expires
+    pub expires_in: u64,
+    /// Minimum number of seconds to wait between token requests
+    #[serde(default = "default_poll_interval")]
+    pub interval: u64,
+}
+
+fn default_poll_interval() -> u64 {
+    5
+}
+
+/// Token issued by the OAuth token endpoint
+#[derive(Deserialize)]
+pub struct AuthToken {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    /// Seconds until the access token expires
+    #[serde(default)]
+    pub expires_in: Option<u64>,
 }
 
 #[derive(Default, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AppConfig {
     pub key_info: Option<LoginInfo>,
+    /// Index of the active key for each provider environment variable that
+    /// holds more than one key.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub active_keys: HashMap<String, usize>,
+    /// Name of the profile from forge.yaml that is currently active
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>,
+    /// Credentials with which forge is authorized by remote MCP servers, by
+    /// the name of the server
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub mcp_credentials: HashMap<String, McpCredential>,
+    /// Classes of operations the user allowed in each workspace without being
+    /// asked again
+    #[serde(default, skip_serializing_if = "AllowedOperations::is_empty")]
+    pub allowed_operations: AllowedOperations,
+}
+
+/// Classes of operations allowed by the user for good in a workspace, when
+/// asked to confirm one of them. The ones allowed for a session are kept in
+/// its conversation instead.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AllowedOperations {
+    /// Classes allowed by the canonical path of the workspace
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub workspaces: BTreeMap<PathBuf, BTreeSet<OperationClass>>,
+}
+
+impl AllowedOperations {
+    pub fn is_empty(&self) -> bool {
+        self.workspaces.is_empty()
+    }
+
+    pub fn allow_in_workspace(&mut self, workspace: PathBuf, class: OperationClass) {
+        self.workspaces.entry(workspace).or_default().insert(class);
+    }
+
+    /// Whether the operations of the class were allowed in the workspace
+    pub fn is_allowed(&self, class: OperationClass, workspace: &Path) -> bool {
+        self.workspaces
+            .get(workspace)
+            .is_some_and(|classes| classes.contains(&class))
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize, From)]
@@ -27,4 +101,200 @@ pub struct LoginInfo {
     pub name: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub auth_provider_id: Option<String>,
+    /// Token used to obtain a new `api_key` once it expires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl LoginInfo {
+    /// Seconds before the actual expiry at which a token is treated as expired,
+    /// so that it doesn't lapse mid-request.
+    const EXPIRY_SKEW_SECS: i64 = 60;
+
+    pub fn from_token(token: AuthToken, now: DateTime<Utc>) -> Self {
+        let api_key_masked = mask_key(&token.access_token);
+        Self {
+            api_key: token.access_token,
+            api_key_name: "oauth".to_string(),
+            api_key_masked,
+            email: None,
+            name: None,
+            auth_provider_id: None,
+            refresh_token: token.refresh_token,
+            expires_at: token
+                .expires_in
+                .map(|secs| now + Duration::seconds(secs as i64)),
+        }
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at
+            .is_some_and(|at| at - Duration::seconds(Self::EXPIRY_SKEW_SECS) <= now)
+    }
+}
+
+/// OAuth client registered with the authorization server of a remote MCP
+/// server, along with the token it was issued
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpCredential {
+    /// URL of the MCP server the token was issued for
+    pub url: String,
+    pub client_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    pub token_endpoint: String,
+    pub access_token: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl McpCredential {
+    /// Replaces the token of the credential, keeping the refresh token when
+    /// the server doesn't rotate it
+    pub fn token(self, token: AuthToken, now: DateTime<Utc>) -> Self {
+        Self {
+            access_token: token.access_token,
+            refresh_token: token.refresh_token.or(self.refresh_token),
+            expires_at: token
+                .expires_in
+                .map(|secs| now + Duration::seconds(secs as i64)),
+            ..self
+        }
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at
+            .is_some_and(|at| at - Duration::seconds(LoginInfo::EXPIRY_SKEW_SECS) <= now)
+    }
+}
+
+/// Authorization of forge by a remote MCP server, waiting for the user to
+/// approve it in the browser
+pub struct McpAuthorization {
+    /// Name of the MCP server
+    pub server: String,
+    /// Page of the authorization server on which the user approves forge
+    pub url: Url,
+    /// URI to which the browser is redirected once the user decided
+    pub redirect_uri: Url,
+    /// Value the redirect must carry, to tell it apart from forged ones
+    pub state: String,
+    /// Secret of the PKCE challenge sent with the authorization request
+    pub code_verifier: String,
+    /// Registered client, without a token yet
+    pub credential: McpCredential,
+    /// Query parameters of the redirect
+    pub redirect: oneshot::Receiver<BTreeMap<String, String>>,
+}
+
+fn mask_key(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() <= 8 {
+        return "*".repeat(chars.len());
+    }
+    let prefix: String = chars[..4].iter().collect();
+    let suffix: String = chars[chars.len() - 4..].iter().collect();
+    format!("{prefix}...{suffix}")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_allowed_operations_is_allowed() {
+        let mut fixture = AllowedOperations::default();
+        fixture.allow_in_workspace(PathBuf::from("/projects/forge"), OperationClass::FileWrite);
+
+        let actual = [
+            fixture.is_allowed(OperationClass::FileWrite, Path::new("/projects/forge")),
+            fixture.is_allowed(OperationClass::FileWrite, Path::new("/projects/app")),
+            fixture.is_allowed(OperationClass::FileDelete, Path::new("/projects/forge")),
+        ];
+
+        let expected = [true, false, false];
+        assert_eq!(actual, expected);
+    }
+
+    fn fixture_token(expires_in: Option<u64>) -> AuthToken {
+        AuthToken {
+            access_token: "forge-access-token-1234".to_string(),
+            refresh_token: Some("refresh".to_string()),
+            expires_in,
+        }
+    }
+
+    #[test]
+    fn test_login_info_from_token() {
+        let now = Utc::now();
+        let actual = LoginInfo::from_token(fixture_token(Some(3600)), now);
+
+        assert_eq!(actual.api_key, "forge-access-token-1234");
+        assert_eq!(actual.api_key_masked, "forg...1234");
+        assert_eq!(actual.refresh_token, Some("refresh".to_string()));
+        assert_eq!(actual.expires_at, Some(now + Duration::seconds(3600)));
+    }
+
+    #[test]
+    fn test_login_info_is_expired() {
+        let now = Utc::now();
+        let fixture = LoginInfo::from_token(fixture_token(Some(3600)), now);
+
+        assert_eq!(fixture.is_expired(now), false);
+        assert_eq!(fixture.is_expired(now + Duration::seconds(3560)), true);
+        assert_eq!(fixture.is_expired(now + Duration::seconds(7200)), true);
+    }
+
+    #[test]
+    fn test_login_info_without_expiry_never_expires() {
+        let now = Utc::now();
+        let fixture = LoginInfo::from_token(fixture_token(None), now);
+
+        assert_eq!(fixture.is_expired(now + Duration::days(365)), false);
+    }
+
+    #[test]
+    fn test_mcp_credential_keeps_refresh_token() {
+        let now = Utc::now();
+        let fixture = McpCredential {
+            url: "https://mcp.example.com/mcp".to_string(),
+            client_id: "client".to_string(),
+            client_secret: None,
+            token_endpoint: "https://mcp.example.com/token".to_string(),
+            access_token: "old".to_string(),
+            refresh_token: Some("refresh".to_string()),
+            expires_at: Some(now),
+        };
+
+        let actual = fixture.token(
+            AuthToken {
+                access_token: "new".to_string(),
+                refresh_token: None,
+                expires_in: Some(3600),
+            },
+            now,
+        );
+
+        assert_eq!(actual.access_token, "new");
+        assert_eq!(actual.refresh_token, Some("refresh".to_string()));
+        assert_eq!(actual.is_expired(now), false);
+    }
+
+    #[test]
+    fn test_init_auth_default_interval() {
+        let actual: InitAuth = serde_json::from_str(
+            r#"{"device_code":"dc","user_code":"ABCD-EFGH","verification_uri":"https://forgecode.dev/device","expires_in":900}"#,
+        )
+        .unwrap();
+
+        assert_eq!(actual.interval, 5);
+        assert_eq!(actual.verification_uri_complete, None);
+    }
 }
@@ -0,0 +1,31 @@
+use forge_domain::ModelId;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Clone)]
+pub struct EmbeddingRequest {
+    pub model: ModelId,
+    pub input: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct EmbeddingResponse {
+    pub data: Vec<Embedding>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Embedding {
+    pub index: usize,
+    pub embedding: Vec<f32>,
+}
+
+impl EmbeddingResponse {
+    /// Returns the embeddings in the order of the inputs, as providers don't
+    /// have to keep it
+    pub fn into_embeddings(mut self) -> Vec<Vec<f32>> {
+        self.data.sort_by_key(|embedding| embedding.index);
+        self.data
+            .into_iter()
+            .map(|embedding| embedding.embedding)
+            .collect()
+    }
+}
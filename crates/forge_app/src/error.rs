@@ -8,9 +8,6 @@ pub enum Error {
     #[error("Tool {0} not found")]
     NotFound(ToolName),
 
-    #[error("Tool '{tool_name}' timed out after {timeout} minutes")]
-    CallTimeout { tool_name: ToolName, timeout: u64 },
-
     #[error(
         "Tool '{name}' is not available. Please try again with one of these tools: [{supported_tools}]"
     )]
@@ -19,9 +16,31 @@ pub enum Error {
         supported_tools: String,
     },
 
+    #[error(
+        "Agent '{agent}' is not available for handoffs. Please try again with one of these agents: [{supported_agents}]"
+    )]
+    HandoffNotAllowed {
+        agent: String,
+        supported_agents: String,
+    },
+
+    #[error(
+        "Tool '{0}' is not available in plan mode. Only read the workspace and create the plan until the user approves it"
+    )]
+    NotAllowedInPlanMode(ToolName),
+
+    #[error("Blocked by a hook: {0}")]
+    BlockedByHook(String),
+
     #[error("Empty tool response")]
     EmptyToolResponse,
 
     #[error("Authentication still in progress")]
     AuthInProgress,
+
+    #[error("Profile '{0}' is not defined in forge.yaml")]
+    ProfileNotFound(String),
+
+    #[error("Semantic search requires an embedding model, set `retrieval.model` in forge.yaml")]
+    RetrievalNotConfigured,
 }
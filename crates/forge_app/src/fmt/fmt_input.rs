@@ -2,7 +2,7 @@ use std::convert::AsRef;
 use std::path::Path;
 
 use forge_display::TitleFormat;
-use forge_domain::{Environment, Tools};
+use forge_domain::{BackgroundAction, Environment, Tools, parse_unified_diff};
 
 use crate::fmt::content::{ContentFormat, FormatContent};
 use crate::utils::format_display_path;
@@ -53,6 +53,17 @@ impl FormatContent for Tools {
                 };
                 Some(TitleFormat::debug(title).into())
             }
+            Tools::ForgeToolFsSemanticSearch(input) => {
+                Some(TitleFormat::debug(format!("Semantic search for '{}'", input.query)).into())
+            }
+            Tools::ForgeToolFsOutline(input) => {
+                let display_path = display_path_for(&input.path);
+                Some(TitleFormat::debug("Outline").sub_title(display_path).into())
+            }
+            Tools::ForgeToolFsFindSymbol(input) => {
+                Some(TitleFormat::debug(format!("Find definitions of '{}'", input.symbol)).into())
+            }
+            Tools::ForgeToolFsStats(_) => Some(TitleFormat::debug("Workspace stats").into()),
             Tools::ForgeToolFsRemove(input) => {
                 let display_path = display_path_for(&input.path);
                 Some(TitleFormat::debug("Remove").sub_title(display_path).into())
@@ -65,23 +76,104 @@ impl FormatContent for Tools {
                         .into(),
                 )
             }
+            Tools::ForgeToolFsAstEdit(input) => {
+                let display_path = display_path_for(&input.path);
+                let sub_title = match &input.symbol {
+                    Some(symbol) => format!("{display_path} {symbol}"),
+                    None => display_path,
+                };
+                Some(
+                    TitleFormat::debug(input.operation.as_ref())
+                        .sub_title(sub_title)
+                        .into(),
+                )
+            }
+            Tools::ForgeToolFsApplyPatch(input) => {
+                let paths = parse_unified_diff(&input.patch)
+                    .map(|diffs| {
+                        diffs
+                            .iter()
+                            .map(|diff| diff.path().display().to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    })
+                    .unwrap_or_default();
+                Some(TitleFormat::debug("Apply Patch").sub_title(paths).into())
+            }
             Tools::ForgeToolFsUndo(input) => {
                 let display_path = display_path_for(&input.path);
                 Some(TitleFormat::debug("Undo").sub_title(display_path).into())
             }
-            Tools::ForgeToolProcessShell(input) => Some(
-                TitleFormat::debug(format!("Execute [{}]", env.shell))
-                    .sub_title(&input.command)
+            Tools::ForgeToolLspFindReferences(input) => Some(
+                TitleFormat::debug(format!("Find references to '{}'", input.symbol))
+                    .sub_title(format!("{}:{}", display_path_for(&input.path), input.line))
+                    .into(),
+            ),
+            Tools::ForgeToolLspRenameSymbol(input) => Some(
+                TitleFormat::debug(format!("Rename '{}' to '{}'", input.symbol, input.new_name))
+                    .sub_title(format!("{}:{}", display_path_for(&input.path), input.line))
+                    .into(),
+            ),
+            Tools::ForgeToolProcessShell(input) => {
+                let shell = input
+                    .shell
+                    .map_or_else(|| env.shell.clone(), |shell| shell.to_string());
+                Some(
+                    TitleFormat::debug(format!("Execute [{shell}]"))
+                        .sub_title(&input.command)
+                        .into(),
+                )
+            }
+            Tools::ForgeToolProcessDocker(input) => {
+                let title = TitleFormat::debug(format!("Docker {}", input.action.as_ref()));
+                Some(match &input.target {
+                    Some(target) => title.sub_title(target).into(),
+                    None => title.into(),
+                })
+            }
+            Tools::ForgeToolProcessBackground(input) => {
+                let title = TitleFormat::debug(format!("Background {}", input.action.as_ref()));
+                let sub_title = match input.action {
+                    BackgroundAction::Start => input.command.clone(),
+                    _ => input.id.map(|id| format!("#{id}")),
+                };
+                Some(match sub_title {
+                    Some(sub_title) => title.sub_title(sub_title).into(),
+                    None => title.into(),
+                })
+            }
+            // Only the first line is shown, the code can be long
+            Tools::ForgeToolProcessPython(input) => Some(
+                TitleFormat::debug("Python")
+                    .sub_title(input.code.lines().next().unwrap_or_default())
                     .into(),
             ),
             Tools::ForgeToolNetFetch(input) => {
                 Some(TitleFormat::debug("GET").sub_title(&input.url).into())
             }
+            Tools::ForgeToolNetRequest(input) => Some(
+                TitleFormat::debug(input.method.as_ref())
+                    .sub_title(&input.url)
+                    .into(),
+            ),
+            Tools::ForgeToolNetBrowser(input) => {
+                let title = TitleFormat::debug(format!("Browser {}", input.action.as_ref()));
+                let target = input.url.as_ref().or(input.selector.as_ref());
+                Some(match target {
+                    Some(target) => title.sub_title(target).into(),
+                    None => title.into(),
+                })
+            }
             Tools::ForgeToolFollowup(input) => Some(
                 TitleFormat::debug("Follow-up")
                     .sub_title(&input.question)
                     .into(),
             ),
+            Tools::ForgeToolAskUser(input) => Some(
+                TitleFormat::debug("Question")
+                    .sub_title(&input.question)
+                    .into(),
+            ),
             Tools::ForgeToolAttemptCompletion(input) => {
                 Some(ContentFormat::Markdown(input.result.clone()))
             }
@@ -94,6 +186,12 @@ impl FormatContent for Tools {
             Tools::ForgeToolTaskListUpdate(_) => {
                 Some(TitleFormat::debug("Task Update".to_string()).into())
             }
+            Tools::ForgeToolTaskListComplete(_) => {
+                Some(TitleFormat::debug("Task Complete".to_string()).into())
+            }
+            Tools::ForgeToolTaskListReprioritize(_) => {
+                Some(TitleFormat::debug("Task Reprioritize".to_string()).into())
+            }
             Tools::ForgeToolTaskListList(_) => {
                 Some(TitleFormat::debug("Task Read".to_string()).into())
             }
@@ -101,6 +199,32 @@ impl FormatContent for Tools {
                 Some(TitleFormat::debug("Task Clear".to_string()).into())
             }
             Tools::ForgeToolPlanCreate(_) => None,
+            Tools::ForgeToolMemoryRemember(input) => {
+                Some(TitleFormat::debug("Remember").sub_title(&input.fact).into())
+            }
+            Tools::ForgeToolMemoryRecall(input) => {
+                Some(TitleFormat::debug("Recall").sub_title(&input.query).into())
+            }
+            Tools::ForgeToolSpawnAgent(input) => Some(
+                TitleFormat::debug("Sub-agent")
+                    .sub_title(&input.task)
+                    .into(),
+            ),
+            Tools::ForgeToolFanOut(input) => Some(
+                TitleFormat::debug(format!("Fan-out over {} items", input.items.len()))
+                    .sub_title(&input.task)
+                    .into(),
+            ),
+            // The handoff is shown once the other agent takes over
+            Tools::ForgeToolHandoff(_) => None,
+            Tools::ForgeToolMessageSend(input) => Some(
+                TitleFormat::debug(format!("Message {}", input.to))
+                    .sub_title(&input.subject)
+                    .into(),
+            ),
+            Tools::ForgeToolMessageReceive(_) => {
+                Some(TitleFormat::debug("Receive Messages").into())
+            }
         }
     }
 }
@@ -151,6 +275,7 @@ mod tests {
             stdout_max_prefix_length: 10,
             stdout_max_suffix_length: 10,
             tool_timeout: 300,
+            max_concurrent_commands: 4,
             stdout_max_line_length: 2000,
             http: Default::default(),
             max_file_size: 0,
@@ -234,7 +359,10 @@ mod tests {
     fn test_shell_command() {
         let fixture = Tools::ForgeToolProcessShell(Shell {
             command: "ls -la".to_string(),
-            cwd: PathBuf::from("/home/user/project"),
+            cwd: Some(PathBuf::from("/home/user/project")),
+            shell: None,
+            stdin: None,
+            max_attempts: None,
             keep_ansi: false,
             explanation: None,
         });
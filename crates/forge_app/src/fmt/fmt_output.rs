@@ -1,6 +1,7 @@
 use forge_display::{DiffFormat, GrepFormat, TitleFormat};
 use forge_domain::Environment;
 
+use crate::DryRunOutput;
 use crate::fmt::content::{ContentFormat, FormatContent};
 use crate::operation::Operation;
 use crate::utils::{format_display_path, format_match};
@@ -11,6 +12,10 @@ impl FormatContent for Operation {
             Operation::FsRead { input: _, output: _ } => None,
             Operation::FsCreate { input: _, output: _ } => None,
             Operation::FsRemove { input: _ } => None,
+            Operation::FsSemanticSearch { input: _, output: _ } => None,
+            Operation::FsOutline { input: _, output: _ } => None,
+            Operation::FsFindSymbol { input: _, output: _ } => None,
+            Operation::FsStats { input: _, output: _ } => None,
             Operation::FsSearch { input: _, output } => output.as_ref().map(|result| {
                 ContentFormat::PlainText(
                     GrepFormat::new(
@@ -23,19 +28,51 @@ impl FormatContent for Operation {
                     .format(),
                 )
             }),
-            Operation::FsPatch { input: _, output } => Some(ContentFormat::PlainText(
-                DiffFormat::format(&output.before, &output.after)
-                    .diff()
-                    .to_string(),
-            )),
+            Operation::FsPatch { input: _, output } | Operation::FsAstEdit { input: _, output } => {
+                Some(ContentFormat::PlainText(
+                    DiffFormat::format(&output.before, &output.after)
+                        .diff()
+                        .to_string(),
+                ))
+            }
+            Operation::FsApplyPatch { input: _, output }
+            | Operation::LspRenameSymbol { input: _, output }
+            | Operation::DryRun { output: DryRunOutput::Changes(output) } => {
+                Some(ContentFormat::PlainText(
+                    output
+                        .files
+                        .iter()
+                        .map(|file| {
+                            DiffFormat::format(
+                                file.before.as_deref().unwrap_or_default(),
+                                file.after.as_deref().unwrap_or_default(),
+                            )
+                            .diff()
+                            .to_string()
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                ))
+            }
             Operation::FsUndo { input: _, output: _ } => None,
+            Operation::LspFindReferences { input: _, output: _ } => None,
             Operation::NetFetch { input: _, output: _ } => None,
+            Operation::NetRequest { input: _, output: _ } => None,
+            Operation::NetBrowser { input: _, output: _ } => None,
             Operation::Shell { output: _ } => None,
+            Operation::Python { input: _, output: _ } => None,
+            Operation::Process { input: _, output: _ } => None,
+            Operation::DryRun { output: DryRunOutput::Command(_) } => None,
+            Operation::DryRun { output: DryRunOutput::Python(_) } => None,
+            Operation::DryRun { output: DryRunOutput::Skipped } => None,
             Operation::FollowUp { output: _ } => None,
+            Operation::AskUser { answer: _ } => None,
             Operation::AttemptCompletion => None,
             Operation::TaskListAppend { _input: _, before, after }
             | Operation::TaskListAppendMultiple { _input: _, before, after }
             | Operation::TaskListUpdate { _input: _, before, after }
+            | Operation::TaskListComplete { _input: _, before, after }
+            | Operation::TaskListReprioritize { _input: _, before, after }
             | Operation::TaskListList { _input: _, before, after }
             | Operation::TaskListClear { _input: _, before, after } => Some(
                 ContentFormat::Markdown(crate::fmt::fmt_task::to_markdown(before, after)),
@@ -47,6 +84,10 @@ impl FormatContent for Operation {
                 ))
                 .into(),
             ),
+            Operation::MemoryRemember { input: _, output: _ } => None,
+            Operation::MemoryRecall { input: _, output: _ } => None,
+            Operation::MessageSend { message: _ } => None,
+            Operation::MessageReceive { messages: _ } => None,
         }
     }
 }
@@ -122,6 +163,7 @@ mod tests {
             stdout_max_prefix_length: 10,
             stdout_max_suffix_length: 10,
             tool_timeout: 300,
+            max_concurrent_commands: 4,
             stdout_max_line_length: 2000,
             http: Default::default(),
             max_file_size: 0,
@@ -271,6 +313,7 @@ mod tests {
                 file_pattern: None,
                 max_search_lines: None,
                 start_index: None,
+                context_lines: None,
                 explanation: Some("Search for Hello".to_string()),
             },
             output: Some(SearchResult {
@@ -314,6 +357,7 @@ mod tests {
                 file_pattern: None,
                 max_search_lines: None,
                 start_index: None,
+                context_lines: None,
                 explanation: Some("Search for nonexistent".to_string()),
             },
             output: Some(SearchResult {
@@ -342,6 +386,7 @@ mod tests {
                 file_pattern: None,
                 max_search_lines: None,
                 start_index: None,
+                context_lines: None,
                 explanation: Some("Search test".to_string()),
             },
             output: None,
@@ -478,6 +523,8 @@ mod tests {
                     stdout: "file1.txt\nfile2.txt".to_string(),
                     stderr: "".to_string(),
                     exit_code: Some(0),
+                    failed_attempts: Vec::new(),
+                    cwd: None,
                 },
                 shell: "/bin/bash".to_string(),
             },
@@ -499,6 +546,8 @@ mod tests {
                     stdout: "output line".to_string(),
                     stderr: "warning line".to_string(),
                     exit_code: Some(0),
+                    failed_attempts: Vec::new(),
+                    cwd: None,
                 },
                 shell: "/bin/bash".to_string(),
             },
@@ -520,6 +569,8 @@ mod tests {
                     stdout: "".to_string(),
                     stderr: "Error: command not found".to_string(),
                     exit_code: Some(127),
+                    failed_attempts: Vec::new(),
+                    cwd: None,
                 },
                 shell: "/bin/bash".to_string(),
             },
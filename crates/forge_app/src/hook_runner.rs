@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use forge_domain::{
+    CommandSettings, Environment, Hook, HookOutcome, HookPayload, HttpMethod, ShellKind, Workflow,
+};
+
+use crate::{EnvironmentService, NetRequestService, ShellService};
+
+/// Runs the hooks configured in the workflow for an action of the agent loop
+pub struct HookRunner<S> {
+    services: Arc<S>,
+}
+
+impl<S: EnvironmentService + ShellService + NetRequestService> HookRunner<S> {
+    pub fn new(services: Arc<S>) -> Self {
+        Self { services }
+    }
+
+    /// Runs the hooks of the workflow that apply to the payload in the order
+    /// they are configured, stopping at the first one that blocks the action.
+    /// A hook that fails to run is logged and skipped, so that a broken hook
+    /// doesn't stop the agent.
+    pub async fn run(&self, workflow: &Workflow, payload: &HookPayload) -> HookOutcome {
+        let mut hooks = workflow
+            .hooks
+            .iter()
+            .filter(|hook| hook.applies(payload))
+            .peekable();
+        if hooks.peek().is_none() {
+            return HookOutcome::default();
+        }
+
+        let env = self.services.get_environment();
+        // Hooks are written by the user, so the policy of the agents doesn't
+        // apply to them
+        let settings = CommandSettings::default().env(workflow.env.clone().unwrap_or_default());
+        let mut outcome = HookOutcome::default();
+
+        for hook in hooks {
+            match self.run_hook(hook, payload, &env, &settings).await {
+                Ok(hook_outcome) => outcome = outcome.merge(hook_outcome),
+                Err(error) => {
+                    tracing::warn!(error = ?error, event = ?payload.event, "Failed to run a hook")
+                }
+            }
+            if outcome.block.is_some() {
+                break;
+            }
+        }
+
+        outcome
+    }
+
+    async fn run_hook(
+        &self,
+        hook: &Hook,
+        payload: &HookPayload,
+        env: &Environment,
+        settings: &CommandSettings,
+    ) -> anyhow::Result<HookOutcome> {
+        let mut outcome = HookOutcome::default();
+
+        if let Some(command) = hook.render(payload, ShellKind::from_program(&env.shell))? {
+            tracing::debug!(command = %command, "Running hook command");
+            let output = self
+                .services
+                .execute(command, env.cwd.clone(), hook.shell, false, None, settings)
+                .await?;
+            outcome = outcome.merge(HookOutcome::from_command(&output.output));
+        }
+
+        if outcome.block.is_none()
+            && let Some(url) = &hook.url
+        {
+            tracing::debug!(url = %url, "Calling hook webhook");
+            let headers =
+                BTreeMap::from([("Content-Type".to_string(), "application/json".to_string())]);
+            let response = self
+                .services
+                .request(
+                    HttpMethod::Post,
+                    url.clone(),
+                    headers,
+                    Some(serde_json::to_string(payload)?),
+                )
+                .await?;
+            outcome = outcome.merge(HookOutcome::from_response(response.code, &response.content));
+        }
+
+        Ok(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    use forge_domain::{CommandOutput, HookEvent, HttpConfig, RetryConfig};
+    use pretty_assertions::assert_eq;
+    use url::Url;
+
+    use super::*;
+    use crate::{HttpResponse, ShellOutput};
+
+    /// Runs the commands `ok <message>` and `block <reason>`, fails to run any
+    /// other and can't reach any webhook
+    #[derive(Default)]
+    struct Fixture {
+        commands: Mutex<Vec<String>>,
+    }
+
+    impl EnvironmentService for Fixture {
+        fn get_environment(&self) -> Environment {
+            Environment {
+                os: "linux".to_string(),
+                pid: 1234,
+                cwd: PathBuf::from("/home/user/project"),
+                home: Some(PathBuf::from("/home/user")),
+                shell: "/bin/bash".to_string(),
+                base_path: PathBuf::from("/home/user/.forge"),
+                forge_api_url: Url::parse("http://localhost:8000").unwrap(),
+                retry_config: RetryConfig {
+                    initial_backoff_ms: 0,
+                    min_delay_ms: 0,
+                    backoff_factor: 0,
+                    max_retry_attempts: 0,
+                    retry_status_codes: Default::default(),
+                    max_delay: Default::default(),
+                    suppress_retry_errors: Default::default(),
+                },
+                tool_timeout: 300,
+                max_concurrent_commands: 4,
+                max_search_lines: 1000,
+                fetch_truncation_limit: 1024,
+                stdout_max_prefix_length: 256,
+                stdout_max_suffix_length: 256,
+                max_read_size: 4096,
+                http: HttpConfig::default(),
+                max_file_size: 1024,
+                max_search_result_bytes: 200,
+                stdout_max_line_length: 200,
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ShellService for Fixture {
+        async fn execute(
+            &self,
+            command: String,
+            _cwd: PathBuf,
+            _shell: Option<ShellKind>,
+            _keep_ansi: bool,
+            _stdin: Option<String>,
+            _settings: &CommandSettings,
+        ) -> anyhow::Result<ShellOutput> {
+            self.commands.lock().unwrap().push(command.clone());
+            let (exit_code, stdout) = match command.split_once(' ') {
+                Some(("ok", message)) => (0, message.to_string()),
+                Some(("block", reason)) => (1, reason.to_string()),
+                _ => anyhow::bail!("'{command}' can't be run"),
+            };
+            let output = CommandOutput {
+                command,
+                stdout,
+                stderr: String::new(),
+                exit_code: Some(exit_code),
+                failed_attempts: Vec::new(),
+                cwd: None,
+            };
+            Ok(ShellOutput { output, shell: "bash".to_string() })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl NetRequestService for Fixture {
+        async fn request(
+            &self,
+            _method: HttpMethod,
+            url: String,
+            _headers: BTreeMap<String, String>,
+            _body: Option<String>,
+        ) -> anyhow::Result<HttpResponse> {
+            anyhow::bail!("Failed to send request to {url}")
+        }
+    }
+
+    fn workflow(hooks: Vec<Hook>) -> Workflow {
+        Workflow { hooks, ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn test_failing_hooks_are_skipped() {
+        let fixture = HookRunner::new(Arc::new(Fixture::default()));
+        let workflow = workflow(vec![
+            Hook::new(HookEvent::PostTurn).command("missing"),
+            Hook::new(HookEvent::PostTurn).url("http://localhost:1/hook"),
+            Hook::new(HookEvent::PostTurn).command("ok done"),
+        ]);
+
+        let actual = fixture
+            .run(&workflow, &HookPayload::new(HookEvent::PostTurn))
+            .await;
+
+        let expected = HookOutcome { block: None, messages: vec!["done".to_string()] };
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_blocking_hook_stops_the_following_ones() {
+        let services = Arc::new(Fixture::default());
+        let fixture = HookRunner::new(services.clone());
+        let workflow = workflow(vec![
+            Hook::new(HookEvent::PreTurn).command("block not now"),
+            Hook::new(HookEvent::PreTurn).command("ok never run"),
+            Hook::new(HookEvent::PostTurn).command("ok other event"),
+        ]);
+
+        let outcome = fixture
+            .run(&workflow, &HookPayload::new(HookEvent::PreTurn))
+            .await;
+        let commands = services.commands.lock().unwrap().clone();
+
+        let actual = (outcome.block, commands);
+        let expected = (
+            Some("not now".to_string()),
+            vec!["block not now".to_string()],
+        );
+        assert_eq!(actual, expected);
+    }
+}
@@ -6,13 +6,16 @@ mod compact;
 pub mod dto;
 mod error;
 mod fmt;
+mod hook_runner;
 mod mcp_executor;
 mod operation;
 mod orch;
 #[cfg(test)]
 mod orch_spec;
+mod plugin_executor;
 mod retry;
 mod services;
+mod summarize;
 mod tool_executor;
 mod tool_registry;
 mod truncation;
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use forge_display::TitleFormat;
-use forge_domain::{ToolCallContext, ToolCallFull, ToolName, ToolOutput};
+use forge_domain::{Agent, ToolCallContext, ToolCallFull, ToolName, ToolOutput};
 
 use crate::McpService;
 
@@ -23,10 +23,18 @@ impl<S: McpService> McpExecutor<S> {
             .send_text(TitleFormat::info("MCP").sub_title(input.name.as_str()))
             .await?;
 
+        // The servers work where the commands of the agent do
+        let cwd = context.cwd.clone().filter(|cwd| cwd.is_dir());
+        self.services.set_mcp_cwd(cwd).await?;
         self.services.call(input).await
     }
 
-    pub async fn contains_tool(&self, tool_name: &ToolName) -> anyhow::Result<bool> {
+    /// Checks if the tool is provided by an MCP server and the agent is allowed
+    /// to use it
+    pub async fn contains_tool(&self, agent: &Agent, tool_name: &ToolName) -> anyhow::Result<bool> {
+        if !agent.allows_tool(tool_name) {
+            return Ok(false);
+        }
         let mcp_tools = self.services.list().await?;
         Ok(mcp_tools.iter().any(|tool| tool.name == *tool_name))
     }
@@ -1,12 +1,14 @@
 // Tests for this module can be found in: tests/orch_*.rs
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use async_recursion::async_recursion;
 use derive_setters::Setters;
+use forge_display::TitleFormat;
 use forge_domain::*;
 use forge_template::Element;
+use futures::StreamExt;
 use serde_json::Value;
 use tracing::{debug, info, warn};
 
@@ -15,6 +17,14 @@ use crate::compact::Compactor;
 
 pub type ArcSender = Arc<tokio::sync::mpsc::Sender<anyhow::Result<ChatResponse>>>;
 
+/// Number of times the conversation can be handed off in a turn, so that
+/// agents don't keep handing it back and forth
+const MAX_HANDOFFS_PER_TURN: usize = 5;
+
+/// Answers to the question asking the user to approve a plan made in plan mode
+const EXECUTE_PLAN: &str = "Execute the plan";
+const KEEP_PLANNING: &str = "Keep planning";
+
 #[derive(Clone, Setters)]
 #[setters(into, strip_option)]
 pub struct Orchestrator<S> {
@@ -25,7 +35,18 @@ pub struct Orchestrator<S> {
     tool_definitions: Vec<ToolDefinition>,
     models: Vec<Model>,
     files: Vec<String>,
+    git_branch: Option<String>,
+    project_memory: Vec<ProjectMemory>,
+    memories: Vec<Memory>,
     current_time: chrono::DateTime<chrono::Local>,
+    workflow: Arc<Workflow>,
+    guard: Arc<Guard>,
+    redactor: Arc<Redactor>,
+    generated: Arc<GeneratedFiles>,
+    #[setters(skip)]
+    handoffs: usize,
+    #[setters(skip)]
+    hook_messages: Vec<String>,
 }
 
 impl<S: AgentService> Orchestrator<S> {
@@ -43,7 +64,16 @@ impl<S: AgentService> Orchestrator<S> {
             tool_definitions: Default::default(),
             models: Default::default(),
             files: Default::default(),
+            git_branch: Default::default(),
+            project_memory: Default::default(),
+            memories: Default::default(),
             current_time,
+            workflow: Default::default(),
+            guard: Default::default(),
+            redactor: Default::default(),
+            generated: Default::default(),
+            handoffs: 0,
+            hook_messages: Default::default(),
         }
     }
 
@@ -67,6 +97,10 @@ impl<S: AgentService> Orchestrator<S> {
             // Send the start notification
             self.send(ChatResponse::ToolCallStart(tool_call.clone()))
                 .await?;
+            self.publish(LifecycleEventKind::ToolCallStart {
+                agent_id: agent.id.clone(),
+                call: tool_call.clone(),
+            });
 
             // Execute the tool
             let tool_result = self
@@ -87,6 +121,23 @@ impl<S: AgentService> Orchestrator<S> {
             // Send the end notification
             self.send(ChatResponse::ToolCallEnd(tool_result.clone()))
                 .await?;
+            self.publish(LifecycleEventKind::ToolCallEnd {
+                agent_id: agent.id.clone(),
+                result: tool_result.clone(),
+            });
+
+            if self.conversation.persist {
+                self.services
+                    .checkpoint(
+                        &self.conversation.id,
+                        SessionEvent::ToolResult {
+                            call: tool_call.clone(),
+                            result: tool_result.clone(),
+                            tasks: tool_context.tasks.clone(),
+                        },
+                    )
+                    .await?;
+            }
 
             // Ensure all tool calls and results are recorded
             // Adding task completion records is critical for compaction to work correctly
@@ -103,17 +154,30 @@ impl<S: AgentService> Orchestrator<S> {
         Ok(())
     }
 
+    /// Publishes the event to the subscribers of the lifecycle of the turns
+    fn publish(&self, kind: LifecycleEventKind) {
+        self.services
+            .publish_lifecycle(LifecycleEvent::new(self.conversation.id, kind));
+    }
+
     /// Get the allowed tools for an agent
     fn get_allowed_tools(&mut self, agent: &Agent) -> anyhow::Result<Vec<ToolDefinition>> {
         let completion = ToolsDiscriminants::ForgeToolAttemptCompletion;
+        let handoff = ToolsDiscriminants::ForgeToolHandoff;
+        let plan = ToolsDiscriminants::ForgeToolPlanCreate;
+        let plan_mode = self.conversation.plan_mode;
         let mut tools = vec![];
         if !self.tool_definitions.is_empty() {
-            let allowed = agent.tools.iter().flatten().collect::<HashSet<_>>();
             tools.extend(
                 self.tool_definitions
                     .iter()
                     .filter(|tool| tool.name != completion.name())
-                    .filter(|tool| allowed.contains(&tool.name))
+                    .filter(|tool| self.can_hand_off() || tool.name != handoff.name())
+                    .filter(|tool| !plan_mode || Tools::is_planning(&tool.name))
+                    // Every agent can create the plan it's asked for in plan mode
+                    .filter(|tool| {
+                        agent.allows_tool(&tool.name) || (plan_mode && tool.name == plan.name())
+                    })
                     .cloned(),
             );
         }
@@ -123,6 +187,27 @@ impl<S: AgentService> Orchestrator<S> {
         Ok(tools)
     }
 
+    fn can_hand_off(&self) -> bool {
+        self.handoffs < MAX_HANDOFFS_PER_TURN
+    }
+
+    /// Agents the agent can hand the conversation off to
+    fn handoff_agents(&self, agent: &Agent) -> Vec<HandoffAgent> {
+        if !self.can_hand_off() {
+            return vec![];
+        }
+        agent
+            .handoffs
+            .iter()
+            .flatten()
+            .filter_map(|id| self.conversation.get_agent(id).ok())
+            .map(|agent| HandoffAgent {
+                id: agent.id.clone(),
+                description: agent.description.clone(),
+            })
+            .collect()
+    }
+
     /// Checks if parallel tool calls is supported by agent
     fn is_parallel_tool_call_supported(&self, agent: &Agent) -> bool {
         agent
@@ -200,6 +285,12 @@ impl<S: AgentService> Orchestrator<S> {
                 false => Some(ToolUsagePrompt::from(&self.get_allowed_tools(agent)?).to_string()),
             };
 
+            let workspace = WorkspaceVariables {
+                git_branch: self.git_branch.clone(),
+                variables: variables.clone(),
+                ..WorkspaceVariables::new(&env, &files)
+            };
+
             let ctx = SystemContext {
                 env: Some(env),
                 tool_information,
@@ -208,7 +299,16 @@ impl<S: AgentService> Orchestrator<S> {
                 custom_rules: agent.custom_rules.as_ref().cloned().unwrap_or_default(),
                 variables: variables.clone(),
                 supports_parallel_tool_calls,
-                agent_prompt: Some(self.services.render(&system_prompt.template, &()).await?),
+                agent_prompt: Some(
+                    self.services
+                        .render(&system_prompt.template, &workspace)
+                        .await?,
+                ),
+                project_memory: self.project_memory.clone(),
+                memories: self.memories.clone(),
+                repo_map: self.conversation.repo_map.clone(),
+                handoffs: self.handoff_agents(agent),
+                plan_mode: self.conversation.plan_mode,
             };
 
             let rendered_prompt = self
@@ -223,6 +323,68 @@ impl<S: AgentService> Orchestrator<S> {
     }
 
     pub async fn chat(&mut self, event: Event) -> anyhow::Result<()> {
+        // Instructions sent while no turn was running weren't meant for this one
+        let stale = self
+            .services
+            .receive_steering(&self.conversation.id, false)
+            .await?;
+        if !stale.is_empty() {
+            debug!(
+                conversation_id = %self.conversation.id,
+                count = stale.len(),
+                "Dropping stale steering"
+            );
+        }
+
+        self.publish(LifecycleEventKind::TurnStart { event: event.name.clone() });
+        let result = self.run_turn(event).await;
+        if let Err(error) = &result {
+            self.publish(LifecycleEventKind::Error { message: format!("{error:#}") });
+        }
+
+        // Nor are the ones sent too late for this turn to take them meant for the
+        // next one, which the user is told about
+        let late = self
+            .services
+            .receive_steering(&self.conversation.id, false)
+            .await
+            .unwrap_or_default();
+        if !late.is_empty() {
+            self.send(ChatResponse::Text {
+                text: TitleFormat::error("Not steered, the agent was done")
+                    .sub_title(late.join(" "))
+                    .to_string(),
+                is_complete: true,
+                is_md: false,
+            })
+            .await
+            .ok();
+        }
+        self.publish(LifecycleEventKind::TurnEnd);
+        result
+    }
+
+    async fn run_turn(&mut self, event: Event) -> anyhow::Result<()> {
+        let outcome = self
+            .services
+            .run_hooks(
+                &self.workflow,
+                &self.turn_hook_payload(HookEvent::PreTurn, &event),
+            )
+            .await;
+        if let Some(reason) = outcome.block {
+            self.send(ChatResponse::Text {
+                text: TitleFormat::error("Blocked by a hook")
+                    .sub_title(reason)
+                    .to_string(),
+                is_complete: true,
+                is_md: false,
+            })
+            .await?;
+            return Ok(());
+        }
+        self.hook_messages = outcome.messages;
+
         let target_agents = {
             debug!(
                 conversation_id = %self.conversation.id.clone(),
@@ -238,16 +400,82 @@ impl<S: AgentService> Orchestrator<S> {
             self.init_agent(agent_id, &event).await?;
         }
 
+        // Nothing is left to block once the turn is over, so everything the
+        // hooks have to say is shown to the user
+        let outcome = self
+            .services
+            .run_hooks(
+                &self.workflow,
+                &self.turn_hook_payload(HookEvent::PostTurn, &event),
+            )
+            .await;
+        for message in outcome.messages.into_iter().chain(outcome.block) {
+            self.send(ChatResponse::Text {
+                text: TitleFormat::info("Hook").sub_title(message).to_string(),
+                is_complete: true,
+                is_md: false,
+            })
+            .await?;
+        }
+
         Ok(())
     }
 
+    /// Gives the instructions of the user to the agent. When they cut its
+    /// response short, what it had answered so far is kept before them.
+    async fn steer(
+        &self,
+        mut context: Context,
+        model_id: &ModelId,
+        partial: Option<&str>,
+        messages: Vec<String>,
+    ) -> anyhow::Result<Context> {
+        info!(conversation_id = %self.conversation.id, interrupted = partial.is_some(), "Steering the agent");
+        let partial = partial.map(|partial| remove_tag_with_prefix(partial, "forge_"));
+        if let Some(partial) = partial
+            .as_ref()
+            .filter(|partial| !partial.trim().is_empty())
+        {
+            self.send(ChatResponse::Text { text: partial.clone(), is_complete: true, is_md: true })
+                .await?;
+            context = context.add_message(ContextMessage::assistant(partial, None, None));
+        }
+        self.send(ChatResponse::Text {
+            text: TitleFormat::action("Steered")
+                .sub_title(messages.join(" "))
+                .to_string(),
+            is_complete: true,
+            is_md: false,
+        })
+        .await?;
+
+        let steering = Steering { messages, interrupted: partial.is_some() };
+        Ok(context.add_message(ContextMessage::user(
+            steering.to_element(),
+            model_id.clone().into(),
+        )))
+    }
+
+    /// Payload of the hooks that run around the turn started by the event
+    fn turn_hook_payload(&self, hook_event: HookEvent, event: &Event) -> HookPayload {
+        let payload = HookPayload::new(hook_event).conversation_id(self.conversation.id);
+        match event.value.as_ref() {
+            Some(Value::String(message)) => payload.message(message.clone()),
+            Some(value) => payload.message(value.to_string()),
+            None => payload,
+        }
+    }
+
     async fn execute_chat_turn(
         &self,
         model_id: &ModelId,
+        provider: Option<&ProviderDetails>,
         context: Context,
         tool_supported: bool,
         reasoning_supported: bool,
+        partial: Arc<std::sync::Mutex<String>>,
     ) -> anyhow::Result<ChatCompletionMessageFull> {
+        partial.lock().unwrap().clear();
         let mut transformers = TransformToolCalls::new()
             .when(|_| !tool_supported)
             .pipe(ImageHandling::new())
@@ -255,33 +483,100 @@ impl<S: AgentService> Orchestrator<S> {
             .pipe(ReasoningNormalizer.when(|_| reasoning_supported));
         let response = self
             .services
-            .chat_agent(model_id, transformers.transform(context))
+            .chat_agent(
+                model_id,
+                transformers.transform(self.guard.withhold(context)),
+                provider,
+            )
             .await?;
+
+        // Reasoning is sent as it's streamed, apart from the answer which is only
+        // sent once complete, and kept until then in case the turn is steered
+        let sender = self.sender.clone();
+        let response: BoxStream<ChatCompletionMessage, anyhow::Error> =
+            Box::pin(response.then(move |item| {
+                let sender = sender.clone();
+                let partial = partial.clone();
+                async move {
+                    if let Ok(message) = &item
+                        && let Some(content) = &message.content
+                    {
+                        partial.lock().unwrap().push_str(content.as_str());
+                    }
+                    if let (Some(sender), Ok(message)) = (&sender, &item)
+                        && let Some(reasoning) = &message.reasoning
+                        && !reasoning.as_str().is_empty()
+                    {
+                        let content = reasoning.as_str().to_string();
+                        let _ = sender.send(Ok(ChatResponse::Reasoning { content })).await;
+                    }
+                    item
+                }
+            }));
         response.into_full(!tool_supported).await
     }
-    /// Checks if compaction is needed and performs it if necessary
+    /// Checks if compaction is needed and performs it if necessary, along with
+    /// what triggered it
     async fn check_and_compact(
         &self,
         agent: &Agent,
+        model_id: &ModelId,
         context: &Context,
-    ) -> anyhow::Result<Option<Context>> {
+    ) -> anyhow::Result<Option<(CompactionTrigger, Context)>> {
         // Estimate token count for compaction decision
         let token_count = context.token_count();
-        if agent.should_compact(context, *token_count) {
-            info!(agent_id = %agent.id, "Compaction needed");
+        if let Some(trigger) =
+            agent.compaction_trigger(context, *token_count, self.context_length(model_id))
+        {
+            info!(agent_id = %agent.id, %trigger, "Compaction needed");
             Compactor::new(self.services.clone())
-                .compact(agent, context.clone(), false)
+                .compact(agent, context.clone(), false, &self.conversation.pins)
                 .await
-                .map(Some)
+                .map(|context| Some((trigger, context)))
         } else {
             debug!(agent_id = %agent.id, "Compaction not needed");
             Ok(None)
         }
     }
 
+    /// Compacts the context before it's sent when it fills more of the context
+    /// window of the model than the agent allows, instead of waiting for the
+    /// provider to reject it
+    async fn compact_to_fit(
+        &self,
+        agent: &Agent,
+        model_id: &ModelId,
+        context: &Context,
+    ) -> anyhow::Result<Option<Context>> {
+        let Some(compact) = agent.compact.as_ref() else {
+            return Ok(None);
+        };
+        let context_length = self.context_length(model_id);
+        // The usage reported for the last request misses the messages added since
+        let token_count = (*context.token_count()).max(context.token_count_approx());
+        if !compact.exceeds_context_window(token_count, context_length) {
+            return Ok(None);
+        }
+
+        info!(agent_id = %agent.id, token_count, context_length, "Context exceeds the target utilization of the model");
+        Compactor::new(self.services.clone())
+            .compact(agent, context.clone(), true, &self.conversation.pins)
+            .await
+            .map(Some)
+    }
+
+    /// Context window of the model, if the provider lists it
+    fn context_length(&self, model_id: &ModelId) -> Option<u64> {
+        self.models
+            .iter()
+            .find(|model| &model.id == model_id)
+            .and_then(|model| model.context_length)
+    }
+
     // Create a helper method with the core functionality
     async fn init_agent(&mut self, agent_id: &AgentId, event: &Event) -> anyhow::Result<()> {
         let mut tool_failure_attempts = HashMap::new();
+        let mut failure_tracker = FailureTracker::default();
         let variables = self.conversation.variables.clone();
         debug!(
             conversation_id = %self.conversation.id,
@@ -290,7 +585,12 @@ impl<S: AgentService> Orchestrator<S> {
             "Initializing agent"
         );
         let agent = self.conversation.get_agent(agent_id)?.clone();
-        let model_id = agent
+        let mut loop_detector = LoopDetector::new(
+            agent
+                .max_repeated_calls
+                .unwrap_or(DEFAULT_MAX_REPEATED_CALLS),
+        );
+        let mut model_id = agent
             .model
             .clone()
             .ok_or(Error::MissingModel(agent.id.clone()))?;
@@ -364,62 +664,188 @@ impl<S: AgentService> Orchestrator<S> {
                 })
             });
 
+        // Messages passed on by the hooks that ran before the turn
+        let hook_messages = std::mem::take(&mut self.hook_messages);
+        context = hook_messages.into_iter().fold(context, |ctx, message| {
+            ctx.add_message(ContextMessage::user(
+                Element::new("hook_message").cdata(message),
+                model_id.clone().into(),
+            ))
+        });
+
         // Indicates whether the tool execution has been completed
         let mut is_complete = false;
 
         let mut empty_tool_call_count = 0;
         let mut request_count = 0;
 
+        // Work done for the message, checked against the hard limits of the agent
+        // along with the work of the sub-agents it spawns
+        let turn_started = Instant::now();
+        let max_duration = agent.max_duration.map(Duration::from_secs);
+        let mut turn_cost = 0.0;
+        let mut turn_tool_calls = 0;
+        let mut last_message = None;
+        let mut limit_reached = false;
+        let spending = Arc::new(Spending::default());
+        let partial_result = |requests: usize, cost_usd: f64, tool_calls: usize, last_message| {
+            let (sub_agent_requests, sub_agent_cost) = spending.spent();
+            PartialResult {
+                turns: requests as u64 + sub_agent_requests,
+                cost_usd: cost_usd + sub_agent_cost,
+                duration: turn_started.elapsed(),
+                tool_calls,
+                last_message,
+            }
+        };
+
         // Retrieve the number of requests allowed per tick.
         let max_requests_per_turn = self.conversation.max_requests_per_turn;
 
         // Store tool calls at turn level
         let mut turn_has_tool_calls = false;
 
+        // Handoff the agent stops for, to be run once it's done
+        let mut handoff = None;
+
+        // Plan made in plan mode, and whether the agent was reminded to make one
+        let mut plan = None;
+        let mut plan_reminded = false;
+
+        // Whether the context is compacted before a request that doesn't fit the
+        // context window of the model
+        let mut compact_to_fit = true;
+
         while !is_complete {
+            // Messages other agents sent while this one was working are given to it
+            // before its next step
+            for message in self
+                .services
+                .receive_messages(&self.conversation.root_id(), &agent.id)
+                .await?
+            {
+                context = context.add_message(ContextMessage::user(
+                    message.to_element(),
+                    model_id.clone().into(),
+                ));
+            }
+
+            // So are the instructions the user sent while the tools ran
+            let steering = self
+                .services
+                .receive_steering(&self.conversation.id, false)
+                .await?;
+            if !steering.is_empty() {
+                context = self.steer(context, &model_id, None, steering).await?;
+            }
+
+            // Tokens before any compaction of this request, for the token budget
+            let tokens_before = context.token_count_approx();
+            let mut compaction = None;
+
+            if compact_to_fit
+                && let Some(compacted_context) =
+                    self.compact_to_fit(&agent, &model_id, &context).await?
+            {
+                // A summary that didn't shrink the context won't the next time
+                // either, so the rest of the turn goes without one rather than
+                // paying for it with every request
+                compact_to_fit = shrank(tokens_before, &compacted_context);
+                if compacted_context.messages.len() < context.messages.len() {
+                    compaction = Some(CompactionTrigger::ContextWindow);
+                }
+                context = compacted_context;
+            }
+
             // Set context for the current loop iteration
             self.conversation.context = Some(context.clone());
+            self.conversation.record_request(context.clone());
             self.services.update(self.conversation.clone()).await?;
 
+            let started_at = chrono::Utc::now();
+            let request_started = Instant::now();
+
+            // Answer of the model streamed so far
+            let partial = Arc::new(std::sync::Mutex::new(String::new()));
+
             // Run the main chat request and compaction check in parallel
             let main_request = crate::retry::retry_with_config(
                 &self.environment.retry_config,
-                || self.execute_chat_turn(&model_id, context.clone(), tool_supported, reasoning_supported),
-                self.sender.as_ref().map(|sender| {
-                    let sender = sender.clone();
+                || {
+                    self.execute_chat_turn(
+                        &model_id,
+                        agent.provider.as_ref(),
+                        context.clone(),
+                        tool_supported,
+                        reasoning_supported,
+                        partial.clone(),
+                    )
+                },
+                Some({
+                    let sender = self.sender.clone();
+                    let services = self.services.clone();
+                    let conversation_id = self.conversation.id;
                     let agent_id = agent.id.clone();
                     let model_id = model_id.clone();
                     move |error: &anyhow::Error, duration: Duration| {
                         let root_cause = error.root_cause();
                         tracing::error!(agent_id = %agent_id, error = ?root_cause, model=%model_id, "Retry Attempt");
-                        let retry_event = ChatResponse::RetryAttempt {
-                            cause: error.into(),
-                            duration,
-                        };
-                        let _ = sender.try_send(Ok(retry_event));
+                        let cause = Cause::from(error);
+                        services.publish_lifecycle(LifecycleEvent::new(
+                            conversation_id,
+                            LifecycleEventKind::Retry {
+                                agent_id: agent_id.clone(),
+                                cause: cause.as_str().to_string(),
+                                delay: duration,
+                            },
+                        ));
+                        if let Some(sender) = &sender {
+                            let retry_event = ChatResponse::RetryAttempt { cause, duration };
+                            let _ = sender.try_send(Ok(retry_event));
+                        }
                     }
                 }),
             );
 
             // Prepare compaction task that runs in parallel
+            let compaction_request = self.check_and_compact(&agent, &model_id, &context);
 
-            // Execute both operations in parallel
+            // Execute both operations in parallel, unless the user steers the agent
+            // before the response is complete
             let (
                 ChatCompletionMessageFull {
                     tool_calls,
                     content,
                     usage,
-                    reasoning,
                     reasoning_details,
                     finish_reason,
+                    ..
                 },
                 compaction_result,
-            ) = tokio::try_join!(main_request, self.check_and_compact(&agent, &context))?;
+            ) = tokio::select! {
+                biased;
+                steering = self.services.receive_steering(&self.conversation.id, true) => {
+                    let partial = std::mem::take(&mut *partial.lock().unwrap());
+                    context = self.steer(context, &model_id, Some(&partial), steering?).await?;
+                    continue;
+                }
+                limit = turn_timeout(turn_started, max_duration) => {
+                    let partial =
+                        partial_result(request_count, turn_cost, turn_tool_calls, last_message);
+                    self.abort_turn(&agent.id, limit, partial).await?;
+                    limit_reached = true;
+                    break;
+                }
+                result = async move { tokio::try_join!(main_request, compaction_request) } => result?,
+            };
 
             // Apply compaction result if it completed successfully
             match compaction_result {
-                Some(compacted_context) => {
+                Some((trigger, compacted_context)) => {
                     info!(agent_id = %agent.id, "Using compacted context from execution");
+                    if compacted_context.messages.len() < context.messages.len() {
+                        compaction.get_or_insert(trigger);
+                    }
                     context = compacted_context;
                 }
                 None => {
@@ -441,12 +867,62 @@ impl<S: AgentService> Orchestrator<S> {
             // Send the usage information if available
             self.send(ChatResponse::Usage(usage.clone())).await?;
 
+            let duration_ms = request_started.elapsed().as_millis() as u64;
+            let turn_usage = usage.clone();
             context = context.usage(usage);
 
+            // The usage was counted for the context as it was before the compaction
+            let used = if compaction.is_some() {
+                TokenCount::Approx(context.token_count_approx())
+            } else {
+                context.token_count()
+            };
+            let compaction = compaction.map(|trigger| ContextCompaction {
+                before: tokens_before,
+                after: context.token_count_approx(),
+                trigger,
+            });
+            if let Some(compaction) = &compaction {
+                self.publish(LifecycleEventKind::Compaction {
+                    agent_id: agent.id.clone(),
+                    compaction: compaction.clone(),
+                });
+            }
+            self.send(ChatResponse::TokenBudget(TokenBudget {
+                used,
+                limit: self.context_length(&model_id),
+                compaction,
+            }))
+            .await?;
+
+            // Journal the answer before its tools run, so that a crash while they run
+            // doesn't lose the turn
+            if self.conversation.persist {
+                self.services
+                    .checkpoint(
+                        &self.conversation.id,
+                        SessionEvent::Assistant {
+                            messages: self
+                                .conversation
+                                .context
+                                .as_ref()
+                                .map_or(0, |context| context.messages.len()),
+                            content: content.clone(),
+                            tool_calls: tool_calls.clone(),
+                        },
+                    )
+                    .await?;
+            }
+
             let has_tool_calls = !tool_calls.is_empty();
 
             debug!(agent_id = %agent.id, tool_call_count = tool_calls.len(), "Tool call count");
 
+            let message = remove_tag_with_prefix(&content, "forge_");
+            if !message.trim().is_empty() {
+                last_message = Some(message.trim().to_string());
+            }
+
             is_complete = tool_calls.iter().any(|call| Tools::is_complete(&call.name));
 
             if !is_complete && has_tool_calls {
@@ -463,17 +939,21 @@ impl<S: AgentService> Orchestrator<S> {
                 .await?;
             }
 
-            if let Some(reasoning) = reasoning.as_ref()
-                && !is_complete
-                && reasoning_supported
-            {
-                // If reasoning is present, send it as a separate message
-                self.send(ChatResponse::Reasoning { content: reasoning.to_string() })
-                    .await?;
-            }
-
-            let mut tool_context =
-                ToolCallContext::new(self.conversation.tasks.clone()).sender(self.sender.clone());
+            let mut tool_context = ToolCallContext::new(self.conversation.tasks.clone())
+                .file_versions(self.conversation.file_versions.clone())
+                .cwd(self.conversation.cwd.clone())
+                .dry_run(self.conversation.dry_run)
+                .plan_mode(self.conversation.plan_mode)
+                .allowed_operations(self.conversation.allowed_operations.clone())
+                .conversation_id(Some(self.conversation.id))
+                .root_conversation_id(Some(self.conversation.root_id()))
+                .agent_id(Some(agent.id.clone()))
+                .workflow(self.workflow.clone())
+                .guard(self.guard.clone())
+                .redactor(self.redactor.clone())
+                .generated(self.generated.clone())
+                .spending(spending.clone())
+                .sender(self.sender.clone());
 
             // Check if tool calls are within allowed limits if max_tool_failure_per_turn is
             // configured
@@ -481,9 +961,102 @@ impl<S: AgentService> Orchestrator<S> {
                 self.check_tool_call_failures(&tool_failure_attempts, &tool_calls);
 
             // Process tool calls and update context
-            let mut tool_call_records = self
-                .execute_tool_calls(&agent, &tool_calls, &mut tool_context)
+            let tools_started = Instant::now();
+            // The tools, such as a command or a sub-agent, are stopped once the
+            // turn runs out of time
+            let mut tool_call_records = tokio::select! {
+                records = self.execute_tool_calls(&agent, &tool_calls, &mut tool_context) => records?,
+                limit = turn_timeout(turn_started, max_duration) => {
+                    let partial =
+                        partial_result(request_count, turn_cost, turn_tool_calls, last_message);
+                    self.abort_turn(&agent.id, limit, partial).await?;
+                    limit_reached = true;
+                    break;
+                }
+            };
+            let tool_duration_ms = tools_started.elapsed().as_millis() as u64;
+
+            // The tool registry only succeeds for the handoffs the agent is allowed
+            if self.can_hand_off() {
+                handoff =
+                    tool_call_records.iter().find_map(|(call, result)| {
+                        match Tools::try_from(call.clone()) {
+                            Ok(Tools::ForgeToolHandoff(handoff)) if !result.is_error() => {
+                                Some(handoff)
+                            }
+                            _ => None,
+                        }
+                    });
+                is_complete = is_complete || handoff.is_some();
+            }
+
+            if self.conversation.plan_mode {
+                plan = tool_call_records
+                    .iter()
+                    .rev()
+                    .find_map(|(call, result)| match Tools::try_from(call.clone()) {
+                        Ok(Tools::ForgeToolPlanCreate(plan)) if !result.is_error() => Some(plan),
+                        _ => None,
+                    })
+                    .or(plan);
+            }
+
+            // A tool failing again with the same error gets a reflection on the attempts
+            // made, so that the agent doesn't keep making the same broken call
+            let mut reflected = vec![false; tool_call_records.len()];
+            for ((call, result), reflected) in tool_call_records.iter_mut().zip(&mut reflected) {
+                if let Some(failure) = failure_tracker.record(call, result) {
+                    warn!(agent_id = %agent.id, tool = %failure.tool, failures = failure.failures, "Tool keeps failing with the same error");
+                    result
+                        .output
+                        .combine_mut(ToolOutput::text(failure.to_element()));
+                    *reflected = true;
+                }
+            }
+            let repeated_failure = reflected.contains(&true);
+            if repeated_failure
+                && let Some(escalation_model) = agent.escalation_model.as_ref()
+                && *escalation_model != model_id
+            {
+                info!(agent_id = %agent.id, from = %model_id, to = %escalation_model, "Escalating to a stronger model");
+                self.send(ChatResponse::Text {
+                    text: TitleFormat::action("Escalating")
+                        .sub_title(format!(
+                            "to {escalation_model} after repeated tool failures"
+                        ))
+                        .to_string(),
+                    is_complete: true,
+                    is_md: false,
+                })
                 .await?;
+                model_id = escalation_model.clone();
+            }
+
+            // An agent repeating the same calls is told it's stuck, so that the turn isn't
+            // spent going round in circles. A call that was already given a reflection on
+            // its failures isn't given another one.
+            let mut tool_loop = None;
+            for ((call, result), reflected) in tool_call_records.iter_mut().zip(reflected) {
+                if let Some(detected) = loop_detector.record(call) {
+                    if !reflected {
+                        result
+                            .output
+                            .combine_mut(ToolOutput::text(detected.to_element()));
+                    }
+                    tool_loop = Some(detected);
+                }
+            }
+            if let Some(tool_loop) = tool_loop {
+                warn!(agent_id = %agent.id, tool_loop = %tool_loop, "Agent is stuck in a loop");
+                self.send(ChatResponse::Text {
+                    text: TitleFormat::error("Loop detected")
+                        .sub_title(tool_loop.to_string())
+                        .to_string(),
+                    is_complete: true,
+                    is_md: false,
+                })
+                .await?;
+            }
 
             // Update the tool call attempts, if the tool call is an error
             // we increment the attempts, otherwise we remove it from the attempts map
@@ -511,6 +1084,12 @@ impl<S: AgentService> Orchestrator<S> {
 
             context = context.append_message(content.clone(), reasoning_details, tool_call_records);
 
+            // Answers the user gave to questions of the agent follow the tool results
+            for message in tool_context.user_messages.drain(..) {
+                context =
+                    context.add_message(ContextMessage::user(message, model_id.clone().into()));
+            }
+
             if !(turn_has_tool_calls || has_tool_calls) {
                 // No tools were called in the previous turn nor were they called in this step;
                 // Means that this is conversation.
@@ -561,6 +1140,22 @@ impl<S: AgentService> Orchestrator<S> {
                 empty_tool_call_count = 0;
             }
 
+            // In plan mode the agent is reminded once to make the plan before completing
+            let attempts_completion = tool_calls
+                .iter()
+                .any(|call| call.name == ToolsDiscriminants::ForgeToolAttemptCompletion.name());
+            if self.conversation.plan_mode
+                && attempts_completion
+                && plan.is_none()
+                && !plan_reminded
+            {
+                let content = "Create the plan with the `forge_tool_plan_create` tool before completing the task.";
+                context =
+                    context.add_message(ContextMessage::user(content, model_id.clone().into()));
+                plan_reminded = true;
+                is_complete = false;
+            }
+
             if allowed_limits_exceeded {
                 // Tool call retry limit exceeded, force completion
                 warn!(
@@ -585,11 +1180,39 @@ impl<S: AgentService> Orchestrator<S> {
 
             // Update context in the conversation
             context = SetModel::new(model_id.clone()).transform(context);
+            turn_cost += turn_usage.cost.unwrap_or_default();
+            turn_tool_calls += tool_calls.len();
+            self.conversation.turns.push(TurnUsage {
+                model: model_id.clone(),
+                started_at,
+                usage: turn_usage,
+                duration_ms,
+                tool_calls: tool_calls.iter().map(|call| call.name.clone()).collect(),
+                tool_duration_ms,
+            });
             self.conversation.tasks = tool_context.tasks;
+            self.conversation.file_versions = tool_context.file_versions;
+            self.conversation.allowed_operations = tool_context.allowed_operations;
+            self.conversation.cwd = tool_context.cwd;
             self.conversation.context = Some(context.clone());
             self.services.update(self.conversation.clone()).await?;
             request_count += 1;
 
+            let partial = partial_result(
+                request_count,
+                turn_cost,
+                turn_tool_calls,
+                last_message.clone(),
+            );
+            if !is_complete
+                && let Some(limit) =
+                    agent.turn_limit_reached(partial.turns, partial.cost_usd, partial.duration)
+            {
+                self.abort_turn(&agent.id, limit, partial).await?;
+                is_complete = true;
+                limit_reached = true;
+            }
+
             if !is_complete && let Some(max_request_allowed) = max_requests_per_turn {
                 // Check if agent has reached the maximum request per turn limit
                 if request_count >= max_request_allowed {
@@ -616,9 +1239,101 @@ impl<S: AgentService> Orchestrator<S> {
             turn_has_tool_calls = turn_has_tool_calls || has_tool_calls;
         }
 
+        // An aborted turn is over, whatever the agent was about to do next
+        if limit_reached {
+            return Ok(());
+        }
+
+        if let Some(handoff) = handoff {
+            self.hand_off(&agent.id, handoff).await?;
+        }
+
+        if self.conversation.plan_mode
+            && let Some(plan) = plan
+        {
+            self.review_plan(&agent.id, plan).await?;
+        }
+
         Ok(())
     }
 
+    /// Tells the user the turn of the agent was aborted on reaching one of its
+    /// hard limits, with what it got done
+    async fn abort_turn(
+        &self,
+        agent_id: &AgentId,
+        limit: TurnLimit,
+        partial: PartialResult,
+    ) -> anyhow::Result<()> {
+        warn!(
+            agent_id = %agent_id,
+            limit = %limit,
+            "Agent has reached a hard limit, aborting the turn"
+        );
+        self.send(ChatResponse::Interrupt {
+            reason: InterruptionReason::TurnLimitReached { limit, partial },
+        })
+        .await
+    }
+
+    /// Asks the user to approve the plan made in plan mode, and has the agent
+    /// carry it out once approved
+    #[async_recursion]
+    async fn review_plan(&mut self, agent_id: &AgentId, plan: PlanCreate) -> anyhow::Result<()> {
+        let answer = ToolCallContext::new(self.conversation.tasks.clone())
+            .sender(self.sender.clone())
+            .ask(
+                format!("Execute the plan {}?", plan.plan_name),
+                vec![EXECUTE_PLAN.to_string(), KEEP_PLANNING.to_string()],
+                false,
+            )
+            .await?;
+        if answer.as_deref() != Some(EXECUTE_PLAN) {
+            return Ok(());
+        }
+
+        info!(conversation_id = %self.conversation.id, plan = %plan.plan_name, "Executing the approved plan");
+        self.conversation.plan_mode = false;
+        self.send(ChatResponse::Text {
+            text: TitleFormat::action("Plan mode is off")
+                .sub_title(format!("executing {}", plan.plan_name))
+                .to_string(),
+            is_complete: true,
+            is_md: false,
+        })
+        .await?;
+
+        let plan = Element::new("approved_plan")
+            .attr("name", &plan.plan_name)
+            .attr("version", &plan.version)
+            .cdata(&plan.content);
+        let event = Event::new(
+            format!("{agent_id}/user_task_init"),
+            Some(format!("Carry out the plan the user approved:\n{plan}")),
+        );
+        self.conversation.insert_event(event.clone());
+        self.init_agent(agent_id, &event).await
+    }
+
+    /// Hands the conversation off to another agent, which takes over from the
+    /// context it was given instead of the messages exchanged so far
+    #[async_recursion]
+    async fn hand_off(&mut self, from: &AgentId, handoff: Handoff) -> anyhow::Result<()> {
+        let to = AgentId::new(&handoff.agent);
+        info!(conversation_id = %self.conversation.id, %from, %to, "Handing off conversation");
+
+        self.handoffs += 1;
+        self.conversation.context = None;
+        self.conversation
+            .set_variable("operating_agent".to_string(), Value::from(to.as_str()));
+        self.send(ChatResponse::Handoff { from: from.clone(), to: to.clone() })
+            .await?;
+
+        let event = Event::new(format!("{to}/user_task_init"), Some(handoff.context));
+        self.conversation.insert_event(event.clone());
+        self.init_agent(&to, &event).await
+    }
+
     fn check_tool_call_failures(
         &self,
         tool_failure_attempts: &HashMap<ToolName, usize>,
@@ -665,3 +1380,51 @@ impl<S: AgentService> Orchestrator<S> {
         Ok(context)
     }
 }
+
+/// Waits until the turn started at `started` has run for as long as the agent
+/// may work on a message, forever when its time isn't limited
+async fn turn_timeout(started: Instant, max_duration: Option<Duration>) -> TurnLimit {
+    match max_duration {
+        Some(max_duration) => {
+            tokio::time::sleep_until(tokio::time::Instant::from_std(started + max_duration)).await;
+            TurnLimit::Duration(max_duration)
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Whether the compacted context has fewer tokens than the context had before
+fn shrank(tokens_before: usize, compacted: &Context) -> bool {
+    compacted.token_count_approx() < tokens_before
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_shrank() {
+        let fixture = Context::default()
+            .add_message(ContextMessage::user(
+                "Fix the parser and run the tests",
+                None,
+            ))
+            .add_message(ContextMessage::assistant(
+                "Fixed it, the tests pass",
+                None,
+                None,
+            ));
+        let tokens_before = fixture.token_count_approx();
+        let summary = Context::default().add_message(ContextMessage::user("Parser fixed", None));
+
+        let actual = (
+            shrank(tokens_before, &fixture),
+            shrank(tokens_before, &summary),
+        );
+
+        let expected = (false, true);
+        assert_eq!(actual, expected);
+    }
+}
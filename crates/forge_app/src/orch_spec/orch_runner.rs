@@ -2,7 +2,8 @@ use std::collections::VecDeque;
 use std::sync::Arc;
 
 use forge_domain::{
-    ChatCompletionMessage, ChatResponse, Conversation, ConversationId, ToolCallFull, ToolResult,
+    AgentId, AgentMessage, ChatCompletionMessage, ChatResponse, Conversation, ConversationId,
+    HookEvent, HookOutcome, HookPayload, LifecycleEvent, ToolCallFull, ToolResult, Workflow,
 };
 use handlebars::{Handlebars, no_escape};
 use rust_embed::Embed;
@@ -26,6 +27,18 @@ pub struct Runner {
 
     // Mock completions from the LLM (Each value is produced as an event in the stream)
     test_completions: Mutex<VecDeque<ChatCompletionMessage>>,
+
+    // Mock outcomes of the hooks, by the event they run for
+    test_hook_outcomes: Vec<(HookEvent, HookOutcome)>,
+
+    // Messages sent by other agents, until the agent they are sent to takes them
+    test_messages: Mutex<Vec<AgentMessage>>,
+
+    // Instructions of the user, each taken by the request it interrupts
+    test_steering: Mutex<VecDeque<String>>,
+
+    // Events of the lifecycle of the turn, in the order they were published
+    lifecycle_events: std::sync::Mutex<Vec<LifecycleEvent>>,
 }
 
 impl Runner {
@@ -45,6 +58,10 @@ impl Runner {
             conversation_history: Mutex::new(Vec::new()),
             test_tool_calls: Mutex::new(VecDeque::from(setup.mock_tool_call_responses.clone())),
             test_completions: Mutex::new(VecDeque::from(setup.mock_assistant_responses.clone())),
+            test_hook_outcomes: setup.mock_hook_outcomes.clone(),
+            test_messages: Mutex::new(setup.mock_messages.clone()),
+            test_steering: Mutex::new(VecDeque::from(setup.mock_steering.clone())),
+            lifecycle_events: Default::default(),
         }
     }
 
@@ -71,7 +88,9 @@ impl Runner {
             setup.current_time,
         )
         .sender(Arc::new(tx))
-        .files(setup.files.clone());
+        .files(setup.files.clone())
+        .project_memory(setup.project_memory.clone())
+        .memories(setup.memories.clone());
 
         let (mut orch, runner) = (orch, services);
         let event = setup.event.clone();
@@ -83,6 +102,10 @@ impl Runner {
             .output
             .conversation_history
             .extend(runner.get_history().await);
+        setup
+            .output
+            .lifecycle_events
+            .extend(runner.lifecycle_events.lock().unwrap().drain(..));
 
         result
     }
@@ -94,6 +117,7 @@ impl AgentService for Runner {
         &self,
         _id: &forge_domain::ModelId,
         context: forge_domain::Context,
+        _provider: Option<&forge_domain::ProviderDetails>,
     ) -> forge_domain::ResultStream<ChatCompletionMessage, anyhow::Error> {
         let mut responses = self.test_completions.lock().await;
         if let Some(message) = responses.pop_front() {
@@ -136,4 +160,49 @@ impl AgentService for Runner {
         self.conversation_history.lock().await.push(conversation);
         Ok(())
     }
+
+    async fn run_hooks(&self, _workflow: &Workflow, payload: &HookPayload) -> HookOutcome {
+        self.test_hook_outcomes
+            .iter()
+            .find(|(event, _)| *event == payload.event)
+            .map(|(_, outcome)| outcome.clone())
+            .unwrap_or_default()
+    }
+
+    async fn checkpoint(
+        &self,
+        _id: &forge_domain::ConversationId,
+        _event: forge_domain::SessionEvent,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn receive_messages(
+        &self,
+        _conversation: &ConversationId,
+        agent: &AgentId,
+    ) -> anyhow::Result<Vec<AgentMessage>> {
+        let mut messages = self.test_messages.lock().await;
+        let (received, pending) = messages.drain(..).partition(|message| message.to == *agent);
+        *messages = pending;
+        Ok(received)
+    }
+
+    async fn receive_steering(
+        &self,
+        _id: &ConversationId,
+        wait: bool,
+    ) -> anyhow::Result<Vec<String>> {
+        if !wait {
+            return Ok(Vec::new());
+        }
+        match self.test_steering.lock().await.pop_front() {
+            Some(message) => Ok(vec![message]),
+            None => std::future::pending().await,
+        }
+    }
+
+    fn publish_lifecycle(&self, event: LifecycleEvent) {
+        self.lifecycle_events.lock().unwrap().push(event);
+    }
 }
@@ -4,8 +4,9 @@ use std::path::PathBuf;
 use chrono::{DateTime, Local};
 use derive_setters::Setters;
 use forge_domain::{
-    Agent, AgentId, ChatCompletionMessage, ChatResponse, ContextMessage, Conversation, Environment,
-    Event, HttpConfig, ModelId, RetryConfig, Role, Template, ToolCallFull, ToolResult, Workflow,
+    Agent, AgentId, AgentMessage, ChatCompletionMessage, ChatResponse, ContextMessage,
+    Conversation, Environment, Event, HookEvent, HookOutcome, HttpConfig, LifecycleEvent, Memory,
+    ModelId, ProjectMemory, RetryConfig, Role, Template, ToolCallFull, ToolResult, Workflow,
 };
 use url::Url;
 
@@ -17,9 +18,14 @@ pub struct TestContext {
     pub event: Event,
     pub mock_tool_call_responses: Vec<(ToolCallFull, ToolResult)>,
     pub mock_assistant_responses: Vec<ChatCompletionMessage>,
+    pub mock_hook_outcomes: Vec<(HookEvent, HookOutcome)>,
+    pub mock_messages: Vec<AgentMessage>,
+    pub mock_steering: Vec<String>,
     pub workflow: Workflow,
     pub templates: HashMap<String, String>,
     pub files: Vec<String>,
+    pub project_memory: Vec<ProjectMemory>,
+    pub memories: Vec<Memory>,
     pub env: Environment,
     pub current_time: DateTime<Local>,
 
@@ -39,6 +45,9 @@ impl TestContext {
             current_time: Local::now(),
             mock_assistant_responses: Default::default(),
             mock_tool_call_responses: Default::default(),
+            mock_hook_outcomes: Default::default(),
+            mock_messages: Default::default(),
+            mock_steering: Default::default(),
             workflow: Workflow::new()
                 .model(ModelId::new("openai/gpt-1"))
                 .agents(vec![
@@ -52,6 +61,8 @@ impl TestContext {
                 .tool_supported(true),
             templates: Default::default(),
             files: Default::default(),
+            project_memory: Default::default(),
+            memories: Default::default(),
             env: Environment {
                 os: "MacOS".to_string(),
                 pid: 1234,
@@ -72,6 +83,7 @@ impl TestContext {
                     suppress_retry_errors: Default::default(),
                 },
                 tool_timeout: 300,
+                max_concurrent_commands: 4,
                 max_search_lines: 1000,
                 fetch_truncation_limit: 1024,
                 stdout_max_prefix_length: 256,
@@ -95,6 +107,7 @@ impl TestContext {
 pub struct TestOutput {
     pub conversation_history: Vec<Conversation>,
     pub chat_responses: Vec<anyhow::Result<ChatResponse>>,
+    pub lifecycle_events: Vec<LifecycleEvent>,
 }
 
 impl TestOutput {
@@ -1,6 +1,7 @@
 use forge_domain::{
-    ChatCompletionMessage, ChatResponse, Content, FinishReason, Role, ToolCallFull, ToolOutput,
-    ToolResult,
+    AgentId, AgentMessage, ChatCompletionMessage, ChatResponse, Content, ContextMessage,
+    FinishReason, HookEvent, HookOutcome, InterruptionReason, LifecycleEventKind, ModelId, Role,
+    ToolCallFull, ToolOutput, ToolResult, TurnLimit,
 };
 use pretty_assertions::assert_eq;
 use serde_json::json;
@@ -51,7 +52,7 @@ async fn test_attempt_completion_content() {
     ctx.run().await.unwrap();
     let response_len = ctx.output.chat_responses.len();
 
-    assert_eq!(response_len, 2, "Response length should be 2");
+    assert_eq!(response_len, 3, "Response length should be 3");
 
     let first_text_response = ctx
         .output
@@ -70,6 +71,55 @@ async fn test_attempt_completion_content() {
     )
 }
 
+#[tokio::test]
+async fn test_token_budget_is_reported() {
+    let mut ctx = TestContext::init_forge_task("Hi").mock_assistant_responses(vec![
+        ChatCompletionMessage::assistant(Content::full("Hello!")).finish_reason(FinishReason::Stop),
+    ]);
+
+    ctx.run().await.unwrap();
+
+    let actual = ctx
+        .output
+        .chat_responses
+        .iter()
+        .flatten()
+        .filter_map(|response| match response {
+            ChatResponse::TokenBudget(budget) => Some(budget.compaction.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let expected = vec![None];
+    assert_eq!(actual, expected);
+}
+
+#[tokio::test]
+async fn test_reasoning_is_sent_apart_from_text() {
+    let mut ctx = TestContext::init_forge_task("Hi").mock_assistant_responses(vec![
+        ChatCompletionMessage::assistant(Content::full("Hello!"))
+            .reasoning(Content::part("The user greets me"))
+            .finish_reason(FinishReason::Stop),
+    ]);
+
+    ctx.run().await.unwrap();
+
+    let actual = ctx
+        .output
+        .chat_responses
+        .iter()
+        .flatten()
+        .filter_map(|response| match response {
+            ChatResponse::Reasoning { content } => Some(content.clone()),
+            ChatResponse::Text { text, is_md: true, .. } => Some(text.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let expected = vec!["The user greets me".to_string(), "Hello!".to_string()];
+    assert_eq!(actual, expected);
+}
+
 #[tokio::test]
 async fn test_attempt_completion_with_task() {
     let tool_call = ToolCallFull::new("fs_read").arguments(json!({"path": "abc.txt"}));
@@ -101,6 +151,369 @@ async fn test_attempt_completion_with_task() {
     assert_eq!(tool_call_error_count, 3, "Respond with the error thrice");
 }
 
+#[tokio::test]
+async fn test_handoff_to_another_agent() {
+    let tool_call = ToolCallFull::new("forge_tool_handoff")
+        .arguments(json!({"agent": "must", "context": "Review the changes to abc.txt"}));
+    let tool_result =
+        ToolResult::new("forge_tool_handoff").output(Ok(ToolOutput::text("Handed off")));
+
+    let mut ctx = TestContext::init_forge_task("Fix abc.txt")
+        .mock_tool_call_responses(vec![(tool_call.clone(), tool_result)])
+        .mock_assistant_responses(vec![
+            ChatCompletionMessage::assistant("Fixed abc.txt").tool_calls(vec![tool_call.into()]),
+            ChatCompletionMessage::assistant(Content::full("Looks good"))
+                .finish_reason(FinishReason::Stop),
+        ]);
+
+    ctx.run().await.unwrap();
+
+    let actual = ctx
+        .output
+        .chat_responses
+        .iter()
+        .flatten()
+        .find_map(|response| match response {
+            ChatResponse::Handoff { from, to } => Some((from.clone(), to.clone())),
+            _ => None,
+        });
+    let expected = Some((AgentId::new("forge"), AgentId::new("must")));
+    assert_eq!(actual, expected);
+
+    // The agent taking over only gets the context it was handed
+    let actual = ctx
+        .output
+        .context_messages()
+        .iter()
+        .filter(|message| message.has_role(Role::User))
+        .filter_map(|message| message.content())
+        .map(|content| content.contains("Review the changes to abc.txt"))
+        .collect::<Vec<_>>();
+    let expected = vec![true];
+    assert_eq!(actual, expected);
+    assert!(
+        ctx.output
+            .system_prompt()
+            .is_some_and(|prompt| prompt.ends_with("You are Muse"))
+    );
+}
+
+#[tokio::test]
+async fn test_turn_limit_aborts_turn() {
+    let tool_call = ToolCallFull::new("fs_read").arguments(json!({"path": "abc.txt"}));
+    let tool_result = ToolResult::new("fs_read").output(Ok(ToolOutput::text("Greetings")));
+
+    let mut ctx = TestContext::init_forge_task("Read a file")
+        .mock_tool_call_responses(vec![(tool_call.clone().into(), tool_result)])
+        .mock_assistant_responses(vec![
+            ChatCompletionMessage::assistant("Reading abc.txt").tool_calls(vec![tool_call.into()]),
+            ChatCompletionMessage::assistant("Im done!"),
+        ]);
+    ctx.workflow.agents[0].max_turns = Some(1);
+
+    ctx.run().await.unwrap();
+
+    let actual = ctx
+        .output
+        .chat_responses
+        .iter()
+        .flatten()
+        .find_map(|response| match response {
+            ChatResponse::Interrupt {
+                reason: InterruptionReason::TurnLimitReached { limit, partial },
+            } => Some((
+                limit.clone(),
+                partial.turns,
+                partial.tool_calls,
+                partial.last_message.clone(),
+            )),
+            _ => None,
+        });
+
+    let expected = Some((
+        TurnLimit::Turns(1),
+        1,
+        1,
+        Some("Reading abc.txt".to_string()),
+    ));
+    assert_eq!(actual, expected);
+}
+
+#[tokio::test]
+async fn test_pre_turn_hook_blocks_message() {
+    let mut ctx = TestContext::init_forge_task("Deploy to production").mock_hook_outcomes(vec![(
+        HookEvent::PreTurn,
+        HookOutcome {
+            block: Some("Deploys are frozen".to_string()),
+            messages: vec![],
+        },
+    )]);
+
+    ctx.run().await.unwrap();
+
+    let actual = ctx.output.chat_responses.iter().flatten().any(|response| {
+        matches!(response, ChatResponse::Text { text, .. } if text.contains("Deploys are frozen"))
+    });
+    assert!(actual);
+    assert!(ctx.output.conversation_history.is_empty());
+}
+
+#[tokio::test]
+async fn test_turn_hook_messages() {
+    let mut ctx = TestContext::init_forge_task("Fix abc.txt")
+        .mock_hook_outcomes(vec![
+            (
+                HookEvent::PreTurn,
+                HookOutcome { block: None, messages: vec!["On branch main".to_string()] },
+            ),
+            (
+                HookEvent::PostTurn,
+                HookOutcome { block: None, messages: vec!["Notified".to_string()] },
+            ),
+        ])
+        .mock_assistant_responses(vec![
+            ChatCompletionMessage::assistant(Content::full("Fixed"))
+                .finish_reason(FinishReason::Stop),
+        ]);
+
+    ctx.run().await.unwrap();
+
+    let actual = ctx
+        .output
+        .context_messages()
+        .iter()
+        .filter(|message| message.has_role(Role::User))
+        .filter_map(|message| message.content())
+        .map(|content| content.contains("<hook_message>"))
+        .collect::<Vec<_>>();
+    let expected = vec![false, true];
+    assert_eq!(actual, expected);
+
+    let actual = ctx.output.chat_responses.iter().flatten().any(
+        |response| matches!(response, ChatResponse::Text { text, .. } if text.contains("Notified")),
+    );
+    assert!(actual);
+}
+
+#[tokio::test]
+async fn test_agent_messages_are_delivered() {
+    let mut ctx = TestContext::init_forge_task("Fix abc.txt")
+        .mock_messages(vec![
+            AgentMessage::new(
+                AgentId::new("reviewer"),
+                AgentId::new("forge"),
+                "Unused import",
+                "Remove `use std::fmt` from abc.txt",
+            ),
+            AgentMessage::new(
+                AgentId::new("reviewer"),
+                AgentId::new("tester"),
+                "Flaky test",
+                "Retry the network tests",
+            ),
+        ])
+        .mock_assistant_responses(vec![
+            ChatCompletionMessage::assistant(Content::full("Fixed"))
+                .finish_reason(FinishReason::Stop),
+        ]);
+
+    ctx.run().await.unwrap();
+
+    let actual = ctx
+        .output
+        .context_messages()
+        .iter()
+        .filter(|message| message.has_role(Role::User))
+        .filter_map(|message| message.content())
+        .filter(|content| content.contains("<agent_message"))
+        .map(|content| content.contains("Unused import"))
+        .collect::<Vec<_>>();
+    let expected = vec![true];
+    assert_eq!(actual, expected);
+}
+
+#[tokio::test]
+async fn test_steering_interrupts_response() {
+    let mut ctx = TestContext::init_forge_task("Add a client for the API")
+        .mock_steering(vec!["Use the v2 API".to_string()])
+        .mock_assistant_responses(vec![
+            ChatCompletionMessage::assistant(Content::full("Added a v2 client"))
+                .finish_reason(FinishReason::Stop),
+        ]);
+
+    ctx.run().await.unwrap();
+
+    let actual = ctx
+        .output
+        .context_messages()
+        .iter()
+        .filter_map(|message| message.content())
+        .filter(|content| content.contains("<user_steering"))
+        .map(|content| content.contains("Use the v2 API"))
+        .collect::<Vec<_>>();
+    let expected = vec![true];
+    assert_eq!(actual, expected);
+
+    let actual = ctx.output.chat_responses.iter().flatten().any(|response| {
+        matches!(response, ChatResponse::Text { text, .. } if text == "Added a v2 client")
+    });
+    assert!(actual);
+}
+
+#[tokio::test]
+async fn test_lifecycle_events_are_published() {
+    let tool_call = ToolCallFull::new("fs_read").arguments(json!({"path": "abc.txt"}));
+    let tool_result = ToolResult::new("fs_read").output(Ok(ToolOutput::text("Greetings")));
+
+    let mut ctx = TestContext::init_forge_task("Read a file")
+        .mock_tool_call_responses(vec![(tool_call.clone(), tool_result.clone())])
+        .mock_assistant_responses(vec![
+            ChatCompletionMessage::assistant("Reading abc.txt")
+                .tool_calls(vec![tool_call.clone().into()]),
+            ChatCompletionMessage::assistant(Content::full("It says Greetings"))
+                .finish_reason(FinishReason::Stop),
+        ]);
+
+    ctx.run().await.unwrap();
+
+    let actual = ctx
+        .output
+        .lifecycle_events
+        .into_iter()
+        .map(|event| event.kind)
+        .collect::<Vec<_>>();
+    let expected = vec![
+        LifecycleEventKind::TurnStart { event: "forge/user_task_init".to_string() },
+        LifecycleEventKind::ToolCallStart { agent_id: AgentId::new("forge"), call: tool_call },
+        LifecycleEventKind::ToolCallEnd { agent_id: AgentId::new("forge"), result: tool_result },
+        LifecycleEventKind::TurnEnd,
+    ];
+    assert_eq!(actual, expected);
+}
+
+/// Texts of the tool results sent to the model, in order
+fn tool_result_texts(ctx: &TestContext) -> Vec<String> {
+    ctx.output
+        .context_messages()
+        .iter()
+        .filter_map(|message| match message {
+            ContextMessage::Tool(result) => Some(
+                result
+                    .output
+                    .values
+                    .iter()
+                    .filter_map(|value| value.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_repeated_tool_failure_escalates() {
+    let first = ToolCallFull::new("fs_read").arguments(json!({"path": "abc.txt"}));
+    let second = ToolCallFull::new("fs_read").arguments(json!({"path": "./abc.txt"}));
+    let failed = ToolResult::new("fs_read").failure(anyhow::anyhow!("File not found"));
+
+    let mut ctx = TestContext::init_forge_task("Read a file")
+        .mock_tool_call_responses(vec![
+            (first.clone(), failed.clone()),
+            (second.clone(), failed),
+        ])
+        .mock_assistant_responses(vec![
+            ChatCompletionMessage::assistant("Reading abc.txt").tool_calls(vec![first.into()]),
+            ChatCompletionMessage::assistant("Reading it again").tool_calls(vec![second.into()]),
+            ChatCompletionMessage::assistant(Content::full("The file doesn't exist"))
+                .finish_reason(FinishReason::Stop),
+        ]);
+    ctx.workflow.agents[0].escalation_model = Some(ModelId::new("openai/gpt-2"));
+
+    ctx.run().await.unwrap();
+
+    let actual = tool_result_texts(&ctx)
+        .iter()
+        .map(|text| text.contains("<repeated_tool_failure"))
+        .collect::<Vec<_>>();
+    let expected = vec![false, true];
+    assert_eq!(actual, expected);
+
+    let actual = ctx.output.chat_responses.iter().flatten().any(|response| {
+        matches!(response, ChatResponse::Text { text, .. } if text.contains("openai/gpt-2"))
+    });
+    assert!(actual);
+}
+
+#[tokio::test]
+async fn test_repeated_tool_call_is_detected() {
+    let tool_call = ToolCallFull::new("fs_read").arguments(json!({"path": "abc.txt"}));
+    let tool_result = ToolResult::new("fs_read").output(Ok(ToolOutput::text("Hello, World!")));
+
+    let mut ctx = TestContext::init_forge_task("Read a file")
+        .mock_tool_call_responses(vec![
+            (tool_call.clone(), tool_result.clone()),
+            (tool_call.clone(), tool_result.clone()),
+            (tool_call.clone(), tool_result),
+        ])
+        .mock_assistant_responses(vec![
+            ChatCompletionMessage::assistant("Reading abc.txt")
+                .tool_calls(vec![tool_call.clone().into()]),
+            ChatCompletionMessage::assistant("Reading it again")
+                .tool_calls(vec![tool_call.clone().into()]),
+            ChatCompletionMessage::assistant("Reading it once more")
+                .tool_calls(vec![tool_call.into()]),
+            ChatCompletionMessage::assistant(Content::full("It says hello"))
+                .finish_reason(FinishReason::Stop),
+        ]);
+
+    ctx.run().await.unwrap();
+
+    let actual = tool_result_texts(&ctx)
+        .iter()
+        .map(|text| text.contains("<tool_loop"))
+        .collect::<Vec<_>>();
+    let expected = vec![false, false, true];
+    assert_eq!(actual, expected);
+
+    let actual = ctx.output.chat_responses.iter().flatten().any(|response| {
+        matches!(response, ChatResponse::Text { text, .. } if text.contains("Loop detected"))
+    });
+    assert!(actual);
+}
+
+#[tokio::test]
+async fn test_repeated_failing_call_is_reflected_on_once() {
+    let tool_call = ToolCallFull::new("fs_read").arguments(json!({"path": "abc.txt"}));
+    let failed = ToolResult::new("fs_read").failure(anyhow::anyhow!("File not found"));
+
+    let mut ctx = TestContext::init_forge_task("Read a file")
+        .mock_tool_call_responses(vec![
+            (tool_call.clone(), failed.clone()),
+            (tool_call.clone(), failed.clone()),
+            (tool_call.clone(), failed),
+        ])
+        .mock_assistant_responses(vec![
+            ChatCompletionMessage::assistant("Reading abc.txt")
+                .tool_calls(vec![tool_call.clone().into()]),
+            ChatCompletionMessage::assistant("Reading it again")
+                .tool_calls(vec![tool_call.clone().into()]),
+            ChatCompletionMessage::assistant("Reading it once more")
+                .tool_calls(vec![tool_call.into()]),
+            ChatCompletionMessage::assistant(Content::full("The file doesn't exist"))
+                .finish_reason(FinishReason::Stop),
+        ]);
+
+    ctx.run().await.unwrap();
+
+    let actual = tool_result_texts(&ctx)
+        .iter()
+        .map(|text| text.matches("<reflection").count())
+        .collect::<Vec<_>>();
+    let expected = vec![0, 1, 1];
+    assert_eq!(actual, expected);
+}
+
 #[tokio::test]
 async fn test_empty_responses() {
     let mut ctx = TestContext::init_forge_task("Read a file").mock_assistant_responses(vec![
@@ -1,5 +1,8 @@
-use forge_domain::{ChatCompletionMessage, Content, Workflow};
+use forge_domain::{
+    Agent, AgentId, ChatCompletionMessage, Content, Memory, ProjectMemory, Template, Workflow,
+};
 use insta::assert_snapshot;
+use pretty_assertions::assert_eq;
 
 use crate::orch_spec::orch_runner::TestContext;
 
@@ -37,3 +40,84 @@ async fn test_system_prompt_tool_supported() {
     let system_prompt = ctx.output.system_prompt().unwrap();
     assert_snapshot!(system_prompt);
 }
+
+#[tokio::test]
+async fn test_system_prompt_workspace_variables() {
+    let fixture = TestContext::init_forge_task("This is a test");
+    let agent = Agent::new(AgentId::new("forge")).system_prompt(Template::new(
+        "You are Forge in {{cwd}} on {{os}}:\n{{project_files_overview}}",
+    ));
+    let workflow = fixture.workflow.clone().agents(vec![agent]);
+    let mut ctx = fixture
+        .workflow(workflow)
+        .files(vec!["Cargo.toml".to_string(), "src/".to_string()])
+        .mock_assistant_responses(vec![ChatCompletionMessage::assistant(Content::full(
+            "Sure",
+        ))]);
+
+    ctx.run().await.unwrap();
+
+    let system_prompt = ctx.output.system_prompt().unwrap();
+    let actual = system_prompt
+        .rsplit("</non_negotiable_rules>")
+        .next()
+        .unwrap()
+        .trim();
+    let expected = "You are Forge in /Users/tushar on MacOS:\nsrc/\nCargo.toml";
+    assert_eq!(actual, expected);
+}
+
+#[tokio::test]
+async fn test_system_prompt_project_memory() {
+    let mut ctx = TestContext::init_forge_task("This is a test")
+        .project_memory(vec![
+            ProjectMemory {
+                path: "/Users/tushar/AGENTS.md".to_string(),
+                content: "Use tabs".to_string(),
+            },
+            ProjectMemory {
+                path: "/Users/tushar/app/AGENTS.md".to_string(),
+                content: "Use spaces".to_string(),
+            },
+        ])
+        .mock_assistant_responses(vec![ChatCompletionMessage::assistant(Content::full(
+            "Sure",
+        ))]);
+
+    ctx.run().await.unwrap();
+
+    let system_prompt = ctx.output.system_prompt().unwrap();
+    let actual = system_prompt
+        .split("<project_memory>")
+        .nth(1)
+        .and_then(|memory| memory.split("</project_memory>").next())
+        .unwrap()
+        .trim();
+    let expected = "Instructions kept in the project, listed from the repository root down to the working directory. Always follow them, and when they conflict, the ones listed later take precedence.\n<file path=\"/Users/tushar/AGENTS.md\">\nUse tabs\n</file>\n<file path=\"/Users/tushar/app/AGENTS.md\">\nUse spaces\n</file>";
+    assert_eq!(actual, expected);
+}
+
+#[tokio::test]
+async fn test_system_prompt_memories() {
+    let mut ctx = TestContext::init_forge_task("How are the tests run?")
+        .memories(vec![Memory {
+            id: 1,
+            fact: "Run the tests with cargo nextest".to_string(),
+            created_at: Default::default(),
+        }])
+        .mock_assistant_responses(vec![ChatCompletionMessage::assistant(Content::full(
+            "Sure",
+        ))]);
+
+    ctx.run().await.unwrap();
+
+    let system_prompt = ctx.output.system_prompt().unwrap();
+    let actual = system_prompt
+        .split("<memories>")
+        .nth(1)
+        .and_then(|memories| memories.split("</memories>").next())
+        .unwrap()
+        .trim();
+    let expected = "Facts remembered about the project in earlier sessions. Rely on them instead of asking the user again.\n- Run the tests with cargo nextest";
+    assert_eq!(actual, expected);
+}
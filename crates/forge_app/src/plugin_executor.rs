@@ -0,0 +1,37 @@
+use std::sync::Arc;
+
+use forge_display::TitleFormat;
+use forge_domain::{ToolCallContext, ToolCallFull, ToolDefinition, ToolName, ToolOutput};
+
+use crate::PluginService;
+
+pub struct PluginExecutor<S> {
+    services: Arc<S>,
+}
+
+impl<S: PluginService> PluginExecutor<S> {
+    pub fn new(services: Arc<S>) -> Self {
+        Self { services }
+    }
+
+    pub async fn execute(
+        &self,
+        input: ToolCallFull,
+        context: &mut ToolCallContext,
+    ) -> anyhow::Result<ToolOutput> {
+        context
+            .send_text(TitleFormat::info("Plugin").sub_title(input.name.as_str()))
+            .await?;
+
+        self.services.call_plugin(input).await
+    }
+
+    pub async fn contains_tool(&self, tool_name: &ToolName) -> anyhow::Result<bool> {
+        let tools = self.services.plugin_tools().await?;
+        Ok(tools.iter().any(|tool| tool.name == *tool_name))
+    }
+
+    pub async fn tools(&self) -> anyhow::Result<Vec<ToolDefinition>> {
+        self.services.plugin_tools().await
+    }
+}
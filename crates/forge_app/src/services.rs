@@ -1,19 +1,27 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use bytes::Bytes;
 use forge_domain::{
-    Agent, Attachment, ChatCompletionMessage, CommandOutput, Context, Conversation, ConversationId,
-    Environment, File, McpConfig, Model, ModelId, PatchOperation, Provider, ResultStream, Scope,
-    ToolCallFull, ToolDefinition, ToolOutput, Workflow,
+    Agent, AgentId, AgentMessage, AstOperation, Attachment, BackgroundProcess,
+    ChatCompletionMessage, CommandOutput, CommandSettings, Context, Conversation, ConversationId,
+    Environment, File, GeneratedFiles, GeneratedKind, HttpMethod, Image, LifecycleEvent, McpConfig,
+    McpPrompt, McpReload, McpServerEvent, MemoryStore, Model, ModelId, OpenApiTool, PatchOperation,
+    Permission, ProcessLogs, ProjectMemory, Provider, ProviderDetails, ProviderWarning,
+    RepoMapFile, ResultStream, Scope, Session, SessionEvent, SessionIndex, ShellKind, StoredItem,
+    TaskList, ToolCallFull, ToolCallQuery, ToolCallRecord, ToolDefinition, ToolOutput, Tools,
+    WalkerFilter, Workflow, WorkspaceChunk, WorkspaceStats,
 };
 use merge::Merge;
 use reqwest::Response;
 use reqwest::header::HeaderMap;
 use reqwest_eventsource::EventSource;
+use tokio::sync::broadcast;
 use url::Url;
 
 use crate::Walker;
-use crate::dto::{AppConfig, InitAuth, LoginInfo};
+use crate::dto::{AppConfig, InitAuth, LoginInfo, McpAuthorization};
 use crate::user::{User, UserUsage};
 
 #[derive(Debug)]
@@ -22,6 +30,25 @@ pub struct ShellOutput {
     pub shell: String,
 }
 
+/// Result of an action on the processes running in the background
+#[derive(Debug)]
+pub enum ProcessOutput {
+    Started(BackgroundProcess),
+    Stopped(BackgroundProcess),
+    Logs(ProcessLogs),
+    List(Vec<BackgroundProcess>),
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct PythonOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// Representation of the value of the last expression, if any
+    pub result: Option<String>,
+    /// Traceback of the error raised by the code, if any
+    pub error: Option<String>,
+}
+
 #[derive(Debug)]
 pub struct PatchOutput {
     pub warning: Option<String>,
@@ -29,6 +56,77 @@ pub struct PatchOutput {
     pub after: String,
 }
 
+#[derive(Debug)]
+pub struct PatchedFile {
+    pub path: PathBuf,
+    // Not set when the file was created by the patch
+    pub before: Option<String>,
+    // Not set when the file was removed by the patch
+    pub after: Option<String>,
+    pub warning: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct ApplyPatchOutput {
+    pub files: Vec<PatchedFile>,
+}
+
+/// What a tool call would have done, had dry-run mode been off
+#[derive(Debug)]
+pub enum DryRunOutput {
+    /// Changes the call would make to the files
+    Changes(ApplyPatchOutput),
+    /// Shell command that would be executed
+    Command(String),
+    /// Python code that would be run
+    Python(String),
+    /// Call whose changes can't be worked out without making them, so it's
+    /// skipped
+    Skipped,
+}
+
+#[derive(Debug)]
+pub struct SymbolReference {
+    pub path: PathBuf,
+    // 1-based
+    pub line: u64,
+    // Content of the line, without indentation
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    // Names of the enclosing definitions followed by its own, eg: `Parser::parse`
+    pub path: String,
+    // Kind of definition, eg: `function`, `struct` or `impl`
+    pub kind: String,
+    // First line of the definition, without indentation
+    pub signature: String,
+    // 1-based, inclusive
+    pub start_line: u64,
+    pub end_line: u64,
+}
+
+impl Symbol {
+    /// Number of definitions enclosing this one
+    pub fn depth(&self) -> usize {
+        self.path.matches("::").count() + self.path.matches('.').count()
+    }
+}
+
+#[derive(Debug)]
+pub struct SymbolMatch {
+    pub path: PathBuf,
+    pub symbol: Symbol,
+}
+
+#[derive(Debug)]
+pub struct ChunkMatch {
+    pub chunk: WorkspaceChunk,
+    // Cosine similarity of the chunk with the query
+    pub score: f32,
+}
+
 #[derive(Debug)]
 pub struct ReadOutput {
     pub content: Content,
@@ -40,6 +138,12 @@ pub struct ReadOutput {
 #[derive(Debug)]
 pub enum Content {
     File(String),
+    /// First lines of a lockfile, minified or generated file, read in full
+    /// only when a range of lines is asked for
+    Summary {
+        kind: GeneratedKind,
+        preview: String,
+    },
 }
 
 #[derive(Debug)]
@@ -56,7 +160,15 @@ pub struct Match {
 #[derive(Debug)]
 pub enum MatchResult {
     Error(String),
-    Found { line_number: usize, line: String },
+    Found {
+        line_number: usize,
+        line: String,
+    },
+    /// A line around a match, reported when context lines are requested
+    Context {
+        line_number: usize,
+        line: String,
+    },
 }
 
 #[derive(Debug)]
@@ -73,6 +185,19 @@ pub enum ResponseContext {
     Raw,
 }
 
+#[derive(Debug)]
+pub struct BrowserPage {
+    pub url: String,
+    pub title: Option<String>,
+    pub content: String,
+}
+
+#[derive(Debug)]
+pub enum BrowserOutput {
+    Page(BrowserPage),
+    Screenshot(Image),
+}
+
 #[derive(Debug)]
 pub struct FsCreateOutput {
     pub path: String,
@@ -99,7 +224,7 @@ pub struct FsUndoOutput {
 
 #[derive(Debug)]
 pub struct PolicyDecision {
-    pub allowed: bool,
+    pub permission: Permission,
     pub path: Option<PathBuf>,
 }
 
@@ -112,6 +237,15 @@ pub trait ProviderService: Send + Sync {
         provider: Provider,
     ) -> ResultStream<ChatCompletionMessage, anyhow::Error>;
     async fn models(&self, provider: Provider) -> anyhow::Result<Vec<Model>>;
+
+    /// Computes the embeddings of the inputs with the embedding model, in the
+    /// order of the inputs
+    async fn embed(
+        &self,
+        model: &ModelId,
+        input: Vec<String>,
+        provider: Provider,
+    ) -> anyhow::Result<Vec<Vec<f32>>>;
 }
 
 #[async_trait::async_trait]
@@ -127,6 +261,55 @@ pub trait McpConfigManager: Send + Sync {
 pub trait McpService: Send + Sync {
     async fn list(&self) -> anyhow::Result<Vec<ToolDefinition>>;
     async fn call(&self, call: ToolCallFull) -> anyhow::Result<ToolOutput>;
+
+    /// Lists the prompts advertised by the MCP servers
+    async fn prompts(&self) -> anyhow::Result<Vec<McpPrompt>>;
+
+    /// Fills in a prompt of an MCP server with the arguments and returns its
+    /// text
+    async fn prompt(
+        &self,
+        prompt: &McpPrompt,
+        arguments: BTreeMap<String, String>,
+    ) -> anyhow::Result<String>;
+
+    /// Applies the changes of the MCP configuration, starting the servers that
+    /// were added, stopping the removed ones and restarting the changed ones
+    async fn reload(&self) -> anyhow::Result<McpReload>;
+
+    /// Subscribes to the changes of the availability of the MCP servers
+    fn mcp_events(&self) -> broadcast::Receiver<McpServerEvent>;
+
+    /// Reasons the MCP servers that are down failed, by the name of the server
+    async fn mcp_failures(&self) -> BTreeMap<String, String>;
+
+    /// Registers forge with the authorization server of the remote MCP server
+    /// and returns the authorization the user has to approve in the browser
+    async fn authorize(&self, server: &str) -> anyhow::Result<McpAuthorization>;
+
+    /// Waits for the user to approve the authorization and stores the token
+    /// issued for the server
+    async fn await_authorization(&self, authorization: McpAuthorization) -> anyhow::Result<()>;
+
+    /// Forgets the token issued by the remote MCP server
+    async fn deauthorize(&self, server: &str) -> anyhow::Result<()>;
+
+    /// Tells the servers the agent works in the directory, `None` being the
+    /// one forge was started in
+    async fn set_mcp_cwd(&self, cwd: Option<PathBuf>) -> anyhow::Result<()>;
+}
+
+/// Tools provided by WASM plugins
+#[async_trait::async_trait]
+pub trait PluginService: Send + Sync {
+    async fn plugin_tools(&self) -> anyhow::Result<Vec<ToolDefinition>>;
+    async fn call_plugin(&self, call: ToolCallFull) -> anyhow::Result<ToolOutput>;
+}
+
+#[async_trait::async_trait]
+pub trait OpenApiService: Send + Sync {
+    /// Returns the tools generated from the OpenAPI documents in forge.yaml
+    async fn openapi_tools(&self) -> anyhow::Result<Vec<OpenApiTool>>;
 }
 
 #[async_trait::async_trait]
@@ -204,6 +387,15 @@ pub trait WorkflowService {
 #[async_trait::async_trait]
 pub trait FileDiscoveryService: Send + Sync {
     async fn collect_files(&self, config: Walker) -> anyhow::Result<Vec<File>>;
+
+    /// Branch checked out in the git repository holding the directory, `None`
+    /// outside of a repository or when no branch is checked out
+    async fn git_branch(&self, cwd: &Path) -> Option<String>;
+
+    /// Reads the AGENTS.md, FORGE.md and `.forge/rules/*.md` files from the
+    /// root of the repository holding the directory down to the directory
+    /// itself, so that the nested ones come last and take precedence
+    async fn project_memory(&self, cwd: &Path) -> Vec<ProjectMemory>;
 }
 
 #[async_trait::async_trait]
@@ -241,6 +433,42 @@ pub trait FsPatchService: Send + Sync {
     ) -> anyhow::Result<PatchOutput>;
 }
 
+#[async_trait::async_trait]
+pub trait FsAstEditService: Send + Sync {
+    /// Edits the definition a symbol path points to, through the syntax tree
+    /// of the file.
+    async fn ast_edit(
+        &self,
+        path: String,
+        symbol: Option<String>,
+        operation: AstOperation,
+        content: String,
+    ) -> anyhow::Result<PatchOutput>;
+}
+
+#[async_trait::async_trait]
+pub trait FsApplyPatchService: Send + Sync {
+    /// Applies a unified diff to the files it touches, relative to `cwd`.
+    /// Either all files are changed or none of them are.
+    async fn apply_patch(&self, cwd: PathBuf, patch: String) -> anyhow::Result<ApplyPatchOutput>;
+}
+
+#[async_trait::async_trait]
+pub trait FsPreviewService: Send + Sync {
+    /// Returns the changes a tool call would make to the files, without
+    /// writing anything. Paths in patches are relative to `cwd`.
+    async fn preview(&self, tool: Tools, cwd: PathBuf) -> anyhow::Result<ApplyPatchOutput>;
+}
+
+#[async_trait::async_trait]
+pub trait FsTransactionService: Send + Sync {
+    /// Returns the bytes of a file, or `None` when it doesn't exist.
+    async fn current(&self, path: &Path) -> anyhow::Result<Option<Vec<u8>>>;
+    /// Restores a file to the given bytes. The file is removed when there is
+    /// no content, ie: it didn't exist before.
+    async fn restore(&self, path: &Path, content: Option<Vec<u8>>) -> anyhow::Result<()>;
+}
+
 #[async_trait::async_trait]
 pub trait FsReadService: Send + Sync {
     /// Reads a file at the specified path and returns its content.
@@ -261,11 +489,15 @@ pub trait FsRemoveService: Send + Sync {
 #[async_trait::async_trait]
 pub trait FsSearchService: Send + Sync {
     /// Searches for a file at the specified path and returns its content.
+    /// Content matches include `context_lines` lines before and after them.
+    /// The directories of the workspace are walked with the filter.
     async fn search(
         &self,
         path: String,
         regex: Option<String>,
         file_pattern: Option<String>,
+        context_lines: usize,
+        filter: &WalkerFilter,
     ) -> anyhow::Result<Option<SearchResult>>;
 }
 
@@ -295,15 +527,183 @@ pub trait NetFetchService: Send + Sync {
     async fn fetch(&self, url: String, raw: Option<bool>) -> anyhow::Result<HttpResponse>;
 }
 
+#[async_trait::async_trait]
+pub trait NetRequestService: Send + Sync {
+    /// Sends an HTTP request and returns the response, whatever its status
+    /// code.
+    async fn request(
+        &self,
+        method: HttpMethod,
+        url: String,
+        headers: BTreeMap<String, String>,
+        body: Option<String>,
+    ) -> anyhow::Result<HttpResponse>;
+}
+
+#[async_trait::async_trait]
+pub trait BrowserService: Send + Sync {
+    /// Loads the URL in the browser and returns the readable text of the page.
+    async fn open(&self, url: String) -> anyhow::Result<BrowserPage>;
+
+    /// Returns the readable text of the currently open page.
+    async fn text(&self) -> anyhow::Result<BrowserPage>;
+
+    /// Clicks the element matching the CSS selector and returns the page that
+    /// is open afterwards.
+    async fn click(&self, selector: String) -> anyhow::Result<BrowserPage>;
+
+    /// Captures the visible part of the currently open page.
+    async fn screenshot(&self) -> anyhow::Result<Image>;
+
+    /// Returns the URL of the currently open page, if any.
+    async fn current_url(&self) -> anyhow::Result<Option<String>>;
+
+    /// Returns the URL of the page last returned by the browser, if any. The
+    /// open page may have navigated away from it since.
+    async fn seen_url(&self) -> anyhow::Result<Option<String>>;
+
+    /// Closes the currently open page.
+    async fn close(&self) -> anyhow::Result<()>;
+}
+
+#[async_trait::async_trait]
+pub trait LspService: Send + Sync {
+    /// Finds the references to the symbol on the given line of the file,
+    /// including its declaration, using the language server of the file.
+    async fn find_references(
+        &self,
+        cwd: PathBuf,
+        path: PathBuf,
+        line: u64,
+        symbol: String,
+    ) -> anyhow::Result<Vec<SymbolReference>>;
+
+    /// Computes the changes that rename the symbol on the given line of the
+    /// file across the workspace. The files are not written.
+    async fn rename_symbol(
+        &self,
+        cwd: PathBuf,
+        path: PathBuf,
+        line: u64,
+        symbol: String,
+        new_name: String,
+    ) -> anyhow::Result<ApplyPatchOutput>;
+}
+
+#[async_trait::async_trait]
+pub trait WorkspaceIndexService: Send + Sync {
+    /// Finds the chunks of the workspace closest in meaning to the query,
+    /// ordered by relevance. The files that changed since the last search are
+    /// indexed first, with the embedding model of the provider. Only the files
+    /// the filter walks are indexed, leaving out the generated ones.
+    async fn semantic_search(
+        &self,
+        provider: Provider,
+        model: ModelId,
+        query: String,
+        limit: usize,
+        filter: &WalkerFilter,
+        generated: &GeneratedFiles,
+    ) -> anyhow::Result<Vec<ChunkMatch>>;
+}
+
+#[async_trait::async_trait]
+pub trait SymbolIndexService: Send + Sync {
+    /// Outlines the definitions of a source file, in the order they appear.
+    async fn outline_file(&self, path: PathBuf) -> anyhow::Result<Vec<Symbol>>;
+
+    /// Finds the definitions of the workspace whose symbol path ends with the
+    /// given one, in the files the filter walks that aren't generated. The
+    /// files that changed since the last lookup are outlined first.
+    async fn find_symbol(
+        &self,
+        symbol: String,
+        filter: &WalkerFilter,
+        generated: &GeneratedFiles,
+    ) -> anyhow::Result<Vec<SymbolMatch>>;
+
+    /// Outlines the source files of the workspace for the repo map, counting
+    /// the other files that mention their top level definitions. The files
+    /// that changed since the last lookup are outlined first.
+    async fn map_files(
+        &self,
+        filter: &WalkerFilter,
+        generated: &GeneratedFiles,
+    ) -> anyhow::Result<Vec<RepoMapFile>>;
+
+    /// Like `map_files`, without waiting for the files to be outlined: returns
+    /// them as of the last lookup and updates the index in the background.
+    /// Returns `None` until the workspace was outlined once.
+    async fn try_map_files(
+        &self,
+        filter: &WalkerFilter,
+        generated: &GeneratedFiles,
+    ) -> Option<Vec<RepoMapFile>>;
+}
+
+#[async_trait::async_trait]
+pub trait WorkspaceStatsService: Send + Sync {
+    /// Counts the files and lines of the workspace by language and finds its
+    /// largest files. Ignored, binary and generated files are left out, along
+    /// with the ones the filter doesn't walk. Files beyond `max_files` aren't
+    /// counted, and the statistics are then marked as truncated.
+    async fn workspace_stats(
+        &self,
+        filter: &WalkerFilter,
+        generated: &GeneratedFiles,
+        max_files: usize,
+    ) -> anyhow::Result<WorkspaceStats>;
+}
+
 #[async_trait::async_trait]
 pub trait ShellService: Send + Sync {
-    /// Executes a shell command and returns the output.
+    /// Executes a shell command with the shell, or the one of the user if not
+    /// specified, and the variables of the project, and returns the output.
+    /// The command reads `stdin` as its input, or an empty input if not
+    /// specified. A command the policy refuses fails with a
+    /// `CommandViolation` without being run.
     async fn execute(
         &self,
         command: String,
         cwd: PathBuf,
+        shell: Option<ShellKind>,
         keep_ansi: bool,
+        stdin: Option<String>,
+        settings: &CommandSettings,
     ) -> anyhow::Result<ShellOutput>;
+
+    /// Starts a shell command in the background, such as a dev server, which
+    /// keeps running until it's stopped.
+    async fn start_process(
+        &self,
+        command: String,
+        cwd: PathBuf,
+        settings: &CommandSettings,
+    ) -> anyhow::Result<BackgroundProcess>;
+
+    /// Stops a background process along with the processes it started.
+    async fn stop_process(&self, id: u32) -> anyhow::Result<BackgroundProcess>;
+
+    /// Returns the last `lines` lines a background process wrote to each of
+    /// its output streams, without ANSI escape codes.
+    async fn process_logs(&self, id: u32, lines: usize) -> anyhow::Result<ProcessLogs>;
+
+    /// Lists the background processes, including the ones that exited.
+    async fn list_processes(&self) -> anyhow::Result<Vec<BackgroundProcess>>;
+}
+
+#[async_trait::async_trait]
+pub trait PythonService: Send + Sync {
+    /// Runs the code in the Python interpreter of the conversation, which is
+    /// started on the first call and keeps its state for the following ones.
+    async fn run_python(
+        &self,
+        conversation_id: Option<ConversationId>,
+        code: String,
+        cwd: PathBuf,
+        timeout: Duration,
+        restart: bool,
+    ) -> anyhow::Result<PythonOutput>;
 }
 
 #[async_trait::async_trait]
@@ -316,12 +716,34 @@ pub trait AppConfigService: Send + Sync {
 pub trait AuthService: Send + Sync {
     async fn init_auth(&self) -> anyhow::Result<InitAuth>;
     async fn login(&self, auth: &InitAuth) -> anyhow::Result<LoginInfo>;
+    /// Exchanges a refresh token for a new access token
+    async fn refresh(&self, refresh_token: &str) -> anyhow::Result<LoginInfo>;
     async fn user_info(&self, api_key: &str) -> anyhow::Result<User>;
     async fn user_usage(&self, api_key: &str) -> anyhow::Result<UserUsage>;
 }
 #[async_trait::async_trait]
 pub trait ProviderRegistry: Send + Sync {
     async fn get_provider(&self, config: AppConfig) -> anyhow::Result<Provider>;
+
+    /// Resolves the provider configured on an agent, independently of the
+    /// active provider
+    async fn get_agent_provider(
+        &self,
+        config: AppConfig,
+        details: &ProviderDetails,
+    ) -> anyhow::Result<Provider>;
+
+    /// Switches to the next configured API key for the given provider, the
+    /// active one when `details` is `None`, recording the choice in `config`.
+    /// Returns `false` when there is no other key to rotate to.
+    async fn rotate_key(
+        &self,
+        config: &mut AppConfig,
+        details: Option<&ProviderDetails>,
+    ) -> anyhow::Result<bool>;
+
+    /// Validates the provider configuration of all profiles in the workflow
+    async fn validate_providers(&self) -> anyhow::Result<Vec<ProviderWarning>>;
 }
 
 #[async_trait::async_trait]
@@ -332,13 +754,166 @@ pub trait AgentLoaderService: Send + Sync {
 
 #[async_trait::async_trait]
 pub trait PolicyService: Send + Sync {
-    /// Check if an operation is allowed and handle user confirmation if needed
-    /// Returns PolicyDecision with allowed flag and optional policy file path
-    /// (only when created)
+    /// Check if an operation is allowed, denied or needs confirmation from the
+    /// user. Returns PolicyDecision with the permission and optional policy
+    /// file path (only when created)
     async fn check_operation_permission(
         &self,
         operation: &forge_domain::Operation,
     ) -> anyhow::Result<PolicyDecision>;
+
+    /// Adds a policy that allows operations similar to the given one. Returns
+    /// the path of the updated policy file, if a policy was added.
+    async fn remember_operation(
+        &self,
+        operation: &forge_domain::Operation,
+    ) -> anyhow::Result<Option<PathBuf>>;
+}
+
+#[async_trait::async_trait]
+pub trait ToolAuditService: Send + Sync {
+    /// Appends the record of a tool call to the audit log
+    async fn record_tool_call(&self, record: ToolCallRecord) -> anyhow::Result<()>;
+
+    /// Returns the recorded tool calls matching the query, from the oldest to
+    /// the most recent
+    async fn tool_calls(&self, query: ToolCallQuery) -> anyhow::Result<Vec<ToolCallRecord>>;
+}
+
+#[async_trait::async_trait]
+pub trait TaskService: Send + Sync {
+    /// Reads the task list of the workspace, which is empty if no task was
+    /// added yet
+    async fn load_tasks(&self) -> anyhow::Result<TaskList>;
+
+    /// Replaces the task list of the workspace
+    async fn save_tasks(&self, tasks: &TaskList) -> anyhow::Result<()>;
+}
+
+#[async_trait::async_trait]
+pub trait MemoryService: Send + Sync {
+    /// Reads the facts remembered across sessions, for all the projects
+    async fn load_memories(&self) -> anyhow::Result<MemoryStore>;
+
+    /// Changes the facts remembered across sessions and saves them, while the
+    /// other processes of forge wait to change them
+    async fn update_memories<T: Send + 'static>(
+        &self,
+        update: Box<dyn FnOnce(&mut MemoryStore) -> T + Send>,
+    ) -> anyhow::Result<T>;
+
+    /// Project the memories of the directory are kept under: the root of its
+    /// repository, so that the subdirectories of a project and the paths
+    /// leading to it through a link share them
+    async fn memory_project(&self, cwd: &Path) -> PathBuf;
+}
+
+#[async_trait::async_trait]
+pub trait RequestRecorderService: Send + Sync {
+    /// Starts the dump of a request sent to the model, removing the oldest
+    /// dumps so that only `keep` of them remain. Returns the path of the dump
+    /// the responses are appended to.
+    async fn record_request(
+        &self,
+        model: &ModelId,
+        context: &Context,
+        keep: usize,
+    ) -> anyhow::Result<PathBuf>;
+
+    /// Appends a response streamed back for the request to its dump
+    async fn record_response(
+        &self,
+        dump: &Path,
+        response: Result<&ChatCompletionMessage, &anyhow::Error>,
+    ) -> anyhow::Result<()>;
+}
+
+#[async_trait::async_trait]
+pub trait SessionService: Send + Sync {
+    /// Saves the conversation and records it in the index of the sessions
+    async fn save_session(&self, conversation: &Conversation) -> anyhow::Result<()>;
+
+    /// Appends a step of the ongoing turn to the journal of the session, so
+    /// that it survives a crash before the session is saved again
+    async fn record_session_event(
+        &self,
+        id: &ConversationId,
+        event: &SessionEvent,
+    ) -> anyhow::Result<()>;
+
+    /// Reads the index of the saved sessions, the most recent first
+    async fn load_sessions(&self) -> anyhow::Result<SessionIndex>;
+
+    /// Reads the conversation of the session found by its id or its name, or
+    /// of the most recent session of the workspace for `last`, recovering the
+    /// steps journaled after it was saved
+    async fn load_session(&self, key: &str) -> anyhow::Result<Conversation>;
+
+    /// Names the session found by its id or its current name
+    async fn rename_session(&self, key: &str, name: &str) -> anyhow::Result<Session>;
+
+    /// Adds the tags to the session found by its id or its name
+    async fn tag_session(&self, key: &str, tags: &[String]) -> anyhow::Result<Session>;
+
+    /// Removes the tags from the session found by its id or its name
+    async fn untag_session(&self, key: &str, tags: &[String]) -> anyhow::Result<Session>;
+
+    /// Removes the session found by its id or its name, along with its saved
+    /// conversation
+    async fn delete_session(&self, key: &str) -> anyhow::Result<Session>;
+}
+
+#[async_trait::async_trait]
+pub trait MailboxService: Send + Sync {
+    /// Queues a message for the agent of the conversation it is sent to
+    async fn post_message(
+        &self,
+        conversation: &ConversationId,
+        message: AgentMessage,
+    ) -> anyhow::Result<()>;
+
+    /// Takes the messages sent to the agent in the conversation, oldest first.
+    /// When none has arrived yet, waits up to `wait` for one.
+    async fn take_messages(
+        &self,
+        conversation: &ConversationId,
+        agent: &AgentId,
+        wait: Option<Duration>,
+    ) -> anyhow::Result<Vec<AgentMessage>>;
+}
+
+#[async_trait::async_trait]
+pub trait SteeringService: Send + Sync {
+    /// Queues an instruction of the user for the turn running on the
+    /// conversation
+    async fn steer(&self, conversation_id: &ConversationId, message: String) -> anyhow::Result<()>;
+
+    /// Takes the instructions sent to the conversation, oldest first. When
+    /// none has arrived yet and `wait` is set, waits for one.
+    async fn take_steering(
+        &self,
+        conversation_id: &ConversationId,
+        wait: bool,
+    ) -> anyhow::Result<Vec<String>>;
+}
+
+pub trait LifecycleService: Send + Sync {
+    /// Publishes the event to the subscribers, if any
+    fn publish(&self, event: LifecycleEvent);
+
+    /// Subscribes to the events of the turns of all the conversations
+    fn lifecycle_events(&self) -> broadcast::Receiver<LifecycleEvent>;
+}
+
+#[async_trait::async_trait]
+pub trait StorageService: Send + Sync {
+    /// Lists the sessions, request dumps and snapshots stored under the base
+    /// path, with the bytes removing each of them frees
+    async fn stored_items(&self) -> anyhow::Result<Vec<StoredItem>>;
+
+    /// Removes the stored items and returns the ones removed. A session saved
+    /// since it was listed is left alone.
+    async fn remove_stored(&self, items: &[StoredItem]) -> anyhow::Result<Vec<StoredItem>>;
 }
 
 /// Core app trait providing access to services and repositories.
@@ -356,19 +931,41 @@ pub trait Services: Send + Sync + 'static + Clone {
     type FsCreateService: FsCreateService;
     type PlanCreateService: PlanCreateService;
     type FsPatchService: FsPatchService;
+    type FsApplyPatchService: FsApplyPatchService;
+    type FsAstEditService: FsAstEditService;
+    type FsPreviewService: FsPreviewService;
+    type FsTransactionService: FsTransactionService;
     type FsReadService: FsReadService;
     type FsRemoveService: FsRemoveService;
     type FsSearchService: FsSearchService;
     type FollowUpService: FollowUpService;
     type FsUndoService: FsUndoService;
     type NetFetchService: NetFetchService;
+    type NetRequestService: NetRequestService;
+    type BrowserService: BrowserService;
+    type LspService: LspService;
+    type WorkspaceIndexService: WorkspaceIndexService;
+    type SymbolIndexService: SymbolIndexService;
+    type WorkspaceStatsService: WorkspaceStatsService;
     type ShellService: ShellService;
+    type PythonService: PythonService;
     type McpService: McpService;
+    type PluginService: PluginService;
+    type OpenApiService: OpenApiService;
     type AuthService: AuthService;
     type AppConfigService: AppConfigService;
     type ProviderRegistry: ProviderRegistry;
     type AgentLoaderService: AgentLoaderService;
     type PolicyService: PolicyService;
+    type ToolAuditService: ToolAuditService;
+    type TaskService: TaskService;
+    type MemoryService: MemoryService;
+    type RequestRecorderService: RequestRecorderService;
+    type SessionService: SessionService;
+    type StorageService: StorageService;
+    type MailboxService: MailboxService;
+    type SteeringService: SteeringService;
+    type LifecycleService: LifecycleService;
 
     fn provider_service(&self) -> &Self::ProviderService;
     fn conversation_service(&self) -> &Self::ConversationService;
@@ -380,20 +977,42 @@ pub trait Services: Send + Sync + 'static + Clone {
     fn fs_create_service(&self) -> &Self::FsCreateService;
     fn plan_create_service(&self) -> &Self::PlanCreateService;
     fn fs_patch_service(&self) -> &Self::FsPatchService;
+    fn fs_apply_patch_service(&self) -> &Self::FsApplyPatchService;
+    fn fs_ast_edit_service(&self) -> &Self::FsAstEditService;
+    fn fs_preview_service(&self) -> &Self::FsPreviewService;
+    fn fs_transaction_service(&self) -> &Self::FsTransactionService;
     fn fs_read_service(&self) -> &Self::FsReadService;
     fn fs_remove_service(&self) -> &Self::FsRemoveService;
     fn fs_search_service(&self) -> &Self::FsSearchService;
     fn follow_up_service(&self) -> &Self::FollowUpService;
     fn fs_undo_service(&self) -> &Self::FsUndoService;
     fn net_fetch_service(&self) -> &Self::NetFetchService;
+    fn net_request_service(&self) -> &Self::NetRequestService;
+    fn browser_service(&self) -> &Self::BrowserService;
+    fn lsp_service(&self) -> &Self::LspService;
+    fn workspace_index_service(&self) -> &Self::WorkspaceIndexService;
+    fn symbol_index_service(&self) -> &Self::SymbolIndexService;
+    fn workspace_stats_service(&self) -> &Self::WorkspaceStatsService;
     fn shell_service(&self) -> &Self::ShellService;
+    fn python_service(&self) -> &Self::PythonService;
     fn mcp_service(&self) -> &Self::McpService;
+    fn plugin_service(&self) -> &Self::PluginService;
+    fn openapi_service(&self) -> &Self::OpenApiService;
     fn environment_service(&self) -> &Self::EnvironmentService;
     fn auth_service(&self) -> &Self::AuthService;
     fn app_config_service(&self) -> &Self::AppConfigService;
     fn provider_registry(&self) -> &Self::ProviderRegistry;
     fn agent_loader_service(&self) -> &Self::AgentLoaderService;
     fn policy_service(&self) -> &Self::PolicyService;
+    fn tool_audit_service(&self) -> &Self::ToolAuditService;
+    fn task_service(&self) -> &Self::TaskService;
+    fn memory_service(&self) -> &Self::MemoryService;
+    fn request_recorder_service(&self) -> &Self::RequestRecorderService;
+    fn session_service(&self) -> &Self::SessionService;
+    fn storage_service(&self) -> &Self::StorageService;
+    fn mailbox_service(&self) -> &Self::MailboxService;
+    fn steering_service(&self) -> &Self::SteeringService;
+    fn lifecycle_service(&self) -> &Self::LifecycleService;
 }
 
 #[async_trait::async_trait]
@@ -433,6 +1052,15 @@ impl<I: Services> ProviderService for I {
     async fn models(&self, provider: Provider) -> anyhow::Result<Vec<Model>> {
         self.provider_service().models(provider).await
     }
+
+    async fn embed(
+        &self,
+        model: &ModelId,
+        input: Vec<String>,
+        provider: Provider,
+    ) -> anyhow::Result<Vec<Vec<f32>>> {
+        self.provider_service().embed(model, input, provider).await
+    }
 }
 
 #[async_trait::async_trait]
@@ -457,6 +1085,64 @@ impl<I: Services> McpService for I {
     async fn call(&self, call: ToolCallFull) -> anyhow::Result<ToolOutput> {
         self.mcp_service().call(call).await
     }
+
+    async fn prompts(&self) -> anyhow::Result<Vec<McpPrompt>> {
+        self.mcp_service().prompts().await
+    }
+
+    async fn prompt(
+        &self,
+        prompt: &McpPrompt,
+        arguments: BTreeMap<String, String>,
+    ) -> anyhow::Result<String> {
+        self.mcp_service().prompt(prompt, arguments).await
+    }
+
+    async fn reload(&self) -> anyhow::Result<McpReload> {
+        self.mcp_service().reload().await
+    }
+
+    fn mcp_events(&self) -> broadcast::Receiver<McpServerEvent> {
+        self.mcp_service().mcp_events()
+    }
+
+    async fn mcp_failures(&self) -> BTreeMap<String, String> {
+        self.mcp_service().mcp_failures().await
+    }
+
+    async fn authorize(&self, server: &str) -> anyhow::Result<McpAuthorization> {
+        self.mcp_service().authorize(server).await
+    }
+
+    async fn await_authorization(&self, authorization: McpAuthorization) -> anyhow::Result<()> {
+        self.mcp_service().await_authorization(authorization).await
+    }
+
+    async fn deauthorize(&self, server: &str) -> anyhow::Result<()> {
+        self.mcp_service().deauthorize(server).await
+    }
+
+    async fn set_mcp_cwd(&self, cwd: Option<PathBuf>) -> anyhow::Result<()> {
+        self.mcp_service().set_mcp_cwd(cwd).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: Services> PluginService for I {
+    async fn plugin_tools(&self) -> anyhow::Result<Vec<ToolDefinition>> {
+        self.plugin_service().plugin_tools().await
+    }
+
+    async fn call_plugin(&self, call: ToolCallFull) -> anyhow::Result<ToolOutput> {
+        self.plugin_service().call_plugin(call).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: Services> OpenApiService for I {
+    async fn openapi_tools(&self) -> anyhow::Result<Vec<OpenApiTool>> {
+        self.openapi_service().openapi_tools().await
+    }
 }
 
 #[async_trait::async_trait]
@@ -510,6 +1196,14 @@ impl<I: Services> FileDiscoveryService for I {
     async fn collect_files(&self, config: Walker) -> anyhow::Result<Vec<File>> {
         self.file_discovery_service().collect_files(config).await
     }
+
+    async fn git_branch(&self, cwd: &Path) -> Option<String> {
+        self.file_discovery_service().git_branch(cwd).await
+    }
+
+    async fn project_memory(&self, cwd: &Path) -> Vec<ProjectMemory> {
+        self.file_discovery_service().project_memory(cwd).await
+    }
 }
 
 #[async_trait::async_trait]
@@ -556,6 +1250,46 @@ impl<I: Services> FsPatchService for I {
     }
 }
 
+#[async_trait::async_trait]
+impl<I: Services> FsApplyPatchService for I {
+    async fn apply_patch(&self, cwd: PathBuf, patch: String) -> anyhow::Result<ApplyPatchOutput> {
+        self.fs_apply_patch_service().apply_patch(cwd, patch).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: Services> FsAstEditService for I {
+    async fn ast_edit(
+        &self,
+        path: String,
+        symbol: Option<String>,
+        operation: AstOperation,
+        content: String,
+    ) -> anyhow::Result<PatchOutput> {
+        self.fs_ast_edit_service()
+            .ast_edit(path, symbol, operation, content)
+            .await
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: Services> FsPreviewService for I {
+    async fn preview(&self, tool: Tools, cwd: PathBuf) -> anyhow::Result<ApplyPatchOutput> {
+        self.fs_preview_service().preview(tool, cwd).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: Services> FsTransactionService for I {
+    async fn current(&self, path: &Path) -> anyhow::Result<Option<Vec<u8>>> {
+        self.fs_transaction_service().current(path).await
+    }
+
+    async fn restore(&self, path: &Path, content: Option<Vec<u8>>) -> anyhow::Result<()> {
+        self.fs_transaction_service().restore(path, content).await
+    }
+}
+
 #[async_trait::async_trait]
 impl<I: Services> FsReadService for I {
     async fn read(
@@ -584,9 +1318,11 @@ impl<I: Services> FsSearchService for I {
         path: String,
         regex: Option<String>,
         file_pattern: Option<String>,
+        context_lines: usize,
+        filter: &WalkerFilter,
     ) -> anyhow::Result<Option<SearchResult>> {
         self.fs_search_service()
-            .search(path, regex, file_pattern)
+            .search(path, regex, file_pattern, context_lines, filter)
             .await
     }
 }
@@ -619,15 +1355,202 @@ impl<I: Services> NetFetchService for I {
     }
 }
 
+#[async_trait::async_trait]
+impl<I: Services> NetRequestService for I {
+    async fn request(
+        &self,
+        method: HttpMethod,
+        url: String,
+        headers: BTreeMap<String, String>,
+        body: Option<String>,
+    ) -> anyhow::Result<HttpResponse> {
+        self.net_request_service()
+            .request(method, url, headers, body)
+            .await
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: Services> BrowserService for I {
+    async fn open(&self, url: String) -> anyhow::Result<BrowserPage> {
+        self.browser_service().open(url).await
+    }
+
+    async fn text(&self) -> anyhow::Result<BrowserPage> {
+        self.browser_service().text().await
+    }
+
+    async fn click(&self, selector: String) -> anyhow::Result<BrowserPage> {
+        self.browser_service().click(selector).await
+    }
+
+    async fn screenshot(&self) -> anyhow::Result<Image> {
+        self.browser_service().screenshot().await
+    }
+
+    async fn current_url(&self) -> anyhow::Result<Option<String>> {
+        self.browser_service().current_url().await
+    }
+
+    async fn seen_url(&self) -> anyhow::Result<Option<String>> {
+        self.browser_service().seen_url().await
+    }
+
+    async fn close(&self) -> anyhow::Result<()> {
+        self.browser_service().close().await
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: Services> LspService for I {
+    async fn find_references(
+        &self,
+        cwd: PathBuf,
+        path: PathBuf,
+        line: u64,
+        symbol: String,
+    ) -> anyhow::Result<Vec<SymbolReference>> {
+        self.lsp_service()
+            .find_references(cwd, path, line, symbol)
+            .await
+    }
+
+    async fn rename_symbol(
+        &self,
+        cwd: PathBuf,
+        path: PathBuf,
+        line: u64,
+        symbol: String,
+        new_name: String,
+    ) -> anyhow::Result<ApplyPatchOutput> {
+        self.lsp_service()
+            .rename_symbol(cwd, path, line, symbol, new_name)
+            .await
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: Services> WorkspaceIndexService for I {
+    async fn semantic_search(
+        &self,
+        provider: Provider,
+        model: ModelId,
+        query: String,
+        limit: usize,
+        filter: &WalkerFilter,
+        generated: &GeneratedFiles,
+    ) -> anyhow::Result<Vec<ChunkMatch>> {
+        self.workspace_index_service()
+            .semantic_search(provider, model, query, limit, filter, generated)
+            .await
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: Services> SymbolIndexService for I {
+    async fn outline_file(&self, path: PathBuf) -> anyhow::Result<Vec<Symbol>> {
+        self.symbol_index_service().outline_file(path).await
+    }
+
+    async fn find_symbol(
+        &self,
+        symbol: String,
+        filter: &WalkerFilter,
+        generated: &GeneratedFiles,
+    ) -> anyhow::Result<Vec<SymbolMatch>> {
+        self.symbol_index_service()
+            .find_symbol(symbol, filter, generated)
+            .await
+    }
+
+    async fn map_files(
+        &self,
+        filter: &WalkerFilter,
+        generated: &GeneratedFiles,
+    ) -> anyhow::Result<Vec<RepoMapFile>> {
+        self.symbol_index_service()
+            .map_files(filter, generated)
+            .await
+    }
+
+    async fn try_map_files(
+        &self,
+        filter: &WalkerFilter,
+        generated: &GeneratedFiles,
+    ) -> Option<Vec<RepoMapFile>> {
+        self.symbol_index_service()
+            .try_map_files(filter, generated)
+            .await
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: Services> WorkspaceStatsService for I {
+    async fn workspace_stats(
+        &self,
+        filter: &WalkerFilter,
+        generated: &GeneratedFiles,
+        max_files: usize,
+    ) -> anyhow::Result<WorkspaceStats> {
+        self.workspace_stats_service()
+            .workspace_stats(filter, generated, max_files)
+            .await
+    }
+}
+
 #[async_trait::async_trait]
 impl<I: Services> ShellService for I {
     async fn execute(
         &self,
         command: String,
         cwd: PathBuf,
+        shell: Option<ShellKind>,
         keep_ansi: bool,
+        stdin: Option<String>,
+        settings: &CommandSettings,
     ) -> anyhow::Result<ShellOutput> {
-        self.shell_service().execute(command, cwd, keep_ansi).await
+        self.shell_service()
+            .execute(command, cwd, shell, keep_ansi, stdin, settings)
+            .await
+    }
+
+    async fn start_process(
+        &self,
+        command: String,
+        cwd: PathBuf,
+        settings: &CommandSettings,
+    ) -> anyhow::Result<BackgroundProcess> {
+        self.shell_service()
+            .start_process(command, cwd, settings)
+            .await
+    }
+
+    async fn stop_process(&self, id: u32) -> anyhow::Result<BackgroundProcess> {
+        self.shell_service().stop_process(id).await
+    }
+
+    async fn process_logs(&self, id: u32, lines: usize) -> anyhow::Result<ProcessLogs> {
+        self.shell_service().process_logs(id, lines).await
+    }
+
+    async fn list_processes(&self) -> anyhow::Result<Vec<BackgroundProcess>> {
+        self.shell_service().list_processes().await
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: Services> PythonService for I {
+    async fn run_python(
+        &self,
+        conversation_id: Option<ConversationId>,
+        code: String,
+        cwd: PathBuf,
+        timeout: Duration,
+        restart: bool,
+    ) -> anyhow::Result<PythonOutput> {
+        self.python_service()
+            .run_python(conversation_id, code, cwd, timeout, restart)
+            .await
     }
 }
 
@@ -642,6 +1565,28 @@ impl<I: Services> ProviderRegistry for I {
     async fn get_provider(&self, config: AppConfig) -> anyhow::Result<Provider> {
         self.provider_registry().get_provider(config).await
     }
+
+    async fn get_agent_provider(
+        &self,
+        config: AppConfig,
+        details: &ProviderDetails,
+    ) -> anyhow::Result<Provider> {
+        self.provider_registry()
+            .get_agent_provider(config, details)
+            .await
+    }
+
+    async fn rotate_key(
+        &self,
+        config: &mut AppConfig,
+        details: Option<&ProviderDetails>,
+    ) -> anyhow::Result<bool> {
+        self.provider_registry().rotate_key(config, details).await
+    }
+
+    async fn validate_providers(&self) -> anyhow::Result<Vec<ProviderWarning>> {
+        self.provider_registry().validate_providers().await
+    }
 }
 
 #[async_trait::async_trait]
@@ -665,6 +1610,10 @@ impl<I: Services> AuthService for I {
         self.auth_service().login(auth).await
     }
 
+    async fn refresh(&self, refresh_token: &str) -> anyhow::Result<LoginInfo> {
+        self.auth_service().refresh(refresh_token).await
+    }
+
     async fn user_info(&self, api_key: &str) -> anyhow::Result<User> {
         self.auth_service().user_info(api_key).await
     }
@@ -678,7 +1627,12 @@ impl<I: Services> AuthService for I {
 #[async_trait::async_trait]
 pub trait HttpClientService: Send + Sync + 'static {
     async fn get(&self, url: &Url, headers: Option<HeaderMap>) -> anyhow::Result<Response>;
-    async fn post(&self, url: &Url, body: bytes::Bytes) -> anyhow::Result<Response>;
+    async fn post(
+        &self,
+        url: &Url,
+        headers: Option<HeaderMap>,
+        body: bytes::Bytes,
+    ) -> anyhow::Result<Response>;
     async fn delete(&self, url: &Url) -> anyhow::Result<Response>;
 
     /// Posts JSON data and returns a server-sent events stream
@@ -707,4 +1661,178 @@ impl<I: Services> PolicyService for I {
             .check_operation_permission(operation)
             .await
     }
+
+    async fn remember_operation(
+        &self,
+        operation: &forge_domain::Operation,
+    ) -> anyhow::Result<Option<PathBuf>> {
+        self.policy_service().remember_operation(operation).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: Services> ToolAuditService for I {
+    async fn record_tool_call(&self, record: ToolCallRecord) -> anyhow::Result<()> {
+        self.tool_audit_service().record_tool_call(record).await
+    }
+
+    async fn tool_calls(&self, query: ToolCallQuery) -> anyhow::Result<Vec<ToolCallRecord>> {
+        self.tool_audit_service().tool_calls(query).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: Services> TaskService for I {
+    async fn load_tasks(&self) -> anyhow::Result<TaskList> {
+        self.task_service().load_tasks().await
+    }
+
+    async fn save_tasks(&self, tasks: &TaskList) -> anyhow::Result<()> {
+        self.task_service().save_tasks(tasks).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: Services> MemoryService for I {
+    async fn load_memories(&self) -> anyhow::Result<MemoryStore> {
+        self.memory_service().load_memories().await
+    }
+
+    async fn update_memories<T: Send + 'static>(
+        &self,
+        update: Box<dyn FnOnce(&mut MemoryStore) -> T + Send>,
+    ) -> anyhow::Result<T> {
+        self.memory_service().update_memories(update).await
+    }
+
+    async fn memory_project(&self, cwd: &Path) -> PathBuf {
+        self.memory_service().memory_project(cwd).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: Services> RequestRecorderService for I {
+    async fn record_request(
+        &self,
+        model: &ModelId,
+        context: &Context,
+        keep: usize,
+    ) -> anyhow::Result<PathBuf> {
+        self.request_recorder_service()
+            .record_request(model, context, keep)
+            .await
+    }
+
+    async fn record_response(
+        &self,
+        dump: &Path,
+        response: Result<&ChatCompletionMessage, &anyhow::Error>,
+    ) -> anyhow::Result<()> {
+        self.request_recorder_service()
+            .record_response(dump, response)
+            .await
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: Services> SessionService for I {
+    async fn save_session(&self, conversation: &Conversation) -> anyhow::Result<()> {
+        self.session_service().save_session(conversation).await
+    }
+
+    async fn record_session_event(
+        &self,
+        id: &ConversationId,
+        event: &SessionEvent,
+    ) -> anyhow::Result<()> {
+        self.session_service().record_session_event(id, event).await
+    }
+
+    async fn load_sessions(&self) -> anyhow::Result<SessionIndex> {
+        self.session_service().load_sessions().await
+    }
+
+    async fn load_session(&self, key: &str) -> anyhow::Result<Conversation> {
+        self.session_service().load_session(key).await
+    }
+
+    async fn rename_session(&self, key: &str, name: &str) -> anyhow::Result<Session> {
+        self.session_service().rename_session(key, name).await
+    }
+
+    async fn tag_session(&self, key: &str, tags: &[String]) -> anyhow::Result<Session> {
+        self.session_service().tag_session(key, tags).await
+    }
+
+    async fn untag_session(&self, key: &str, tags: &[String]) -> anyhow::Result<Session> {
+        self.session_service().untag_session(key, tags).await
+    }
+
+    async fn delete_session(&self, key: &str) -> anyhow::Result<Session> {
+        self.session_service().delete_session(key).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: Services> StorageService for I {
+    async fn stored_items(&self) -> anyhow::Result<Vec<StoredItem>> {
+        self.storage_service().stored_items().await
+    }
+
+    async fn remove_stored(&self, items: &[StoredItem]) -> anyhow::Result<Vec<StoredItem>> {
+        self.storage_service().remove_stored(items).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: Services> MailboxService for I {
+    async fn post_message(
+        &self,
+        conversation: &ConversationId,
+        message: AgentMessage,
+    ) -> anyhow::Result<()> {
+        self.mailbox_service()
+            .post_message(conversation, message)
+            .await
+    }
+
+    async fn take_messages(
+        &self,
+        conversation: &ConversationId,
+        agent: &AgentId,
+        wait: Option<Duration>,
+    ) -> anyhow::Result<Vec<AgentMessage>> {
+        self.mailbox_service()
+            .take_messages(conversation, agent, wait)
+            .await
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: Services> SteeringService for I {
+    async fn steer(&self, conversation_id: &ConversationId, message: String) -> anyhow::Result<()> {
+        self.steering_service()
+            .steer(conversation_id, message)
+            .await
+    }
+
+    async fn take_steering(
+        &self,
+        conversation_id: &ConversationId,
+        wait: bool,
+    ) -> anyhow::Result<Vec<String>> {
+        self.steering_service()
+            .take_steering(conversation_id, wait)
+            .await
+    }
+}
+
+impl<I: Services> LifecycleService for I {
+    fn publish(&self, event: LifecycleEvent) {
+        self.lifecycle_service().publish(event)
+    }
+
+    fn lifecycle_events(&self) -> broadcast::Receiver<LifecycleEvent> {
+        self.lifecycle_service().lifecycle_events()
+    }
 }
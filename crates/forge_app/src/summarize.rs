@@ -0,0 +1,323 @@
+use std::sync::Arc;
+
+use forge_domain::{
+    ChatCompletionMessageFull, Context, ContextMessage, ModelId, Pins, ProviderDetails,
+    ResultStreamExt, Role, extract_tag_content,
+};
+
+use crate::agent::AgentService;
+
+/// Approximate tokens of history sent in a request. A longer history is
+/// summarized a chunk at a time, each along with the summary of the chunks
+/// before it.
+const CHUNK_TOKENS: usize = 32_000;
+
+/// Summarizes a conversation on request of the user. Unlike compaction, the
+/// whole history is summarized into decisions, open tasks and files touched.
+pub struct Summarizer<S> {
+    services: Arc<S>,
+}
+
+impl<S: AgentService> Summarizer<S> {
+    pub fn new(services: Arc<S>) -> Self {
+        Self { services }
+    }
+
+    /// Summarizes the messages of the context with the model, served by the
+    /// given provider if any. Returns `None` when there's nothing to summarize
+    /// yet.
+    pub async fn summarize(
+        &self,
+        model: &ModelId,
+        provider: Option<&ProviderDetails>,
+        context: &Context,
+    ) -> anyhow::Result<Option<String>> {
+        let history = history(context);
+        if history.is_empty() {
+            return Ok(None);
+        }
+
+        let prompt = self
+            .services
+            .render(
+                "{{> forge-system-prompt-conversation-summary.hbs}}",
+                &serde_json::json!({}),
+            )
+            .await?;
+
+        // The prompt asks to consolidate the earlier summaries found in the
+        // conversation
+        let mut summary: Option<String> = None;
+        for chunk in chunks(history, CHUNK_TOKENS) {
+            let conversation = summary
+                .iter()
+                .map(|summary| ContextMessage::user(format!("<summary>{summary}</summary>"), None))
+                .chain(chunk)
+                .fold(Context::default(), |conversation, message| {
+                    conversation.add_message(message)
+                });
+            let request = Context::default()
+                .add_message(ContextMessage::system(prompt.clone()))
+                .add_message(ContextMessage::user(
+                    format!("<conversation>{}</conversation>", conversation.to_text()),
+                    Some(model.clone()),
+                ));
+
+            let ChatCompletionMessageFull { content, .. } = self
+                .services
+                .chat_agent(model, request, provider)
+                .await?
+                .into_full(false)
+                .await?;
+            let content = extract_tag_content(&content, "summary").unwrap_or(&content);
+            summary = Some(content.trim().to_string());
+        }
+        Ok(summary)
+    }
+
+    /// Replaces the history of the context with the summary. The system
+    /// messages and the pinned ones are kept.
+    pub async fn replace_history(
+        &self,
+        mut context: Context,
+        summary: &str,
+        pins: &Pins,
+    ) -> anyhow::Result<Context> {
+        let summary = self
+            .services
+            .render(
+                "{{> forge-partial-summary-frame.hbs}}",
+                &serde_json::json!({ "summary": summary }),
+            )
+            .await?;
+
+        let (system, pinned): (Vec<_>, Vec<_>) = context
+            .messages
+            .into_iter()
+            .filter(|message| message.has_role(Role::System) || pins.keeps(message))
+            .partition(|message| message.has_role(Role::System));
+        context.messages = system
+            .into_iter()
+            .chain(std::iter::once(ContextMessage::user(summary, None)))
+            .chain(pinned)
+            .collect();
+        // The usage was reported for the history that's gone
+        context.usage = None;
+        Ok(context)
+    }
+}
+
+/// The messages of the context without the system prompt
+fn history(context: &Context) -> Vec<ContextMessage> {
+    context
+        .messages
+        .iter()
+        .filter(|message| !message.has_role(Role::System))
+        .cloned()
+        .collect()
+}
+
+/// Splits the messages into chunks of at most the given approximate tokens. A
+/// message larger than that is a chunk of its own.
+fn chunks(messages: Vec<ContextMessage>, max_tokens: usize) -> Vec<Vec<ContextMessage>> {
+    let mut chunks = Vec::new();
+    let mut chunk = Vec::new();
+    let mut tokens = 0;
+    for message in messages {
+        let count = message.token_count_approx();
+        if !chunk.is_empty() && tokens + count > max_tokens {
+            chunks.push(std::mem::take(&mut chunk));
+            tokens = 0;
+        }
+        tokens += count;
+        chunk.push(message);
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use forge_domain::{
+        Agent, AgentId, AgentMessage, ChatCompletionMessage, Content, Conversation, ConversationId,
+        HookOutcome, HookPayload, LifecycleEvent, ResultStream, SessionEvent, ToolCallContext,
+        ToolCallFull, ToolResult, Workflow,
+    };
+    use pretty_assertions::assert_eq;
+    use tokio::sync::Mutex;
+
+    use super::*;
+
+    /// Answers the requests with the summaries in order and keeps the requests
+    #[derive(Default)]
+    struct Fixture {
+        summaries: Mutex<VecDeque<String>>,
+        requests: Mutex<Vec<Context>>,
+    }
+
+    #[async_trait::async_trait]
+    impl AgentService for Fixture {
+        async fn chat_agent(
+            &self,
+            _id: &ModelId,
+            context: Context,
+            _provider: Option<&ProviderDetails>,
+        ) -> ResultStream<ChatCompletionMessage, anyhow::Error> {
+            self.requests.lock().await.push(context);
+            let summary = self.summaries.lock().await.pop_front().unwrap();
+            let message = ChatCompletionMessage::assistant(Content::full(format!(
+                "<summary>{summary}</summary>"
+            )));
+            Ok(Box::pin(tokio_stream::iter(std::iter::once(Ok(message)))))
+        }
+
+        async fn call(
+            &self,
+            _agent: &Agent,
+            _context: &mut ToolCallContext,
+            _call: ToolCallFull,
+        ) -> ToolResult {
+            unimplemented!()
+        }
+
+        async fn render(
+            &self,
+            template: &str,
+            _object: &(impl serde::Serialize + Sync),
+        ) -> anyhow::Result<String> {
+            Ok(template.to_string())
+        }
+
+        async fn update(&self, _conversation: Conversation) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        async fn run_hooks(&self, _workflow: &Workflow, _payload: &HookPayload) -> HookOutcome {
+            unimplemented!()
+        }
+
+        async fn checkpoint(
+            &self,
+            _id: &ConversationId,
+            _event: SessionEvent,
+        ) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        async fn receive_messages(
+            &self,
+            _conversation: &ConversationId,
+            _agent: &AgentId,
+        ) -> anyhow::Result<Vec<AgentMessage>> {
+            unimplemented!()
+        }
+
+        async fn receive_steering(
+            &self,
+            _id: &ConversationId,
+            _wait: bool,
+        ) -> anyhow::Result<Vec<String>> {
+            unimplemented!()
+        }
+
+        fn publish_lifecycle(&self, _event: LifecycleEvent) {}
+    }
+
+    fn summarizer(summaries: &[&str]) -> Summarizer<Fixture> {
+        let fixture = Fixture::default();
+        fixture
+            .summaries
+            .try_lock()
+            .unwrap()
+            .extend(summaries.iter().map(|summary| summary.to_string()));
+        Summarizer::new(Arc::new(fixture))
+    }
+
+    /// Message of about the given approximate tokens
+    fn message(tokens: usize) -> ContextMessage {
+        ContextMessage::user("word".repeat(tokens), None)
+    }
+
+    #[tokio::test]
+    async fn test_summarize_without_history() {
+        let fixture = summarizer(&[]);
+        let context = Context::default().add_message(ContextMessage::system("You are Forge"));
+
+        let actual = fixture
+            .summarize(&ModelId::new("model"), None, &context)
+            .await
+            .unwrap();
+
+        assert_eq!(actual, None);
+    }
+
+    #[tokio::test]
+    async fn test_summarize_history_in_chunks() {
+        let fixture = summarizer(&["first half", "whole history"]);
+        let context = Context::default()
+            .add_message(ContextMessage::system("You are Forge"))
+            .add_message(message(CHUNK_TOKENS / 2))
+            .add_message(message(CHUNK_TOKENS / 2))
+            .add_message(message(CHUNK_TOKENS / 2));
+
+        let summary = fixture
+            .summarize(&ModelId::new("model"), None, &context)
+            .await
+            .unwrap();
+        let requests = fixture.services.requests.lock().await;
+
+        let actual = (
+            summary,
+            requests.len(),
+            requests[0].to_text().contains("first half"),
+            requests[1].to_text().contains("first half"),
+        );
+        let expected = (Some("whole history".to_string()), 2, false, true);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_chunks() {
+        let fixture = vec![message(3), message(2), message(6), message(1)];
+
+        let actual = chunks(fixture, 5)
+            .iter()
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .map(|m| m.token_count_approx())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let expected = vec![vec![3, 2], vec![6], vec![1]];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_replace_history_keeps_system_and_pinned_messages() {
+        let fixture = summarizer(&[]);
+        let context = Context::default()
+            .add_message(ContextMessage::system("You are Forge"))
+            .add_message(ContextMessage::user("Fix the parser", None));
+
+        let actual = fixture
+            .replace_history(context, "The parser was fixed", &Pins::default())
+            .await
+            .unwrap()
+            .messages
+            .iter()
+            .map(|message| message.content().unwrap_or_default().to_string())
+            .collect::<Vec<_>>();
+
+        let expected = vec![
+            "You are Forge".to_string(),
+            "{{> forge-partial-summary-frame.hbs}}".to_string(),
+        ];
+        assert_eq!(actual, expected);
+    }
+}
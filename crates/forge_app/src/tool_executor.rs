@@ -1,8 +1,17 @@
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
-use anyhow::Context;
+use anyhow::{Context, bail};
+use console::strip_ansi_codes;
 use forge_display::TitleFormat;
-use forge_domain::{ToolCallContext, ToolCallFull, ToolOutput, Tools};
+use forge_domain::{
+    AgentId, AgentMessage, Approval, Background, BackgroundAction, BrowserAction, ChatResponse,
+    CommandSettings, CustomTool, Environment, FSWrite, GeneratedFiles, Hunk, NetRequest,
+    OpenApiTool, OperationClass, Permission, RetryPolicy, Shell, ShellKind, TaskList,
+    ToolCallContext, ToolCallFull, ToolName, ToolOutput, Tools, parse_unified_diff,
+};
+use url::Url;
 
 use crate::error::Error;
 use crate::fmt::content::FormatContent;
@@ -10,11 +19,120 @@ use crate::operation::{Operation, TempContentFiles};
 use crate::services::ShellService;
 use crate::utils::format_display_path;
 use crate::{
-    ConversationService, EnvironmentService, FollowUpService, FsCreateService, FsPatchService,
-    FsReadService, FsRemoveService, FsSearchService, FsUndoService, NetFetchService,
-    PlanCreateService, PolicyService,
+    AppConfigService, BrowserOutput, BrowserService, Content, ConversationService, DryRunOutput,
+    EnvironmentService, FollowUpService, FsApplyPatchService, FsAstEditService, FsCreateService,
+    FsPatchService, FsPreviewService, FsReadService, FsRemoveService, FsSearchService,
+    FsTransactionService, FsUndoService, LspService, MailboxService, MemoryService,
+    NetFetchService, NetRequestService, OpenApiService, PlanCreateService, PolicyService,
+    ProcessOutput, ProviderRegistry, PythonService, ReadOutput, ShellOutput, SymbolIndexService,
+    TaskService, WorkflowService, WorkspaceIndexService, WorkspaceStatsService,
 };
 
+/// Whether the user allowed an operation, and for a change of a file reviewed
+/// hunk by hunk, which part of it
+enum Consent {
+    Denied,
+    Given,
+    /// Some hunks were rejected, so the file is written with the accepted ones
+    /// only
+    GivenInPart(ReviewedChange),
+}
+
+/// Content of a file with the hunks of a change the user accepted
+struct ReviewedChange {
+    path: PathBuf,
+    content: String,
+    rejected: usize,
+    hunks: usize,
+}
+
+/// Chunks returned by the semantic search when the call doesn't limit them
+const DEFAULT_SEMANTIC_SEARCH_RESULTS: u32 = 10;
+
+/// Memories returned by a recall when the call doesn't limit them
+const DEFAULT_MEMORY_RECALL_RESULTS: u32 = 10;
+
+/// Files counted at most by the workspace statistics tool
+const STATS_MAX_FILES: usize = 10_000;
+
+/// Lines of a lockfile, minified or generated file shown when it's read in
+/// full, each cut to [`SUMMARY_LINE_LENGTH`] characters
+const SUMMARY_LINES: usize = 10;
+const SUMMARY_LINE_LENGTH: usize = 200;
+
+/// Replaces the content of a lockfile, minified or generated file with its
+/// first lines, matching the patterns of the generated files against the path
+/// relative to the working directory
+fn summarize(
+    output: ReadOutput,
+    path: &Path,
+    cwd: &Path,
+    generated: &GeneratedFiles,
+) -> ReadOutput {
+    let Content::File(content) = &output.content else {
+        return output;
+    };
+    let relative = path.strip_prefix(cwd).unwrap_or(path);
+    let Some(kind) = generated.detect(relative, content) else {
+        return output;
+    };
+
+    let lines = content
+        .lines()
+        .take(SUMMARY_LINES)
+        .map(|line| line.chars().take(SUMMARY_LINE_LENGTH).collect::<String>())
+        .collect::<Vec<_>>();
+    ReadOutput {
+        end_line: (output.start_line + lines.len() as u64).saturating_sub(1),
+        content: Content::Summary { kind, preview: lines.join("\n") },
+        ..output
+    }
+}
+
+/// Files a tool call writes to, `None` for tools that don't change files. The
+/// files changed by a rename are only known once the language server answered,
+/// so the call records them itself.
+fn written_paths(input: &Tools, cwd: &Path) -> Option<Vec<PathBuf>> {
+    Some(match input {
+        Tools::ForgeToolFsCreate(input) => vec![PathBuf::from(&input.path)],
+        Tools::ForgeToolFsPatch(input) => vec![PathBuf::from(&input.path)],
+        Tools::ForgeToolFsAstEdit(input) => vec![PathBuf::from(&input.path)],
+        Tools::ForgeToolFsRemove(input) => vec![PathBuf::from(&input.path)],
+        Tools::ForgeToolFsUndo(input) => vec![PathBuf::from(&input.path)],
+        Tools::ForgeToolFsApplyPatch(input) => {
+            // An invalid patch doesn't write anything and fails on its own
+            let mut paths: Vec<_> = parse_unified_diff(&input.patch)
+                .unwrap_or_default()
+                .into_iter()
+                .flat_map(|diff| [diff.old_path, diff.new_path])
+                .flatten()
+                .map(|path| cwd.join(path))
+                .collect();
+            paths.dedup();
+            paths
+        }
+        Tools::ForgeToolLspRenameSymbol(_) => Vec::new(),
+        _ => return None,
+    })
+}
+
+/// Returns true for the tool calls that are only previewed in dry-run mode
+fn has_side_effects(input: &Tools, cwd: &Path) -> bool {
+    written_paths(input, cwd).is_some()
+        || matches!(
+            input,
+            Tools::ForgeToolProcessShell(_) | Tools::ForgeToolProcessPython(_)
+        )
+        || matches!(
+            input,
+            Tools::ForgeToolProcessBackground(Background {
+                action: BackgroundAction::Start,
+                command: Some(_),
+                ..
+            })
+        )
+}
+
 pub struct ToolExecutor<S> {
     services: Arc<S>,
 }
@@ -24,54 +142,507 @@ impl<
         + FsCreateService
         + FsSearchService
         + NetFetchService
+        + NetRequestService
+        + OpenApiService
+        + BrowserService
         + FsRemoveService
         + FsPatchService
+        + FsApplyPatchService
+        + FsAstEditService
+        + FsPreviewService
+        + FsTransactionService
         + FsUndoService
+        + LspService
+        + WorkspaceIndexService
+        + SymbolIndexService
+        + WorkspaceStatsService
+        + AppConfigService
+        + ProviderRegistry
         + ShellService
+        + PythonService
         + FollowUpService
         + ConversationService
         + EnvironmentService
         + PlanCreateService
-        + PolicyService,
+        + PolicyService
+        + TaskService
+        + MemoryService
+        + MailboxService
+        + WorkflowService,
 > ToolExecutor<S>
 {
     pub fn new(services: Arc<S>) -> Self {
         Self { services }
     }
 
-    /// Check if a tool operation is allowed based on the workflow policies
+    /// Check if a tool operation is allowed based on the workflow policies.
+    /// Returns the part of the change to make instead, when the user accepted
+    /// only some hunks of it.
     async fn check_tool_permission(
         &self,
         tool_input: &Tools,
         context: &mut ToolCallContext,
+    ) -> anyhow::Result<Option<ReviewedChange>> {
+        // A memory is given to every later conversation of the project, so a
+        // fact the agent picked up from a file or a page could steer them. The
+        // user approves each one.
+        if let Tools::ForgeToolMemoryRemember(input) = tool_input {
+            let message = format!(
+                "Remember for the next conversations: {}. How would you like to proceed?",
+                input.fact
+            );
+            let options = vec![Approval::Accept, Approval::Reject];
+            return match context.request_approval(message, options).await? {
+                Approval::Reject => Err(anyhow::anyhow!("Operation denied by policy or user.")),
+                _ => Ok(None),
+            };
+        }
+
+        // The paths and commands of the code can't be told from it, so the
+        // guardrails on them rule it out
+        if let Tools::ForgeToolProcessPython(input) = tool_input
+            && let Some(violation) = context.guard.check_code(&input.code)
+        {
+            return Err(violation.into());
+        }
+
+        // Relative paths are where the shell commands moved to
+        let shell = ShellKind::from_program(&self.services.get_environment().shell);
+        let operation = tool_input.to_policy_operation(self.cwd(context), shell);
+        self.check_class_permission(
+            operation,
+            OperationClass::from_tool(tool_input),
+            Some(tool_input),
+            context,
+        )
+        .await
+    }
+
+    async fn check_permission(
+        &self,
+        operation: Option<forge_domain::Operation>,
+        context: &mut ToolCallContext,
     ) -> anyhow::Result<()> {
-        let cwd = self.services.get_environment().cwd;
-        let operation = tool_input.to_policy_operation(cwd.clone());
+        let class = operation.as_ref().and_then(OperationClass::from_operation);
+        self.check_class_permission(operation, class, None, context)
+            .await?;
+        Ok(())
+    }
+
+    /// Checks the operation against the guardrails, then against the
+    /// policies, the confirmation of its class deciding the operations they
+    /// would ask about when the workflow sets one. The change of the file
+    /// `tool` makes, if any, can be reviewed when it has to be confirmed.
+    async fn check_class_permission(
+        &self,
+        operation: Option<forge_domain::Operation>,
+        class: Option<OperationClass>,
+        tool: Option<&Tools>,
+        context: &mut ToolCallContext,
+    ) -> anyhow::Result<Option<ReviewedChange>> {
         if let Some(operation) = operation {
+            // Guardrails can't be approved, so they are checked before the
+            // policies
+            if let Some(violation) = context.guard.check_operation(&operation) {
+                return Err(violation.into());
+            }
+
+            let confirmation = match class {
+                Some(class) => self
+                    .services
+                    .read_merged(None)
+                    .await?
+                    .confirmations
+                    .and_then(|confirmations| confirmations.get(class)),
+                None => None,
+            };
+
             let decision = self.services.check_operation_permission(&operation).await?;
 
             // Send custom policy message to the user when a policy file was created
             if let Some(policy_path) = decision.path {
+                self.send_permissions_update(&policy_path, context).await?;
+            }
+            let permission = match confirmation {
+                Some(confirmation) => confirmation.permission(decision.permission),
+                None => decision.permission,
+            };
+
+            let consent = match permission {
+                Permission::Allow => Consent::Given,
+                Permission::Deny => Consent::Denied,
+                Permission::Confirm => {
+                    self.confirm(&operation, class, confirmation.is_some(), tool, context)
+                        .await?
+                }
+            };
+
+            match consent {
+                Consent::Denied => {
+                    return Err(anyhow::anyhow!("Operation denied by policy or user."));
+                }
+                Consent::Given => {}
+                Consent::GivenInPart(change) => return Ok(Some(change)),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Asks the user to confirm the operation, unless the operations of its
+    /// class were allowed for the session or the workspace. When the
+    /// confirmation comes from the class rather than the policies, remembering
+    /// the answer allows the class in the workspace. A change of a file is
+    /// sent along for review, so that the user can accept some of its hunks.
+    /// The operations of the session are kept in the conversation, and the
+    /// ones of the workspace in the config, by the canonical path of the
+    /// workspace.
+    async fn confirm(
+        &self,
+        operation: &forge_domain::Operation,
+        class: Option<OperationClass>,
+        by_class: bool,
+        tool: Option<&Tools>,
+        context: &mut ToolCallContext,
+    ) -> anyhow::Result<Consent> {
+        let cwd = self.services.get_environment().cwd;
+        let workspace = cwd.canonicalize().unwrap_or_else(|_| cwd.clone());
+        if let Some(class) = class {
+            if context.allowed_operations.contains(&class) {
+                return Ok(Consent::Given);
+            }
+            let config = self.services.read_app_config().await?;
+            if config.allowed_operations.is_allowed(class, &workspace) {
+                return Ok(Consent::Given);
+            }
+        }
+
+        let message = format!("{}. How would you like to proceed?", operation.message());
+        // Only the operations of a class can be allowed for the rest of the
+        // session or in the workspace
+        let mut options = vec![
+            Approval::Accept,
+            Approval::Reject,
+            Approval::AcceptAndRemember,
+        ];
+        if class.is_some() {
+            options.extend([Approval::AcceptForSession, Approval::AcceptForWorkspace]);
+        }
+        let review = match tool {
+            Some(tool) => self.change_review(tool, &cwd).await,
+            None => None,
+        };
+        let (approval, accepted) = match &review {
+            Some((path, _, hunks)) => {
                 context
-                    .send_text(
-                        TitleFormat::info("Permissions Update")
-                            .sub_title(format_display_path(policy_path.as_path(), &cwd)),
-                    )
-                    .await?;
+                    .request_change_approval(message, options, path.clone(), hunks.clone())
+                    .await?
+            }
+            None => (context.request_approval(message, options).await?, None),
+        };
+        let approval = match approval {
+            Approval::AcceptAndRemember if by_class => Approval::AcceptForWorkspace,
+            approval => approval,
+        };
+        let allowed = match (approval, class) {
+            (Approval::Accept, _) => true,
+            (Approval::Reject, _) => false,
+            (Approval::AcceptAndRemember, _) => {
+                if let Some(policy_path) = self.services.remember_operation(operation).await? {
+                    self.send_permissions_update(&policy_path, context).await?;
+                }
+                true
+            }
+            (Approval::AcceptForSession | Approval::AcceptForWorkspace, None) => true,
+            (Approval::AcceptForSession, Some(class)) => {
+                context.allowed_operations.insert(class);
+                true
+            }
+            (Approval::AcceptForWorkspace, Some(class)) => {
+                let mut config = self.services.read_app_config().await?;
+                config
+                    .allowed_operations
+                    .allow_in_workspace(workspace, class);
+                self.services.write_app_config(&config).await?;
+                true
+            }
+        };
+
+        if !allowed {
+            return Ok(Consent::Denied);
+        }
+        let (Some((path, before, hunks)), Some(accepted)) = (review, accepted) else {
+            return Ok(Consent::Given);
+        };
+        let rejected = accepted.iter().filter(|accepted| !**accepted).count();
+        if rejected == 0 {
+            return Ok(Consent::Given);
+        }
+        if rejected >= hunks.len() {
+            return Ok(Consent::Denied);
+        }
+        Ok(Consent::GivenInPart(ReviewedChange {
+            content: Hunk::apply(&before, &hunks, &accepted),
+            rejected,
+            hunks: hunks.len(),
+            path,
+        }))
+    }
+
+    /// Path, content and hunks of the change a file tool would make, for the
+    /// tools changing a single file that is kept. A change that can't be
+    /// previewed is confirmed as a whole, and fails once it is made.
+    async fn change_review(
+        &self,
+        tool: &Tools,
+        cwd: &Path,
+    ) -> Option<(PathBuf, String, Vec<Hunk>)> {
+        if !matches!(
+            tool,
+            Tools::ForgeToolFsCreate(_) | Tools::ForgeToolFsPatch(_) | Tools::ForgeToolFsAstEdit(_)
+        ) {
+            return None;
+        }
+        let preview = self
+            .services
+            .preview(tool.clone(), cwd.to_path_buf())
+            .await
+            .ok()?;
+        let [file] = <[_; 1]>::try_from(preview.files).ok()?;
+        let before = file.before.unwrap_or_default();
+        let hunks = Hunk::split(&before, &file.after?);
+        (!hunks.is_empty()).then_some((file.path, before, hunks))
+    }
+
+    async fn send_permissions_update(
+        &self,
+        policy_path: &Path,
+        context: &ToolCallContext,
+    ) -> anyhow::Result<()> {
+        let cwd = self.services.get_environment().cwd;
+        context
+            .send_text(
+                TitleFormat::info("Permissions Update")
+                    .sub_title(format_display_path(policy_path, &cwd)),
+            )
+            .await
+    }
+
+    /// Check if the tool itself is allowed to be called, based on the tool
+    /// rules of the policies. This applies to every tool, including MCP tools,
+    /// plugins and agents.
+    pub async fn check_tool_name(
+        &self,
+        name: &ToolName,
+        context: &mut ToolCallContext,
+    ) -> anyhow::Result<()> {
+        let cwd = self.services.get_environment().cwd;
+        let operation = forge_domain::Operation::Tool {
+            name: name.clone(),
+            cwd,
+            message: format!("Call tool: {name}"),
+        };
+        self.check_permission(Some(operation), context).await
+    }
+
+    /// A page navigating to another URL, by a redirect, a click or a script,
+    /// has to pass the same fetch policies as opening it directly. The page is
+    /// closed when the navigation isn't allowed, so that its content can't be
+    /// read later.
+    async fn check_navigation(
+        &self,
+        before: Option<&str>,
+        after: &str,
+        context: &mut ToolCallContext,
+    ) -> anyhow::Result<()> {
+        // Moving within the page doesn't load anything new
+        let without_fragment = |url: &str| {
+            Url::parse(url).ok().map(|mut url| {
+                url.set_fragment(None);
+                url
+            })
+        };
+        if before.is_some_and(|before| {
+            without_fragment(before).is_some_and(|before| Some(before) == without_fragment(after))
+        }) {
+            return Ok(());
+        }
+
+        let operation = forge_domain::Operation::Fetch {
+            url: after.to_string(),
+            cwd: self.services.get_environment().cwd,
+            message: format!("Open URL in browser: {after}"),
+        };
+        if let Err(error) = self.check_permission(Some(operation), context).await {
+            self.services.close().await?;
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// Records the content of the files as the agent sees them after reading
+    /// or writing them.
+    async fn observe(
+        &self,
+        paths: &[PathBuf],
+        context: &mut ToolCallContext,
+    ) -> anyhow::Result<()> {
+        for path in paths {
+            match self.services.current(path).await? {
+                Some(content) => context.file_versions.observe(path, &content),
+                None => context.file_versions.forget(path),
             }
-            if !decision.allowed {
-                return Err(anyhow::anyhow!("Operation denied by policy or user."));
+        }
+        Ok(())
+    }
+
+    /// Refuses to write files that were changed by someone else since the
+    /// agent last saw them, and records their original content in the batch
+    /// transaction.
+    async fn begin_write(
+        &self,
+        paths: &[PathBuf],
+        context: &mut ToolCallContext,
+    ) -> anyhow::Result<()> {
+        let cwd = self.services.get_environment().cwd;
+        let mut originals = Vec::new();
+        let mut conflicts = Vec::new();
+        for path in paths {
+            let current = self.services.current(path).await?;
+            if context.file_versions.is_stale(path, current.as_deref()) {
+                conflicts.push(format_display_path(path, &cwd));
             }
+            originals.push((path.clone(), current));
+        }
+        if !conflicts.is_empty() {
+            bail!(
+                "The following files were modified since they were last read, read them again before changing them: {}",
+                conflicts.join(", ")
+            );
+        }
+
+        for (path, original) in originals {
+            context.transaction.record(path, original);
         }
         Ok(())
     }
 
-    async fn dump_operation(&self, operation: &Operation) -> anyhow::Result<TempContentFiles> {
+    /// Restores a file to its original content. Returns false when the file
+    /// was left untouched.
+    async fn restore_original(
+        &self,
+        path: &Path,
+        original: Option<Vec<u8>>,
+    ) -> anyhow::Result<bool> {
+        if self.services.current(path).await? == original {
+            return Ok(false);
+        }
+        self.services.restore(path, original).await?;
+        Ok(true)
+    }
+
+    /// Restores every file written by the batch to its original content and
+    /// refuses any further write in the batch, so that a batch of file changes
+    /// is never left half applied.
+    async fn rollback(&self, error: anyhow::Error, context: &mut ToolCallContext) -> anyhow::Error {
+        let cwd = self.services.get_environment().cwd;
+        let mut restored = Vec::new();
+        let mut failed = Vec::new();
+        for (path, original) in context.transaction.abort() {
+            match self.restore_original(&path, original.clone()).await {
+                Ok(true) => restored.push(format_display_path(&path, &cwd)),
+                Ok(false) => {}
+                Err(restore_error) => {
+                    tracing::error!(error = ?restore_error, path = %path.display(), "Failed to roll back file");
+                    failed.push(format_display_path(&path, &cwd));
+                    continue;
+                }
+            }
+            match &original {
+                Some(content) => context.file_versions.observe(&path, content),
+                None => context.file_versions.forget(&path),
+            }
+        }
+
+        if !failed.is_empty() {
+            error.context(format!(
+                "Rolling back the file changes of this batch failed for: {}. Check these files before making more changes.",
+                failed.join(", ")
+            ))
+        } else if !restored.is_empty() {
+            error.context(format!(
+                "All the file changes of this batch were rolled back: {}",
+                restored.join(", ")
+            ))
+        } else {
+            error
+        }
+    }
+
+    /// Runs a tool call that writes files as part of the batch transaction.
+    /// When it fails or isn't allowed, all the files written by the batch are
+    /// rolled back.
+    async fn call_transactional(
+        &self,
+        input: Tools,
+        paths: &[PathBuf],
+        context: &mut ToolCallContext,
+    ) -> anyhow::Result<Operation> {
+        if context.transaction.is_aborted() {
+            bail!(
+                "Skipped because an earlier file change of this batch failed and all the file changes of the batch were rolled back. Make the changes again once the failure is fixed."
+            );
+        }
+
+        let result = async {
+            let reviewed = self.check_tool_permission(&input, context).await?;
+            self.begin_write(paths, context).await?;
+            match reviewed {
+                Some(change) => self.write_reviewed(change, context).await,
+                None => self.call_internal(input, context).await,
+            }
+        }
+        .await;
+        match result {
+            Ok(operation) => {
+                self.observe(paths, context).await?;
+                Ok(operation)
+            }
+            Err(error) => Err(self.rollback(error, context).await),
+        }
+    }
+
+    /// Writes the hunks of a change the user accepted, letting the agent know
+    /// that the others were left out
+    async fn write_reviewed(
+        &self,
+        change: ReviewedChange,
+        context: &mut ToolCallContext,
+    ) -> anyhow::Result<Operation> {
+        let path = change.path.display().to_string();
+        context.user_messages.push(format!(
+            "I rejected {} of the {} hunks of your change to {path}, only the other hunks were written.",
+            change.rejected, change.hunks
+        ));
+        let input = FSWrite {
+            path,
+            content: change.content,
+            overwrite: true,
+            explanation: None,
+        };
+        self.call_internal(Tools::ForgeToolFsCreate(input), context)
+            .await
+    }
+
+    async fn dump_operation(
+        &self,
+        operation: &Operation,
+        env: &Environment,
+    ) -> anyhow::Result<TempContentFiles> {
         match operation {
-            Operation::NetFetch { input: _, output } => {
+            Operation::NetFetch { input: _, output }
+            | Operation::NetRequest { input: _, output } => {
                 let original_length = output.content.len();
-                let is_truncated =
-                    original_length > self.services.get_environment().fetch_truncation_limit;
+                let is_truncated = original_length > env.fetch_truncation_limit;
                 let mut files = TempContentFiles::default();
 
                 if is_truncated {
@@ -83,8 +654,20 @@ impl<
 
                 Ok(files)
             }
+            Operation::NetBrowser { input: _, output: BrowserOutput::Page(page) } => {
+                let is_truncated = page.content.len() > env.fetch_truncation_limit;
+                let mut files = TempContentFiles::default();
+
+                if is_truncated {
+                    files = files.stdout(
+                        self.create_temp_file("forge_browser_", ".md", &page.content)
+                            .await?,
+                    );
+                }
+
+                Ok(files)
+            }
             Operation::Shell { output } => {
-                let env = self.services.get_environment();
                 let stdout_lines = output.output.stdout.lines().count();
                 let stderr_lines = output.output.stderr.lines().count();
                 let stdout_truncated =
@@ -109,6 +692,25 @@ impl<
 
                 Ok(files)
             }
+            Operation::Python { input: _, output } => {
+                let max_lines = env.stdout_max_prefix_length + env.stdout_max_suffix_length;
+                let mut files = TempContentFiles::default();
+
+                if output.stdout.lines().count() > max_lines {
+                    files = files.stdout(
+                        self.create_temp_file("forge_python_stdout_", ".txt", &output.stdout)
+                            .await?,
+                    );
+                }
+                if output.stderr.lines().count() > max_lines {
+                    files = files.stderr(
+                        self.create_temp_file("forge_python_stderr_", ".txt", &output.stderr)
+                            .await?,
+                    );
+                }
+
+                Ok(files)
+            }
             _ => Ok(TempContentFiles::default()),
         }
     }
@@ -152,6 +754,14 @@ impl<
                         input.end_line.map(|i| i as u64),
                     )
                     .await?;
+                // A whole lockfile, minified or generated file is summarized, its
+                // lines can still be read with a range
+                let output = if input.start_line.is_none() && input.end_line.is_none() {
+                    let cwd = self.services.get_environment().cwd;
+                    summarize(output, Path::new(&input.path), &cwd, &context.generated)
+                } else {
+                    output
+                };
                 (input, output).into()
             }
             Tools::ForgeToolFsCreate(input) => {
@@ -167,16 +777,65 @@ impl<
                 (input, output).into()
             }
             Tools::ForgeToolFsSearch(input) => {
+                let filter = context.workflow.walker.clone().unwrap_or_default();
                 let output = self
                     .services
                     .search(
                         input.path.clone(),
                         input.regex.clone(),
                         input.file_pattern.clone(),
+                        input.context_lines.unwrap_or_default() as usize,
+                        &filter,
+                    )
+                    .await?;
+                (input, output).into()
+            }
+            Tools::ForgeToolFsSemanticSearch(input) => {
+                let retrieval = self
+                    .services
+                    .read_merged(None)
+                    .await?
+                    .retrieval
+                    .ok_or(Error::RetrievalNotConfigured)?;
+                let config = self.services.read_app_config().await.unwrap_or_default();
+                let provider = self.services.get_provider(config).await?;
+                let filter = context.workflow.walker.clone().unwrap_or_default();
+                let output = self
+                    .services
+                    .semantic_search(
+                        provider,
+                        retrieval.model,
+                        input.query.clone(),
+                        input.max_results.unwrap_or(DEFAULT_SEMANTIC_SEARCH_RESULTS) as usize,
+                        &filter,
+                        &context.generated,
                     )
                     .await?;
                 (input, output).into()
             }
+            Tools::ForgeToolFsOutline(input) => {
+                let output = self
+                    .services
+                    .outline_file(PathBuf::from(&input.path))
+                    .await?;
+                (input, output).into()
+            }
+            Tools::ForgeToolFsFindSymbol(input) => {
+                let filter = context.workflow.walker.clone().unwrap_or_default();
+                let output = self
+                    .services
+                    .find_symbol(input.symbol.clone(), &filter, &context.generated)
+                    .await?;
+                (input, output).into()
+            }
+            Tools::ForgeToolFsStats(input) => {
+                let filter = context.workflow.walker.clone().unwrap_or_default();
+                let output = self
+                    .services
+                    .workspace_stats(&filter, &context.generated, STATS_MAX_FILES)
+                    .await?;
+                (input, output).into()
+            }
             Tools::ForgeToolFsRemove(input) => {
                 let _output = self.services.remove(input.path.clone()).await?;
                 input.into()
@@ -193,21 +852,205 @@ impl<
                     .await?;
                 (input, output).into()
             }
+            Tools::ForgeToolFsAstEdit(input) => {
+                let output = self
+                    .services
+                    .ast_edit(
+                        input.path.clone(),
+                        input.symbol.clone(),
+                        input.operation.clone(),
+                        input.content.clone(),
+                    )
+                    .await?;
+                (input, output).into()
+            }
+            Tools::ForgeToolFsApplyPatch(input) => {
+                let cwd = self.services.get_environment().cwd;
+                for diff in parse_unified_diff(&input.patch)? {
+                    let path = cwd.join(diff.path());
+                    let operation = forge_domain::Operation::Write {
+                        message: format!(
+                            "Apply patch to file: `{}`",
+                            format_display_path(&path, &cwd)
+                        ),
+                        path,
+                        cwd: cwd.clone(),
+                    };
+                    self.check_permission(Some(operation), context).await?;
+                }
+
+                let output = self.services.apply_patch(cwd, input.patch.clone()).await?;
+                (input, output).into()
+            }
             Tools::ForgeToolFsUndo(input) => {
                 let output = self.services.undo(input.path.clone()).await?;
                 (input, output).into()
             }
+            Tools::ForgeToolLspFindReferences(input) => {
+                let output = self
+                    .services
+                    .find_references(
+                        self.services.get_environment().cwd,
+                        PathBuf::from(&input.path),
+                        input.line,
+                        input.symbol.clone(),
+                    )
+                    .await?;
+                (input, output).into()
+            }
+            Tools::ForgeToolLspRenameSymbol(input) => {
+                let cwd = self.services.get_environment().cwd;
+                let output = self
+                    .services
+                    .rename_symbol(
+                        cwd.clone(),
+                        PathBuf::from(&input.path),
+                        input.line,
+                        input.symbol.clone(),
+                        input.new_name.clone(),
+                    )
+                    .await?;
+
+                let paths: Vec<_> = output.files.iter().map(|file| file.path.clone()).collect();
+                for path in &paths {
+                    let operation = forge_domain::Operation::Write {
+                        message: format!(
+                            "Rename '{}' to '{}' in file: `{}`",
+                            input.symbol,
+                            input.new_name,
+                            format_display_path(path, &cwd)
+                        ),
+                        path: path.clone(),
+                        cwd: cwd.clone(),
+                    };
+                    self.check_permission(Some(operation), context).await?;
+                }
+                self.begin_write(&paths, context).await?;
+                for file in &output.files {
+                    self.services
+                        .create(
+                            file.path.display().to_string(),
+                            file.after.clone().unwrap_or_default(),
+                            true,
+                            true,
+                        )
+                        .await?;
+                }
+                self.observe(&paths, context).await?;
+                (input, output).into()
+            }
             Tools::ForgeToolProcessShell(input) => {
+                let retry = input
+                    .max_attempts
+                    .map(|max_attempts| RetryPolicy::default().max_attempts(max_attempts));
+                self.run_command(input, retry, context).await?.into()
+            }
+            Tools::ForgeToolProcessDocker(input) => {
+                let env = self.services.get_environment();
+                let command = input.to_command(ShellKind::from_program(&env.shell))?;
+                self.run_command(Shell { command, ..Default::default() }, None, context)
+                    .await?
+                    .into()
+            }
+            Tools::ForgeToolProcessBackground(input) => {
+                let output = match input.action {
+                    BackgroundAction::Start => {
+                        let command = input
+                            .command
+                            .clone()
+                            .context("The 'start' action requires a command")?;
+                        let cwd = input.cwd.clone().unwrap_or_else(|| self.cwd(context));
+                        let settings = self.command_settings(context);
+                        ProcessOutput::Started(
+                            self.services.start_process(command, cwd, &settings).await?,
+                        )
+                    }
+                    BackgroundAction::Logs => ProcessOutput::Logs(
+                        self.services
+                            .process_logs(input.process_id()?, input.tail())
+                            .await?,
+                    ),
+                    BackgroundAction::Stop => ProcessOutput::Stopped(
+                        self.services.stop_process(input.process_id()?).await?,
+                    ),
+                    BackgroundAction::List => {
+                        ProcessOutput::List(self.services.list_processes().await?)
+                    }
+                };
+                (input, output).into()
+            }
+            Tools::ForgeToolProcessPython(input) => {
+                let cwd = self.cwd(context);
                 let output = self
                     .services
-                    .execute(input.command.clone(), input.cwd.clone(), input.keep_ansi)
+                    .run_python(
+                        context.conversation_id,
+                        input.code.clone(),
+                        cwd,
+                        input.timeout(),
+                        input.restart.unwrap_or_default(),
+                    )
                     .await?;
-                output.into()
+                (input, output).into()
             }
             Tools::ForgeToolNetFetch(input) => {
                 let output = self.services.fetch(input.url.clone(), input.raw).await?;
                 (input, output).into()
             }
+            Tools::ForgeToolNetRequest(input) => {
+                let output = self
+                    .services
+                    .request(
+                        input.method.clone(),
+                        input.url.clone(),
+                        input.headers.clone().unwrap_or_default(),
+                        input.body.clone(),
+                    )
+                    .await?;
+                (input, output).into()
+            }
+            Tools::ForgeToolNetBrowser(input) => {
+                // The page can navigate on its own, through redirects or
+                // scripts, so the URL it ends up on is checked after every
+                // action against the one the agent last saw
+                let seen = self.services.seen_url().await?;
+                let output = match input.action {
+                    BrowserAction::Open => {
+                        let url = input
+                            .url
+                            .clone()
+                            .context("The 'open' action requires a url")?;
+                        let page = self.services.open(url.clone()).await?;
+                        self.check_navigation(Some(&url), &page.url, context)
+                            .await?;
+                        BrowserOutput::Page(page)
+                    }
+                    BrowserAction::Text => {
+                        let page = self.services.text().await?;
+                        self.check_navigation(seen.as_deref(), &page.url, context)
+                            .await?;
+                        BrowserOutput::Page(page)
+                    }
+                    BrowserAction::Click => {
+                        let selector = input
+                            .selector
+                            .clone()
+                            .context("The 'click' action requires a selector")?;
+                        let page = self.services.click(selector).await?;
+                        self.check_navigation(seen.as_deref(), &page.url, context)
+                            .await?;
+                        BrowserOutput::Page(page)
+                    }
+                    BrowserAction::Screenshot => {
+                        if let Some(current) = self.services.current_url().await? {
+                            self.check_navigation(seen.as_deref(), &current, context)
+                                .await?;
+                        }
+                        BrowserOutput::Screenshot(self.services.screenshot().await?)
+                    }
+                };
+                (input, output).into()
+            }
             Tools::ForgeToolFollowup(input) => {
                 let output = self
                     .services
@@ -227,40 +1070,82 @@ impl<
                     .await?;
                 output.into()
             }
+            Tools::ForgeToolAskUser(input) => {
+                let answer = context
+                    .ask(
+                        &input.question,
+                        input.options.clone(),
+                        input.multiple.unwrap_or_default(),
+                    )
+                    .await?;
+                context.user_messages.extend(answer.clone());
+                Operation::AskUser { answer }
+            }
             Tools::ForgeToolAttemptCompletion(_input) => {
                 crate::operation::Operation::AttemptCompletion
             }
             Tools::ForgeToolTaskListAppend(input) => {
-                let before = context.tasks.clone();
-                context.tasks.append(&input.task);
-                Operation::TaskListAppend { _input: input, before, after: context.tasks.clone() }
+                let (before, after) = self
+                    .update_tasks(context, |tasks| {
+                        tasks.append(&input.task);
+                        Ok(())
+                    })
+                    .await?;
+                Operation::TaskListAppend { _input: input, before, after }
             }
             Tools::ForgeToolTaskListAppendMultiple(input) => {
-                let before = context.tasks.clone();
-                context.tasks.append_multiple(input.tasks.clone());
-                Operation::TaskListAppendMultiple {
-                    _input: input,
-                    before,
-                    after: context.tasks.clone(),
-                }
+                let (before, after) = self
+                    .update_tasks(context, |tasks| {
+                        tasks.append_multiple(input.tasks.clone());
+                        Ok(())
+                    })
+                    .await?;
+                Operation::TaskListAppendMultiple { _input: input, before, after }
             }
             Tools::ForgeToolTaskListUpdate(input) => {
-                let before = context.tasks.clone();
-                context
-                    .tasks
-                    .update_status(input.task_id, input.status.clone())
-                    .context("Task not found")?;
-                Operation::TaskListUpdate { _input: input, before, after: context.tasks.clone() }
+                let (before, after) = self
+                    .update_tasks(context, |tasks| {
+                        tasks
+                            .update_status(input.task_id, input.status.clone())
+                            .context("Task not found")?;
+                        Ok(())
+                    })
+                    .await?;
+                Operation::TaskListUpdate { _input: input, before, after }
+            }
+            Tools::ForgeToolTaskListComplete(input) => {
+                let (before, after) = self
+                    .update_tasks(context, |tasks| {
+                        tasks.mark_done(input.task_id).context("Task not found")?;
+                        Ok(())
+                    })
+                    .await?;
+                Operation::TaskListComplete { _input: input, before, after }
+            }
+            Tools::ForgeToolTaskListReprioritize(input) => {
+                let (before, after) = self
+                    .update_tasks(context, |tasks| {
+                        tasks
+                            .reprioritize(input.task_id, input.position)
+                            .context("Task not found")?;
+                        Ok(())
+                    })
+                    .await?;
+                Operation::TaskListReprioritize { _input: input, before, after }
             }
             Tools::ForgeToolTaskListList(input) => {
-                let before = context.tasks.clone();
                 // No operation needed, just return the current state
-                Operation::TaskListList { _input: input, before, after: context.tasks.clone() }
+                let (before, after) = self.update_tasks(context, |_| Ok(())).await?;
+                Operation::TaskListList { _input: input, before, after }
             }
             Tools::ForgeToolTaskListClear(input) => {
-                let before = context.tasks.clone();
-                context.tasks.clear();
-                Operation::TaskListClear { _input: input, before, after: context.tasks.clone() }
+                let (before, after) = self
+                    .update_tasks(context, |tasks| {
+                        tasks.clear();
+                        Ok(())
+                    })
+                    .await?;
+                Operation::TaskListClear { _input: input, before, after }
             }
             Tools::ForgeToolPlanCreate(input) => {
                 let output = self
@@ -273,6 +1158,65 @@ impl<
                     .await?;
                 (input, output).into()
             }
+            Tools::ForgeToolMemoryRemember(input) => {
+                let project = self
+                    .services
+                    .memory_project(&self.services.get_environment().cwd)
+                    .await;
+                let fact = input.fact.clone();
+                let output = self
+                    .services
+                    .update_memories(Box::new(move |memories| {
+                        memories.remember(&project, fact, chrono::Utc::now())
+                    }))
+                    .await?;
+                Operation::MemoryRemember { input, output }
+            }
+            Tools::ForgeToolMemoryRecall(input) => {
+                let project = self
+                    .services
+                    .memory_project(&self.services.get_environment().cwd)
+                    .await;
+                let output = self.services.load_memories().await?.recall(
+                    &project,
+                    &input.query,
+                    input.max_results.unwrap_or(DEFAULT_MEMORY_RECALL_RESULTS) as usize,
+                );
+                Operation::MemoryRecall { input, output }
+            }
+            Tools::ForgeToolMessageSend(input) => {
+                let from = context.agent_id.clone().unwrap_or_default();
+                let conversation = context
+                    .root_conversation_id
+                    .context("Messages can only be sent within a conversation")?;
+                let message =
+                    AgentMessage::new(from, AgentId::new(&input.to), input.subject, input.body);
+                self.services
+                    .post_message(&conversation, message.clone())
+                    .await?;
+                Operation::MessageSend { message }
+            }
+            Tools::ForgeToolMessageReceive(input) => {
+                let agent = context.agent_id.clone().unwrap_or_default();
+                let conversation = context
+                    .root_conversation_id
+                    .context("Messages can only be received within a conversation")?;
+                let messages = self
+                    .services
+                    .take_messages(
+                        &conversation,
+                        &agent,
+                        input.wait_seconds.map(Duration::from_secs),
+                    )
+                    .await?;
+                Operation::MessageReceive { messages }
+            }
+            // The tool registry hands sub-agents to the agent executor
+            Tools::ForgeToolSpawnAgent(_) | Tools::ForgeToolFanOut(_) => {
+                bail!("Sub-agents are run by the agent executor")
+            }
+            // The tool registry validates handoffs, which the orchestrator then runs
+            Tools::ForgeToolHandoff(_) => bail!("Handoffs are run by the orchestrator"),
         })
     }
 
@@ -288,10 +1232,26 @@ impl<
             context.send(content).await?;
         }
 
-        // Check permissions before executing the tool
-        self.check_tool_permission(&tool_input, context).await?;
-
-        let execution_result = self.call_internal(tool_input.clone(), context).await;
+        // Writes are checked as part of the transaction, so that a denied write
+        // rolls back the batch too
+        let execution_result = match written_paths(&tool_input, &env.cwd) {
+            // Nothing is changed in dry-run mode, so there is nothing to check
+            _ if context.dry_run && has_side_effects(&tool_input, &env.cwd) => {
+                self.dry_run(tool_input.clone(), &env).await
+            }
+            Some(written) => {
+                self.call_transactional(tool_input.clone(), &written, context)
+                    .await
+            }
+            None => {
+                // Check permissions before executing the tool
+                self.check_tool_permission(&tool_input, context).await?;
+                self.call_internal(tool_input.clone(), context).await
+            }
+        };
+        if let (Tools::ForgeToolFsRead(input), Ok(_)) = (&tool_input, &execution_result) {
+            self.observe(&[PathBuf::from(&input.path)], context).await?;
+        }
 
         if let Err(ref error) = execution_result {
             tracing::error!(error = ?error, "Tool execution failed");
@@ -304,8 +1264,254 @@ impl<
             context.send(output).await?;
         }
 
-        let truncation_path = self.dump_operation(&operation).await?;
+        self.tool_output(tool_name, operation, env, context).await
+    }
+
+    /// Previews what a call would do instead of making it
+    async fn dry_run(&self, input: Tools, env: &Environment) -> anyhow::Result<Operation> {
+        let output = match input {
+            Tools::ForgeToolProcessShell(input) => DryRunOutput::Command(input.command),
+            Tools::ForgeToolProcessPython(input) => DryRunOutput::Python(input.code),
+            Tools::ForgeToolProcessBackground(Background { command: Some(command), .. }) => {
+                DryRunOutput::Command(command)
+            }
+            // The snapshot to restore and the edits of the language server are
+            // only known once the call is made
+            Tools::ForgeToolFsUndo(_) | Tools::ForgeToolLspRenameSymbol(_) => DryRunOutput::Skipped,
+            input => DryRunOutput::Changes(self.services.preview(input, env.cwd.clone()).await?),
+        };
+        Ok(Operation::DryRun { output })
+    }
+
+    /// Converts an operation into the output of the tool. Secrets are masked
+    /// before the output is truncated, so that they aren't saved to the full
+    /// output either. Output limits configured for the tool in forge.yaml take
+    /// precedence over the ones of the environment.
+    async fn tool_output(
+        &self,
+        tool_name: ToolName,
+        operation: Operation,
+        env: Environment,
+        context: &ToolCallContext,
+    ) -> anyhow::Result<ToolOutput> {
+        let operation = operation.redact(&context.redactor);
+        let truncation = context.workflow.truncation_for(&tool_name);
+        let env = truncation.apply(env);
+        let truncation_path = if truncation.saves_full_output() {
+            self.dump_operation(&operation, &env).await?
+        } else {
+            TempContentFiles::default()
+        };
 
         Ok(operation.into_tool_output(tool_name, truncation_path, &env))
     }
+
+    /// Returns the variables the shell commands run with, the commands the
+    /// agents may run and the ones retried, as configured in forge.yaml
+    fn command_settings(&self, context: &ToolCallContext) -> CommandSettings {
+        let workflow = &context.workflow;
+        CommandSettings {
+            env: workflow.env.clone().unwrap_or_default(),
+            policy: workflow.command_policy.clone().unwrap_or_default(),
+            retries: workflow.command_retries.clone(),
+            retry: None,
+        }
+    }
+
+    /// Returns the directory the commands not given one run in, which the
+    /// shell commands may have moved to with `cd`. The workspace is used again
+    /// once that directory is removed.
+    pub(crate) fn cwd(&self, context: &ToolCallContext) -> PathBuf {
+        context
+            .cwd
+            .clone()
+            .filter(|cwd| cwd.is_dir())
+            .unwrap_or_else(|| self.services.get_environment().cwd)
+    }
+
+    /// Runs a shell command of the agent. The output is sent to the frontends
+    /// with its colors and returned without them, unless `keep_ansi` is set.
+    /// The command is retried with `retry` if given, or with the retries of
+    /// forge.yaml matching it.
+    async fn run_command(
+        &self,
+        input: Shell,
+        retry: Option<RetryPolicy>,
+        context: &mut ToolCallContext,
+    ) -> anyhow::Result<ShellOutput> {
+        let settings = self.command_settings(context).retry(retry);
+        let cwd = input.cwd.unwrap_or_else(|| self.cwd(context));
+        let mut output = self
+            .services
+            .execute(
+                input.command,
+                cwd,
+                input.shell,
+                true,
+                input.stdin,
+                &settings,
+            )
+            .await?;
+        context
+            .send(ChatResponse::CommandOutput(output.output.clone()))
+            .await?;
+
+        // The next commands start where this one moved to, as in a terminal,
+        // unless it left the workspace. The agent is told where they start.
+        if let Some(cwd) = output.output.cwd.take() {
+            if is_in_workspace(&cwd, &self.services.get_environment().cwd) {
+                context.cwd = Some(cwd);
+            }
+            output.output.cwd = Some(self.cwd(context));
+        }
+
+        if !input.keep_ansi {
+            output.output.stdout = strip_ansi_codes(&output.output.stdout).into_owned();
+            output.output.stderr = strip_ansi_codes(&output.output.stderr).into_owned();
+        }
+        Ok(output)
+    }
+
+    /// Returns the timeout for a call of the given tool. Overrides in
+    /// forge.yaml take precedence over the environment default.
+    pub fn timeout(&self, tool_name: &ToolName, context: &ToolCallContext) -> Duration {
+        let timeout = context
+            .workflow
+            .timeout_for(tool_name)
+            .unwrap_or(self.services.get_environment().tool_timeout);
+        Duration::from_secs(timeout)
+    }
+
+    /// Applies a change to the task list of the workspace, returning the list
+    /// before and after the change
+    async fn update_tasks(
+        &self,
+        context: &mut ToolCallContext,
+        update: impl FnOnce(&mut TaskList) -> anyhow::Result<()>,
+    ) -> anyhow::Result<(TaskList, TaskList)> {
+        let before = self.services.load_tasks().await?;
+        let mut after = before.clone();
+        update(&mut after)?;
+        if after != before {
+            self.services.save_tasks(&after).await?;
+        }
+        context.tasks = after.clone();
+        Ok((before, after))
+    }
+
+    /// Returns the custom tools defined in forge.yaml
+    pub async fn custom_tools(&self) -> anyhow::Result<Vec<CustomTool>> {
+        Ok(self.services.read_merged(None).await?.custom_tools)
+    }
+
+    /// Returns the tools generated from the OpenAPI documents in forge.yaml
+    pub async fn openapi_tools(&self) -> anyhow::Result<Vec<OpenApiTool>> {
+        self.services.openapi_tools().await
+    }
+
+    /// Calls an operation of an OpenAPI document by sending the request built
+    /// from the call arguments, once it is allowed by the request policies.
+    pub async fn execute_openapi(
+        &self,
+        tool: &OpenApiTool,
+        input: ToolCallFull,
+        context: &mut ToolCallContext,
+    ) -> anyhow::Result<ToolOutput> {
+        let request = tool.request(&input.arguments)?;
+        let env = self.services.get_environment();
+        context
+            .send_text(TitleFormat::info("API").sub_title(format!(
+                "{} {}",
+                request.method.as_ref(),
+                request.url
+            )))
+            .await?;
+
+        self.check_permission(
+            Some(forge_domain::Operation::Request {
+                method: request.method.as_ref().to_string(),
+                url: request.url.clone(),
+                cwd: env.cwd.clone(),
+                message: format!(
+                    "{} request to URL: {} ({})",
+                    request.method.as_ref(),
+                    request.url,
+                    input.name
+                ),
+            }),
+            context,
+        )
+        .await?;
+
+        let output = self
+            .services
+            .request(
+                request.method.clone(),
+                request.url.clone(),
+                request.headers.clone(),
+                request.body.clone(),
+            )
+            .await?;
+        let input_request = NetRequest {
+            method: request.method,
+            url: request.url,
+            headers: Some(request.headers),
+            body: request.body,
+            explanation: None,
+        };
+        let operation = Operation::NetRequest { input: input_request, output };
+
+        if let Some(output) = operation.to_content(&env) {
+            context.send(output).await?;
+        }
+
+        self.tool_output(input.name, operation, env, context).await
+    }
+
+    /// Runs a custom tool by rendering its command template with the call
+    /// arguments and executing it in the shell.
+    pub async fn execute_custom(
+        &self,
+        tool: &CustomTool,
+        input: ToolCallFull,
+        context: &mut ToolCallContext,
+    ) -> anyhow::Result<ToolOutput> {
+        let env = self.services.get_environment();
+        let command = tool.render(&input.arguments, ShellKind::from_program(&env.shell))?;
+        context
+            .send_text(TitleFormat::info("Tool").sub_title(input.name.as_str()))
+            .await?;
+
+        let operation = if context.dry_run {
+            Operation::DryRun { output: DryRunOutput::Command(command) }
+        } else {
+            self.check_permission(
+                Some(forge_domain::Operation::Execute {
+                    command: command.clone(),
+                    cwd: self.cwd(context),
+                    message: format!("Execute custom tool {}: {command}", input.name),
+                }),
+                context,
+            )
+            .await?;
+
+            let input = Shell { command, shell: tool.shell, ..Default::default() };
+            self.run_command(input, tool.retry.clone(), context)
+                .await?
+                .into()
+        };
+
+        if let Some(output) = operation.to_content(&env) {
+            context.send(output).await?;
+        }
+
+        self.tool_output(input.name, operation, env, context).await
+    }
+}
+
+/// Whether the directory is the workspace or one of its subdirectories, once
+/// their symbolic links are resolved
+fn is_in_workspace(dir: &Path, workspace: &Path) -> bool {
+    let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    canonical(dir).starts_with(canonical(workspace))
 }
@@ -1,26 +1,31 @@
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use anyhow::Context;
 use console::style;
 use forge_domain::{
-    Agent, AgentInput, ChatResponse, ToolCallContext, ToolCallFull, ToolDefinition, ToolName,
-    ToolOutput, ToolResult, Tools, ToolsDiscriminants,
+    Agent, AgentInput, ChatResponse, CommandViolation, GuardrailViolation, HookEvent, HookPayload,
+    ToolCallContext, ToolCallFull, ToolCallRecord, ToolDefinition, ToolName, ToolOutput,
+    ToolResult, Tools, ToolsDiscriminants,
 };
+use forge_template::Element;
 use strum::IntoEnumIterator;
 use tokio::time::timeout;
 
 use crate::agent_executor::AgentExecutor;
 use crate::error::Error;
+use crate::hook_runner::HookRunner;
 use crate::mcp_executor::McpExecutor;
+use crate::plugin_executor::PluginExecutor;
 use crate::tool_executor::ToolExecutor;
-use crate::{EnvironmentService, McpService, Services};
+use crate::{McpService, Services, ToolAuditService};
 
 pub struct ToolRegistry<S> {
     tool_executor: ToolExecutor<S>,
     agent_executor: AgentExecutor<S>,
     mcp_executor: McpExecutor<S>,
-    tool_timeout: Duration,
+    plugin_executor: PluginExecutor<S>,
+    hook_runner: HookRunner<S>,
+    services: Arc<S>,
 }
 
 impl<S: Services> ToolRegistry<S> {
@@ -29,25 +34,29 @@ impl<S: Services> ToolRegistry<S> {
             tool_executor: ToolExecutor::new(services.clone()),
             agent_executor: AgentExecutor::new(services.clone()),
             mcp_executor: McpExecutor::new(services.clone()),
-            tool_timeout: Duration::from_secs(services.get_environment().tool_timeout),
+            plugin_executor: PluginExecutor::new(services.clone()),
+            hook_runner: HookRunner::new(services.clone()),
+            services,
         }
     }
 
     async fn call_with_timeout<F, Fut>(
         &self,
         tool_name: &ToolName,
+        duration: Duration,
         future: F,
     ) -> anyhow::Result<ToolOutput>
     where
         F: FnOnce() -> Fut,
         Fut: std::future::Future<Output = anyhow::Result<ToolOutput>>,
     {
-        timeout(self.tool_timeout, future())
-            .await
-            .context(Error::CallTimeout {
-                timeout: self.tool_timeout.as_secs() / 60,
-                tool_name: tool_name.clone(),
-            })?
+        match timeout(duration, future()).await {
+            Ok(output) => output,
+            Err(_) => {
+                tracing::warn!(tool_name = %tool_name, timeout = ?duration, "Tool call timed out");
+                Ok(ToolOutput::timeout(tool_name, duration))
+            }
+        }
     }
 
     async fn call_inner(
@@ -56,15 +65,151 @@ impl<S: Services> ToolRegistry<S> {
         input: ToolCallFull,
         context: &mut ToolCallContext,
     ) -> anyhow::Result<ToolOutput> {
-        Self::validate_tool_call(agent, &input.name)?;
+        if context.plan_mode {
+            Self::validate_planning_tool_call(agent, &input.name)?;
+        } else {
+            Self::validate_tool_call(agent, &input.name)?;
+        }
+        self.tool_executor
+            .check_tool_name(&input.name, context)
+            .await?;
+        // The tools that aren't built in don't say which files they access, so
+        // their arguments are checked for paths instead
+        if !Tools::contains(&input.name)
+            && let Some(violation) = context
+                .guard
+                .check_arguments(&input.arguments, &self.tool_executor.cwd(context))
+        {
+            return Ok(violation.to_tool_output());
+        }
+
+        let mut payload = HookPayload::new(HookEvent::PreToolCall)
+            .agent(agent.id.clone())
+            .tool(input.name.clone())
+            .arguments(input.arguments.clone());
+        if let Some(conversation_id) = context.conversation_id {
+            payload = payload.conversation_id(conversation_id);
+        }
+        let pre = self.hook_runner.run(&context.workflow, &payload).await;
+        if let Some(reason) = pre.block {
+            return Err(Error::BlockedByHook(reason).into());
+        }
+
+        // A call going against the guardrails or the command policy is refused
+        // with the reason instead of failing
+        let mut output = match self.execute_tool(agent, input, context).await {
+            Ok(output) => output,
+            Err(error) => match error.downcast::<GuardrailViolation>() {
+                Ok(violation) => return Ok(violation.to_tool_output()),
+                Err(error) => match error.downcast::<CommandViolation>() {
+                    Ok(violation) => return Ok(violation.to_tool_output()),
+                    Err(error) => return Err(error),
+                },
+            },
+        };
+
+        let text = output
+            .values
+            .iter()
+            .filter_map(|value| value.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        if let Some(violation) = context.guard.check_content(&text) {
+            return Ok(violation.to_tool_output());
+        }
+        let post = self
+            .hook_runner
+            .run(
+                &context.workflow,
+                &payload.event(HookEvent::PostToolCall).output(text),
+            )
+            .await;
+        if let Some(reason) = post.block {
+            output = output
+                .is_error(true)
+                .combine(ToolOutput::text(Element::new("hook_error").cdata(reason)));
+        }
+        for message in pre.messages.into_iter().chain(post.messages) {
+            output.combine_mut(ToolOutput::text(
+                Element::new("hook_message").cdata(message),
+            ));
+        }
 
+        Ok(output)
+    }
+
+    async fn execute_tool(
+        &self,
+        agent: &Agent,
+        input: ToolCallFull,
+        context: &mut ToolCallContext,
+    ) -> anyhow::Result<ToolOutput> {
         tracing::info!(tool_name = %input.name, arguments = %input.arguments, "Executing tool call");
         let tool_name = input.name.clone();
+        let duration = self.tool_executor.timeout(&tool_name, context);
 
         // First, try to call a Forge tool
-        if Tools::contains(&input.name) {
-            self.call_with_timeout(&tool_name, || self.tool_executor.execute(input, context))
+        // NOTE: Questions wait for the user, so they should not timeout
+        if input.name == ToolsDiscriminants::ForgeToolAskUser.name() {
+            self.tool_executor.execute(input, context).await
+        } else if input.name == ToolsDiscriminants::ForgeToolSpawnAgent.name()
+            && let Ok(Tools::ForgeToolSpawnAgent(spawn)) = Tools::try_from(input.clone())
+        {
+            let available = self.list().await?.into_iter().map(|tool| tool.name);
+            let sub_agent = agent.sub_agent(available, &spawn.tools);
+            // NOTE: Sub-agents should not timeout, their budget bounds them
+            self.agent_executor.spawn(sub_agent, spawn, context).await
+        } else if input.name == ToolsDiscriminants::ForgeToolFanOut.name()
+            && let Ok(Tools::ForgeToolFanOut(fan_out)) = Tools::try_from(input.clone())
+        {
+            let available = self.list().await?.into_iter().map(|tool| tool.name);
+            let sub_agent = agent.sub_agent(available, &fan_out.tools);
+            // NOTE: Sub-agents should not timeout, their budget bounds them
+            self.agent_executor
+                .fan_out(sub_agent, fan_out, context)
                 .await
+        } else if input.name == ToolsDiscriminants::ForgeToolHandoff.name()
+            && let Ok(Tools::ForgeToolHandoff(handoff)) = Tools::try_from(input.clone())
+        {
+            // The orchestrator hands the conversation off once the agent stops
+            Self::validate_handoff(agent, &handoff.agent)?;
+            Ok(ToolOutput::text(
+                Element::new("handoff")
+                    .attr("agent", &handoff.agent)
+                    .text("The conversation is handed off, don't do anything else."),
+            ))
+        } else if Tools::contains(&input.name) {
+            self.call_with_timeout(&tool_name, duration, || {
+                self.tool_executor.execute(input, context)
+            })
+            .await
+        } else if let Some(tool) = self
+            .tool_executor
+            .custom_tools()
+            .await?
+            .into_iter()
+            .find(|tool| tool.name == input.name)
+        {
+            self.call_with_timeout(&tool_name, duration, || {
+                self.tool_executor.execute_custom(&tool, input, context)
+            })
+            .await
+        } else if let Some(tool) = self
+            .tool_executor
+            .openapi_tools()
+            .await?
+            .into_iter()
+            .find(|tool| tool.name == input.name)
+        {
+            self.call_with_timeout(&tool_name, duration, || {
+                self.tool_executor.execute_openapi(&tool, input, context)
+            })
+            .await
+        } else if self.plugin_executor.contains_tool(&input.name).await? {
+            self.call_with_timeout(&tool_name, duration, || {
+                self.plugin_executor.execute(input, context)
+            })
+            .await
         } else if self.agent_executor.contains_tool(&input.name).await? {
             // Handle agent delegation tool calls
             let agent_input = AgentInput::try_from(&input)?;
@@ -72,9 +217,11 @@ impl<S: Services> ToolRegistry<S> {
             self.agent_executor
                 .execute(input.name.to_string(), agent_input.task, context)
                 .await
-        } else if self.mcp_executor.contains_tool(&input.name).await? {
+        } else if self.mcp_executor.contains_tool(agent, &input.name).await? {
             let output = self
-                .call_with_timeout(&tool_name, || self.mcp_executor.execute(input, context))
+                .call_with_timeout(&tool_name, duration, || {
+                    self.mcp_executor.execute(input, context)
+                })
                 .await?;
             let text = output
                 .values
@@ -105,17 +252,54 @@ impl<S: Services> ToolRegistry<S> {
     ) -> ToolResult {
         let call_id = call.call_id.clone();
         let tool_name = call.name.clone();
-        let output = self.call_inner(agent, call, context).await;
+        let timestamp = chrono::Utc::now();
+        let started = Instant::now();
+        let output = self.call_inner(agent, call.clone(), context).await;
+        let mut result = ToolResult::new(tool_name).call_id(call_id).output(output);
+        // Every tool goes through here, including the MCP ones and the errors,
+        // so that no output reaches the model with its secrets
+        result.output = context.redactor.redact_output(result.output);
+
+        // Failing to write the audit log shouldn't fail the tool call
+        let record = ToolCallRecord::new(
+            agent.id.clone(),
+            &call,
+            &result.output,
+            timestamp,
+            started.elapsed().as_millis() as u64,
+        )
+        .redact(&context.redactor);
+        if let Err(error) = self.services.record_tool_call(record).await {
+            tracing::warn!(%error, "Failed to record tool call");
+        }
 
-        ToolResult::new(tool_name).call_id(call_id).output(output)
+        result
     }
 
     pub async fn list(&self) -> anyhow::Result<Vec<ToolDefinition>> {
         let mcp_tools = self.mcp_executor.services.list().await?;
+        let plugin_tools = self.plugin_executor.tools().await?;
         let agent_tools = self.agent_executor.tool_agents().await?;
+        let custom_tools = self
+            .tool_executor
+            .custom_tools()
+            .await?
+            .iter()
+            .map(|tool| tool.definition())
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let openapi_tools = self
+            .tool_executor
+            .openapi_tools()
+            .await?
+            .iter()
+            .map(|tool| tool.definition())
+            .collect::<anyhow::Result<Vec<_>>>()?;
 
         let tools = Tools::iter()
             .map(|tool| tool.definition())
+            .chain(custom_tools.into_iter())
+            .chain(openapi_tools.into_iter())
+            .chain(plugin_tools.into_iter())
             .chain(mcp_tools.into_iter())
             .chain(agent_tools.into_iter())
             .collect::<Vec<_>>();
@@ -137,7 +321,7 @@ impl<S> ToolRegistry<S> {
             .map(|tool| tool.as_str())
             .collect();
 
-        if !agent_tools.contains(&tool_name.as_str())
+        if !agent.allows_tool(tool_name)
             && *tool_name != ToolsDiscriminants::ForgeToolAttemptCompletion.name()
         {
             tracing::error!(tool_name = %tool_name, "No tool with name");
@@ -149,6 +333,33 @@ impl<S> ToolRegistry<S> {
         }
         Ok(())
     }
+
+    /// Validates a tool call made in plan mode, where only the tools that read
+    /// the workspace are allowed, and the plan can always be created
+    fn validate_planning_tool_call(agent: &Agent, tool_name: &ToolName) -> Result<(), Error> {
+        if !Tools::is_planning(tool_name) {
+            return Err(Error::NotAllowedInPlanMode(tool_name.clone()));
+        }
+        if *tool_name == ToolsDiscriminants::ForgeToolPlanCreate.name() {
+            return Ok(());
+        }
+        Self::validate_tool_call(agent, tool_name)
+    }
+
+    /// Validates that the agent can hand the conversation off to the target
+    fn validate_handoff(agent: &Agent, target: &str) -> Result<(), Error> {
+        let handoffs = agent.handoffs.iter().flatten();
+        if !handoffs.clone().any(|id| id.as_str() == target) {
+            return Err(Error::HandoffNotAllowed {
+                agent: target.to_string(),
+                supported_agents: handoffs
+                    .map(|id| id.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            });
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -189,6 +400,50 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_excluded_tool_call() {
+        let fixture = Agent::new(AgentId::new("test_agent"))
+            .exclude_tools(vec![ToolName::new("process"), ToolName::new("fs_remove")]);
+
+        let actual = [
+            "forge_tool_fs_read",
+            "forge_tool_fs_remove",
+            "forge_tool_process_shell",
+        ]
+        .map(|name| ToolRegistry::<()>::validate_tool_call(&fixture, &ToolName::new(name)).is_ok());
+
+        let expected = [true, false, false];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_planning_tool_call() {
+        let actual = [
+            "forge_tool_fs_read",
+            "forge_tool_fs_create",
+            "forge_tool_plan_create",
+            "forge_tool_fs_search",
+        ]
+        .map(|name| {
+            ToolRegistry::<()>::validate_planning_tool_call(&agent(), &ToolName::new(name)).is_ok()
+        });
+
+        let expected = [true, false, true, false];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_handoff_err() {
+        let fixture = agent().handoffs(vec![AgentId::new("reviewer"), AgentId::new("tester")]);
+
+        let actual = ToolRegistry::<()>::validate_handoff(&fixture, "docs")
+            .unwrap_err()
+            .to_string();
+
+        let expected = "Agent 'docs' is not available for handoffs. Please try again with one of these agents: [reviewer, tester]";
+        assert_eq!(actual, expected);
+    }
+
     #[tokio::test]
     async fn test_completion_tool_call() {
         let result = ToolRegistry::<()>::validate_tool_call(
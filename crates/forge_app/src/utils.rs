@@ -43,6 +43,15 @@ pub fn format_match(matched: &Match, base_dir: &Path) -> String {
                 line
             )
         }
+        // Same separator as grep and ripgrep use for context lines
+        Some(MatchResult::Context { line_number, line }) => {
+            format!(
+                "{}-{}-{}",
+                format_display_path(Path::new(&matched.path), base_dir),
+                line_number,
+                line
+            )
+        }
         None => format_display_path(Path::new(&matched.path), base_dir),
     }
 }
@@ -1,6 +1,8 @@
 use std::path::PathBuf;
+use std::time::SystemTime;
 
 use derive_setters::Setters;
+use forge_domain::{GeneratedFiles, GeneratedKind, WalkerFilter};
 
 /// Configuration for filesystem walking operations
 #[derive(Debug, Clone, Setters)]
@@ -20,6 +22,19 @@ pub struct Walker {
     pub max_total_size: Option<u64>,
     /// Whether to skip binary files
     pub skip_binary: bool,
+    /// Patterns of the only files to process, like the lines of a `.gitignore`
+    /// (empty for all files)
+    pub include: Vec<String>,
+    /// Patterns of the files and directories to skip, like the lines of a
+    /// `.gitignore`
+    pub exclude: Vec<String>,
+    /// Whether to follow symbolic links
+    pub follow_symlinks: bool,
+    /// Directory the include and exclude patterns are relative to (None for
+    /// the base directory)
+    pub root: Option<PathBuf>,
+    /// Tells the generated files, which are marked
+    pub generated: GeneratedFiles,
 }
 
 impl Walker {
@@ -33,6 +48,11 @@ impl Walker {
             max_files: Some(100),
             max_total_size: Some(10 * 1024 * 1024), // 10MB
             skip_binary: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            follow_symlinks: false,
+            root: None,
+            generated: GeneratedFiles::default(),
         }
     }
 
@@ -46,8 +66,30 @@ impl Walker {
             max_files: None,
             max_total_size: None,
             skip_binary: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            follow_symlinks: false,
+            root: None,
+            generated: GeneratedFiles::default(),
         }
     }
+
+    /// Applies the filter configured in the workflow, its settings overriding
+    /// the limits of the walker
+    pub fn filter(mut self, filter: &WalkerFilter) -> Self {
+        if let Some(depth) = filter.max_depth {
+            self.max_depth = Some(depth);
+        }
+        if let Some(file_size) = filter.max_file_size {
+            self.max_file_size = Some(file_size);
+        }
+        self.include.extend(filter.include.iter().cloned());
+        self.exclude.extend(filter.exclude.iter().cloned());
+        if let Some(follow_symlinks) = filter.follow_symlinks {
+            self.follow_symlinks = follow_symlinks;
+        }
+        self
+    }
 }
 
 impl Default for Walker {
@@ -65,6 +107,11 @@ pub struct WalkedFile {
     pub file_name: Option<String>,
     /// Size in bytes
     pub size: u64,
+    /// Time of the last modification (None when unknown)
+    pub modified: Option<SystemTime>,
+    /// Kind of the generated file, as told by its path (None for the files
+    /// written by hand and the ones whose content tells)
+    pub generated: Option<GeneratedKind>,
 }
 
 impl WalkedFile {
@@ -0,0 +1,37 @@
+use std::sync::LazyLock;
+
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::{LinesWithEndings, as_24_bit_terminal_escaped};
+
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+
+static THEME: LazyLock<Theme> = LazyLock::new(|| {
+    let mut themes = ThemeSet::load_defaults();
+    themes
+        .themes
+        .remove("base16-ocean.dark")
+        .unwrap_or_default()
+});
+
+/// Highlights the code for the terminal, with the syntax of the language
+/// named by the info string of its fence, or as plain text when the language
+/// is missing or unknown
+pub(crate) fn highlight_code(code: &str, language: Option<&str>) -> String {
+    let syntax = language
+        .and_then(|language| SYNTAXES.find_syntax_by_token(language))
+        .unwrap_or_else(|| SYNTAXES.find_syntax_plain_text());
+    let mut highlighter = HighlightLines::new(syntax, &THEME);
+
+    let mut output = String::new();
+    for line in LinesWithEndings::from(code) {
+        match highlighter.highlight_line(line, &SYNTAXES) {
+            Ok(ranges) => output.push_str(&as_24_bit_terminal_escaped(&ranges, false)),
+            Err(_) => output.push_str(line),
+        }
+    }
+    // Reset the colors so they don't run into the text after the block
+    output.push_str("\x1b[0m");
+    output
+}
@@ -1,5 +1,6 @@
 pub mod diff;
 pub mod grep;
+mod highlight;
 pub mod markdown;
 pub mod title;
 
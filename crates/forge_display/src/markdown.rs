@@ -3,6 +3,8 @@ use regex::Regex;
 use termimad::crossterm::style::{Attribute, Color};
 use termimad::{CompoundStyle, LineStyle, MadSkin};
 
+use crate::highlight::highlight_code;
+
 /// MarkdownFormat provides functionality for formatting markdown text for
 /// terminal display.
 #[derive(Clone, Setters, Default)]
@@ -10,6 +12,19 @@ use termimad::{CompoundStyle, LineStyle, MadSkin};
 pub struct MarkdownFormat {
     skin: MadSkin,
     max_consecutive_newlines: usize,
+    /// Highlights the syntax of fenced code blocks instead of rendering them
+    /// as plain text
+    highlight_code: bool,
+}
+
+/// Part of the markdown content, rendered on its own
+#[derive(Debug, PartialEq, Eq)]
+enum Segment {
+    Markdown(String),
+    Code {
+        language: Option<String>,
+        code: String,
+    },
 }
 
 impl MarkdownFormat {
@@ -26,7 +41,7 @@ impl MarkdownFormat {
         strikethrough_style.add_attr(Attribute::Dim);
         skin.strikeout = strikethrough_style;
 
-        Self { skin, max_consecutive_newlines: 2 }
+        Self { skin, max_consecutive_newlines: 2, highlight_code: false }
     }
 
     /// Render the markdown content to a string formatted for terminal display.
@@ -40,11 +55,23 @@ impl MarkdownFormat {
         // Strip excessive newlines before rendering
         let processed_content = self.strip_excessive_newlines(content_string.trim());
 
-        self.skin
-            .term_text(&processed_content)
-            .to_string()
-            .trim()
-            .to_string()
+        if !self.highlight_code {
+            return self.render_markdown(&processed_content);
+        }
+
+        split_code_blocks(&processed_content)
+            .into_iter()
+            .map(|segment| match segment {
+                Segment::Markdown(markdown) => self.render_markdown(&markdown),
+                Segment::Code { language, code } => highlight_code(&code, language.as_deref()),
+            })
+            .filter(|rendered| !rendered.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    fn render_markdown(&self, content: &str) -> String {
+        self.skin.term_text(content).to_string().trim().to_string()
     }
 
     /// Strip excessive consecutive newlines from content
@@ -64,6 +91,63 @@ impl MarkdownFormat {
     }
 }
 
+/// Splits the content into the fenced code blocks and the markdown around
+/// them. A block whose fence is never closed runs to the end of the content.
+fn split_code_blocks(content: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut markdown = String::new();
+    let mut lines = content.lines();
+
+    while let Some(line) = lines.next() {
+        let Some((fence, info)) = opening_fence(line) else {
+            markdown.push_str(line);
+            markdown.push('\n');
+            continue;
+        };
+
+        if !markdown.trim().is_empty() {
+            segments.push(Segment::Markdown(std::mem::take(&mut markdown)));
+        }
+        markdown.clear();
+
+        let mut code = Vec::new();
+        for line in lines.by_ref() {
+            if is_closing_fence(line, fence) {
+                break;
+            }
+            code.push(line);
+        }
+        let language = info.split_whitespace().next().map(str::to_string);
+        segments.push(Segment::Code { language, code: code.join("\n") });
+    }
+
+    if !markdown.trim().is_empty() {
+        segments.push(Segment::Markdown(markdown));
+    }
+    segments
+}
+
+/// Fence opening a code block, with the info string after it
+fn opening_fence(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_start();
+    let marker = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let length = line.chars().take_while(|c| *c == marker).count();
+    if length < 3 {
+        return None;
+    }
+    let (fence, info) = line.split_at(length);
+    // The info string of a backtick fence can't hold backticks
+    if marker == '`' && info.contains('`') {
+        return None;
+    }
+    Some((fence, info.trim()))
+}
+
+fn is_closing_fence(line: &str, fence: &str) -> bool {
+    let line = line.trim();
+    line.starts_with(fence) && line.chars().all(|c| fence.starts_with(c))
+}
+
 #[cfg(test)]
 mod tests {
     use pretty_assertions::assert_eq;
@@ -90,6 +174,35 @@ mod tests {
         assert!(actual.is_empty());
     }
 
+    #[test]
+    fn test_split_code_blocks() {
+        let fixture = "Intro\n\n```rust title\nfn main() {}\n````\n~~~\nplain\n\n```\nOutro";
+        let actual = split_code_blocks(fixture);
+        let expected = vec![
+            Segment::Markdown("Intro\n\n".to_string()),
+            Segment::Code {
+                language: Some("rust".to_string()),
+                code: "fn main() {}".to_string(),
+            },
+            Segment::Code { language: None, code: "plain\n\n```\nOutro".to_string() },
+        ];
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_render_highlighted_code_keeps_its_text() {
+        let fixture = "# Heading\n\n```rust\nlet x = 1;\n```\n\nDone";
+        let markdown = MarkdownFormat::new().highlight_code(true);
+        let actual = markdown.render(fixture);
+
+        assert!(actual.contains("\x1b[38;2;"));
+        let actual = strip_ansi_escapes::strip_str(&actual);
+        let expected = "Heading\n\nlet x = 1;\n\nDone";
+
+        assert_eq!(actual.trim(), expected);
+    }
+
     #[test]
     fn test_strip_excessive_newlines_default() {
         let fixture = "Line 1\n\n\n\nLine 2";
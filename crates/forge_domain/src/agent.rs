@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::time::Duration;
 
 use derive_more::derive::Display;
 use derive_setters::Setters;
@@ -6,13 +7,13 @@ use merge::Merge;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::compact::Compact;
+use crate::compact::{Compact, CompactionTrigger};
 use crate::merge::Key;
 use crate::temperature::Temperature;
 use crate::template::Template;
 use crate::{
-    Context, Error, EventContext, MaxTokens, ModelId, Result, SystemContext, ToolDefinition,
-    ToolName, TopK, TopP,
+    Context, Error, EventContext, MaxTokens, McpFilter, ModelId, ProviderDetails, Result,
+    SystemContext, ToolDefinition, ToolName, ToolsDiscriminants, TopK, TopP, TurnLimit,
 };
 
 // Unique identifier for an agent
@@ -70,6 +71,18 @@ pub struct Agent {
     #[merge(strategy = crate::merge::option)]
     pub model: Option<ModelId>,
 
+    /// Model the agent switches to for the rest of the turn once a tool keeps
+    /// failing with the same error, e.g. a stronger model than `model`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub escalation_model: Option<ModelId>,
+
+    /// Provider the agent's requests are sent to, e.g. a cheaper provider for
+    /// summarization. If not specified, the active provider is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub provider: Option<ProviderDetails>,
+
     // Human-readable description of the agent's purpose
     #[serde(default, skip_serializing_if = "Option::is_none")]
     #[merge(strategy = crate::merge::option)]
@@ -85,22 +98,64 @@ pub struct Agent {
     #[merge(strategy = crate::merge::option)]
     pub user_prompt: Option<Template<EventContext>>,
 
-    /// Tools that the agent can use
+    /// Tools that the agent can use. Besides full tool names, entries can be
+    /// the name without the `forge_tool_` prefix (eg: `fs_read`), a group of
+    /// tools (eg: `fs` or `net`) or the last part of a name (eg: `read` or
+    /// `shell`)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     #[merge(strategy = merge_opt_vec)]
     pub tools: Option<Vec<ToolName>>,
 
+    /// Tools that the agent can't use, written the same way as `tools`. When
+    /// `tools` isn't set, the agent can use every tool that isn't excluded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = merge_opt_vec)]
+    pub exclude_tools: Option<Vec<ToolName>>,
+
+    /// MCP servers and tools the agent can use. When set, it decides which MCP
+    /// tools the agent can use instead of `tools`, and `exclude_tools` still
+    /// applies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub mcp: Option<McpFilter>,
+
+    /// Agents the conversation can be handed off to, e.g. a reviewer once the
+    /// changes are made. The agent gets the handoff tool when it's set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = merge_opt_vec)]
+    pub handoffs: Option<Vec<AgentId>>,
+
     // The transforms feature has been removed
     /// Used to specify the events the agent is interested in
     #[serde(default, skip_serializing_if = "Option::is_none")]
     #[merge(strategy = merge_opt_vec)]
     pub subscribe: Option<Vec<String>>,
 
-    /// Maximum number of turns the agent can take
+    /// Maximum number of turns the agent can take for a message. The turn is
+    /// aborted once it's reached.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     #[merge(strategy = crate::merge::option)]
     pub max_turns: Option<u64>,
 
+    /// Maximum cost in USD of the requests the agent makes for a message, as
+    /// reported by the provider. The turn is aborted once it's reached.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub max_cost_usd: Option<f64>,
+
+    /// Maximum time in seconds the agent can work on a message. The turn is
+    /// aborted once it's reached.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub max_duration: Option<u64>,
+
+    /// Number of times in a row the agent can make an identical tool call, or
+    /// alternate between the same two calls, before it's told it's stuck in a
+    /// loop. Defaults to 3, values below 2 turn the detection off.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub max_repeated_calls: Option<usize>,
+
     /// Maximum depth to which the file walker should traverse for this agent
     /// If not provided, the maximum possible depth will be used
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -228,13 +283,21 @@ impl Agent {
             title: Default::default(),
             tool_supported: Default::default(),
             model: Default::default(),
+            escalation_model: Default::default(),
+            provider: Default::default(),
             description: Default::default(),
             system_prompt: Default::default(),
             user_prompt: Default::default(),
             tools: Default::default(),
+            exclude_tools: Default::default(),
+            mcp: Default::default(),
+            handoffs: Default::default(),
             // transforms field removed
             subscribe: Default::default(),
             max_turns: Default::default(),
+            max_cost_usd: Default::default(),
+            max_duration: Default::default(),
+            max_repeated_calls: Default::default(),
             max_walker_depth: Default::default(),
             compact: Default::default(),
             custom_rules: Default::default(),
@@ -253,13 +316,93 @@ impl Agent {
         Ok(ToolDefinition::new(self.id.as_str().to_string())
             .description(self.description.clone().unwrap()))
     }
-    /// Checks if compaction should be applied
-    pub fn should_compact(&self, context: &Context, token_count: usize) -> bool {
-        // Return false if compaction is not configured
-        if let Some(compact) = &self.compact {
-            compact.should_compact(context, token_count)
+
+    /// Checks if the agent is allowed to use the tool, based on its `tools`,
+    /// `exclude_tools` and `mcp`. The handoff tool is only allowed to agents
+    /// with `handoffs`
+    pub fn allows_tool(&self, name: &ToolName) -> bool {
+        if *name == ToolsDiscriminants::ForgeToolHandoff.name() {
+            return self
+                .handoffs
+                .as_ref()
+                .is_some_and(|agents| !agents.is_empty());
+        }
+        let included = match (&self.tools, &self.mcp) {
+            (_, Some(mcp)) if name.mcp_server().is_some() => mcp.allows(name),
+            (Some(tools), _) => tools.iter().any(|tool| name.matches(tool.as_str())),
+            (None, _) => self.exclude_tools.is_some(),
+        };
+        let excluded = self
+            .exclude_tools
+            .iter()
+            .flatten()
+            .any(|tool| name.matches(tool.as_str()));
+        included && !excluded
+    }
+
+    /// Copy of the agent run as a sub-agent, allowed the tools among
+    /// `available` that the agent can use and that match `requested`, all of
+    /// them when nothing is requested. Sub-agents don't spawn sub-agents of
+    /// their own, nor fan out or hand the conversation off.
+    pub fn sub_agent(
+        &self,
+        available: impl IntoIterator<Item = ToolName>,
+        requested: &[String],
+    ) -> Self {
+        let spawn = [
+            ToolsDiscriminants::ForgeToolSpawnAgent.name(),
+            ToolsDiscriminants::ForgeToolFanOut.name(),
+        ];
+        let tools = available
+            .into_iter()
+            .filter(|name| !spawn.contains(name) && self.allows_tool(name))
+            .filter(|name| requested.is_empty() || requested.iter().any(|tool| name.matches(tool)))
+            .collect();
+        Self {
+            tools: Some(tools),
+            exclude_tools: None,
+            mcp: None,
+            handoffs: None,
+            ..self.clone()
+        }
+    }
+
+    /// Checks if compaction should be applied, and what triggers it
+    pub fn compaction_trigger(
+        &self,
+        context: &Context,
+        token_count: usize,
+        context_length: Option<u64>,
+    ) -> Option<CompactionTrigger> {
+        // Nothing triggers compaction if it's not configured
+        self.compact
+            .as_ref()
+            .and_then(|compact| compact.trigger(context, token_count, context_length))
+    }
+
+    /// Returns the hard limit of the agent reached by the work done for a
+    /// message so far, if any
+    pub fn turn_limit_reached(
+        &self,
+        turns: u64,
+        cost_usd: f64,
+        elapsed: Duration,
+    ) -> Option<TurnLimit> {
+        let max_duration = self.max_duration.map(Duration::from_secs);
+        if let Some(max_turns) = self.max_turns
+            && turns >= max_turns
+        {
+            Some(TurnLimit::Turns(max_turns))
+        } else if let Some(max_cost_usd) = self.max_cost_usd
+            && cost_usd >= max_cost_usd
+        {
+            Some(TurnLimit::CostUsd(max_cost_usd))
+        } else if let Some(max_duration) = max_duration
+            && elapsed >= max_duration
+        {
+            Some(TurnLimit::Duration(max_duration))
         } else {
-            false
+            None
         }
     }
 
@@ -373,6 +516,151 @@ mod tests {
         assert!(tools.contains(&ToolName::new("tool4")));
     }
 
+    #[test]
+    fn test_allows_tool() {
+        let fixture = [
+            Agent::new("none"),
+            Agent::new("included").tools(vec![ToolName::new("read"), ToolName::new("net")]),
+            Agent::new("excluded").exclude_tools(vec![ToolName::new("shell")]),
+            Agent::new("both")
+                .tools(vec![ToolName::new("fs")])
+                .exclude_tools(vec![ToolName::new("fs_remove")]),
+        ];
+        let tools = [
+            "forge_tool_fs_read",
+            "forge_tool_fs_remove",
+            "forge_tool_net_fetch",
+            "forge_tool_process_shell",
+        ]
+        .map(ToolName::new);
+
+        let actual = fixture
+            .iter()
+            .map(|agent| tools.iter().map(|tool| agent.allows_tool(tool)).collect())
+            .collect::<Vec<Vec<_>>>();
+
+        let expected = vec![
+            vec![false, false, false, false],
+            vec![true, false, true, false],
+            vec![true, true, true, false],
+            vec![true, false, false, false],
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_turn_limit_reached() {
+        let fixture = Agent::new("forge")
+            .max_turns(10u64)
+            .max_cost_usd(2.0)
+            .max_duration(300u64);
+
+        let actual = [
+            fixture.turn_limit_reached(3, 0.5, Duration::from_secs(60)),
+            fixture.turn_limit_reached(10, 0.5, Duration::from_secs(60)),
+            fixture.turn_limit_reached(3, 2.5, Duration::from_secs(60)),
+            fixture.turn_limit_reached(3, 0.5, Duration::from_secs(301)),
+            Agent::new("forge").turn_limit_reached(100, 100.0, Duration::from_secs(3600)),
+        ];
+
+        let expected = [
+            None,
+            Some(TurnLimit::Turns(10)),
+            Some(TurnLimit::CostUsd(2.0)),
+            Some(TurnLimit::Duration(Duration::from_secs(300))),
+            None,
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_allows_handoff_tool() {
+        let fixture = [
+            Agent::new("none").tools(vec![ToolName::new("forge_tool_handoff")]),
+            Agent::new("empty").handoffs(vec![]),
+            Agent::new("reviewer").handoffs(vec![AgentId::new("reviewer")]),
+        ];
+
+        let actual =
+            fixture.map(|agent| agent.allows_tool(&ToolsDiscriminants::ForgeToolHandoff.name()));
+
+        let expected = [false, false, true];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_sub_agent() {
+        let fixture = Agent::new("forge")
+            .tools(vec![
+                ToolName::new("fs"),
+                ToolName::new("spawn_agent"),
+                ToolName::new("fan_out"),
+            ])
+            .exclude_tools(vec![ToolName::new("fs_remove")]);
+        let available = [
+            "forge_tool_fs_read",
+            "forge_tool_fs_search",
+            "forge_tool_fs_remove",
+            "forge_tool_process_shell",
+            "forge_tool_spawn_agent",
+            "forge_tool_fan_out",
+        ]
+        .map(ToolName::new);
+
+        let actual = [
+            fixture.sub_agent(available.clone(), &[]),
+            fixture.sub_agent(available, &["read".to_string(), "shell".to_string()]),
+        ]
+        .map(|agent| agent.tools);
+
+        let expected = [
+            Some(vec![
+                ToolName::new("forge_tool_fs_read"),
+                ToolName::new("forge_tool_fs_search"),
+            ]),
+            Some(vec![ToolName::new("forge_tool_fs_read")]),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_allows_mcp_tool() {
+        let fixture = [
+            Agent::new("tools").tools(vec![ToolName::new("read")]),
+            Agent::new("server")
+                .tools(vec![ToolName::new("read")])
+                .mcp(McpFilter {
+                    include: Some(vec!["github".to_string()]),
+                    exclude: Some(vec!["mcp_github_tool_delete_*".to_string()]),
+                }),
+            Agent::new("glob")
+                .mcp(McpFilter {
+                    include: Some(vec!["mcp_*_tool_create_*".to_string()]),
+                    exclude: None,
+                })
+                .exclude_tools(vec![ToolName::new("mcp_linear")]),
+        ];
+        let tools = [
+            "forge_tool_fs_read",
+            "mcp_github_tool_create_issue",
+            "mcp_github_tool_delete_repo",
+            "mcp_linear_tool_create_issue",
+        ]
+        .map(ToolName::new);
+
+        let actual = fixture
+            .iter()
+            .map(|agent| tools.iter().map(|tool| agent.allows_tool(tool)).collect())
+            .collect::<Vec<Vec<_>>>();
+
+        let expected = vec![
+            vec![true, false, false, false],
+            vec![true, true, false, false],
+            vec![true, true, false, false],
+        ];
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn test_merge_subscribe() {
         // Base has no value, should take other's values
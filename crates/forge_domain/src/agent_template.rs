@@ -0,0 +1,156 @@
+use strum_macros::{Display, EnumIter, EnumString};
+
+use crate::{Agent, AgentId, Template, ToolsDiscriminants};
+
+/// Built-in templates of agents, written to forge.yaml by `forge agent new`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum AgentTemplate {
+    CodeReviewer,
+    TestWriter,
+    MigrationAssistant,
+}
+
+impl AgentTemplate {
+    pub fn title(&self) -> &'static str {
+        match self {
+            AgentTemplate::CodeReviewer => "Code review focussed",
+            AgentTemplate::TestWriter => "Testing focussed",
+            AgentTemplate::MigrationAssistant => "Migration focussed",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            AgentTemplate::CodeReviewer => {
+                "Reviews changes made to the codebase without modifying it. Looks for bugs, unhandled errors, edge cases, security issues and missing tests, and reports the findings by severity, with their location and a suggested fix."
+            }
+            AgentTemplate::TestWriter => {
+                "Writes the missing tests of the codebase following the conventions of the project, runs them and reports the bugs they find instead of changing the code under test."
+            }
+            AgentTemplate::MigrationAssistant => {
+                "Migrates the codebase to a new version of a dependency, framework or language, or from one API to another, in small steps that keep the project building and its tests passing."
+            }
+        }
+    }
+
+    fn system_prompt(&self) -> &'static str {
+        match self {
+            AgentTemplate::CodeReviewer => {
+                include_str!("../../../templates/forge-agent-template-code-reviewer.hbs")
+            }
+            AgentTemplate::TestWriter => {
+                include_str!("../../../templates/forge-agent-template-test-writer.hbs")
+            }
+            AgentTemplate::MigrationAssistant => {
+                include_str!("../../../templates/forge-agent-template-migration-assistant.hbs")
+            }
+        }
+    }
+
+    fn tools(&self) -> Vec<ToolsDiscriminants> {
+        use ToolsDiscriminants::*;
+        match self {
+            AgentTemplate::CodeReviewer => vec![
+                ForgeToolFsRead,
+                ForgeToolFsSearch,
+                ForgeToolFsSemanticSearch,
+                ForgeToolLspFindReferences,
+                ForgeToolAskUser,
+                ForgeToolMemoryRecall,
+            ],
+            AgentTemplate::TestWriter => vec![
+                ForgeToolFsRead,
+                ForgeToolFsCreate,
+                ForgeToolFsPatch,
+                ForgeToolFsSearch,
+                ForgeToolFsSemanticSearch,
+                ForgeToolLspFindReferences,
+                ForgeToolProcessShell,
+                ForgeToolAskUser,
+                ForgeToolMemoryRecall,
+            ],
+            AgentTemplate::MigrationAssistant => vec![
+                ForgeToolFsRead,
+                ForgeToolFsCreate,
+                ForgeToolFsRemove,
+                ForgeToolFsPatch,
+                ForgeToolFsApplyPatch,
+                ForgeToolFsAstEdit,
+                ForgeToolFsSearch,
+                ForgeToolFsSemanticSearch,
+                ForgeToolFsUndo,
+                ForgeToolLspFindReferences,
+                ForgeToolLspRenameSymbol,
+                ForgeToolProcessShell,
+                ForgeToolNetFetch,
+                ForgeToolAskUser,
+                ForgeToolMemoryRemember,
+                ForgeToolMemoryRecall,
+            ],
+        }
+    }
+
+    /// Creates the definition of an agent with the given id from the template,
+    /// with its prompts and tools filled in
+    pub fn agent(&self, id: impl Into<AgentId>) -> Agent {
+        let id = id.into();
+        let user_prompt = format!(
+            "{{{{#if (eq event.name '{id}/user_task_update')}}}}\n<feedback>{{{{event.value}}}}</feedback>\n{{{{else}}}}\n<task>{{{{event.value}}}}</task>\n{{{{/if}}}}\n<system_date>{{{{current_time}}}}</system_date>"
+        );
+        Agent::new(id)
+            .title(self.title())
+            .description(self.description())
+            .system_prompt(Template::new(self.system_prompt().trim_end()))
+            .user_prompt(Template::new(user_prompt))
+            .tools(
+                self.tools()
+                    .iter()
+                    .map(|tool| tool.name())
+                    .collect::<Vec<_>>(),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use strum::IntoEnumIterator;
+
+    use super::*;
+    use crate::ToolName;
+
+    #[test]
+    fn test_agent() {
+        let fixture = AgentTemplate::CodeReviewer;
+
+        let actual = fixture.agent("security-reviewer");
+
+        assert_eq!(actual.id, AgentId::new("security-reviewer"));
+        assert_eq!(
+            actual.user_prompt.unwrap().template,
+            "{{#if (eq event.name 'security-reviewer/user_task_update')}}\n<feedback>{{event.value}}</feedback>\n{{else}}\n<task>{{event.value}}</task>\n{{/if}}\n<system_date>{{current_time}}</system_date>"
+        );
+        let tools = actual.tools.unwrap();
+        assert!(!tools.contains(&ToolName::new("forge_tool_fs_patch")));
+        assert!(!tools.contains(&ToolName::new("forge_tool_process_shell")));
+    }
+
+    #[test]
+    fn test_display() {
+        let actual = AgentTemplate::iter()
+            .map(|template| template.to_string())
+            .collect::<Vec<_>>();
+
+        let expected = vec!["code-reviewer", "test-writer", "migration-assistant"];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_from_str() {
+        let actual = "migration-assistant".parse::<AgentTemplate>().unwrap();
+
+        let expected = AgentTemplate::MigrationAssistant;
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,112 @@
+use std::fmt::Display;
+use std::path::PathBuf;
+
+use crate::{AgentId, ChatResponse, ConversationId};
+
+/// Task an agent works on in the background, on a conversation of its own,
+/// while the user keeps chatting
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackgroundTask {
+    pub conversation_id: ConversationId,
+    pub agent: AgentId,
+    pub task: String,
+    /// Git worktree the task works in, so that its changes don't mix with the
+    /// ones made in the workspace meanwhile
+    pub worktree: PathBuf,
+}
+
+/// Approval or question of a background task, waiting for the user to answer
+/// it
+#[derive(Debug, Clone)]
+pub struct BackgroundRequest {
+    pub task: BackgroundTask,
+    /// The `ApprovalRequest` or `Question` of the agent
+    pub request: ChatResponse,
+}
+
+/// Shown to the user once a background task is done or needs them
+#[derive(Debug, Clone, PartialEq)]
+pub enum Notification {
+    TaskCompleted {
+        task: BackgroundTask,
+        /// Last answer of the agent, if it gave one
+        summary: Option<String>,
+    },
+    TaskFailed {
+        task: BackgroundTask,
+        error: String,
+    },
+    /// The agent asked for an approval or asked a question, which is put to
+    /// the user before their next prompt
+    TaskWaiting {
+        task: BackgroundTask,
+        message: String,
+    },
+}
+
+impl Notification {
+    pub fn task(&self) -> &BackgroundTask {
+        match self {
+            Notification::TaskCompleted { task, .. }
+            | Notification::TaskFailed { task, .. }
+            | Notification::TaskWaiting { task, .. } => task,
+        }
+    }
+}
+
+impl Display for Notification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let task = self.task();
+        match self {
+            Notification::TaskCompleted { .. } => {
+                write!(
+                    f,
+                    "Background task of {} completed: {}",
+                    task.agent, task.task
+                )
+            }
+            Notification::TaskFailed { error, .. } => {
+                write!(f, "Background task of {} failed: {error}", task.agent)
+            }
+            Notification::TaskWaiting { message, .. } => {
+                write!(
+                    f,
+                    "Background task of {} is waiting for an answer: {message}",
+                    task.agent
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let task = BackgroundTask {
+            conversation_id: ConversationId::generate(),
+            agent: AgentId::new("forge"),
+            task: "Update the changelog".to_string(),
+            worktree: PathBuf::from("/home/user/forge/worktrees/task"),
+        };
+
+        let actual = [
+            Notification::TaskCompleted { task: task.clone(), summary: None }.to_string(),
+            Notification::TaskFailed { task: task.clone(), error: "Rate limited".to_string() }
+                .to_string(),
+            Notification::TaskWaiting { task, message: "Remove CHANGELOG.old?".to_string() }
+                .to_string(),
+        ];
+
+        let expected = [
+            "Background task of forge completed: Update the changelog",
+            "Background task of forge failed: Rate limited",
+            "Background task of forge is waiting for an answer: Remove CHANGELOG.old?",
+        ];
+        assert_eq!(actual, expected);
+    }
+}
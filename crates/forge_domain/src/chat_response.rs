@@ -1,6 +1,10 @@
 use std::time::Duration;
 
-use crate::{ToolCallFull, ToolResult, Usage};
+use crate::{
+    AgentId, Approval, ApprovalReply, ChangeReview, CommandOutput, McpServerEvent, PartialResult,
+    ProviderWarning, Reply, TokenBudget, ToolCallFull, ToolResult, TurnLimit, Usage,
+    WorkItemUpdate,
+};
 
 /// Events that are emitted by the agent for external consumption. This includes
 /// events for all internal state changes.
@@ -16,7 +20,14 @@ pub enum ChatResponse {
     },
     ToolCallStart(ToolCallFull),
     ToolCallEnd(ToolResult),
+    /// Output of a shell command run by the agent, colors included. Frontends
+    /// that echo the output to the terminal while the command runs, like the
+    /// CLI, have already shown it.
+    CommandOutput(CommandOutput),
     Usage(Usage),
+    /// Tokens of the context window used after a request, and the compaction
+    /// of the context if there was one
+    TokenBudget(TokenBudget),
     RetryAttempt {
         cause: Cause,
         duration: Duration,
@@ -27,12 +38,52 @@ pub enum ChatResponse {
     Reasoning {
         content: String,
     },
+    ProviderWarning(ProviderWarning),
+    /// The tools of an MCP server became unavailable, or available again
+    McpServer(McpServerEvent),
+    /// An operation requires confirmation from the user before it can proceed.
+    /// The answer is sent back through `reply`, picked from `options`. When
+    /// the operation changes a file, `review` holds the change so that it can
+    /// be accepted in part.
+    ApprovalRequest {
+        message: String,
+        options: Vec<Approval>,
+        reply: ApprovalReply,
+        review: Option<ChangeReview>,
+    },
+    /// The agent asks the user a question and waits for the answer, which is
+    /// sent back through `reply`. When there are options the answer is picked
+    /// from them, several of them joined with commas when `multiple` is set.
+    Question {
+        question: String,
+        options: Vec<String>,
+        multiple: bool,
+        reply: Reply<String>,
+    },
+    /// The conversation was handed off to another agent, which now answers
+    /// the user
+    Handoff {
+        from: AgentId,
+        to: AgentId,
+    },
+    /// An item of a fan-out was started or is done
+    WorkItem(WorkItemUpdate),
 }
 
 #[derive(Debug, Clone)]
 pub enum InterruptionReason {
-    MaxToolFailurePerTurnLimitReached { limit: u64 },
-    MaxRequestPerTurnLimitReached { limit: u64 },
+    MaxToolFailurePerTurnLimitReached {
+        limit: u64,
+    },
+    MaxRequestPerTurnLimitReached {
+        limit: u64,
+    },
+    /// A hard limit of the agent was reached and the turn was aborted, with
+    /// what got done until then
+    TurnLimitReached {
+        limit: TurnLimit,
+        partial: PartialResult,
+    },
 }
 
 #[derive(Clone)]
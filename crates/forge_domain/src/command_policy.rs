@@ -0,0 +1,675 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::{LazyLock, Mutex};
+
+use anyhow::Context;
+use derive_setters::Setters;
+use forge_template::Element;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{ShellKind, ToolOutput};
+
+/// Commands the agents may run in the shell, checked before a command is
+/// started in addition to the restricted shell. A command chaining several
+/// commands with `;`, `&&`, `||`, `|` or `&` is checked one command at a time,
+/// and so are the commands it nests in a substitution, in `eval` or in the
+/// `-c` of a shell. Quotes are removed before matching.
+///
+/// The policy is a guard against mistakes rather than a sandbox: a shell can
+/// build a command in more ways than it can follow, such as a script written
+/// to a file and run afterwards.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, Setters, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct CommandPolicy {
+    /// Patterns of the only commands that may run. Every command may run when
+    /// empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[serde(with = "serde_yml::with::singleton_map_recursive")]
+    #[schemars(with = "Vec<CommandPattern>")]
+    pub allow: Vec<CommandPattern>,
+
+    /// Patterns of the commands that may not run, taking precedence over
+    /// `allow`. They also match behind the programs running another one, such
+    /// as `sudo` or `env`, and the words of a prefix may be separated by
+    /// options, so that `git push` denies `git -c x=y push` as well.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[serde(with = "serde_yml::with::singleton_map_recursive")]
+    #[schemars(with = "Vec<CommandPattern>")]
+    pub deny: Vec<CommandPattern>,
+}
+
+/// Pattern matching a command of the shell
+// The fields holding it use `singleton_map`, so that it's written as
+// `prefix: git push` in YAML rather than as a tag like `!prefix git push`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandPattern {
+    /// Words the command starts with, eg: `git push` matches `git push origin`
+    /// but not `git pull`. Variables set before the command are skipped.
+    Prefix(String),
+    /// Regular expression searched in the command, eg: `^npm (test|run lint)$`
+    Regex(String),
+}
+
+impl Display for CommandPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandPattern::Prefix(prefix) => write!(f, "prefix '{prefix}'"),
+            CommandPattern::Regex(regex) => write!(f, "regex '{regex}'"),
+        }
+    }
+}
+
+/// Why a command was refused by the [`CommandPolicy`]
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum CommandViolation {
+    #[error("The command `{command}` is denied by the command policy {pattern}")]
+    Denied {
+        command: String,
+        pattern: CommandPattern,
+    },
+
+    #[error("The command `{command}` doesn't match any pattern allowed by the command policy")]
+    NotAllowed {
+        command: String,
+        allowed: Vec<CommandPattern>,
+    },
+
+    #[error(
+        "The command substitution or variable in `{command}` can't be checked against the command policy"
+    )]
+    Substitution { command: String },
+}
+
+impl CommandViolation {
+    /// Refusal given back to the agent instead of the output of the command,
+    /// with what it may run instead
+    pub fn to_tool_output(&self) -> ToolOutput {
+        let (command, reflection) = match self {
+            CommandViolation::Denied { command, .. } => (
+                command,
+                "Reach the same result with a different command, or ask the user to run this one.",
+            ),
+            CommandViolation::NotAllowed { command, .. } => (
+                command,
+                "Only the commands matching the allowed patterns can run. Use one of them \
+                 instead, or ask the user to run this one.",
+            ),
+            CommandViolation::Substitution { command } => (
+                command,
+                "Run the substituted command on its own first and use its output in the next \
+                 command.",
+            ),
+        };
+        let allowed = match self {
+            CommandViolation::NotAllowed { allowed, .. } => allowed.as_slice(),
+            _ => &[],
+        };
+        ToolOutput::text(
+            Element::new("command_policy_refusal")
+                .attr("command", command)
+                .append(Element::new("reason").text(self.to_string()))
+                .append(
+                    allowed
+                        .iter()
+                        .map(|pattern| Element::new("allowed").text(pattern)),
+                )
+                .append(Element::new("reflection").text(reflection)),
+        )
+        .is_error(true)
+    }
+}
+
+/// Programs running the command given to them as arguments
+const WRAPPERS: &[&str] = &[
+    "sudo", "doas", "env", "command", "builtin", "exec", "nohup", "time", "nice", "ionice",
+    "timeout", "stdbuf", "setsid", "xargs",
+];
+
+/// Shells running the command line given to their `-c` option
+const SHELLS: &[&str] = &["sh", "bash", "zsh", "dash", "ksh", "fish"];
+
+/// Compiled regular expressions of the patterns, as the same few are matched
+/// against every command
+static REGEXES: LazyLock<Mutex<HashMap<String, Regex>>> = LazyLock::new(Default::default);
+
+fn regex(pattern: &str) -> anyhow::Result<Regex> {
+    let mut regexes = REGEXES.lock().unwrap();
+    if let Some(regex) = regexes.get(pattern) {
+        return Ok(regex.clone());
+    }
+    let regex = Regex::new(pattern)
+        .with_context(|| format!("Invalid command policy pattern '{pattern}'"))?;
+    regexes.insert(pattern.to_string(), regex.clone());
+    Ok(regex)
+}
+
+impl CommandPattern {
+    /// Whether the command is the one of the pattern, as it is written
+    pub(crate) fn matches(&self, command: &str) -> anyhow::Result<bool> {
+        match self {
+            CommandPattern::Prefix(prefix) => {
+                let words = words(command, '\\');
+                let words = words.iter().skip_while(|word| is_assignment(word));
+                let prefix = prefix.split_whitespace().collect::<Vec<_>>();
+                Ok(!prefix.is_empty() && words.take(prefix.len()).eq(prefix.iter().copied()))
+            }
+            CommandPattern::Regex(pattern) => Ok(regex(pattern)?.is_match(command)),
+        }
+    }
+
+    /// Whether the command runs the one of the pattern, behind wrappers and
+    /// global options as well
+    fn denies(&self, command: &str, words: &[String]) -> anyhow::Result<bool> {
+        let programs = programs(words);
+        match self {
+            CommandPattern::Prefix(prefix) => {
+                let prefix = prefix.split_whitespace().collect::<Vec<_>>();
+                let Some((program, rest)) = prefix.split_first() else {
+                    return Ok(false);
+                };
+                Ok(programs.into_iter().any(|position| {
+                    basename(&words[position]) == *program
+                        && is_subsequence(rest, &words[position + 1..])
+                }))
+            }
+            CommandPattern::Regex(pattern) => {
+                let regex = regex(pattern)?;
+                Ok(regex.is_match(command)
+                    || programs
+                        .into_iter()
+                        .any(|position| regex.is_match(&words[position..].join(" "))))
+            }
+        }
+    }
+}
+
+impl CommandPolicy {
+    /// Checks each of the commands the command line runs in the shell, a
+    /// POSIX one if not known. Fails with a [`CommandViolation`] for the first
+    /// one the policy refuses.
+    pub fn check(&self, command_line: &str, shell: Option<ShellKind>) -> anyhow::Result<()> {
+        if self.allow.is_empty() && self.deny.is_empty() {
+            return Ok(());
+        }
+        let escape = escape_char(shell);
+        for command in split_commands(command_line, escape) {
+            self.check_command(&command, shell)?;
+        }
+        Ok(())
+    }
+
+    fn check_command(&self, command: &str, shell: Option<ShellKind>) -> anyhow::Result<()> {
+        let escape = escape_char(shell);
+        let words = words(command, escape);
+        // The backtick substitutes a command in the POSIX shells only
+        let is_substitution = |c: char| c == '$' || (c == '`' && escape == '\\');
+
+        // The program a variable or a substitution expands to isn't known
+        // before the command runs
+        if programs(&words)
+            .into_iter()
+            .any(|position| words[position].contains(is_substitution))
+        {
+            return Err(CommandViolation::Substitution { command: command.to_string() }.into());
+        }
+
+        for pattern in &self.deny {
+            if pattern.denies(command, &words)? {
+                return Err(CommandViolation::Denied {
+                    command: command.to_string(),
+                    pattern: pattern.clone(),
+                }
+                .into());
+            }
+        }
+        // The commands run by a substitution aren't known to be allowed
+        // before they run
+        if !self.allow.is_empty()
+            && (command.contains("$(") || (escape == '\\' && command.contains('`')))
+        {
+            return Err(CommandViolation::Substitution { command: command.to_string() }.into());
+        }
+        for nested in nested_commands(command, &words, escape) {
+            self.check(&nested, shell)?;
+        }
+
+        if self.allow.is_empty() {
+            return Ok(());
+        }
+        let mut allowed = false;
+        for pattern in &self.allow {
+            allowed |= pattern.matches(command)?;
+        }
+        if !allowed {
+            return Err(CommandViolation::NotAllowed {
+                command: command.to_string(),
+                allowed: self.allow.clone(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// Character escaping the next one in the shell: the backtick in PowerShell,
+/// the caret in cmd and the backslash in the POSIX shells
+fn escape_char(shell: Option<ShellKind>) -> char {
+    match shell {
+        Some(ShellKind::Pwsh) => '`',
+        Some(ShellKind::Cmd) => '^',
+        _ => '\\',
+    }
+}
+
+/// Splits a command into its words the way the shell does, removing the
+/// quotes and the characters escaping another one
+fn words(command: &str, escape: char) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = None::<String>;
+    let mut quote = None;
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some('"'), c) if c == escape => {
+                let word = current.get_or_insert_default();
+                match chars.next() {
+                    // A POSIX shell keeps the backslash before the characters
+                    // it doesn't escape
+                    Some(next) if escape == '\\' && !matches!(next, '"' | '\\' | '$' | '`') => {
+                        word.extend([escape, next])
+                    }
+                    Some(next) => word.push(next),
+                    None => word.push(escape),
+                }
+            }
+            (Some(_), c) => current.get_or_insert_default().push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                current.get_or_insert_default();
+            }
+            (None, c) if c == escape => current.get_or_insert_default().extend(chars.next()),
+            (None, c) if c.is_whitespace() => words.extend(current.take()),
+            (None, c) => current.get_or_insert_default().push(c),
+        }
+    }
+    words.extend(current);
+    words
+}
+
+/// Positions of the words that may be the program the command runs: the first
+/// one after the variables set, and the words following a wrapper such as
+/// `sudo` that aren't options
+pub(crate) fn programs(words: &[String]) -> Vec<usize> {
+    let Some(first) = words.iter().position(|word| !is_assignment(word)) else {
+        return Vec::new();
+    };
+    let mut positions = vec![first];
+    if WRAPPERS.contains(&basename(&words[first])) {
+        positions.extend(
+            (first + 1..words.len())
+                .filter(|&position| !words[position].starts_with('-'))
+                .filter(|&position| !is_assignment(&words[position])),
+        );
+    }
+    positions
+}
+
+/// Command lines the command runs itself: the ones of its substitutions, the
+/// `-c` of a shell and the arguments of `eval`
+fn nested_commands(command: &str, words: &[String], escape: char) -> Vec<String> {
+    let mut nested = substitutions(command, escape);
+    for position in programs(words) {
+        let program = basename(&words[position]);
+        let rest = &words[position + 1..];
+        if SHELLS.contains(&program) {
+            let option = rest.iter().position(|word| {
+                word.starts_with('-') && !word.starts_with("--") && word.contains('c')
+            });
+            nested.extend(option.and_then(|option| rest.get(option + 1)).cloned());
+        } else if program == "eval" {
+            nested.push(rest.join(" "));
+        }
+    }
+    nested
+}
+
+/// Commands run by the `$(...)`, `<(...)`, `>(...)` and backtick substitutions
+/// of the command, outside of single quotes. Backticks substitute a command in
+/// the POSIX shells only, the ones escaping with a backslash.
+fn substitutions(command: &str, escape: char) -> Vec<String> {
+    let mut substitutions = Vec::new();
+    let mut single_quoted = false;
+    let mut double_quoted = false;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' if !double_quoted => single_quoted = !single_quoted,
+            '"' if !single_quoted => double_quoted = !double_quoted,
+            c if c == escape && !single_quoted => {
+                chars.next();
+            }
+            '$' | '<' | '>' if !single_quoted && chars.peek() == Some(&'(') => {
+                chars.next();
+                let mut depth = 1;
+                let mut body = String::new();
+                for c in chars.by_ref() {
+                    depth += match c {
+                        '(' => 1,
+                        ')' => -1,
+                        _ => 0,
+                    };
+                    if depth == 0 {
+                        break;
+                    }
+                    body.push(c);
+                }
+                substitutions.push(body);
+            }
+            '`' if !single_quoted && escape == '\\' => {
+                substitutions.push(chars.by_ref().take_while(|&c| c != '`').collect());
+            }
+            _ => {}
+        }
+    }
+    substitutions
+}
+
+/// Name of the program run from a path, eg: `git` for `/usr/bin/git`
+pub(crate) fn basename(program: &str) -> &str {
+    program.rsplit('/').next().unwrap_or(program)
+}
+
+/// Whether the words of the pattern all appear in the command, in order
+fn is_subsequence(pattern: &[&str], words: &[String]) -> bool {
+    let mut words = words.iter();
+    pattern
+        .iter()
+        .all(|expected| words.any(|word| word == expected))
+}
+
+/// Whether a word sets a variable for the command, eg: `RUST_LOG=debug`
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty()
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+    })
+}
+
+/// Commands a POSIX shell runs for the command line with their words,
+/// including the ones it nests in substitutions, in `eval` or in the `-c` of a
+/// shell
+pub(crate) fn run_commands(command_line: &str) -> Vec<(String, Vec<String>)> {
+    let mut commands = Vec::new();
+    for command in split_commands(command_line, '\\') {
+        let words = words(&command, '\\');
+        let nested = nested_commands(&command, &words, '\\');
+        commands.push((command, words));
+        for nested in nested {
+            commands.extend(run_commands(&nested));
+        }
+    }
+    commands
+}
+
+/// Splits a command line into the commands it runs, on the operators outside
+/// of quotes and not escaped. The parentheses and braces grouping them are
+/// dropped.
+pub(crate) fn split_commands(command_line: &str, escape: char) -> Vec<String> {
+    let mut commands = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let mut chars = command_line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => {
+                quote = None;
+                current.push(c);
+            }
+            (Some('"'), c) if c == escape => {
+                current.push(c);
+                current.extend(chars.next());
+            }
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                current.push(c);
+            }
+            (None, c) if c == escape => {
+                current.push(c);
+                current.extend(chars.next());
+            }
+            // Redirections such as `2>&1` and `&>` don't separate commands
+            (None, '&') if current.ends_with(['>', '<']) || chars.peek() == Some(&'>') => {
+                current.push(c);
+            }
+            (None, ';' | '|' | '&' | '\n') => {
+                // `&&` and `||` are a single operator
+                if chars.peek() == Some(&c) {
+                    chars.next();
+                }
+                commands.push(std::mem::take(&mut current));
+            }
+            (None, c) => current.push(c),
+        }
+    }
+    commands.push(current);
+
+    commands
+        .into_iter()
+        .map(|command| ungroup(&command))
+        .filter(|command| !command.is_empty())
+        .collect()
+}
+
+/// Drops the parentheses and braces opening or closing a group around a
+/// command, keeping the ones of a substitution such as `$(pwd)`
+fn ungroup(command: &str) -> String {
+    let mut command = command
+        .trim()
+        .trim_start_matches(|c: char| c == '(' || c == '{' || c.is_whitespace())
+        .to_string();
+    while (command.ends_with(')') && command.matches(')').count() > command.matches('(').count())
+        || (command.ends_with('}') && command.matches('}').count() > command.matches('{').count())
+    {
+        command.pop();
+        command.truncate(command.trim_end().len());
+    }
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn violation(policy: &CommandPolicy, command_line: &str) -> Option<CommandViolation> {
+        policy
+            .check(command_line, None)
+            .err()
+            .map(|error| error.downcast::<CommandViolation>().unwrap())
+    }
+
+    #[test]
+    fn test_command_policy_from_yaml() {
+        let fixture =
+            "allow:\n  - prefix: cargo\n  - regex: ^npm test$\ndeny:\n  - prefix: git push\n";
+
+        let actual: CommandPolicy = serde_yml::from_str(fixture).unwrap();
+
+        let expected = CommandPolicy::default()
+            .allow(vec![
+                CommandPattern::Prefix("cargo".to_string()),
+                CommandPattern::Regex("^npm test$".to_string()),
+            ])
+            .deny(vec![CommandPattern::Prefix("git push".to_string())]);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_split_commands() {
+        let fixture =
+            "cd app && (npm test || echo 'a; b | c') ; ls $(pwd) 2>&1 | wc -l &\n{ make; }";
+
+        let actual = split_commands(fixture, '\\');
+
+        let expected = vec![
+            "cd app",
+            "npm test",
+            "echo 'a; b | c'",
+            "ls $(pwd) 2>&1",
+            "wc -l",
+            "make",
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_deny_takes_precedence() {
+        let fixture = CommandPolicy::default()
+            .allow(vec![CommandPattern::Prefix("git".to_string())])
+            .deny(vec![CommandPattern::Prefix("git push".to_string())]);
+
+        let actual = (
+            violation(&fixture, "git status"),
+            violation(&fixture, "git status && GIT_TRACE=1 git  push origin"),
+        );
+
+        let expected = (
+            None,
+            Some(CommandViolation::Denied {
+                command: "GIT_TRACE=1 git  push origin".to_string(),
+                pattern: CommandPattern::Prefix("git push".to_string()),
+            }),
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_prefix_matches_whole_words() {
+        let fixture =
+            CommandPolicy::default().allow(vec![CommandPattern::Prefix("cargo".to_string())]);
+
+        let actual = violation(&fixture, "cargo-evil build");
+
+        let expected = Some(CommandViolation::NotAllowed {
+            command: "cargo-evil build".to_string(),
+            allowed: vec![CommandPattern::Prefix("cargo".to_string())],
+        });
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_allow_checks_every_command() {
+        let fixture = CommandPolicy::default().allow(vec![
+            CommandPattern::Prefix("cargo".to_string()),
+            CommandPattern::Regex("^npm (test|run lint)$".to_string()),
+        ]);
+
+        let actual = violation(&fixture, "npm test && cargo test; curl example.com");
+
+        let expected = Some(CommandViolation::NotAllowed {
+            command: "curl example.com".to_string(),
+            allowed: fixture.allow.clone(),
+        });
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_allow_refuses_substitution() {
+        let fixture =
+            CommandPolicy::default().allow(vec![CommandPattern::Prefix("echo".to_string())]);
+
+        let actual = violation(&fixture, "echo $(rm -rf build)");
+
+        let expected =
+            Some(CommandViolation::Substitution { command: "echo $(rm -rf build)".to_string() });
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_deny_is_not_bypassed() {
+        let fixture =
+            CommandPolicy::default().deny(vec![CommandPattern::Prefix("git push".to_string())]);
+
+        let actual = [
+            "\"git\" push",
+            "git -c x=y push origin",
+            "sudo -u root git push",
+            "env GIT_TRACE=1 command /usr/bin/git push",
+            "bash -c 'git push'",
+            "eval \"git push\"",
+            "echo $(git push)",
+            "$(echo git) push",
+            "$GIT push",
+        ]
+        .map(|command| violation(&fixture, command).is_some());
+
+        let expected = [true; 9];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_deny_allows_other_commands() {
+        let fixture =
+            CommandPolicy::default().deny(vec![CommandPattern::Prefix("git push".to_string())]);
+
+        let actual = [
+            "git pull",
+            "echo git push",
+            "ls $(pwd)",
+            "bash -c 'git status'",
+        ]
+        .map(|command| violation(&fixture, command));
+
+        let expected = [None, None, None, None];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_backtick_is_an_escape_in_powershell() {
+        let fixture = CommandPolicy::default()
+            .allow(vec![CommandPattern::Prefix("echo".to_string())])
+            .deny(vec![CommandPattern::Prefix("git push".to_string())]);
+
+        let actual = [
+            fixture.check("echo `hello`", Some(ShellKind::Pwsh)).is_ok(),
+            fixture.check("echo `hello`", None).is_ok(),
+            fixture.check("g`it push", Some(ShellKind::Pwsh)).is_ok(),
+            fixture
+                .check("echo \\$(git push)", Some(ShellKind::Pwsh))
+                .is_ok(),
+        ];
+
+        let expected = [true, false, false, false];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_words_removes_quotes() {
+        let fixture = r#"git commit -m "a \"b\" c" 'd e'\ f"#;
+
+        let actual = words(fixture, '\\');
+
+        let expected = vec!["git", "commit", "-m", "a \"b\" c", "d e f"];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_invalid_regex_fails() {
+        let fixture = CommandPolicy::default().deny(vec![CommandPattern::Regex("(".to_string())]);
+
+        let actual = fixture.check("ls", None).is_err();
+
+        assert!(actual);
+    }
+}
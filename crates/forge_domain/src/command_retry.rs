@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+use derive_setters::Setters;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::CommandPattern;
+use crate::command_policy::split_commands;
+
+/// Times a command runs at most whatever its policy asks for, so that a
+/// command failing for good doesn't hold the turn
+const MAX_ATTEMPTS: u32 = 10;
+
+/// Longest time waited between two attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Runs a failing command again, such as a flaky integration test or an
+/// install over an unreliable network
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Setters, JsonSchema)]
+#[setters(into)]
+pub struct RetryPolicy {
+    /// Maximum number of times the command runs, the first time included, at
+    /// most 10
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+
+    /// Milliseconds waited before the second attempt, doubled before each
+    /// attempt after it up to a minute
+    #[serde(default = "default_backoff_ms")]
+    pub backoff_ms: u64,
+
+    /// Exit codes the command runs again on. Every exit code other than 0 is
+    /// retried when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exit_codes: Vec<i32>,
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+fn default_backoff_ms() -> u64 {
+    1000
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            backoff_ms: default_backoff_ms(),
+            exit_codes: Vec::new(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether the attempt, counted from 1, that exited with the code is
+    /// followed by another one. A command killed by a signal isn't retried.
+    pub fn retries(&self, attempt: u32, exit_code: Option<i32>) -> bool {
+        attempt < self.max_attempts.min(MAX_ATTEMPTS)
+            && exit_code.is_some_and(|code| {
+                code != 0 && (self.exit_codes.is_empty() || self.exit_codes.contains(&code))
+            })
+    }
+
+    /// Time waited after the attempt, counted from 1, before the next one
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        Duration::from_millis(self.backoff_ms.saturating_mul(factor)).min(MAX_BACKOFF)
+    }
+}
+
+/// Retries of the commands matching a pattern
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CommandRetry {
+    /// Commands retried, eg: `prefix: npm install`. A command line is retried
+    /// when one of the commands it chains matches, and then runs again as a
+    /// whole, the commands chained before the matching one included.
+    #[serde(with = "serde_yml::with::singleton_map")]
+    #[schemars(with = "CommandPattern")]
+    pub command: CommandPattern,
+
+    #[serde(flatten)]
+    pub retry: RetryPolicy,
+}
+
+impl CommandRetry {
+    pub fn matches(&self, command_line: &str) -> anyhow::Result<bool> {
+        for command in split_commands(command_line, '\\') {
+            if self.command.matches(&command)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_retry_policy_retries() {
+        let fixture = RetryPolicy::default().exit_codes(vec![1, 101]);
+
+        let actual = [
+            fixture.retries(1, Some(101)),
+            fixture.retries(2, Some(1)),
+            fixture.retries(3, Some(1)),
+            fixture.retries(1, Some(2)),
+            fixture.retries(1, Some(0)),
+            fixture.retries(1, None),
+        ];
+
+        let expected = [true, true, false, false, false, false];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_retry_policy_is_capped() {
+        let fixture = RetryPolicy::default()
+            .max_attempts(u32::MAX)
+            .backoff_ms(10_000u64);
+
+        let actual = (
+            fixture.retries(9, Some(1)),
+            fixture.retries(10, Some(1)),
+            fixture.backoff(4),
+        );
+
+        let expected = (true, false, Duration::from_secs(60));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_retry_policy_backoff_doubles() {
+        let fixture = RetryPolicy::default().backoff_ms(500u64);
+
+        let actual = [1, 2, 3].map(|attempt| fixture.backoff(attempt));
+
+        let expected = [500, 1000, 2000].map(Duration::from_millis);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_command_retry_matches_chained_command() {
+        let fixture: CommandRetry =
+            serde_yml::from_str("command:\n  prefix: npm install\nmax_attempts: 5\n").unwrap();
+
+        let actual = (
+            fixture.matches("cd web && npm install --silent").unwrap(),
+            fixture.matches("npm test").unwrap(),
+            fixture.retry.max_attempts,
+            fixture.retry.backoff_ms,
+        );
+
+        let expected = (true, false, 5, 1000);
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,3 +1,5 @@
+use std::fmt::Display;
+
 use derive_setters::Setters;
 use merge::Merge;
 use schemars::JsonSchema;
@@ -36,6 +38,17 @@ pub struct Compact {
     #[merge(strategy = crate::merge::option)]
     pub token_threshold: Option<usize>,
 
+    /// Share of the context window of the model the context can fill before
+    /// triggering compaction. Valid values are between 0.0 and 1.0. Unlike
+    /// target_utilization the request isn't held back while compacting.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_optional_percentage"
+    )]
+    #[merge(strategy = crate::merge::option)]
+    pub token_percentage: Option<f64>,
+
     /// Maximum number of conversation turns before triggering compaction
     #[serde(skip_serializing_if = "Option::is_none")]
     #[merge(strategy = crate::merge::option)]
@@ -67,17 +80,76 @@ pub struct Compact {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     #[merge(strategy = crate::merge::option)]
     pub on_turn_end: Option<bool>,
+
+    /// Share of the context window of the model the context can fill before
+    /// it is compacted, ahead of being sent. Valid values are between 0.0 and
+    /// 1.0, defaults to 0.8.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_optional_percentage"
+    )]
+    #[merge(strategy = crate::merge::option)]
+    pub target_utilization: Option<f64>,
+
+    /// Whether the context is only compacted when asked to with /compact. All
+    /// the thresholds, including target_utilization, are ignored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub explicit_only: Option<bool>,
+}
+
+/// What triggered an automatic compaction, reported to the user along with it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompactionTrigger {
+    TokenThreshold,
+    TokenPercentage,
+    TurnThreshold,
+    MessageThreshold,
+    TurnEnd,
+    ContextWindow,
 }
 
+impl Display for CompactionTrigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            CompactionTrigger::TokenThreshold => "the token threshold was reached",
+            CompactionTrigger::TokenPercentage => {
+                "the token percentage of the context window was reached"
+            }
+            CompactionTrigger::TurnThreshold => "the turn threshold was reached",
+            CompactionTrigger::MessageThreshold => "the message threshold was reached",
+            CompactionTrigger::TurnEnd => "the turn ended",
+            CompactionTrigger::ContextWindow => "the context window of the model was nearly full",
+        };
+        f.write_str(reason)
+    }
+}
+
+/// Share of the context window of the model used when none is configured
+pub const DEFAULT_TARGET_UTILIZATION: f64 = 0.8;
+
 fn deserialize_percentage<'de, D>(deserializer: D) -> Result<f64, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    use serde::de::Error;
+    check_percentage(f64::deserialize(deserializer)?)
+}
 
-    let value = f64::deserialize(deserializer)?;
+/// Deserializes a percentage that may be left out or set to `null`
+fn deserialize_optional_percentage<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<f64>::deserialize(deserializer)?
+        .map(check_percentage)
+        .transpose()
+}
+
+fn check_percentage<E: serde::de::Error>(value: f64) -> Result<f64, E> {
     if !(0.0..=1.0).contains(&value) {
-        return Err(Error::custom(format!(
+        return Err(E::custom(format!(
             "percentage must be between 0.0 and 1.0, got {value}"
         )));
     }
@@ -113,6 +185,7 @@ impl Compact {
         Self {
             max_tokens: None,
             token_threshold: None,
+            token_percentage: None,
             turn_threshold: None,
             message_threshold: None,
             prompt: None,
@@ -121,16 +194,59 @@ impl Compact {
             eviction_window: 0.2, // Default to 20% compaction
             retention_window: 0,
             on_turn_end: None,
+            target_utilization: None,
+            explicit_only: None,
         }
     }
 
+    /// Whether automatic compaction is turned off
+    pub fn is_explicit_only(&self) -> bool {
+        self.explicit_only.unwrap_or_default()
+    }
+
+    /// Checks if the context fills more of the context window of the model
+    /// than the target utilization allows, so that it has to be compacted
+    /// before it's sent
+    pub fn exceeds_context_window(&self, token_count: usize, context_length: Option<u64>) -> bool {
+        let Some(context_length) = context_length.filter(|_| !self.is_explicit_only()) else {
+            return false;
+        };
+        let utilization = self
+            .target_utilization
+            .unwrap_or(DEFAULT_TARGET_UTILIZATION);
+        token_count as f64 > context_length as f64 * utilization
+    }
+
     /// Determines if compaction should be triggered based on the current
     /// context
     pub fn should_compact(&self, context: &Context, token_count: usize) -> bool {
-        self.should_compact_due_to_tokens(token_count)
-            || self.should_compact_due_to_turns(context)
-            || self.should_compact_due_to_messages(context)
-            || self.should_compact_on_turn_end(context)
+        self.trigger(context, token_count, None).is_some()
+    }
+
+    /// Finds the threshold that triggers compaction of the context, if any.
+    /// The token percentage is only checked when the context window of the
+    /// model is known.
+    pub fn trigger(
+        &self,
+        context: &Context,
+        token_count: usize,
+        context_length: Option<u64>,
+    ) -> Option<CompactionTrigger> {
+        if self.is_explicit_only() {
+            None
+        } else if self.should_compact_due_to_tokens(token_count) {
+            Some(CompactionTrigger::TokenThreshold)
+        } else if self.should_compact_due_to_token_percentage(token_count, context_length) {
+            Some(CompactionTrigger::TokenPercentage)
+        } else if self.should_compact_due_to_turns(context) {
+            Some(CompactionTrigger::TurnThreshold)
+        } else if self.should_compact_due_to_messages(context) {
+            Some(CompactionTrigger::MessageThreshold)
+        } else if self.should_compact_on_turn_end(context) {
+            Some(CompactionTrigger::TurnEnd)
+        } else {
+            None
+        }
     }
 
     /// Checks if compaction should be triggered due to token count exceeding
@@ -145,6 +261,21 @@ impl Compact {
         }
     }
 
+    /// Checks if compaction should be triggered due to the context filling
+    /// the configured share of the context window
+    fn should_compact_due_to_token_percentage(
+        &self,
+        token_count: usize,
+        context_length: Option<u64>,
+    ) -> bool {
+        match (self.token_percentage, context_length) {
+            (Some(percentage), Some(context_length)) => {
+                token_count as f64 >= context_length as f64 * percentage
+            }
+            _ => false,
+        }
+    }
+
     /// Checks if compaction should be triggered due to turn count exceeding
     /// threshold
     fn should_compact_due_to_turns(&self, context: &Context) -> bool {
@@ -272,6 +403,91 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_exceeds_context_window() {
+        let fixture = Compact::new();
+
+        let actual = [
+            fixture.exceeds_context_window(80_000, Some(100_000)),
+            fixture.exceeds_context_window(80_001, Some(100_000)),
+            fixture
+                .clone()
+                .target_utilization(0.5)
+                .exceeds_context_window(60_000, Some(100_000)),
+            fixture.exceeds_context_window(1_000_000, None),
+            fixture
+                .clone()
+                .explicit_only(true)
+                .exceeds_context_window(1_000_000, Some(100_000)),
+        ];
+
+        let expected = [false, true, true, false, false];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_trigger() {
+        let fixture = Compact::new()
+            .token_percentage(0.5)
+            .message_threshold(4_usize);
+
+        let actual = [
+            fixture.trigger(&ctx("ua"), 60_000, Some(100_000)),
+            fixture.trigger(&ctx("ua"), 60_000, None),
+            fixture.trigger(&ctx("uaua"), 1_000, Some(100_000)),
+            fixture.trigger(&ctx("ua"), 1_000, Some(100_000)),
+            fixture
+                .clone()
+                .explicit_only(true)
+                .trigger(&ctx("uaua"), 60_000, Some(100_000)),
+        ];
+
+        let expected = [
+            Some(CompactionTrigger::TokenPercentage),
+            None,
+            Some(CompactionTrigger::MessageThreshold),
+            None,
+            None,
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_explicit_only_from_yaml() {
+        let fixture = "token_threshold: 1000\nexplicit_only: true\n";
+
+        let compact = serde_yml::from_str::<Compact>(fixture).unwrap();
+        let actual = (
+            compact.is_explicit_only(),
+            compact.should_compact(&ctx("ua"), 2000),
+        );
+
+        let expected = (true, false);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_target_utilization_out_of_range() {
+        let fixture = r#"{"target_utilization": 1.5}"#;
+
+        let actual = serde_json::from_str::<Compact>(fixture).is_err();
+
+        assert!(actual);
+    }
+
+    #[test]
+    fn test_target_utilization_null() {
+        let fixture = "target_utilization: null\ntoken_percentage: null";
+
+        let actual = serde_yml::from_str::<Compact>(fixture).unwrap();
+
+        let expected = (None, None);
+        assert_eq!(
+            (actual.target_utilization, actual.token_percentage),
+            expected
+        );
+    }
+
     #[test]
     fn test_should_compact_due_to_turns_exceeds_threshold() {
         let fixture = Compact::new()
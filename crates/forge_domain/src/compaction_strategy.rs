@@ -1,4 +1,6 @@
-use crate::{Context, Role};
+use std::collections::HashSet;
+
+use crate::{Context, ContextMessage, Role};
 
 /// Strategy for context compaction that unifies different compaction approaches
 #[derive(Debug, Clone)]
@@ -118,38 +120,47 @@ fn find_sequence_preserving_last_n(
         return None;
     }
 
-    // Don't break between a tool call and its result
-    if messages.get(end).is_some_and(|msg| msg.has_tool_call()) {
-        // If the last message has a tool call, adjust end to include the tool result
-        // This means either not compacting at all, or reducing the end by 1
+    // Move the end back until no tool call is separated from its results, as
+    // providers reject results whose call is gone and calls without results
+    while !is_boundary(messages, start, end) {
         if end == start {
-            // If start == end and it has a tool call, don't compact
             return None;
-        } else {
-            // Otherwise reduce end by 1
-            return Some((start, end.saturating_sub(1)));
         }
+        end -= 1;
     }
 
-    if messages.get(end).is_some_and(|msg| msg.has_tool_result())
-        && messages
-            .get(end.saturating_add(1))
-            .is_some_and(|msg| msg.has_tool_result())
+    Some((start, end))
+}
+
+/// Whether the messages from `start` to `end` can be summarized apart from the
+/// ones that follow, which holds when none of the tool calls among them has a
+/// result after `end`. A tool call at `end` may still be waiting for results.
+fn is_boundary(messages: &[ContextMessage], start: usize, end: usize) -> bool {
+    if messages[end].has_tool_call()
+        || messages
+            .get(end + 1)
+            .is_some_and(|message| message.has_tool_result())
     {
-        // If the last message is a tool result and the next one is also a tool result,
-        // we need to adjust the end.
-        while end >= start && messages.get(end).is_some_and(|msg| msg.has_tool_result()) {
-            end = end.saturating_sub(1);
-        }
-        end = end.saturating_sub(1);
+        return false;
     }
 
-    // Return the sequence only if it has at least one message
-    if end >= start {
-        Some((start, end))
-    } else {
-        None
-    }
+    // Results are matched to their calls by id, wherever they ended up
+    let call_ids = messages[start..=end]
+        .iter()
+        .filter_map(|message| match message {
+            ContextMessage::Text(text) => text.tool_calls.as_ref(),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|call| call.call_id.as_ref())
+        .collect::<HashSet<_>>();
+    !messages[end + 1..].iter().any(|message| match message {
+        ContextMessage::Tool(result) => result
+            .call_id
+            .as_ref()
+            .is_some_and(|call_id| call_ids.contains(call_id)),
+        _ => false,
+    })
 }
 
 #[cfg(test)]
@@ -164,17 +175,21 @@ mod tests {
         let model_id = ModelId::new("gpt-4");
         let pattern = pattern.to_string();
 
-        let tool_call = ToolCallFull {
+        // Each tool call has its own id, shared by the results that follow it
+        let tool_call = |id: usize| ToolCallFull {
             name: ToolName::new("forge_tool_fs_read"),
-            call_id: Some(ToolCallId::new("call_123")),
+            call_id: Some(ToolCallId::new(format!("call_{id}"))),
             arguments: json!({"path": "/test/path"}),
         };
 
-        let tool_result = ToolResult::new(ToolName::new("forge_tool_fs_read"))
-            .call_id(ToolCallId::new("call_123"))
-            .success(json!({"content": "File content"}).to_string());
+        let tool_result = |id: usize| {
+            ToolResult::new(ToolName::new("forge_tool_fs_read"))
+                .call_id(ToolCallId::new(format!("call_{id}")))
+                .success(json!({"content": "File content"}).to_string())
+        };
 
         let mut context = Context::default();
+        let mut call_count = 0;
 
         for c in pattern.chars() {
             match c {
@@ -193,14 +208,16 @@ mod tests {
                     ))
                 }
                 't' => {
+                    call_count += 1;
                     context = context.add_message(ContextMessage::assistant(
                         "Assistant message with tool call",
                         None,
-                        Some(vec![tool_call.clone()]),
+                        Some(vec![tool_call(call_count)]),
                     ))
                 }
                 'r' => {
-                    context = context.add_message(ContextMessage::tool_result(tool_result.clone()))
+                    context =
+                        context.add_message(ContextMessage::tool_result(tool_result(call_count)))
                 }
                 _ => panic!("Invalid character in test pattern: {c}"),
             }
@@ -323,6 +340,15 @@ mod tests {
         let expected = "s[u]trrrrrra";
         assert_eq!(actual, expected);
 
+        // Results that don't directly follow their call
+        let actual = seq("sutaur", 1);
+        let expected = "s[u]taur";
+        assert_eq!(actual, expected);
+
+        let actual = seq("sutauraua", 2);
+        let expected = "s[utaura]ua";
+        assert_eq!(actual, expected);
+
         // Conversation patterns
         let actual = seq("suauauaua", 0);
         let expected = "s[uauauaua]";
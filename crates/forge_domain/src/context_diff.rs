@@ -0,0 +1,287 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use crate::ToolValue;
+use crate::context::{Context, ContextMessage, Role};
+
+/// Characters of a message shown when it's listed in a diff
+const PREVIEW_CHARS: usize = 60;
+
+/// Short description of a message of the context
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MessageSummary {
+    pub role: String,
+    pub preview: String,
+    pub chars: usize,
+}
+
+impl From<&ContextMessage> for MessageSummary {
+    fn from(message: &ContextMessage) -> Self {
+        match message {
+            ContextMessage::Text(text) => {
+                let calls = text
+                    .tool_calls
+                    .iter()
+                    .flatten()
+                    .map(|call| call.name.to_string())
+                    .collect::<Vec<_>>();
+                let preview = match text.content.lines().find(|line| !line.trim().is_empty()) {
+                    Some(line) => preview(line.trim()),
+                    None if !calls.is_empty() => format!("calls {}", calls.join(", ")),
+                    None => String::new(),
+                };
+                Self {
+                    role: text.role.to_string(),
+                    preview,
+                    chars: text.content.chars().count(),
+                }
+            }
+            ContextMessage::Tool(result) => Self {
+                role: "Tool".to_string(),
+                preview: result.name.to_string(),
+                chars: result
+                    .output
+                    .values
+                    .iter()
+                    .map(|value| match value {
+                        ToolValue::Text(text) => text.chars().count(),
+                        _ => 0,
+                    })
+                    .sum(),
+            },
+            ContextMessage::Image(_) => Self {
+                role: Role::User.to_string(),
+                preview: "[image]".to_string(),
+                chars: 0,
+            },
+        }
+    }
+}
+
+impl Display for MessageSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} ({} chars)", self.role, self.preview, self.chars)
+    }
+}
+
+/// How messages of the context changed from one request to the next
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MessageChange {
+    /// The message is sent for the first time
+    Added { message: MessageSummary },
+    /// The message was dropped from the context
+    Removed { message: MessageSummary },
+    /// The messages were replaced by a summary
+    Compacted {
+        removed: Vec<MessageSummary>,
+        summary: MessageSummary,
+    },
+    /// The message is sent again with a shorter content
+    Truncated {
+        before: MessageSummary,
+        after: MessageSummary,
+    },
+}
+
+/// Differences between the contexts sent in two requests, to find out what the
+/// model stopped seeing
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContextDiff {
+    pub previous_messages: usize,
+    pub current_messages: usize,
+    pub unchanged: usize,
+    pub changes: Vec<MessageChange>,
+}
+
+impl ContextDiff {
+    pub fn new(previous: &Context, current: &Context) -> Self {
+        let (before, after) = (&previous.messages, &current.messages);
+        let common = common_messages(before, after);
+
+        let mut changes = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        for (next_i, next_j) in common.iter().copied().chain([(before.len(), after.len())]) {
+            changes.extend(gap_changes(&before[i..next_i], &after[j..next_j]));
+            (i, j) = (next_i + 1, next_j + 1);
+        }
+
+        Self {
+            previous_messages: before.len(),
+            current_messages: after.len(),
+            unchanged: common.len(),
+            changes,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Positions of the messages found in both contexts, in order, using the
+/// longest common subsequence
+fn common_messages(before: &[ContextMessage], after: &[ContextMessage]) -> Vec<(usize, usize)> {
+    let mut lengths = vec![vec![0usize; after.len() + 1]; before.len() + 1];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            lengths[i][j] = if before[i] == after[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut common = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < before.len() && j < after.len() {
+        if before[i] == after[j] {
+            common.push((i, j));
+            (i, j) = (i + 1, j + 1);
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    common
+}
+
+/// Changes between runs of messages found in only one of the contexts
+fn gap_changes(removed: &[ContextMessage], added: &[ContextMessage]) -> Vec<MessageChange> {
+    let removed = removed.iter().map(MessageSummary::from).collect::<Vec<_>>();
+    let mut added = added.iter().map(MessageSummary::from).collect::<Vec<_>>();
+
+    let truncated = !removed.is_empty()
+        && removed.len() == added.len()
+        && removed
+            .iter()
+            .zip(&added)
+            .all(|(before, after)| before.role == after.role && after.chars < before.chars);
+    if truncated {
+        return removed
+            .into_iter()
+            .zip(added)
+            .map(|(before, after)| MessageChange::Truncated { before, after })
+            .collect();
+    }
+
+    // Compaction replaces the messages with a summary from the user
+    if !removed.is_empty() && added.len() == 1 && added[0].role == Role::User.to_string() {
+        return vec![MessageChange::Compacted { removed, summary: added.remove(0) }];
+    }
+
+    removed
+        .into_iter()
+        .map(|message| MessageChange::Removed { message })
+        .chain(
+            added
+                .into_iter()
+                .map(|message| MessageChange::Added { message }),
+        )
+        .collect()
+}
+
+fn preview(line: &str) -> String {
+    match line.char_indices().nth(PREVIEW_CHARS) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+impl Display for ContextDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} → {} messages, {} unchanged",
+            self.previous_messages, self.current_messages, self.unchanged
+        )?;
+        for change in &self.changes {
+            match change {
+                MessageChange::Added { message } => write!(f, "\n+ {message}")?,
+                MessageChange::Removed { message } => write!(f, "\n- {message}")?,
+                MessageChange::Compacted { removed, summary } => {
+                    write!(f, "\n~ {} messages compacted into {summary}", removed.len())?;
+                    for message in removed {
+                        write!(f, "\n    - {message}")?;
+                    }
+                }
+                MessageChange::Truncated { before, after } => write!(
+                    f,
+                    "\n✂ {}: {} truncated from {} to {} chars",
+                    after.role, after.preview, before.chars, after.chars
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{ToolCallId, ToolOutput, ToolResult};
+
+    fn tool_result(output: &str) -> ContextMessage {
+        ContextMessage::tool_result(
+            ToolResult::new("forge_tool_process_shell")
+                .call_id(ToolCallId::new("call_1"))
+                .output(Ok(ToolOutput::text(output))),
+        )
+    }
+
+    #[test]
+    fn test_context_diff() {
+        let previous = Context::default()
+            .add_message(ContextMessage::system("You are Forge"))
+            .add_message(ContextMessage::user("Fix the build", None))
+            .add_message(ContextMessage::assistant("Running the tests", None, None))
+            .add_message(tool_result("error: 3 tests failed\nfirst failure"))
+            .add_message(ContextMessage::assistant("Fixed the tests", None, None));
+        let current = Context::default()
+            .add_message(ContextMessage::system("You are Forge"))
+            .add_message(ContextMessage::user("Summary of the work so far", None))
+            .add_message(ContextMessage::assistant("Fixed the tests", None, None))
+            .add_message(ContextMessage::user("Now run clippy", None));
+
+        let actual = ContextDiff::new(&previous, &current).to_string();
+
+        let expected = "5 → 4 messages, 2 unchanged
+~ 3 messages compacted into User: Summary of the work so far (26 chars)
+    - User: Fix the build (13 chars)
+    - Assistant: Running the tests (17 chars)
+    - Tool: forge_tool_process_shell (35 chars)
++ User: Now run clippy (14 chars)";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_context_diff_truncated() {
+        let previous = Context::default()
+            .add_message(ContextMessage::user("Run the tests", None))
+            .add_message(tool_result("error: 3 tests failed\nfirst failure"));
+        let current = Context::default()
+            .add_message(ContextMessage::user("Run the tests", None))
+            .add_message(tool_result("error: 3 tests failed"));
+
+        let actual = ContextDiff::new(&previous, &current).changes;
+
+        let expected = vec![MessageChange::Truncated {
+            before: MessageSummary {
+                role: "Tool".to_string(),
+                preview: "forge_tool_process_shell".to_string(),
+                chars: 35,
+            },
+            after: MessageSummary {
+                role: "Tool".to_string(),
+                preview: "forge_tool_process_shell".to_string(),
+                chars: 21,
+            },
+        }];
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
 
 use derive_more::derive::Display;
 use derive_setters::Setters;
@@ -8,7 +9,11 @@ use serde_json::Value;
 use uuid::Uuid;
 
 use crate::task::TaskList;
-use crate::{Agent, AgentId, Compact, Context, Error, Event, ModelId, Result, ToolName, Workflow};
+use crate::{
+    Agent, AgentId, Compact, Context, ContextDiff, ContextMessage, ConversationExport, Error,
+    Event, ExportFormat, FileVersions, ModelId, OperationClass, Pins, Result, Role, SessionStats,
+    ToolName, TurnUsage, Workflow,
+};
 
 #[derive(Debug, Default, Display, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 #[serde(transparent)]
@@ -41,8 +46,48 @@ pub struct Conversation {
     pub agents: Vec<Agent>,
     pub events: Vec<Event>,
     pub tasks: TaskList,
+    #[serde(default)]
+    pub file_versions: FileVersions,
+    /// Directory the shell commands of the agents moved to with `cd`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    /// Previews the changes of the tools instead of making them
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Restricts the agents to reading the workspace until the user approves
+    /// the plan they made
+    #[serde(default)]
+    pub plan_mode: bool,
+    /// Classes of operations the user allowed for the rest of the session
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub allowed_operations: BTreeSet<OperationClass>,
+    /// Files and messages that compaction never drops
+    #[serde(default)]
+    pub pins: Pins,
+    /// Tags the session is created with
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Map of the files and definitions of the workspace, generated at the
+    /// start of the conversation and refreshed on demand
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo_map: Option<String>,
+    /// Usage of each request sent to the model, for the stats of the session
+    #[serde(default)]
+    pub turns: Vec<TurnUsage>,
+    /// Whether the conversation is saved as a session, which the ones of
+    /// sub-agents never are
+    #[serde(default)]
+    pub persist: bool,
+    /// Conversation the user started, when this one is of a sub-agent it
+    /// spawned. The agents of the same conversation exchange messages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<ConversationId>,
     pub max_tool_failure_per_turn: Option<usize>,
     pub max_requests_per_turn: Option<usize>,
+    /// Contexts sent in the last two requests to the provider, kept to debug
+    /// what changed between them
+    #[serde(skip)]
+    pub requests: Vec<Context>,
 }
 
 impl Conversation {
@@ -59,9 +104,15 @@ impl Conversation {
             .ok_or(Error::NoModelDefined(agent.id.clone()))
     }
 
-    /// Sets the model for all agents in the conversation
+    /// Sets the model for all agents in the conversation. Agents served by a
+    /// provider of their own keep their model, as it's unlikely to be offered
+    /// by the active provider.
     pub fn set_model(&mut self, model: &ModelId) -> Result<()> {
-        for agent in self.agents.iter_mut() {
+        for agent in self
+            .agents
+            .iter_mut()
+            .filter(|agent| agent.provider.is_none())
+        {
             agent.model = Some(model.clone());
             if let Some(ref mut compact) = agent.compact {
                 compact.model = Some(model.clone());
@@ -149,7 +200,10 @@ impl Conversation {
                 }
             }
 
-            if !additional_tools.is_empty() {
+            // An agent that only excludes tools can already use the additional ones
+            if !additional_tools.is_empty()
+                && (agent.tools.is_some() || agent.exclude_tools.is_none())
+            {
                 agent.tools = Some(
                     agent
                         .tools
@@ -174,11 +228,67 @@ impl Conversation {
             agents,
             events: Default::default(),
             tasks: TaskList::new(),
+            file_versions: Default::default(),
+            cwd: None,
+            dry_run: workflow.dry_run.unwrap_or_default(),
+            plan_mode: false,
+            allowed_operations: Default::default(),
+            pins: Default::default(),
+            tags: Default::default(),
+            repo_map: None,
+            turns: Default::default(),
+            persist: workflow.save_sessions.unwrap_or_default(),
+            root: None,
             max_tool_failure_per_turn: workflow.max_tool_failure_per_turn,
             max_requests_per_turn: workflow.max_requests_per_turn,
+            requests: Default::default(),
         }
     }
 
+    /// A new conversation with the agents and settings of this one, but none of
+    /// its history, e.g. to run a task alongside it
+    pub fn fork(&self) -> Self {
+        Self {
+            id: ConversationId::generate(),
+            archived: false,
+            context: None,
+            variables: self.variables.clone(),
+            agents: self.agents.clone(),
+            events: Default::default(),
+            tasks: TaskList::new(),
+            file_versions: Default::default(),
+            cwd: None,
+            dry_run: self.dry_run,
+            plan_mode: self.plan_mode,
+            allowed_operations: Default::default(),
+            pins: Default::default(),
+            tags: self.tags.clone(),
+            repo_map: self.repo_map.clone(),
+            turns: Default::default(),
+            persist: self.persist,
+            root: None,
+            max_tool_failure_per_turn: self.max_tool_failure_per_turn,
+            max_requests_per_turn: self.max_requests_per_turn,
+            requests: Default::default(),
+        }
+    }
+
+    /// Makes this the conversation of an agent working for the one with the
+    /// root, such as an agent called as a tool or a sub-agent. It isn't saved
+    /// as a session of its own, so that resuming the last session picks up the
+    /// conversation of the user rather than the one of an agent.
+    pub fn delegated(mut self, root: Option<ConversationId>) -> Self {
+        self.persist = false;
+        self.root = root;
+        self
+    }
+
+    /// Returns the conversation the user started, which is this one unless it's
+    /// the one of a sub-agent
+    pub fn root_id(&self) -> ConversationId {
+        self.root.unwrap_or(self.id)
+    }
+
     /// Returns all the agents that are subscribed to the given event.
     pub fn subscriptions(&self, event_name: &str) -> Vec<Agent> {
         self.agents
@@ -231,6 +341,57 @@ impl Conversation {
         self.variables.remove(key).is_some()
     }
 
+    /// Pins the last reply of the agent so that compaction keeps it. Returns
+    /// false when the agent hasn't replied yet.
+    pub fn pin_last_reply(&mut self) -> bool {
+        let reply = self.context.as_ref().and_then(|context| {
+            context
+                .messages
+                .iter()
+                .rev()
+                .find_map(|message| match message {
+                    ContextMessage::Text(text)
+                        if text.has_role(Role::Assistant)
+                            && text.tool_calls.is_none()
+                            && !text.content.trim().is_empty() =>
+                    {
+                        Some(text.content.clone())
+                    }
+                    _ => None,
+                })
+        });
+        match reply {
+            Some(reply) => {
+                self.pins.pin_message(reply);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Keeps the context of a request sent to the provider, forgetting the
+    /// older ones that are no longer needed for a diff
+    pub fn record_request(&mut self, context: Context) -> &mut Self {
+        self.requests.push(context);
+        let stale = self.requests.len().saturating_sub(2);
+        self.requests.drain(..stale);
+        self
+    }
+
+    /// Tokens, cost and time spent over the turns of the conversation
+    pub fn stats(&self) -> SessionStats {
+        SessionStats::aggregate(&self.turns)
+    }
+
+    /// What changed in the context between the last two requests, `None` until
+    /// two requests were sent
+    pub fn context_diff(&self) -> Option<ContextDiff> {
+        match self.requests.as_slice() {
+            [previous, current] => Some(ContextDiff::new(previous, current)),
+            _ => None,
+        }
+    }
+
     /// Generates an HTML representation of the conversation
     ///
     /// This method uses Handlebars to render the conversation as HTML
@@ -245,6 +406,26 @@ impl Conversation {
         crate::conversation_html::render_conversation_html(self)
     }
 
+    /// Renders the context of the conversation as a markdown transcript
+    pub fn to_markdown(&self) -> String {
+        crate::conversation_markdown::render_conversation_markdown(self)
+    }
+
+    /// Renders the context of the conversation in the given format: a
+    /// markdown transcript, a standalone HTML page or JSON in the shape of
+    /// [`ConversationExport`]
+    pub fn export(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::Markdown => self.to_markdown(),
+            ExportFormat::Html => {
+                crate::conversation_transcript::render_conversation_transcript(self)
+            }
+            ExportFormat::Json => {
+                serde_json::to_string_pretty(&ConversationExport::from(self)).unwrap_or_default()
+            }
+        }
+    }
+
     /// Add an event to the conversation
     pub fn insert_event(&mut self, event: Event) -> &mut Self {
         self.events.push(event);
@@ -279,7 +460,8 @@ mod tests {
     use serde_json::json;
 
     use crate::{
-        Agent, AgentId, Command, Compact, Error, MaxTokens, ModelId, Temperature, Workflow,
+        Agent, AgentId, Command, Compact, Error, MaxTokens, ModelId, ProviderDetails, Temperature,
+        Workflow,
     };
 
     #[test]
@@ -300,6 +482,33 @@ mod tests {
         assert!(conversation.events.is_empty());
     }
 
+    #[test]
+    fn test_conversation_is_saved_only_when_enabled() {
+        let id = super::ConversationId::generate();
+        let enabled = Workflow::new().save_sessions(true);
+
+        let actual = (
+            super::Conversation::new_inner(id, Workflow::new(), vec![]).persist,
+            super::Conversation::new_inner(id, enabled, vec![]).persist,
+        );
+
+        let expected = (false, true);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_delegated_conversation_isnt_saved() {
+        let root = super::ConversationId::generate();
+        let workflow = Workflow::new().save_sessions(true);
+
+        let actual =
+            super::Conversation::new_inner(super::ConversationId::generate(), workflow, vec![])
+                .delegated(Some(root));
+
+        let expected = (false, Some(root));
+        assert_eq!((actual.persist, actual.root), expected);
+    }
+
     #[test]
     fn test_conversation_new_with_workflow_variables() {
         // Arrange
@@ -1122,4 +1331,103 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_set_model_keeps_agent_provider_model() {
+        let workflow = Workflow::new().agents(vec![
+            Agent::new("agent-1").model(ModelId::new("sonnet-4")),
+            Agent::new("agent-2")
+                .model(ModelId::new("gpt-4o-mini"))
+                .provider(ProviderDetails::new("openai")),
+        ]);
+
+        let id = super::ConversationId::generate();
+        let mut conversation = super::Conversation::new_inner(id.clone(), workflow, vec![]);
+
+        conversation.set_model(&ModelId::new("qwen-2")).unwrap();
+
+        let actual = conversation
+            .agents
+            .iter()
+            .map(|agent| agent.model.clone())
+            .collect::<Vec<_>>();
+        let expected = vec![
+            Some(ModelId::new("qwen-2")),
+            Some(ModelId::new("gpt-4o-mini")),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_fork() {
+        let id = super::ConversationId::generate();
+        let mut fixture = super::Conversation::new_inner(
+            id,
+            Workflow::new().agents(vec![Agent::new("forge")]),
+            vec![],
+        );
+        fixture.dry_run = true;
+        fixture.context = Some(crate::Context::default());
+        fixture.insert_event(crate::Event::new("forge/user_task_init", Some("Hi")));
+
+        let actual = fixture.fork();
+
+        assert_ne!(actual.id, fixture.id);
+        assert_eq!(
+            actual
+                .agents
+                .iter()
+                .map(|agent| &agent.id)
+                .collect::<Vec<_>>(),
+            vec![&AgentId::new("forge")]
+        );
+        assert!(actual.dry_run);
+        assert!(actual.context.is_none());
+        assert!(actual.events.is_empty());
+    }
+
+    #[test]
+    fn test_pin_last_reply() {
+        let id = super::ConversationId::generate();
+        let mut fixture = super::Conversation::new_inner(id, Workflow::new(), vec![]);
+        fixture.context = Some(
+            crate::Context::default()
+                .add_message(crate::ContextMessage::user("Which indent?", None))
+                .add_message(crate::ContextMessage::assistant("Use tabs", None, None))
+                .add_message(crate::ContextMessage::assistant(
+                    "",
+                    None,
+                    Some(vec![crate::ToolCallFull::new("forge_tool_fs_read")]),
+                )),
+        );
+
+        let actual = fixture.pin_last_reply();
+
+        assert!(actual);
+        assert_eq!(fixture.pins.messages, vec!["Use tabs".to_string()]);
+    }
+
+    #[test]
+    fn test_record_request_keeps_last_two() {
+        let id = super::ConversationId::generate();
+        let mut fixture = super::Conversation::new_inner(id, Workflow::new(), vec![]);
+        let first = crate::Context::default()
+            .add_message(crate::ContextMessage::user("Fix the build", None));
+        let second = first
+            .clone()
+            .add_message(crate::ContextMessage::assistant("Done", None, None));
+        let third = second
+            .clone()
+            .add_message(crate::ContextMessage::user("Thanks", None));
+
+        fixture
+            .record_request(first)
+            .record_request(second.clone())
+            .record_request(third.clone());
+        let actual = fixture.context_diff();
+
+        let expected = Some(crate::ContextDiff::new(&second, &third));
+        assert_eq!(fixture.requests, vec![second, third]);
+        assert_eq!(actual, expected);
+    }
 }
@@ -0,0 +1,234 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use strum_macros::EnumString;
+
+use crate::context::{ContextMessage, Role};
+use crate::conversation::{Conversation, ConversationId};
+use crate::{ModelId, ToolCallFull, ToolCallId, ToolName, ToolValue};
+
+/// Version of the JSON export, bumped whenever a change could break the tools
+/// reading it
+const EXPORT_VERSION: u32 = 1;
+
+/// Formats a conversation can be exported to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, EnumString)]
+#[strum(ascii_case_insensitive)]
+pub enum ExportFormat {
+    #[default]
+    #[strum(serialize = "md", serialize = "markdown")]
+    Markdown,
+    #[strum(serialize = "html", serialize = "htm")]
+    Html,
+    #[strum(serialize = "json")]
+    Json,
+}
+
+impl ExportFormat {
+    /// Format matching the extension of the path, if it has a known one
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// Context of a conversation in a stable shape, for tools consuming exports.
+/// Unlike the conversation itself, which changes with forge, fields are only
+/// added to it without bumping the version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConversationExport {
+    pub version: u32,
+    pub id: ConversationId,
+    pub messages: Vec<ExportedMessage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "role", rename_all = "snake_case")]
+pub enum ExportedMessage {
+    System {
+        content: String,
+    },
+    User {
+        content: String,
+    },
+    Assistant {
+        content: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        model: Option<ModelId>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tool_calls: Vec<ExportedToolCall>,
+    },
+    Tool {
+        name: ToolName,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        call_id: Option<ToolCallId>,
+        is_error: bool,
+        output: String,
+    },
+    Image {
+        mime_type: String,
+        url: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedToolCall {
+    pub name: ToolName,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub call_id: Option<ToolCallId>,
+    pub arguments: Value,
+}
+
+impl From<&ToolCallFull> for ExportedToolCall {
+    fn from(call: &ToolCallFull) -> Self {
+        Self {
+            name: call.name.clone(),
+            call_id: call.call_id.clone(),
+            arguments: call.arguments.clone(),
+        }
+    }
+}
+
+impl From<&ContextMessage> for ExportedMessage {
+    fn from(message: &ContextMessage) -> Self {
+        match message {
+            ContextMessage::Text(text) => {
+                let content = text.content.clone();
+                match text.role {
+                    Role::System => ExportedMessage::System { content },
+                    Role::User => ExportedMessage::User { content },
+                    Role::Assistant => ExportedMessage::Assistant {
+                        content,
+                        model: text.model.clone(),
+                        tool_calls: text
+                            .tool_calls
+                            .iter()
+                            .flatten()
+                            .map(ExportedToolCall::from)
+                            .collect(),
+                    },
+                }
+            }
+            ContextMessage::Tool(result) => ExportedMessage::Tool {
+                name: result.name.clone(),
+                call_id: result.call_id.clone(),
+                is_error: result.is_error(),
+                output: result
+                    .output
+                    .values
+                    .iter()
+                    .filter_map(|value| match value {
+                        ToolValue::Text(text) => Some(text.as_str()),
+                        ToolValue::Image(_) => Some("[image]"),
+                        ToolValue::Empty => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            },
+            ContextMessage::Image(image) => ExportedMessage::Image {
+                mime_type: image.mime_type().clone(),
+                url: image.url().clone(),
+            },
+        }
+    }
+}
+
+impl From<&Conversation> for ConversationExport {
+    fn from(conversation: &Conversation) -> Self {
+        Self {
+            version: EXPORT_VERSION,
+            id: conversation.id,
+            messages: conversation
+                .context
+                .iter()
+                .flat_map(|context| context.messages.iter())
+                .map(ExportedMessage::from)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Context, ToolOutput, ToolResult, Workflow};
+
+    #[test]
+    fn test_conversation_export() {
+        let id = ConversationId::parse("5d3a8f1e-2b9c-4e7a-9f61-0c8d2e4b7a13").unwrap();
+        let context = Context::default()
+            .add_message(ContextMessage::system("You are Forge"))
+            .add_message(ContextMessage::user("Fix the build", None))
+            .add_message(ContextMessage::assistant(
+                "Reading the manifest",
+                None,
+                Some(vec![
+                    ToolCallFull::new("forge_tool_fs_read")
+                        .call_id(ToolCallId::new("call_1"))
+                        .arguments(json!({"path": "Cargo.toml"})),
+                ]),
+            ))
+            .add_message(ContextMessage::tool_result(
+                ToolResult::new("forge_tool_fs_read")
+                    .call_id(ToolCallId::new("call_1"))
+                    .output(Ok(ToolOutput::text("[package]"))),
+            ));
+        let fixture =
+            Conversation::new(id, Workflow::new(), Default::default()).context(Some(context));
+
+        let actual = serde_json::to_value(ConversationExport::from(&fixture)).unwrap();
+
+        let expected = json!({
+            "version": 1,
+            "id": "5d3a8f1e-2b9c-4e7a-9f61-0c8d2e4b7a13",
+            "messages": [
+                {"role": "system", "content": "You are Forge"},
+                {"role": "user", "content": "Fix the build"},
+                {
+                    "role": "assistant",
+                    "content": "Reading the manifest",
+                    "tool_calls": [
+                        {"name": "forge_tool_fs_read", "call_id": "call_1", "arguments": {"path": "Cargo.toml"}}
+                    ]
+                },
+                {
+                    "role": "tool",
+                    "name": "forge_tool_fs_read",
+                    "call_id": "call_1",
+                    "is_error": false,
+                    "output": "[package]"
+                }
+            ]
+        });
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_export_format_from_path() {
+        let actual = [
+            ExportFormat::from_path(Path::new("notes/session.HTML")),
+            ExportFormat::from_path(Path::new("session.json")),
+            ExportFormat::from_path(Path::new("session.md")),
+            ExportFormat::from_path(Path::new("session.txt")),
+        ];
+
+        let expected = [
+            Some(ExportFormat::Html),
+            Some(ExportFormat::Json),
+            Some(ExportFormat::Markdown),
+            None,
+        ];
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::context::{ContextMessage, Role, TextMessage};
+use crate::{Context, ModelId};
+
+/// Tools whose conversations can be imported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// Session file of Claude Code, one JSON event per line, as found under
+    /// `~/.claude/projects`
+    ClaudeCode,
+    /// `conversations.json` of a ChatGPT data export, or a single
+    /// conversation of it
+    OpenAi,
+}
+
+impl ImportFormat {
+    /// Format of the content, told apart by OpenAI exports being a single JSON
+    /// document holding a `mapping` of the messages
+    pub fn detect(content: &str) -> Self {
+        match serde_json::from_str::<Value>(content) {
+            Ok(Value::Array(_)) => Self::OpenAi,
+            Ok(Value::Object(object)) if object.contains_key("mapping") => Self::OpenAi,
+            _ => Self::ClaudeCode,
+        }
+    }
+}
+
+/// Conversation of another tool converted to a context forge can continue.
+/// Only the text of the messages is kept: the tools of other agents don't
+/// exist in forge, so their calls are noted in the answers and their results
+/// are left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedConversation {
+    pub title: Option<String>,
+    pub context: Context,
+}
+
+impl ImportedConversation {
+    /// Reads the conversations of the content, skipping those without any
+    /// message
+    pub fn parse(content: &str, format: ImportFormat) -> anyhow::Result<Vec<Self>> {
+        let conversations = match format {
+            ImportFormat::ClaudeCode => vec![Self::from_claude_code(content)],
+            ImportFormat::OpenAi => {
+                let export: OpenAiExport = serde_json::from_str(content)?;
+                match export {
+                    OpenAiExport::Many(conversations) => conversations,
+                    OpenAiExport::One(conversation) => vec![conversation],
+                }
+                .iter()
+                .map(Self::from_openai)
+                .collect()
+            }
+        };
+
+        Ok(conversations
+            .into_iter()
+            .filter(|conversation| !conversation.context.messages.is_empty())
+            .collect())
+    }
+
+    fn from_claude_code(content: &str) -> Self {
+        let mut title = None;
+        let mut messages = Vec::new();
+        // Lines of events added by newer versions are skipped
+        for line in content.lines() {
+            match serde_json::from_str(line) {
+                Ok(ClaudeCodeLine::User { message, is_sidechain, is_meta })
+                    if !is_sidechain && !is_meta =>
+                {
+                    push_message(&mut messages, Role::User, message.text(), None)
+                }
+                Ok(ClaudeCodeLine::Assistant { message, is_sidechain }) if !is_sidechain => {
+                    let model = message.model.clone();
+                    push_message(&mut messages, Role::Assistant, message.text(), model)
+                }
+                Ok(ClaudeCodeLine::Summary { summary }) => title = Some(summary),
+                _ => {}
+            }
+        }
+
+        Self { title, context: Context::default().messages(messages) }
+    }
+
+    fn from_openai(conversation: &OpenAiConversation) -> Self {
+        // Edited prompts branch the conversation, the current node being the
+        // last message of the branch shown
+        let mut path = Vec::new();
+        let mut node = conversation.current_node.as_ref();
+        while let Some(id) = node {
+            let Some(current) = conversation.mapping.get(id) else {
+                break;
+            };
+            if path.len() > conversation.mapping.len() {
+                break;
+            }
+            path.push(current);
+            node = current.parent.as_ref();
+        }
+
+        let mut messages = Vec::new();
+        for message in path
+            .into_iter()
+            .rev()
+            .filter_map(|node| node.message.as_ref())
+        {
+            if message.metadata.is_visually_hidden_from_conversation {
+                continue;
+            }
+            let role = match message.author.role.as_str() {
+                "user" => Role::User,
+                "assistant" => Role::Assistant,
+                _ => continue,
+            };
+            let model = message.metadata.model_slug.clone();
+            push_message(&mut messages, role, message.content.text(), model);
+        }
+
+        Self {
+            title: conversation.title.clone(),
+            context: Context::default().messages(messages),
+        }
+    }
+}
+
+/// Adds the text to the messages, appending it to the last one when it has the
+/// same role, as the messages of other tools are split into several events
+fn push_message(
+    messages: &mut Vec<ContextMessage>,
+    role: Role,
+    content: String,
+    model: Option<ModelId>,
+) {
+    let content = content.trim();
+    if content.is_empty() {
+        return;
+    }
+
+    match messages.last_mut() {
+        Some(ContextMessage::Text(last)) if last.role == role => {
+            last.content = format!("{}\n\n{content}", last.content);
+            last.model = last.model.take().or(model);
+        }
+        _ => messages.push(ContextMessage::Text(TextMessage {
+            role,
+            content: content.to_string(),
+            tool_calls: None,
+            reasoning_details: None,
+            model,
+        })),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClaudeCodeLine {
+    User {
+        message: ClaudeCodeMessage,
+        #[serde(default, rename = "isSidechain")]
+        is_sidechain: bool,
+        #[serde(default, rename = "isMeta")]
+        is_meta: bool,
+    },
+    Assistant {
+        message: ClaudeCodeMessage,
+        #[serde(default, rename = "isSidechain")]
+        is_sidechain: bool,
+    },
+    Summary {
+        summary: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct ClaudeCodeMessage {
+    #[serde(default)]
+    model: Option<ModelId>,
+    content: ClaudeCodeContent,
+}
+
+impl ClaudeCodeMessage {
+    fn text(&self) -> String {
+        match &self.content {
+            ClaudeCodeContent::Text(text) => text.clone(),
+            ClaudeCodeContent::Blocks(blocks) => blocks
+                .iter()
+                .filter_map(|block| match block {
+                    ClaudeCodeBlock::Text { text } => Some(text.clone()),
+                    ClaudeCodeBlock::ToolUse { name, input } => {
+                        Some(format!("Called `{name}` with `{input}`"))
+                    }
+                    ClaudeCodeBlock::Other => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ClaudeCodeContent {
+    Text(String),
+    Blocks(Vec<ClaudeCodeBlock>),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClaudeCodeBlock {
+    Text {
+        text: String,
+    },
+    ToolUse {
+        name: String,
+        input: Value,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OpenAiExport {
+    Many(Vec<OpenAiConversation>),
+    One(OpenAiConversation),
+}
+
+#[derive(Deserialize)]
+struct OpenAiConversation {
+    #[serde(default)]
+    title: Option<String>,
+    mapping: HashMap<String, OpenAiNode>,
+    #[serde(default)]
+    current_node: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiNode {
+    #[serde(default)]
+    parent: Option<String>,
+    #[serde(default)]
+    message: Option<OpenAiMessage>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiMessage {
+    author: OpenAiAuthor,
+    content: OpenAiContent,
+    #[serde(default)]
+    metadata: OpenAiMetadata,
+}
+
+#[derive(Deserialize)]
+struct OpenAiAuthor {
+    role: String,
+}
+
+#[derive(Deserialize)]
+struct OpenAiContent {
+    #[serde(default)]
+    parts: Vec<Value>,
+    #[serde(default)]
+    text: Option<String>,
+}
+
+impl OpenAiContent {
+    /// Text of the content, images and other attachments being left out
+    fn text(&self) -> String {
+        let parts = self
+            .parts
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>();
+        if parts.is_empty() {
+            self.text.clone().unwrap_or_default()
+        } else {
+            parts.join("\n")
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+struct OpenAiMetadata {
+    #[serde(default)]
+    model_slug: Option<ModelId>,
+    #[serde(default)]
+    is_visually_hidden_from_conversation: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_claude_code_session() {
+        let fixture = [
+            json!({"type": "summary", "summary": "Fix the build", "leafUuid": "1"}),
+            json!({"type": "user", "isMeta": true, "message": {"role": "user", "content": "Caveat"}}),
+            json!({"type": "user", "message": {"role": "user", "content": "Fix the build"}}),
+            json!({"type": "assistant", "message": {"id": "msg_1", "model": "claude-sonnet-4", "role": "assistant", "content": [
+                {"type": "thinking", "thinking": "Let me look"},
+                {"type": "text", "text": "Reading the manifest"}
+            ]}}),
+            json!({"type": "assistant", "message": {"id": "msg_1", "model": "claude-sonnet-4", "role": "assistant", "content": [
+                {"type": "tool_use", "id": "toolu_1", "name": "Read", "input": {"file_path": "Cargo.toml"}}
+            ]}}),
+            json!({"type": "user", "message": {"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "toolu_1", "content": "[package]"}
+            ]}}),
+            json!({"type": "assistant", "isSidechain": true, "message": {"role": "assistant", "content": "Subagent"}}),
+            json!({"type": "assistant", "message": {"id": "msg_2", "model": "claude-sonnet-4", "role": "assistant", "content": [
+                {"type": "text", "text": "Fixed"}
+            ]}}),
+        ]
+        .map(|line| line.to_string())
+        .join("\n");
+
+        let actual = ImportedConversation::parse(&fixture, ImportFormat::detect(&fixture)).unwrap();
+
+        let model = Some(ModelId::new("claude-sonnet-4"));
+        let expected = vec![ImportedConversation {
+            title: Some("Fix the build".to_string()),
+            context: Context::default()
+                .add_message(ContextMessage::user("Fix the build", None))
+                .add_message(ContextMessage::Text(TextMessage::assistant(
+                    "Reading the manifest\n\nCalled `Read` with `{\"file_path\":\"Cargo.toml\"}`\n\nFixed",
+                    None,
+                    model,
+                ))),
+        }];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_openai_export() {
+        let fixture = json!([
+            {
+                "title": "Rust lifetimes",
+                "current_node": "3",
+                "mapping": {
+                    "0": {"id": "0", "parent": null, "message": null},
+                    "1": {"id": "1", "parent": "0", "message": {
+                        "author": {"role": "system"},
+                        "content": {"content_type": "text", "parts": [""]},
+                        "metadata": {"is_visually_hidden_from_conversation": true}
+                    }},
+                    "2": {"id": "2", "parent": "1", "message": {
+                        "author": {"role": "user"},
+                        "content": {"content_type": "text", "parts": ["What is 'a?"]},
+                        "metadata": {}
+                    }},
+                    "2b": {"id": "2b", "parent": "2", "message": {
+                        "author": {"role": "assistant"},
+                        "content": {"content_type": "text", "parts": ["Regenerated"]},
+                        "metadata": {}
+                    }},
+                    "3": {"id": "3", "parent": "2", "message": {
+                        "author": {"role": "assistant"},
+                        "content": {"content_type": "text", "parts": ["A lifetime"]},
+                        "metadata": {"model_slug": "gpt-4o"}
+                    }}
+                }
+            },
+            {"title": "Empty", "current_node": null, "mapping": {}}
+        ])
+        .to_string();
+
+        let actual = ImportedConversation::parse(&fixture, ImportFormat::detect(&fixture)).unwrap();
+
+        let expected = vec![ImportedConversation {
+            title: Some("Rust lifetimes".to_string()),
+            context: Context::default()
+                .add_message(ContextMessage::user("What is 'a?", None))
+                .add_message(ContextMessage::Text(TextMessage::assistant(
+                    "A lifetime",
+                    None,
+                    Some(ModelId::new("gpt-4o")),
+                ))),
+        }];
+        assert_eq!(actual, expected);
+    }
+}
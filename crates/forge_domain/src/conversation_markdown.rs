@@ -0,0 +1,220 @@
+use crate::context::{ContextMessage, Role};
+use crate::conversation::Conversation;
+use crate::{ToolResult, ToolValue};
+
+/// Renders the context of the conversation as a markdown transcript. Tool
+/// outputs are collapsed and attached files are listed instead of inlined.
+pub fn render_conversation_markdown(conversation: &Conversation) -> String {
+    let mut sections = vec![format!("# Conversation {}", conversation.id)];
+    let messages = conversation
+        .context
+        .as_ref()
+        .map(|context| context.messages.as_slice())
+        .unwrap_or_default();
+
+    let attachments = messages
+        .iter()
+        .filter_map(attached_path)
+        .map(|path| format!("- `{path}`"))
+        .collect::<Vec<_>>();
+    if !attachments.is_empty() {
+        sections.push(format!("## Attachments\n\n{}", attachments.join("\n")));
+    }
+
+    if messages.is_empty() {
+        sections.push("_No messages yet._".to_string());
+    }
+    sections.extend(messages.iter().map(render_message));
+
+    sections.join("\n\n") + "\n"
+}
+
+fn render_message(message: &ContextMessage) -> String {
+    match message {
+        ContextMessage::Text(text) => {
+            if let Some(path) = attached_path(message) {
+                return format!("## User\n\n_Attached `{path}`_");
+            }
+            let mut section = format!("## {}", text.role);
+            let content = text.content.trim();
+            if text.role == Role::System {
+                section.push_str(&format!(
+                    "\n\n{}",
+                    details("System prompt", &fenced(content, "markdown"))
+                ));
+            } else if !content.is_empty() {
+                section.push_str(&format!("\n\n{content}"));
+            }
+            for call in text.tool_calls.iter().flatten() {
+                section.push_str(&format!(
+                    "\n\n**Tool call** `{}`\n\n{}",
+                    call.name,
+                    fenced(
+                        &serde_json::to_string_pretty(&call.arguments).unwrap_or_default(),
+                        "json"
+                    )
+                ));
+            }
+            section
+        }
+        ContextMessage::Tool(result) => render_tool_result(result),
+        ContextMessage::Image(_) => "## User\n\n_Image_".to_string(),
+    }
+}
+
+fn render_tool_result(result: &ToolResult) -> String {
+    let output = result
+        .output
+        .values
+        .iter()
+        .filter_map(|value| match value {
+            ToolValue::Text(text) => Some(text.as_str()),
+            ToolValue::Image(_) => Some("[image]"),
+            ToolValue::Empty => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let summary = if result.is_error() {
+        format!("Tool output `{}` (failed)", result.name)
+    } else {
+        format!("Tool output `{}`", result.name)
+    };
+    format!("## Tool\n\n{}", details(&summary, &fenced(&output, "")))
+}
+
+/// Path of the file attached by the message, if it holds one
+pub(crate) fn attached_path(message: &ContextMessage) -> Option<&str> {
+    match message {
+        ContextMessage::Text(text)
+            if text.role == Role::User && text.content.starts_with("<file_content") =>
+        {
+            let (_, rest) = text.content.split_once("path=\"")?;
+            rest.split_once('"').map(|(path, _)| path)
+        }
+        _ => None,
+    }
+}
+
+fn details(summary: &str, content: &str) -> String {
+    format!("<details>\n<summary>{summary}</summary>\n\n{content}\n\n</details>")
+}
+
+/// Fences the content with more backticks than it holds in a row, so that
+/// code blocks in it don't end the fence early
+fn fenced(content: &str, language: &str) -> String {
+    let longest = content
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{fence}{language}\n{content}\n{fence}")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Context, ConversationId, ToolCallFull, ToolCallId, ToolOutput, Workflow};
+
+    #[test]
+    fn test_render_conversation_markdown() {
+        let id = ConversationId::parse("5d3a8f1e-2b9c-4e7a-9f61-0c8d2e4b7a13").unwrap();
+        let context = Context::default()
+            .add_message(ContextMessage::system("You are Forge"))
+            .add_message(ContextMessage::user("Fix the build", None))
+            .add_message(ContextMessage::user(
+                "<file_content\n  path=\"/project/src/lib.rs\"\n  start_line=\"1\"\n>",
+                None,
+            ))
+            .add_message(ContextMessage::assistant(
+                "Reading the manifest",
+                None,
+                Some(vec![
+                    ToolCallFull::new("forge_tool_fs_read")
+                        .call_id(ToolCallId::new("call_1"))
+                        .arguments(json!({"path": "Cargo.toml"})),
+                ]),
+            ))
+            .add_message(ContextMessage::tool_result(
+                ToolResult::new("forge_tool_fs_read")
+                    .call_id(ToolCallId::new("call_1"))
+                    .output(Ok(ToolOutput::text("```toml\n[package]\n```"))),
+            ))
+            .add_message(ContextMessage::assistant("Fixed", None, None));
+        let fixture =
+            Conversation::new(id, Workflow::new(), Default::default()).context(Some(context));
+
+        let actual = render_conversation_markdown(&fixture);
+
+        let expected = r#"# Conversation 5d3a8f1e-2b9c-4e7a-9f61-0c8d2e4b7a13
+
+## Attachments
+
+- `/project/src/lib.rs`
+
+## System
+
+<details>
+<summary>System prompt</summary>
+
+```markdown
+You are Forge
+```
+
+</details>
+
+## User
+
+Fix the build
+
+## User
+
+_Attached `/project/src/lib.rs`_
+
+## Assistant
+
+Reading the manifest
+
+**Tool call** `forge_tool_fs_read`
+
+```json
+{
+  "path": "Cargo.toml"
+}
+```
+
+## Tool
+
+<details>
+<summary>Tool output `forge_tool_fs_read`</summary>
+
+````
+```toml
+[package]
+```
+````
+
+</details>
+
+## Assistant
+
+Fixed
+"#;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_render_conversation_markdown_without_context() {
+        let id = ConversationId::parse("5d3a8f1e-2b9c-4e7a-9f61-0c8d2e4b7a13").unwrap();
+        let fixture = Conversation::new(id, Workflow::new(), Default::default());
+
+        let actual = render_conversation_markdown(&fixture);
+
+        let expected =
+            "# Conversation 5d3a8f1e-2b9c-4e7a-9f61-0c8d2e4b7a13\n\n_No messages yet._\n";
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,325 @@
+use forge_template::Element;
+
+use crate::context::{ContextMessage, Role};
+use crate::conversation::Conversation;
+use crate::conversation_markdown::attached_path;
+use crate::{ToolCallFull, ToolResult, ToolValue};
+
+/// Words highlighted as keywords in code blocks, whatever their language
+const KEYWORDS: &[&str] = &[
+    "as",
+    "async",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "def",
+    "else",
+    "enum",
+    "except",
+    "export",
+    "false",
+    "fn",
+    "for",
+    "from",
+    "func",
+    "function",
+    "if",
+    "impl",
+    "import",
+    "in",
+    "interface",
+    "let",
+    "loop",
+    "match",
+    "mod",
+    "mut",
+    "new",
+    "null",
+    "pub",
+    "raise",
+    "return",
+    "self",
+    "static",
+    "struct",
+    "this",
+    "trait",
+    "true",
+    "try",
+    "type",
+    "use",
+    "var",
+    "where",
+    "while",
+    "with",
+    "yield",
+    "None",
+    "Self",
+    "True",
+    "False",
+];
+
+/// Renders the context of the conversation as a standalone HTML page. Code
+/// blocks are highlighted, and tool calls and their outputs are collapsed.
+pub fn render_conversation_transcript(conversation: &Conversation) -> String {
+    let messages = conversation
+        .context
+        .as_ref()
+        .map(|context| context.messages.as_slice())
+        .unwrap_or_default();
+
+    let body = Element::new("body")
+        .append(Element::new("h1").text(format!("Conversation {}", conversation.id)))
+        .append(
+            messages
+                .is_empty()
+                .then(|| Element::new("p").text("No messages yet.")),
+        )
+        .append(messages.iter().map(render_message));
+
+    let html = Element::new("html")
+        .attr("lang", "en")
+        .append(
+            Element::new("head")
+                .append(Element::new("meta").attr("charset", "UTF-8"))
+                .append(
+                    Element::new("meta")
+                        .attr("name", "viewport")
+                        .attr("content", "width=device-width, initial-scale=1.0"),
+                )
+                .append(Element::new("title").text(format!("Conversation {}", conversation.id)))
+                .append(Element::new("style").text(include_str!("conversation_transcript.css"))),
+        )
+        .append(body);
+
+    format!("<!DOCTYPE html>\n{}\n", html.render())
+}
+
+fn render_message(message: &ContextMessage) -> Element {
+    match message {
+        ContextMessage::Text(text) => {
+            if let Some(path) = attached_path(message) {
+                return Element::new("details.message.message-user")
+                    .append(Element::new("summary").text(format!("Attached {path}")))
+                    .append(Element::new("pre").text(&text.content));
+            }
+            if text.role == Role::System {
+                return Element::new("details.message.message-system")
+                    .append(Element::new("summary").text("System prompt"))
+                    .append(render_content(&text.content));
+            }
+
+            let role = text.role.to_string();
+            Element::new(format!("div.message.message-{}", role.to_lowercase()))
+                .append(
+                    Element::new("div.role").text(role).append(
+                        text.model
+                            .as_ref()
+                            .map(|model| Element::new("span.model").text(model)),
+                    ),
+                )
+                .append(render_content(&text.content))
+                .append(text.tool_calls.iter().flatten().map(render_tool_call))
+        }
+        ContextMessage::Tool(result) => render_tool_result(result),
+        ContextMessage::Image(image) => Element::new("div.message.message-user")
+            .append(Element::new("div.role").text("User"))
+            .append(Element::new("img").attr("src", image.url())),
+    }
+}
+
+fn render_tool_call(call: &ToolCallFull) -> Element {
+    let arguments = serde_json::to_string_pretty(&call.arguments).unwrap_or_default();
+    Element::new("details.tool-call")
+        .append(
+            Element::new("summary")
+                .text("Tool call ")
+                .append(Element::new("code").text(&call.name)),
+        )
+        .append(Element::new("pre").html(highlight(&arguments, "json")))
+}
+
+fn render_tool_result(result: &ToolResult) -> Element {
+    let output = result
+        .output
+        .values
+        .iter()
+        .filter_map(|value| match value {
+            ToolValue::Text(text) => Some(text.as_str()),
+            ToolValue::Image(_) => Some("[image]"),
+            ToolValue::Empty => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let (name, summary) = if result.is_error() {
+        ("details.tool-result.tool-error", "Tool output (failed) ")
+    } else {
+        ("details.tool-result", "Tool output ")
+    };
+    Element::new(name)
+        .append(
+            Element::new("summary")
+                .text(summary)
+                .append(Element::new("code").text(&result.name)),
+        )
+        .append(Element::new("pre").text(output))
+}
+
+/// Renders the paragraphs of the content, highlighting its fenced code blocks
+fn render_content(content: &str) -> Vec<Element> {
+    let mut elements = Vec::new();
+    let mut text = Vec::new();
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        let Some(fence) = fence_of(line) else {
+            text.push(line);
+            continue;
+        };
+
+        elements.extend(render_text(&text.join("\n")));
+        text.clear();
+        let language = line.trim_start()[fence.len()..].trim();
+        let code = lines
+            .by_ref()
+            .take_while(|line| fence_of(line).is_none_or(|end| end.len() < fence.len()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        elements.push(
+            Element::new("div.code-block")
+                .append((!language.is_empty()).then(|| Element::new("div.language").text(language)))
+                .append(Element::new("pre").html(highlight(&code, language))),
+        );
+    }
+    elements.extend(render_text(&text.join("\n")));
+    elements
+}
+
+fn render_text(text: &str) -> Option<Element> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| Element::new("p").text(text))
+}
+
+/// Backticks opening or closing a fenced code block on the line
+fn fence_of(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    let length = line.chars().take_while(|c| *c == '`').count();
+    (length >= 3).then(|| &line[..length])
+}
+
+/// Wraps the comments, strings, numbers and keywords of the code in spans,
+/// escaping the rest of it
+fn highlight(code: &str, language: &str) -> String {
+    let hash_comments = matches!(
+        language,
+        "sh" | "bash" | "shell" | "zsh" | "python" | "py" | "toml" | "yaml" | "yml" | "ruby"
+    );
+    // Quotes also start lifetimes and chars in Rust
+    let single_quotes = !matches!(language, "rust" | "rs");
+
+    let chars = code.chars().collect::<Vec<_>>();
+    let mut html = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i];
+        let class = if (c == '/' && chars.get(i + 1) == Some(&'/')) || (c == '#' && hash_comments) {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            Some("comment")
+        } else if c == '"' || (c == '\'' && single_quotes) {
+            i += 1;
+            while i < chars.len() && chars[i] != c && chars[i] != '\n' {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            i = (i + 1).min(chars.len());
+            Some("string")
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                i += 1;
+            }
+            Some("number")
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word = chars[start..i].iter().collect::<String>();
+            KEYWORDS.contains(&word.as_str()).then_some("keyword")
+        } else {
+            i += 1;
+            None
+        };
+
+        let token = escape(&chars[start..i].iter().collect::<String>());
+        match class {
+            Some(class) => html.push_str(&format!("<span class=\"tok-{class}\">{token}</span>")),
+            None => html.push_str(&token),
+        }
+    }
+    html
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Context, ConversationId, ToolCallId, ToolOutput, Workflow};
+
+    #[test]
+    fn test_highlight() {
+        let fixture = "let x = \"a<b\"; // 42";
+
+        let actual = highlight(fixture, "rust");
+
+        let expected = "<span class=\"tok-keyword\">let</span> x = <span class=\"tok-string\">&quot;a&lt;b&quot;</span>; <span class=\"tok-comment\">// 42</span>";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_render_conversation_transcript() {
+        let id = ConversationId::parse("5d3a8f1e-2b9c-4e7a-9f61-0c8d2e4b7a13").unwrap();
+        let context = Context::default()
+            .add_message(ContextMessage::user("Fix the build", None))
+            .add_message(ContextMessage::assistant(
+                "Run this:\n```sh\ncargo build\n```",
+                None,
+                Some(vec![
+                    ToolCallFull::new("forge_tool_process_shell")
+                        .call_id(ToolCallId::new("call_1"))
+                        .arguments(json!({"command": "cargo build"})),
+                ]),
+            ))
+            .add_message(ContextMessage::tool_result(
+                ToolResult::new("forge_tool_process_shell")
+                    .call_id(ToolCallId::new("call_1"))
+                    .output(Ok(ToolOutput::text("error[E0425]").is_error(true))),
+            ));
+        let fixture =
+            Conversation::new(id, Workflow::new(), Default::default()).context(Some(context));
+
+        let actual = render_conversation_transcript(&fixture);
+
+        assert!(actual.starts_with("<!DOCTYPE html>"));
+        assert!(actual.contains("<p>Fix the build</p>"));
+        assert!(actual.contains("<p>Run this:</p>"));
+        assert!(actual.contains("<pre>cargo build</pre>"));
+        assert!(actual.contains("class=\"tool-call\""));
+        assert!(actual.contains("class=\"tool-result tool-error\""));
+        assert!(actual.contains("<pre>error[E0425]</pre>"));
+    }
+}
@@ -0,0 +1,216 @@
+use anyhow::Context;
+use derive_setters::Setters;
+use merge::Merge;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::merge::Key;
+use crate::{RetryPolicy, ShellKind, ToolDefinition, ToolName};
+
+/// A tool defined in forge.yaml that runs a shell command
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Merge, Setters, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct CustomTool {
+    /// Name of the tool as seen by the agents. It also has to be listed in the
+    /// `tools` of every agent that should be able to use it.
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub name: ToolName,
+
+    /// Description of what the tool does, shown to the model
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub description: String,
+
+    /// JSON schema of the tool arguments. If not specified, the tool takes no
+    /// arguments.
+    #[serde(default = "default_input_schema")]
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub input_schema: Value,
+
+    /// Shell command to run. Placeholders like `{{path}}` are replaced with the
+    /// shell-quoted value of the matching argument.
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub command: String,
+
+    /// Shell to run the command with, e.g. `pwsh` for a command that has to
+    /// run the same way on Windows. If not specified, the shell of the user
+    /// is used and the arguments are quoted for it.
+    #[merge(strategy = crate::merge::option)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<ShellKind>,
+
+    /// Runs the command again when it fails, for a tool running flaky tests or
+    /// installs over the network
+    #[merge(strategy = crate::merge::option)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+}
+
+fn default_input_schema() -> Value {
+    serde_json::json!({ "type": "object", "properties": {} })
+}
+
+impl Key for CustomTool {
+    type Id = ToolName;
+
+    fn key(&self) -> &Self::Id {
+        &self.name
+    }
+}
+
+impl CustomTool {
+    pub fn new(name: impl ToString, command: impl ToString) -> Self {
+        Self {
+            name: ToolName::new(name),
+            description: String::new(),
+            input_schema: default_input_schema(),
+            command: command.to_string(),
+            shell: None,
+            retry: None,
+        }
+    }
+
+    pub fn definition(&self) -> anyhow::Result<ToolDefinition> {
+        let input_schema = serde_json::from_value(self.input_schema.clone())
+            .with_context(|| format!("Invalid input_schema for tool '{}'", self.name))?;
+        Ok(ToolDefinition::new(self.name.as_str())
+            .description(self.description.clone())
+            .input_schema(input_schema))
+    }
+
+    /// Renders the command template with the given arguments, quoted for the
+    /// shell of the tool or else for the shell of the user. Missing and `null`
+    /// arguments are replaced with an empty string.
+    pub fn render(
+        &self,
+        arguments: &Value,
+        user_shell: Option<ShellKind>,
+    ) -> anyhow::Result<String> {
+        render_command(&self.command, arguments, self.shell.or(user_shell))
+    }
+}
+
+/// Replaces the placeholders of a command template with the values they name,
+/// quoted for the shell or for a POSIX shell if not specified. Nested values
+/// are named with a dotted path, like `{{arguments.path}}`.
+pub(crate) fn render_command(
+    template: &str,
+    values: &Value,
+    shell: Option<ShellKind>,
+) -> anyhow::Result<String> {
+    let shell = shell.unwrap_or(ShellKind::Bash);
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + end].trim();
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(&shell.quote(&argument(values, name)?));
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+
+    Ok(rendered)
+}
+
+fn argument(values: &Value, name: &str) -> anyhow::Result<String> {
+    let value = name
+        .split('.')
+        .try_fold(values, |value, key| value.get(key));
+    Ok(match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(value)) => value.clone(),
+        Some(value) => serde_json::to_string(value)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_render_quotes_arguments() {
+        let fixture = CustomTool::new("grep_logs", "grep -n {{ pattern }} {{path}}");
+
+        let actual = fixture
+            .render(&json!({"pattern": "it's", "path": "logs/app.log"}), None)
+            .unwrap();
+
+        let expected = r"grep -n 'it'\''s' 'logs/app.log'";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_render_non_string_and_missing_arguments() {
+        let fixture = CustomTool::new("tail", "tail -n {{lines}} {{path}}");
+
+        let actual = fixture.render(&json!({"lines": 20}), None).unwrap();
+
+        let expected = "tail -n '20' ''";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_render_does_not_expand_shell_syntax() {
+        let fixture = CustomTool::new("echo", "echo {{text}}");
+
+        let actual = fixture
+            .render(&json!({"text": "$(rm -rf ~)"}), None)
+            .unwrap();
+
+        let expected = "echo '$(rm -rf ~)'";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_render_quotes_for_the_shell() {
+        let fixture = CustomTool::new("grep_logs", "Select-String {{pattern}} {{path}}")
+            .shell(ShellKind::Pwsh);
+
+        let actual = fixture
+            .render(&json!({"pattern": "it's", "path": "logs\\app.log"}), None)
+            .unwrap();
+
+        let expected = r"Select-String 'it''s' 'logs\app.log'";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_render_quotes_for_the_user_shell() {
+        let fixture = CustomTool::new("grep_logs", "Select-String {{pattern}}");
+
+        let actual = fixture
+            .render(&json!({"pattern": "it's"}), Some(ShellKind::Pwsh))
+            .unwrap();
+
+        let expected = "Select-String 'it''s'";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_render_nested_arguments() {
+        let fixture = "cargo fmt -- {{arguments.path}} {{arguments.missing.path}}";
+
+        let actual =
+            render_command(fixture, &json!({"arguments": {"path": "src/lib.rs"}}), None).unwrap();
+
+        let expected = "cargo fmt -- 'src/lib.rs' ''";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_definition() {
+        let fixture = CustomTool::new("deploy", "make deploy").description("Deploys the app");
+
+        let actual = fixture.definition().unwrap();
+
+        assert_eq!(actual.name, ToolName::new("deploy"));
+        assert_eq!(actual.description, "Deploys the app");
+    }
+}
@@ -53,6 +53,9 @@ pub struct Environment {
     /// Maximum execution time in seconds for a single tool call.
     /// Controls how long a tool can run before being terminated.
     pub tool_timeout: u64,
+    /// Maximum number of shell commands run at the same time. Commands in the
+    /// same working directory always run one after the other.
+    pub max_concurrent_commands: usize,
 }
 
 impl Environment {
@@ -70,6 +73,9 @@ impl Environment {
     pub fn snapshot_path(&self) -> PathBuf {
         self.base_path.join("snapshots")
     }
+    pub fn tool_calls_path(&self) -> PathBuf {
+        self.base_path.join("tool_calls.jsonl")
+    }
     pub fn mcp_user_config(&self) -> PathBuf {
         self.base_path.join(".mcp.json")
     }
@@ -83,10 +89,38 @@ impl Environment {
     pub fn permissions_path(&self) -> PathBuf {
         self.base_path.join("permissions.yaml")
     }
+    /// Facts remembered by the agent across sessions, keyed by project
+    pub fn memory_path(&self) -> PathBuf {
+        self.base_path.join("memory.json")
+    }
+    /// Conversations saved so they can be listed and resumed later
+    pub fn sessions_path(&self) -> PathBuf {
+        self.base_path.join("sessions")
+    }
+    /// Sockets of the conversations shared with other frontends
+    pub fn live_sessions_path(&self) -> PathBuf {
+        self.base_path.join("live")
+    }
+    /// Git worktrees the background tasks work in
+    pub fn worktrees_path(&self) -> PathBuf {
+        self.base_path.join("worktrees")
+    }
+    /// Dumps of the requests sent to the provider, when enabled in forge.yaml
+    pub fn request_dump_path(&self) -> PathBuf {
+        self.base_path.join("requests")
+    }
+    /// Embeddings of the files of the workspaces, used for semantic search
+    pub fn workspace_indexes_path(&self) -> PathBuf {
+        self.base_path.join("indexes")
+    }
 
     pub fn mcp_local_config(&self) -> PathBuf {
         self.cwd.join(".mcp.json")
     }
+    /// Task list of the workspace, kept between sessions
+    pub fn tasks_path(&self) -> PathBuf {
+        self.cwd.join(".forge").join("tasks.json")
+    }
     pub fn version(&self) -> String {
         VERSION.to_string()
     }
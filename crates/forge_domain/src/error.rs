@@ -71,6 +71,10 @@ pub enum Error {
 
     #[error(transparent)]
     Retryable(anyhow::Error),
+
+    #[error("Provider rejected the API key with status {status}: {error}")]
+    #[from(skip)]
+    KeyRejected { status: u16, error: anyhow::Error },
 }
 
 pub type Result<A> = std::result::Result<A, Error>;
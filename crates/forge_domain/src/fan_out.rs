@@ -0,0 +1,59 @@
+use derive_more::Display;
+
+/// How the work on an item of a fan-out is going
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum WorkItemStatus {
+    #[display("running")]
+    Running,
+    #[display("completed")]
+    Completed,
+    #[display("budget_exhausted")]
+    BudgetExhausted,
+    #[display("failed")]
+    Failed,
+    /// The turn of the sub-agent ended without it completing the task
+    #[display("incomplete")]
+    Incomplete,
+    /// The shared budget was used up before the item was started
+    #[display("skipped")]
+    Skipped,
+}
+
+impl WorkItemStatus {
+    /// Checks if the work on the item is over, whatever its outcome
+    pub fn is_done(&self) -> bool {
+        !matches!(self, WorkItemStatus::Running)
+    }
+}
+
+/// Status of an item of a fan-out, sent each time it changes
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkItemUpdate {
+    /// Position of the item in the list, from 1
+    pub index: usize,
+    /// Number of items in the list
+    pub total: usize,
+    pub item: String,
+    pub status: WorkItemStatus,
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_is_done() {
+        let actual = [
+            WorkItemStatus::Running,
+            WorkItemStatus::Completed,
+            WorkItemStatus::Failed,
+            WorkItemStatus::Skipped,
+        ]
+        .map(|status| status.is_done());
+
+        let expected = [false, true, true, true];
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Versions of the files as the agent last saw them, either by reading or by
+/// writing them. Used to detect files that were changed by someone else
+/// before the agent overwrites them.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileVersions(HashMap<PathBuf, String>);
+
+impl FileVersions {
+    /// Records the content of a file as seen by the agent
+    pub fn observe(&mut self, path: impl Into<PathBuf>, content: &[u8]) {
+        self.0.insert(path.into(), hash(content));
+    }
+
+    /// Forgets a file, eg: once it has been removed
+    pub fn forget(&mut self, path: &Path) {
+        self.0.remove(path);
+    }
+
+    /// Returns true if the agent has seen the file and its current content is
+    /// different from the one it saw. A missing file is stale only if the
+    /// agent has seen it before.
+    pub fn is_stale(&self, path: &Path, current: Option<&[u8]>) -> bool {
+        match (self.0.get(path), current) {
+            (Some(seen), Some(current)) => *seen != hash(current),
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+}
+
+fn hash(content: &[u8]) -> String {
+    let mut hasher = fnv_rs::Fnv64::default();
+    hasher.write(content);
+    format!("{:x}", hasher.finish())
+}
+
+/// File writes made by a batch of tool calls. The original content of every
+/// file is recorded before its first write so that the whole batch can be
+/// rolled back when one of its writes fails.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FileTransaction {
+    originals: Vec<(PathBuf, Option<Vec<u8>>)>,
+    aborted: bool,
+}
+
+impl FileTransaction {
+    /// Returns true if the file has already been written in this batch
+    pub fn contains(&self, path: &Path) -> bool {
+        self.originals.iter().any(|(recorded, _)| recorded == path)
+    }
+
+    /// Records the content of a file before it is written. The content is
+    /// `None` when the file doesn't exist yet. Only the first record of a
+    /// file is kept.
+    pub fn record(&mut self, path: impl Into<PathBuf>, original: Option<Vec<u8>>) {
+        let path = path.into();
+        if !self.contains(&path) {
+            self.originals.push((path, original));
+        }
+    }
+
+    /// Marks the transaction as aborted and returns the original content of
+    /// the written files, most recent first, so that they can be restored.
+    pub fn abort(&mut self) -> Vec<(PathBuf, Option<Vec<u8>>)> {
+        self.aborted = true;
+        std::mem::take(&mut self.originals)
+            .into_iter()
+            .rev()
+            .collect()
+    }
+
+    /// Returns true once the transaction has been rolled back. No more writes
+    /// should be made in the batch after that.
+    pub fn is_aborted(&self) -> bool {
+        self.aborted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_file_versions_stale() {
+        let mut fixture = FileVersions::default();
+        fixture.observe("/a.txt", b"hello");
+
+        let actual = (
+            fixture.is_stale(Path::new("/a.txt"), Some(b"hello")),
+            fixture.is_stale(Path::new("/a.txt"), Some(b"changed")),
+            fixture.is_stale(Path::new("/a.txt"), None),
+            fixture.is_stale(Path::new("/b.txt"), Some(b"unseen")),
+        );
+
+        let expected = (false, true, true, false);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_transaction_keeps_first_original() {
+        let mut fixture = FileTransaction::default();
+        fixture.record("/a.txt", Some(b"one".to_vec()));
+        fixture.record("/b.txt", None);
+        fixture.record("/a.txt", Some(b"two".to_vec()));
+
+        let actual = fixture.abort();
+
+        let expected = vec![
+            (PathBuf::from("/b.txt"), None),
+            (PathBuf::from("/a.txt"), Some(b"one".to_vec())),
+        ];
+        assert_eq!(actual, expected);
+        assert!(fixture.is_aborted());
+    }
+}
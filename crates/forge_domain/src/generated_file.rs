@@ -0,0 +1,222 @@
+use std::path::Path;
+use std::sync::LazyLock;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::RegexSet;
+use strum_macros::Display;
+
+/// Names of the lockfiles of the package managers
+const LOCKFILES: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lock",
+    "poetry.lock",
+    "Pipfile.lock",
+    "uv.lock",
+    "Gemfile.lock",
+    "composer.lock",
+    "go.sum",
+    "flake.lock",
+    "mix.lock",
+    "pubspec.lock",
+    "Podfile.lock",
+    "packages.lock.json",
+];
+
+/// Comments that code generators write at the top of their output, matched
+/// against whole lines so that files merely mentioning them aren't taken as
+/// generated
+static GENERATED_MARKERS: LazyLock<RegexSet> = LazyLock::new(|| {
+    RegexSet::new([
+        // The convention of Go, followed by many other generators
+        r"^// Code generated .* DO NOT EDIT\.$",
+        r"^\s*(//|#|/?\*+|--)\s*@generated\b",
+        r"^\s*(//|#|/?\*+|--)\s*DO NOT EDIT\b",
+        r"^\s*(//|#|/?\*+|--)\s*<auto-generated",
+        r"(?i)^\s*(//|#|/?\*+|--)\s*(this (file|code) (is|was|has been) )?(auto-?)?generated (by|from|with) ",
+    ])
+    .unwrap()
+});
+
+/// Lines at the top of a file in which the generated markers are looked for
+const HEADER_LINES: usize = 5;
+
+/// Extensions of the assets that are usually minified
+const MINIFIABLE_EXTENSIONS: &[&str] = &["js", "mjs", "cjs", "css", "json", "html", "svg"];
+
+/// Length from which a line of an asset is taken as minified
+const MINIFIED_LINE_LENGTH: usize = 1000;
+
+/// Kinds of text files that are produced by tools rather than written, whose
+/// content is summarized for the agent instead of being read in full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum GeneratedKind {
+    Lockfile,
+    Minified,
+    Generated,
+}
+
+impl GeneratedKind {
+    /// Describes the file, to tell the agent why its content was skipped
+    pub fn description(&self) -> &'static str {
+        match self {
+            GeneratedKind::Lockfile => "a lockfile",
+            GeneratedKind::Minified => "minified",
+            GeneratedKind::Generated => "generated",
+        }
+    }
+}
+
+/// Tells the files produced by tools from the ones written by hand, from the
+/// glob patterns of the generated files of the project, compiled once
+#[derive(Debug, Clone)]
+pub struct GeneratedFiles {
+    globs: GlobSet,
+}
+
+impl Default for GeneratedFiles {
+    fn default() -> Self {
+        Self { globs: GlobSet::empty() }
+    }
+}
+
+impl GeneratedFiles {
+    /// Compiles the patterns, leaving out the invalid ones with a warning so
+    /// that they don't keep the files from being read
+    pub fn new(patterns: &[String]) -> Self {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            match Glob::new(pattern) {
+                Ok(glob) => {
+                    builder.add(glob);
+                }
+                Err(error) => {
+                    tracing::warn!(pattern = %pattern, error = %error, "Invalid generated files pattern")
+                }
+            }
+        }
+        let globs = builder.build().unwrap_or_else(|error| {
+            tracing::warn!(error = %error, "Failed to compile the generated files patterns");
+            GlobSet::empty()
+        });
+        Self { globs }
+    }
+
+    /// Kind of the file from its path alone: the lockfiles, the minified
+    /// assets named so and the files matching the patterns. Returns `None`
+    /// when the content has to be looked at to tell.
+    pub fn detect_path(&self, path: &Path) -> Option<GeneratedKind> {
+        let name = path.file_name().and_then(|name| name.to_str())?;
+        if LOCKFILES.contains(&name) {
+            return Some(GeneratedKind::Lockfile);
+        }
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        if name.contains(".min.") || extension == Some("map") {
+            return Some(GeneratedKind::Minified);
+        }
+        self.globs
+            .is_match(path)
+            .then_some(GeneratedKind::Generated)
+    }
+
+    /// Kind of the file from its path and the first lines of its content.
+    /// Returns `None` for the files that are written by hand.
+    pub fn detect(&self, path: &Path, content: &str) -> Option<GeneratedKind> {
+        if let Some(kind) = self.detect_path(path) {
+            return Some(kind);
+        }
+
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        let minified = extension.is_some_and(|ext| MINIFIABLE_EXTENSIONS.contains(&ext))
+            && content
+                .lines()
+                .take(HEADER_LINES)
+                .any(|line| line.len() >= MINIFIED_LINE_LENGTH);
+        if minified {
+            return Some(GeneratedKind::Minified);
+        }
+
+        content
+            .lines()
+            .take(HEADER_LINES)
+            .any(|line| GENERATED_MARKERS.is_match(line))
+            .then_some(GeneratedKind::Generated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let long_line = "var a=1;".repeat(200);
+        let fixture = [
+            ("Cargo.lock", "version = 3"),
+            ("web/app.min.js", "var a=1;"),
+            ("web/bundle.js", long_line.as_str()),
+            (
+                "api/service.pb.go",
+                "// Code generated by protoc-gen-go. DO NOT EDIT.\npackage api",
+            ),
+            ("src/schema.rs", "// @generated by diesel\nuse diesel::*;"),
+            ("gen/client.ts", "export const client = {};"),
+            ("src/main.rs", "fn main() {}"),
+            ("web/app.js", "var a = 1;"),
+            (
+                "src/codegen.rs",
+                "//! Writes code generated from the schema, marked DO NOT EDIT\nfn main() {}",
+            ),
+            (
+                "README.md",
+                "# Tools\nThe files are autogenerated by `make`",
+            ),
+        ];
+
+        let actual = fixture.map(|(path, content)| {
+            GeneratedFiles::new(&["gen/**".to_string(), "src/[".to_string()])
+                .detect(Path::new(path), content)
+        });
+
+        let expected = [
+            Some(GeneratedKind::Lockfile),
+            Some(GeneratedKind::Minified),
+            Some(GeneratedKind::Minified),
+            Some(GeneratedKind::Generated),
+            Some(GeneratedKind::Generated),
+            Some(GeneratedKind::Generated),
+            None,
+            None,
+            None,
+            None,
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_detect_path() {
+        let fixture = GeneratedFiles::new(&["*.g.dart".to_string()]);
+
+        let actual = [
+            "go.sum",
+            "web/app.min.css",
+            "lib/model.g.dart",
+            "api/service.pb.go",
+        ]
+        .map(|path| fixture.detect_path(Path::new(path)));
+
+        let expected = [
+            Some(GeneratedKind::Lockfile),
+            Some(GeneratedKind::Minified),
+            Some(GeneratedKind::Generated),
+            None,
+        ];
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,350 @@
+use derive_setters::Setters;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::custom_tool::render_command;
+use crate::{AgentId, CommandOutput, ConversationId, ShellKind, ToolName};
+
+/// Point of the agent loop at which a hook runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    /// Before a tool is called. Blocking it skips the call and tells the agent
+    /// why.
+    PreToolCall,
+    /// After a tool is called. Blocking it marks the result as failed, e.g.
+    /// when the file written doesn't pass the linter.
+    PostToolCall,
+    /// Before the message of the user is sent to the agent. Blocking it
+    /// rejects the message.
+    PreTurn,
+    /// Once the agent is done with the message of the user
+    PostTurn,
+}
+
+/// A shell command or a webhook run at a point of the agent loop, e.g. to lint
+/// the files once they are written or to send a notification once a turn ends
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Setters, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct Hook {
+    /// Point of the agent loop at which the hook runs
+    pub on: HookEvent,
+
+    /// Tools the hook runs for, by name or by a part of their name like
+    /// `shell` or `fs`. Only used by the tool call events. If not specified,
+    /// the hook runs for all the tools.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+
+    /// Shell command to run. Placeholders like `{{tool}}` or
+    /// `{{arguments.path}}` are replaced with the shell-quoted value of the
+    /// payload. A non-zero exit code blocks the action with the output as the
+    /// reason, otherwise the output is passed on as a message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+
+    /// Shell to run the command with. If not specified, the shell of the user
+    /// is used and the values are quoted for it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<ShellKind>,
+
+    /// URL the payload is posted to as JSON. The response can block the action
+    /// with `{"block": true, "reason": "..."}` or pass on a message with
+    /// `{"message": "..."}`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl Hook {
+    pub fn new(on: HookEvent) -> Self {
+        Self { on, tools: Vec::new(), command: None, shell: None, url: None }
+    }
+
+    /// Checks if the hook runs for the payload
+    pub fn applies(&self, payload: &HookPayload) -> bool {
+        self.on == payload.event
+            && (self.tools.is_empty()
+                || payload
+                    .tool
+                    .as_ref()
+                    .is_some_and(|tool| self.tools.iter().any(|pattern| tool.matches(pattern))))
+    }
+
+    /// Renders the command of the hook with the values of the payload, quoted
+    /// for the shell of the hook or else for the shell of the user
+    pub fn render(
+        &self,
+        payload: &HookPayload,
+        user_shell: Option<ShellKind>,
+    ) -> anyhow::Result<Option<String>> {
+        let Some(command) = &self.command else {
+            return Ok(None);
+        };
+        render_command(
+            command,
+            &serde_json::to_value(payload)?,
+            self.shell.or(user_shell),
+        )
+        .map(Some)
+    }
+}
+
+/// What the hooks are told about the action they run for
+#[derive(Debug, Clone, PartialEq, Serialize, Setters)]
+#[setters(strip_option, into)]
+pub struct HookPayload {
+    pub event: HookEvent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<ConversationId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent: Option<AgentId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool: Option<ToolName>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<Value>,
+    /// Text output of the tool, for the post tool call hooks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// Message of the user, for the turn hooks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl HookPayload {
+    pub fn new(event: HookEvent) -> Self {
+        Self {
+            event,
+            conversation_id: None,
+            agent: None,
+            tool: None,
+            arguments: None,
+            output: None,
+            message: None,
+        }
+    }
+}
+
+/// Response of a webhook. An empty response lets the action through.
+#[derive(Debug, Default, Deserialize)]
+struct HookResponse {
+    #[serde(default)]
+    block: bool,
+    #[serde(default)]
+    reason: Option<String>,
+    #[serde(default)]
+    message: Option<String>,
+}
+
+/// Combined result of the hooks run for an action
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HookOutcome {
+    /// Reason the action is blocked, if a hook blocked it
+    pub block: Option<String>,
+    /// Messages passed on by the hooks
+    pub messages: Vec<String>,
+}
+
+impl HookOutcome {
+    /// Outcome of a hook command, blocking the action when the command failed
+    pub fn from_command(output: &CommandOutput) -> Self {
+        let text = [output.stdout.trim(), output.stderr.trim()]
+            .into_iter()
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if output.exit_code == Some(0) {
+            return Self::default().message(output.stdout.trim());
+        }
+
+        let reason = match output.exit_code {
+            _ if !text.is_empty() => text,
+            Some(code) => format!("'{}' exited with code {code}", output.command),
+            None => format!("'{}' was terminated", output.command),
+        };
+        Self { block: Some(reason), messages: Vec::new() }
+    }
+
+    /// Outcome of a webhook, blocking the action when the request failed
+    pub fn from_response(code: u16, content: &str) -> Self {
+        let content = content.trim();
+        if !(200..300).contains(&code) {
+            let reason = match content {
+                "" => format!("the webhook responded with status {code}"),
+                content => content.to_string(),
+            };
+            return Self { block: Some(reason), messages: Vec::new() };
+        }
+
+        // Responses that aren't JSON are passed on as they are
+        let response = match content {
+            "" => HookResponse::default(),
+            content => serde_json::from_str(content).unwrap_or_else(|_| HookResponse {
+                message: Some(content.to_string()),
+                ..Default::default()
+            }),
+        };
+
+        let outcome = Self::default().message(response.message.unwrap_or_default());
+        if response.block {
+            Self {
+                block: Some(
+                    response
+                        .reason
+                        .unwrap_or_else(|| "blocked by the webhook".to_string()),
+                ),
+                ..outcome
+            }
+        } else {
+            outcome
+        }
+    }
+
+    /// Adds the outcome of another hook
+    pub fn merge(mut self, other: HookOutcome) -> Self {
+        self.block = self.block.or(other.block);
+        self.messages.extend(other.messages);
+        self
+    }
+
+    fn message(mut self, message: impl Into<String>) -> Self {
+        let message = message.into();
+        if !message.trim().is_empty() {
+            self.messages.push(message);
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn command_output(stdout: &str, stderr: &str, exit_code: Option<i32>) -> CommandOutput {
+        CommandOutput {
+            command: "cargo clippy".to_string(),
+            stdout: stdout.to_string(),
+            stderr: stderr.to_string(),
+            exit_code,
+            failed_attempts: Vec::new(),
+            cwd: None,
+        }
+    }
+
+    #[test]
+    fn test_applies() {
+        let fixture = Hook::new(HookEvent::PreToolCall).tools(vec!["shell".to_string()]);
+
+        let actual = [
+            HookPayload::new(HookEvent::PreToolCall)
+                .tool(ToolName::new("forge_tool_process_shell")),
+            HookPayload::new(HookEvent::PreToolCall).tool(ToolName::new("forge_tool_fs_read")),
+            HookPayload::new(HookEvent::PostToolCall)
+                .tool(ToolName::new("forge_tool_process_shell")),
+            HookPayload::new(HookEvent::PreToolCall),
+        ]
+        .iter()
+        .map(|payload| fixture.applies(payload))
+        .collect::<Vec<_>>();
+
+        let expected = vec![true, false, false, false];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_applies_to_all_tools() {
+        let fixture = Hook::new(HookEvent::PostTurn);
+
+        let actual = fixture.applies(&HookPayload::new(HookEvent::PostTurn));
+
+        assert!(actual);
+    }
+
+    #[test]
+    fn test_render() {
+        let fixture = Hook::new(HookEvent::PostToolCall).command("cargo fmt -- {{arguments.path}}");
+        let payload = HookPayload::new(HookEvent::PostToolCall)
+            .tool(ToolName::new("forge_tool_fs_create"))
+            .arguments(json!({"path": "src/main.rs"}));
+
+        let actual = fixture.render(&payload, None).unwrap();
+
+        let expected = Some("cargo fmt -- 'src/main.rs'".to_string());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_from_command_success() {
+        let fixture = command_output("  all good \n", "warning: unused", Some(0));
+
+        let actual = HookOutcome::from_command(&fixture);
+
+        let expected = HookOutcome { block: None, messages: vec!["all good".to_string()] };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_from_command_failure() {
+        let actual = [
+            command_output("error: unused variable", "1 error", Some(1)),
+            command_output("", "", Some(2)),
+            command_output("", "", None),
+        ]
+        .iter()
+        .map(|output| HookOutcome::from_command(output).block)
+        .collect::<Vec<_>>();
+
+        let expected = vec![
+            Some("error: unused variable\n1 error".to_string()),
+            Some("'cargo clippy' exited with code 2".to_string()),
+            Some("'cargo clippy' was terminated".to_string()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_from_response() {
+        let actual = [
+            HookOutcome::from_response(200, ""),
+            HookOutcome::from_response(200, r#"{"message": "Deploys are frozen"}"#),
+            HookOutcome::from_response(200, r#"{"block": true, "reason": "No force pushes"}"#),
+            HookOutcome::from_response(200, "noted"),
+            HookOutcome::from_response(500, ""),
+        ];
+
+        let expected = [
+            HookOutcome::default(),
+            HookOutcome {
+                block: None,
+                messages: vec!["Deploys are frozen".to_string()],
+            },
+            HookOutcome { block: Some("No force pushes".to_string()), messages: vec![] },
+            HookOutcome { block: None, messages: vec!["noted".to_string()] },
+            HookOutcome {
+                block: Some("the webhook responded with status 500".to_string()),
+                messages: vec![],
+            },
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_merge() {
+        let fixture = HookOutcome { block: None, messages: vec!["formatted".to_string()] };
+
+        let actual = fixture.merge(HookOutcome {
+            block: Some("lint failed".to_string()),
+            messages: vec!["2 warnings".to_string()],
+        });
+
+        let expected = HookOutcome {
+            block: Some("lint failed".to_string()),
+            messages: vec!["formatted".to_string(), "2 warnings".to_string()],
+        };
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,96 +1,185 @@
 mod agent;
+mod agent_template;
 mod attachment;
+mod background_task;
 mod chat_request;
 mod chat_response;
+mod command_policy;
+mod command_retry;
 mod compact;
 mod compaction_result;
 mod compaction_strategy;
 mod context;
+mod context_diff;
 mod conversation;
+mod conversation_export;
 mod conversation_html;
+mod conversation_import;
+mod conversation_markdown;
+mod conversation_transcript;
+mod custom_tool;
 mod env;
 mod error;
 mod event;
+mod fan_out;
 mod file;
+mod file_transaction;
+mod generated_file;
+mod guardrail;
+mod hook;
 mod http_config;
 mod image;
+mod lifecycle;
+mod live;
+mod mailbox;
 mod max_tokens;
 mod mcp;
+mod memory;
 mod merge;
 mod message;
 mod model;
+mod openapi;
+mod pin;
+mod pipeline;
+mod plugin;
 mod point;
 mod policies;
+mod profile;
+mod project_env;
 mod provider;
 mod reasoning;
+mod redaction;
+mod replay;
+mod reply;
+mod repo_map;
+mod request_dump;
 mod result_stream_ext;
+mod retention;
+mod retrieval;
 mod retry_config;
+mod schedule;
+mod session;
+mod session_stats;
 mod shell;
+mod steering;
 mod suggestion;
 mod system_context;
 mod task;
 mod temperature;
 mod template;
+mod token_budget;
 mod tool_call;
 mod tool_call_context;
 mod tool_call_parser;
+mod tool_call_record;
 mod tool_choice;
 mod tool_definition;
+mod tool_failure;
+mod tool_loop;
 mod tool_name;
 mod tool_result;
+mod tool_truncation;
 mod tool_usage;
 mod tools;
 mod top_k;
 mod top_p;
 mod transformer;
+mod turn_limit;
+mod unified_diff;
 mod update;
+mod walker_filter;
 mod workflow;
+mod workspace_stats;
 mod xml;
 
 pub use agent::*;
+pub use agent_template::*;
 pub use attachment::*;
+pub use background_task::*;
 pub use chat_request::*;
 pub use chat_response::*;
+pub use command_policy::*;
+pub use command_retry::*;
 pub use compact::*;
 pub use compaction_result::*;
 pub use compaction_strategy::*;
 pub use context::*;
+pub use context_diff::*;
 pub use conversation::*;
+pub use conversation_export::*;
 pub use conversation_html::*;
+pub use conversation_import::*;
+pub use conversation_markdown::*;
+pub use custom_tool::*;
 pub use env::*;
 pub use error::*;
 pub use event::*;
+pub use fan_out::*;
 pub use file::*;
+pub use file_transaction::*;
+pub use generated_file::*;
+pub use guardrail::*;
+pub use hook::*;
 pub use http_config::*;
 pub use image::*;
+pub use lifecycle::*;
+pub use live::*;
+pub use mailbox::*;
 pub use max_tokens::*;
 pub use mcp::*;
+pub use memory::*;
 pub use message::*;
 pub use model::*;
+pub use openapi::*;
+pub use pin::*;
+pub use pipeline::*;
+pub use plugin::*;
 pub use point::*;
 pub use policies::*;
+pub use profile::*;
+pub use project_env::*;
 pub use provider::*;
 pub use reasoning::*;
+pub use redaction::*;
+pub use replay::*;
+pub use reply::*;
+pub use repo_map::*;
+pub use request_dump::*;
 pub use result_stream_ext::*;
+pub use retention::*;
+pub use retrieval::*;
 pub use retry_config::*;
+pub use schedule::*;
+pub use session::*;
+pub use session_stats::*;
 pub use shell::*;
+pub use steering::*;
 pub use suggestion::*;
 pub use system_context::*;
 pub use task::*;
 pub use temperature::*;
 pub use template::*;
+pub use token_budget::*;
 pub use tool_call::*;
 pub use tool_call_context::*;
 pub use tool_call_parser::*;
+pub use tool_call_record::*;
 pub use tool_choice::*;
 pub use tool_definition::*;
+pub use tool_failure::*;
+pub use tool_loop::*;
 pub use tool_name::*;
 pub use tool_result::*;
+pub use tool_truncation::*;
 pub use tool_usage::*;
 pub use tools::*;
 pub use top_k::*;
 pub use top_p::*;
 pub use transformer::*;
+pub use turn_limit::*;
+pub use unified_diff::*;
 pub use update::*;
+pub use walker_filter::*;
 pub use workflow::*;
+pub use workspace_stats::*;
 pub use xml::*;
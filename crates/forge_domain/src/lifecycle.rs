@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use serde::{Serialize, Serializer};
+
+use crate::{AgentId, ContextCompaction, ConversationId, ToolCallFull, ToolResult};
+
+/// Something that happened during a turn of a conversation, published as it
+/// happens so that integrators can follow the progress of the agents
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LifecycleEvent {
+    pub conversation_id: ConversationId,
+    pub kind: LifecycleEventKind,
+}
+
+impl LifecycleEvent {
+    pub fn new(conversation_id: ConversationId, kind: LifecycleEventKind) -> Self {
+        Self { conversation_id, kind }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LifecycleEventKind {
+    /// A turn started, to handle the event with this name
+    TurnStart { event: String },
+    /// The turn is over, whether it succeeded or not
+    TurnEnd,
+    ToolCallStart {
+        agent_id: AgentId,
+        call: ToolCallFull,
+    },
+    ToolCallEnd {
+        agent_id: AgentId,
+        result: ToolResult,
+    },
+    /// The context of the agent was compacted before its next request
+    Compaction {
+        agent_id: AgentId,
+        compaction: ContextCompaction,
+    },
+    /// A request to the model failed and is sent again after the delay
+    Retry {
+        agent_id: AgentId,
+        cause: String,
+        #[serde(rename = "delay_ms", serialize_with = "as_millis")]
+        delay: Duration,
+    },
+    /// The turn failed with the error, and ends right after
+    Error { message: String },
+}
+
+/// What the subscribers to the lifecycle events receive: the events, or how
+/// many of them they missed by falling behind
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LifecycleUpdate {
+    Event(LifecycleEvent),
+    Lagged { missed: u64 },
+}
+
+fn as_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_lifecycle_update_serialization() {
+        let conversation_id = ConversationId::generate();
+        let fixture = [
+            LifecycleUpdate::Event(LifecycleEvent::new(
+                conversation_id.clone(),
+                LifecycleEventKind::Retry {
+                    agent_id: AgentId::new("forge"),
+                    cause: "Rate limited".to_string(),
+                    delay: Duration::from_secs(2),
+                },
+            )),
+            LifecycleUpdate::Lagged { missed: 3 },
+        ];
+
+        let actual = serde_json::to_value(fixture).unwrap();
+
+        let expected = json!([
+            {
+                "type": "event",
+                "conversation_id": conversation_id.into_string(),
+                "kind": {"type": "retry", "agent_id": "forge", "cause": "Rate limited", "delay_ms": 2000}
+            },
+            {"type": "lagged", "missed": 3}
+        ]);
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{AgentId, ChatResponse, ConversationId, ToolCallFull, ToolResult};
+
+/// What the frontends attached to a shared conversation may do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareMode {
+    /// Frontends watch the turns run by the host
+    ReadOnly,
+    /// Frontends can also send prompts, which are run one turn at a time
+    /// with those of the host
+    Collaborative,
+}
+
+/// Event of a shared conversation, sent to the attached frontends as a line
+/// of JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveEvent {
+    /// First event sent to a frontend once attached
+    Attached {
+        conversation_id: ConversationId,
+        mode: ShareMode,
+        /// Whether a turn is running
+        busy: bool,
+    },
+    /// Prompt a turn was started with, by the host or by a collaborator
+    Prompt {
+        content: String,
+    },
+    Text {
+        text: String,
+        is_complete: bool,
+        is_md: bool,
+    },
+    Reasoning {
+        content: String,
+    },
+    Summary {
+        content: String,
+    },
+    ToolCallStart {
+        call: ToolCallFull,
+    },
+    ToolCallEnd {
+        result: ToolResult,
+    },
+    /// The conversation was handed off to another agent
+    Handoff {
+        from: AgentId,
+        to: AgentId,
+    },
+    /// The host is asked a question or to approve an operation, which only it
+    /// can answer
+    Waiting {
+        message: String,
+    },
+    Error {
+        message: String,
+    },
+    /// The turn is over, the next prompt can be sent
+    TurnEnd,
+}
+
+/// Message of an attached frontend to the host of the conversation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveCommand {
+    /// Runs a turn with the prompt, when the conversation is shared
+    /// collaboratively
+    Prompt { content: String },
+}
+
+impl LiveEvent {
+    /// Event showing the response to the attached frontends. Responses only
+    /// meaningful to the host, such as the usage, have none.
+    pub fn from_response(response: &ChatResponse) -> Option<Self> {
+        Some(match response {
+            ChatResponse::Text { text, is_complete, is_md } => {
+                Self::Text { text: text.clone(), is_complete: *is_complete, is_md: *is_md }
+            }
+            ChatResponse::Reasoning { content } => Self::Reasoning { content: content.clone() },
+            ChatResponse::Summary { content } => Self::Summary { content: content.clone() },
+            ChatResponse::ToolCallStart(call) => Self::ToolCallStart { call: call.clone() },
+            ChatResponse::ToolCallEnd(result) => Self::ToolCallEnd { result: result.clone() },
+            ChatResponse::Handoff { from, to } => {
+                Self::Handoff { from: from.clone(), to: to.clone() }
+            }
+            ChatResponse::ApprovalRequest { message, .. } => {
+                Self::Waiting { message: message.clone() }
+            }
+            ChatResponse::Question { question, .. } => Self::Waiting { message: question.clone() },
+            ChatResponse::ProviderWarning(warning) => Self::Error { message: warning.to_string() },
+            _ => return None,
+        })
+    }
+
+    /// Response an attached frontend shows the event as, if it shows it the
+    /// way the host does
+    pub fn into_response(self) -> Option<ChatResponse> {
+        Some(match self {
+            Self::Text { text, is_complete, is_md } => {
+                ChatResponse::Text { text, is_complete, is_md }
+            }
+            Self::Reasoning { content } => ChatResponse::Reasoning { content },
+            Self::Summary { content } => ChatResponse::Summary { content },
+            Self::ToolCallStart { call } => ChatResponse::ToolCallStart(call),
+            Self::ToolCallEnd { result } => ChatResponse::ToolCallEnd(result),
+            Self::Handoff { from, to } => ChatResponse::Handoff { from, to },
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Reply, ToolCallId, ToolOutput, Usage};
+
+    #[test]
+    fn test_live_event_round_trip() {
+        let fixture = [
+            ChatResponse::Text { text: "Done".to_string(), is_complete: true, is_md: true },
+            ChatResponse::ToolCallEnd(
+                ToolResult::new("forge_tool_fs_read")
+                    .call_id(ToolCallId::new("call_1"))
+                    .output(Ok(ToolOutput::text("[package]"))),
+            ),
+        ];
+
+        let actual = fixture
+            .iter()
+            .filter_map(LiveEvent::from_response)
+            .map(|event| serde_json::to_string(&event).unwrap())
+            .map(|line| serde_json::from_str::<LiveEvent>(&line).unwrap())
+            .filter_map(LiveEvent::into_response)
+            .map(|response| format!("{response:?}"))
+            .collect::<Vec<_>>();
+
+        let expected = fixture
+            .iter()
+            .map(|response| format!("{response:?}"))
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_from_response_leaves_the_answers_to_the_host() {
+        let (reply, _answer) = Reply::channel();
+        let fixture = [
+            ChatResponse::Question {
+                question: "Which database?".to_string(),
+                options: vec![],
+                multiple: false,
+                reply,
+            },
+            ChatResponse::Usage(Usage::default()),
+        ];
+
+        let actual = fixture
+            .iter()
+            .map(LiveEvent::from_response)
+            .collect::<Vec<_>>();
+
+        let expected = vec![
+            Some(LiveEvent::Waiting { message: "Which database?".to_string() }),
+            None,
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_into_response_of_the_events_of_the_session() {
+        let fixture = [
+            LiveEvent::Prompt { content: "Fix the build".to_string() },
+            LiveEvent::Waiting { message: "Which database?".to_string() },
+            LiveEvent::TurnEnd,
+        ];
+
+        let actual = fixture
+            .into_iter()
+            .map(|event| event.into_response().is_some())
+            .collect::<Vec<_>>();
+
+        let expected = vec![false, false, false];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_live_command_line() {
+        let fixture = LiveCommand::Prompt { content: "Fix the build".to_string() };
+
+        let actual = serde_json::to_value(&fixture).unwrap();
+
+        let expected = json!({"type": "prompt", "content": "Fix the build"});
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::{HashMap, VecDeque};
+
+use anyhow::bail;
+use forge_template::Element;
+
+use crate::AgentId;
+
+/// A message an agent sends to another agent running at the same time, e.g. a
+/// reviewer asking the coder to fix what it found
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentMessage {
+    pub from: AgentId,
+    pub to: AgentId,
+    /// What the message is about, in a few words
+    pub subject: String,
+    pub body: String,
+}
+
+impl AgentMessage {
+    pub fn new(
+        from: AgentId,
+        to: AgentId,
+        subject: impl Into<String>,
+        body: impl Into<String>,
+    ) -> Self {
+        Self { from, to, subject: subject.into(), body: body.into() }
+    }
+
+    /// Renders the message as it is given to the agent it is sent to
+    pub fn to_element(&self) -> Element {
+        Element::new("agent_message")
+            .attr("from", &self.from)
+            .attr("subject", &self.subject)
+            .cdata(&self.body)
+    }
+}
+
+/// Messages that can wait to be received in a mailbox, so that messages sent
+/// to an agent that never reads them don't pile up
+const MAX_MESSAGES: usize = 100;
+
+/// Messages sent between the agents of a conversation that haven't been
+/// received yet, queued by the agent they are sent to
+#[derive(Debug, Default, Clone)]
+pub struct Mailbox {
+    queues: HashMap<AgentId, VecDeque<AgentMessage>>,
+}
+
+impl Mailbox {
+    /// Queues the message for the agent it is sent to. Fails when the mailbox
+    /// is full.
+    pub fn post(&mut self, message: AgentMessage) -> anyhow::Result<()> {
+        if self.len() >= MAX_MESSAGES {
+            bail!(
+                "{MAX_MESSAGES} messages are waiting to be received, the message to '{}' wasn't sent",
+                message.to
+            );
+        }
+        self.queues
+            .entry(message.to.clone())
+            .or_default()
+            .push_back(message);
+        Ok(())
+    }
+
+    /// Takes the messages sent to the agent, oldest first
+    pub fn take(&mut self, agent: &AgentId) -> Vec<AgentMessage> {
+        self.queues.remove(agent).map(Vec::from).unwrap_or_default()
+    }
+
+    /// Number of messages waiting to be received
+    pub fn len(&self) -> usize {
+        self.queues.values().map(VecDeque::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queues.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn message(to: &str, subject: &str) -> AgentMessage {
+        AgentMessage::new(AgentId::new("reviewer"), AgentId::new(to), subject, "")
+    }
+
+    #[test]
+    fn test_take() {
+        let mut fixture = Mailbox::default();
+        fixture.post(message("coder", "Unused import")).unwrap();
+        fixture.post(message("tester", "Flaky test")).unwrap();
+        fixture.post(message("coder", "Missing docs")).unwrap();
+
+        let actual = fixture.take(&AgentId::new("coder"));
+
+        let expected = vec![
+            message("coder", "Unused import"),
+            message("coder", "Missing docs"),
+        ];
+        assert_eq!(actual, expected);
+        assert_eq!(fixture.take(&AgentId::new("coder")), vec![]);
+        assert_eq!(
+            fixture.take(&AgentId::new("tester")),
+            vec![message("tester", "Flaky test")]
+        );
+    }
+
+    #[test]
+    fn test_post_to_full_mailbox() {
+        let mut fixture = Mailbox::default();
+        for _ in 0..MAX_MESSAGES {
+            fixture.post(message("coder", "Unused import")).unwrap();
+        }
+
+        let actual = fixture.post(message("tester", "Flaky test")).is_err();
+
+        assert!(actual);
+        assert_eq!(fixture.len(), MAX_MESSAGES);
+    }
+
+    #[test]
+    fn test_to_element() {
+        let fixture = AgentMessage::new(
+            AgentId::new("reviewer"),
+            AgentId::new("coder"),
+            "Unused import",
+            "Remove `use std::fmt` from src/lib.rs",
+        );
+
+        let actual = fixture.to_element().render();
+
+        let expected = "<agent_message\n  from=\"reviewer\"\n  subject=\"Unused import\"\n><![CDATA[Remove `use std::fmt` from src/lib.rs]]>\n</agent_message>";
+        assert_eq!(actual, expected);
+    }
+}
@@ -6,20 +6,24 @@ use std::fmt::{Display, Formatter};
 use std::ops::Deref;
 
 use derive_setters::Setters;
+use glob::Pattern;
 use merge::Merge;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::ToolName;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Scope {
     Local,
     User,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Hash, JsonSchema)]
 #[serde(untagged)]
 pub enum McpServerConfig {
     Stdio(McpStdioServer),
-    Sse(McpSseServer),
+    Http(McpHttpServer),
 }
 
 impl McpServerConfig {
@@ -33,12 +37,21 @@ impl McpServerConfig {
     }
 
     /// Create a new SSE-based MCP server
-    pub fn new_sse(url: impl Into<String>) -> Self {
-        Self::Sse(McpSseServer { url: url.into() })
+    pub fn new_sse(url: impl Into<String>, headers: BTreeMap<String, String>) -> Self {
+        Self::Http(McpHttpServer { url: url.into(), transport: None, headers })
+    }
+
+    /// Create a new MCP server using the Streamable HTTP transport
+    pub fn new_http(url: impl Into<String>, headers: BTreeMap<String, String>) -> Self {
+        Self::Http(McpHttpServer {
+            url: url.into(),
+            transport: Some(McpHttpTransport::Http),
+            headers,
+        })
     }
 }
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize, Setters, PartialEq, Hash)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize, Setters, PartialEq, Hash, JsonSchema)]
 #[setters(strip_option, into)]
 pub struct McpStdioServer {
     /// Command to execute for starting this MCP server
@@ -54,11 +67,67 @@ pub struct McpStdioServer {
     pub env: BTreeMap<String, String>,
 }
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Hash)]
-pub struct McpSseServer {
+#[derive(Default, Debug, Clone, Serialize, Deserialize, Setters, PartialEq, Hash, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct McpHttpServer {
     /// Url of the MCP server
     #[serde(skip_serializing_if = "String::is_empty")]
     pub url: String,
+
+    /// Transport used to reach the server. Defaults to sse.
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub transport: Option<McpHttpTransport>,
+
+    /// Headers sent with every request, e.g. to authenticate. `${VAR}` is
+    /// replaced with the value of the environment variable VAR.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl McpHttpServer {
+    /// Returns the headers with the environment variables they reference
+    /// replaced by their values. References to unset variables are kept as
+    /// they are.
+    pub fn resolve_headers(
+        &self,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> BTreeMap<String, String> {
+        self.headers
+            .iter()
+            .map(|(name, value)| (name.clone(), expand_env(value, &env)))
+            .collect()
+    }
+}
+
+/// Transports of the MCP servers reached over HTTP
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum McpHttpTransport {
+    /// Server-sent events, with the messages of the client posted to a
+    /// separate endpoint
+    Sse,
+    /// Streamable HTTP, where every message is posted to the url and answered
+    /// with JSON or a stream of server-sent events
+    Http,
+}
+
+pub(crate) fn expand_env(value: &str, env: impl Fn(&str) -> Option<String>) -> String {
+    let mut output = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 2..start + end];
+        output.push_str(&rest[..start]);
+        match env(name) {
+            Some(value) => output.push_str(&value),
+            None => output.push_str(&rest[start..=start + end]),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    output.push_str(rest);
+    output
 }
 
 impl Display for McpServerConfig {
@@ -75,8 +144,11 @@ impl Display for McpServerConfig {
                     output.push_str(&format!("{key}={value} "));
                 });
             }
-            McpServerConfig::Sse(sse) => {
-                output.push_str(&format!("{} ", sse.url));
+            McpServerConfig::Http(http) => {
+                output.push_str(&format!("{} ", http.url));
+                if let Some(McpHttpTransport::Http) = http.transport {
+                    output.push_str("(http) ");
+                }
             }
         }
 
@@ -84,6 +156,108 @@ impl Display for McpServerConfig {
     }
 }
 
+/// A prompt template advertised by an MCP server
+#[derive(Default, Debug, Clone, PartialEq, Eq, Setters)]
+#[setters(strip_option, into)]
+pub struct McpPrompt {
+    /// Name of the server advertising the prompt
+    pub server: String,
+    /// Name of the prompt on the server
+    pub name: String,
+    pub description: Option<String>,
+    /// Arguments the prompt is filled in with, in the order they are asked for
+    pub arguments: Vec<McpPromptArgument>,
+}
+
+impl McpPrompt {
+    /// Name of the slash command running the prompt, e.g. `/github:create-pr`
+    pub fn command(&self) -> String {
+        format!("/{}:{}", self.server, self.name)
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Setters)]
+#[setters(strip_option, into)]
+pub struct McpPromptArgument {
+    pub name: String,
+    pub description: Option<String>,
+    pub required: bool,
+}
+
+/// Servers that were started, stopped or restarted by a reload of the MCP
+/// configuration
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct McpReload {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Servers whose configuration changed
+    pub restarted: Vec<String>,
+}
+
+impl McpReload {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.restarted.is_empty()
+    }
+}
+
+/// MCP servers and tools an agent can use. Entries are either the name of a
+/// server or a glob pattern of the names of its tools (eg:
+/// `mcp_github_tool_create_*`).
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct McpFilter {
+    /// Servers and tools the agent can use, every MCP tool when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include: Option<Vec<String>>,
+
+    /// Servers and tools the agent can't use
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclude: Option<Vec<String>>,
+}
+
+impl McpFilter {
+    /// Checks if the MCP tool is included and not excluded
+    pub fn allows(&self, name: &ToolName) -> bool {
+        let included = self
+            .include
+            .as_ref()
+            .is_none_or(|include| include.iter().any(|entry| Self::matches(name, entry)));
+        let excluded = self
+            .exclude
+            .iter()
+            .flatten()
+            .any(|entry| Self::matches(name, entry));
+        included && !excluded
+    }
+
+    fn matches(name: &ToolName, entry: &str) -> bool {
+        name.mcp_server() == Some(entry)
+            || Pattern::new(entry).is_ok_and(|pattern| pattern.matches(name.as_str()))
+    }
+}
+
+/// Change of the availability of the tools of an MCP server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum McpServerEvent {
+    /// The server failed to start or stopped responding, its tools can't be
+    /// called until it is restarted
+    Unavailable { server: String, reason: String },
+    /// The server was restarted and its tools can be called again
+    Restored { server: String },
+}
+
+impl Display for McpServerEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            McpServerEvent::Unavailable { server, reason } => {
+                write!(f, "MCP server '{server}' is unavailable: {reason}")
+            }
+            McpServerEvent::Restored { server } => {
+                write!(f, "MCP server '{server}' is available again")
+            }
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Hash, Merge)]
 #[serde(rename_all = "camelCase")]
 pub struct McpConfig {
@@ -105,3 +279,48 @@ impl From<BTreeMap<String, McpServerConfig>> for McpConfig {
         Self { mcp_servers }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_http_server_from_json() {
+        let fixture = r#"{"type": "http", "url": "https://mcp.example.com/mcp", "headers": {"Authorization": "Bearer ${TOKEN}"}}"#;
+
+        let actual: McpServerConfig = serde_json::from_str(fixture).unwrap();
+
+        let expected = McpServerConfig::Http(
+            McpHttpServer::default()
+                .url("https://mcp.example.com/mcp")
+                .transport(McpHttpTransport::Http)
+                .headers(BTreeMap::from([(
+                    "Authorization".to_string(),
+                    "Bearer ${TOKEN}".to_string(),
+                )])),
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_resolve_headers() {
+        let fixture = McpHttpServer::default().headers(BTreeMap::from([
+            ("Authorization".to_string(), "Bearer ${TOKEN}".to_string()),
+            ("X-Team".to_string(), "${TEAM}-${UNSET}".to_string()),
+        ]));
+
+        let actual = fixture.resolve_headers(|name| match name {
+            "TOKEN" => Some("abc".to_string()),
+            "TEAM" => Some("core".to_string()),
+            _ => None,
+        });
+
+        let expected = BTreeMap::from([
+            ("Authorization".to_string(), "Bearer abc".to_string()),
+            ("X-Team".to_string(), "core-${UNSET}".to_string()),
+        ]);
+        assert_eq!(actual, expected);
+    }
+}
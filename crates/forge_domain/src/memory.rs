@@ -0,0 +1,215 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Words too common to tell memories apart when recalling them
+const STOP_WORDS: [&str; 12] = [
+    "the", "and", "for", "with", "that", "this", "from", "are", "was", "you", "how", "what",
+];
+
+/// A fact about a project the agent was asked to remember
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Memory {
+    pub id: u32,
+    pub fact: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Facts remembered across sessions, keyed by the root of the project they
+/// were remembered in
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MemoryStore {
+    projects: BTreeMap<String, Vec<Memory>>,
+}
+
+impl MemoryStore {
+    pub fn memories(&self, project: &Path) -> &[Memory] {
+        self.projects
+            .get(&project_key(project))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Remembers the fact for the project. A fact that's already remembered is
+    /// returned as is instead of being added twice.
+    pub fn remember(
+        &mut self,
+        project: &Path,
+        fact: impl Into<String>,
+        created_at: DateTime<Utc>,
+    ) -> Memory {
+        let fact = fact.into().trim().to_string();
+        let memories = self.projects.entry(project_key(project)).or_default();
+        if let Some(memory) = memories.iter().find(|memory| memory.fact == fact) {
+            return memory.clone();
+        }
+
+        let id = memories
+            .iter()
+            .map(|memory| memory.id)
+            .max()
+            .unwrap_or_default()
+            + 1;
+        let memory = Memory { id, fact, created_at };
+        memories.push(memory.clone());
+        memory
+    }
+
+    /// Forgets the memory of the project with the id, returning it if there
+    /// was one
+    pub fn forget(&mut self, project: &Path, id: u32) -> Option<Memory> {
+        let memories = self.projects.get_mut(&project_key(project))?;
+        let position = memories.iter().position(|memory| memory.id == id)?;
+        Some(memories.remove(position))
+    }
+
+    /// Replaces the fact of the memory of the project with the id, returning
+    /// the memory if there is one
+    pub fn edit(&mut self, project: &Path, id: u32, fact: impl Into<String>) -> Option<Memory> {
+        let memory = self
+            .projects
+            .get_mut(&project_key(project))?
+            .iter_mut()
+            .find(|memory| memory.id == id)?;
+        memory.fact = fact.into().trim().to_string();
+        Some(memory.clone())
+    }
+
+    /// Memories of the project sharing the most words with the query, the
+    /// most recent first on a tie. Memories sharing no word are left out,
+    /// unless the query has no meaningful word in which case the most recent
+    /// ones are returned.
+    pub fn recall(&self, project: &Path, query: &str, limit: usize) -> Vec<Memory> {
+        let query = terms(query);
+        let mut scored = self
+            .memories(project)
+            .iter()
+            .map(|memory| (query.intersection(&terms(&memory.fact)).count(), memory))
+            .filter(|(score, _)| query.is_empty() || *score > 0)
+            .collect::<Vec<_>>();
+        scored.sort_by(|(a_score, a), (b_score, b)| {
+            b_score.cmp(a_score).then_with(|| b.id.cmp(&a.id))
+        });
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, memory)| memory.clone())
+            .collect()
+    }
+}
+
+fn project_key(project: &Path) -> String {
+    project.display().to_string()
+}
+
+/// Lowercased words of the text that are meaningful enough to match on
+fn terms(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.len() > 2)
+        .map(str::to_lowercase)
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn fixture() -> MemoryStore {
+        let created_at = DateTime::<Utc>::default();
+        let mut store = MemoryStore::default();
+        store.remember(
+            Path::new("/project"),
+            "Run the tests with cargo nextest",
+            created_at,
+        );
+        store.remember(
+            Path::new("/project"),
+            "Errors are reported with anyhow",
+            created_at,
+        );
+        store.remember(
+            Path::new("/project"),
+            "Tests use pretty_assertions",
+            created_at,
+        );
+        store.remember(
+            Path::new("/other"),
+            "Tests are written in Python",
+            created_at,
+        );
+        store
+    }
+
+    #[test]
+    fn test_remember_twice() {
+        let mut fixture = fixture();
+
+        let actual = fixture.remember(
+            Path::new("/project"),
+            " Errors are reported with anyhow ",
+            DateTime::<Utc>::default(),
+        );
+
+        let expected = 2;
+        assert_eq!(actual.id, expected);
+        assert_eq!(fixture.memories(Path::new("/project")).len(), 3);
+    }
+
+    #[test]
+    fn test_forget_and_edit() {
+        let mut fixture = fixture();
+
+        let forgotten = fixture
+            .forget(Path::new("/project"), 1)
+            .map(|memory| memory.id);
+        let edited = fixture
+            .edit(Path::new("/project"), 2, " Errors use thiserror ")
+            .map(|memory| memory.fact);
+        let missing = fixture.forget(Path::new("/other"), 2);
+
+        let actual = (
+            forgotten,
+            edited,
+            missing,
+            fixture.memories(Path::new("/project")).len(),
+        );
+        let expected = (Some(1), Some("Errors use thiserror".to_string()), None, 2);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_recall() {
+        let fixture = fixture();
+
+        let actual = fixture
+            .recall(Path::new("/project"), "How are the tests run?", 5)
+            .into_iter()
+            .map(|memory| memory.fact)
+            .collect::<Vec<_>>();
+
+        let expected = vec![
+            "Run the tests with cargo nextest".to_string(),
+            "Tests use pretty_assertions".to_string(),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_recall_without_terms() {
+        let fixture = fixture();
+
+        let actual = fixture
+            .recall(Path::new("/project"), "and so?", 2)
+            .into_iter()
+            .map(|memory| memory.id)
+            .collect::<Vec<_>>();
+
+        let expected = vec![3, 2];
+        assert_eq!(actual, expected);
+    }
+}
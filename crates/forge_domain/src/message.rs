@@ -19,7 +19,7 @@ pub struct Usage {
 /// Represents a message that was received from the LLM provider
 /// NOTE: Tool call messages are part of the larger Response object and not part
 /// of the message.
-#[derive(Default, Clone, Debug, Setters, PartialEq)]
+#[derive(Default, Clone, Debug, Setters, PartialEq, Serialize)]
 #[setters(into, strip_option)]
 pub struct ChatCompletionMessage {
     pub content: Option<Content>,
@@ -37,7 +37,7 @@ impl From<FinishReason> for ChatCompletionMessage {
 }
 
 /// Represents partial or full content of a message
-#[derive(Clone, Debug, PartialEq, Eq, From)]
+#[derive(Clone, Debug, PartialEq, Eq, From, Serialize)]
 pub enum Content {
     Part(ContentPart),
     Full(ContentFull),
@@ -0,0 +1,520 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use derive_setters::Setters;
+use merge::Merge;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use url::Url;
+
+use crate::merge::Key;
+use crate::{HttpMethod, ToolDefinition, ToolName};
+
+/// Maximum depth to which `$ref`s are inlined, which also stops recursive
+/// schemas
+const MAX_REF_DEPTH: usize = 8;
+
+/// Maximum length of a tool name accepted by the providers
+const MAX_TOOL_NAME_LENGTH: usize = 64;
+
+/// An OpenAPI document whose operations are made available to the agents as
+/// tools. Requests are checked against the `request` policies like the ones
+/// of the request tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Merge, Setters, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct OpenApi {
+    /// Name of the API, used as the prefix of the names of its tools
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub name: String,
+
+    /// Path to the OpenAPI document in JSON or YAML, relative to the working
+    /// directory
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub spec: PathBuf,
+
+    /// URL the paths of the document are relative to. If not specified, the
+    /// first server of the document is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub base_url: Option<String>,
+
+    /// Headers sent with every request, eg: Authorization
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl Key for OpenApi {
+    type Id = String;
+
+    fn key(&self) -> &Self::Id {
+        &self.name
+    }
+}
+
+impl OpenApi {
+    pub fn new(name: impl Into<String>, spec: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            spec: spec.into(),
+            base_url: None,
+            headers: BTreeMap::new(),
+        }
+    }
+}
+
+/// Location of an argument of an operation in the request
+#[derive(Debug, Clone, PartialEq)]
+enum ParameterLocation {
+    Path,
+    Query,
+    Header,
+}
+
+/// An HTTP request built from the arguments of a call
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenApiRequest {
+    pub method: HttpMethod,
+    pub url: String,
+    pub headers: BTreeMap<String, String>,
+    pub body: Option<String>,
+}
+
+/// A tool generated from an operation of an OpenAPI document
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenApiTool {
+    pub name: ToolName,
+    pub description: String,
+    pub input_schema: Value,
+    method: HttpMethod,
+    base_url: String,
+    path: String,
+    parameters: Vec<(String, ParameterLocation)>,
+    headers: BTreeMap<String, String>,
+}
+
+impl OpenApiTool {
+    /// Generates a tool for each operation of the document
+    pub fn from_spec(api: &OpenApi, spec: &Value) -> anyhow::Result<Vec<Self>> {
+        let base_url = match &api.base_url {
+            Some(base_url) => base_url.clone(),
+            None => spec
+                .pointer("/servers/0/url")
+                .and_then(Value::as_str)
+                .with_context(|| {
+                    format!(
+                        "The OpenAPI document of '{}' has no server, set its base_url in forge.yaml",
+                        api.name
+                    )
+                })?
+                .to_string(),
+        };
+        let paths = spec
+            .get("paths")
+            .and_then(Value::as_object)
+            .with_context(|| format!("The OpenAPI document of '{}' has no paths", api.name))?;
+
+        let mut tools = Vec::new();
+        for (path, item) in paths {
+            let item = resolve(spec, item, 0);
+            let shared = item.get("parameters").cloned().unwrap_or(json!([]));
+            for (method, operation) in item.as_object().into_iter().flatten() {
+                let Some(method) = http_method(method) else {
+                    continue;
+                };
+                tools.push(Self::from_operation(
+                    api, spec, &base_url, path, method, operation, &shared,
+                )?);
+            }
+        }
+        Ok(tools)
+    }
+
+    fn from_operation(
+        api: &OpenApi,
+        spec: &Value,
+        base_url: &str,
+        path: &str,
+        method: HttpMethod,
+        operation: &Value,
+        shared: &Value,
+    ) -> anyhow::Result<Self> {
+        let id = match operation.get("operationId").and_then(Value::as_str) {
+            Some(id) => id.to_string(),
+            None => format!("{}_{path}", method.as_ref().to_lowercase()),
+        };
+
+        let mut description = [operation.get("summary"), operation.get("description")]
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        if !description.is_empty() {
+            description.push_str("\n\n");
+        }
+        description.push_str(&format!("Sends a {} request to {path}", method.as_ref()));
+
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        let mut parameters = Vec::new();
+        let declared = shared.as_array().into_iter().flatten().chain(
+            operation
+                .get("parameters")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten(),
+        );
+        for parameter in declared {
+            let parameter = resolve(spec, parameter, 0);
+            let Some(name) = parameter.get("name").and_then(Value::as_str) else {
+                continue;
+            };
+            let location = match parameter.get("in").and_then(Value::as_str) {
+                Some("path") => ParameterLocation::Path,
+                Some("query") => ParameterLocation::Query,
+                Some("header") => ParameterLocation::Header,
+                _ => continue,
+            };
+            let mut schema = parameter.get("schema").cloned().unwrap_or(json!({}));
+            if let (Some(schema), Some(text)) =
+                (schema.as_object_mut(), parameter.get("description"))
+            {
+                schema.insert("description".to_string(), text.clone());
+            }
+            // Operation parameters override the path ones with the same name
+            required.retain(|existing| existing != name);
+            if location == ParameterLocation::Path
+                || parameter.get("required").and_then(Value::as_bool) == Some(true)
+            {
+                required.push(Value::String(name.to_string()));
+            }
+            parameters.retain(|(existing, _)| existing != name);
+            parameters.push((name.to_string(), location));
+            properties.insert(name.to_string(), schema);
+        }
+
+        if let Some(body) = operation.get("requestBody") {
+            let body = resolve(spec, body, 0);
+            let mut schema = body
+                .pointer("/content/application~1json/schema")
+                .cloned()
+                .unwrap_or(json!({}));
+            if let (Some(schema), Some(text)) = (schema.as_object_mut(), body.get("description")) {
+                schema.insert("description".to_string(), text.clone());
+            }
+            if body.get("required").and_then(Value::as_bool) == Some(true) {
+                required.push(Value::String("body".to_string()));
+            }
+            properties.insert("body".to_string(), schema);
+        }
+
+        let mut input_schema = json!({ "type": "object", "properties": properties });
+        if !required.is_empty() {
+            input_schema["required"] = Value::Array(required);
+        }
+
+        Ok(Self {
+            name: ToolName::new(tool_name(&api.name, &id)),
+            description,
+            input_schema,
+            method,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            path: path.to_string(),
+            parameters,
+            headers: api.headers.clone(),
+        })
+    }
+
+    pub fn definition(&self) -> anyhow::Result<ToolDefinition> {
+        let input_schema = serde_json::from_value(self.input_schema.clone())
+            .with_context(|| format!("Invalid input schema for tool '{}'", self.name))?;
+        Ok(ToolDefinition::new(self.name.as_str())
+            .description(self.description.clone())
+            .input_schema(input_schema))
+    }
+
+    /// Builds the request of a call with the given arguments
+    pub fn request(&self, arguments: &Value) -> anyhow::Result<OpenApiRequest> {
+        let mut path = self.path.clone();
+        let mut query = Vec::new();
+        let mut headers = self.headers.clone();
+
+        for (name, location) in &self.parameters {
+            let value = match arguments.get(name) {
+                None | Some(Value::Null) if *location == ParameterLocation::Path => {
+                    anyhow::bail!("Missing path parameter '{name}'")
+                }
+                None | Some(Value::Null) => continue,
+                Some(Value::String(value)) => value.clone(),
+                Some(value) => value.to_string(),
+            };
+            match location {
+                ParameterLocation::Path => {
+                    path = path.replace(&format!("{{{name}}}"), &encode_path_segment(&value))
+                }
+                ParameterLocation::Query => query.push((name.clone(), value)),
+                ParameterLocation::Header => {
+                    headers.insert(name.clone(), value);
+                }
+            }
+        }
+
+        let mut url = Url::parse(&format!("{}{path}", self.base_url))
+            .with_context(|| format!("Invalid URL for tool '{}'", self.name))?;
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+
+        let body = match arguments.get("body") {
+            None | Some(Value::Null) => None,
+            Some(body) => {
+                headers
+                    .entry("Content-Type".to_string())
+                    .or_insert_with(|| "application/json".to_string());
+                Some(serde_json::to_string(body)?)
+            }
+        };
+
+        Ok(OpenApiRequest {
+            method: self.method.clone(),
+            url: url.to_string(),
+            headers,
+            body,
+        })
+    }
+}
+
+fn http_method(method: &str) -> Option<HttpMethod> {
+    match method {
+        "get" => Some(HttpMethod::Get),
+        "post" => Some(HttpMethod::Post),
+        "put" => Some(HttpMethod::Put),
+        "patch" => Some(HttpMethod::Patch),
+        "delete" => Some(HttpMethod::Delete),
+        "head" => Some(HttpMethod::Head),
+        _ => None,
+    }
+}
+
+/// Returns a copy of the value where the local `$ref`s are replaced with what
+/// they point to
+fn resolve(spec: &Value, value: &Value, depth: usize) -> Value {
+    match value {
+        Value::Object(object) => {
+            if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+                return match reference.strip_prefix('#') {
+                    Some(pointer) if depth < MAX_REF_DEPTH => spec
+                        .pointer(pointer)
+                        .map(|target| resolve(spec, target, depth + 1))
+                        .unwrap_or(json!({})),
+                    _ => json!({}),
+                };
+            }
+            Value::Object(
+                object
+                    .iter()
+                    .map(|(key, value)| (key.clone(), resolve(spec, value, depth)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| resolve(spec, value, depth))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+/// Builds the name of a tool from the name of the API and the id of the
+/// operation, keeping only the characters accepted by the providers
+fn tool_name(api: &str, id: &str) -> String {
+    let name = format!("{api}_{id}")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let mut name = name
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    name.truncate(MAX_TOOL_NAME_LENGTH);
+    name
+}
+
+fn encode_path_segment(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            byte => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn fixture_spec() -> Value {
+        json!({
+            "openapi": "3.0.0",
+            "servers": [{ "url": "https://api.example.com/v1/" }],
+            "paths": {
+                "/pets/{petId}": {
+                    "parameters": [
+                        { "name": "petId", "in": "path", "required": true, "schema": { "type": "string" } }
+                    ],
+                    "get": {
+                        "operationId": "getPet",
+                        "summary": "Returns a pet",
+                        "parameters": [
+                            { "name": "fields", "in": "query", "description": "Fields to return", "schema": { "type": "string" } }
+                        ]
+                    },
+                    "put": {
+                        "operationId": "updatePet",
+                        "requestBody": {
+                            "required": true,
+                            "content": {
+                                "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } }
+                            }
+                        }
+                    }
+                },
+                "/health": {
+                    "get": {}
+                }
+            },
+            "components": {
+                "schemas": {
+                    "Pet": { "type": "object", "properties": { "name": { "type": "string" } } }
+                }
+            }
+        })
+    }
+
+    fn fixture_tools() -> Vec<OpenApiTool> {
+        let api = OpenApi::new("petstore", "petstore.yaml").headers(BTreeMap::from([(
+            "Authorization".to_string(),
+            "Bearer token".to_string(),
+        )]));
+        let mut tools = OpenApiTool::from_spec(&api, &fixture_spec()).unwrap();
+        tools.sort_by(|a, b| a.name.as_str().cmp(b.name.as_str()));
+        tools
+    }
+
+    fn fixture_tool(name: &str) -> OpenApiTool {
+        fixture_tools()
+            .into_iter()
+            .find(|tool| tool.name.as_str() == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_tool_names() {
+        let actual = fixture_tools()
+            .into_iter()
+            .map(|tool| tool.name.to_string())
+            .collect::<Vec<_>>();
+
+        let expected = vec![
+            "petstore_getPet",
+            "petstore_get_health",
+            "petstore_updatePet",
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_input_schema() {
+        let fixture = fixture_tools();
+
+        let actual = fixture
+            .iter()
+            .map(|tool| tool.input_schema.clone())
+            .collect::<Vec<_>>();
+
+        let expected = vec![
+            json!({
+                "type": "object",
+                "properties": {
+                    "petId": { "type": "string" },
+                    "fields": { "type": "string", "description": "Fields to return" }
+                },
+                "required": ["petId"]
+            }),
+            json!({ "type": "object", "properties": {} }),
+            json!({
+                "type": "object",
+                "properties": {
+                    "petId": { "type": "string" },
+                    "body": { "type": "object", "properties": { "name": { "type": "string" } } }
+                },
+                "required": ["petId", "body"]
+            }),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_request() {
+        let fixture = fixture_tool("petstore_getPet");
+
+        let actual = fixture
+            .request(&json!({ "petId": "a b", "fields": "name,age" }))
+            .unwrap();
+
+        let expected = OpenApiRequest {
+            method: HttpMethod::Get,
+            url: "https://api.example.com/v1/pets/a%20b?fields=name%2Cage".to_string(),
+            headers: BTreeMap::from([("Authorization".to_string(), "Bearer token".to_string())]),
+            body: None,
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_request_with_body() {
+        let fixture = fixture_tool("petstore_updatePet");
+
+        let actual = fixture
+            .request(&json!({ "petId": 7, "body": { "name": "Rex" } }))
+            .unwrap();
+
+        let expected = OpenApiRequest {
+            method: HttpMethod::Put,
+            url: "https://api.example.com/v1/pets/7".to_string(),
+            headers: BTreeMap::from([
+                ("Authorization".to_string(), "Bearer token".to_string()),
+                ("Content-Type".to_string(), "application/json".to_string()),
+            ]),
+            body: Some(r#"{"name":"Rex"}"#.to_string()),
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_request_missing_path_parameter() {
+        let fixture = fixture_tool("petstore_getPet");
+
+        let actual = fixture.request(&json!({})).unwrap_err().to_string();
+
+        let expected = "Missing path parameter 'petId'";
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{ContextMessage, Role};
+
+/// Items of a conversation that compaction keeps in the context as they are
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pins {
+    /// Absolute paths of the files whose content stays in the context
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+    /// Content of the messages that are never summarized
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub messages: Vec<String>,
+}
+
+impl Pins {
+    /// Pins the file, returns false if it was already pinned
+    pub fn pin_file(&mut self, path: impl ToString) -> bool {
+        let path = path.to_string();
+        if self.files.contains(&path) {
+            return false;
+        }
+        self.files.push(path);
+        true
+    }
+
+    /// Pins the message with the content, returns false if it was already
+    /// pinned
+    pub fn pin_message(&mut self, content: impl ToString) -> bool {
+        let content = content.to_string();
+        if self.messages.contains(&content) {
+            return false;
+        }
+        self.messages.push(content);
+        true
+    }
+
+    /// Unpins the file, returns false if it wasn't pinned
+    pub fn unpin_file(&mut self, path: &str) -> bool {
+        let len = self.files.len();
+        self.files.retain(|file| file != path);
+        self.files.len() != len
+    }
+
+    /// Whether the message holds the content of the pinned file, as attached
+    /// to a message of the user
+    pub fn holds_file(path: &str, message: &ContextMessage) -> bool {
+        match message {
+            ContextMessage::Text(text) => {
+                text.has_role(Role::User)
+                    && text.content.starts_with("<file_content")
+                    && text.content.contains(&format!("path=\"{path}\""))
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether compaction has to keep the message. Messages with tool calls are
+    /// never kept, as their results would be summarized.
+    pub fn keeps(&self, message: &ContextMessage) -> bool {
+        match message {
+            ContextMessage::Text(text) if text.tool_calls.is_none() => {
+                self.messages.contains(&text.content)
+                    || self
+                        .files
+                        .iter()
+                        .any(|path| Self::holds_file(path, message))
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{ToolCallFull, ToolName};
+
+    #[test]
+    fn test_keeps_pinned_items() {
+        let mut fixture = Pins::default();
+        fixture.pin_file("/project/src/lib.rs");
+        fixture.pin_message("Always use tabs");
+
+        let messages = [
+            ContextMessage::user(
+                "<file_content\n  path=\"/project/src/lib.rs\"\n  start_line=\"1\"\n>",
+                None,
+            ),
+            ContextMessage::user(
+                "<file_content\n  path=\"/project/src/main.rs\"\n  start_line=\"1\"\n>",
+                None,
+            ),
+            ContextMessage::assistant("Always use tabs", None, None),
+            ContextMessage::assistant(
+                "Always use tabs",
+                None,
+                Some(vec![ToolCallFull::new(ToolName::new("forge_tool_fs_read"))]),
+            ),
+            ContextMessage::user("Use spaces", None),
+        ];
+        let actual = messages
+            .iter()
+            .map(|message| fixture.keeps(message))
+            .collect::<Vec<_>>();
+
+        let expected = vec![true, false, true, false, false];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_pin_file_once() {
+        let mut fixture = Pins::default();
+
+        let actual = [
+            fixture.pin_file("/project/src/lib.rs"),
+            fixture.pin_file("/project/src/lib.rs"),
+            fixture.unpin_file("/project/src/lib.rs"),
+            fixture.unpin_file("/project/src/lib.rs"),
+        ];
+
+        let expected = [true, false, true, false];
+        assert_eq!(actual, expected);
+    }
+}
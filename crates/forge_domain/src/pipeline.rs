@@ -0,0 +1,329 @@
+use std::collections::BTreeMap;
+
+use derive_more::Display;
+use derive_setters::Setters;
+use merge::Merge;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::merge::Key;
+use crate::{AgentId, ChatResponse, ConversationId};
+
+/// Steps run one after the other by `forge run <name>`, each by an agent on a
+/// prompt built from the inputs of the pipeline and the outputs of the steps
+/// before it. Each step is saved as a session tagged `pipeline` and with the
+/// name of the pipeline. A pipeline of the project replaces the global one of
+/// the same name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Merge, Setters, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct Pipeline {
+    /// Name the pipeline is run by
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub name: String,
+
+    /// What the pipeline is for, shown when listing the pipelines
+    #[merge(strategy = crate::merge::std::overwrite)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Names of the values given with `--input name=value`, available to the
+    /// prompts as `{{inputs.name}}`
+    #[merge(strategy = crate::merge::std::overwrite)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<String>,
+
+    /// Steps of the pipeline, in the order they run
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub steps: Vec<PipelineStep>,
+}
+
+impl Key for Pipeline {
+    type Id = String;
+
+    fn key(&self) -> &Self::Id {
+        &self.name
+    }
+}
+
+/// A prompt sent to an agent as a step of a pipeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Setters, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct PipelineStep {
+    /// Name of the step, by which the later steps refer to its output as
+    /// `{{steps.name.output}}`
+    pub name: String,
+
+    /// Prompt sent to the agent, a Handlebars template of the inputs and of
+    /// the `output` and `status` of the steps before
+    pub prompt: String,
+
+    /// Agent the prompt is sent to. Defaults to the main agent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<AgentId>,
+
+    /// Handlebars template the step only runs if it renders to something
+    /// other than nothing or `false`, e.g. `{{eq steps.test.status
+    /// "failed"}}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+
+    /// Keep running the next steps if this one fails. Defaults to false.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub continue_on_error: Option<bool>,
+}
+
+impl PipelineStep {
+    pub fn new(name: impl Into<String>, prompt: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            prompt: prompt.into(),
+            agent: None,
+            when: None,
+            continue_on_error: None,
+        }
+    }
+
+    /// Checks if the rendered condition of the step lets it run
+    pub fn holds(condition: &str) -> bool {
+        !matches!(condition.trim(), "" | "false")
+    }
+}
+
+impl Pipeline {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            inputs: Vec::new(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Finds the pipeline with the name, which has to be defined once
+    pub fn find(pipelines: Vec<Pipeline>, name: &str) -> anyhow::Result<Pipeline> {
+        let mut found = pipelines
+            .into_iter()
+            .filter(|pipeline| pipeline.name == name);
+        let pipeline = found
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No pipeline named {name}"))?;
+        if found.next().is_some() {
+            anyhow::bail!("Pipeline {name} is defined twice");
+        }
+        Ok(pipeline)
+    }
+
+    /// Checks that the inputs given are the ones the pipeline takes and that
+    /// its steps can be told apart
+    pub fn validate(&self, inputs: &BTreeMap<String, String>) -> anyhow::Result<()> {
+        if let Some(missing) = self.inputs.iter().find(|name| !inputs.contains_key(*name)) {
+            anyhow::bail!("Missing input {missing} of pipeline {}", self.name);
+        }
+        if let Some(unknown) = inputs.keys().find(|name| !self.inputs.contains(name)) {
+            anyhow::bail!("Unknown input {unknown} of pipeline {}", self.name);
+        }
+        for (index, step) in self.steps.iter().enumerate() {
+            if self.steps[..index]
+                .iter()
+                .any(|other| other.name == step.name)
+            {
+                anyhow::bail!(
+                    "Step {} of pipeline {} is defined twice",
+                    step.name,
+                    self.name
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How a step of a pipeline ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Display)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    #[display("completed")]
+    Completed,
+    #[display("failed")]
+    Failed,
+    /// The condition of the step didn't hold
+    #[display("skipped")]
+    Skipped,
+}
+
+/// What a running pipeline sends to the user
+#[derive(Debug)]
+pub enum PipelineEvent {
+    /// An approval or a question of the agent of a step, which waits for the
+    /// answer of the user
+    Request(ChatResponse),
+    /// A step, once it's done
+    Step(StepRun),
+}
+
+/// A step of a pipeline, once it's done
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepRun {
+    pub step: String,
+    pub status: StepStatus,
+    /// Session the step is saved as, unless it was skipped
+    pub conversation_id: Option<ConversationId>,
+    /// Last answer of the agent, if it gave one
+    pub output: Option<String>,
+    /// Why the step failed, if it did
+    pub error: Option<String>,
+}
+
+impl StepRun {
+    pub fn skipped(step: impl Into<String>) -> Self {
+        Self {
+            step: step.into(),
+            status: StepStatus::Skipped,
+            conversation_id: None,
+            output: None,
+            error: None,
+        }
+    }
+}
+
+/// Values the templates of the steps are rendered with
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct PipelineContext {
+    inputs: BTreeMap<String, String>,
+    steps: BTreeMap<String, StepValues>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct StepValues {
+    status: StepStatus,
+    output: String,
+}
+
+impl PipelineContext {
+    pub fn new(inputs: BTreeMap<String, String>) -> Self {
+        Self { inputs, steps: BTreeMap::new() }
+    }
+
+    /// Makes the output and the status of the step available to the next
+    /// steps
+    pub fn record(&mut self, run: &StepRun) {
+        self.steps.insert(
+            run.step.clone(),
+            StepValues {
+                status: run.status,
+                output: run.output.clone().unwrap_or_default(),
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn fixture() -> Pipeline {
+        Pipeline::new("release")
+            .inputs(vec!["version".to_string()])
+            .steps(vec![
+                PipelineStep::new("test", "Run the tests"),
+                PipelineStep::new("fix", "Fix {{steps.test.output}}"),
+            ])
+    }
+
+    #[test]
+    fn test_validate() {
+        let fixture = fixture();
+
+        let actual = [
+            BTreeMap::from([("version".to_string(), "1.2.0".to_string())]),
+            BTreeMap::new(),
+            BTreeMap::from([
+                ("version".to_string(), "1.2.0".to_string()),
+                ("branch".to_string(), "main".to_string()),
+            ]),
+        ]
+        .map(|inputs| fixture.validate(&inputs).map_err(|error| error.to_string()));
+
+        let expected = [
+            Ok(()),
+            Err("Missing input version of pipeline release".to_string()),
+            Err("Unknown input branch of pipeline release".to_string()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_validate_duplicate_step() {
+        let fixture = fixture().steps(vec![
+            PipelineStep::new("test", "Run the tests"),
+            PipelineStep::new("test", "Run the tests again"),
+        ]);
+
+        let actual = fixture
+            .validate(&BTreeMap::from([("version".to_string(), "1".to_string())]))
+            .map_err(|error| error.to_string());
+
+        let expected = Err("Step test of pipeline release is defined twice".to_string());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_find() {
+        let fixture = vec![
+            fixture(),
+            Pipeline::new("deploy"),
+            Pipeline::new("deploy").description("Deploy again"),
+        ];
+
+        let actual = ["release", "deploy", "bump"].map(|name| {
+            Pipeline::find(fixture.clone(), name)
+                .map(|pipeline| pipeline.name)
+                .map_err(|error| error.to_string())
+        });
+
+        let expected = [
+            Ok("release".to_string()),
+            Err("Pipeline deploy is defined twice".to_string()),
+            Err("No pipeline named bump".to_string()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_holds() {
+        let actual = ["true", " ", "false", "3 failures"].map(PipelineStep::holds);
+
+        let expected = [true, false, false, true];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_context() {
+        let mut fixture = PipelineContext::new(BTreeMap::from([(
+            "version".to_string(),
+            "1.2.0".to_string(),
+        )]));
+        fixture.record(&StepRun {
+            step: "test".to_string(),
+            status: StepStatus::Failed,
+            conversation_id: None,
+            output: Some("2 tests failed".to_string()),
+            error: None,
+        });
+        fixture.record(&StepRun::skipped("fix"));
+
+        let actual = serde_json::to_value(&fixture).unwrap();
+
+        let expected = json!({
+            "inputs": {"version": "1.2.0"},
+            "steps": {
+                "test": {"status": "failed", "output": "2 tests failed"},
+                "fix": {"status": "skipped", "output": ""}
+            }
+        });
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+
+use derive_setters::Setters;
+use merge::Merge;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::merge::Key;
+
+/// A WASM component implementing the `forge:tool` interface, whose tools are
+/// made available to the agents.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Merge, Setters, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct Plugin {
+    /// Name of the plugin, used to identify it in forge.yaml
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub name: String,
+
+    /// Path to the WASM component, relative to the working directory
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub path: PathBuf,
+
+    /// Capabilities granted to the plugin. Plugins are fully sandboxed by
+    /// default.
+    #[serde(default)]
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub capabilities: PluginCapabilities,
+}
+
+/// Capabilities a plugin is allowed to use
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, Setters, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct PluginCapabilities {
+    /// Directories the plugin can read and write, relative to the working
+    /// directory. They are mounted at the same relative path inside the
+    /// sandbox.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub directories: Vec<PathBuf>,
+
+    /// Whether the plugin can resolve host names and open network connections
+    #[serde(default)]
+    pub network: bool,
+}
+
+impl Key for Plugin {
+    type Id = String;
+
+    fn key(&self) -> &Self::Id {
+        &self.name
+    }
+}
+
+impl Plugin {
+    pub fn new(name: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            path: path.into(),
+            capabilities: PluginCapabilities::default(),
+        }
+    }
+}
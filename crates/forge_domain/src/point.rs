@@ -50,6 +50,20 @@ impl<C> Point<C> {
             updated_at: self.updated_at,
         })
     }
+
+    /// Cosine similarity of the embedding of the point with the one of the
+    /// query, from -1 for opposite meanings to 1 for the same meaning
+    pub fn similarity(&self, query: &Query) -> f32 {
+        let dot = self
+            .embedding
+            .iter()
+            .zip(&query.embedding)
+            .map(|(a, b)| a * b)
+            .sum::<f32>();
+        let norm = |embedding: &[f32]| embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+        let norms = norm(&self.embedding) * norm(&query.embedding);
+        if norms == 0.0 { 0.0 } else { dot / norms }
+    }
 }
 
 #[derive(Debug, Clone, Setters)]
@@ -65,3 +79,25 @@ impl Query {
         Self { embedding, limit: None, distance: None }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_similarity() {
+        let fixture = Point::new("content", vec![3.0, 0.0, 4.0]);
+
+        let actual = [
+            fixture.similarity(&Query::new(vec![6.0, 0.0, 8.0])),
+            fixture.similarity(&Query::new(vec![0.0, 1.0, 0.0])),
+            fixture.similarity(&Query::new(vec![-3.0, 0.0, -4.0])),
+            fixture.similarity(&Query::new(vec![0.0, 0.0, 0.0])),
+        ];
+
+        let expected = [1.0, 0.0, -1.0, 0.0];
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,45 @@
+use strum_macros::{Display, EnumIter};
+
+use crate::Reply;
+
+/// User response to an operation that requires confirmation
+#[derive(Debug, Clone, PartialEq, Eq, Display, EnumIter)]
+pub enum Approval {
+    /// Allow the operation
+    #[strum(to_string = "Accept")]
+    Accept,
+    /// Deny the operation
+    #[strum(to_string = "Reject")]
+    Reject,
+    /// Allow the operation and remember this choice for similar operations
+    #[strum(to_string = "Accept and Remember")]
+    AcceptAndRemember,
+    /// Allow the operations of the same class until the end of the session
+    #[strum(to_string = "Always Accept in this Session")]
+    AcceptForSession,
+    /// Allow the operations of the same class in the workspace from now on
+    #[strum(to_string = "Always Accept in this Workspace")]
+    AcceptForWorkspace,
+}
+
+/// Channel through which the UI answers an approval request. A request that
+/// is dropped without an answer is treated as rejected.
+pub type ApprovalReply = Reply<Approval>;
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reply_is_received_once() {
+        let (fixture, rx) = ApprovalReply::channel();
+
+        let first = fixture.clone().send(Approval::Accept);
+        let second = fixture.send(Approval::Reject);
+        let actual = rx.await.unwrap();
+
+        assert_eq!((first, second, actual), (true, false, Approval::Accept));
+    }
+}
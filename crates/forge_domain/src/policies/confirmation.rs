@@ -0,0 +1,230 @@
+use merge::Merge;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumIter};
+
+use super::operation::Operation;
+use super::types::Permission;
+use crate::Tools;
+
+/// Classes of operations whose confirmation is configured as a whole
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+    Display,
+    EnumIter,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum OperationClass {
+    FileWrite,
+    FileDelete,
+    Shell,
+    Network,
+}
+
+impl OperationClass {
+    /// Class of the operation made by the tool call, `None` for the tools that
+    /// only read
+    pub fn from_tool(tool: &Tools) -> Option<Self> {
+        match tool {
+            Tools::ForgeToolFsCreate(_)
+            | Tools::ForgeToolFsPatch(_)
+            | Tools::ForgeToolFsApplyPatch(_)
+            | Tools::ForgeToolFsAstEdit(_)
+            | Tools::ForgeToolFsUndo(_)
+            | Tools::ForgeToolLspRenameSymbol(_) => Some(OperationClass::FileWrite),
+            Tools::ForgeToolFsRemove(_) => Some(OperationClass::FileDelete),
+            Tools::ForgeToolProcessShell(_)
+            | Tools::ForgeToolProcessDocker(_)
+            | Tools::ForgeToolProcessPython(_)
+            | Tools::ForgeToolProcessBackground(_) => Some(OperationClass::Shell),
+            Tools::ForgeToolNetFetch(_)
+            | Tools::ForgeToolNetBrowser(_)
+            | Tools::ForgeToolNetRequest(_) => Some(OperationClass::Network),
+            _ => None,
+        }
+    }
+
+    /// Class of an operation checked on its own, apart from the tool call
+    /// making it. Removals are checked as writes by the policies, so they are
+    /// only told apart by [`OperationClass::from_tool`].
+    pub fn from_operation(operation: &Operation) -> Option<Self> {
+        match operation {
+            Operation::Write { .. } => Some(OperationClass::FileWrite),
+            Operation::Execute { .. } => Some(OperationClass::Shell),
+            Operation::Fetch { .. } | Operation::Request { .. } => Some(OperationClass::Network),
+            Operation::Read { .. } | Operation::Tool { .. } => None,
+        }
+    }
+}
+
+/// Whether an operation is allowed without asking, refused, or confirmed with
+/// the user first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Confirmation {
+    Always,
+    Never,
+    Ask,
+}
+
+impl From<Confirmation> for Permission {
+    fn from(value: Confirmation) -> Self {
+        match value {
+            Confirmation::Always => Permission::Allow,
+            Confirmation::Never => Permission::Deny,
+            Confirmation::Ask => Permission::Confirm,
+        }
+    }
+}
+
+impl Confirmation {
+    /// Permission of an operation of a class confirmed this way, given the one
+    /// of the policies. A denial of the policies, explicit or by default,
+    /// holds, and so does an operation they allow unless the class is never
+    /// confirmed. The confirmation decides the operations the policies would
+    /// ask about.
+    pub fn permission(self, policy: Permission) -> Permission {
+        match (policy, self) {
+            (Permission::Deny, _) | (_, Confirmation::Never) => Permission::Deny,
+            (Permission::Allow, _) => Permission::Allow,
+            (Permission::Confirm, confirmation) => confirmation.into(),
+        }
+    }
+}
+
+/// Confirmation of the operations of each class. A class that is set decides
+/// the operations of the class that the policies would ask about.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, Merge, JsonSchema)]
+#[merge(strategy = merge::option::overwrite_none)]
+pub struct Confirmations {
+    /// Creating and changing files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_write: Option<Confirmation>,
+
+    /// Removing files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_delete: Option<Confirmation>,
+
+    /// Running shell commands, containers and Python code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<Confirmation>,
+
+    /// Fetching URLs, sending requests and browsing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<Confirmation>,
+}
+
+impl Confirmations {
+    /// Confirmation of the operations of the class, if it's set
+    pub fn get(&self, class: OperationClass) -> Option<Confirmation> {
+        match class {
+            OperationClass::FileWrite => self.file_write,
+            OperationClass::FileDelete => self.file_delete,
+            OperationClass::Shell => self.shell,
+            OperationClass::Network => self.network,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{ExecuteRule, FSRemove, Policy, PolicyConfig, PolicyEngine, Rule, Shell};
+
+    #[test]
+    fn test_get() {
+        let fixture: Confirmations = serde_yml::from_str("shell: ask\nfile_delete: never").unwrap();
+
+        let actual = [
+            OperationClass::FileWrite,
+            OperationClass::FileDelete,
+            OperationClass::Shell,
+        ]
+        .map(|class| fixture.get(class));
+
+        let expected = [None, Some(Confirmation::Never), Some(Confirmation::Ask)];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_operation_class_from_tool() {
+        let actual = [
+            Tools::ForgeToolFsRemove(FSRemove { path: "a.txt".to_string(), ..Default::default() }),
+            Tools::ForgeToolProcessShell(Shell { command: "ls".to_string(), ..Default::default() }),
+        ]
+        .map(|tool| OperationClass::from_tool(&tool));
+
+        let expected = [
+            Some(OperationClass::FileDelete),
+            Some(OperationClass::Shell),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_permission_of_a_denied_command_always_run() {
+        let policies = PolicyConfig::new().add_policy(Policy::Simple {
+            permission: Permission::Deny,
+            rule: Rule::Execute(ExecuteRule { command: "rm -rf*".to_string(), dir: None }),
+        });
+        let operation = Operation::Execute {
+            command: "rm -rf /".to_string(),
+            cwd: Default::default(),
+            message: "Run rm -rf /".to_string(),
+        };
+
+        let actual =
+            Confirmation::Always.permission(PolicyEngine::new(&policies).can_perform(&operation));
+
+        let expected = Permission::Deny;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_permission_of_an_unlisted_request_always_sent() {
+        let policies = PolicyConfig::new();
+        let operation = Operation::Request {
+            method: "POST".to_string(),
+            url: "https://api.example.com/deploy".to_string(),
+            cwd: Default::default(),
+            message: "Send a request".to_string(),
+        };
+
+        let actual =
+            Confirmation::Always.permission(PolicyEngine::new(&policies).can_perform(&operation));
+
+        let expected = Permission::Deny;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_permission_decides_what_the_policies_ask_about() {
+        let actual = [
+            Confirmation::Always.permission(Permission::Confirm),
+            Confirmation::Ask.permission(Permission::Confirm),
+            Confirmation::Ask.permission(Permission::Allow),
+            Confirmation::Never.permission(Permission::Allow),
+        ];
+
+        let expected = [
+            Permission::Allow,
+            Permission::Confirm,
+            Permission::Allow,
+            Permission::Deny,
+        ];
+        assert_eq!(actual, expected);
+    }
+}
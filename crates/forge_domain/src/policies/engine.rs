@@ -26,12 +26,20 @@ impl<'a> PolicyEngine<'a> {
     }
 
     /// Internal helper function to evaluate policies for a given operation
-    /// Returns permission result, defaults to Confirm if no policies match
+    /// Returns permission result, defaults to Confirm if no policies match.
+    /// Tool calls are allowed by default, since the operations they perform
+    /// are checked separately. HTTP requests are denied by default, so that
+    /// only the APIs allowed by a policy can be called.
     fn evaluate_policies(&self, operation: &Operation) -> Permission {
+        let default = match operation {
+            Operation::Tool { .. } => Permission::Allow,
+            Operation::Request { .. } => Permission::Deny,
+            _ => Permission::Confirm,
+        };
         let has_policies = !self.policies.policies.is_empty();
 
         if !has_policies {
-            return Permission::Confirm;
+            return default;
         }
 
         let mut last_allow: Option<Permission> = None;
@@ -53,8 +61,8 @@ impl<'a> PolicyEngine<'a> {
             }
         }
 
-        // Return last allow if found, otherwise default to Confirm
-        last_allow.unwrap_or(Permission::Confirm)
+        // Return last allow if found, otherwise fall back to the default
+        last_allow.unwrap_or(default)
     }
 
     /// Helper function to evaluate a set of policies
@@ -91,7 +99,10 @@ mod tests {
     use pretty_assertions::assert_eq;
 
     use super::*;
-    use crate::{ExecuteRule, Fetch, Permission, Policy, PolicyConfig, ReadRule, Rule, WriteRule};
+    use crate::{
+        ExecuteRule, Fetch, Permission, Policy, PolicyConfig, ReadRule, RequestRule, Rule,
+        ToolName, ToolRule, WriteRule,
+    };
 
     fn fixture_workflow_with_read_policy() -> PolicyConfig {
         let policies = PolicyConfig::new().add_policy(Policy::Simple {
@@ -207,4 +218,63 @@ mod tests {
 
         assert_eq!(actual, Permission::Allow);
     }
+
+    #[test]
+    fn test_policy_engine_tool_call_allowed_by_default() {
+        let fixture_workflow = fixture_workflow_with_read_policy();
+        let fixture = PolicyEngine::new(&fixture_workflow);
+        let operation = Operation::Tool {
+            name: ToolName::new("forge_tool_fs_remove"),
+            cwd: std::path::PathBuf::from("/test/cwd"),
+            message: "Call tool: forge_tool_fs_remove".to_string(),
+        };
+
+        let actual = fixture.can_perform(&operation);
+
+        assert_eq!(actual, Permission::Allow);
+    }
+
+    #[test]
+    fn test_policy_engine_tool_call_denied() {
+        let fixture_workflow = PolicyConfig::new().add_policy(Policy::Simple {
+            permission: Permission::Deny,
+            rule: Rule::Tool(ToolRule { tool: "forge_tool_fs_remove".to_string(), dir: None }),
+        });
+        let fixture = PolicyEngine::new(&fixture_workflow);
+        let operation = Operation::Tool {
+            name: ToolName::new("forge_tool_fs_remove"),
+            cwd: std::path::PathBuf::from("/test/cwd"),
+            message: "Call tool: forge_tool_fs_remove".to_string(),
+        };
+
+        let actual = fixture.can_perform(&operation);
+
+        assert_eq!(actual, Permission::Deny);
+    }
+
+    #[test]
+    fn test_policy_engine_request_requires_allowlist() {
+        let fixture_workflow = PolicyConfig::new().add_policy(Policy::Simple {
+            permission: Permission::Allow,
+            rule: Rule::Request(RequestRule {
+                request: "http://localhost:8080/*".to_string(),
+                method: None,
+                dir: None,
+            }),
+        });
+        let fixture = PolicyEngine::new(&fixture_workflow);
+        let operation = |url: &str| Operation::Request {
+            method: "GET".to_string(),
+            url: url.to_string(),
+            cwd: std::path::PathBuf::from("/test/cwd"),
+            message: format!("GET request to URL: {url}"),
+        };
+
+        let actual = (
+            fixture.can_perform(&operation("http://localhost:8080/health")),
+            fixture.can_perform(&operation("https://api.example.com/users")),
+        );
+
+        assert_eq!(actual, (Permission::Allow, Permission::Deny));
+    }
 }
@@ -1,13 +1,19 @@
+mod approval;
 mod config;
+mod confirmation;
 mod engine;
 mod operation;
 mod policy;
+mod review;
 mod rule;
 mod types;
 
+pub use approval::*;
 pub use config::*;
+pub use confirmation::*;
 pub use engine::*;
 pub use operation::*;
 pub use policy::*;
+pub use review::*;
 pub use rule::*;
 pub use types::*;
@@ -1,5 +1,7 @@
 use std::path::PathBuf;
 
+use crate::ToolName;
+
 /// Operations that can be performed and need policy checking
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Operation {
@@ -27,4 +29,31 @@ pub enum Operation {
         cwd: PathBuf,
         message: String,
     },
+    /// HTTP request to an API
+    Request {
+        method: String,
+        url: String,
+        cwd: PathBuf,
+        message: String,
+    },
+    /// Call of a tool by its name
+    Tool {
+        name: ToolName,
+        cwd: PathBuf,
+        message: String,
+    },
+}
+
+impl Operation {
+    /// Human readable description of the operation
+    pub fn message(&self) -> &str {
+        match self {
+            Operation::Write { message, .. }
+            | Operation::Read { message, .. }
+            | Operation::Execute { message, .. }
+            | Operation::Fetch { message, .. }
+            | Operation::Request { message, .. }
+            | Operation::Tool { message, .. } => message,
+        }
+    }
 }
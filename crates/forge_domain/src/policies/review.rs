@@ -0,0 +1,141 @@
+use std::path::PathBuf;
+
+use similar::{DiffTag, TextDiff};
+
+use crate::Reply;
+
+/// Lines of a file replaced by a change, accepted or rejected on their own
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// Line of the file before the change where the hunk starts, 0-based
+    pub old_start: usize,
+    /// Lines removed by the hunk, with their line endings
+    pub removed: Vec<String>,
+    /// Lines added by the hunk, with their line endings
+    pub added: Vec<String>,
+}
+
+impl Hunk {
+    /// Splits the change from `before` to `after` into hunks, each a run of
+    /// changed lines between unchanged ones
+    pub fn split(before: &str, after: &str) -> Vec<Hunk> {
+        let diff = TextDiff::from_lines(before, after);
+        let old = diff.old_slices();
+        let new = diff.new_slices();
+
+        let mut hunks = Vec::<Hunk>::new();
+        let mut in_hunk = false;
+        for op in diff.ops() {
+            let (tag, old_range, new_range) = op.as_tag_tuple();
+            if tag == DiffTag::Equal {
+                in_hunk = false;
+                continue;
+            }
+            if !in_hunk {
+                hunks.push(Hunk { old_start: old_range.start, removed: vec![], added: vec![] });
+                in_hunk = true;
+            }
+            if let Some(hunk) = hunks.last_mut() {
+                hunk.removed
+                    .extend(old[old_range].iter().map(|line| line.to_string()));
+                hunk.added
+                    .extend(new[new_range].iter().map(|line| line.to_string()));
+            }
+        }
+        hunks
+    }
+
+    /// Content of the file with the hunks accepted applied to `before`, which
+    /// they were split from. Hunks without an answer are applied too.
+    pub fn apply(before: &str, hunks: &[Hunk], accepted: &[bool]) -> String {
+        let old = before.split_inclusive('\n').collect::<Vec<_>>();
+        let mut content = String::new();
+        let mut line = 0;
+        for (index, hunk) in hunks.iter().enumerate() {
+            let start = hunk.old_start.clamp(line, old.len());
+            content.extend(old[line..start].iter().copied());
+            if accepted.get(index).copied().unwrap_or(true) {
+                content.extend(hunk.added.iter().map(String::as_str));
+            } else {
+                content.extend(hunk.removed.iter().map(String::as_str));
+            }
+            line = (start + hunk.removed.len()).min(old.len());
+        }
+        content.extend(old[line..].iter().copied());
+        content
+    }
+}
+
+/// Change a tool is about to make to a file, sent along with the approval
+/// request so that the UI can let the user review it hunk by hunk
+#[derive(Debug, Clone)]
+pub struct ChangeReview {
+    pub path: PathBuf,
+    pub hunks: Vec<Hunk>,
+    /// Whether each hunk is accepted, answered before the approval. When it
+    /// isn't answered the approval applies to the whole change.
+    pub reply: Reply<Vec<bool>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_split_into_hunks_between_unchanged_lines() {
+        let fixture = Hunk::split("a\nb\nc\nd\ne\n", "a\nB\nc\nd\ne\nf\n");
+
+        let actual = fixture;
+
+        let expected = vec![
+            Hunk {
+                old_start: 1,
+                removed: lines(&["b\n"]),
+                added: lines(&["B\n"]),
+            },
+            Hunk { old_start: 5, removed: vec![], added: lines(&["f\n"]) },
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_apply_accepted_hunks_only() {
+        let before = "a\nb\nc\nd\ne\n";
+        let after = "x\na\nc\nd\nE\n";
+        let fixture = Hunk::split(before, after);
+
+        let actual = (0..fixture.len())
+            .map(|rejected| {
+                let accepted = (0..fixture.len())
+                    .map(|index| index != rejected)
+                    .collect::<Vec<_>>();
+                Hunk::apply(before, &fixture, &accepted)
+            })
+            .collect::<Vec<_>>();
+
+        let expected = vec![
+            "a\nc\nd\nE\n".to_string(),
+            "x\na\nb\nc\nd\nE\n".to_string(),
+            "x\na\nc\nd\ne\n".to_string(),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_apply_all_hunks_gives_change() {
+        let before = "fn main() {\n    println!(\"a\");\n}";
+        let after = "fn main() {\n    println!(\"b\");\n    println!(\"c\");\n}\n";
+        let fixture = Hunk::split(before, after);
+
+        let actual = Hunk::apply(before, &fixture, &[]);
+
+        let expected = after;
+        assert_eq!(actual, expected);
+    }
+}
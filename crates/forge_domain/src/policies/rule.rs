@@ -39,6 +39,24 @@ pub struct Fetch {
     pub dir: Option<String>,
 }
 
+/// Rule for HTTP requests with a URL pattern and an optional method pattern
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+pub struct RequestRule {
+    pub request: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+}
+
+/// Rule for tool calls with a tool name pattern
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+pub struct ToolRule {
+    pub tool: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+}
+
 /// Rules that define what operations are covered by a policy
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
@@ -51,6 +69,10 @@ pub enum Rule {
     Execute(ExecuteRule),
     /// Rule for network fetch operations with a URL pattern
     Fetch(Fetch),
+    /// Rule for HTTP requests with a URL pattern and an optional method pattern
+    Request(RequestRule),
+    /// Rule for tool calls with a tool name pattern
+    Tool(ToolRule),
 }
 
 impl Rule {
@@ -94,6 +116,28 @@ impl Rule {
                 };
                 url_matches && dir_matches
             }
+            (Rule::Request(rule), Operation::Request { method, url, cwd, message: _ }) => {
+                let url_matches = match_pattern(&rule.request, url);
+                let method_matches = match &rule.method {
+                    Some(method_pattern) => match_pattern(&method_pattern.to_uppercase(), method),
+                    None => true, // If no method pattern is specified, it matches any method
+                };
+                let dir_matches = match &rule.dir {
+                    Some(wd_pattern) => match_pattern(wd_pattern, cwd),
+                    None => true, /* If no working directory pattern is specified, it matches any
+                                   * directory */
+                };
+                url_matches && method_matches && dir_matches
+            }
+            (Rule::Tool(rule), Operation::Tool { name, cwd, message: _ }) => {
+                let name_matches = match_pattern(&rule.tool, name.as_str());
+                let dir_matches = match &rule.dir {
+                    Some(wd_pattern) => match_pattern(wd_pattern, cwd),
+                    None => true, /* If no working directory pattern is specified, it matches any
+                                   * directory */
+                };
+                name_matches && dir_matches
+            }
             _ => false,
         }
     }
@@ -240,6 +284,42 @@ mod tests {
         assert_eq!(actual, true);
     }
 
+    #[test]
+    fn test_tool_name_pattern_match() {
+        let fixture = Rule::Tool(ToolRule { tool: "github_*".to_string(), dir: None });
+        let operation = Operation::Tool {
+            name: crate::ToolName::new("github_create_issue"),
+            cwd: PathBuf::from("/home/user/project"),
+            message: "Call tool: github_create_issue".to_string(),
+        };
+
+        let actual = fixture.matches(&operation);
+
+        assert_eq!(actual, true);
+    }
+
+    #[test]
+    fn test_request_rule_matches_method() {
+        let fixture = Rule::Request(RequestRule {
+            request: "http://localhost:8080/*".to_string(),
+            method: Some("get".to_string()),
+            dir: None,
+        });
+        let operation = |method: &str| Operation::Request {
+            method: method.to_string(),
+            url: "http://localhost:8080/api/users".to_string(),
+            cwd: PathBuf::from("/home/user/project"),
+            message: format!("{method} request to URL: http://localhost:8080/api/users"),
+        };
+
+        let actual = (
+            fixture.matches(&operation("GET")),
+            fixture.matches(&operation("DELETE")),
+        );
+
+        assert_eq!(actual, (true, false));
+    }
+
     #[test]
     fn test_execute_working_directory_pattern_match() {
         let fixture = Rule::Execute(ExecuteRule {
@@ -317,6 +397,27 @@ impl Display for Fetch {
     }
 }
 
+impl Display for RequestRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let method = self.method.as_deref().unwrap_or("*");
+        if let Some(wd) = &self.dir {
+            write!(f, "request {method} '{}' in '{}'", self.request, wd)
+        } else {
+            write!(f, "request {method} '{}'", self.request)
+        }
+    }
+}
+
+impl Display for ToolRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(wd) = &self.dir {
+            write!(f, "call '{}' in '{}'", self.tool, wd)
+        } else {
+            write!(f, "call '{}'", self.tool)
+        }
+    }
+}
+
 impl Display for Rule {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -324,6 +425,8 @@ impl Display for Rule {
             Rule::Read(rule) => write!(f, "{rule}"),
             Rule::Execute(rule) => write!(f, "{rule}"),
             Rule::Fetch(rule) => write!(f, "{rule}"),
+            Rule::Request(rule) => write!(f, "{rule}"),
+            Rule::Tool(rule) => write!(f, "{rule}"),
         }
     }
 }
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use derive_more::Display;
+use derive_setters::Setters;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{ModelId, RetryOverrides};
+
+/// A named set of provider and model settings that can be switched at
+/// runtime, eg: to keep work and personal accounts apart.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, Setters, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct Profile {
+    /// Provider used while this profile is active. If not specified, the
+    /// provider is detected from the environment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<ProviderDetails>,
+
+    /// Default model used while this profile is active. If not specified, the
+    /// workflow model is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<ModelId>,
+}
+
+/// Connection details for an upstream provider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Setters, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct ProviderDetails {
+    /// Type of the provider. One of `forge`, `openai`, `open_router`,
+    /// `requesty`, `xai` or `anthropic`.
+    pub provider_type: String,
+
+    /// Overrides the default base URL of the provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+
+    /// Name of the environment variable holding the API key. Multiple keys
+    /// can be provided as a comma separated list. If not specified, the
+    /// default variable for the provider type is used (eg:
+    /// `OPENAI_API_KEY`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_env: Option<String>,
+
+    /// Additional HTTP headers sent with every request to the provider, eg:
+    /// `X-Org-Id` for gateways or attribution headers for OpenRouter.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+
+    /// Overrides the global retry configuration for this provider, eg: to fail
+    /// fast on a local model server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryOverrides>,
+}
+
+impl ProviderDetails {
+    pub fn new(provider_type: impl Into<String>) -> Self {
+        Self {
+            provider_type: provider_type.into(),
+            base_url: None,
+            api_key_env: None,
+            headers: HashMap::new(),
+            retry: None,
+        }
+    }
+}
+
+/// Problems found while validating the provider configuration of a profile
+#[derive(Debug, Clone, PartialEq, Display)]
+pub enum ProviderWarning {
+    #[display("Profile '{profile}' uses unknown provider type '{provider_type}'")]
+    UnknownProviderType {
+        profile: String,
+        provider_type: String,
+    },
+    #[display("Profile '{profile}' has a malformed base_url '{base_url}': {reason}")]
+    InvalidBaseUrl {
+        profile: String,
+        base_url: String,
+        reason: String,
+    },
+    #[display("Profile '{profile}' has an invalid header '{header}': {reason}")]
+    InvalidHeader {
+        profile: String,
+        header: String,
+        reason: String,
+    },
+    #[display("Profile '{profile}' expects an API key in '{env}' but it is not set")]
+    MissingApiKey { profile: String, env: String },
+}
@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+
+use derive_setters::Setters;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::mcp::expand_env;
+
+/// Environment variables the shell commands of the project run with, such as
+/// the `DATABASE_URL` of its tests, without having to export them globally
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, Setters, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct ProjectEnv {
+    /// `.env` files the variables are loaded from, relative to the working
+    /// directory, e.g. `.env.test`. The variables of a file override the ones
+    /// of the files listed before it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+
+    /// Variables set for the commands, overriding the ones of the files.
+    /// `${VAR}` is replaced with the value of VAR in the files or in the
+    /// environment of forge.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub vars: BTreeMap<String, String>,
+}
+
+impl ProjectEnv {
+    /// Returns the variables loaded from the files along with `vars`, whose
+    /// references are looked up in the loaded variables first and in `env`
+    /// then. References to unset variables are kept as they are.
+    pub fn resolve(
+        &self,
+        mut loaded: BTreeMap<String, String>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> BTreeMap<String, String> {
+        let vars = self
+            .vars
+            .iter()
+            .map(|(name, value)| {
+                let value = expand_env(value, |name| {
+                    loaded.get(name).cloned().or_else(|| env(name))
+                });
+                (name.clone(), value)
+            })
+            .collect::<Vec<_>>();
+        loaded.extend(vars);
+        loaded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let fixture = ProjectEnv::default().vars(BTreeMap::from([
+            (
+                "DATABASE_URL".to_string(),
+                "postgres://${DB_HOST}/${USER}_test".to_string(),
+            ),
+            ("RUST_LOG".to_string(), "${LOG_LEVEL}".to_string()),
+            ("DB_HOST".to_string(), "db:5432".to_string()),
+        ]));
+        let loaded = BTreeMap::from([("DB_HOST".to_string(), "localhost:5432".to_string())]);
+
+        let actual = fixture.resolve(loaded, |name| (name == "USER").then(|| "ada".to_string()));
+
+        let expected = BTreeMap::from([
+            (
+                "DATABASE_URL".to_string(),
+                "postgres://localhost:5432/ada_test".to_string(),
+            ),
+            ("DB_HOST".to_string(), "db:5432".to_string()),
+            ("RUST_LOG".to_string(), "${LOG_LEVEL}".to_string()),
+        ]);
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,6 +1,10 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+use crate::RetryOverrides;
+
 #[derive(Debug, Clone)]
 pub enum ProviderUrl {
     OpenAI(String),
@@ -18,8 +22,26 @@ impl ProviderUrl {
 /// Providers that can be used.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Provider {
-    OpenAI { url: Url, key: Option<String> },
-    Anthropic { url: Url, key: String },
+    OpenAI {
+        url: Url,
+        key: Option<String>,
+        /// Additional headers sent with every request
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        headers: HashMap<String, String>,
+        /// Overrides of the global retry configuration
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        retry: Option<RetryOverrides>,
+    },
+    Anthropic {
+        url: Url,
+        key: String,
+        /// Additional headers sent with every request
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        headers: HashMap<String, String>,
+        /// Overrides of the global retry configuration
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        retry: Option<RetryOverrides>,
+    },
 }
 
 impl Provider {
@@ -61,6 +83,8 @@ impl Provider {
         Provider::OpenAI {
             url: Url::parse(Provider::FORGE_URL).unwrap(),
             key: Some(key.into()),
+            headers: HashMap::new(),
+            retry: None,
         }
     }
 
@@ -68,6 +92,8 @@ impl Provider {
         Provider::OpenAI {
             url: Url::parse(Provider::OPENAI_URL).unwrap(),
             key: Some(key.into()),
+            headers: HashMap::new(),
+            retry: None,
         }
     }
 
@@ -75,6 +101,8 @@ impl Provider {
         Provider::OpenAI {
             url: Url::parse(Provider::OPEN_ROUTER_URL).unwrap(),
             key: Some(key.into()),
+            headers: HashMap::new(),
+            retry: None,
         }
     }
 
@@ -82,6 +110,8 @@ impl Provider {
         Provider::OpenAI {
             url: Url::parse(Provider::REQUESTY_URL).unwrap(),
             key: Some(key.into()),
+            headers: HashMap::new(),
+            retry: None,
         }
     }
 
@@ -89,6 +119,8 @@ impl Provider {
         Provider::OpenAI {
             url: Url::parse(Provider::XAI_URL).unwrap(),
             key: Some(key.into()),
+            headers: HashMap::new(),
+            retry: None,
         }
     }
 
@@ -96,6 +128,40 @@ impl Provider {
         Provider::Anthropic {
             url: Url::parse(Provider::ANTHROPIC_URL).unwrap(),
             key: key.into(),
+            headers: HashMap::new(),
+            retry: None,
+        }
+    }
+
+    /// Replaces the additional headers sent with every request
+    pub fn with_headers(mut self, headers: HashMap<String, String>) -> Self {
+        match &mut self {
+            Provider::OpenAI { headers: set_headers, .. } => *set_headers = headers,
+            Provider::Anthropic { headers: set_headers, .. } => *set_headers = headers,
+        }
+        self
+    }
+
+    /// Replaces the overrides of the global retry configuration
+    pub fn with_retry(mut self, retry: Option<RetryOverrides>) -> Self {
+        match &mut self {
+            Provider::OpenAI { retry: set_retry, .. } => *set_retry = retry,
+            Provider::Anthropic { retry: set_retry, .. } => *set_retry = retry,
+        }
+        self
+    }
+
+    pub fn retry(&self) -> Option<&RetryOverrides> {
+        match self {
+            Provider::OpenAI { retry, .. } => retry.as_ref(),
+            Provider::Anthropic { retry, .. } => retry.as_ref(),
+        }
+    }
+
+    pub fn headers(&self) -> &HashMap<String, String> {
+        match self {
+            Provider::OpenAI { headers, .. } => headers,
+            Provider::Anthropic { headers, .. } => headers,
         }
     }
 
@@ -179,6 +245,8 @@ mod tests {
         let mut provider = Provider::OpenAI {
             url: Url::from_str("https://example.com/").unwrap(),
             key: None,
+            headers: HashMap::new(),
+            retry: None,
         };
 
         // Test URL without trailing slash
@@ -187,7 +255,9 @@ mod tests {
             provider,
             Provider::OpenAI {
                 url: Url::from_str("https://new-openai-url.com/").unwrap(),
-                key: None
+                key: None,
+                headers: HashMap::new(),
+                retry: None,
             }
         );
 
@@ -197,7 +267,9 @@ mod tests {
             provider,
             Provider::OpenAI {
                 url: Url::from_str("https://another-openai-url.com/").unwrap(),
-                key: None
+                key: None,
+                headers: HashMap::new(),
+                retry: None,
             }
         );
 
@@ -207,7 +279,9 @@ mod tests {
             provider,
             Provider::OpenAI {
                 url: Url::from_str("https://new-openai-url.com/v1/api/").unwrap(),
-                key: None
+                key: None,
+                headers: HashMap::new(),
+                retry: None,
             }
         );
 
@@ -217,7 +291,9 @@ mod tests {
             provider,
             Provider::OpenAI {
                 url: Url::from_str("https://another-openai-url.com/v2/api/").unwrap(),
-                key: None
+                key: None,
+                headers: HashMap::new(),
+                retry: None,
             }
         );
     }
@@ -227,6 +303,8 @@ mod tests {
         let mut provider = Provider::Anthropic {
             url: Url::from_str("https://example.com/").unwrap(),
             key: "key".to_string(),
+            headers: HashMap::new(),
+            retry: None,
         };
 
         // Test URL without trailing slash
@@ -235,7 +313,9 @@ mod tests {
             provider,
             Provider::Anthropic {
                 url: Url::from_str("https://new-anthropic-url.com/").unwrap(),
-                key: "key".to_string()
+                key: "key".to_string(),
+                headers: HashMap::new(),
+                retry: None,
             }
         );
 
@@ -245,7 +325,9 @@ mod tests {
             provider,
             Provider::Anthropic {
                 url: Url::from_str("https://another-anthropic-url.com/").unwrap(),
-                key: "key".to_string()
+                key: "key".to_string(),
+                headers: HashMap::new(),
+                retry: None,
             }
         );
 
@@ -255,7 +337,9 @@ mod tests {
             provider,
             Provider::Anthropic {
                 url: Url::from_str("https://new-anthropic-url.com/v1/complete/").unwrap(),
-                key: "key".to_string()
+                key: "key".to_string(),
+                headers: HashMap::new(),
+                retry: None,
             }
         );
 
@@ -265,7 +349,9 @@ mod tests {
             provider,
             Provider::Anthropic {
                 url: Url::from_str("https://another-anthropic-url.com/v2/complete/").unwrap(),
-                key: "key".to_string()
+                key: "key".to_string(),
+                headers: HashMap::new(),
+                retry: None,
             }
         );
     }
@@ -277,10 +363,19 @@ mod tests {
         let expected = Provider::OpenAI {
             url: Url::from_str("https://api.x.ai/v1/").unwrap(),
             key: Some(fixture.to_string()),
+            headers: HashMap::new(),
+            retry: None,
         };
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_with_headers() {
+        let fixture = HashMap::from([("X-Org-Id".to_string(), "org-1".to_string())]);
+        let actual = Provider::anthropic("key").with_headers(fixture.clone());
+        assert_eq!(actual.headers(), &fixture);
+    }
+
     #[test]
     fn test_is_xai() {
         let fixture_xai = Provider::xai("key");
@@ -0,0 +1,158 @@
+use serde::Deserialize;
+
+use crate::context::{ContextMessage, Role};
+use crate::{Context, Conversation, ModelId};
+
+/// A request sent to the model in the past, read back to send it again,
+/// possibly to another model, and compare the answers
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedRequest {
+    pub model: Option<ModelId>,
+    pub context: Context,
+    /// Content of the answer the model gave back then, if it was recorded
+    pub answer: Option<String>,
+}
+
+/// Line of a dump, as read back. Only the content of the responses is kept.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum DumpLine {
+    Request { model: ModelId, context: Context },
+    Response { message: DumpedMessage },
+    Error {},
+}
+
+#[derive(Deserialize)]
+struct DumpedMessage {
+    content: Option<DumpedContent>,
+}
+
+#[derive(Deserialize)]
+enum DumpedContent {
+    Part(String),
+    Full(String),
+}
+
+impl RecordedRequest {
+    /// Reads the request of a dump written by the request recorder, along with
+    /// the content of the responses streamed back for it
+    pub fn from_dump(dump: &str) -> anyhow::Result<Self> {
+        let mut lines = dump.lines().filter(|line| !line.trim().is_empty());
+        let first = lines
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("The dump is empty"))?;
+        let DumpLine::Request { model, context } = serde_json::from_str(first)? else {
+            anyhow::bail!("The dump doesn't start with a request");
+        };
+
+        let mut answer = String::new();
+        for line in lines {
+            if let Ok(DumpLine::Response { message }) = serde_json::from_str(line) {
+                match message.content {
+                    Some(DumpedContent::Part(part)) => answer.push_str(&part),
+                    Some(DumpedContent::Full(full)) => answer = full,
+                    None => {}
+                }
+            }
+        }
+
+        Ok(Self {
+            model: Some(model),
+            context,
+            answer: (!answer.is_empty()).then_some(answer),
+        })
+    }
+
+    /// Rebuilds the requests of the conversation from its context, each
+    /// answer of the model being the response to the messages preceding it
+    pub fn from_conversation(conversation: &Conversation) -> Vec<Self> {
+        let Some(context) = conversation.context.as_ref() else {
+            return Vec::new();
+        };
+        let main_model = conversation.main_model().ok();
+
+        context
+            .messages
+            .iter()
+            .enumerate()
+            .filter_map(|(index, message)| match message {
+                ContextMessage::Text(text) if text.role == Role::Assistant => Some(Self {
+                    model: text.model.clone().or_else(|| main_model.clone()),
+                    context: context.clone().messages(context.messages[..index].to_vec()),
+                    answer: Some(text.content.clone()),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{ChatCompletionMessage, Content, ConversationId, DumpRecord, Workflow};
+
+    #[test]
+    fn test_from_dump() {
+        let model = ModelId::new("gpt-4");
+        let context = Context::default().add_message(ContextMessage::user("Hi", None));
+        let fixture = [
+            DumpRecord::Request {
+                model: &model,
+                created_at: Default::default(),
+                context: &context,
+            }
+            .to_line()
+            .unwrap(),
+            DumpRecord::Response {
+                message: &ChatCompletionMessage::default().content(Content::part("Hel")),
+            }
+            .to_line()
+            .unwrap(),
+            DumpRecord::Response {
+                message: &ChatCompletionMessage::default().content(Content::part("lo")),
+            }
+            .to_line()
+            .unwrap(),
+        ]
+        .concat();
+
+        let actual = RecordedRequest::from_dump(&String::from_utf8(fixture).unwrap()).unwrap();
+
+        let expected = RecordedRequest {
+            model: Some(model),
+            context,
+            answer: Some("Hello".to_string()),
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_from_conversation() {
+        let context = Context::default()
+            .add_message(ContextMessage::system("You are Forge"))
+            .add_message(ContextMessage::user("Hi", None))
+            .add_message(ContextMessage::assistant("Hello", None, None))
+            .add_message(ContextMessage::user("Bye", None))
+            .add_message(ContextMessage::assistant("Goodbye", None, None));
+        let fixture = Conversation::new(
+            ConversationId::generate(),
+            Workflow::new(),
+            Default::default(),
+        )
+        .context(Some(context.clone()));
+
+        let actual = RecordedRequest::from_conversation(&fixture)
+            .into_iter()
+            .map(|request| (request.context.messages.len(), request.answer))
+            .collect::<Vec<_>>();
+
+        let expected = vec![
+            (2, Some("Hello".to_string())),
+            (4, Some("Goodbye".to_string())),
+        ];
+        assert_eq!(actual, expected);
+    }
+}
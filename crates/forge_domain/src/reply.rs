@@ -0,0 +1,63 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::oneshot;
+
+/// Channel through which the UI answers a request sent with a chat response.
+/// It can be answered only once; a request that is dropped or cancelled without
+/// an answer is treated as not answered by the one waiting for it.
+pub struct Reply<T>(Arc<Mutex<Option<oneshot::Sender<T>>>>);
+
+impl<T> Reply<T> {
+    /// Creates a reply channel along with the receiver of the answer
+    pub fn channel() -> (Self, oneshot::Receiver<T>) {
+        let (tx, rx) = oneshot::channel();
+        (Self(Arc::new(Mutex::new(Some(tx)))), rx)
+    }
+
+    /// Sends the answer, returning false if the request was already answered
+    /// or is no longer waiting for one
+    pub fn send(&self, answer: T) -> bool {
+        self.take()
+            .is_some_and(|sender| sender.send(answer).is_ok())
+    }
+
+    /// Gives up on answering the request, even if copies of the reply are
+    /// still kept around
+    pub fn cancel(&self) {
+        self.take();
+    }
+
+    fn take(&self) -> Option<oneshot::Sender<T>> {
+        self.0.lock().ok().and_then(|mut sender| sender.take())
+    }
+}
+
+impl<T> Clone for Reply<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> std::fmt::Debug for Reply<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Reply").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cancelled_reply_is_not_received() {
+        let (fixture, rx) = Reply::<String>::channel();
+
+        fixture.clone().cancel();
+        let sent = fixture.send("answer".to_string());
+        let actual = rx.await.ok();
+
+        assert_eq!((sent, actual), (false, None));
+    }
+}
@@ -0,0 +1,166 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::SystemTime;
+
+use derive_setters::Setters;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Files shown in the repo map by default
+const MAX_FILES: usize = 50;
+
+/// Definitions shown for each file of the repo map by default
+const MAX_SYMBOLS: usize = 10;
+
+/// Settings of the repo map, a compact outline of the most important files of
+/// the workspace and of their definitions that the agents are given at the
+/// start of a conversation
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, Setters, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct RepoMapConfig {
+    /// Maximum number of files in the map. Defaults to 50.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_files: Option<usize>,
+
+    /// Maximum number of definitions shown for each file. Defaults to 10.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_symbols: Option<usize>,
+}
+
+/// Source file of the workspace, as outlined for the repo map
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepoMapFile {
+    /// Path relative to the workspace
+    pub path: String,
+    /// Signatures of the definitions, in the order they appear and indented
+    /// under the ones enclosing them
+    pub symbols: Vec<String>,
+    /// Number of other files that mention one of its top level definitions
+    pub references: usize,
+    pub modified: Option<SystemTime>,
+}
+
+impl RepoMapConfig {
+    /// Renders the map of the files most referenced by the others and most
+    /// recently modified, grouped by directory. Returns `None` when there's
+    /// no file to map.
+    pub fn render(&self, files: Vec<RepoMapFile>) -> Option<String> {
+        let max_files = self.max_files.unwrap_or(MAX_FILES);
+        let max_symbols = self.max_symbols.unwrap_or(MAX_SYMBOLS);
+
+        // Each file is ranked by its references and by its recency, the files
+        // ranking best on both coming first
+        let mut by_references: Vec<_> = files.iter().collect();
+        by_references.sort_by_key(|file| Reverse(file.references));
+        let mut by_recency: Vec<_> = files.iter().collect();
+        by_recency.sort_by_key(|file| Reverse(file.modified));
+        let mut ranks: BTreeMap<&str, usize> = BTreeMap::new();
+        for (rank, file) in by_references.iter().enumerate() {
+            *ranks.entry(&file.path).or_default() += rank;
+        }
+        for (rank, file) in by_recency.iter().enumerate() {
+            *ranks.entry(&file.path).or_default() += rank;
+        }
+
+        let mut ranked: Vec<_> = files.iter().collect();
+        ranked.sort_by(|a, b| {
+            ranks[a.path.as_str()]
+                .cmp(&ranks[b.path.as_str()])
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        ranked.truncate(max_files);
+        if ranked.is_empty() {
+            return None;
+        }
+
+        let mut directories: BTreeMap<String, Vec<&RepoMapFile>> = BTreeMap::new();
+        for file in ranked {
+            let directory = Path::new(&file.path)
+                .parent()
+                .map(|parent| parent.to_string_lossy().to_string())
+                .filter(|parent| !parent.is_empty())
+                .unwrap_or_else(|| ".".to_string());
+            directories.entry(directory).or_default().push(file);
+        }
+
+        let mut lines = Vec::new();
+        for (directory, mut files) in directories {
+            files.sort_by(|a, b| a.path.cmp(&b.path));
+            lines.push(format!("{directory}/"));
+            for file in files {
+                let name = Path::new(&file.path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| file.path.clone());
+                lines.push(format!("  {name}"));
+                for symbol in file.symbols.iter().take(max_symbols) {
+                    lines.push(format!("    {symbol}"));
+                }
+                if file.symbols.len() > max_symbols {
+                    lines.push(format!("    ... {} more", file.symbols.len() - max_symbols));
+                }
+            }
+        }
+        Some(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn file(path: &str, symbols: &[&str], references: usize, age: u64) -> RepoMapFile {
+        RepoMapFile {
+            path: path.to_string(),
+            symbols: symbols.iter().map(|symbol| symbol.to_string()).collect(),
+            references,
+            modified: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1000 - age)),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let fixture = vec![
+            file("README.md", &[], 0, 900),
+            file(
+                "src/parser.rs",
+                &["pub struct Parser", "impl Parser", "  pub fn parse(&self)"],
+                4,
+                10,
+            ),
+            file("src/main.rs", &["fn main()"], 0, 0),
+            file("src/lexer.rs", &["pub struct Lexer"], 2, 500),
+            file("build.rs", &["fn main()"], 0, 800),
+        ];
+
+        let actual = RepoMapConfig::default()
+            .max_files(3usize)
+            .max_symbols(2usize)
+            .render(fixture);
+
+        let expected = [
+            "src/",
+            "  lexer.rs",
+            "    pub struct Lexer",
+            "  main.rs",
+            "    fn main()",
+            "  parser.rs",
+            "    pub struct Parser",
+            "    impl Parser",
+            "    ... 1 more",
+        ]
+        .join("\n");
+        assert_eq!(actual, Some(expected));
+    }
+
+    #[test]
+    fn test_render_empty() {
+        let actual = RepoMapConfig::default().render(vec![]);
+        assert_eq!(actual, None);
+    }
+}
@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use derive_setters::Setters;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{ChatCompletionMessage, Context, ModelId};
+
+/// Dumps kept when no limit is configured
+const DEFAULT_KEEP: usize = 20;
+
+/// Settings of the dumps of the requests sent to the provider, along with the
+/// responses streamed back, to debug what the model was given
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, Setters, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct RequestDump {
+    /// Whether requests are dumped. Defaults to false.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+
+    /// Number of dumps kept, the oldest ones are removed. Defaults to 20.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep: Option<usize>,
+}
+
+impl RequestDump {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or_default()
+    }
+
+    pub fn keep(&self) -> usize {
+        self.keep.unwrap_or(DEFAULT_KEEP)
+    }
+}
+
+/// A line of a dump. The request comes first, followed by the responses in
+/// the order they were streamed.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DumpRecord<'a> {
+    Request {
+        model: &'a ModelId,
+        created_at: DateTime<Utc>,
+        context: &'a Context,
+    },
+    Response {
+        message: &'a ChatCompletionMessage,
+    },
+    Error {
+        error: String,
+    },
+}
+
+impl DumpRecord<'_> {
+    /// The record as a line of JSON, ending with a newline
+    pub fn to_line(&self) -> serde_json::Result<Vec<u8>> {
+        let mut line = serde_json::to_vec(self)?;
+        line.push(b'\n');
+        Ok(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::Content;
+
+    #[test]
+    fn test_response_record() {
+        let message = ChatCompletionMessage::default().content(Content::part("Hello"));
+        let fixture = DumpRecord::Response { message: &message };
+
+        let actual = String::from_utf8(fixture.to_line().unwrap()).unwrap();
+
+        let expected = r#"{"type":"response","message":{"content":{"Part":"Hello"},"reasoning":null,"reasoning_details":null,"tool_calls":[],"finish_reason":null,"usage":null}}
+"#;
+        assert_eq!(actual, expected);
+    }
+}
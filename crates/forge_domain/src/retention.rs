@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use derive_more::Display;
+use derive_setters::Setters;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Limits on the sessions, request dumps and snapshots stored under the base
+/// path. Data beyond them is pruned when forge starts and by `forge gc`.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, Setters, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct Retention {
+    /// Days after which stored data is removed. Kept forever when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u64>,
+
+    /// Megabytes the stored data may take together, the oldest data being
+    /// removed first. Unlimited when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_disk_usage_mb: Option<u64>,
+}
+
+impl Retention {
+    /// Whether any limit is set
+    pub fn is_set(&self) -> bool {
+        self.max_age_days.is_some() || self.max_disk_usage_mb.is_some()
+    }
+
+    /// Items beyond the limits: those older than the maximum age, then the
+    /// oldest of the others until the rest fits in the disk usage
+    pub fn select(&self, mut items: Vec<StoredItem>, now: DateTime<Utc>) -> Vec<StoredItem> {
+        items.sort_by_key(|item| item.modified_at);
+
+        let mut selected = Vec::new();
+        if let Some(days) = self.max_age_days {
+            let cutoff = now - chrono::Duration::days(days as i64);
+            let expired = items.partition_point(|item| item.modified_at < cutoff);
+            selected.extend(items.drain(..expired));
+        }
+
+        if let Some(megabytes) = self.max_disk_usage_mb {
+            let limit = megabytes * 1024 * 1024;
+            let mut usage: u64 = items.iter().map(|item| item.size).sum();
+            let mut items = items.into_iter();
+            while usage > limit {
+                let Some(item) = items.next() else {
+                    break;
+                };
+                usage -= item.size;
+                selected.push(item);
+            }
+        }
+
+        selected
+    }
+}
+
+/// Kinds of data stored under the base path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display)]
+pub enum StoredKind {
+    #[display("Sessions")]
+    Session,
+    #[display("Request Dumps")]
+    RequestDump,
+    #[display("Snapshots")]
+    Snapshot,
+}
+
+/// Piece of stored data, removed as a whole
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredItem {
+    pub kind: StoredKind,
+    /// Id of the session or the snapshot, or path of the dump
+    pub id: String,
+    /// Bytes freed by removing it
+    pub size: u64,
+    pub modified_at: DateTime<Utc>,
+}
+
+/// Data removed, or that would be removed on a dry run, by kind
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PruneReport {
+    pub dry_run: bool,
+    pub reclaimed: BTreeMap<StoredKind, Reclaimed>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Reclaimed {
+    pub count: usize,
+    pub bytes: u64,
+}
+
+impl PruneReport {
+    pub fn new<'a>(items: impl IntoIterator<Item = &'a StoredItem>, dry_run: bool) -> Self {
+        let mut reclaimed = BTreeMap::<StoredKind, Reclaimed>::new();
+        for item in items {
+            let entry = reclaimed.entry(item.kind).or_default();
+            entry.count += 1;
+            entry.bytes += item.size;
+        }
+        Self { dry_run, reclaimed }
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.reclaimed
+            .values()
+            .map(|reclaimed| reclaimed.bytes)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reclaimed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn item(kind: StoredKind, id: &str, size: u64, days_ago: i64) -> StoredItem {
+        StoredItem {
+            kind,
+            id: id.to_string(),
+            size,
+            modified_at: DateTime::<Utc>::from_timestamp(0, 0).unwrap()
+                + chrono::Duration::days(100 - days_ago),
+        }
+    }
+
+    #[test]
+    fn test_select() {
+        let now = DateTime::<Utc>::from_timestamp(0, 0).unwrap() + chrono::Duration::days(100);
+        let fixture = Retention::default()
+            .max_age_days(30u64)
+            .max_disk_usage_mb(2u64);
+        let mb = 1024 * 1024;
+        let items = vec![
+            item(StoredKind::Snapshot, "recent", mb, 1),
+            item(StoredKind::Session, "expired", 10, 40),
+            item(StoredKind::RequestDump, "oldest", mb, 20),
+            item(StoredKind::Session, "older", mb, 10),
+        ];
+
+        let actual = fixture
+            .select(items, now)
+            .into_iter()
+            .map(|item| item.id)
+            .collect::<Vec<_>>();
+
+        let expected = vec!["expired".to_string(), "oldest".to_string()];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_prune_report() {
+        let items = [
+            item(StoredKind::Session, "a", 10, 1),
+            item(StoredKind::Session, "b", 20, 1),
+            item(StoredKind::Snapshot, "c", 5, 1),
+        ];
+
+        let actual = PruneReport::new(&items, true);
+
+        let expected = PruneReport {
+            dry_run: true,
+            reclaimed: BTreeMap::from([
+                (StoredKind::Session, Reclaimed { count: 2, bytes: 30 }),
+                (StoredKind::Snapshot, Reclaimed { count: 1, bytes: 5 }),
+            ]),
+        };
+        assert_eq!(actual, expected);
+        assert_eq!(actual.total_bytes(), 35);
+    }
+}
@@ -0,0 +1,165 @@
+use std::path::Path;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{Attachment, AttachmentContent, ModelId};
+
+/// Chunks of the workspace added to each message of the user by default
+const DEFAULT_CONTEXT_CHUNKS: usize = 3;
+
+/// Lines of a file in a chunk
+const CHUNK_LINES: usize = 40;
+
+/// Longest chunk, in bytes, so that files with very long lines, such as
+/// minified code, stay within the input limit of the embedding model
+const MAX_CHUNK_BYTES: usize = 4000;
+
+/// Settings of the retrieval of the parts of the workspace that are relevant
+/// to a message, which are found with the embeddings of the files
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Retrieval {
+    /// Embedding model of the provider used to index the workspace, eg:
+    /// text-embedding-3-small
+    pub model: ModelId,
+
+    /// Number of the most relevant chunks of the workspace that are attached
+    /// to each message of the user. Set it to 0 to only retrieve them with the
+    /// semantic search tool. Defaults to 3.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_chunks: Option<usize>,
+}
+
+impl Retrieval {
+    pub fn context_chunks(&self) -> usize {
+        self.context_chunks.unwrap_or(DEFAULT_CONTEXT_CHUNKS)
+    }
+}
+
+/// A range of lines of a file of the workspace, the unit in which the
+/// workspace is indexed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceChunk {
+    /// Path of the file relative to the workspace
+    pub path: String,
+    // 1-based and inclusive
+    pub start_line: u64,
+    pub end_line: u64,
+    pub total_lines: u64,
+    pub content: String,
+}
+
+impl WorkspaceChunk {
+    /// Splits the content of the file into chunks of consecutive lines. Blank
+    /// chunks are left out as there's nothing in them to retrieve.
+    pub fn split(path: &str, content: &str) -> Vec<WorkspaceChunk> {
+        let lines = content.lines().collect::<Vec<_>>();
+        let total_lines = lines.len() as u64;
+
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        while start < lines.len() {
+            let mut end = start;
+            let mut bytes = 0;
+            while end < lines.len() && end - start < CHUNK_LINES {
+                bytes += lines[end].len() + 1;
+                end += 1;
+                if bytes >= MAX_CHUNK_BYTES {
+                    break;
+                }
+            }
+
+            let mut text = lines[start..end].join("\n");
+            if text.len() > MAX_CHUNK_BYTES {
+                let mut boundary = MAX_CHUNK_BYTES;
+                while !text.is_char_boundary(boundary) {
+                    boundary -= 1;
+                }
+                text.truncate(boundary);
+            }
+            if !text.trim().is_empty() {
+                chunks.push(WorkspaceChunk {
+                    path: path.to_string(),
+                    start_line: start as u64 + 1,
+                    end_line: end as u64,
+                    total_lines,
+                    content: text,
+                });
+            }
+            start = end;
+        }
+        chunks
+    }
+
+    /// Text from which the embedding of the chunk is computed. The path is
+    /// included, as queries often name the module they're about.
+    pub fn embedding_input(&self) -> String {
+        format!("{}\n{}", self.path, self.content)
+    }
+
+    /// Attaches the chunk to a message, with the path of the file in the
+    /// workspace at cwd
+    pub fn into_attachment(self, cwd: &Path) -> Attachment {
+        Attachment {
+            path: cwd.join(&self.path).display().to_string(),
+            content: AttachmentContent::FileContent {
+                content: self.content,
+                start_line: self.start_line,
+                end_line: self.end_line,
+                total_lines: self.total_lines,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_split_into_chunks() {
+        let fixture = (1..=50)
+            .map(|line| {
+                if line <= 40 {
+                    format!("line {line}")
+                } else {
+                    String::new()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+            + "\nfn main() {}";
+
+        let actual = WorkspaceChunk::split("src/main.rs", &fixture)
+            .into_iter()
+            .map(|chunk| (chunk.start_line, chunk.end_line, chunk.total_lines))
+            .collect::<Vec<_>>();
+
+        let expected = vec![(1, 40, 51), (41, 51, 51)];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_split_skips_blank_chunks() {
+        let fixture = "\n".repeat(45);
+
+        let actual = WorkspaceChunk::split("notes.txt", &fixture);
+
+        let expected: Vec<WorkspaceChunk> = vec![];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_split_limits_chunk_size() {
+        let fixture = "é".repeat(MAX_CHUNK_BYTES);
+
+        let actual = WorkspaceChunk::split("bundle.min.js", &fixture)[0]
+            .content
+            .len();
+
+        let expected = MAX_CHUNK_BYTES;
+        assert_eq!(actual, expected);
+    }
+}
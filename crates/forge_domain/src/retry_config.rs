@@ -1,5 +1,6 @@
 use derive_setters::Setters;
 use merge::Merge;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Merge, Setters, PartialEq)]
@@ -53,6 +54,47 @@ impl RetryConfig {
     // Implementation moved to forge_app::retry module to avoid backon dependency
 }
 
+/// Overrides of the global retry configuration for a single provider
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, Setters, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct RetryOverrides {
+    /// Maximum number of retry attempts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retry_attempts: Option<usize>,
+
+    /// Minimum delay in milliseconds between retry attempts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_delay_ms: Option<u64>,
+
+    /// Backoff multiplication factor for each retry attempt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backoff_factor: Option<u64>,
+
+    /// HTTP status codes that should trigger retries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_status_codes: Option<Vec<u16>>,
+}
+
+impl RetryOverrides {
+    /// Applies the overrides on top of the given retry configuration
+    pub fn apply(&self, config: &RetryConfig) -> RetryConfig {
+        let mut config = config.clone();
+        if let Some(max_retry_attempts) = self.max_retry_attempts {
+            config.max_retry_attempts = max_retry_attempts;
+        }
+        if let Some(min_delay_ms) = self.min_delay_ms {
+            config.min_delay_ms = min_delay_ms;
+        }
+        if let Some(backoff_factor) = self.backoff_factor {
+            config.backoff_factor = backoff_factor;
+        }
+        if let Some(retry_status_codes) = &self.retry_status_codes {
+            config.retry_status_codes = retry_status_codes.clone();
+        }
+        config
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use pretty_assertions::assert_eq;
@@ -92,4 +134,27 @@ mod tests {
         assert_eq!(config.retry_status_codes, vec![429, 503]);
         assert_eq!(config.suppress_retry_errors, true);
     }
+
+    #[test]
+    fn test_retry_overrides_apply() {
+        let fixture = RetryOverrides::default()
+            .max_retry_attempts(1usize)
+            .retry_status_codes(vec![503]);
+
+        let actual = fixture.apply(&RetryConfig::default());
+
+        let expected = RetryConfig::default()
+            .max_retry_attempts(1usize)
+            .retry_status_codes(vec![503]);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_retry_overrides_empty_keeps_config() {
+        let fixture = RetryConfig::default().backoff_factor(3u64);
+
+        let actual = RetryOverrides::default().apply(&fixture);
+
+        assert_eq!(actual, fixture);
+    }
 }
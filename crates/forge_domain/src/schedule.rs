@@ -0,0 +1,224 @@
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::Context as _;
+use chrono::{DateTime, TimeZone};
+use derive_setters::Setters;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{AgentId, ConversationId};
+
+/// A prompt run by `forge schedule` without the user, on a cron expression or
+/// when files of the workspace change. Each run is saved as a session tagged
+/// `schedule` and with the name of the schedule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Setters, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct Schedule {
+    /// Name of the schedule, used to tag the sessions of its runs
+    pub name: String,
+
+    /// Prompt sent to the agent
+    pub prompt: String,
+
+    /// Cron expression the schedule runs on, in local time, e.g. `0 2 * * *`
+    /// for every night at 2am. A leading field for the seconds is optional.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cron: Option<String>,
+
+    /// Files of the workspace, as globs like `src/**.rs`, whose changes trigger
+    /// a run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub watch: Vec<String>,
+
+    /// Agent the prompt is sent to. Defaults to the main agent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<AgentId>,
+
+    /// Path to the workflow the runs use, instead of the one of the workspace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workflow: Option<PathBuf>,
+}
+
+impl Schedule {
+    pub fn new(name: impl Into<String>, prompt: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            prompt: prompt.into(),
+            cron: None,
+            watch: Vec::new(),
+            agent: None,
+            workflow: None,
+        }
+    }
+
+    /// Time of the first run after the given one, `None` when the schedule
+    /// doesn't run on a cron expression or the expression never fires again
+    pub fn next_run<Tz: TimeZone>(
+        &self,
+        after: &DateTime<Tz>,
+    ) -> anyhow::Result<Option<DateTime<Tz>>> {
+        let Some(expression) = self.cron.as_deref() else {
+            return Ok(None);
+        };
+
+        // The usual five fields of crontab run on the minute
+        let expression = match expression.split_whitespace().count() {
+            5 => format!("0 {expression}"),
+            _ => expression.to_string(),
+        };
+        let schedule = cron::Schedule::from_str(&expression).with_context(|| {
+            format!(
+                "Invalid cron expression '{expression}' for schedule {}",
+                self.name
+            )
+        })?;
+        Ok(schedule.after(after).next())
+    }
+
+    /// Matcher of the files, relative to the workspace, whose changes trigger
+    /// a run, `None` when the schedule doesn't watch any file
+    pub fn watcher(&self) -> anyhow::Result<Option<GlobSet>> {
+        if self.watch.is_empty() {
+            return Ok(None);
+        }
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &self.watch {
+            builder.add(
+                Glob::new(pattern).with_context(|| {
+                    format!("Invalid glob '{pattern}' for schedule {}", self.name)
+                })?,
+            );
+        }
+        Ok(Some(builder.build()?))
+    }
+}
+
+/// What started a run of a schedule
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScheduleTrigger {
+    /// The run was asked for, e.g. with `forge schedule --run`
+    Manual,
+    /// The cron expression of the schedule fired
+    Cron,
+    /// Watched files were added, changed or removed
+    Changes(Vec<String>),
+}
+
+impl Display for ScheduleTrigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduleTrigger::Manual => write!(f, "manual"),
+            ScheduleTrigger::Cron => write!(f, "cron"),
+            ScheduleTrigger::Changes(paths) => write!(f, "changes to {}", paths.join(", ")),
+        }
+    }
+}
+
+/// A run of a schedule, once it's done
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledRun {
+    pub schedule: String,
+    pub trigger: ScheduleTrigger,
+    /// Session the run is saved as
+    pub conversation_id: ConversationId,
+    /// Last answer of the agent, if it gave one
+    pub summary: Option<String>,
+    /// Why the run failed, if it did
+    pub error: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_next_run() {
+        let after = Utc.with_ymd_and_hms(2025, 1, 1, 10, 30, 0).unwrap();
+
+        let actual = [
+            Schedule::new("nightly", "Audit the dependencies").cron("0 2 * * *"),
+            Schedule::new("hourly", "Triage the issues").cron("0 0 * * * *"),
+            Schedule::new("docs", "Regenerate the docs"),
+        ]
+        .iter()
+        .map(|schedule| schedule.next_run(&after).unwrap())
+        .collect::<Vec<_>>();
+
+        let expected = vec![
+            Some(Utc.with_ymd_and_hms(2025, 1, 2, 2, 0, 0).unwrap()),
+            Some(Utc.with_ymd_and_hms(2025, 1, 1, 11, 0, 0).unwrap()),
+            None,
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_next_run_invalid_cron() {
+        let fixture = Schedule::new("nightly", "Audit the dependencies").cron("at night");
+
+        let actual = fixture.next_run(&Utc::now());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn test_trigger_display() {
+        let actual = [
+            ScheduleTrigger::Manual.to_string(),
+            ScheduleTrigger::Cron.to_string(),
+            ScheduleTrigger::Changes(vec!["src/main.rs".to_string(), "src/lib.rs".to_string()])
+                .to_string(),
+        ];
+
+        let expected = ["manual", "cron", "changes to src/main.rs, src/lib.rs"];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_watcher() {
+        let fixture = Schedule::new("docs", "Regenerate the docs")
+            .watch(vec!["src/**.rs".to_string()])
+            .watcher()
+            .unwrap()
+            .unwrap();
+
+        let actual = [
+            "src/main.rs",
+            "src/parser/lexer.rs",
+            "README.md",
+            "tests/cli.rs",
+        ]
+        .iter()
+        .map(|path| fixture.is_match(path))
+        .collect::<Vec<_>>();
+
+        let expected = vec![true, true, false, false];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_watcher_without_watch() {
+        let fixture = Schedule::new("nightly", "Audit the dependencies");
+
+        let actual = fixture.watcher().unwrap();
+
+        assert!(actual.is_none());
+    }
+
+    #[test]
+    fn test_watcher_invalid_glob() {
+        let fixture =
+            Schedule::new("docs", "Regenerate the docs").watch(vec!["src/[.rs".to_string()]);
+
+        let actual = fixture.watcher().unwrap_err().to_string();
+
+        let expected = "Invalid glob 'src/[.rs' for schedule docs";
+        assert_eq!(actual, expected);
+    }
+}
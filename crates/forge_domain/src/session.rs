@@ -0,0 +1,422 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{Conversation, ConversationId, ModelId, TaskList, ToolCallFull, ToolResult};
+
+/// A conversation saved under the base path, so that it can be found again
+/// after forge exits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub id: ConversationId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Directory forge was started in
+    pub workspace: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<ModelId>,
+    /// Tags of the session, without their leading `#`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl Session {
+    /// Adds the tags the session doesn't have yet, ignoring the empty ones
+    fn add_tags<'a>(&mut self, tags: impl IntoIterator<Item = &'a String>) {
+        for tag in tags.into_iter().filter_map(|tag| normalize_tag(tag)) {
+            if !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
+        }
+    }
+}
+
+/// The tag without its leading `#` and in lowercase, `None` when nothing is
+/// left of it
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().trim_start_matches('#').trim().to_lowercase();
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// Criteria the listed sessions must all meet
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionFilter {
+    /// Tags the session must all have
+    pub tags: Vec<String>,
+    pub workspace: Option<PathBuf>,
+    pub model: Option<ModelId>,
+    /// Sessions last updated before this time are left out
+    pub since: Option<DateTime<Utc>>,
+    /// Sessions created after this time are left out
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl SessionFilter {
+    pub fn matches(&self, session: &Session) -> bool {
+        self.tags
+            .iter()
+            .filter_map(|tag| normalize_tag(tag))
+            .all(|tag| session.tags.contains(&tag))
+            && self
+                .workspace
+                .as_ref()
+                .is_none_or(|workspace| &session.workspace == workspace)
+            && self
+                .model
+                .as_ref()
+                .is_none_or(|model| session.model.as_ref() == Some(model))
+            && self.since.is_none_or(|since| session.updated_at >= since)
+            && self.until.is_none_or(|until| session.created_at <= until)
+    }
+}
+
+/// Metadata of the saved sessions, the most recently updated first
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionIndex {
+    sessions: Vec<Session>,
+}
+
+impl SessionIndex {
+    pub fn sessions(&self) -> &[Session] {
+        &self.sessions
+    }
+
+    /// Sessions meeting the criteria of the filter, the most recent first
+    pub fn filter(&self, filter: &SessionFilter) -> Vec<Session> {
+        self.sessions
+            .iter()
+            .filter(|session| filter.matches(session))
+            .cloned()
+            .collect()
+    }
+
+    /// Records that the conversation was saved, adding it to the index the
+    /// first time with the tags it was created with, and moving it to the
+    /// front afterwards
+    pub fn touch(
+        &mut self,
+        id: ConversationId,
+        workspace: &Path,
+        model: Option<ModelId>,
+        tags: &[String],
+        now: DateTime<Utc>,
+    ) {
+        let mut session = match self.sessions.iter().position(|session| session.id == id) {
+            Some(index) => self.sessions.remove(index),
+            None => {
+                let mut session = Session {
+                    id,
+                    name: None,
+                    created_at: now,
+                    updated_at: now,
+                    workspace: workspace.to_path_buf(),
+                    model: None,
+                    tags: Vec::new(),
+                };
+                session.add_tags(tags);
+                session
+            }
+        };
+        session.updated_at = now;
+        session.model = model.or(session.model);
+        self.sessions.insert(0, session);
+    }
+
+    /// Most recently updated session of the workspace
+    pub fn latest(&self, workspace: &Path) -> Option<&Session> {
+        self.sessions
+            .iter()
+            .find(|session| session.workspace == workspace)
+    }
+
+    /// Finds the session by its id or, failing that, by its name
+    pub fn find(&self, key: &str) -> Option<&Session> {
+        self.sessions
+            .iter()
+            .find(|session| session.id.into_string() == key)
+            .or_else(|| {
+                self.sessions
+                    .iter()
+                    .find(|session| session.name.as_deref() == Some(key))
+            })
+    }
+
+    /// Names the session found by the key, failing if the name is already
+    /// taken by another session
+    pub fn rename(&mut self, key: &str, name: impl Into<String>) -> anyhow::Result<Session> {
+        let name = name.into().trim().to_string();
+        anyhow::ensure!(!name.is_empty(), "Session name can't be empty");
+        let id = self.lookup(key)?.id;
+        if let Some(other) = self.find(&name).filter(|other| other.id != id) {
+            anyhow::bail!("Session {} is already named {name}", other.id);
+        }
+
+        let session = self.lookup_mut(key)?;
+        session.name = Some(name);
+        Ok(session.clone())
+    }
+
+    /// Adds the tags to the session found by the key
+    pub fn tag(&mut self, key: &str, tags: &[String]) -> anyhow::Result<Session> {
+        let session = self.lookup_mut(key)?;
+        session.add_tags(tags);
+        Ok(session.clone())
+    }
+
+    /// Removes the tags from the session found by the key
+    pub fn untag(&mut self, key: &str, tags: &[String]) -> anyhow::Result<Session> {
+        let tags = tags
+            .iter()
+            .filter_map(|tag| normalize_tag(tag))
+            .collect::<Vec<_>>();
+        let session = self.lookup_mut(key)?;
+        session.tags.retain(|tag| !tags.contains(tag));
+        Ok(session.clone())
+    }
+
+    /// Removes the session found by the key from the index
+    pub fn remove(&mut self, key: &str) -> anyhow::Result<Session> {
+        let id = self.lookup(key)?.id;
+        let index = self
+            .sessions
+            .iter()
+            .position(|session| session.id == id)
+            .expect("session was just found");
+        Ok(self.sessions.remove(index))
+    }
+
+    fn lookup_mut(&mut self, key: &str) -> anyhow::Result<&mut Session> {
+        let id = self.lookup(key)?.id;
+        Ok(self
+            .sessions
+            .iter_mut()
+            .find(|session| session.id == id)
+            .expect("session was just found"))
+    }
+
+    fn lookup(&self, key: &str) -> anyhow::Result<&Session> {
+        self.find(key)
+            .ok_or_else(|| anyhow::anyhow!("Session not found: {key}"))
+    }
+}
+
+/// A step of a turn, appended to the journal of the session as soon as it
+/// happens. The journal is restarted whenever the session is saved, so it only
+/// holds the steps a crash would otherwise lose.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SessionEvent {
+    /// The model answered the saved context, made of its first `messages`
+    /// messages
+    Assistant {
+        messages: usize,
+        content: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tool_calls: Vec<ToolCallFull>,
+    },
+    /// A tool call of the answer completed, leaving the tasks as they are
+    ToolResult {
+        call: ToolCallFull,
+        result: ToolResult,
+        tasks: TaskList,
+    },
+}
+
+impl Conversation {
+    /// Applies the steps journaled after the conversation was saved: the
+    /// answer of the model along with the tool calls that completed. Tool
+    /// calls that never completed are dropped from the answer, as if the model
+    /// hadn't made them. Steps following another context than the saved one
+    /// are ignored.
+    pub fn recover(&mut self, events: impl IntoIterator<Item = SessionEvent>) {
+        let saved = self
+            .context
+            .as_ref()
+            .map_or(0, |context| context.messages.len());
+        let mut turn = None;
+        for event in events {
+            match event {
+                SessionEvent::Assistant { messages, content, .. } if messages == saved => {
+                    turn = Some((content, Vec::new()));
+                }
+                SessionEvent::Assistant { .. } => break,
+                SessionEvent::ToolResult { call, result, tasks } => {
+                    if let Some((_, records)) = turn.as_mut() {
+                        records.push((call, result));
+                        self.tasks = tasks;
+                    }
+                }
+            }
+        }
+
+        if let Some((content, records)) = turn {
+            let context = self.context.take().unwrap_or_default();
+            self.context = Some(context.append_message(content, None, records));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{Context, ContextMessage, ToolCallId, ToolOutput, Workflow};
+
+    const FIRST: &str = "5d3a8f1e-2b9c-4e7a-9f61-0c8d2e4b7a13";
+    const SECOND: &str = "9b2e7c4d-1a3f-4d8e-b6c5-7e0f1a2b3c4d";
+
+    fn fixture() -> SessionIndex {
+        let mut index = SessionIndex::default();
+        index.touch(
+            ConversationId::parse(FIRST).unwrap(),
+            Path::new("/project"),
+            Some(ModelId::new("gpt-4")),
+            &["#Bugfix".to_string()],
+            DateTime::<Utc>::from_timestamp(1, 0).unwrap(),
+        );
+        index.touch(
+            ConversationId::parse(SECOND).unwrap(),
+            Path::new("/other"),
+            None,
+            &[],
+            DateTime::<Utc>::from_timestamp(2, 0).unwrap(),
+        );
+        index
+    }
+
+    #[test]
+    fn test_touch_moves_the_session_to_the_front() {
+        let mut fixture = fixture();
+
+        fixture.touch(
+            ConversationId::parse(FIRST).unwrap(),
+            Path::new("/elsewhere"),
+            None,
+            &["client-x".to_string()],
+            DateTime::<Utc>::from_timestamp(3, 0).unwrap(),
+        );
+        let actual = fixture.sessions()[0].clone();
+
+        let expected = Session {
+            id: ConversationId::parse(FIRST).unwrap(),
+            name: None,
+            created_at: DateTime::<Utc>::from_timestamp(1, 0).unwrap(),
+            updated_at: DateTime::<Utc>::from_timestamp(3, 0).unwrap(),
+            workspace: PathBuf::from("/project"),
+            model: Some(ModelId::new("gpt-4")),
+            tags: vec!["bugfix".to_string()],
+        };
+        assert_eq!(actual, expected);
+        assert_eq!(fixture.sessions().len(), 2);
+    }
+
+    #[test]
+    fn test_rename_and_find_by_name() {
+        let mut fixture = fixture();
+
+        fixture.rename(FIRST, " bugfix ").unwrap();
+        let actual = fixture
+            .find("bugfix")
+            .map(|session| session.id.into_string());
+
+        let expected = Some(FIRST.to_string());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_rename_to_a_taken_name() {
+        let mut fixture = fixture();
+        fixture.rename(FIRST, "bugfix").unwrap();
+
+        let actual = fixture.rename(SECOND, "bugfix").is_err();
+
+        assert!(actual);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut fixture = fixture();
+
+        let actual = fixture.remove(SECOND).unwrap().id.into_string();
+
+        let expected = SECOND.to_string();
+        assert_eq!(actual, expected);
+        assert_eq!(fixture.sessions().len(), 1);
+        assert!(fixture.remove(SECOND).is_err());
+    }
+
+    #[test]
+    fn test_tag_and_untag() {
+        let mut fixture = fixture();
+
+        fixture
+            .tag(SECOND, &["#client-x".to_string(), "Bugfix".to_string()])
+            .unwrap();
+        let actual = fixture
+            .untag(SECOND, &["#bugfix".to_string()])
+            .unwrap()
+            .tags;
+
+        let expected = vec!["client-x".to_string()];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_filter() {
+        let fixture = fixture();
+        let filter = SessionFilter {
+            tags: vec!["#bugfix".to_string()],
+            model: Some(ModelId::new("gpt-4")),
+            until: DateTime::<Utc>::from_timestamp(1, 0),
+            ..Default::default()
+        };
+
+        let actual = fixture
+            .filter(&filter)
+            .into_iter()
+            .map(|session| session.id.into_string())
+            .collect::<Vec<_>>();
+
+        let expected = vec![FIRST.to_string()];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_recover() {
+        let call = ToolCallFull::new("forge_tool_fs_read").call_id(ToolCallId::new("call_1"));
+        let pending = ToolCallFull::new("forge_tool_fs_read").call_id(ToolCallId::new("call_2"));
+        let result = ToolResult::new("forge_tool_fs_read")
+            .call_id(ToolCallId::new("call_1"))
+            .output(Ok(ToolOutput::text("[package]")));
+        let mut tasks = TaskList::new();
+        tasks.append("Read the manifest");
+        let context = Context::default().add_message(ContextMessage::user("Fix the build", None));
+        let mut fixture = Conversation::new(
+            ConversationId::parse(FIRST).unwrap(),
+            Workflow::new(),
+            Default::default(),
+        )
+        .context(Some(context.clone()));
+
+        fixture.recover(vec![
+            SessionEvent::Assistant {
+                messages: 1,
+                content: "Reading".to_string(),
+                tool_calls: vec![call.clone(), pending],
+            },
+            SessionEvent::ToolResult {
+                call: call.clone(),
+                result: result.clone(),
+                tasks: tasks.clone(),
+            },
+        ]);
+
+        let expected = context.append_message("Reading", None, vec![(call, result)]);
+        assert_eq!(fixture.context, Some(expected));
+        assert_eq!(fixture.tasks, tasks);
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{ModelId, ToolName, Usage};
+
+/// Usage of a request sent to the model during the conversation, along with
+/// the tools called in its answer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TurnUsage {
+    pub model: ModelId,
+    pub started_at: DateTime<Utc>,
+    pub usage: Usage,
+    /// Time waited for the answer in milliseconds
+    pub duration_ms: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolName>,
+    /// Time taken by the tools of the answer in milliseconds
+    #[serde(default)]
+    pub tool_duration_ms: u64,
+}
+
+/// Tokens, cost and time spent over the turns of a conversation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionStats {
+    /// Number of requests sent to the model
+    pub requests: usize,
+    pub prompt_tokens: usize,
+    pub completion_tokens: usize,
+    pub cached_tokens: usize,
+    /// Cost of the requests, when the provider reported it for any of them
+    pub cost: Option<f64>,
+    /// Number of calls of each tool
+    pub tool_calls: BTreeMap<ToolName, usize>,
+    /// Time waited for the model in milliseconds
+    pub model_duration_ms: u64,
+    /// Time taken by the tools in milliseconds
+    pub tool_duration_ms: u64,
+    /// Time elapsed from the first request to the end of the last turn in
+    /// milliseconds
+    pub wall_clock_ms: u64,
+}
+
+impl SessionStats {
+    pub fn aggregate<'a>(turns: impl IntoIterator<Item = &'a TurnUsage>) -> Self {
+        let mut stats = Self::default();
+        let mut started_at: Option<DateTime<Utc>> = None;
+        let mut ended_at: Option<DateTime<Utc>> = None;
+        for turn in turns {
+            stats.requests += 1;
+            stats.prompt_tokens += *turn.usage.prompt_tokens;
+            stats.completion_tokens += *turn.usage.completion_tokens;
+            stats.cached_tokens += *turn.usage.cached_tokens;
+            if let Some(cost) = turn.usage.cost {
+                *stats.cost.get_or_insert(0.0) += cost;
+            }
+            for name in &turn.tool_calls {
+                *stats.tool_calls.entry(name.clone()).or_default() += 1;
+            }
+            stats.model_duration_ms += turn.duration_ms;
+            stats.tool_duration_ms += turn.tool_duration_ms;
+
+            let end = turn.started_at
+                + chrono::Duration::milliseconds((turn.duration_ms + turn.tool_duration_ms) as i64);
+            started_at = Some(started_at.map_or(turn.started_at, |at| at.min(turn.started_at)));
+            ended_at = Some(ended_at.map_or(end, |at| at.max(end)));
+        }
+
+        if let (Some(started_at), Some(ended_at)) = (started_at, ended_at) {
+            stats.wall_clock_ms = (ended_at - started_at).num_milliseconds().max(0) as u64;
+        }
+        stats
+    }
+
+    pub fn total_tokens(&self) -> usize {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    pub fn total_tool_calls(&self) -> usize {
+        self.tool_calls.values().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::TokenCount;
+
+    fn turn(started_at: i64, cost: Option<f64>, tool_calls: &[&str]) -> TurnUsage {
+        TurnUsage {
+            model: ModelId::new("gpt-4"),
+            started_at: DateTime::<Utc>::from_timestamp(started_at, 0).unwrap(),
+            usage: Usage {
+                prompt_tokens: TokenCount::Actual(100),
+                completion_tokens: TokenCount::Actual(20),
+                total_tokens: TokenCount::Actual(120),
+                cached_tokens: TokenCount::Actual(50),
+                cost,
+            },
+            duration_ms: 2000,
+            tool_calls: tool_calls.iter().map(|name| ToolName::new(*name)).collect(),
+            tool_duration_ms: 500,
+        }
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let fixture = [
+            turn(0, Some(0.01), &["forge_tool_fs_read", "forge_tool_fs_read"]),
+            turn(10, None, &["forge_tool_process_shell"]),
+        ];
+
+        let actual = SessionStats::aggregate(&fixture);
+
+        let expected = SessionStats {
+            requests: 2,
+            prompt_tokens: 200,
+            completion_tokens: 40,
+            cached_tokens: 100,
+            cost: Some(0.01),
+            tool_calls: BTreeMap::from([
+                (ToolName::new("forge_tool_fs_read"), 2),
+                (ToolName::new("forge_tool_process_shell"), 1),
+            ]),
+            model_duration_ms: 4000,
+            tool_duration_ms: 1000,
+            wall_clock_ms: 12500,
+        };
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,10 +1,28 @@
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+use derive_setters::Setters;
+use schemars::JsonSchema;
+use serde::Serialize;
+use strum::IntoEnumIterator;
+use strum_macros::{AsRefStr, EnumIter};
+
+use crate::{CommandPolicy, CommandRetry, ProjectEnv, RetryPolicy};
+
 /// Output from a command execution
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CommandOutput {
     pub command: String,
     pub stdout: String,
     pub stderr: String,
     pub exit_code: Option<i32>,
+    /// Outputs of the earlier attempts of a command that was retried, oldest
+    /// first
+    pub failed_attempts: Vec<CommandOutput>,
+    /// Directory the command moved to with `cd`, when it did and the shell
+    /// reports it
+    pub cwd: Option<PathBuf>,
 }
 
 impl CommandOutput {
@@ -12,3 +30,322 @@ impl CommandOutput {
         self.exit_code.is_none_or(|code| code >= 0)
     }
 }
+
+/// Settings of forge.yaml the shell commands are run with
+#[derive(Debug, Clone, Default, PartialEq, Setters)]
+#[setters(into)]
+pub struct CommandSettings {
+    /// Environment variables of the project
+    pub env: ProjectEnv,
+    /// Commands the agents may run, which doesn't apply to the commands of
+    /// hooks and of the user
+    pub policy: CommandPolicy,
+    /// Commands run again when they fail
+    pub retries: Vec<CommandRetry>,
+    /// Retry of the command being run, taking precedence over `retries`
+    pub retry: Option<RetryPolicy>,
+}
+
+impl CommandSettings {
+    /// Returns how the command line is retried when it fails, if it is
+    pub fn retry_for(&self, command_line: &str) -> anyhow::Result<Option<&RetryPolicy>> {
+        if let Some(retry) = &self.retry {
+            return Ok(Some(retry));
+        }
+        for retry in &self.retries {
+            if retry.matches(command_line)? {
+                return Ok(Some(&retry.retry));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Shell a command is run with instead of the shell of the user, so that a
+/// command written for one shell runs the same way on every platform
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, eserde::Deserialize, AsRefStr, EnumIter)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum ShellKind {
+    Bash,
+    Zsh,
+    Fish,
+    Pwsh,
+    Cmd,
+}
+
+impl ShellKind {
+    /// Program started to run the command
+    pub fn program(&self) -> &str {
+        self.as_ref()
+    }
+
+    /// Kind of a shell from its program, such as `/bin/zsh` or
+    /// `C:\Windows\System32\WindowsPowerShell\v1.0\powershell.exe`
+    pub fn from_program(program: &str) -> Option<ShellKind> {
+        let name = program.rsplit(['/', '\\']).next()?.to_ascii_lowercase();
+        match name.strip_suffix(".exe").unwrap_or(&name) {
+            "bash" => Some(ShellKind::Bash),
+            "zsh" => Some(ShellKind::Zsh),
+            "fish" => Some(ShellKind::Fish),
+            // Windows PowerShell takes the same arguments as PowerShell 7
+            "pwsh" | "powershell" => Some(ShellKind::Pwsh),
+            "cmd" => Some(ShellKind::Cmd),
+            _ => None,
+        }
+    }
+
+    /// Arguments passed to the program before the command. With `/S`, cmd
+    /// strips the quotes around the command and leaves the ones inside it.
+    pub fn args(&self) -> &'static [&'static str] {
+        match self {
+            ShellKind::Bash | ShellKind::Zsh | ShellKind::Fish => &["-c"],
+            ShellKind::Pwsh => &["-NoProfile", "-NonInteractive", "-Command"],
+            ShellKind::Cmd => &["/S", "/C"],
+        }
+    }
+
+    /// Quotes a value so that the shell passes it through as a single word.
+    /// cmd still expands the variables like `%PATH%` of a quoted value.
+    pub fn quote(&self, value: &str) -> String {
+        match self {
+            ShellKind::Bash | ShellKind::Zsh => format!("'{}'", value.replace('\'', r"'\''")),
+            // Unlike in POSIX shells, a backslash escapes within single quotes
+            ShellKind::Fish => {
+                format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'"))
+            }
+            // PowerShell takes the typographic single quotes for quotes too
+            ShellKind::Pwsh => {
+                let mut quoted = String::with_capacity(value.len() + 2);
+                quoted.push('\'');
+                for c in value.chars() {
+                    if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+                        quoted.push(c);
+                    }
+                    quoted.push(c);
+                }
+                quoted.push('\'');
+                quoted
+            }
+            ShellKind::Cmd => format!("\"{}\"", value.replace('"', "\"\"")),
+        }
+    }
+}
+
+impl Display for ShellKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.program())
+    }
+}
+
+impl JsonSchema for ShellKind {
+    fn schema_name() -> String {
+        "ShellKind".to_string()
+    }
+
+    fn json_schema(_gen: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
+        use schemars::schema::{InstanceType, Schema, SchemaObject};
+        let variants: Vec<serde_json::Value> = Self::iter()
+            .map(|variant| variant.as_ref().into())
+            .collect();
+        Schema::Object(SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(variants),
+            ..Default::default()
+        })
+    }
+}
+
+/// Command started in the background, such as a dev server, that keeps
+/// running across turns until it's stopped
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackgroundProcess {
+    /// Identifies the process among the ones started, in the order they were
+    pub id: u32,
+    pub command: String,
+    pub cwd: PathBuf,
+    /// Id of the process in the operating system
+    pub pid: Option<u32>,
+    pub status: ProcessStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProcessStatus {
+    Running,
+    /// The process exited with the code, which is `None` when it was killed
+    /// by a signal
+    Exited(Option<i32>),
+}
+
+impl Display for ProcessStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessStatus::Running => write!(f, "running"),
+            ProcessStatus::Exited(Some(code)) => write!(f, "exited with code {code}"),
+            ProcessStatus::Exited(None) => write!(f, "killed"),
+        }
+    }
+}
+
+/// Most recent output of a background process
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessLogs {
+    pub process: BackgroundProcess,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Lines written by a background process to one of its streams. Only the most
+/// recent lines are kept, so that a process running for hours doesn't fill up
+/// the memory.
+#[derive(Debug, Default)]
+pub struct ProcessLog {
+    lines: VecDeque<String>,
+}
+
+impl ProcessLog {
+    /// Lines kept for each stream of a process
+    const MAX_LINES: usize = 2000;
+
+    pub fn push(&mut self, line: impl Into<String>) {
+        if self.lines.len() == Self::MAX_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line.into());
+    }
+
+    /// The last `lines` lines, oldest first
+    pub fn tail(&self, lines: usize) -> String {
+        let skip = self.lines.len().saturating_sub(lines);
+        self.lines
+            .iter()
+            .skip(skip)
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::CommandPattern;
+
+    #[test]
+    fn test_process_log_tail() {
+        let mut fixture = ProcessLog::default();
+        for line in ["compiling", "listening on :3000", "GET /"] {
+            fixture.push(line);
+        }
+
+        let actual = fixture.tail(2);
+
+        let expected = "listening on :3000\nGET /";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_process_log_keeps_recent_lines() {
+        let mut fixture = ProcessLog::default();
+        for line in 0..ProcessLog::MAX_LINES + 5 {
+            fixture.push(line.to_string());
+        }
+
+        let actual = fixture.tail(usize::MAX).lines().next().map(str::to_string);
+
+        let expected = Some("5".to_string());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        let fixture = r#"it's "C:\dir"’"#;
+
+        let actual = ShellKind::iter()
+            .map(|shell| (shell, shell.quote(fixture)))
+            .collect::<Vec<_>>();
+
+        let expected = vec![
+            (ShellKind::Bash, r#"'it'\''s "C:\dir"’'"#.to_string()),
+            (ShellKind::Zsh, r#"'it'\''s "C:\dir"’'"#.to_string()),
+            (ShellKind::Fish, r#"'it\'s "C:\\dir"’'"#.to_string()),
+            (ShellKind::Pwsh, r#"'it''s "C:\dir"’’'"#.to_string()),
+            (ShellKind::Cmd, r#""it's ""C:\dir""’""#.to_string()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_command_settings_retry_for() {
+        let retry = |command: &str, max_attempts: u32| CommandRetry {
+            command: CommandPattern::Prefix(command.to_string()),
+            retry: RetryPolicy::default().max_attempts(max_attempts),
+        };
+        let fixture =
+            CommandSettings::default().retries(vec![retry("cargo test", 2), retry("cargo", 5)]);
+
+        let actual = [
+            fixture.retry_for("cargo test --workspace").unwrap(),
+            fixture.retry_for("cargo build").unwrap(),
+            fixture.retry_for("ls").unwrap(),
+        ]
+        .map(|retry| retry.map(|retry| retry.max_attempts));
+
+        let expected = [Some(2), Some(5), None];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_command_settings_retry_of_the_call_first() {
+        let fixture = CommandSettings::default()
+            .retries(vec![CommandRetry {
+                command: CommandPattern::Prefix("cargo".to_string()),
+                retry: RetryPolicy::default(),
+            }])
+            .retry(RetryPolicy::default().max_attempts(7u32));
+
+        let actual = fixture.retry_for("cargo test").unwrap().cloned();
+
+        let expected = Some(RetryPolicy::default().max_attempts(7u32));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_shell_kind_from_program() {
+        let fixture = [
+            "/bin/zsh",
+            "/usr/local/bin/fish",
+            r"C:\Program Files\PowerShell\7\pwsh.exe",
+            r"C:\Windows\System32\WindowsPowerShell\v1.0\powershell.exe",
+            r"C:\Windows\system32\CMD.EXE",
+            "/bin/sh",
+        ];
+
+        let actual = fixture.map(ShellKind::from_program);
+
+        let expected = [
+            Some(ShellKind::Zsh),
+            Some(ShellKind::Fish),
+            Some(ShellKind::Pwsh),
+            Some(ShellKind::Pwsh),
+            Some(ShellKind::Cmd),
+            None,
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_process_status_display() {
+        let actual = [
+            ProcessStatus::Running,
+            ProcessStatus::Exited(Some(1)),
+            ProcessStatus::Exited(None),
+        ]
+        .map(|status| status.to_string());
+
+        let expected = ["running", "exited with code 1", "killed"].map(str::to_string);
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use forge_template::Element;
+
+use crate::ConversationId;
+
+/// Instructions the user sent while the agent was working, given to it at the
+/// next safe point of the turn
+#[derive(Debug, Clone, PartialEq)]
+pub struct Steering {
+    pub messages: Vec<String>,
+    /// Whether the response of the model was cut short to take them
+    pub interrupted: bool,
+}
+
+impl Steering {
+    pub fn new(messages: Vec<String>) -> Self {
+        Self { messages, interrupted: false }
+    }
+
+    /// Renders the instructions as they are given to the agent
+    pub fn to_element(&self) -> Element {
+        let element = Element::new("user_steering").append(
+            self.messages
+                .iter()
+                .map(|message| Element::new("message").cdata(message)),
+        );
+        if self.interrupted {
+            element
+                .attr("interrupted", true)
+                .append(Element::new("reflection").text(
+                    "The user stopped your response above to send this. Follow it, continuing from \
+                 where you stopped where it still applies.",
+                ))
+        } else {
+            element
+        }
+    }
+}
+
+/// Instructions that haven't been given to the agent yet, queued by the
+/// conversation they are sent to
+#[derive(Debug, Default, Clone)]
+pub struct SteeringQueue {
+    queues: HashMap<ConversationId, Vec<String>>,
+}
+
+impl SteeringQueue {
+    /// Queues the instruction for the turn running on the conversation
+    pub fn post(&mut self, conversation_id: ConversationId, message: impl Into<String>) {
+        self.queues
+            .entry(conversation_id)
+            .or_default()
+            .push(message.into());
+    }
+
+    /// Takes the instructions sent to the conversation, oldest first
+    pub fn take(&mut self, conversation_id: &ConversationId) -> Vec<String> {
+        self.queues.remove(conversation_id).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_take() {
+        let conversation_id = ConversationId::generate();
+        let mut fixture = SteeringQueue::default();
+        fixture.post(conversation_id, "Use the v2 API");
+        fixture.post(ConversationId::generate(), "Stop");
+        fixture.post(conversation_id, "Skip the docs");
+
+        let actual = fixture.take(&conversation_id);
+
+        let expected = vec!["Use the v2 API".to_string(), "Skip the docs".to_string()];
+        assert_eq!(actual, expected);
+        assert_eq!(fixture.take(&conversation_id), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_to_element() {
+        let fixture = Steering::new(vec!["Use the v2 API".to_string()]);
+
+        let actual = fixture.to_element().render();
+
+        let expected =
+            "<user_steering>\n<message><![CDATA[Use the v2 API]]></message>\n</user_steering>";
+        assert_eq!(actual, expected);
+    }
+}
@@ -4,7 +4,7 @@ use derive_setters::Setters;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::Environment;
+use crate::{AgentId, Environment, Memory};
 
 #[derive(Debug, Setters, Clone, Serialize, Deserialize)]
 #[setters(strip_option)]
@@ -41,4 +41,106 @@ pub struct SystemContext {
     /// Custom prompt to be used in the system context.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub agent_prompt: Option<String>,
+
+    /// Instructions kept in the project, from the repository root down to the
+    /// working directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub project_memory: Vec<ProjectMemory>,
+
+    /// Facts remembered in earlier sessions that are relevant to the task
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub memories: Vec<Memory>,
+
+    /// Map of the files and definitions of the workspace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo_map: Option<String>,
+
+    /// Agents the conversation can be handed off to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub handoffs: Vec<HandoffAgent>,
+
+    /// Indicates whether the agent is planning, and can't change the workspace
+    #[serde(default)]
+    pub plan_mode: bool,
+}
+
+/// An agent the conversation can be handed off to, as described to the agent
+/// handing it off
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HandoffAgent {
+    pub id: AgentId,
+    pub description: Option<String>,
+}
+
+/// A file of instructions for agents kept in the project, such as AGENTS.md
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectMemory {
+    pub path: String,
+    pub content: String,
+}
+
+/// Variables of the workspace the system prompt of an agent is rendered with,
+/// such as `{{cwd}}` or `{{git_branch}}`
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct WorkspaceVariables {
+    pub cwd: String,
+    pub os: String,
+    pub shell: String,
+    /// Branch checked out in the workspace, null outside of a git repository
+    pub git_branch: Option<String>,
+    /// Files and directories of the workspace, one per line
+    pub project_files_overview: String,
+    /// Variables of the conversation
+    pub variables: HashMap<String, Value>,
+}
+
+impl WorkspaceVariables {
+    pub fn new(env: &Environment, files: &[String]) -> Self {
+        Self {
+            cwd: env.cwd.display().to_string(),
+            os: env.os.clone(),
+            shell: env.shell.clone(),
+            git_branch: None,
+            project_files_overview: files_overview(files),
+            variables: Default::default(),
+        }
+    }
+}
+
+/// Lists the directories ahead of the files, as they give the layout of the
+/// project
+fn files_overview(files: &[String]) -> String {
+    let (mut directories, mut files): (Vec<_>, Vec<_>) = files
+        .iter()
+        .map(String::as_str)
+        .partition(|path| path.ends_with('/'));
+    directories.sort();
+    files.sort();
+    directories
+        .into_iter()
+        .chain(files)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_files_overview() {
+        let fixture = [
+            "README.md".to_string(),
+            "src/".to_string(),
+            "Cargo.toml".to_string(),
+            ".github/".to_string(),
+        ];
+
+        let actual = files_overview(&fixture);
+
+        let expected = ".github/\nsrc/\nCargo.toml\nREADME.md";
+        assert_eq!(actual, expected);
+    }
 }
@@ -126,6 +126,16 @@ impl TaskList {
         Some(self.tasks[task_index].clone())
     }
 
+    /// Moves a task to the given position of the list, starting from 1 for the
+    /// first task. Positions past the end move the task to the end.
+    pub fn reprioritize(&mut self, task_id: i32, position: usize) -> Option<Task> {
+        let task_index = self.tasks.iter().position(|t| t.id == task_id)?;
+        let task = self.tasks.remove(task_index)?;
+        let position = position.saturating_sub(1).min(self.tasks.len());
+        self.tasks.insert(position, task.clone());
+        Some(task)
+    }
+
     pub fn clear(&mut self) {
         self.tasks.clear();
         self.next_id = 1;
@@ -326,4 +336,31 @@ mod tests {
 
         assert!(result.is_none());
     }
+
+    #[test]
+    fn test_task_list_reprioritize() {
+        let mut fixture = TaskList::new();
+        fixture.append_multiple(vec!["Task 1".into(), "Task 2".into(), "Task 3".into()]);
+
+        fixture.reprioritize(3, 1);
+        fixture.reprioritize(2, 10);
+        let actual = fixture
+            .tasks()
+            .iter()
+            .map(|task| task.id)
+            .collect::<Vec<_>>();
+
+        let expected = vec![3, 1, 2];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_task_list_reprioritize_nonexistent() {
+        let mut fixture = TaskList::new();
+        fixture.append("Task 1");
+
+        let actual = fixture.reprioritize(999, 1);
+
+        assert!(actual.is_none());
+    }
 }
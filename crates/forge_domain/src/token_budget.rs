@@ -0,0 +1,106 @@
+use std::fmt::Display;
+
+use derive_setters::Setters;
+use serde::Serialize;
+
+use crate::{CompactionTrigger, TokenCount};
+
+/// Tokens of the context window of the model used by the conversation,
+/// reported after each request so that the limit is visible before it's hit
+#[derive(Debug, Clone, Default, PartialEq, Setters)]
+#[setters(strip_option)]
+pub struct TokenBudget {
+    /// Tokens of the context, as counted by the provider or estimated
+    pub used: TokenCount,
+    /// Context window of the model, unknown when the provider doesn't list it
+    pub limit: Option<u64>,
+    /// Set when the context was compacted since the previous report
+    pub compaction: Option<ContextCompaction>,
+}
+
+/// Estimated tokens of the context before and after it was compacted, and
+/// what triggered it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContextCompaction {
+    pub before: usize,
+    pub after: usize,
+    pub trigger: CompactionTrigger,
+}
+
+impl TokenBudget {
+    pub fn new(used: TokenCount) -> Self {
+        Self { used, ..Default::default() }
+    }
+
+    /// Tokens left in the context window
+    pub fn remaining(&self) -> Option<u64> {
+        self.limit
+            .map(|limit| limit.saturating_sub(*self.used as u64))
+    }
+
+    /// Share of the context window that's used, from 0 to 1
+    pub fn utilization(&self) -> Option<f64> {
+        self.limit
+            .filter(|limit| *limit > 0)
+            .map(|limit| (*self.used as f64 / limit as f64).min(1.0))
+    }
+}
+
+impl Display for TokenBudget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.limit, self.utilization()) {
+            (Some(limit), Some(utilization)) => write!(
+                f,
+                "{}/{limit} tokens ({:.0}%)",
+                self.used,
+                utilization * 100.0
+            ),
+            _ => write!(f, "{} tokens", self.used),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_budget_with_limit() {
+        let fixture = TokenBudget::new(TokenCount::Actual(150_000)).limit(200_000u64);
+
+        let actual = (
+            fixture.remaining(),
+            fixture.utilization(),
+            fixture.to_string(),
+        );
+
+        let expected = (
+            Some(50_000),
+            Some(0.75),
+            "150000/200000 tokens (75%)".to_string(),
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_budget_over_limit() {
+        let fixture = TokenBudget::new(TokenCount::Approx(250_000)).limit(200_000u64);
+
+        let actual = (fixture.remaining(), fixture.utilization());
+
+        let expected = (Some(0), Some(1.0));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_budget_without_limit() {
+        let fixture = TokenBudget::new(TokenCount::Approx(1200));
+
+        let actual = (fixture.remaining(), fixture.to_string());
+
+        let expected = (None, "~1200 tokens".to_string());
+        assert_eq!(actual, expected);
+    }
+}
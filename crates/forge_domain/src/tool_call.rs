@@ -7,7 +7,7 @@ use crate::xml::extract_tag_content;
 use crate::{Error, Result, ToolName};
 
 /// Unique identifier for a using a tool
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[serde(transparent)]
 pub struct ToolCallId(pub(crate) String);
 
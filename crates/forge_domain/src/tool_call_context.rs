@@ -1,9 +1,15 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use derive_setters::Setters;
 use tokio::sync::mpsc::Sender;
 
-use crate::{ChatResponse, TaskList};
+use crate::{
+    AgentId, Approval, ApprovalReply, ChangeReview, ChatResponse, ConversationId, FileTransaction,
+    FileVersions, GeneratedFiles, Guard, Hunk, OperationClass, Redactor, Reply, Spending, TaskList,
+    Workflow,
+};
 
 /// Type alias for Arc<Sender<Result<ChatResponse>>>
 type ArcSender = Arc<Sender<anyhow::Result<ChatResponse>>>;
@@ -13,12 +19,67 @@ type ArcSender = Arc<Sender<anyhow::Result<ChatResponse>>>;
 pub struct ToolCallContext {
     sender: Option<ArcSender>,
     pub tasks: TaskList,
+    pub file_versions: FileVersions,
+    /// File writes made by the current batch of tool calls
+    pub transaction: FileTransaction,
+    /// Previews the changes of the tools instead of making them
+    pub dry_run: bool,
+    /// Only allows the tools that can be used while planning
+    pub plan_mode: bool,
+    /// Classes of operations the user allowed for the rest of the session
+    pub allowed_operations: BTreeSet<OperationClass>,
+    /// Conversation in which the tools are called
+    pub conversation_id: Option<ConversationId>,
+    /// Conversation the user started, which the sub-agents spawned in this one
+    /// share, and whose agents exchange messages
+    pub root_conversation_id: Option<ConversationId>,
+    /// Agent calling the tools, which the messages it sends to other agents
+    /// are from
+    pub agent_id: Option<AgentId>,
+    /// Messages of the user received while calling the tools, added to the
+    /// context after the tool results
+    pub user_messages: Vec<String>,
+    /// Directory the shell commands moved to with `cd`, where the commands
+    /// not given one run instead of the workspace
+    pub cwd: Option<PathBuf>,
+    /// Settings of forge.yaml, read once at the start of the turn rather than
+    /// for each call
+    pub workflow: Arc<Workflow>,
+    /// Guardrails of the workflow, compiled once for the turn
+    pub guard: Arc<Guard>,
+    /// Masks the secrets of the output of the tools, compiled once for the
+    /// turn
+    pub redactor: Arc<Redactor>,
+    /// Patterns of the generated files of the workflow, compiled once for the
+    /// turn
+    pub generated: Arc<GeneratedFiles>,
+    /// Work of the sub-agents the tools spawn, counted against the limits of
+    /// the turn
+    pub spending: Arc<Spending>,
 }
 
 impl ToolCallContext {
     /// Creates a new ToolCallContext with default values
     pub fn new(task_list: TaskList) -> Self {
-        Self { sender: None, tasks: task_list }
+        Self {
+            sender: None,
+            tasks: task_list,
+            file_versions: Default::default(),
+            transaction: Default::default(),
+            dry_run: false,
+            plan_mode: false,
+            allowed_operations: Default::default(),
+            conversation_id: None,
+            root_conversation_id: None,
+            agent_id: None,
+            user_messages: Default::default(),
+            cwd: None,
+            workflow: Default::default(),
+            guard: Default::default(),
+            redactor: Default::default(),
+            generated: Default::default(),
+            spending: Default::default(),
+        }
     }
 
     /// Send a message through the sender if available
@@ -33,6 +94,79 @@ impl ToolCallContext {
         self.send(ChatResponse::Text { text: content.to_string(), is_complete: true, is_md: false })
             .await
     }
+
+    /// Asks the user to approve an operation with one of the options and waits
+    /// for the answer. The operation is rejected when there is no one to ask.
+    pub async fn request_approval(
+        &self,
+        message: impl ToString,
+        options: Vec<Approval>,
+    ) -> anyhow::Result<Approval> {
+        if self.sender.is_none() {
+            return Ok(Approval::Reject);
+        }
+
+        let (reply, rx) = ApprovalReply::channel();
+        self.send(ChatResponse::ApprovalRequest {
+            message: message.to_string(),
+            options,
+            reply,
+            review: None,
+        })
+        .await?;
+        Ok(rx.await.unwrap_or(Approval::Reject))
+    }
+
+    /// Asks the user to approve a change of a file, which the UI may let them
+    /// review hunk by hunk. Returns the approval along with whether each hunk
+    /// is accepted, when the user reviewed them.
+    pub async fn request_change_approval(
+        &self,
+        message: impl ToString,
+        options: Vec<Approval>,
+        path: PathBuf,
+        hunks: Vec<Hunk>,
+    ) -> anyhow::Result<(Approval, Option<Vec<bool>>)> {
+        if self.sender.is_none() {
+            return Ok((Approval::Reject, None));
+        }
+
+        let (reply, rx) = ApprovalReply::channel();
+        let (review, mut review_rx) = Reply::channel();
+        self.send(ChatResponse::ApprovalRequest {
+            message: message.to_string(),
+            options,
+            reply,
+            review: Some(ChangeReview { path, hunks, reply: review }),
+        })
+        .await?;
+        let approval = rx.await.unwrap_or(Approval::Reject);
+        // The review is answered before the approval, if at all
+        Ok((approval, review_rx.try_recv().ok()))
+    }
+
+    /// Asks the user a question and waits for the answer. Returns None when
+    /// the user didn't answer or there is no one to ask.
+    pub async fn ask(
+        &self,
+        question: impl ToString,
+        options: Vec<String>,
+        multiple: bool,
+    ) -> anyhow::Result<Option<String>> {
+        if self.sender.is_none() {
+            return Ok(None);
+        }
+
+        let (reply, rx) = Reply::channel();
+        self.send(ChatResponse::Question {
+            question: question.to_string(),
+            options,
+            multiple,
+            reply,
+        })
+        .await?;
+        Ok(rx.await.ok())
+    }
 }
 
 #[cfg(test)]
@@ -45,6 +179,51 @@ mod tests {
         assert!(context.sender.is_none());
     }
 
+    #[tokio::test]
+    async fn test_request_approval_without_sender() {
+        let context = ToolCallContext::new(TaskList::new());
+        let actual = context
+            .request_approval("Remove file", vec![Approval::Accept, Approval::Reject])
+            .await
+            .unwrap();
+        assert_eq!(actual, Approval::Reject);
+    }
+
+    #[tokio::test]
+    async fn test_request_change_approval_with_review() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+        let context = ToolCallContext::new(TaskList::new()).sender(Some(Arc::new(tx)));
+        let fixture = Hunk::split("a\nb\n", "A\nb\nc\n");
+        tokio::spawn(async move {
+            if let Some(Ok(ChatResponse::ApprovalRequest { reply, review: Some(review), .. })) =
+                rx.recv().await
+            {
+                review.reply.send(vec![false, true]);
+                reply.send(Approval::Accept);
+            }
+        });
+
+        let actual = context
+            .request_change_approval(
+                "Patch file",
+                vec![Approval::Accept, Approval::Reject],
+                PathBuf::from("/a.txt"),
+                fixture,
+            )
+            .await
+            .unwrap();
+
+        let expected = (Approval::Accept, Some(vec![false, true]));
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_ask_without_sender() {
+        let context = ToolCallContext::new(TaskList::new());
+        let actual = context.ask("Which file?", vec![], false).await.unwrap();
+        assert_eq!(actual, None);
+    }
+
     #[test]
     fn test_with_sender() {
         // This is just a type check test - we don't actually create a sender
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+use forge_template::Element;
+
+use crate::{ToolCallFull, ToolName, ToolResult};
+
+/// Failures in a row of a tool with the same error after which the agent is
+/// asked to reflect on its attempts
+const REPEATED_FAILURE_THRESHOLD: usize = 2;
+
+/// Attempts shown to the agent, the last ones, so that the reflection added to
+/// each failure doesn't grow with the failures
+const MAX_ATTEMPTS: usize = 3;
+
+const REPEATED_FAILURE_REFLECTION: &str = "This tool keeps failing with the same error, so \
+                                           making the same call again will fail again. Before \
+                                           the next call: 1. Say what the error tells you is \
+                                           wrong. 2. Compare the attempts above and find what \
+                                           didn't change between them. 3. Take a different \
+                                           approach, e.g. read the current state of what you're \
+                                           changing first, use another tool, or ask the user.";
+
+/// Failures in a row of the tools during a turn, to tell the agent when it
+/// keeps making calls that fail the same way
+#[derive(Debug, Default)]
+pub struct FailureTracker {
+    failures: HashMap<ToolName, RepeatedFailure>,
+}
+
+/// Calls of a tool that failed in a row with the same error
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepeatedFailure {
+    pub tool: ToolName,
+    pub error: String,
+    /// Calls that failed in a row
+    pub failures: usize,
+    /// Arguments of the last calls, oldest first
+    pub attempts: Vec<String>,
+}
+
+impl FailureTracker {
+    /// Records the result of a call, returning the failures in a row of the
+    /// tool once the same error repeats
+    pub fn record(&mut self, call: &ToolCallFull, result: &ToolResult) -> Option<RepeatedFailure> {
+        if !result.is_error() {
+            self.failures.remove(&call.name);
+            return None;
+        }
+
+        let error = result.output.as_str().unwrap_or_default().to_string();
+        let failure = self
+            .failures
+            .entry(call.name.clone())
+            .or_insert_with(|| RepeatedFailure {
+                tool: call.name.clone(),
+                error: error.clone(),
+                failures: 0,
+                attempts: Vec::new(),
+            });
+        if failure.error != error {
+            failure.error = error;
+            failure.failures = 0;
+            failure.attempts.clear();
+        }
+        failure.failures += 1;
+        failure.attempts.push(call.arguments.to_string());
+        if failure.attempts.len() > MAX_ATTEMPTS {
+            failure.attempts.remove(0);
+        }
+
+        (failure.failures >= REPEATED_FAILURE_THRESHOLD).then(|| failure.clone())
+    }
+}
+
+impl RepeatedFailure {
+    /// Renders the attempts along with a reflection prompt, added to the
+    /// result of the last one
+    pub fn to_element(&self) -> Element {
+        Element::new("repeated_tool_failure")
+            .attr("tool", &self.tool)
+            .attr("failures", self.failures)
+            .append(
+                self.attempts
+                    .iter()
+                    .map(|arguments| Element::new("attempt").cdata(arguments)),
+            )
+            .append(Element::new("reflection").text(REPEATED_FAILURE_REFLECTION))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+    use crate::ToolOutput;
+
+    fn call(path: &str) -> ToolCallFull {
+        ToolCallFull::new("fs_patch").arguments(json!({"path": path}))
+    }
+
+    fn failed(error: &str) -> ToolResult {
+        ToolResult::new("fs_patch").failure(anyhow::anyhow!(error.to_string()))
+    }
+
+    #[test]
+    fn test_record_repeated_failure() {
+        let mut fixture = FailureTracker::default();
+
+        let actual = [
+            fixture.record(&call("a.rs"), &failed("No match found")),
+            fixture.record(&call("b.rs"), &failed("No match found")),
+        ];
+
+        let expected = [
+            None,
+            Some(RepeatedFailure {
+                tool: ToolName::new("fs_patch"),
+                error: failed("No match found")
+                    .output
+                    .as_str()
+                    .unwrap()
+                    .to_string(),
+                failures: 2,
+                attempts: vec![
+                    r#"{"path":"a.rs"}"#.to_string(),
+                    r#"{"path":"b.rs"}"#.to_string(),
+                ],
+            }),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_record_keeps_the_last_attempts() {
+        let mut fixture = FailureTracker::default();
+
+        let failure = ["a.rs", "b.rs", "c.rs", "d.rs", "e.rs"]
+            .map(|path| fixture.record(&call(path), &failed("No match found")))
+            .into_iter()
+            .last()
+            .flatten()
+            .unwrap();
+
+        let actual = (failure.failures, failure.attempts);
+        let expected = (
+            5,
+            vec![
+                r#"{"path":"c.rs"}"#.to_string(),
+                r#"{"path":"d.rs"}"#.to_string(),
+                r#"{"path":"e.rs"}"#.to_string(),
+            ],
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_record_resets() {
+        let mut fixture = FailureTracker::default();
+        let succeeded = ToolResult::new("fs_patch").output(Ok(ToolOutput::text("Patched")));
+
+        let actual = [
+            fixture.record(&call("a.rs"), &failed("No match found")),
+            fixture.record(&call("a.rs"), &failed("File not found")),
+            fixture.record(&call("a.rs"), &succeeded),
+            fixture.record(&call("a.rs"), &failed("File not found")),
+        ]
+        .map(|failure| failure.is_some());
+
+        let expected = [false, false, false, false];
+        assert_eq!(actual, expected);
+    }
+}
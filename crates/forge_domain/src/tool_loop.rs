@@ -0,0 +1,219 @@
+use std::fmt;
+
+use forge_template::Element;
+
+use crate::{ToolCallFull, ToolName};
+
+/// Times an identical call, or each of two alternating calls, can be made in a
+/// row before the loop is broken, unless the agent sets its own
+pub const DEFAULT_MAX_REPEATED_CALLS: usize = 3;
+
+const LOOP_REFLECTION: &str = "You are going round in circles: the calls above return the same \
+                               results each time, so you already have everything they can tell \
+                               you. Don't make them again. Work with the results you have, or \
+                               tell the user what you need to make progress.";
+
+/// Calls made by the agent during a turn, to tell when it keeps repeating the
+/// same call or alternates between the same two calls
+#[derive(Debug)]
+pub struct LoopDetector {
+    max_repeats: usize,
+    calls: Vec<(ToolName, String)>,
+}
+
+/// Calls the agent kept making in a row
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolLoop {
+    /// The same call with the same arguments
+    Repeat {
+        tool: ToolName,
+        arguments: String,
+        times: usize,
+    },
+    /// Two calls made one after the other
+    Oscillation {
+        first: (ToolName, String),
+        second: (ToolName, String),
+        times: usize,
+    },
+}
+
+impl LoopDetector {
+    /// Creates a detector breaking the loops once a call repeats `max_repeats`
+    /// times. Loops aren't detected when it's below two.
+    pub fn new(max_repeats: usize) -> Self {
+        Self { max_repeats, calls: Vec::new() }
+    }
+
+    /// Records a call, returning the loop once it's detected. The calls made
+    /// up to then are forgotten, so that the agent gets a chance to get out of
+    /// it.
+    pub fn record(&mut self, call: &ToolCallFull) -> Option<ToolLoop> {
+        if self.max_repeats < 2 {
+            return None;
+        }
+        self.calls
+            .push((call.name.clone(), call.arguments.to_string()));
+
+        let tool_loop = self.repeat().or_else(|| self.oscillation());
+        if tool_loop.is_some() {
+            self.calls.clear();
+        }
+        tool_loop
+    }
+
+    fn repeat(&self) -> Option<ToolLoop> {
+        let start = self.calls.len().checked_sub(self.max_repeats)?;
+        let recent = &self.calls[start..];
+        let (tool, arguments) = recent.last()?;
+        recent
+            .iter()
+            .all(|call| call == &recent[0])
+            .then(|| ToolLoop::Repeat {
+                tool: tool.clone(),
+                arguments: arguments.clone(),
+                times: self.max_repeats,
+            })
+    }
+
+    fn oscillation(&self) -> Option<ToolLoop> {
+        let start = self.calls.len().checked_sub(self.max_repeats * 2)?;
+        let recent = &self.calls[start..];
+        let (first, second) = (&recent[0], &recent[1]);
+        let alternates = first != second
+            && recent
+                .iter()
+                .enumerate()
+                .all(|(i, call)| call == if i % 2 == 0 { first } else { second });
+        alternates.then(|| ToolLoop::Oscillation {
+            first: first.clone(),
+            second: second.clone(),
+            times: self.max_repeats,
+        })
+    }
+}
+
+impl ToolLoop {
+    /// Renders the loop along with a reflection prompt, added to the result of
+    /// the last call
+    pub fn to_element(&self) -> Element {
+        let element = match self {
+            ToolLoop::Repeat { tool, arguments, times } => Element::new("tool_loop")
+                .attr("kind", "repeat")
+                .attr("times", times)
+                .append(Element::new("call").attr("tool", tool).cdata(arguments)),
+            ToolLoop::Oscillation { first, second, times } => Element::new("tool_loop")
+                .attr("kind", "oscillation")
+                .attr("times", times)
+                .append([first, second].into_iter().map(|(tool, arguments)| {
+                    Element::new("call").attr("tool", tool).cdata(arguments)
+                })),
+        };
+        element.append(Element::new("reflection").text(LOOP_REFLECTION))
+    }
+}
+
+impl fmt::Display for ToolLoop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToolLoop::Repeat { tool, times, .. } => {
+                write!(
+                    f,
+                    "{tool} was called {times} times in a row with the same arguments"
+                )
+            }
+            ToolLoop::Oscillation { first, second, times } => write!(
+                f,
+                "{} and {} were called one after the other {times} times",
+                first.0, second.0
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn call(name: &str, path: &str) -> ToolCallFull {
+        ToolCallFull::new(name).arguments(json!({"path": path}))
+    }
+
+    #[test]
+    fn test_record_repeat() {
+        let mut fixture = LoopDetector::new(3);
+
+        let actual = [
+            fixture.record(&call("fs_read", "a.rs")),
+            fixture.record(&call("fs_read", "a.rs")),
+            fixture.record(&call("fs_read", "a.rs")),
+            fixture.record(&call("fs_read", "a.rs")),
+        ];
+
+        let expected = [
+            None,
+            None,
+            Some(ToolLoop::Repeat {
+                tool: ToolName::new("fs_read"),
+                arguments: r#"{"path":"a.rs"}"#.to_string(),
+                times: 3,
+            }),
+            None,
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_record_oscillation() {
+        let mut fixture = LoopDetector::new(2);
+
+        let actual = [
+            fixture.record(&call("fs_patch", "a.rs")),
+            fixture.record(&call("fs_undo", "a.rs")),
+            fixture.record(&call("fs_patch", "a.rs")),
+            fixture.record(&call("fs_undo", "a.rs")),
+        ]
+        .map(|tool_loop| tool_loop.map(|tool_loop| tool_loop.to_string()));
+
+        let expected = [
+            None,
+            None,
+            None,
+            Some("fs_patch and fs_undo were called one after the other 2 times".to_string()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_record_different_calls() {
+        let mut fixture = LoopDetector::new(2);
+
+        let actual = [
+            fixture.record(&call("fs_read", "a.rs")),
+            fixture.record(&call("fs_read", "b.rs")),
+            fixture.record(&call("fs_read", "c.rs")),
+            fixture.record(&call("fs_read", "a.rs")),
+        ]
+        .map(|tool_loop| tool_loop.is_some());
+
+        let expected = [false, false, false, false];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_record_disabled() {
+        let mut fixture = LoopDetector::new(0);
+
+        let actual = [
+            fixture.record(&call("fs_read", "a.rs")),
+            fixture.record(&call("fs_read", "a.rs")),
+        ]
+        .map(|tool_loop| tool_loop.is_some());
+
+        let expected = [false, false];
+        assert_eq!(actual, expected);
+    }
+}
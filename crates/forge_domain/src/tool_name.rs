@@ -4,7 +4,9 @@ use regex::Regex;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
 #[serde(transparent)]
 pub struct ToolName(String);
 
@@ -45,9 +47,31 @@ impl ToolName {
         &self.0
     }
 
+    /// Name of the MCP server that provides the tool, if it's an MCP tool
+    pub fn mcp_server(&self) -> Option<&str> {
+        let (server, _) = self.0.strip_prefix("mcp_")?.split_once("_tool_")?;
+        Some(server)
+    }
+
     pub fn into_sanitized(self) -> Self {
         ToolName::sanitized(self.0.as_str())
     }
+
+    /// Checks if the pattern refers to this tool, either by its full name, by
+    /// its name without the `forge_tool_` prefix, by a group it belongs to
+    /// (eg: `fs` for `forge_tool_fs_read`) or by the last part of its name
+    /// (eg: `read`)
+    pub fn matches(&self, pattern: &str) -> bool {
+        let name = self.0.strip_prefix("forge_tool_").unwrap_or(&self.0);
+        self.0 == pattern
+            || name == pattern
+            || name
+                .strip_prefix(pattern)
+                .is_some_and(|rest| rest.starts_with('_'))
+            || name
+                .strip_suffix(pattern)
+                .is_some_and(|rest| rest.ends_with('_'))
+    }
 }
 
 impl From<String> for ToolName {
@@ -229,4 +253,23 @@ mod tests {
         let expected = ToolName::new("test123case");
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_matches() {
+        let fixture = ToolName::new("forge_tool_fs_read");
+
+        let actual = [
+            "forge_tool_fs_read",
+            "fs_read",
+            "fs",
+            "read",
+            "ead",
+            "forge",
+            "forge_tool_fs_search",
+        ]
+        .map(|pattern| fixture.matches(pattern));
+
+        let expected = [true, true, true, true, false, false, false];
+        assert_eq!(actual, expected);
+    }
 }
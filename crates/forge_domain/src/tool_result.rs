@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use derive_setters::Setters;
 use forge_template::Element;
 use serde::{Deserialize, Serialize};
@@ -7,6 +9,11 @@ use crate::{Image, ToolCallFull, ToolCallId, ToolName};
 const REFLECTION_PROMPT: &str =
     include_str!("../../../templates/forge-partial-tool-error-reflection.hbs");
 
+const TIMEOUT_REFLECTION: &str = "The tool was stopped because it took too long. Consider \
+                                  limiting the work it does, for example by running long \
+                                  commands in the background or with smaller inputs, or use a \
+                                  different approach.";
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize, Setters)]
 #[setters(into)]
 pub struct ToolResult {
@@ -94,6 +101,18 @@ impl ToolOutput {
         }
     }
 
+    /// Output of a tool call that didn't complete within the timeout, so that
+    /// the agent can recover instead of failing the whole turn
+    pub fn timeout(tool_name: &ToolName, timeout: Duration) -> Self {
+        ToolOutput::text(
+            Element::new("tool_call_timeout")
+                .attr("tool", tool_name)
+                .attr("timeout_secs", timeout.as_secs())
+                .append(Element::new("reflection").text(TIMEOUT_REFLECTION)),
+        )
+        .is_error(true)
+    }
+
     pub fn image(img: Image) -> Self {
         ToolOutput { is_error: false, values: vec![ToolValue::Image(img)] }
     }
@@ -172,4 +191,18 @@ mod tests {
         assert!(failure.is_error());
         insta::assert_snapshot!(failure.output.as_str().unwrap());
     }
+
+    #[test]
+    fn test_timeout_output() {
+        let actual = ToolOutput::timeout(
+            &ToolName::new("forge_tool_process_shell"),
+            Duration::from_secs(300),
+        );
+
+        let text = actual.as_str().unwrap();
+        assert!(actual.is_error);
+        assert!(text.starts_with("<tool_call_timeout"));
+        assert!(text.contains(r#"tool="forge_tool_process_shell""#));
+        assert!(text.contains(r#"timeout_secs="300""#));
+    }
 }
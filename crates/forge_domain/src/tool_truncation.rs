@@ -0,0 +1,127 @@
+use derive_setters::Setters;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::Environment;
+
+/// Part of a long output that is kept when it is truncated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncationStrategy {
+    /// Keep the first lines of the output
+    Head,
+    /// Keep the last lines of the output
+    Tail,
+    /// Keep the first and last lines of the output, dropping the middle
+    Middle,
+}
+
+/// Truncation settings for the output of a tool. Unset fields fall back to
+/// the limits of the environment.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, Setters, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct ToolTruncation {
+    /// Part of the output that is kept when it is truncated. Applies to line
+    /// based output, such as the output of shell commands.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<TruncationStrategy>,
+
+    /// Maximum number of lines kept in the output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_lines: Option<usize>,
+
+    /// Maximum number of bytes kept in the output of fetch and search tools
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<usize>,
+
+    /// Whether the full output is saved to a temporary file whose path is
+    /// referenced in the truncated output. Defaults to true.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub save_full_output: Option<bool>,
+}
+
+impl ToolTruncation {
+    /// Returns the environment with its truncation limits overridden by these
+    /// settings
+    pub fn apply(&self, env: Environment) -> Environment {
+        let (prefix, suffix) =
+            self.shell_lines(env.stdout_max_prefix_length, env.stdout_max_suffix_length);
+        let mut env = env
+            .stdout_max_prefix_length(prefix)
+            .stdout_max_suffix_length(suffix);
+        if let Some(max_lines) = self.max_lines {
+            env = env.max_search_lines(max_lines);
+        }
+        if let Some(max_bytes) = self.max_bytes {
+            env = env
+                .fetch_truncation_limit(max_bytes)
+                .max_search_result_bytes(max_bytes);
+        }
+        env
+    }
+
+    /// Returns true if the full output should be saved to a file when it is
+    /// truncated
+    pub fn saves_full_output(&self) -> bool {
+        self.save_full_output.unwrap_or(true)
+    }
+
+    /// Splits the lines kept from shell output into the number of leading and
+    /// trailing lines, starting from the given defaults
+    fn shell_lines(&self, prefix: usize, suffix: usize) -> (usize, usize) {
+        let total = self.max_lines.unwrap_or(prefix + suffix);
+        match self.strategy {
+            Some(TruncationStrategy::Head) => (total, 0),
+            Some(TruncationStrategy::Tail) => (0, total),
+            Some(TruncationStrategy::Middle) => (total - total / 2, total / 2),
+            None if self.max_lines.is_some() => (total - total / 2, total / 2),
+            None => (prefix, suffix),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_shell_lines() {
+        let fixture = ToolTruncation::default();
+
+        let actual = vec![
+            fixture.shell_lines(100, 50),
+            fixture.clone().max_lines(11usize).shell_lines(100, 50),
+            fixture
+                .clone()
+                .strategy(TruncationStrategy::Head)
+                .shell_lines(100, 50),
+            fixture
+                .clone()
+                .strategy(TruncationStrategy::Tail)
+                .max_lines(20usize)
+                .shell_lines(100, 50),
+            fixture
+                .clone()
+                .strategy(TruncationStrategy::Middle)
+                .shell_lines(100, 50),
+        ];
+
+        let expected = vec![(100, 50), (6, 5), (150, 0), (0, 20), (75, 75)];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_saves_full_output_by_default() {
+        let actual = (
+            ToolTruncation::default().saves_full_output(),
+            ToolTruncation::default()
+                .save_full_output(false)
+                .saves_full_output(),
+        );
+
+        let expected = (true, false);
+        assert_eq!(actual, expected);
+    }
+}
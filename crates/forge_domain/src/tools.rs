@@ -1,5 +1,5 @@
 #![allow(clippy::enum_variant_names)]
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::path::{Path, PathBuf};
 
 use convert_case::{Case, Casing};
@@ -13,7 +13,8 @@ use strum::IntoEnumIterator;
 use strum_macros::{AsRefStr, Display, EnumDiscriminants, EnumIter};
 
 use crate::{
-    Status, ToolCallArgumentError, ToolCallFull, ToolDefinition, ToolDescription, ToolName,
+    ShellKind, Status, ToolCallArgumentError, ToolCallFull, ToolDefinition, ToolDescription,
+    ToolName,
 };
 
 /// Enum representing all possible tool input types.
@@ -40,19 +41,42 @@ pub enum Tools {
     ForgeToolFsRead(FSRead),
     ForgeToolFsCreate(FSWrite),
     ForgeToolFsSearch(FSSearch),
+    ForgeToolFsSemanticSearch(FSSemanticSearch),
+    ForgeToolFsOutline(FSOutline),
+    ForgeToolFsFindSymbol(FSFindSymbol),
+    ForgeToolFsStats(FSStats),
     ForgeToolFsRemove(FSRemove),
     ForgeToolFsPatch(FSPatch),
+    ForgeToolFsApplyPatch(FSApplyPatch),
+    ForgeToolFsAstEdit(FSAstEdit),
     ForgeToolFsUndo(FSUndo),
+    ForgeToolLspFindReferences(LspFindReferences),
+    ForgeToolLspRenameSymbol(LspRenameSymbol),
     ForgeToolProcessShell(Shell),
+    ForgeToolProcessDocker(Docker),
+    ForgeToolProcessPython(PythonRun),
+    ForgeToolProcessBackground(Background),
     ForgeToolNetFetch(NetFetch),
+    ForgeToolNetBrowser(NetBrowser),
+    ForgeToolNetRequest(NetRequest),
     ForgeToolFollowup(Followup),
+    ForgeToolAskUser(AskUser),
     ForgeToolAttemptCompletion(AttemptCompletion),
     ForgeToolTaskListAppend(TaskListAppend),
     ForgeToolTaskListAppendMultiple(TaskListAppendMultiple),
     ForgeToolTaskListUpdate(TaskListUpdate),
+    ForgeToolTaskListComplete(TaskListComplete),
+    ForgeToolTaskListReprioritize(TaskListReprioritize),
     ForgeToolTaskListList(TaskListList),
     ForgeToolTaskListClear(TaskListClear),
     ForgeToolPlanCreate(PlanCreate),
+    ForgeToolMemoryRemember(MemoryRemember),
+    ForgeToolMemoryRecall(MemoryRecall),
+    ForgeToolSpawnAgent(SpawnAgent),
+    ForgeToolFanOut(FanOut),
+    ForgeToolHandoff(Handoff),
+    ForgeToolMessageSend(MessageSend),
+    ForgeToolMessageReceive(MessageReceive),
 }
 
 /// Input structure for agent tool calls. This serves as the generic schema
@@ -134,8 +158,10 @@ pub struct FSWrite {
 /// (when regex omitted). Uses case-insensitive Rust regex syntax. Requires
 /// absolute paths. Avoids binary files and excluded directories. Best for code
 /// exploration, API usage discovery, configuration settings, or finding
-/// patterns across projects. For large pages, returns the first 200
-/// lines and stores the complete content in a temporary file for
+/// patterns across projects. Set context_lines to see the lines around each
+/// match. Prefer it over shell commands like grep or find, which behave
+/// differently on each operating system. For large pages, returns the first
+/// 200 lines and stores the complete content in a temporary file for
 /// subsequent access.
 #[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
 pub struct FSSearch {
@@ -160,6 +186,83 @@ pub struct FSSearch {
     /// If not provided, it will search all files (*).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub file_pattern: Option<String>,
+
+    /// Number of lines to show before and after each content match. Context
+    /// lines are formatted as `path-line-content` while matches are
+    /// formatted as `path:line:content`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_lines: Option<u32>,
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+/// Finds the parts of the workspace closest in meaning to a query in natural
+/// language, such as "where are failed HTTP requests retried". Unlike a regex
+/// search it finds code that shares no words with the query, so it's best for
+/// exploring an unfamiliar codebase or locating where a behavior is
+/// implemented. Returns the most relevant chunks of files with their line
+/// ranges, ordered by relevance. Files that changed since the last search are
+/// indexed again first. Requires an embedding model to be configured.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct FSSemanticSearch {
+    /// Description of the code to find, in natural language
+    pub query: String,
+
+    /// Maximum number of chunks to return. Defaults to 10.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_results: Option<u32>,
+
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+/// Outlines the definitions of a source file: its functions, methods, types,
+/// impl blocks, classes and modules, each with its line range and first line,
+/// nested under the definitions enclosing them. Much cheaper than reading a
+/// large file in full, use it to find the lines to read with
+/// `forge_tool_fs_read`. Supports Rust, Python, TypeScript, JavaScript, Go,
+/// Java, Scala, Ruby and C++ files.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct FSOutline {
+    /// The absolute path of the file to outline
+    pub path: String,
+
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+/// Finds where a function, method, type or other definition is defined across
+/// the workspace, by its name or its symbol path, eg: `parse` or
+/// `Parser::parse`. Unlike a regex search it only matches definitions, not the
+/// uses of the name. Returns the file, line range and first line of every
+/// matching definition. Files that changed since the last lookup are outlined
+/// again first. Supports Rust, Python, TypeScript, JavaScript, Go, Java,
+/// Scala, Ruby and C++ files.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct FSFindSymbol {
+    /// Name of the definition, optionally prefixed with the names of its
+    /// enclosing definitions separated by `::` or `.`
+    pub symbol: String,
+
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+/// Summarizes the size of the workspace: its number of files and lines of
+/// code, the files and lines written in each language and its largest files.
+/// Use it first to get a sense of an unfamiliar codebase, such as its main
+/// languages and where its bulk is, before exploring it. Counts the text files
+/// that aren't ignored, generated or minified.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct FSStats {
     /// One sentence explanation as to why this specific tool is being used, and
     /// how it contributes to the goal.
     #[serde(default)]
@@ -274,6 +377,120 @@ pub struct FSPatch {
     pub explanation: Option<String>,
 }
 
+/// Applies a unified diff, as produced by `diff -u` or `git diff`, to one or
+/// more files at once. Paths in the diff are relative to the working
+/// directory. Use it for changes spanning several hunks or files instead of
+/// rewriting whole files; `/dev/null` as the old or new path creates or
+/// deletes a file. Hunks are located by their context lines, tolerating
+/// shifted line numbers and whitespace differences. Either all files are
+/// changed or, if any hunk doesn't match, none of them are and the failing
+/// hunks are reported.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct FSApplyPatch {
+    /// The unified diff to apply, with `---`/`+++` file headers and `@@` hunk
+    /// headers
+    pub patch: String,
+
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+/// Operation types that can be performed on a symbol of a source file
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, AsRefStr, EnumIter)]
+#[serde(rename_all = "snake_case")]
+pub enum AstOperation {
+    /// Replace the body of the function, method, class or type, keeping its
+    /// signature
+    #[default]
+    ReplaceBody,
+
+    /// Replace the whole definition of the symbol
+    Replace,
+
+    /// Insert content before the definition of the symbol
+    InsertBefore,
+
+    /// Insert content after the definition of the symbol
+    InsertAfter,
+
+    /// Insert an import after the existing imports of the file
+    InsertImport,
+
+    /// Wrap the definition of the symbol in a block
+    Wrap,
+}
+
+impl JsonSchema for AstOperation {
+    fn schema_name() -> String {
+        std::any::type_name::<Self>()
+            .split("::")
+            .last()
+            .unwrap_or("AstOperation")
+            .to_string()
+    }
+
+    fn json_schema(_gen: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
+        use schemars::schema::{InstanceType, Schema, SchemaObject};
+        let variants: Vec<serde_json::Value> = Self::iter()
+            .map(|variant| variant.as_ref().to_case(Case::Snake).into())
+            .collect();
+        Schema::Object(SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(variants),
+            metadata: Some(Box::new(schemars::schema::Metadata {
+                ..Default::default()
+            })),
+            ..Default::default()
+        })
+    }
+}
+
+/// Edits a source file through its syntax tree instead of matching text.
+/// Definitions are addressed by their symbol path, the names of the enclosing
+/// definitions followed by its own, eg: `Parser::parse` for a method of an
+/// impl block or `Shape.area` for a method of a class. Supports replacing the
+/// body or the whole definition, inserting code before or after it, wrapping
+/// it in a block and adding imports. More robust than `forge_tool_fs_patch`
+/// for large files since it doesn't depend on the exact text of the file.
+/// Supports Rust, Python, TypeScript, JavaScript, Go, Java, Scala, Ruby and
+/// C++ files. Fails without changing the file if the symbol isn't found or if
+/// the edit would introduce syntax errors.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct FSAstEdit {
+    /// The absolute path to the file to modify
+    pub path: String,
+
+    /// Symbol path of the definition to edit, with names separated by `::`
+    /// or `.`. The enclosing names can be omitted as long as the symbol is
+    /// unique. Not used by `insert_import`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+
+    /// The operation to perform on the symbol. Possible options are:
+    /// - 'replace_body': Replace the body of the function, method, class or
+    ///   type, keeping its signature. The content is the new body without its
+    ///   braces.
+    /// - 'replace': Replace the whole definition of the symbol
+    /// - 'insert_before': Insert content before the definition of the symbol
+    /// - 'insert_after': Insert content after the definition of the symbol
+    /// - 'insert_import': Insert an import after the existing imports of the
+    ///   file, unless the file already has it
+    /// - 'wrap': Wrap the definition of the symbol in a block. The content is
+    ///   the block with a `$SYMBOL` placeholder where the definition goes.
+    pub operation: AstOperation,
+
+    /// The content to use for the operation. It is re-indented to match the
+    /// position of the symbol in the file.
+    pub content: String,
+
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
 /// Reverts the most recent file operation (create/modify/delete) on a specific
 /// file. Use this tool when you need to recover from incorrect file changes or
 /// if a revert is requested by the user.
@@ -287,6 +504,58 @@ pub struct FSUndo {
     pub explanation: Option<String>,
 }
 
+/// Finds every reference to a symbol across the workspace, including its
+/// declaration, using the language server of the file. Unlike a regex search
+/// it only returns actual uses of the symbol, skipping comments, strings and
+/// unrelated identifiers with the same name. The symbol is identified by a
+/// file and line where it appears along with its name. Requires the language
+/// server (rust-analyzer, typescript-language-server, pyright, gopls or
+/// clangd) to be installed.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct LspFindReferences {
+    /// The absolute path of a file where the symbol appears
+    pub path: String,
+
+    /// The line of the file where the symbol appears (1-based)
+    pub line: u64,
+
+    /// The name of the symbol, exactly as written on that line
+    pub symbol: String,
+
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+/// Renames a symbol and updates every reference to it across the workspace
+/// using the language server of the file. Prefer it over search and replace
+/// for renaming functions, types, variables, fields or modules, since only
+/// actual uses of the symbol are changed. The symbol is identified by a file
+/// and line where it appears along with its name. Every changed file is
+/// reported with its diff, and either all of them are changed or none are.
+/// Requires the language server (rust-analyzer, typescript-language-server,
+/// pyright, gopls or clangd) to be installed.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct LspRenameSymbol {
+    /// The absolute path of a file where the symbol appears
+    pub path: String,
+
+    /// The line of the file where the symbol appears (1-based)
+    pub line: u64,
+
+    /// The name of the symbol, exactly as written on that line
+    pub symbol: String,
+
+    /// The new name of the symbol
+    pub new_name: String,
+
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
 /// Executes shell commands with safety measures using restricted bash (rbash).
 /// Prevents potentially harmful operations like absolute path execution and
 /// directory changes. Use for file system interaction, running utilities,
@@ -299,8 +568,30 @@ pub struct Shell {
     /// The shell command to execute.
     pub command: String,
 
-    /// The working directory where the command should be executed.
-    pub cwd: PathBuf,
+    /// The working directory where the command should be executed. Defaults
+    /// to the directory the previous commands moved to with `cd`, or the
+    /// workspace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+
+    /// The shell to run the command with: 'bash', 'zsh', 'fish', 'pwsh' or
+    /// 'cmd'. Only set it when the command is written for a specific shell,
+    /// the shell of the user is used otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<ShellKind>,
+
+    /// Content written to the standard input of the command, e.g. a patch for
+    /// 'patch -p1' or the answers a script prompts for. The command reads an
+    /// empty input if not specified, it can't read from the terminal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdin: Option<String>,
+
+    /// Maximum number of times the command runs when it fails, e.g. 3 for a
+    /// flaky integration test or an install over an unreliable network. Each
+    /// failed attempt is reported with its output. The command runs once if
+    /// not specified, and 10 times at most.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
 
     /// Whether to preserve ANSI escape codes in the output.
     /// If true, ANSI escape codes will be preserved in the output.
@@ -315,6 +606,280 @@ pub struct Shell {
     pub explanation: Option<String>,
 }
 
+/// Inspects Docker containers and Docker Compose services to debug why a
+/// service is failing. Use 'ps' to list containers with their status,
+/// 'inspect' for the configuration and state of a container, 'logs' for its
+/// most recent log lines and 'exec' to run a command inside it. Set `compose`
+/// to address the services of the Compose project in the working directory
+/// instead of containers. Output is bounded the same way as shell output;
+/// prefer this tool over running docker in the shell.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct Docker {
+    /// The action to perform:
+    /// - 'ps': List the containers, including stopped ones
+    /// - 'inspect': Show the configuration and state of the target
+    /// - 'logs': Show the most recent log lines of the target
+    /// - 'exec': Run a command inside the target
+    pub action: DockerAction,
+
+    /// Name or ID of the container, or name of the service when `compose` is
+    /// true. Required for all actions except 'ps'
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+
+    /// Command to run inside the target, required for the 'exec' action
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+
+    /// Address the services of the Docker Compose project in the working
+    /// directory instead of containers (default: false)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compose: Option<bool>,
+
+    /// Number of log lines to return for the 'logs' action (default: 200)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tail: Option<u64>,
+
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+/// Runs Python code in a persistent interpreter that keeps variables, imports
+/// and functions between calls within the conversation. Prefer it over running
+/// `python -c` in the shell for data exploration and computations that build
+/// on earlier results. Returns the captured stdout and stderr, the value of
+/// the last expression as in a notebook cell, and the traceback if the code
+/// raised. Code running past the timeout is interrupted, keeping the state of
+/// the interpreter. Requires python3 to be installed.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct PythonRun {
+    /// The Python code to run
+    pub code: String,
+
+    /// Maximum number of seconds the code may run (default: 60)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+
+    /// Restart the interpreter before running the code, discarding everything
+    /// defined so far (default: false)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restart: Option<bool>,
+
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+impl PythonRun {
+    const DEFAULT_TIMEOUT: u64 = 60;
+
+    /// Maximum time the code may run
+    pub fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.timeout.unwrap_or(Self::DEFAULT_TIMEOUT))
+    }
+}
+
+/// Runs long-lived commands such as dev servers and file watchers in the
+/// background, so that they keep running across turns instead of blocking
+/// the shell tool until it times out. Use 'start' to launch a command, which
+/// returns the id of the process right away, 'logs' to read the most recent
+/// lines it wrote to stdout and stderr, 'stop' to shut it down along with
+/// the processes it started and 'list' to see the processes and whether they
+/// are still running. Stop the processes once they are no longer needed.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct Background {
+    /// The action to perform:
+    /// - 'start': Start the command in the background
+    /// - 'logs': Show the most recent output of a process
+    /// - 'stop': Stop a process
+    /// - 'list': List the processes, including the ones that exited
+    pub action: BackgroundAction,
+
+    /// Shell command to start, required for the 'start' action
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+
+    /// Working directory of the command for the 'start' action (default: the
+    /// directory the shell commands moved to with `cd`, or the workspace)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+
+    /// Id of the process returned by 'start', required for the 'logs' and
+    /// 'stop' actions
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u32>,
+
+    /// Number of lines of each stream to return for the 'logs' action
+    /// (default: 100)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tail: Option<usize>,
+
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+impl Background {
+    const DEFAULT_TAIL: usize = 100;
+
+    /// Number of lines of each stream returned by the 'logs' action
+    pub fn tail(&self) -> usize {
+        self.tail.unwrap_or(Self::DEFAULT_TAIL)
+    }
+
+    /// Id of the process the action is performed on
+    pub fn process_id(&self) -> anyhow::Result<u32> {
+        self.id.ok_or_else(|| {
+            anyhow::anyhow!(
+                "The '{}' action requires the id of a process",
+                self.action.as_ref()
+            )
+        })
+    }
+}
+
+/// Actions supported by the background process tool
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, AsRefStr, EnumIter)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum BackgroundAction {
+    /// Start a command in the background
+    #[default]
+    Start,
+
+    /// Show the most recent output of a process
+    Logs,
+
+    /// Stop a process
+    Stop,
+
+    /// List the processes
+    List,
+}
+
+impl JsonSchema for BackgroundAction {
+    fn schema_name() -> String {
+        "BackgroundAction".to_string()
+    }
+
+    fn json_schema(_gen: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
+        use schemars::schema::{InstanceType, Schema, SchemaObject};
+        let variants: Vec<serde_json::Value> = Self::iter()
+            .map(|variant| variant.as_ref().into())
+            .collect();
+        Schema::Object(SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(variants),
+            ..Default::default()
+        })
+    }
+}
+
+/// Actions supported by the docker tool
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, AsRefStr, EnumIter)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum DockerAction {
+    /// List containers
+    #[default]
+    Ps,
+
+    /// Show the configuration and state of a container
+    Inspect,
+
+    /// Show the logs of a container
+    Logs,
+
+    /// Run a command inside a container
+    Exec,
+}
+
+impl JsonSchema for DockerAction {
+    fn schema_name() -> String {
+        "DockerAction".to_string()
+    }
+
+    fn json_schema(_gen: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
+        use schemars::schema::{InstanceType, Schema, SchemaObject};
+        let variants: Vec<serde_json::Value> = Self::iter()
+            .map(|variant| variant.as_ref().into())
+            .collect();
+        Schema::Object(SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(variants),
+            ..Default::default()
+        })
+    }
+}
+
+impl Docker {
+    const DEFAULT_TAIL: u64 = 200;
+
+    /// Builds the docker command line for the requested action. Arguments are
+    /// quoted for the shell, or else for a POSIX shell, so that they are passed
+    /// to docker verbatim.
+    pub fn to_command(&self, shell: Option<ShellKind>) -> anyhow::Result<String> {
+        let shell = shell.unwrap_or(ShellKind::Bash);
+        let quote = |arg: &str| shell.quote(arg);
+        let compose = self.compose.unwrap_or_default();
+        let docker = if compose { "docker compose" } else { "docker" };
+        let target = || {
+            self.target.as_deref().map(quote).ok_or_else(|| {
+                anyhow::anyhow!("The '{}' action requires a target", self.action.as_ref())
+            })
+        };
+
+        Ok(match self.action {
+            DockerAction::Ps if compose => "docker compose ps --all".to_string(),
+            DockerAction::Ps => format!(
+                "docker ps --all --format {}",
+                quote(r"table {{.Names}}\t{{.Image}}\t{{.Status}}\t{{.Ports}}")
+            ),
+            // Compose has no inspect command, so the containers of the service are looked up.
+            // cmd can't substitute a command, so it inspects them one at a time.
+            DockerAction::Inspect if compose && shell == ShellKind::Cmd => format!(
+                "for /f %i in ('docker compose ps --all --quiet {}') do @docker inspect %i",
+                target()?
+            ),
+            DockerAction::Inspect if compose => {
+                format!(
+                    "docker inspect $(docker compose ps --all --quiet {})",
+                    target()?
+                )
+            }
+            DockerAction::Inspect => format!("docker inspect {}", target()?),
+            DockerAction::Logs => format!(
+                "{docker} logs --no-color --timestamps --tail {} {}",
+                self.tail.unwrap_or(Self::DEFAULT_TAIL),
+                target()?
+            ),
+            DockerAction::Exec => {
+                let command = self
+                    .command
+                    .as_deref()
+                    .ok_or_else(|| anyhow::anyhow!("The 'exec' action requires a command"))?;
+                // Compose allocates a TTY by default, which fails without a terminal
+                let tty = if compose { " -T" } else { "" };
+                format!("{docker} exec{tty} {} sh -c {}", target()?, quote(command))
+            }
+        })
+    }
+}
+
 /// Input type for the net fetch tool
 #[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
 pub struct NetFetch {
@@ -332,6 +897,140 @@ pub struct NetFetch {
     pub explanation: Option<String>,
 }
 
+/// Controls a headless browser, for pages that only render with JavaScript
+/// such as single page apps and documentation sites. Use the `open` action to
+/// load a URL and get the readable text of the page, `click` with a CSS
+/// selector to follow links or press buttons, `text` to read the current page
+/// again and `screenshot` to see what the page looks like. The page is kept
+/// between calls. Prefer `forge_tool_net_fetch` for static pages and APIs as
+/// it is much faster. For large pages, returns the first 40,000 characters and
+/// stores the complete content in a temporary file for subsequent access.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct NetBrowser {
+    /// The action to perform:
+    /// - 'open': Load the URL and return the text of the page
+    /// - 'text': Return the text of the current page
+    /// - 'click': Click the element matching the selector and return the text
+    ///   of the resulting page
+    /// - 'screenshot': Capture the visible part of the current page
+    pub action: BrowserAction,
+
+    /// URL to load, required for the 'open' action
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// CSS selector of the element to click, required for the 'click' action
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+/// Actions supported by the browser tool
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, AsRefStr, EnumIter)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum BrowserAction {
+    /// Load a URL
+    #[default]
+    Open,
+
+    /// Read the current page
+    Text,
+
+    /// Click an element on the current page
+    Click,
+
+    /// Capture the current page as an image
+    Screenshot,
+}
+
+impl JsonSchema for BrowserAction {
+    fn schema_name() -> String {
+        "BrowserAction".to_string()
+    }
+
+    fn json_schema(_gen: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
+        use schemars::schema::{InstanceType, Schema, SchemaObject};
+        let variants: Vec<serde_json::Value> = Self::iter()
+            .map(|variant| variant.as_ref().into())
+            .collect();
+        Schema::Object(SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(variants),
+            ..Default::default()
+        })
+    }
+}
+
+/// Sends an HTTP request to an API and returns the status code and the
+/// response body. Use it to call internal REST APIs and local services while
+/// debugging, eg: to check an endpoint after changing it. Only URLs allowed by
+/// a `request` policy in forge.yaml can be called; ask the user to allow the
+/// API if the request is denied. For large responses, returns the first 40,000
+/// characters and stores the complete content in a temporary file for
+/// subsequent access.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct NetRequest {
+    /// HTTP method of the request
+    pub method: HttpMethod,
+
+    /// URL to send the request to
+    pub url: String,
+
+    /// Headers to send with the request, eg: Content-Type or Authorization
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub headers: Option<BTreeMap<String, String>>,
+
+    /// Body of the request
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+/// HTTP methods supported by the request tool
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, AsRefStr, EnumIter)]
+#[serde(rename_all = "UPPERCASE")]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum HttpMethod {
+    #[default]
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+    Head,
+}
+
+impl JsonSchema for HttpMethod {
+    fn schema_name() -> String {
+        "HttpMethod".to_string()
+    }
+
+    fn json_schema(_gen: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
+        use schemars::schema::{InstanceType, Schema, SchemaObject};
+        let variants: Vec<serde_json::Value> = Self::iter()
+            .map(|variant| variant.as_ref().into())
+            .collect();
+        Schema::Object(SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(variants),
+            ..Default::default()
+        })
+    }
+}
+
 /// Use this tool when you encounter ambiguities, need clarification, or require
 /// more details to proceed effectively. Use this tool judiciously to maintain a
 /// balance between gathering necessary information and avoiding excessive
@@ -372,6 +1071,31 @@ pub struct Followup {
     pub explanation: Option<String>,
 }
 
+/// Asks the user a clarifying question and waits for the answer, which is
+/// added to the conversation as a message from the user. Unlike
+/// `forge_tool_followup` the task carries on once the user answered. Offer
+/// options when the answer is one of a few known choices, the user then picks
+/// from them. Only ask when the task can't reasonably go on without the
+/// answer.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct AskUser {
+    /// Question to ask the user
+    pub question: String,
+
+    /// Choices offered to the user; leave empty to let the user answer freely
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+
+    /// Allow selecting several of the options (default: false)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multiple: Option<bool>,
+
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
 /// After each tool use, the user will respond with the result of
 /// that tool use, i.e. if it succeeded or failed, along with any reasons for
 /// failure. Once you've received the results of tool uses and can confirm that
@@ -391,8 +1115,8 @@ pub struct AttemptCompletion {
     pub result: String,
 }
 
-/// Add a new task to the end of the task list. Tasks are stored in conversation
-/// state and persist across agent interactions. Use this tool to add individual
+/// Add a new task to the end of the task list. Tasks are stored in the
+/// workspace and persist across sessions. Use this tool to add individual
 /// work items that need to be tracked during development sessions. Task IDs are
 /// auto-generated integers starting from 1.
 #[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
@@ -406,7 +1130,7 @@ pub struct TaskListAppend {
 }
 
 /// Add multiple new tasks to the end of the task list. Tasks are stored in
-/// conversation state and persist across agent interactions. Use this tool to
+/// the workspace and persist across sessions. Use this tool to
 /// add several work items at once during development sessions. Task IDs are
 /// auto-generated integers starting from 1.
 #[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
@@ -434,6 +1158,33 @@ pub struct TaskListUpdate {
     pub explanation: Option<String>,
 }
 
+/// Mark a task of the task list as done. Use this as soon as the work of a task
+/// is finished, so that the list reflects the progress made.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct TaskListComplete {
+    /// The ID of the task to mark as done
+    pub task_id: i32,
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+/// Move a task to another position of the task list, the first task being the
+/// most important one. Use this when priorities change, e.g. a task turns out
+/// to block the others or to be less urgent than planned.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct TaskListReprioritize {
+    /// The ID of the task to move
+    pub task_id: i32,
+    /// The new position of the task, starting from 1 for the highest priority
+    pub position: usize,
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
 /// Display the current task list with statistics. Shows all tasks with their
 /// IDs, descriptions, and status (PENDING, IN_PROGRESS, DONE), along with
 /// summary statistics. Use this tool to review current work items and track
@@ -479,6 +1230,151 @@ pub struct PlanCreate {
     pub explanation: Option<String>,
 }
 
+/// Remembers a fact about the project across sessions. Use this tool when the
+/// user states a convention, preference or decision that applies beyond the
+/// current task (e.g. how to run the tests, which libraries to use), so that it
+/// does not have to be repeated in the next conversations. Write the fact as a
+/// short standalone sentence.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct MemoryRemember {
+    /// The fact to remember, as a short standalone sentence
+    pub fact: String,
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+/// Recalls the facts remembered about the project in previous sessions that are
+/// relevant to a query. The most relevant memories are already given at the
+/// start of a conversation, use this tool to look for others when a task
+/// touches a topic they do not cover.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct MemoryRecall {
+    /// Words describing the topic to recall memories about
+    pub query: String,
+    /// Maximum number of memories to return. Defaults to 10.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_results: Option<u32>,
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+/// Delegates a self-contained part of the task to a sub-agent, which works on
+/// it in a context of its own and reports back a summary of what it did and
+/// found. Use it on large tasks to keep the current context small, e.g. to
+/// investigate a module or to make a well-defined change. The sub-agent does
+/// not see this conversation, so describe the task fully. Limit its tools to
+/// those the task needs, and its requests to bound the work.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct SpawnAgent {
+    /// Complete description of the task, with the paths, names and constraints
+    /// the sub-agent needs and what it should report back
+    pub task: String,
+    /// Tools the sub-agent can use, as full names or groups such as `fs_read`,
+    /// `fs` or `shell`; all the tools available to you when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+    /// Maximum number of requests the sub-agent makes to the model before
+    /// reporting back
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_requests: Option<usize>,
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+/// Runs the same task over a list of independent items, such as failing tests
+/// to fix or files to migrate, with a sub-agent per item and several of them
+/// working in parallel. Each sub-agent gets the task and its item in a context
+/// of its own, and the results of all the items are reported back together.
+/// Only use it when the items don't depend on each other and don't change the
+/// same files. The sub-agents do not see this conversation, so describe the
+/// task fully.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct FanOut {
+    /// Complete description of the task to run for each item, with the paths,
+    /// names and constraints the sub-agents need and what they should report
+    /// back. The item is given after it.
+    pub task: String,
+    /// Items to run the task over, one sub-agent each, e.g. the name of a test
+    /// or the path of a file
+    pub items: Vec<String>,
+    /// Number of sub-agents working at the same time, at most 8. Defaults to 4.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parallelism: Option<usize>,
+    /// Tools the sub-agents can use, as full names or groups such as
+    /// `fs_read`, `fs` or `shell`; all the tools available to you when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+    /// Maximum number of requests all the sub-agents make to the model
+    /// together. The items not started once it's used up are skipped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_requests: Option<usize>,
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+/// Hands the conversation off to another agent better suited for what comes
+/// next, e.g. to review, test or document the changes you made. The agent
+/// takes over the conversation and starts from the context you give it,
+/// without the messages exchanged so far, so include everything it needs:
+/// the goal, the files involved and what was done. Only hand off to one of
+/// the agents listed as available for handoffs, and once the work that
+/// requires you is over.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct Handoff {
+    /// Id of the agent taking over the conversation
+    pub agent: String,
+    /// Everything the agent needs to know to carry on, in place of the
+    /// conversation so far
+    pub context: String,
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+/// Sends a message to another agent working at the same time, e.g. to ask the
+/// coder to fix an issue found in a review or to tell the reviewer a change is
+/// ready. The message is given to the agent before its next step, so there is
+/// no need to wait for it to be read. Keep the body self-contained, the agent
+/// does not see this conversation.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct MessageSend {
+    /// Id of the agent to send the message to
+    pub to: String,
+    /// What the message is about, in a few words
+    pub subject: String,
+    /// Content of the message, with the paths, names and details the agent
+    /// needs to act on it
+    pub body: String,
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+/// Receives the messages other agents sent to this agent. Messages are given
+/// to the agent before each of its steps anyway, use this tool to wait for an
+/// answer when there is nothing else to do until it arrives.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema, ToolDescription, PartialEq)]
+pub struct MessageReceive {
+    /// Seconds to wait for a message when none has arrived yet, at most 300.
+    /// Defaults to not waiting.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wait_seconds: Option<u64>,
+    /// One sentence explanation as to why this specific tool is being used, and
+    /// how it contributes to the goal.
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
 fn default_raw() -> Option<bool> {
     Some(false)
 }
@@ -591,21 +1487,44 @@ impl ToolDescription for Tools {
     fn description(&self) -> String {
         match self {
             Tools::ForgeToolFsPatch(v) => v.description(),
+            Tools::ForgeToolFsApplyPatch(v) => v.description(),
+            Tools::ForgeToolFsAstEdit(v) => v.description(),
             Tools::ForgeToolProcessShell(v) => v.description(),
+            Tools::ForgeToolProcessDocker(v) => v.description(),
+            Tools::ForgeToolProcessPython(v) => v.description(),
+            Tools::ForgeToolProcessBackground(v) => v.description(),
             Tools::ForgeToolFollowup(v) => v.description(),
+            Tools::ForgeToolAskUser(v) => v.description(),
             Tools::ForgeToolNetFetch(v) => v.description(),
+            Tools::ForgeToolNetBrowser(v) => v.description(),
+            Tools::ForgeToolNetRequest(v) => v.description(),
             Tools::ForgeToolAttemptCompletion(v) => v.description(),
             Tools::ForgeToolFsSearch(v) => v.description(),
+            Tools::ForgeToolFsSemanticSearch(v) => v.description(),
+            Tools::ForgeToolFsOutline(v) => v.description(),
+            Tools::ForgeToolFsFindSymbol(v) => v.description(),
+            Tools::ForgeToolFsStats(v) => v.description(),
             Tools::ForgeToolFsRead(v) => v.description(),
             Tools::ForgeToolFsRemove(v) => v.description(),
             Tools::ForgeToolFsUndo(v) => v.description(),
+            Tools::ForgeToolLspFindReferences(v) => v.description(),
+            Tools::ForgeToolLspRenameSymbol(v) => v.description(),
             Tools::ForgeToolFsCreate(v) => v.description(),
             Tools::ForgeToolTaskListAppend(v) => v.description(),
             Tools::ForgeToolTaskListAppendMultiple(v) => v.description(),
             Tools::ForgeToolTaskListUpdate(v) => v.description(),
+            Tools::ForgeToolTaskListComplete(v) => v.description(),
+            Tools::ForgeToolTaskListReprioritize(v) => v.description(),
             Tools::ForgeToolTaskListList(v) => v.description(),
             Tools::ForgeToolTaskListClear(v) => v.description(),
             Tools::ForgeToolPlanCreate(v) => v.description(),
+            Tools::ForgeToolMemoryRemember(v) => v.description(),
+            Tools::ForgeToolMemoryRecall(v) => v.description(),
+            Tools::ForgeToolSpawnAgent(v) => v.description(),
+            Tools::ForgeToolFanOut(v) => v.description(),
+            Tools::ForgeToolHandoff(v) => v.description(),
+            Tools::ForgeToolMessageSend(v) => v.description(),
+            Tools::ForgeToolMessageReceive(v) => v.description(),
         }
     }
 }
@@ -631,25 +1550,52 @@ impl Tools {
             .into_generator();
         match self {
             Tools::ForgeToolFsPatch(_) => r#gen.into_root_schema_for::<FSPatch>(),
+            Tools::ForgeToolFsApplyPatch(_) => r#gen.into_root_schema_for::<FSApplyPatch>(),
+            Tools::ForgeToolFsAstEdit(_) => r#gen.into_root_schema_for::<FSAstEdit>(),
             Tools::ForgeToolProcessShell(_) => r#gen.into_root_schema_for::<Shell>(),
+            Tools::ForgeToolProcessDocker(_) => r#gen.into_root_schema_for::<Docker>(),
+            Tools::ForgeToolProcessPython(_) => r#gen.into_root_schema_for::<PythonRun>(),
+            Tools::ForgeToolProcessBackground(_) => r#gen.into_root_schema_for::<Background>(),
             Tools::ForgeToolFollowup(_) => r#gen.into_root_schema_for::<Followup>(),
+            Tools::ForgeToolAskUser(_) => r#gen.into_root_schema_for::<AskUser>(),
             Tools::ForgeToolNetFetch(_) => r#gen.into_root_schema_for::<NetFetch>(),
+            Tools::ForgeToolNetBrowser(_) => r#gen.into_root_schema_for::<NetBrowser>(),
+            Tools::ForgeToolNetRequest(_) => r#gen.into_root_schema_for::<NetRequest>(),
             Tools::ForgeToolAttemptCompletion(_) => {
                 r#gen.into_root_schema_for::<AttemptCompletion>()
             }
             Tools::ForgeToolFsSearch(_) => r#gen.into_root_schema_for::<FSSearch>(),
+            Tools::ForgeToolFsSemanticSearch(_) => r#gen.into_root_schema_for::<FSSemanticSearch>(),
+            Tools::ForgeToolFsOutline(_) => r#gen.into_root_schema_for::<FSOutline>(),
+            Tools::ForgeToolFsFindSymbol(_) => r#gen.into_root_schema_for::<FSFindSymbol>(),
+            Tools::ForgeToolFsStats(_) => r#gen.into_root_schema_for::<FSStats>(),
             Tools::ForgeToolFsRead(_) => r#gen.into_root_schema_for::<FSRead>(),
             Tools::ForgeToolFsRemove(_) => r#gen.into_root_schema_for::<FSRemove>(),
             Tools::ForgeToolFsUndo(_) => r#gen.into_root_schema_for::<FSUndo>(),
+            Tools::ForgeToolLspFindReferences(_) => {
+                r#gen.into_root_schema_for::<LspFindReferences>()
+            }
+            Tools::ForgeToolLspRenameSymbol(_) => r#gen.into_root_schema_for::<LspRenameSymbol>(),
             Tools::ForgeToolFsCreate(_) => r#gen.into_root_schema_for::<FSWrite>(),
             Tools::ForgeToolTaskListAppend(_) => r#gen.into_root_schema_for::<TaskListAppend>(),
             Tools::ForgeToolTaskListAppendMultiple(_) => {
                 r#gen.into_root_schema_for::<TaskListAppendMultiple>()
             }
             Tools::ForgeToolTaskListUpdate(_) => r#gen.into_root_schema_for::<TaskListUpdate>(),
+            Tools::ForgeToolTaskListComplete(_) => r#gen.into_root_schema_for::<TaskListComplete>(),
+            Tools::ForgeToolTaskListReprioritize(_) => {
+                r#gen.into_root_schema_for::<TaskListReprioritize>()
+            }
             Tools::ForgeToolTaskListList(_) => r#gen.into_root_schema_for::<TaskListList>(),
             Tools::ForgeToolTaskListClear(_) => r#gen.into_root_schema_for::<TaskListClear>(),
             Tools::ForgeToolPlanCreate(_) => r#gen.into_root_schema_for::<PlanCreate>(),
+            Tools::ForgeToolMemoryRemember(_) => r#gen.into_root_schema_for::<MemoryRemember>(),
+            Tools::ForgeToolMemoryRecall(_) => r#gen.into_root_schema_for::<MemoryRecall>(),
+            Tools::ForgeToolSpawnAgent(_) => r#gen.into_root_schema_for::<SpawnAgent>(),
+            Tools::ForgeToolFanOut(_) => r#gen.into_root_schema_for::<FanOut>(),
+            Tools::ForgeToolHandoff(_) => r#gen.into_root_schema_for::<Handoff>(),
+            Tools::ForgeToolMessageSend(_) => r#gen.into_root_schema_for::<MessageSend>(),
+            Tools::ForgeToolMessageReceive(_) => r#gen.into_root_schema_for::<MessageReceive>(),
         }
     }
 
@@ -671,10 +1617,38 @@ impl Tools {
         .any(|v| v.to_string().to_case(Case::Snake).eq(tool_name.as_str()))
     }
 
+    /// Checks if the tool can be used in plan mode, where the agent only reads
+    /// the workspace and talks to the user until the plan is approved
+    pub fn is_planning(tool_name: &ToolName) -> bool {
+        [
+            ToolsDiscriminants::ForgeToolFsRead,
+            ToolsDiscriminants::ForgeToolFsSearch,
+            ToolsDiscriminants::ForgeToolFsSemanticSearch,
+            ToolsDiscriminants::ForgeToolFsOutline,
+            ToolsDiscriminants::ForgeToolFsFindSymbol,
+            ToolsDiscriminants::ForgeToolFsStats,
+            ToolsDiscriminants::ForgeToolLspFindReferences,
+            ToolsDiscriminants::ForgeToolNetFetch,
+            ToolsDiscriminants::ForgeToolFollowup,
+            ToolsDiscriminants::ForgeToolAskUser,
+            ToolsDiscriminants::ForgeToolAttemptCompletion,
+            ToolsDiscriminants::ForgeToolTaskListList,
+            ToolsDiscriminants::ForgeToolPlanCreate,
+            ToolsDiscriminants::ForgeToolMemoryRecall,
+        ]
+        .iter()
+        .any(|tool| tool.name() == *tool_name)
+    }
+
     /// Convert a tool input to its corresponding domain operation for policy
     /// checking. Returns None for tools that don't require permission
-    /// checks.
-    pub fn to_policy_operation(&self, cwd: PathBuf) -> Option<crate::policies::Operation> {
+    /// checks. The commands the tool builds are quoted for the shell of the
+    /// user.
+    pub fn to_policy_operation(
+        &self,
+        cwd: PathBuf,
+        shell: Option<ShellKind>,
+    ) -> Option<crate::policies::Operation> {
         let cwd_path = cwd.clone();
         let display_path_for = |path: &str| {
             format!(
@@ -717,6 +1691,28 @@ impl Tools {
                     message,
                 })
             }
+            // Every file of the workspace can end up in the results
+            Tools::ForgeToolFsSemanticSearch(input) => Some(crate::policies::Operation::Read {
+                path: cwd.clone(),
+                cwd,
+                message: format!("Search the workspace for: {}", input.query),
+            }),
+            Tools::ForgeToolFsOutline(input) => Some(crate::policies::Operation::Read {
+                path: std::path::PathBuf::from(&input.path),
+                cwd,
+                message: format!("Outline file: {}", display_path_for(&input.path)),
+            }),
+            // Every file of the workspace can be looked into
+            Tools::ForgeToolFsFindSymbol(input) => Some(crate::policies::Operation::Read {
+                path: cwd.clone(),
+                cwd,
+                message: format!("Find the definitions of: {}", input.symbol),
+            }),
+            Tools::ForgeToolFsStats(_) => Some(crate::policies::Operation::Read {
+                path: cwd.clone(),
+                cwd,
+                message: "Summarize the size of the workspace".to_string(),
+            }),
             Tools::ForgeToolFsRemove(input) => Some(crate::policies::Operation::Write {
                 path: std::path::PathBuf::from(&input.path),
                 cwd,
@@ -727,26 +1723,97 @@ impl Tools {
                 cwd,
                 message: format!("Modify file: {}", display_path_for(&input.path)),
             }),
+            Tools::ForgeToolFsAstEdit(input) => Some(crate::policies::Operation::Write {
+                path: std::path::PathBuf::from(&input.path),
+                cwd,
+                message: format!("Modify file: {}", display_path_for(&input.path)),
+            }),
             Tools::ForgeToolProcessShell(input) => Some(crate::policies::Operation::Execute {
                 command: input.command.clone(),
                 cwd,
                 message: format!("Execute shell command: {}", input.command),
             }),
+            Tools::ForgeToolProcessDocker(input) => {
+                input
+                    .to_command(shell)
+                    .ok()
+                    .map(|command| crate::policies::Operation::Execute {
+                        message: format!("Execute docker command: {command}"),
+                        command,
+                        cwd,
+                    })
+            }
+            // Running Python is as powerful as running the interpreter from the shell, so it
+            // is checked as such
+            Tools::ForgeToolProcessPython(input) => Some(crate::policies::Operation::Execute {
+                command: "python".to_string(),
+                cwd,
+                message: format!("Run Python code: {}", input.code),
+            }),
+            // Only starting a command runs anything, the other actions are on the processes
+            // started before
+            Tools::ForgeToolProcessBackground(input) => input
+                .command
+                .as_ref()
+                .filter(|_| input.action == BackgroundAction::Start)
+                .map(|command| crate::policies::Operation::Execute {
+                    command: command.clone(),
+                    cwd,
+                    message: format!("Start background process: {command}"),
+                }),
             Tools::ForgeToolNetFetch(input) => Some(crate::policies::Operation::Fetch {
                 url: input.url.clone(),
                 cwd,
                 message: format!("Fetch content from URL: {}", input.url),
             }),
+            // Pages reached by clicking are checked once the browser navigated to them
+            Tools::ForgeToolNetBrowser(input) => input
+                .url
+                .as_ref()
+                .filter(|_| input.action == BrowserAction::Open)
+                .map(|url| crate::policies::Operation::Fetch {
+                    url: url.clone(),
+                    cwd,
+                    message: format!("Open URL in browser: {url}"),
+                }),
+            Tools::ForgeToolNetRequest(input) => Some(crate::policies::Operation::Request {
+                method: input.method.as_ref().to_string(),
+                url: input.url.clone(),
+                cwd,
+                message: format!("{} request to URL: {}", input.method.as_ref(), input.url),
+            }),
+            Tools::ForgeToolLspFindReferences(input) => Some(crate::policies::Operation::Read {
+                path: std::path::PathBuf::from(&input.path),
+                cwd,
+                message: format!(
+                    "Find references to '{}' in: {}",
+                    input.symbol,
+                    display_path_for(&input.path)
+                ),
+            }),
+            // A patch or a rename can touch several files, so the executor checks each of
+            // them
+            Tools::ForgeToolFsApplyPatch(_) | Tools::ForgeToolLspRenameSymbol(_) => None,
+            // The tools of the sub-agents are checked as they call them
+            Tools::ForgeToolSpawnAgent(_) | Tools::ForgeToolFanOut(_) => None,
             // Operations that don't require permission checks
             Tools::ForgeToolFsUndo(_)
             | Tools::ForgeToolFollowup(_)
+            | Tools::ForgeToolAskUser(_)
             | Tools::ForgeToolAttemptCompletion(_)
+            | Tools::ForgeToolHandoff(_)
             | Tools::ForgeToolTaskListAppend(_)
             | Tools::ForgeToolTaskListAppendMultiple(_)
             | Tools::ForgeToolTaskListUpdate(_)
+            | Tools::ForgeToolTaskListComplete(_)
+            | Tools::ForgeToolTaskListReprioritize(_)
             | Tools::ForgeToolTaskListList(_)
             | Tools::ForgeToolTaskListClear(_)
-            | Tools::ForgeToolPlanCreate(_) => None,
+            | Tools::ForgeToolPlanCreate(_)
+            | Tools::ForgeToolMemoryRemember(_)
+            | Tools::ForgeToolMemoryRecall(_)
+            | Tools::ForgeToolMessageSend(_)
+            | Tools::ForgeToolMessageReceive(_) => None,
         }
     }
 }
@@ -841,6 +1908,23 @@ mod tests {
         assert!(!Tools::is_complete(&incomplete_tool));
     }
 
+    #[test]
+    fn test_is_planning() {
+        let fixture = [
+            "forge_tool_fs_read",
+            "forge_tool_fs_search",
+            "forge_tool_plan_create",
+            "forge_tool_fs_patch",
+            "forge_tool_process_shell",
+            "mcp_github_tool_create_issue",
+        ];
+
+        let actual = fixture.map(|name| Tools::is_planning(&ToolName::new(name)));
+
+        let expected = [true, true, true, false, false, false];
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn test_tool_definition() {
         let actual = ToolsDiscriminants::ForgeToolFsRemove.name();
@@ -895,11 +1979,12 @@ mod tests {
             start_index: None,
             max_search_lines: None,
             file_pattern: None,
+            context_lines: None,
             explanation: None,
         });
 
         let operation = search_with_regex
-            .to_policy_operation(PathBuf::from("/test/cwd"))
+            .to_policy_operation(PathBuf::from("/test/cwd"), None)
             .unwrap();
 
         match operation {
@@ -926,11 +2011,12 @@ mod tests {
             start_index: None,
             max_search_lines: None,
             file_pattern: None,
+            context_lines: None,
             explanation: None,
         });
 
         let operation = search_without_regex
-            .to_policy_operation(PathBuf::from("/test/cwd"))
+            .to_policy_operation(PathBuf::from("/test/cwd"), None)
             .unwrap();
 
         match operation {
@@ -954,11 +2040,12 @@ mod tests {
             start_index: None,
             max_search_lines: None,
             file_pattern: Some("*.rs".to_string()),
+            context_lines: None,
             explanation: None,
         });
 
         let operation = search_with_pattern
-            .to_policy_operation(PathBuf::from("/test/cwd"))
+            .to_policy_operation(PathBuf::from("/test/cwd"), None)
             .unwrap();
 
         match operation {
@@ -985,11 +2072,12 @@ mod tests {
             start_index: None,
             max_search_lines: None,
             file_pattern: Some("*.rs".to_string()),
+            context_lines: None,
             explanation: None,
         });
 
         let operation = search_with_both
-            .to_policy_operation(PathBuf::from("/test/cwd"))
+            .to_policy_operation(PathBuf::from("/test/cwd"), None)
             .unwrap();
 
         match operation {
@@ -1002,4 +2090,128 @@ mod tests {
             _ => panic!("Expected Read operation"),
         }
     }
+
+    #[test]
+    fn test_browser_open_requires_fetch_permission() {
+        use std::path::PathBuf;
+
+        use crate::policies::Operation;
+
+        let fixture = |action| {
+            Tools::ForgeToolNetBrowser(NetBrowser {
+                action,
+                url: Some("https://example.com".to_string()),
+                selector: Some("a.next".to_string()),
+                explanation: None,
+            })
+            .to_policy_operation(PathBuf::from("/test/cwd"), None)
+        };
+
+        let actual = (fixture(BrowserAction::Open), fixture(BrowserAction::Click));
+        let expected = (
+            Some(Operation::Fetch {
+                url: "https://example.com".to_string(),
+                cwd: PathBuf::from("/test/cwd"),
+                message: "Open URL in browser: https://example.com".to_string(),
+            }),
+            None,
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_docker_logs_command() {
+        let fixture = Docker {
+            action: DockerAction::Logs,
+            target: Some("api".to_string()),
+            compose: Some(true),
+            ..Default::default()
+        };
+
+        let actual = fixture.to_command(None).unwrap();
+
+        let expected = "docker compose logs --no-color --timestamps --tail 200 'api'";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_docker_exec_command_is_quoted() {
+        let fixture = Docker {
+            action: DockerAction::Exec,
+            target: Some("db".to_string()),
+            command: Some("psql -c 'select 1'".to_string()),
+            ..Default::default()
+        };
+
+        let actual = fixture.to_command(None).unwrap();
+
+        let expected = r#"docker exec 'db' sh -c 'psql -c '\''select 1'\'''"#;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_docker_command_is_quoted_for_the_shell() {
+        let exec = Docker {
+            action: DockerAction::Exec,
+            target: Some("db".to_string()),
+            command: Some("psql -c 'select 1'".to_string()),
+            ..Default::default()
+        };
+        let inspect = Docker {
+            action: DockerAction::Inspect,
+            target: Some("api".to_string()),
+            compose: Some(true),
+            ..Default::default()
+        };
+
+        let actual = [
+            exec.to_command(Some(ShellKind::Pwsh)).unwrap(),
+            exec.to_command(Some(ShellKind::Cmd)).unwrap(),
+            inspect.to_command(Some(ShellKind::Cmd)).unwrap(),
+        ];
+
+        let expected = [
+            r#"docker exec 'db' sh -c 'psql -c ''select 1'''"#.to_string(),
+            r#"docker exec "db" sh -c "psql -c 'select 1'""#.to_string(),
+            r#"for /f %i in ('docker compose ps --all --quiet "api"') do @docker inspect %i"#
+                .to_string(),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_docker_requires_target() {
+        let fixture = Docker { action: DockerAction::Inspect, ..Default::default() };
+
+        let actual = fixture.to_command(None).is_err();
+
+        assert!(actual);
+    }
+
+    #[test]
+    fn test_background_policy_checks_start_only() {
+        let fixture = |action| {
+            Tools::ForgeToolProcessBackground(Background {
+                action,
+                command: Some("npm run dev".to_string()),
+                id: Some(1),
+                ..Default::default()
+            })
+            .to_policy_operation(PathBuf::from("/test/cwd"), None)
+        };
+
+        let actual = (
+            fixture(BackgroundAction::Start),
+            fixture(BackgroundAction::Stop),
+        );
+        let expected = (
+            Some(Operation::Execute {
+                command: "npm run dev".to_string(),
+                cwd: PathBuf::from("/test/cwd"),
+                message: "Start background process: npm run dev".to_string(),
+            }),
+            None,
+        );
+        assert_eq!(actual, expected);
+    }
 }
@@ -0,0 +1,124 @@
+use std::fmt::Display;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Hard limit on the work an agent does for a single message. Once it's
+/// reached the turn is aborted, whatever the agent is in the middle of.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TurnLimit {
+    /// Requests made to the model
+    Turns(u64),
+    /// Cost of the requests, as reported by the provider
+    CostUsd(f64),
+    /// Time since the message was received
+    Duration(Duration),
+}
+
+impl Display for TurnLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TurnLimit::Turns(turns) => write!(f, "{turns} turns"),
+            TurnLimit::CostUsd(cost) => write!(f, "${cost:.2}"),
+            TurnLimit::Duration(duration) => write!(f, "{}s", duration.as_secs()),
+        }
+    }
+}
+
+/// What the agent got done before its turn was aborted
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PartialResult {
+    /// Requests made to the model
+    pub turns: u64,
+    /// Cost of the requests, as reported by the provider
+    pub cost_usd: f64,
+    /// Time the turn ran for
+    pub duration: Duration,
+    /// Tools called by the agent
+    pub tool_calls: usize,
+    /// Last thing the agent said, if anything
+    pub last_message: Option<String>,
+}
+
+impl Display for PartialResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} turns, {} tool calls, ${:.2} in {}s",
+            self.turns,
+            self.tool_calls,
+            self.cost_usd,
+            self.duration.as_secs()
+        )
+    }
+}
+
+/// Work of the sub-agents spawned by the tools of an agent during its turn,
+/// which counts against the limits of the turn as well
+#[derive(Debug, Default)]
+pub struct Spending {
+    // Requests made to the model and their cost
+    spent: Mutex<(u64, f64)>,
+}
+
+impl Spending {
+    /// Counts a request of a sub-agent, with its cost as reported by the
+    /// provider
+    pub fn record(&self, cost_usd: Option<f64>) {
+        let mut spent = self.spent.lock().unwrap();
+        spent.0 += 1;
+        spent.1 += cost_usd.unwrap_or_default();
+    }
+
+    /// Requests made so far, with their cost
+    pub fn spent(&self) -> (u64, f64) {
+        *self.spent.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let actual = [
+            TurnLimit::Turns(20).to_string(),
+            TurnLimit::CostUsd(1.5).to_string(),
+            TurnLimit::Duration(Duration::from_secs(600)).to_string(),
+        ];
+
+        let expected = ["20 turns", "$1.50", "600s"];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_spending_adds_up_requests() {
+        let fixture = Spending::default();
+
+        fixture.record(Some(0.25));
+        fixture.record(None);
+        fixture.record(Some(0.5));
+
+        let actual = fixture.spent();
+        let expected = (3, 0.75);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_partial_result_display() {
+        let fixture = PartialResult {
+            turns: 12,
+            cost_usd: 0.4321,
+            duration: Duration::from_millis(95_500),
+            tool_calls: 31,
+            last_message: None,
+        };
+
+        let actual = fixture.to_string();
+
+        let expected = "12 turns, 31 tool calls, $0.43 in 95s";
+        assert_eq!(actual, expected);
+    }
+}
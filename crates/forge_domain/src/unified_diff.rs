@@ -0,0 +1,235 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, bail};
+
+/// A line of a hunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+/// A hunk of a unified diff, ie: a contiguous change along with its context
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// The `@@ -a,b +c,d @@` header line
+    pub header: String,
+    /// Line where the hunk starts in the original file (1-based)
+    pub old_start: usize,
+    pub lines: Vec<HunkLine>,
+}
+
+impl Hunk {
+    /// Lines the hunk expects to find in the original file
+    pub fn old_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Remove(text) => Some(text.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect()
+    }
+
+    /// Lines that replace the original ones once the hunk is applied
+    pub fn new_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Add(text) => Some(text.as_str()),
+                HunkLine::Remove(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// Changes to a single file. The old path is missing for new files and the
+/// new path is missing for deleted files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    pub old_path: Option<PathBuf>,
+    pub new_path: Option<PathBuf>,
+    pub hunks: Vec<Hunk>,
+}
+
+impl FileDiff {
+    /// Path of the file the diff applies to
+    pub fn path(&self) -> &Path {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or(Path::new(""))
+    }
+}
+
+/// Parses a unified diff, as produced by `diff -u` or `git diff`, into the
+/// changes of every file it touches.
+pub fn parse_unified_diff(patch: &str) -> anyhow::Result<Vec<FileDiff>> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    let mut lines = patch.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if let Some(old) = line.strip_prefix("--- ") {
+            let new = lines
+                .next()
+                .and_then(|line| line.strip_prefix("+++ "))
+                .with_context(|| format!("Expected a '+++' line after '{line}'"))?;
+            files.push(FileDiff {
+                old_path: parse_path(old),
+                new_path: parse_path(new),
+                hunks: Vec::new(),
+            });
+        } else if line.starts_with("@@") {
+            let file = files
+                .last_mut()
+                .with_context(|| format!("Hunk '{line}' is not preceded by file headers"))?;
+            let (old_start, mut old_count, mut new_count) = parse_hunk_header(line)?;
+            let mut hunk = Hunk { header: line.to_string(), old_start, lines: Vec::new() };
+
+            while old_count > 0 || new_count > 0 {
+                let Some(line) = lines.next() else {
+                    bail!("Hunk '{}' ends before all of its lines", hunk.header);
+                };
+                // Some editors strip the trailing space of empty context lines
+                let (marker, text) = match line.chars().next() {
+                    Some(marker) => (marker, &line[marker.len_utf8()..]),
+                    None => (' ', ""),
+                };
+                match marker {
+                    ' ' => {
+                        old_count = old_count.saturating_sub(1);
+                        new_count = new_count.saturating_sub(1);
+                        hunk.lines.push(HunkLine::Context(text.to_string()));
+                    }
+                    '-' => {
+                        old_count = old_count.saturating_sub(1);
+                        hunk.lines.push(HunkLine::Remove(text.to_string()));
+                    }
+                    '+' => {
+                        new_count = new_count.saturating_sub(1);
+                        hunk.lines.push(HunkLine::Add(text.to_string()));
+                    }
+                    '\\' => {}
+                    _ => bail!("Unexpected line '{line}' in hunk '{}'", hunk.header),
+                }
+            }
+            // A trailing "\ No newline at end of file" belongs to the last line
+            lines.next_if(|line| line.starts_with('\\'));
+
+            file.hunks.push(hunk);
+        }
+        // Anything else, like `diff --git` or `index` lines, is ignored
+    }
+
+    if files.is_empty() {
+        bail!("The patch doesn't contain any file changes");
+    }
+    Ok(files)
+}
+
+/// Parses the path of a `---`/`+++` header, dropping timestamps and the `a/`
+/// and `b/` prefixes used by git.
+fn parse_path(header: &str) -> Option<PathBuf> {
+    let path = header.split('\t').next().unwrap_or_default().trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(PathBuf::from(path))
+}
+
+/// Parses `@@ -a,b +c,d @@` into the start line and the number of lines on
+/// both sides. Counts default to 1 when omitted.
+fn parse_hunk_header(header: &str) -> anyhow::Result<(usize, usize, usize)> {
+    let invalid = || format!("Invalid hunk header '{header}'");
+    let mut ranges = header
+        .trim_start_matches('@')
+        .split("@@")
+        .next()
+        .with_context(invalid)?
+        .split_whitespace();
+
+    let parse_range = |range: Option<&str>, sign: char| -> anyhow::Result<(usize, usize)> {
+        let range = range
+            .and_then(|range| range.strip_prefix(sign))
+            .with_context(invalid)?;
+        let (start, count) = range.split_once(',').unwrap_or((range, "1"));
+        Ok((
+            start.parse().with_context(invalid)?,
+            count.parse().with_context(invalid)?,
+        ))
+    };
+
+    let (old_start, old_count) = parse_range(ranges.next(), '-')?;
+    let (_, new_count) = parse_range(ranges.next(), '+')?;
+    Ok((old_start, old_count, new_count))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_git_diff() {
+        let fixture = "diff --git a/src/lib.rs b/src/lib.rs\n\
+            index 1234567..89abcde 100644\n\
+            --- a/src/lib.rs\n\
+            +++ b/src/lib.rs\n\
+            @@ -1,3 +1,3 @@\n \
+            fn main() {\n\
+            -    println!(\"hello\");\n\
+            +    println!(\"world\");\n \
+            }\n";
+
+        let actual = parse_unified_diff(fixture).unwrap();
+
+        let expected = vec![FileDiff {
+            old_path: Some(PathBuf::from("src/lib.rs")),
+            new_path: Some(PathBuf::from("src/lib.rs")),
+            hunks: vec![Hunk {
+                header: "@@ -1,3 +1,3 @@".to_string(),
+                old_start: 1,
+                lines: vec![
+                    HunkLine::Context("fn main() {".to_string()),
+                    HunkLine::Remove("    println!(\"hello\");".to_string()),
+                    HunkLine::Add("    println!(\"world\");".to_string()),
+                    HunkLine::Context("}".to_string()),
+                ],
+            }],
+        }];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_new_file() {
+        let fixture = "--- /dev/null\n+++ b/notes.md\n@@ -0,0 +1 @@\n+# Notes\n";
+
+        let actual = parse_unified_diff(fixture).unwrap();
+
+        let expected = vec![FileDiff {
+            old_path: None,
+            new_path: Some(PathBuf::from("notes.md")),
+            hunks: vec![Hunk {
+                header: "@@ -0,0 +1 @@".to_string(),
+                old_start: 0,
+                lines: vec![HunkLine::Add("# Notes".to_string())],
+            }],
+        }];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_truncated_hunk() {
+        let fixture = "--- a/a.txt\n+++ b/a.txt\n@@ -1,3 +1,3 @@\n one\n-two\n";
+
+        let actual = parse_unified_diff(fixture).is_err();
+
+        assert!(actual);
+    }
+}
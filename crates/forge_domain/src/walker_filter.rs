@@ -0,0 +1,34 @@
+use derive_setters::Setters;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Files of the workspace the agents are shown, to scope them to a part of a
+/// large repository or keep them out of vendored and generated directories.
+/// The paths ignored by the `.gitignore` and `.forgeignore` files are always
+/// left out.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, Setters, JsonSchema)]
+#[setters(strip_option, into)]
+pub struct WalkerFilter {
+    /// Maximum depth of the directories walked, taking precedence over
+    /// `max_walker_depth`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<usize>,
+
+    /// Size in bytes above which files are left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_size: Option<u64>,
+
+    /// Patterns of the only files to walk, written like the lines of a
+    /// `.gitignore`, eg: `services/api/`. Every file is walked when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+
+    /// Patterns of the files and directories to leave out, written like the
+    /// lines of a `.gitignore`, eg: `vendor/`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+
+    /// Whether to follow symbolic links. Defaults to false.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub follow_symlinks: Option<bool>,
+}
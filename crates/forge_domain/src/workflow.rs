@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use derive_setters::Setters;
 use lazy_static::lazy_static;
@@ -9,7 +9,12 @@ use serde_json::Value;
 
 use crate::temperature::Temperature;
 use crate::update::Update;
-use crate::{Agent, AgentId, Compact, MaxTokens, ModelId, TopK, TopP};
+use crate::{
+    Agent, AgentId, CommandPolicy, CommandRetry, Compact, Confirmations, CustomTool, Guardrails,
+    Hook, MaxTokens, McpServerConfig, ModelId, OpenApi, Pipeline, Plugin, Policy, Profile,
+    ProjectEnv, Redaction, RequestDump, Retention, Retrieval, Schedule, ToolName, ToolTruncation,
+    TopK, TopP, WalkerFilter,
+};
 
 /// Configuration for a workflow that contains all settings
 /// required to initialize a workflow.
@@ -53,6 +58,26 @@ pub struct Workflow {
     #[merge(strategy = crate::merge::option)]
     pub max_walker_depth: Option<usize>,
 
+    /// Filters of the walk of the workspace whose files are shown to the
+    /// agents, taking precedence over `max_walker_depth`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub walker: Option<WalkerFilter>,
+
+    /// Glob patterns of the generated files of the workspace, besides the
+    /// lockfiles, minified assets and files marked as generated, which are
+    /// detected. Reading one in full returns a summary of it instead of its
+    /// content.
+    #[merge(strategy = crate::merge::vec::append)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub generated_files: Vec<String>,
+
+    /// Settings of the repo map of the workspace given to the agents at the
+    /// start of a conversation. No map is generated when not provided.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[merge(strategy = crate::merge::option)]
+    pub repo_map: Option<RepoMapConfig>,
+
     /// A set of custom rules that all agents should follow
     /// These rules will be applied in addition to each agent's individual rules
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -141,6 +166,153 @@ pub struct Workflow {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[merge(strategy = crate::merge::option)]
     pub compact: Option<Compact>,
+
+    /// Named provider and model profiles that can be switched between using
+    /// `--profile` or the `/profile` command
+    #[merge(strategy = crate::merge::hashmap)]
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub profiles: HashMap<String, Profile>,
+
+    /// Tools that run a shell command, defined without writing any code
+    #[merge(strategy = crate::merge::vec::unify_by_key)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom_tools: Vec<CustomTool>,
+
+    /// WASM plugins that provide additional tools
+    #[merge(strategy = crate::merge::vec::unify_by_key)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plugins: Vec<Plugin>,
+
+    /// OpenAPI documents whose operations are made available as tools
+    #[merge(strategy = crate::merge::vec::unify_by_key)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub openapi: Vec<OpenApi>,
+
+    /// MCP servers whose tools are made available, in addition to the ones of
+    /// the .mcp.json files, which take precedence
+    #[merge(strategy = std::collections::BTreeMap::extend)]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub mcp_servers: BTreeMap<String, McpServerConfig>,
+
+    /// Directories of the project, besides the current one, that MCP servers
+    /// are told they can work in. Relative paths are resolved against the
+    /// current directory.
+    #[merge(strategy = crate::merge::vec::append)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mcp_roots: Vec<String>,
+
+    /// Policies that allow, deny or ask for confirmation before tools run.
+    /// They are evaluated together with the policies in the permissions
+    /// file.
+    #[merge(strategy = crate::merge::vec::append)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub policies: Vec<Policy>,
+
+    /// Whether the file writes, file deletions, shell commands and network
+    /// calls of the agents run without asking, are refused or are confirmed
+    /// first, taking precedence over the policies for the classes it sets
+    #[merge(strategy = crate::merge::option)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<Confirmations>,
+
+    /// Shell commands or webhooks run before and after tool calls and turns,
+    /// able to block the action or pass a message on to the agent
+    #[merge(strategy = crate::merge::vec::append)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hooks: Vec<Hook>,
+
+    /// Paths, shell commands and content the agents are never allowed to
+    /// touch, run or send to the model, whatever the policies allow
+    #[merge(strategy = crate::merge::option)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guardrails: Option<Guardrails>,
+
+    /// Shell commands the agents may or may not run, refused with an error
+    /// telling the agent what it may run instead
+    #[merge(strategy = crate::merge::option)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command_policy: Option<CommandPolicy>,
+
+    /// Environment variables the shell commands run with, loaded from `.env`
+    /// files of the project or set here
+    #[merge(strategy = crate::merge::option)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<ProjectEnv>,
+
+    /// Shell commands run again when they fail, such as flaky integration
+    /// tests or installs over an unreliable network. The first one matching a
+    /// command applies.
+    #[merge(strategy = crate::merge::vec::append)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub command_retries: Vec<CommandRetry>,
+
+    /// Prompts run by `forge schedule` on a cron expression or when files of
+    /// the workspace change
+    #[merge(strategy = crate::merge::vec::append)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schedules: Vec<Schedule>,
+
+    /// Steps run one after the other by `forge run`, each by an agent on a
+    /// prompt built from the outputs of the steps before it
+    #[merge(strategy = crate::merge::vec::unify_by_key)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pipelines: Vec<Pipeline>,
+
+    /// Timeout in seconds for tool calls. If not specified, FORGE_TOOL_TIMEOUT
+    /// is used, which defaults to 300 seconds.
+    #[merge(strategy = crate::merge::option)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_timeout: Option<u64>,
+
+    /// Timeouts in seconds for individual tools, overriding `tool_timeout`
+    #[merge(strategy = crate::merge::hashmap)]
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tool_timeouts: HashMap<ToolName, u64>,
+
+    /// Truncation settings for the output of individual tools, overriding the
+    /// limits of the environment
+    #[merge(strategy = crate::merge::hashmap)]
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tool_truncation: HashMap<ToolName, ToolTruncation>,
+
+    /// Preview the changes of the tools that write files or run shell
+    /// commands instead of making them. Can be toggled for a session with the
+    /// `/dry-run` command.
+    #[merge(strategy = crate::merge::option)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dry_run: Option<bool>,
+
+    /// Redaction of secrets, such as API keys and tokens, from the output of
+    /// tools before it is sent to the model
+    #[merge(strategy = crate::merge::option)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redaction: Option<Redaction>,
+
+    /// Semantic retrieval of the workspace, which indexes the files with an
+    /// embedding model for the semantic search tool and attaches the parts
+    /// relevant to each message
+    #[merge(strategy = crate::merge::option)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retrieval: Option<Retrieval>,
+
+    /// Dumps of the requests sent to the provider and of the responses
+    /// streamed back, written to the requests directory under the base path
+    #[merge(strategy = crate::merge::option)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_dump: Option<RequestDump>,
+
+    /// Limits on the age and the disk usage of the sessions, request dumps
+    /// and snapshots stored under the base path
+    #[merge(strategy = crate::merge::option)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention: Option<Retention>,
+
+    /// Saves the conversations as sessions under the base path, so that they
+    /// can be listed and resumed. Off by default, as the sessions hold
+    /// everything the agents were sent and read, secrets included.
+    #[merge(strategy = crate::merge::option)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub save_sessions: Option<bool>,
 }
 
 lazy_static! {
@@ -178,6 +350,9 @@ impl Workflow {
             commands: Vec::new(),
             model: None,
             max_walker_depth: None,
+            walker: None,
+            generated_files: Vec::new(),
+            repo_map: None,
             custom_rules: None,
             temperature: None,
             top_p: None,
@@ -189,9 +364,49 @@ impl Workflow {
             max_tool_failure_per_turn: None,
             max_requests_per_turn: None,
             compact: None,
+            profiles: HashMap::new(),
+            custom_tools: Vec::new(),
+            plugins: Vec::new(),
+            openapi: Vec::new(),
+            mcp_servers: BTreeMap::new(),
+            mcp_roots: Vec::new(),
+            policies: Vec::new(),
+            confirmations: None,
+            hooks: Vec::new(),
+            guardrails: None,
+            command_policy: None,
+            env: None,
+            command_retries: Vec::new(),
+            schedules: Vec::new(),
+            pipelines: Vec::new(),
+            tool_timeout: None,
+            tool_timeouts: HashMap::new(),
+            tool_truncation: HashMap::new(),
+            dry_run: None,
+            redaction: None,
+            retrieval: None,
+            request_dump: None,
+            retention: None,
+            save_sessions: None,
         }
     }
 
+    /// Returns the timeout in seconds configured for the given tool, if any
+    pub fn timeout_for(&self, tool_name: &ToolName) -> Option<u64> {
+        self.tool_timeouts
+            .get(tool_name)
+            .copied()
+            .or(self.tool_timeout)
+    }
+
+    /// Returns the truncation settings configured for the given tool
+    pub fn truncation_for(&self, tool_name: &ToolName) -> ToolTruncation {
+        self.tool_truncation
+            .get(tool_name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
     fn find_agent(&self, id: &AgentId) -> Option<&Agent> {
         self.agents.iter().find(|a| a.id == *id)
     }
@@ -207,6 +422,7 @@ mod tests {
     use pretty_assertions::assert_eq;
 
     use super::*;
+    use crate::PipelineStep;
 
     #[test]
     fn test_workflow_new_creates_empty_workflow() {
@@ -228,6 +444,33 @@ mod tests {
         assert_eq!(actual.max_tokens, None);
         assert_eq!(actual.tool_supported, None);
         assert_eq!(actual.compact, None);
+        assert!(actual.profiles.is_empty());
+        assert!(actual.custom_tools.is_empty());
+        assert!(actual.plugins.is_empty());
+        assert!(actual.policies.is_empty());
+        assert_eq!(actual.tool_timeout, None);
+        assert!(actual.tool_timeouts.is_empty());
+        assert!(actual.tool_truncation.is_empty());
+        assert_eq!(actual.dry_run, None);
+    }
+
+    #[test]
+    fn test_timeout_for_prefers_tool_override() {
+        let fixture = Workflow::new()
+            .tool_timeout(60u64)
+            .tool_timeouts(HashMap::from([(
+                ToolName::new("forge_tool_process_shell"),
+                600,
+            )]));
+
+        let actual = (
+            fixture.timeout_for(&ToolName::new("forge_tool_process_shell")),
+            fixture.timeout_for(&ToolName::new("forge_tool_fs_read")),
+            Workflow::new().timeout_for(&ToolName::new("forge_tool_fs_read")),
+        );
+
+        let expected = (Some(600), Some(60), None);
+        assert_eq!(actual, expected);
     }
 
     #[test]
@@ -279,6 +522,62 @@ mod tests {
         // Assert
         assert_eq!(base.tool_supported, Some(true));
     }
+    #[test]
+    fn test_workflow_merge_profiles() {
+        // Fixture
+        let work = Profile::default().model(ModelId::new("work-model"));
+        let personal = Profile::default().model(ModelId::new("personal-model"));
+        let mut base = Workflow::new();
+        base.profiles.insert("work".to_string(), Profile::default());
+        let mut other = Workflow::new();
+        other.profiles.insert("work".to_string(), work.clone());
+        other
+            .profiles
+            .insert("personal".to_string(), personal.clone());
+
+        // Act
+        base.merge(other);
+
+        // Assert
+        let expected = HashMap::from([
+            ("work".to_string(), work),
+            ("personal".to_string(), personal),
+        ]);
+        assert_eq!(base.profiles, expected);
+    }
+
+    #[test]
+    fn test_workflow_merge_custom_tools() {
+        // Fixture
+        let lint = CustomTool::new("lint", "cargo clippy");
+        let test = CustomTool::new("test", "cargo test");
+        let mut base = Workflow::new().custom_tools(vec![lint, test.clone()]);
+        let lint = CustomTool::new("lint", "cargo clippy --all-targets");
+        let other = Workflow::new().custom_tools(vec![lint.clone()]);
+
+        // Act
+        base.merge(other);
+
+        // Assert
+        assert_eq!(base.custom_tools, vec![lint, test]);
+    }
+
+    #[test]
+    fn test_workflow_merge_pipelines() {
+        // Fixture
+        let release = Pipeline::new("release").steps(vec![PipelineStep::new("test", "Test")]);
+        let deploy = Pipeline::new("deploy");
+        let mut base = Workflow::new().pipelines(vec![release, deploy.clone()]);
+        let release = Pipeline::new("release").steps(vec![PipelineStep::new("bump", "Bump")]);
+        let other = Workflow::new().pipelines(vec![release.clone()]);
+
+        // Act
+        base.merge(other);
+
+        // Assert
+        assert_eq!(base.pipelines, vec![release, deploy]);
+    }
+
     #[test]
     fn test_workflow_merge_compact() {
         // Fixture
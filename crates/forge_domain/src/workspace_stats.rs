@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// Languages by the extensions of their files
+const LANGUAGES: &[(&str, &[&str])] = &[
+    ("Rust", &["rs"]),
+    ("Python", &["py", "pyi"]),
+    ("TypeScript", &["ts", "tsx", "mts", "cts"]),
+    ("JavaScript", &["js", "jsx", "mjs", "cjs"]),
+    ("Go", &["go"]),
+    ("Java", &["java"]),
+    ("Kotlin", &["kt", "kts"]),
+    ("Scala", &["scala", "sc"]),
+    ("Ruby", &["rb"]),
+    ("C", &["c", "h"]),
+    ("C++", &["cpp", "cc", "cxx", "hpp", "hh", "hxx"]),
+    ("C#", &["cs"]),
+    ("Swift", &["swift"]),
+    ("PHP", &["php"]),
+    ("Shell", &["sh", "bash", "zsh"]),
+    ("SQL", &["sql"]),
+    ("HTML", &["html", "htm"]),
+    ("CSS", &["css", "scss", "sass", "less"]),
+    ("Markdown", &["md", "mdx"]),
+    ("JSON", &["json"]),
+    ("YAML", &["yaml", "yml"]),
+    ("TOML", &["toml"]),
+];
+
+/// Language of the files whose extension isn't known
+const OTHER_LANGUAGE: &str = "Other";
+
+/// Text file of the workspace, as counted in its statistics
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceFile {
+    /// Path relative to the workspace
+    pub path: String,
+    /// Size in bytes
+    pub size: u64,
+    pub lines: u64,
+}
+
+/// Files and lines of the workspace written in a language
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LanguageStats {
+    pub language: String,
+    pub files: usize,
+    pub lines: u64,
+}
+
+/// Size of the workspace by language, counted over its text files that aren't
+/// ignored or generated
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceStats {
+    pub files: usize,
+    pub lines: u64,
+    /// Languages by the lines written in them, most first
+    pub languages: Vec<LanguageStats>,
+    /// Files by size, largest first
+    pub largest_files: Vec<WorkspaceFile>,
+    /// Whether some files were left out for being too large or too many, the
+    /// counts being lower than the actual ones
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+impl WorkspaceStats {
+    /// Counts the files and lines of each language and keeps the `largest`
+    /// biggest files
+    pub fn new(mut files: Vec<WorkspaceFile>, largest: usize) -> Self {
+        let mut languages: BTreeMap<&str, LanguageStats> = BTreeMap::new();
+        for file in &files {
+            let language = language(Path::new(&file.path)).unwrap_or(OTHER_LANGUAGE);
+            let stats = languages.entry(language).or_insert_with(|| LanguageStats {
+                language: language.to_string(),
+                files: 0,
+                lines: 0,
+            });
+            stats.files += 1;
+            stats.lines += file.lines;
+        }
+        let mut languages: Vec<_> = languages.into_values().collect();
+        languages.sort_by(|a, b| b.lines.cmp(&a.lines).then(b.files.cmp(&a.files)));
+
+        files.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+        let total_files = files.len();
+        let lines = files.iter().map(|file| file.lines).sum();
+        files.truncate(largest);
+
+        Self {
+            files: total_files,
+            lines,
+            languages,
+            largest_files: files,
+            truncated: false,
+        }
+    }
+}
+
+/// Language of a file from its extension
+fn language(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|(_, extensions)| extensions.contains(&extension.as_str()))
+        .map(|(language, _)| *language)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn file(path: &str, size: u64, lines: u64) -> WorkspaceFile {
+        WorkspaceFile { path: path.to_string(), size, lines }
+    }
+
+    #[test]
+    fn test_workspace_stats() {
+        let fixture = vec![
+            file("src/main.rs", 1200, 40),
+            file("src/parser.rs", 9000, 300),
+            file("web/app.ts", 4000, 120),
+            file("README.md", 2000, 60),
+            file("Makefile", 300, 12),
+        ];
+
+        let actual = WorkspaceStats::new(fixture, 2);
+
+        let expected = WorkspaceStats {
+            files: 5,
+            lines: 532,
+            languages: vec![
+                LanguageStats { language: "Rust".to_string(), files: 2, lines: 340 },
+                LanguageStats { language: "TypeScript".to_string(), files: 1, lines: 120 },
+                LanguageStats { language: "Markdown".to_string(), files: 1, lines: 60 },
+                LanguageStats { language: "Other".to_string(), files: 1, lines: 12 },
+            ],
+            largest_files: vec![
+                file("src/parser.rs", 9000, 300),
+                file("web/app.ts", 4000, 120),
+            ],
+            truncated: false,
+        };
+        assert_eq!(actual, expected);
+    }
+}
@@ -23,10 +23,17 @@ impl ForgeEnvironmentInfra {
         Self { restricted, cwd }
     }
 
-    /// Get path to appropriate shell based on platform and mode
+    /// Get path to appropriate shell based on platform and mode. On Windows,
+    /// PowerShell is preferred over cmd as it quotes arguments and handles
+    /// UTF-8 reliably.
     fn get_shell_path(&self) -> String {
         if cfg!(target_os = "windows") {
-            std::env::var("COMSPEC").unwrap_or("cmd.exe".to_string())
+            ["pwsh.exe", "powershell.exe"]
+                .into_iter()
+                .find_map(find_in_path)
+                .map(|path| path.display().to_string())
+                .or_else(|| std::env::var("COMSPEC").ok())
+                .unwrap_or("cmd.exe".to_string())
         } else if self.restricted {
             // Default to rbash in restricted mode
             "/bin/rbash".to_string()
@@ -68,6 +75,9 @@ impl ForgeEnvironmentInfra {
             stdout_max_prefix_length: 200,
             stdout_max_suffix_length: 200,
             tool_timeout: parse_env::<u64>("FORGE_TOOL_TIMEOUT").unwrap_or(300),
+            max_concurrent_commands: parse_env::<usize>("FORGE_MAX_CONCURRENT_COMMANDS")
+                .unwrap_or(4)
+                .max(1),
             stdout_max_line_length: parse_env::<usize>("FORGE_STDOUT_MAX_LINE_LENGTH")
                 .unwrap_or(2000),
             http: resolve_http_config(),
@@ -116,6 +126,13 @@ fn parse_env<T: FromStr>(name: &str) -> Option<T> {
         .and_then(|var| T::from_str(var).ok())
 }
 
+/// Finds a program in the directories of PATH
+fn find_in_path(program: &str) -> Option<PathBuf> {
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|path| path.is_file())
+}
+
 /// Resolves retry configuration from environment variables or returns defaults
 fn resolve_retry_config() -> RetryConfig {
     let mut config = RetryConfig::default();
@@ -1,5 +1,11 @@
+use std::time::Duration;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("Unsupported MCP response: {0}")]
     UnsupportedMcpResponse(&'static str),
+    #[error("MCP server didn't start within {0:?}")]
+    McpStartupTimeout(Duration),
+    #[error("MCP server didn't respond to a ping within {0:?}")]
+    McpPingTimeout(Duration),
 }
@@ -1,12 +1,24 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex as SyncMutex};
+use std::time::SystemTime;
 
-use forge_domain::{CommandOutput, Environment};
+use anyhow::{Context, bail};
+use forge_domain::{
+    BackgroundProcess, CommandOutput, CommandSettings, Environment, ProcessLogs, ProjectEnv,
+    ShellKind,
+};
 use forge_services::CommandInfra;
-use tokio::io::AsyncReadExt;
-use tokio::process::Command;
-use tokio::sync::Mutex;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::{ChildStdin, Command};
+use tokio::sync::{Mutex, OwnedMutexGuard, Semaphore};
+
+use crate::process::BackgroundProcesses;
+
+/// Variable holding the file the shell writes its last directory to
+const CWD_FILE_VAR: &str = "FORGE_CWD_FILE";
 
 /// Service for executing shell commands
 #[derive(Clone, Debug)]
@@ -14,24 +26,88 @@ pub struct ForgeCommandExecutorService {
     restricted: bool,
     env: Environment,
 
-    // Mutex to ensure that only one command is executed at a time
-    ready: Arc<Mutex<()>>,
+    // Whether the output of the commands is written to the terminal while
+    // they run
+    echo: bool,
+
+    // Keeps the outputs of the commands running at the same time apart
+    terminal: Arc<Terminal>,
+
+    // Bounds the number of commands running at the same time
+    permits: Arc<Semaphore>,
+
+    // Commands in the same working directory run one at a time, as they may
+    // write the same files or take the same build locks. Keyed by the
+    // canonical path, so that the spellings of a directory share its lock.
+    dirs: Arc<SyncMutex<HashMap<PathBuf, Arc<Mutex<()>>>>>,
+
+    // Commands started in the background, which don't hold up the others
+    processes: Arc<BackgroundProcesses>,
+
+    // Variables of the `.env` files of the project, parsed again only once a
+    // file changes
+    env_files: Arc<SyncMutex<HashMap<PathBuf, (SystemTime, BTreeMap<String, String>)>>>,
 }
 
 impl ForgeCommandExecutorService {
     pub fn new(restricted: bool, env: Environment) -> Self {
-        Self { restricted, env, ready: Arc::new(Mutex::new(())) }
+        Self {
+            restricted,
+            echo: true,
+            terminal: Default::default(),
+            permits: Arc::new(Semaphore::new(env.max_concurrent_commands)),
+            dirs: Default::default(),
+            env,
+            processes: Default::default(),
+            env_files: Default::default(),
+        }
     }
 
-    fn prepare_command(&self, command_str: &str, working_dir: &Path) -> Command {
+    /// Keeps the output of the commands off the terminal, for frontends that
+    /// draw the whole of it such as the TUI
+    pub fn without_echo(mut self) -> Self {
+        self.echo = false;
+        self
+    }
+
+    fn prepare_command(
+        &self,
+        command_str: &str,
+        working_dir: &Path,
+        shell: Option<ShellKind>,
+        cwd_file: Option<&Path>,
+        settings: &CommandSettings,
+    ) -> anyhow::Result<Command> {
         // Create a basic command
         let is_windows = cfg!(target_os = "windows");
-        let shell = if self.restricted && !is_windows {
-            "rbash"
+        let restricted = self.restricted && !is_windows;
+        if let Some(shell) = shell
+            && restricted
+            && shell != ShellKind::Bash
+        {
+            bail!(
+                "The {shell} shell can't be used in restricted mode, run forge with '-u' to use it"
+            );
+        }
+        // The shell of the user is run with the arguments of its kind, such
+        // as `-NoProfile -Command` for PowerShell
+        let user_shell = ShellKind::from_program(&self.env.shell);
+        let program = match shell {
+            _ if restricted => "rbash",
+            // The shell of the user when it's of the kind asked for, e.g.
+            // Windows PowerShell when pwsh isn't installed
+            Some(shell) if Some(shell) != user_shell => shell.program(),
+            _ => self.env.shell.as_str(),
+        };
+        let kind = if restricted {
+            shell
         } else {
-            self.env.shell.as_str()
+            shell.or(user_shell)
         };
-        let mut command = Command::new(shell);
+        // Refuse the command before anything is started, reading it the way
+        // its shell does
+        settings.policy.check(command_str, kind)?;
+        let mut command = Command::new(program);
 
         // Core color settings for general commands
         command
@@ -50,13 +126,58 @@ impl ForgeCommandExecutorService {
         // Other common tools
         command.env("GREP_OPTIONS", "--color=always"); // GNU grep
 
-        let parameter = if is_windows { "/C" } else { "-c" };
-        command.arg(parameter);
+        // Variables of the project, which can override the ones above
+        command.envs(self.project_vars(&settings.env));
 
+        let args: &[&str] = match kind {
+            Some(kind) => kind.args(),
+            None if is_windows => &["/C"],
+            None => &["-c"],
+        };
+        command.args(args);
+
+        // Windows consoles read and write a legacy code page by default,
+        // mangling the characters outside of it
+        let shell_command = match kind.and_then(utf8_prelude) {
+            Some(prelude) if is_windows => Cow::Owned(format!("{prelude}{command_str}")),
+            _ => Cow::Borrowed(command_str),
+        };
+
+        // POSIX shells write the directory the command ends in to the file
+        // when they exit, so that the next command can start there. rbash
+        // can't redirect output.
+        let tracks_cwd = !restricted
+            && match kind {
+                Some(ShellKind::Bash | ShellKind::Zsh) => true,
+                None => !is_windows,
+                Some(_) => false,
+            };
+        let shell_command = match cwd_file {
+            Some(file) if tracks_cwd => {
+                command.env(CWD_FILE_VAR, file);
+                Cow::Owned(format!(
+                    "trap 'printf %s \"$PWD\" > \"${CWD_FILE_VAR}\"' EXIT; {shell_command}"
+                ))
+            }
+            _ => shell_command,
+        };
+
+        // cmd parses its command line itself, so the command is passed as is
+        // within the quotes `/S` strips
         #[cfg(windows)]
-        command.raw_arg(command_str);
+        match kind {
+            Some(ShellKind::Cmd) => {
+                command.raw_arg(format!("\"{shell_command}\""));
+            }
+            None => {
+                command.raw_arg(shell_command.as_ref());
+            }
+            Some(_) => {
+                command.arg(shell_command.as_ref());
+            }
+        }
         #[cfg(unix)]
-        command.arg(command_str);
+        command.arg(shell_command.as_ref());
 
         tracing::info!(command = command_str, "Executing command");
 
@@ -65,56 +186,286 @@ impl ForgeCommandExecutorService {
         // Set the working directory
         command.current_dir(working_dir);
 
-        // Configure the command for output
+        // Configure the command for output. Nothing is read from the terminal,
+        // which the TUI owns or which there is none of when running headless.
         command
-            .stdin(std::process::Stdio::inherit())
+            .stdin(std::process::Stdio::null())
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped());
 
-        command
+        Ok(command)
     }
 
-    /// Internal method to execute commands with streaming to console
+    /// Loads the variables of the project from its `.env` files, relative to
+    /// the working directory of forge, and forge.yaml. A file that is missing
+    /// or can't be parsed is skipped, so that the commands still run.
+    fn project_vars(&self, env: &ProjectEnv) -> BTreeMap<String, String> {
+        let mut loaded = BTreeMap::new();
+        for file in &env.files {
+            loaded.extend(self.env_file(&self.env.cwd.join(file)));
+        }
+        env.resolve(loaded, |name| std::env::var(name).ok())
+    }
+
+    /// Returns the variables of the `.env` file, parsed once until it changes
+    fn env_file(&self, path: &Path) -> BTreeMap<String, String> {
+        let modified = match std::fs::metadata(path).and_then(|metadata| metadata.modified()) {
+            Ok(modified) => modified,
+            Err(error) => {
+                tracing::warn!(path = %path.display(), %error, "Skipping the env file");
+                return BTreeMap::new();
+            }
+        };
+
+        let mut env_files = self.env_files.lock().unwrap();
+        if let Some((parsed, vars)) = env_files.get(path)
+            && *parsed == modified
+        {
+            return vars.clone();
+        }
+        let vars = parse_env_file(path).unwrap_or_else(|error| {
+            tracing::warn!(path = %path.display(), error = format!("{error:#}"), "Skipping the env file");
+            BTreeMap::new()
+        });
+        env_files.insert(path.to_path_buf(), (modified, vars.clone()));
+        vars
+    }
+
+    /// Waits until no other command runs in the working directory. The locks
+    /// no command holds or waits for are dropped on the way.
+    async fn lock_dir(&self, working_dir: &Path) -> OwnedMutexGuard<()> {
+        let key = working_dir
+            .canonicalize()
+            .unwrap_or_else(|_| working_dir.to_path_buf());
+        let lock = {
+            let mut dirs = self.dirs.lock().unwrap();
+            dirs.retain(|_, lock| Arc::strong_count(lock) > 1);
+            dirs.entry(key).or_default().clone()
+        };
+        lock.lock_owned().await
+    }
+
+    /// Internal method to execute commands with streaming to console. A
+    /// command failing with an exit code its retry policy covers runs again
+    /// after the backoff, keeping the output of each failed attempt.
     async fn execute_command_internal(
         &self,
         command: String,
         working_dir: &Path,
+        shell: Option<ShellKind>,
+        stdin: Option<String>,
+        settings: &CommandSettings,
+    ) -> anyhow::Result<CommandOutput> {
+        let retry = settings.retry_for(&command)?;
+        let mut failed_attempts = Vec::new();
+        let mut attempt = 1;
+        loop {
+            let output = self
+                .execute_attempt(command.clone(), working_dir, shell, stdin.clone(), settings)
+                .await?;
+            match retry {
+                Some(retry) if retry.retries(attempt, output.exit_code) => {
+                    let backoff = retry.backoff(attempt);
+                    tracing::warn!(
+                        command = %command,
+                        attempt,
+                        exit_code = ?output.exit_code,
+                        ?backoff,
+                        "Retrying failed command"
+                    );
+                    failed_attempts.push(output);
+                    // The directory isn't locked while waiting, so the other
+                    // commands in it can run in the meantime
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                _ => return Ok(CommandOutput { failed_attempts, ..output }),
+            }
+        }
+    }
+
+    /// Runs the command once
+    async fn execute_attempt(
+        &self,
+        command: String,
+        working_dir: &Path,
+        shell: Option<ShellKind>,
+        stdin: Option<String>,
+        settings: &CommandSettings,
     ) -> anyhow::Result<CommandOutput> {
-        let ready = self.ready.lock().await;
+        // The directory is locked first so that waiting on it doesn't take up
+        // a permit that a command in another directory could use
+        let dir = self.lock_dir(working_dir).await;
+        let permit = self.permits.acquire().await?;
+
+        let cwd_file = tempfile::NamedTempFile::new()?;
+        let mut prepared_command = self.prepare_command(
+            &command,
+            working_dir,
+            shell,
+            Some(cwd_file.path()),
+            settings,
+        )?;
+        if stdin.is_some() {
+            prepared_command.stdin(std::process::Stdio::piped());
+        }
 
-        let mut prepared_command = self.prepare_command(&command, working_dir);
+        // Only one command streams its output to the terminal at a time, the
+        // others write theirs once they finish
+        let streaming = self.echo.then(|| self.terminal.stream()).flatten();
 
         // Spawn the command
         let mut child = prepared_command.spawn()?;
 
+        let stdin_pipe = child.stdin.take();
         let mut stdout_pipe = child.stdout.take();
         let mut stderr_pipe = child.stderr.take();
 
-        // Stream the output of the command to stdout and stderr concurrently
-        let (status, stdout_buffer, stderr_buffer) = tokio::try_join!(
+        // Write the input while streaming the output of the command to stdout
+        // and stderr, as the command may not read all of its input before
+        // writing
+        let (status, stdout_buffer, stderr_buffer, _) = tokio::try_join!(
             child.wait(),
-            stream(&mut stdout_pipe, io::stdout()),
-            stream(&mut stderr_pipe, io::stderr())
+            stream(&mut stdout_pipe, streaming.is_some().then(io::stdout)),
+            stream(&mut stderr_pipe, streaming.is_some().then(io::stderr)),
+            feed(stdin_pipe, stdin)
         )?;
 
         // Drop happens after `try_join` due to <https://github.com/tokio-rs/tokio/issues/4309>
         drop(stdout_pipe);
         drop(stderr_pipe);
-        drop(ready);
+        drop(permit);
+        drop(dir);
+
+        if self.echo && streaming.is_none() {
+            self.terminal
+                .write(stdout_buffer.clone(), stderr_buffer.clone());
+        }
+        drop(streaming);
 
         Ok(CommandOutput {
             stdout: String::from_utf8_lossy(&stdout_buffer).into_owned(),
             stderr: String::from_utf8_lossy(&stderr_buffer).into_owned(),
             exit_code: status.code(),
+            failed_attempts: Vec::new(),
+            cwd: moved_cwd(cwd_file.path(), working_dir),
             command,
         })
     }
 }
 
-/// reads the output from A and writes it to W
+/// Terminal the output of the commands is written to. One command at a time
+/// streams its output as it runs, while the commands running alongside it
+/// write theirs in one piece once both they and the streaming one are done, so
+/// that the outputs don't interleave.
+#[derive(Debug, Default)]
+struct Terminal {
+    state: SyncMutex<TerminalState>,
+}
+
+#[derive(Debug, Default)]
+struct TerminalState {
+    streaming: bool,
+    // Outputs of the commands that finished while another one was streaming
+    pending: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl Terminal {
+    /// Takes the terminal for a command to stream its output to, unless
+    /// another command does already
+    fn stream(&self) -> Option<Streaming<'_>> {
+        let mut state = self.state.lock().unwrap();
+        if state.streaming {
+            return None;
+        }
+        state.streaming = true;
+        Some(Streaming(self))
+    }
+
+    /// Writes the output of a command that finished, or keeps it until the
+    /// command streaming its output is done
+    fn write(&self, stdout: Vec<u8>, stderr: Vec<u8>) {
+        let mut state = self.state.lock().unwrap();
+        if state.streaming {
+            state.pending.push((stdout, stderr));
+        } else {
+            write_output(&stdout, &stderr);
+        }
+    }
+}
+
+/// Frees the terminal once the command streaming to it is done, writing the
+/// outputs kept meanwhile
+struct Streaming<'a>(&'a Terminal);
+
+impl Drop for Streaming<'_> {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock().unwrap();
+        for (stdout, stderr) in std::mem::take(&mut state.pending) {
+            write_output(&stdout, &stderr);
+        }
+        state.streaming = false;
+    }
+}
+
+fn write_output(stdout: &[u8], stderr: &[u8]) {
+    // The output was kept already, failing to show it isn't an error of the
+    // command
+    let _ = io::stdout()
+        .write_all(stdout)
+        .and_then(|_| io::stdout().flush());
+    let _ = io::stderr()
+        .write_all(stderr)
+        .and_then(|_| io::stderr().flush());
+}
+
+/// Directory the shell wrote to the file when it's another one than the one
+/// the command started in
+fn moved_cwd(cwd_file: &Path, working_dir: &Path) -> Option<PathBuf> {
+    let cwd = std::fs::read_to_string(cwd_file).ok()?;
+    if cwd.is_empty() {
+        return None;
+    }
+    let cwd = PathBuf::from(cwd);
+    let moved = match (cwd.canonicalize(), working_dir.canonicalize()) {
+        (Ok(cwd), Ok(working_dir)) => cwd != working_dir,
+        _ => cwd != working_dir,
+    };
+    moved.then_some(cwd)
+}
+
+/// Statement switching the shell to UTF-8, run before the command
+fn utf8_prelude(kind: ShellKind) -> Option<&'static str> {
+    match kind {
+        // Setting the encodings of the console fails when there's none, such
+        // as when forge runs headless
+        ShellKind::Pwsh => Some(
+            "try { [Console]::InputEncoding = [Console]::OutputEncoding = \
+             New-Object System.Text.UTF8Encoding $false } catch {}; \
+             $OutputEncoding = New-Object System.Text.UTF8Encoding $false; ",
+        ),
+        ShellKind::Cmd => Some("chcp 65001 >NUL 2>&1 & "),
+        ShellKind::Bash | ShellKind::Zsh | ShellKind::Fish => None,
+    }
+}
+
+/// writes the input to the stdin of a command and closes it, so that the
+/// command reads to the end of the input
+async fn feed(pipe: Option<ChildStdin>, input: Option<String>) -> io::Result<()> {
+    if let (Some(mut pipe), Some(input)) = (pipe, input) {
+        // A command may exit without reading all of its input
+        match pipe.write_all(input.as_bytes()).await {
+            Err(error) if error.kind() == io::ErrorKind::BrokenPipe => {}
+            result => result?,
+        }
+    }
+    Ok(())
+}
+
+/// reads the output from A and writes it to W, if any
 async fn stream<A: AsyncReadExt + Unpin, W: Write>(
     io: &mut Option<A>,
-    mut writer: W,
+    mut writer: Option<W>,
 ) -> io::Result<Vec<u8>> {
     let mut output = Vec::new();
     if let Some(io) = io.as_mut() {
@@ -124,9 +475,11 @@ async fn stream<A: AsyncReadExt + Unpin, W: Write>(
             if n == 0 {
                 break;
             }
-            writer.write_all(&buff[..n])?;
-            // note: flush is necessary else we get the cursor could not be found error.
-            writer.flush()?;
+            if let Some(writer) = writer.as_mut() {
+                writer.write_all(&buff[..n])?;
+                // note: flush is necessary else we get the cursor could not be found error.
+                writer.flush()?;
+            }
             output.extend_from_slice(&buff[..n]);
         }
     }
@@ -140,16 +493,24 @@ impl CommandInfra for ForgeCommandExecutorService {
         &self,
         command: String,
         working_dir: PathBuf,
+        shell: Option<ShellKind>,
+        stdin: Option<String>,
+        settings: &CommandSettings,
     ) -> anyhow::Result<CommandOutput> {
-        self.execute_command_internal(command, &working_dir).await
+        self.execute_command_internal(command, &working_dir, shell, stdin, settings)
+            .await
     }
 
     async fn execute_command_raw(
         &self,
         command: &str,
         working_dir: PathBuf,
+        env: &ProjectEnv,
     ) -> anyhow::Result<std::process::ExitStatus> {
-        let mut prepared_command = self.prepare_command(command, &working_dir);
+        // Commands typed by the user aren't subject to the policy of the agents
+        let settings = CommandSettings::default().env(env.clone());
+        let mut prepared_command =
+            self.prepare_command(command, &working_dir, None, None, &settings)?;
 
         // overwrite the stdin, stdout and stderr to inherit
         prepared_command
@@ -159,11 +520,47 @@ impl CommandInfra for ForgeCommandExecutorService {
 
         Ok(prepared_command.spawn()?.wait().await?)
     }
+
+    async fn start_process(
+        &self,
+        command: String,
+        working_dir: PathBuf,
+        settings: &CommandSettings,
+    ) -> anyhow::Result<BackgroundProcess> {
+        let prepared_command =
+            self.prepare_command(&command, &working_dir, None, None, settings)?;
+        self.processes
+            .start(prepared_command, command, working_dir)
+            .await
+    }
+
+    async fn stop_process(&self, id: u32) -> anyhow::Result<BackgroundProcess> {
+        self.processes.stop(id).await
+    }
+
+    async fn process_logs(&self, id: u32, lines: usize) -> anyhow::Result<ProcessLogs> {
+        self.processes.logs(id, lines).await
+    }
+
+    async fn list_processes(&self) -> anyhow::Result<Vec<BackgroundProcess>> {
+        Ok(self.processes.list().await)
+    }
+}
+
+/// Parses the variables of a `.env` file
+fn parse_env_file(path: &Path) -> anyhow::Result<BTreeMap<String, String>> {
+    dotenv::from_path_iter(path)
+        .with_context(|| format!("Failed to load the env file {}", path.display()))?
+        .map(|var| var.with_context(|| format!("Failed to parse the env file {}", path.display())))
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
 
+    use forge_domain::{
+        CommandPattern, CommandPolicy, CommandRetry, CommandViolation, ProcessStatus, RetryPolicy,
+    };
     use pretty_assertions::assert_eq;
     use reqwest::Url;
 
@@ -193,6 +590,7 @@ mod tests {
             stdout_max_line_length: 2000,
             http: Default::default(),
             tool_timeout: 300,
+            max_concurrent_commands: 4,
             max_file_size: 10_000_000,
             forge_api_url: Url::parse("http://forgecode.dev/api").unwrap(),
         }
@@ -205,7 +603,13 @@ mod tests {
         let dir = ".";
 
         let actual = fixture
-            .execute_command(cmd.to_string(), PathBuf::new().join(dir))
+            .execute_command(
+                cmd.to_string(),
+                PathBuf::new().join(dir),
+                None,
+                None,
+                &CommandSettings::default(),
+            )
             .await
             .unwrap();
 
@@ -214,6 +618,8 @@ mod tests {
             stderr: "".to_string(),
             command: "echo \"hello world\"".into(),
             exit_code: Some(0),
+            failed_attempts: Vec::new(),
+            cwd: None,
         };
 
         if cfg!(target_os = "windows") {
@@ -224,4 +630,405 @@ mod tests {
         assert_eq!(actual.stderr, expected.stderr);
         assert_eq!(actual.success(), expected.success());
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_executor_with_shell() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env());
+
+        let actual = fixture
+            .execute_command(
+                "echo $0".to_string(),
+                PathBuf::from("."),
+                Some(ShellKind::Bash),
+                None,
+                &CommandSettings::default(),
+            )
+            .await
+            .unwrap();
+
+        let expected = "bash";
+        assert_eq!(actual.stdout.trim(), expected);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_restricted_mode_refuses_other_shells() {
+        let fixture = ForgeCommandExecutorService::new(true, test_env());
+
+        let actual = fixture
+            .execute_command(
+                "echo hello".to_string(),
+                PathBuf::from("."),
+                Some(ShellKind::Zsh),
+                None,
+                &CommandSettings::default(),
+            )
+            .await
+            .unwrap_err()
+            .to_string();
+
+        let expected =
+            "The zsh shell can't be used in restricted mode, run forge with '-u' to use it";
+        assert_eq!(actual, expected);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_commands_in_different_dirs_run_concurrently() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env());
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["server", "client"] {
+            std::fs::create_dir(dir.path().join(name)).unwrap();
+        }
+
+        // The first command only succeeds if the second runs while it waits
+        let (waiting, signalling) = tokio::join!(
+            fixture.execute_command(
+                "for i in $(seq 500); do [ -f ../client/done ] && exit 0; sleep 0.01; done; exit 1"
+                    .to_string(),
+                dir.path().join("server"),
+                None,
+                None,
+                &CommandSettings::default(),
+            ),
+            fixture.execute_command(
+                "touch done".to_string(),
+                dir.path().join("client"),
+                None,
+                None,
+                &CommandSettings::default(),
+            )
+        );
+
+        let actual = (waiting.unwrap().exit_code, signalling.unwrap().exit_code);
+
+        let expected = (Some(0), Some(0));
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_dir_locks_are_shared_by_spellings_and_pruned() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env());
+        let dir = tempfile::tempdir().unwrap();
+        let other = tempfile::tempdir().unwrap();
+
+        let guard = fixture.lock_dir(dir.path()).await;
+        let waits = tokio::time::timeout(
+            std::time::Duration::from_millis(50),
+            fixture.lock_dir(&dir.path().join(".")),
+        )
+        .await
+        .is_err();
+        drop(guard);
+        let _other = fixture.lock_dir(other.path()).await;
+
+        let actual = (waits, fixture.dirs.lock().unwrap().len());
+        let expected = (true, 1);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_terminal_keeps_output_until_streaming_is_done() {
+        let fixture = Terminal::default();
+
+        let streaming = fixture.stream();
+        let second = fixture.stream();
+        fixture.write(b"done".to_vec(), Vec::new());
+        let kept = fixture.state.lock().unwrap().pending.len();
+        drop(streaming);
+        let written = fixture.state.lock().unwrap().pending.is_empty();
+
+        let actual = (second.is_none(), kept, written, fixture.stream().is_some());
+        let expected = (true, 1, true, true);
+        assert_eq!(actual, expected);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_executor_with_stdin() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env());
+
+        let actual = fixture
+            .execute_command(
+                "sort -r".to_string(),
+                PathBuf::from("."),
+                None,
+                Some("apple\nbanana\ncherry\n".to_string()),
+                &CommandSettings::default(),
+            )
+            .await
+            .unwrap();
+
+        let expected = "cherry\nbanana\napple\n";
+        assert_eq!(actual.stdout, expected);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_executor_without_echo() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env()).without_echo();
+
+        let actual = fixture
+            .execute_command(
+                "printf '\\033[32mok\\033[0m'; echo failed >&2".to_string(),
+                PathBuf::from("."),
+                None,
+                None,
+                &CommandSettings::default(),
+            )
+            .await
+            .unwrap();
+
+        let expected = ("\u{1b}[32mok\u{1b}[0m", "failed\n");
+        assert_eq!((actual.stdout.as_str(), actual.stderr.as_str()), expected);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_executor_without_stdin_reads_nothing() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env());
+
+        let actual = fixture
+            .execute_command(
+                "cat".to_string(),
+                PathBuf::from("."),
+                None,
+                None,
+                &CommandSettings::default(),
+            )
+            .await
+            .unwrap();
+
+        let expected = "";
+        assert_eq!(actual.stdout, expected);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_executor_reports_moved_cwd() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env());
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path().canonicalize().unwrap();
+        std::fs::create_dir(dir.join("web")).unwrap();
+
+        let mut actual = Vec::new();
+        for command in ["cd web && ls", "ls"] {
+            let output = fixture
+                .execute_command(
+                    command.to_string(),
+                    dir.clone(),
+                    None,
+                    None,
+                    &CommandSettings::default(),
+                )
+                .await
+                .unwrap();
+            actual.push(output.cwd);
+        }
+
+        let expected = vec![Some(dir.join("web")), None];
+        assert_eq!(actual, expected);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_executor_with_project_env() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env());
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join(".env.test");
+        std::fs::write(&file, "DB_HOST=localhost:5432\n").unwrap();
+        let env = ProjectEnv::default()
+            .files(vec![file.display().to_string()])
+            .vars(BTreeMap::from([(
+                "DATABASE_URL".to_string(),
+                "postgres://${DB_HOST}/app_test".to_string(),
+            )]));
+
+        let actual = fixture
+            .execute_command(
+                "echo $DATABASE_URL".to_string(),
+                dir.path().to_path_buf(),
+                None,
+                None,
+                &CommandSettings::default().env(env),
+            )
+            .await
+            .unwrap();
+
+        let expected = "postgres://localhost:5432/app_test";
+        assert_eq!(actual.stdout.trim(), expected);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_executor_skips_broken_env_files() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env());
+        let dir = tempfile::tempdir().unwrap();
+        let malformed = dir.path().join(".env.local");
+        std::fs::write(&malformed, "NOT A VARIABLE\n").unwrap();
+        let env = ProjectEnv::default()
+            .files(vec![
+                dir.path().join(".env.missing").display().to_string(),
+                malformed.display().to_string(),
+            ])
+            .vars(BTreeMap::from([(
+                "APP_ENV".to_string(),
+                "test".to_string(),
+            )]));
+
+        let actual = fixture
+            .execute_command(
+                "echo $APP_ENV".to_string(),
+                dir.path().to_path_buf(),
+                None,
+                None,
+                &CommandSettings::default().env(env),
+            )
+            .await
+            .unwrap();
+
+        let expected = "test";
+        assert_eq!(actual.stdout.trim(), expected);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_executor_retries_failed_command() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env()).without_echo();
+        let dir = tempfile::tempdir().unwrap();
+        let retry = RetryPolicy::default().backoff_ms(10u64).exit_codes(vec![3]);
+
+        // Fails the first two times it runs
+        let actual = fixture
+            .execute_command(
+                "echo run >> runs; test $(wc -l < runs) -ge 3 || { echo flaky >&2; exit 3; }"
+                    .to_string(),
+                dir.path().to_path_buf(),
+                None,
+                None,
+                &CommandSettings::default().retry(retry),
+            )
+            .await
+            .unwrap();
+
+        let expected = (
+            Some(0),
+            vec![
+                (Some(3), "flaky\n".to_string()),
+                (Some(3), "flaky\n".to_string()),
+            ],
+        );
+        assert_eq!(
+            (
+                actual.exit_code,
+                actual
+                    .failed_attempts
+                    .into_iter()
+                    .map(|attempt| (attempt.exit_code, attempt.stderr))
+                    .collect::<Vec<_>>()
+            ),
+            expected
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_executor_gives_up_after_max_attempts() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env()).without_echo();
+        let retry = CommandRetry {
+            command: CommandPattern::Prefix("exit".to_string()),
+            retry: RetryPolicy::default().max_attempts(2u32).backoff_ms(10u64),
+        };
+
+        let actual = fixture
+            .execute_command(
+                "exit 1".to_string(),
+                PathBuf::from("."),
+                None,
+                None,
+                &CommandSettings::default().retries(vec![retry]),
+            )
+            .await
+            .unwrap();
+
+        let expected = (Some(1), 1);
+        assert_eq!((actual.exit_code, actual.failed_attempts.len()), expected);
+    }
+
+    #[tokio::test]
+    async fn test_command_policy_refuses_before_running() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env());
+        let policy =
+            CommandPolicy::default().deny(vec![CommandPattern::Prefix("touch".to_string())]);
+        let dir = tempfile::tempdir().unwrap();
+
+        let actual = fixture
+            .execute_command(
+                "echo ok && touch created".to_string(),
+                dir.path().to_path_buf(),
+                None,
+                None,
+                &CommandSettings::default().policy(policy),
+            )
+            .await
+            .unwrap_err()
+            .downcast::<CommandViolation>()
+            .unwrap();
+
+        let expected = CommandViolation::Denied {
+            command: "touch created".to_string(),
+            pattern: CommandPattern::Prefix("touch".to_string()),
+        };
+        assert_eq!(actual, expected);
+        assert!(!dir.path().join("created").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_background_process_logs() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env());
+        let process = fixture
+            .start_process(
+                "echo started; echo failed >&2; sleep 30".to_string(),
+                PathBuf::from("."),
+                &CommandSettings::default(),
+            )
+            .await
+            .unwrap();
+
+        // The output is captured while the process keeps running
+        let mut actual = fixture.process_logs(process.id, 10).await.unwrap();
+        while actual.stdout.is_empty() || actual.stderr.is_empty() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            actual = fixture.process_logs(process.id, 10).await.unwrap();
+        }
+        fixture.stop_process(process.id).await.unwrap();
+
+        let expected = ProcessLogs {
+            process,
+            stdout: "started".to_string(),
+            stderr: "failed".to_string(),
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stop_background_process() {
+        let fixture = ForgeCommandExecutorService::new(false, test_env());
+        let process = fixture
+            .start_process(
+                "sleep 30".to_string(),
+                PathBuf::from("."),
+                &CommandSettings::default(),
+            )
+            .await
+            .unwrap();
+
+        let actual = fixture.stop_process(process.id).await.unwrap().status;
+
+        let expected = ProcessStatus::Exited(None);
+        assert_eq!(actual, expected);
+    }
 }
@@ -1,18 +1,23 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::process::ExitStatus;
 use std::sync::Arc;
 
 use bytes::Bytes;
-use forge_domain::{CommandOutput, Environment, McpServerConfig};
+use forge_domain::{
+    BackgroundProcess, CommandOutput, CommandSettings, Environment, McpServerConfig, Plugin,
+    ProcessLogs, ProjectEnv, ShellKind, ToolDefinition, ToolName, ToolOutput,
+};
 use forge_fs::FileInfo as FileInfoData;
 use forge_services::{
     CommandInfra, DirectoryReaderInfra, EnvironmentInfra, FileDirectoryInfra, FileInfoInfra,
-    FileReaderInfra, FileRemoverInfra, FileWriterInfra, HttpInfra, McpServerInfra, SnapshotInfra,
-    UserInfra, WalkerInfra,
+    FileLock, FileReaderInfra, FileRemoverInfra, FileWriterInfra, HttpInfra, McpServerInfra,
+    OAuthRedirectInfra, PluginInfra, SnapshotInfra, UserInfra, WalkerInfra,
 };
 use reqwest::header::HeaderMap;
 use reqwest::{Response, Url};
 use reqwest_eventsource::EventSource;
+use tokio::sync::oneshot;
 
 use crate::env::ForgeEnvironmentInfra;
 use crate::executor::ForgeCommandExecutorService;
@@ -27,7 +32,9 @@ use crate::http::ForgeHttpInfra;
 use crate::inquire::ForgeInquire;
 use crate::mcp_client::ForgeMcpClient;
 use crate::mcp_server::ForgeMcpServer;
+use crate::oauth_redirect::ForgeOAuthRedirect;
 use crate::walker::ForgeWalkerService;
+use crate::wasm_plugin::ForgeWasmPlugins;
 
 #[derive(Clone)]
 pub struct ForgeInfra {
@@ -44,8 +51,10 @@ pub struct ForgeInfra {
     command_executor_service: Arc<ForgeCommandExecutorService>,
     inquire_service: Arc<ForgeInquire>,
     mcp_server: ForgeMcpServer,
+    oauth_redirect: ForgeOAuthRedirect,
     walker_service: Arc<ForgeWalkerService>,
     http_service: Arc<ForgeHttpInfra>,
+    plugins: Arc<ForgeWasmPlugins>,
 }
 
 impl ForgeInfra {
@@ -71,10 +80,20 @@ impl ForgeInfra {
             )),
             inquire_service: Arc::new(ForgeInquire::new()),
             mcp_server: ForgeMcpServer,
+            oauth_redirect: ForgeOAuthRedirect,
             walker_service: Arc::new(ForgeWalkerService::new()),
             http_service,
+            plugins: Arc::new(ForgeWasmPlugins::new(env.cwd.clone())),
         }
     }
+
+    /// Keeps the output of the shell commands off the terminal, for frontends
+    /// that draw the whole of it such as the TUI
+    pub fn without_echo(mut self) -> Self {
+        self.command_executor_service =
+            Arc::new(Arc::unwrap_or_clone(self.command_executor_service).without_echo());
+        self
+    }
 }
 
 impl EnvironmentInfra for ForgeInfra {
@@ -122,11 +141,23 @@ impl FileWriterInfra for ForgeInfra {
             .await
     }
 
+    async fn append(&self, path: &Path, contents: Bytes) -> anyhow::Result<()> {
+        self.file_write_service.append(path, contents).await
+    }
+
+    async fn replace(&self, path: &Path, contents: Bytes) -> anyhow::Result<()> {
+        self.file_write_service.replace(path, contents).await
+    }
+
     async fn write_temp(&self, prefix: &str, ext: &str, content: &str) -> anyhow::Result<PathBuf> {
         self.file_write_service
             .write_temp(prefix, ext, content)
             .await
     }
+
+    async fn lock(&self, path: &Path) -> anyhow::Result<FileLock> {
+        self.file_write_service.lock(path).await
+    }
 }
 
 #[async_trait::async_trait]
@@ -157,6 +188,36 @@ impl SnapshotInfra for ForgeInfra {
     async fn undo_snapshot(&self, file_path: &Path) -> anyhow::Result<()> {
         self.file_snapshot_service.undo_snapshot(file_path).await
     }
+
+    async fn list_snapshots(&self) -> anyhow::Result<Vec<forge_snaps::Snapshot>> {
+        self.file_snapshot_service.list_snapshots().await
+    }
+
+    async fn undo_last_snapshot(&self) -> anyhow::Result<forge_snaps::Snapshot> {
+        self.file_snapshot_service.undo_last_snapshot().await
+    }
+
+    async fn restore_snapshot(
+        &self,
+        id: &forge_snaps::SnapshotId,
+    ) -> anyhow::Result<forge_snaps::Snapshot> {
+        self.file_snapshot_service.restore_snapshot(id).await
+    }
+
+    async fn snapshot_content(
+        &self,
+        snapshot: &forge_snaps::Snapshot,
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        self.file_snapshot_service.snapshot_content(snapshot).await
+    }
+
+    async fn snapshot_usage(&self) -> anyhow::Result<Vec<(forge_snaps::Snapshot, u64)>> {
+        self.file_snapshot_service.snapshot_usage().await
+    }
+
+    async fn remove_snapshots(&self, ids: &[forge_snaps::SnapshotId]) -> anyhow::Result<()> {
+        self.file_snapshot_service.remove_snapshots(ids).await
+    }
 }
 
 #[async_trait::async_trait]
@@ -179,9 +240,12 @@ impl CommandInfra for ForgeInfra {
         &self,
         command: String,
         working_dir: PathBuf,
+        shell: Option<ShellKind>,
+        stdin: Option<String>,
+        settings: &CommandSettings,
     ) -> anyhow::Result<CommandOutput> {
         self.command_executor_service
-            .execute_command(command, working_dir)
+            .execute_command(command, working_dir, shell, stdin, settings)
             .await
     }
 
@@ -189,11 +253,35 @@ impl CommandInfra for ForgeInfra {
         &self,
         command: &str,
         working_dir: PathBuf,
+        env: &ProjectEnv,
     ) -> anyhow::Result<ExitStatus> {
         self.command_executor_service
-            .execute_command_raw(command, working_dir)
+            .execute_command_raw(command, working_dir, env)
+            .await
+    }
+
+    async fn start_process(
+        &self,
+        command: String,
+        working_dir: PathBuf,
+        settings: &CommandSettings,
+    ) -> anyhow::Result<BackgroundProcess> {
+        self.command_executor_service
+            .start_process(command, working_dir, settings)
             .await
     }
+
+    async fn stop_process(&self, id: u32) -> anyhow::Result<BackgroundProcess> {
+        self.command_executor_service.stop_process(id).await
+    }
+
+    async fn process_logs(&self, id: u32, lines: usize) -> anyhow::Result<ProcessLogs> {
+        self.command_executor_service.process_logs(id, lines).await
+    }
+
+    async fn list_processes(&self) -> anyhow::Result<Vec<BackgroundProcess>> {
+        self.command_executor_service.list_processes().await
+    }
 }
 
 #[async_trait::async_trait]
@@ -228,6 +316,15 @@ impl McpServerInfra for ForgeInfra {
     }
 }
 
+#[async_trait::async_trait]
+impl OAuthRedirectInfra for ForgeInfra {
+    async fn listen_redirect(
+        &self,
+    ) -> anyhow::Result<(Url, oneshot::Receiver<BTreeMap<String, String>>)> {
+        self.oauth_redirect.listen_redirect().await
+    }
+}
+
 #[async_trait::async_trait]
 impl WalkerInfra for ForgeInfra {
     async fn walk(&self, config: forge_app::Walker) -> anyhow::Result<Vec<forge_app::WalkedFile>> {
@@ -241,8 +338,13 @@ impl HttpInfra for ForgeInfra {
         self.http_service.get(url, headers).await
     }
 
-    async fn post(&self, url: &Url, body: Bytes) -> anyhow::Result<Response> {
-        self.http_service.post(url, body).await
+    async fn post(
+        &self,
+        url: &Url,
+        headers: Option<HeaderMap>,
+        body: Bytes,
+    ) -> anyhow::Result<Response> {
+        self.http_service.post(url, headers, body).await
     }
 
     async fn delete(&self, url: &Url) -> anyhow::Result<Response> {
@@ -269,3 +371,19 @@ impl DirectoryReaderInfra for ForgeInfra {
             .await
     }
 }
+
+#[async_trait::async_trait]
+impl PluginInfra for ForgeInfra {
+    async fn plugin_tools(&self, plugin: &Plugin) -> anyhow::Result<Vec<ToolDefinition>> {
+        self.plugins.plugin_tools(plugin).await
+    }
+
+    async fn call_plugin(
+        &self,
+        plugin: &Plugin,
+        tool_name: &ToolName,
+        input: serde_json::Value,
+    ) -> anyhow::Result<ToolOutput> {
+        self.plugins.call_plugin(plugin, tool_name, input).await
+    }
+}
@@ -4,7 +4,7 @@ use std::sync::Arc;
 use anyhow::Result;
 use forge_domain::Environment;
 use forge_services::SnapshotInfra;
-use forge_snaps::Snapshot;
+use forge_snaps::{Snapshot, SnapshotId};
 
 pub struct ForgeFileSnapshotService {
     inner: Arc<forge_snaps::SnapshotService>,
@@ -13,7 +13,10 @@ pub struct ForgeFileSnapshotService {
 impl ForgeFileSnapshotService {
     pub fn new(env: Environment) -> Self {
         Self {
-            inner: Arc::new(forge_snaps::SnapshotService::new(env.snapshot_path())),
+            inner: Arc::new(forge_snaps::SnapshotService::new(
+                env.snapshot_path(),
+                env.cwd.clone(),
+            )),
         }
     }
 }
@@ -29,4 +32,29 @@ impl SnapshotInfra for ForgeFileSnapshotService {
     async fn undo_snapshot(&self, file_path: &Path) -> Result<()> {
         self.inner.undo_snapshot(file_path.to_path_buf()).await
     }
+
+    // Listing
+    async fn list_snapshots(&self) -> Result<Vec<Snapshot>> {
+        self.inner.list().await
+    }
+
+    async fn undo_last_snapshot(&self) -> Result<Snapshot> {
+        self.inner.undo_last().await
+    }
+
+    async fn restore_snapshot(&self, id: &SnapshotId) -> Result<Snapshot> {
+        self.inner.restore(id).await
+    }
+
+    async fn snapshot_content(&self, snapshot: &Snapshot) -> Result<Option<Vec<u8>>> {
+        self.inner.content(snapshot).await
+    }
+
+    async fn snapshot_usage(&self) -> Result<Vec<(Snapshot, u64)>> {
+        self.inner.usage().await
+    }
+
+    async fn remove_snapshots(&self, ids: &[SnapshotId]) -> Result<()> {
+        self.inner.remove(ids).await
+    }
 }
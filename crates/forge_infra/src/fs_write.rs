@@ -2,7 +2,7 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use bytes::Bytes;
-use forge_services::{FileWriterInfra, SnapshotInfra};
+use forge_services::{FileLock, FileWriterInfra, SnapshotInfra};
 
 pub struct ForgeFileWriteService<S> {
     snaps: Arc<S>,
@@ -34,13 +34,36 @@ impl<S: SnapshotInfra> FileWriterInfra for ForgeFileWriteService<S> {
         capture_snapshot: bool,
     ) -> anyhow::Result<()> {
         self.create_parent_dirs(path).await?;
-        if forge_fs::ForgeFS::exists(path) && capture_snapshot {
+        // Files that don't exist yet are captured too, so that creating them
+        // can be undone
+        if capture_snapshot {
             let _ = self.snaps.create_snapshot(path).await?;
         }
 
         Ok(forge_fs::ForgeFS::write(path, contents.to_vec()).await?)
     }
 
+    async fn append(&self, path: &Path, contents: Bytes) -> anyhow::Result<()> {
+        self.create_parent_dirs(path).await?;
+        forge_fs::ForgeFS::append(path, contents).await
+    }
+
+    async fn replace(&self, path: &Path, contents: Bytes) -> anyhow::Result<()> {
+        self.create_parent_dirs(path).await?;
+        let path = path.to_path_buf();
+        // The temporary file is created in the same directory, as a rename
+        // can't move a file to another file system
+        tokio::task::spawn_blocking(move || {
+            let dir = path.parent().unwrap_or(Path::new("."));
+            let mut file = tempfile::NamedTempFile::new_in(dir)?;
+            std::io::Write::write_all(&mut file, &contents)?;
+            file.as_file().sync_all()?;
+            file.persist(&path)?;
+            anyhow::Ok(())
+        })
+        .await?
+    }
+
     async fn write_temp(&self, prefix: &str, ext: &str, content: &str) -> anyhow::Result<PathBuf> {
         let path = tempfile::Builder::new()
             .disable_cleanup(true)
@@ -55,13 +78,34 @@ impl<S: SnapshotInfra> FileWriterInfra for ForgeFileWriteService<S> {
 
         Ok(path)
     }
+
+    async fn lock(&self, path: &Path) -> anyhow::Result<FileLock> {
+        // A file of its own is locked, as the file itself may be replaced by
+        // a rename
+        let mut lock_path = path.as_os_str().to_owned();
+        lock_path.push(".lock");
+        let lock_path = PathBuf::from(lock_path);
+        self.create_parent_dirs(&lock_path).await?;
+
+        tokio::task::spawn_blocking(move || {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&lock_path)?;
+            file.lock()?;
+            Ok(FileLock::new(file))
+        })
+        .await?
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
 
-    use forge_snaps::Snapshot;
+    use forge_snaps::{Snapshot, SnapshotId};
+    use pretty_assertions::assert_eq;
     use tempfile::tempdir;
 
     use super::*;
@@ -75,12 +119,37 @@ mod tests {
                 id: Default::default(),
                 timestamp: Default::default(),
                 path: "".to_string(),
+                hash: None,
             })
         }
 
         async fn undo_snapshot(&self, _path: &Path) -> anyhow::Result<()> {
             Ok(())
         }
+
+        async fn list_snapshots(&self) -> anyhow::Result<Vec<Snapshot>> {
+            Ok(Vec::new())
+        }
+
+        async fn undo_last_snapshot(&self) -> anyhow::Result<Snapshot> {
+            anyhow::bail!("No snapshots found")
+        }
+
+        async fn restore_snapshot(&self, _id: &SnapshotId) -> anyhow::Result<Snapshot> {
+            anyhow::bail!("No snapshots found")
+        }
+
+        async fn snapshot_content(&self, _: &Snapshot) -> anyhow::Result<Option<Vec<u8>>> {
+            Ok(None)
+        }
+
+        async fn snapshot_usage(&self) -> anyhow::Result<Vec<(Snapshot, u64)>> {
+            Ok(Vec::new())
+        }
+
+        async fn remove_snapshots(&self, _ids: &[SnapshotId]) -> anyhow::Result<()> {
+            Ok(())
+        }
     }
 
     fn create_test_service() -> ForgeFileWriteService<MockSnapshotService> {
@@ -109,4 +178,21 @@ mod tests {
         assert!(actual.is_ok());
         assert!(nested_file_path.parent().unwrap().exists());
     }
+
+    #[tokio::test]
+    async fn test_replace_leaves_only_the_file() {
+        let temp_dir = tempdir().unwrap();
+        let service = create_test_service();
+        let path = temp_dir.path().join(".forge").join("tasks.json");
+
+        service.replace(&path, Bytes::from("old")).await.unwrap();
+        service.replace(&path, Bytes::from("new")).await.unwrap();
+
+        let actual = (
+            std::fs::read_to_string(&path).unwrap(),
+            std::fs::read_dir(path.parent().unwrap()).unwrap().count(),
+        );
+        let expected = ("new".to_string(), 1);
+        assert_eq!(actual, expected);
+    }
 }
@@ -13,8 +13,12 @@ mod fs_write;
 mod http;
 mod inquire;
 mod mcp_client;
+mod mcp_http;
 mod mcp_server;
+mod oauth_redirect;
+mod process;
 mod walker;
+mod wasm_plugin;
 
 pub use executor::ForgeCommandExecutorService;
 pub use forge_infra::*;
@@ -1,44 +1,109 @@
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::future::Future;
+use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 use backon::{ExponentialBuilder, Retryable};
-use forge_domain::{Image, McpServerConfig, ToolDefinition, ToolName, ToolOutput};
+use forge_domain::{
+    Image, McpHttpServer, McpHttpTransport, McpPrompt, McpPromptArgument, McpServerConfig,
+    ToolDefinition, ToolName, ToolOutput,
+};
 use forge_services::McpClientInfra;
-use rmcp::model::{CallToolRequestParam, ClientInfo, Implementation, InitializeRequestParam};
+use reqwest::Url;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use rmcp::model::{
+    CallToolRequestParam, ClientCapabilities, ClientInfo, ClientRequest, GetPromptRequestParam,
+    Implementation, ListRootsResult, PingRequest, PromptMessageContent, ResourceContents, Root,
+};
 use rmcp::schemars::schema::RootSchema;
-use rmcp::service::RunningService;
-use rmcp::transport::TokioChildProcess;
-use rmcp::{RoleClient, ServiceExt};
+use rmcp::service::{Peer, RequestContext, RunningService};
+use rmcp::transport::{SseTransport, TokioChildProcess};
+use rmcp::{ClientHandler, Error as McpError, RoleClient, ServiceExt};
 use serde_json::Value;
 use tokio::process::Command;
 
 use crate::error::Error;
+use crate::mcp_http::streamable_http;
 
 const VERSION: &str = match option_env!("APP_VERSION") {
     Some(val) => val,
     None => env!("CARGO_PKG_VERSION"),
 };
 
-type RmcpClient = RunningService<RoleClient, InitializeRequestParam>;
+/// Time a server has to start and complete the initialization
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Time a server has to answer a ping
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+
+type RmcpClient = RunningService<RoleClient, ForgeClientHandler>;
+
+/// Answers the requests the server makes to forge
+#[derive(Clone)]
+struct ForgeClientHandler {
+    roots: Arc<RwLock<Vec<PathBuf>>>,
+}
+
+impl ClientHandler for ForgeClientHandler {
+    fn list_roots(
+        &self,
+        _context: RequestContext<RoleClient>,
+    ) -> impl Future<Output = Result<ListRootsResult, McpError>> + Send + '_ {
+        let roots = self
+            .roots
+            .read()
+            .unwrap()
+            .iter()
+            .filter_map(|path| {
+                Some(Root {
+                    uri: Url::from_directory_path(path).ok()?.to_string(),
+                    name: path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string()),
+                })
+            })
+            .collect();
+        std::future::ready(Ok(ListRootsResult { roots }))
+    }
+
+    fn get_peer(&self) -> Option<Peer<RoleClient>> {
+        None
+    }
+
+    fn set_peer(&mut self, _peer: Peer<RoleClient>) {}
+
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo {
+            protocol_version: Default::default(),
+            capabilities: ClientCapabilities::builder()
+                .enable_roots()
+                .enable_roots_list_changed()
+                .build(),
+            client_info: Implementation { name: "Forge".to_string(), version: VERSION.to_string() },
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct ForgeMcpClient {
     client: Arc<RwLock<Option<Arc<RmcpClient>>>>,
     config: McpServerConfig,
+    roots: Arc<RwLock<Vec<PathBuf>>>,
 }
 
 impl ForgeMcpClient {
     pub fn new(config: McpServerConfig) -> Self {
-        Self { client: Default::default(), config }
+        Self {
+            client: Default::default(),
+            config,
+            roots: Default::default(),
+        }
     }
 
-    fn client_info(&self) -> ClientInfo {
-        ClientInfo {
-            protocol_version: Default::default(),
-            capabilities: Default::default(),
-            client_info: Implementation { name: "Forge".to_string(), version: VERSION.to_string() },
-        }
+    fn handler(&self) -> ForgeClientHandler {
+        ForgeClientHandler { roots: self.roots.clone() }
     }
 
     /// Connects to the MCP server. If `force` is true, it will reconnect even
@@ -47,7 +112,9 @@ impl ForgeMcpClient {
         if let Some(client) = self.get_client() {
             Ok(client.clone())
         } else {
-            let client = self.create_connection().await?;
+            let client = tokio::time::timeout(STARTUP_TIMEOUT, self.create_connection())
+                .await
+                .map_err(|_| Error::McpStartupTimeout(STARTUP_TIMEOUT))??;
             self.set_client(client.clone());
             Ok(client.clone())
         }
@@ -75,19 +142,43 @@ impl ForgeMcpClient {
                 cmd.stdin(std::process::Stdio::inherit())
                     .stdout(std::process::Stdio::piped())
                     .stderr(std::process::Stdio::piped());
-                self.client_info()
+                self.handler()
                     .serve(TokioChildProcess::new(cmd.args(&stdio.args))?)
                     .await?
             }
-            McpServerConfig::Sse(sse) => {
-                let transport = rmcp::transport::SseTransport::start(sse.url.clone()).await?;
-                self.client_info().serve(transport).await?
+            McpServerConfig::Http(http) => {
+                let client = Self::http_client(http)?;
+                match http.transport.unwrap_or(McpHttpTransport::Sse) {
+                    McpHttpTransport::Sse => {
+                        let transport = SseTransport::start_with_client(&http.url, client).await?;
+                        self.handler().serve(transport).await?
+                    }
+                    McpHttpTransport::Http => {
+                        let transport = streamable_http(Url::parse(&http.url)?, client);
+                        self.handler().serve(transport).await?
+                    }
+                }
             }
         };
 
         Ok(Arc::new(client))
     }
 
+    /// Creates an HTTP client sending the headers of the server with every
+    /// request
+    fn http_client(http: &McpHttpServer) -> anyhow::Result<reqwest::Client> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in http.resolve_headers(|name| std::env::var(name).ok()) {
+            headers.insert(
+                HeaderName::try_from(name.as_str())?,
+                HeaderValue::try_from(value)?,
+            );
+        }
+        Ok(reqwest::Client::builder()
+            .default_headers(headers)
+            .build()?)
+    }
+
     async fn list(&self) -> anyhow::Result<Vec<ToolDefinition>> {
         let client = self.connect().await?;
         let tools = client.list_tools(None).await?;
@@ -146,6 +237,95 @@ impl ForgeMcpClient {
             .is_error(result.is_error.unwrap_or_default()))
     }
 
+    async fn prompts(&self) -> anyhow::Result<Vec<McpPrompt>> {
+        let client = self.connect().await?;
+        // Most servers only provide tools
+        if client.peer_info().capabilities.prompts.is_none() {
+            return Ok(vec![]);
+        }
+
+        let prompts = client.list_prompts(None).await?;
+        Ok(prompts
+            .prompts
+            .into_iter()
+            .map(|prompt| McpPrompt {
+                server: Default::default(),
+                name: prompt.name,
+                description: prompt.description,
+                arguments: prompt
+                    .arguments
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|argument| McpPromptArgument {
+                        name: argument.name,
+                        description: argument.description,
+                        required: argument.required.unwrap_or_default(),
+                    })
+                    .collect(),
+            })
+            .collect())
+    }
+
+    async fn prompt(
+        &self,
+        name: &str,
+        arguments: &BTreeMap<String, String>,
+    ) -> anyhow::Result<String> {
+        let client = self.connect().await?;
+        let result = client
+            .get_prompt(GetPromptRequestParam {
+                name: name.to_string(),
+                arguments: Some(
+                    arguments
+                        .iter()
+                        .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+                        .collect(),
+                ),
+            })
+            .await?;
+
+        // Images can't be part of a message typed by the user, so only the text is kept
+        let text = result
+            .messages
+            .into_iter()
+            .filter_map(|message| match message.content {
+                PromptMessageContent::Text { text } => Some(text),
+                PromptMessageContent::Resource { resource } => match resource.raw.resource {
+                    ResourceContents::TextResourceContents { text, .. } => Some(text),
+                    ResourceContents::BlobResourceContents { .. } => None,
+                },
+                PromptMessageContent::Image { .. } => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        Ok(text)
+    }
+
+    async fn ping(&self) -> anyhow::Result<()> {
+        let client = self.connect().await?;
+        let ping = client.send_request(ClientRequest::PingRequest(PingRequest {
+            method: Default::default(),
+        }));
+        let result = match tokio::time::timeout(PING_TIMEOUT, ping).await {
+            Ok(result) => result.map(|_| ()).map_err(anyhow::Error::from),
+            Err(_) => Err(Error::McpPingTimeout(PING_TIMEOUT).into()),
+        };
+        if result.is_err() {
+            // The connection is unusable, the next request starts the server again
+            self.client.write().unwrap().take();
+        }
+        result
+    }
+
+    async fn set_roots(&self, roots: Vec<PathBuf>) -> anyhow::Result<()> {
+        *self.roots.write().unwrap() = roots;
+        // A running server asks for the roots again once it's notified
+        if let Some(client) = self.get_client() {
+            client.notify_roots_list_changed().await?;
+        }
+        Ok(())
+    }
+
     async fn attempt_with_retry<T, F>(&self, call: impl Fn() -> F) -> anyhow::Result<T>
     where
         F: Future<Output = anyhow::Result<T>>,
@@ -181,4 +361,25 @@ impl McpClientInfra for ForgeMcpClient {
         self.attempt_with_retry(|| self.call(tool_name, &input))
             .await
     }
+
+    async fn prompts(&self) -> anyhow::Result<Vec<McpPrompt>> {
+        self.attempt_with_retry(|| self.prompts()).await
+    }
+
+    async fn prompt(
+        &self,
+        name: &str,
+        arguments: BTreeMap<String, String>,
+    ) -> anyhow::Result<String> {
+        self.attempt_with_retry(|| self.prompt(name, &arguments))
+            .await
+    }
+
+    async fn ping(&self) -> anyhow::Result<()> {
+        self.ping().await
+    }
+
+    async fn set_roots(&self, roots: Vec<PathBuf>) -> anyhow::Result<()> {
+        self.set_roots(roots).await
+    }
 }
@@ -0,0 +1,187 @@
+//! Client side of the Streamable HTTP transport of MCP, where every message of
+//! the client is posted to the url of the server, which answers with JSON or
+//! with a stream of server-sent events.
+
+use std::io;
+
+use futures::{Sink, Stream};
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::{Client, Response, Url};
+use rmcp::model::{ClientJsonRpcMessage, ServerJsonRpcMessage};
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Header carrying the id of the session assigned by the server
+const SESSION_ID: &str = "Mcp-Session-Id";
+
+/// Messages of the server that can be waiting to be read by the client
+const CHANNEL_SIZE: usize = 64;
+
+/// Returns the sink of the messages sent to the server at the url and the
+/// stream of the messages it sends back
+pub fn streamable_http(
+    url: Url,
+    client: Client,
+) -> (
+    impl Sink<ClientJsonRpcMessage, Error = io::Error> + Send + 'static,
+    impl Stream<Item = ServerJsonRpcMessage> + Send + 'static,
+) {
+    let (sender, receiver) = mpsc::channel(CHANNEL_SIZE);
+    let session = Session { url, client, sender, id: None };
+    let sink = futures::sink::unfold(session, |mut session, message| async move {
+        session.post(message).await?;
+        Ok::<_, io::Error>(session)
+    });
+    (sink, ReceiverStream::new(receiver))
+}
+
+struct Session {
+    url: Url,
+    client: Client,
+    sender: mpsc::Sender<ServerJsonRpcMessage>,
+    id: Option<String>,
+}
+
+impl Session {
+    async fn post(&mut self, message: ClientJsonRpcMessage) -> io::Result<()> {
+        let mut request = self
+            .client
+            .post(self.url.clone())
+            .header(ACCEPT, "application/json, text/event-stream")
+            .json(&message);
+        if let Some(id) = &self.id {
+            request = request.header(SESSION_ID, id);
+        }
+        let response = request
+            .send()
+            .await
+            .and_then(Response::error_for_status)
+            .map_err(io::Error::other)?;
+
+        if let Some(id) = response
+            .headers()
+            .get(SESSION_ID)
+            .and_then(|id| id.to_str().ok())
+        {
+            self.id = Some(id.to_string());
+        }
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        if content_type.starts_with("text/event-stream") {
+            // Read in the background, so that the client can answer the requests
+            // the server makes before it responds
+            tokio::spawn(forward_events(response, self.sender.clone()));
+        } else if content_type.starts_with("application/json") {
+            let body = response.bytes().await.map_err(io::Error::other)?;
+            for message in parse_messages(&body)? {
+                // The client stopped listening, there's no one to tell
+                let _ = self.sender.send(message).await;
+            }
+        }
+        // Notifications and responses are acknowledged without a body
+        Ok(())
+    }
+}
+
+async fn forward_events(mut response: Response, sender: mpsc::Sender<ServerJsonRpcMessage>) {
+    let mut events = EventParser::default();
+    loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => return,
+            Err(error) => {
+                tracing::warn!(error = ?error, "Failed to read the events of the MCP server");
+                return;
+            }
+        };
+        for data in events.push(&chunk) {
+            let messages = match parse_messages(data.as_bytes()) {
+                Ok(messages) => messages,
+                Err(error) => {
+                    tracing::warn!(error = ?error, "Invalid message from the MCP server");
+                    continue;
+                }
+            };
+            for message in messages {
+                if sender.send(message).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Parses a message, or a batch of messages
+fn parse_messages(body: &[u8]) -> io::Result<Vec<ServerJsonRpcMessage>> {
+    match serde_json::from_slice(body)? {
+        Value::Array(messages) => Ok(messages
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<Result<_, _>>()?),
+        message => Ok(vec![serde_json::from_value(message)?]),
+    }
+}
+
+/// Splits a stream of server-sent events into the data of the events
+#[derive(Default)]
+struct EventParser {
+    buffer: Vec<u8>,
+}
+
+impl EventParser {
+    /// Adds a chunk of the stream, returning the data of the events it
+    /// completes
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer
+            .extend(chunk.iter().copied().filter(|byte| *byte != b'\r'));
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.windows(2).position(|bytes| bytes == b"\n\n") {
+            let event = self.buffer.drain(..end + 2).collect::<Vec<_>>();
+            let data = String::from_utf8_lossy(&event)
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(|data| data.strip_prefix(' ').unwrap_or(data))
+                .collect::<Vec<_>>()
+                .join("\n");
+            if !data.is_empty() {
+                events.push(data);
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_events_split_across_chunks() {
+        let mut fixture = EventParser::default();
+
+        let mut actual = fixture.push(b"event: message\r\ndata: {\"a\":");
+        actual.extend(fixture.push(b"1}\r\n\r\n: keep-alive\n\ndata: [1,\ndata: 2]\n\n"));
+
+        let expected = vec!["{\"a\":1}".to_string(), "[1,\n2]".to_string()];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_batch_of_messages() {
+        let fixture = br#"[{"jsonrpc":"2.0","id":1,"result":{}},{"jsonrpc":"2.0","method":"notifications/tools/list_changed"}]"#;
+
+        let actual = parse_messages(fixture).unwrap().len();
+
+        let expected = 2;
+        assert_eq!(actual, expected);
+    }
+}
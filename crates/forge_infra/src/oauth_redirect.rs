@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+
+use forge_services::OAuthRedirectInfra;
+use reqwest::Url;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+/// Path of the redirect URI
+const CALLBACK_PATH: &str = "/callback";
+
+/// Longest request line that is read before the request is turned away
+const MAX_REQUEST_LINE: usize = 8192;
+
+const RECEIVED: &str = "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nConnection: close\r\n\r\n<html><body><p>Forge received the authorization, you can close this window.</p></body></html>";
+const NOT_FOUND: &str = "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+/// Serves the redirect URI of OAuth authorizations on a local port
+#[derive(Clone, Default)]
+pub struct ForgeOAuthRedirect;
+
+#[async_trait::async_trait]
+impl OAuthRedirectInfra for ForgeOAuthRedirect {
+    async fn listen_redirect(
+        &self,
+    ) -> anyhow::Result<(Url, oneshot::Receiver<BTreeMap<String, String>>)> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let redirect_uri = Url::parse(&format!(
+            "http://127.0.0.1:{}{CALLBACK_PATH}",
+            listener.local_addr()?.port()
+        ))?;
+
+        let (mut sender, receiver) = oneshot::channel();
+        tokio::spawn(async move {
+            // Other requests of the browser, such as the one of the favicon, are
+            // turned away until the redirect arrives or no one waits for it anymore
+            loop {
+                let stream = tokio::select! {
+                    accepted = listener.accept() => match accepted {
+                        Ok((stream, _)) => stream,
+                        Err(_) => return,
+                    },
+                    _ = sender.closed() => return,
+                };
+                if let Some(params) = receive(stream).await {
+                    let _ = sender.send(params);
+                    return;
+                }
+            }
+        });
+
+        Ok((redirect_uri, receiver))
+    }
+}
+
+/// Reads the request line of the connection, answering it, and returns the
+/// query parameters if it's the redirect
+async fn receive(mut stream: TcpStream) -> Option<BTreeMap<String, String>> {
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    while !request.windows(2).any(|bytes| bytes == b"\r\n") && request.len() < MAX_REQUEST_LINE {
+        let read = stream.read(&mut buffer).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+
+    let params = parse_redirect(&String::from_utf8_lossy(&request));
+    let response = if params.is_some() {
+        RECEIVED
+    } else {
+        NOT_FOUND
+    };
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+    params
+}
+
+/// Returns the query parameters of the request if it's made to the callback
+fn parse_redirect(request: &str) -> Option<BTreeMap<String, String>> {
+    let target = request
+        .lines()
+        .next()?
+        .strip_prefix("GET ")?
+        .split(' ')
+        .next()?;
+    let url = Url::parse(&format!("http://127.0.0.1{target}")).ok()?;
+    (url.path() == CALLBACK_PATH).then(|| url.query_pairs().into_owned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_redirect() {
+        let fixture = "GET /callback?code=abc%2F1&state=xyz HTTP/1.1\r\nHost: 127.0.0.1\r\n";
+
+        let actual = parse_redirect(fixture);
+
+        let expected = Some(BTreeMap::from([
+            ("code".to_string(), "abc/1".to_string()),
+            ("state".to_string(), "xyz".to_string()),
+        ]));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_redirect_ignores_other_paths() {
+        let fixture = "GET /favicon.ico HTTP/1.1\r\n";
+
+        let actual = parse_redirect(fixture);
+
+        assert_eq!(actual, None);
+    }
+
+    #[tokio::test]
+    async fn test_listen_redirect_receives_params() {
+        let (redirect_uri, receiver) = ForgeOAuthRedirect.listen_redirect().await.unwrap();
+        let address = format!(
+            "{}:{}",
+            redirect_uri.host_str().unwrap(),
+            redirect_uri.port().unwrap()
+        );
+
+        let mut favicon = TcpStream::connect(&address).await.unwrap();
+        favicon
+            .write_all(b"GET /favicon.ico HTTP/1.1\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        favicon.read_to_string(&mut response).await.unwrap();
+        let mut redirect = TcpStream::connect(&address).await.unwrap();
+        redirect
+            .write_all(b"GET /callback?code=abc&state=xyz HTTP/1.1\r\n\r\n")
+            .await
+            .unwrap();
+
+        let actual = receiver.await.unwrap();
+
+        let expected = BTreeMap::from([
+            ("code".to_string(), "abc".to_string()),
+            ("state".to_string(), "xyz".to_string()),
+        ]);
+        assert_eq!(actual, expected);
+    }
+}
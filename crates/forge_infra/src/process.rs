@@ -0,0 +1,377 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context;
+use forge_domain::{BackgroundProcess, ProcessLog, ProcessLogs, ProcessStatus};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{Notify, watch};
+
+/// Time given to a process to shut down once it's asked to, before it's killed
+const GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Longest line kept from the output of a process, the rest of the line being
+/// dropped
+const MAX_LINE_LENGTH: usize = 4096;
+
+/// Exited processes kept for their logs to be read, the oldest ones being
+/// forgotten first
+const MAX_EXITED: usize = 10;
+
+/// Process started in the background with the output it wrote so far
+struct Running {
+    process: BackgroundProcess,
+    /// Set by the task waiting for the process once it exits
+    status: watch::Receiver<ProcessStatus>,
+    /// Asks the task waiting for the process to kill it
+    kill: Arc<Notify>,
+    stdout: Arc<Mutex<ProcessLog>>,
+    stderr: Arc<Mutex<ProcessLog>>,
+    /// Closed once the process is forgotten, which kills its process group
+    _watchdog: Option<ChildStdin>,
+}
+
+impl Running {
+    fn process(&self) -> BackgroundProcess {
+        BackgroundProcess { status: *self.status.borrow(), ..self.process.clone() }
+    }
+
+    fn exited(&self) -> bool {
+        *self.status.borrow() != ProcessStatus::Running
+    }
+
+    /// Asks the process to shut down and kills it if it's still running
+    /// after the grace period
+    async fn stop(&self) -> anyhow::Result<BackgroundProcess> {
+        if self.exited() {
+            return Ok(self.process());
+        }
+
+        let mut status = self.status.clone();
+        self.signal("TERM").await;
+        if tokio::time::timeout(
+            GRACE_PERIOD,
+            status.wait_for(|s| *s != ProcessStatus::Running),
+        )
+        .await
+        .is_err()
+        {
+            self.signal("KILL").await;
+            self.kill.notify_one();
+        }
+        status
+            .wait_for(|s| *s != ProcessStatus::Running)
+            .await
+            .context("The process was lost while stopping it")?;
+        Ok(self.process())
+    }
+
+    /// Sends the signal to the process group of the process, so that the
+    /// processes started by the shell, such as the dev server itself, get it
+    /// too
+    #[cfg(unix)]
+    async fn signal(&self, signal: &str) {
+        if let Some(pid) = self.process.pid {
+            let _ = Command::new("kill")
+                .args([format!("-{signal}"), "--".to_string(), format!("-{pid}")])
+                .stderr(Stdio::null())
+                .status()
+                .await;
+        }
+    }
+
+    #[cfg(not(unix))]
+    async fn signal(&self, _signal: &str) {
+        self.kill.notify_one();
+    }
+}
+
+/// Keeps track of the commands started in the background, such as dev
+/// servers, so that they can be looked at and stopped in later turns
+#[derive(Default)]
+pub struct BackgroundProcesses {
+    // Never held while waiting, so that stopping a process doesn't hold up the
+    // other ones
+    running: Mutex<BTreeMap<u32, Arc<Running>>>,
+}
+
+impl BackgroundProcesses {
+    /// Spawns the command with its output captured and returns without
+    /// waiting for it
+    pub async fn start(
+        &self,
+        mut command: Command,
+        command_str: String,
+        cwd: PathBuf,
+    ) -> anyhow::Result<BackgroundProcess> {
+        // Background processes can't read from the terminal the user types in
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        command.process_group(0);
+
+        let mut child = command
+            .spawn()
+            .with_context(|| format!("Failed to start: {command_str}"))?;
+
+        let stdout = Arc::new(Mutex::new(ProcessLog::default()));
+        let stderr = Arc::new(Mutex::new(ProcessLog::default()));
+        if let Some(pipe) = child.stdout.take() {
+            tokio::spawn(capture(pipe, stdout.clone()));
+        }
+        if let Some(pipe) = child.stderr.take() {
+            tokio::spawn(capture(pipe, stderr.clone()));
+        }
+
+        let pid = child.id();
+        #[cfg(unix)]
+        let watchdog = pid.and_then(watchdog);
+        #[cfg(not(unix))]
+        let watchdog = None;
+
+        let kill = Arc::new(Notify::new());
+        let status = wait(child, kill.clone());
+
+        let mut running = self.running.lock().unwrap();
+        forget_exited(&mut running);
+        let id = running.keys().next_back().map_or(1, |id| id + 1);
+        let process = BackgroundProcess {
+            id,
+            command: command_str,
+            cwd,
+            pid,
+            status: ProcessStatus::Running,
+        };
+        running.insert(
+            id,
+            Arc::new(Running {
+                process: process.clone(),
+                status,
+                kill,
+                stdout,
+                stderr,
+                _watchdog: watchdog,
+            }),
+        );
+        Ok(process)
+    }
+
+    pub async fn stop(&self, id: u32) -> anyhow::Result<BackgroundProcess> {
+        self.get(id)?.stop().await
+    }
+
+    pub async fn logs(&self, id: u32, lines: usize) -> anyhow::Result<ProcessLogs> {
+        let process = self.get(id)?;
+        Ok(ProcessLogs {
+            process: process.process(),
+            stdout: process.stdout.lock().unwrap().tail(lines),
+            stderr: process.stderr.lock().unwrap().tail(lines),
+        })
+    }
+
+    pub async fn list(&self) -> Vec<BackgroundProcess> {
+        let mut running = self.running.lock().unwrap();
+        forget_exited(&mut running);
+        running.values().map(|process| process.process()).collect()
+    }
+
+    fn get(&self, id: u32) -> anyhow::Result<Arc<Running>> {
+        self.running
+            .lock()
+            .unwrap()
+            .get(&id)
+            .cloned()
+            .with_context(|| not_found(id))
+    }
+}
+
+fn not_found(id: u32) -> String {
+    format!("No background process with id {id}")
+}
+
+/// Forgets the oldest exited processes beyond the ones kept for their logs
+fn forget_exited(running: &mut BTreeMap<u32, Arc<Running>>) {
+    let exited = running
+        .iter()
+        .filter(|(_, process)| process.exited())
+        .map(|(id, _)| *id)
+        .collect::<Vec<_>>();
+    for id in &exited[..exited.len().saturating_sub(MAX_EXITED)] {
+        running.remove(id);
+    }
+}
+
+/// Waits for the process in a task of its own, killing it when asked to, and
+/// returns the status it updates once the process exits
+fn wait(mut child: Child, kill: Arc<Notify>) -> watch::Receiver<ProcessStatus> {
+    let (tx, rx) = watch::channel(ProcessStatus::Running);
+    tokio::spawn(async move {
+        let exited = tokio::select! {
+            status = child.wait() => Some(status),
+            _ = kill.notified() => None,
+        };
+        let status = match exited {
+            Some(status) => status,
+            None => {
+                let _ = child.start_kill();
+                child.wait().await
+            }
+        };
+        tx.send_replace(ProcessStatus::Exited(
+            status.ok().and_then(|status| status.code()),
+        ));
+    });
+    rx
+}
+
+/// Starts a shell that kills the process group of the process once its stdin
+/// is closed. It's closed when the process is forgotten or when forge exits,
+/// even when forge is killed, so that the processes started by the command
+/// don't keep running on their own.
+#[cfg(unix)]
+fn watchdog(pid: u32) -> Option<ChildStdin> {
+    let mut command = Command::new("sh");
+    command
+        .args([
+            "-c",
+            "trap '' HUP INT TERM; read line; kill -9 -$0",
+            &pid.to_string(),
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0);
+    match command.spawn() {
+        Ok(mut child) => child.stdin.take(),
+        Err(error) => {
+            tracing::warn!(error = ?error, "Failed to watch a background process");
+            None
+        }
+    }
+}
+
+/// Keeps the lines written to a stream of a process until it's closed. Lines
+/// end with `\n` or `\r`, which progress bars are redrawn with, and are cut at
+/// `MAX_LINE_LENGTH`.
+async fn capture(stream: impl AsyncRead + Unpin, log: Arc<Mutex<ProcessLog>>) {
+    let mut reader = BufReader::new(stream);
+    let mut line = Vec::new();
+    // The end of the previous line, to read `\r\n` as a single one
+    let mut previous = None;
+    loop {
+        let buf = match reader.fill_buf().await {
+            Ok(buf) if !buf.is_empty() => buf,
+            _ => break,
+        };
+        let end = buf.iter().position(|byte| matches!(byte, b'\n' | b'\r'));
+        let content = &buf[..end.unwrap_or(buf.len())];
+        let room = MAX_LINE_LENGTH.saturating_sub(line.len());
+        line.extend_from_slice(&content[..content.len().min(room)]);
+        let Some(end) = end else {
+            let read = buf.len();
+            reader.consume(read);
+            continue;
+        };
+
+        let terminator = buf[end];
+        reader.consume(end + 1);
+        if !(line.is_empty() && previous == Some(b'\r') && terminator == b'\n') {
+            log.lock().unwrap().push(String::from_utf8_lossy(&line));
+        }
+        previous = Some(terminator);
+        line.clear();
+    }
+    if !line.is_empty() {
+        log.lock().unwrap().push(String::from_utf8_lossy(&line));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    async fn captured(output: &[u8]) -> String {
+        let log = Arc::new(Mutex::new(ProcessLog::default()));
+        capture(output, log.clone()).await;
+        let log = log.lock().unwrap();
+        log.tail(10)
+    }
+
+    #[tokio::test]
+    async fn test_capture_splits_redrawn_lines() {
+        let actual = captured(b"compiling\r\n10%\r50%\r100%\nlistening on :3000").await;
+        let expected = "compiling\n10%\n50%\n100%\nlistening on :3000";
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_capture_cuts_long_lines() {
+        let fixture = format!("{}\nGET /", "=".repeat(MAX_LINE_LENGTH * 3));
+
+        let actual = captured(fixture.as_bytes()).await;
+
+        let expected = format!("{}\nGET /", "=".repeat(MAX_LINE_LENGTH));
+        assert_eq!(actual, expected);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_exited_processes_are_forgotten() {
+        let fixture = BackgroundProcesses::default();
+        for _ in 0..MAX_EXITED + 2 {
+            let mut command = Command::new("sh");
+            command.args(["-c", "exit 0"]);
+            let process = fixture
+                .start(command, "exit 0".to_string(), PathBuf::from("."))
+                .await
+                .unwrap();
+            fixture.stop(process.id).await.unwrap();
+        }
+
+        let actual = fixture
+            .list()
+            .await
+            .into_iter()
+            .map(|process| process.id)
+            .collect::<Vec<_>>();
+
+        let expected = (3..=MAX_EXITED as u32 + 2).collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stop_doesnt_hold_up_other_processes() {
+        let fixture = BackgroundProcesses::default();
+        let mut command = Command::new("sh");
+        command.args(["-c", "trap '' TERM; while true; do sleep 0.1; done"]);
+        let stubborn = fixture
+            .start(command, "stubborn".to_string(), PathBuf::from("."))
+            .await
+            .unwrap();
+
+        let stopping = fixture.stop(stubborn.id);
+        let listing = async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            fixture.list().await.len()
+        };
+        let actual = tokio::time::timeout(Duration::from_secs(1), async {
+            tokio::select! {
+                _ = stopping => None,
+                listed = listing => Some(listed),
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(actual, Some(1));
+    }
+}
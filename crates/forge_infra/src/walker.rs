@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use anyhow::Result;
 use forge_app::{WalkedFile, Walker};
 
@@ -9,18 +11,9 @@ impl ForgeWalkerService {
     }
 
     pub async fn walk(&self, config: Walker) -> Result<Vec<WalkedFile>> {
-        // Convert domain config to forge_walker config
-        let mut walker = if config.max_depth.is_none()
-            && config.max_breadth.is_none()
-            && config.max_file_size.is_none()
-            && config.max_files.is_none()
-            && config.max_total_size.is_none()
-            && !config.skip_binary
-        {
-            forge_walker::Walker::max_all()
-        } else {
-            forge_walker::Walker::min_all()
-        };
+        // Convert domain config to forge_walker config, the limits that aren't
+        // set being unlimited
+        let mut walker = forge_walker::Walker::max_all();
 
         walker = walker.cwd(config.cwd);
 
@@ -39,13 +32,28 @@ impl ForgeWalkerService {
         if let Some(total_size) = config.max_total_size {
             walker = walker.max_total_size(total_size);
         }
-        walker = walker.skip_binary(config.skip_binary);
+        walker = walker
+            .skip_binary(config.skip_binary)
+            .include(config.include)
+            .exclude(config.exclude)
+            .follow_symlinks(config.follow_symlinks);
+        if let Some(root) = config.root {
+            walker = walker.root(root);
+        }
 
         // Execute the walker and convert results
         let files = walker.get().await?;
         let walked_files = files
             .into_iter()
-            .map(|f| WalkedFile { path: f.path, file_name: f.file_name, size: f.size })
+            .map(|f| WalkedFile {
+                generated: (!f.is_dir())
+                    .then(|| config.generated.detect_path(Path::new(&f.path)))
+                    .flatten(),
+                path: f.path,
+                file_name: f.file_name,
+                size: f.size,
+                modified: f.modified,
+            })
             .collect();
 
         Ok(walked_files)
@@ -54,6 +62,7 @@ impl ForgeWalkerService {
 
 #[cfg(test)]
 mod tests {
+    use forge_app::domain::{GeneratedFiles, GeneratedKind};
     use pretty_assertions::assert_eq;
     use tempfile::tempdir;
 
@@ -88,4 +97,52 @@ mod tests {
         let file_count = actual.iter().filter(|f| !f.is_dir()).count();
         assert_eq!(file_count, expected);
     }
+
+    #[tokio::test]
+    async fn test_walker_service_limits_not_set_are_unlimited() {
+        let fixture = tempdir().unwrap();
+        for index in 0..12 {
+            std::fs::write(fixture.path().join(format!("{index}.txt")), "test").unwrap();
+        }
+
+        let service = ForgeWalkerService::new();
+        let config = Walker::unlimited()
+            .cwd(fixture.path().to_path_buf())
+            .max_file_size(1024u64);
+
+        let actual = service.walk(config).await.unwrap();
+
+        let expected = 12; // More than the default breadth of a directory
+        let file_count = actual.iter().filter(|f| !f.is_dir()).count();
+        assert_eq!(file_count, expected);
+    }
+
+    #[tokio::test]
+    async fn test_walker_service_marks_generated_files() {
+        let fixture = tempdir().unwrap();
+        std::fs::write(fixture.path().join("Cargo.lock"), "version = 3").unwrap();
+        std::fs::write(fixture.path().join("model.g.dart"), "class Model {}").unwrap();
+        std::fs::write(fixture.path().join("main.rs"), "fn main() {}").unwrap();
+
+        let service = ForgeWalkerService::new();
+        let config = Walker::unlimited()
+            .cwd(fixture.path().to_path_buf())
+            .generated(GeneratedFiles::new(&["*.g.dart".to_string()]));
+
+        let actual = service
+            .walk(config)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|file| !file.is_dir())
+            .map(|file| (file.path, file.generated))
+            .collect::<Vec<_>>();
+
+        let expected = vec![
+            ("Cargo.lock".to_string(), Some(GeneratedKind::Lockfile)),
+            ("main.rs".to_string(), None),
+            ("model.g.dart".to_string(), Some(GeneratedKind::Generated)),
+        ];
+        assert_eq!(actual, expected);
+    }
 }
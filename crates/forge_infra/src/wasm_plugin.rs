@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::path::{Component as PathComponent, Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context;
+use forge_domain::{Plugin, ToolDefinition, ToolName, ToolOutput};
+use forge_services::PluginInfra;
+use tokio::sync::{Mutex, OnceCell};
+use wasmtime::component::{Component, Linker, ResourceTable};
+use wasmtime::{Config, Engine, Store, StoreLimits, StoreLimitsBuilder};
+use wasmtime_wasi::{DirPerms, FilePerms, WasiCtx, WasiCtxBuilder, WasiView};
+
+mod bindings {
+    wasmtime::component::bindgen!({
+        path: "wit/tool.wit",
+        world: "plugin",
+        async: true,
+    });
+}
+
+/// How often the epoch of the engine is incremented
+const EPOCH_TICK: Duration = Duration::from_millis(100);
+
+/// Time a plugin call may run before it is interrupted, in epoch ticks
+const MAX_CALL_TICKS: u64 = 300;
+
+/// Memory a plugin instance may use
+const MAX_MEMORY: usize = 256 * 1024 * 1024;
+
+struct PluginState {
+    ctx: WasiCtx,
+    table: ResourceTable,
+    limits: StoreLimits,
+}
+
+impl WasiView for PluginState {
+    fn table(&mut self) -> &mut ResourceTable {
+        &mut self.table
+    }
+
+    fn ctx(&mut self) -> &mut WasiCtx {
+        &mut self.ctx
+    }
+}
+
+/// Runs WASM components implementing the `forge:tool` interface. Every call
+/// gets a fresh instance that can only access the capabilities declared in
+/// the plugin manifest, and is interrupted once it runs for too long or uses
+/// too much memory.
+pub struct ForgeWasmPlugins {
+    // Created on first use so that sessions without plugins don't pay for it
+    engine: OnceCell<Engine>,
+    cwd: PathBuf,
+    // Compiling a component is expensive, so it's done once per path
+    components: Mutex<HashMap<PathBuf, Component>>,
+}
+
+impl ForgeWasmPlugins {
+    pub fn new(cwd: PathBuf) -> Self {
+        Self {
+            engine: Default::default(),
+            cwd,
+            components: Default::default(),
+        }
+    }
+
+    async fn engine(&self) -> anyhow::Result<&Engine> {
+        self.engine
+            .get_or_try_init(|| async {
+                let mut config = Config::new();
+                config
+                    .async_support(true)
+                    .wasm_component_model(true)
+                    .epoch_interruption(true);
+                let engine = Engine::new(&config)?;
+
+                // Stores get a deadline in epochs, which the engine counts in ticks
+                let ticker = engine.weak();
+                tokio::spawn(async move {
+                    let mut interval = tokio::time::interval(EPOCH_TICK);
+                    loop {
+                        interval.tick().await;
+                        let Some(engine) = ticker.upgrade() else {
+                            break;
+                        };
+                        engine.increment_epoch();
+                    }
+                });
+                anyhow::Ok(engine)
+            })
+            .await
+    }
+
+    async fn component(&self, path: &Path) -> anyhow::Result<Component> {
+        let path = self.cwd.join(path);
+        let mut components = self.components.lock().await;
+        if let Some(component) = components.get(&path) {
+            return Ok(component.clone());
+        }
+
+        let component = Component::from_file(self.engine().await?, &path)
+            .with_context(|| format!("Failed to load plugin from {}", path.display()))?;
+        components.insert(path, component.clone());
+        Ok(component)
+    }
+
+    async fn instantiate(
+        &self,
+        plugin: &Plugin,
+    ) -> anyhow::Result<(bindings::Plugin, Store<PluginState>)> {
+        let mut builder = WasiCtxBuilder::new();
+        for directory in &plugin.capabilities.directories {
+            builder.preopened_dir(
+                contained_dir(&self.cwd, directory).with_context(|| {
+                    format!("Plugin '{}' can't be given the directory", plugin.name)
+                })?,
+                directory.to_string_lossy(),
+                DirPerms::all(),
+                FilePerms::all(),
+            )?;
+        }
+        if plugin.capabilities.network {
+            builder.inherit_network().allow_ip_name_lookup(true);
+        }
+
+        let state = PluginState {
+            ctx: builder.build(),
+            table: ResourceTable::new(),
+            limits: StoreLimitsBuilder::new().memory_size(MAX_MEMORY).build(),
+        };
+        let engine = self.engine().await?;
+        let mut store = Store::new(engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_epoch_deadline(MAX_CALL_TICKS);
+        store.epoch_deadline_trap();
+        let mut linker = Linker::new(engine);
+        wasmtime_wasi::add_to_linker_async(&mut linker)?;
+
+        let component = self.component(&plugin.path).await?;
+        let instance = bindings::Plugin::instantiate_async(&mut store, &component, &linker)
+            .await
+            .with_context(|| format!("Failed to instantiate plugin '{}'", plugin.name))?;
+
+        Ok((instance, store))
+    }
+}
+
+/// Resolves a directory of the manifest against the workspace, refusing the
+/// ones that lead out of it, through `..` or a symlink
+fn contained_dir(cwd: &Path, directory: &Path) -> anyhow::Result<PathBuf> {
+    if directory
+        .components()
+        .any(|component| !matches!(component, PathComponent::Normal(_) | PathComponent::CurDir))
+    {
+        anyhow::bail!(
+            "{} must be a relative path inside the workspace",
+            directory.display()
+        );
+    }
+
+    let cwd = cwd.canonicalize()?;
+    let resolved = cwd
+        .join(directory)
+        .canonicalize()
+        .with_context(|| format!("{} doesn't exist", directory.display()))?;
+    if !resolved.starts_with(&cwd) {
+        anyhow::bail!("{} leads out of the workspace", directory.display());
+    }
+    Ok(resolved)
+}
+
+#[async_trait::async_trait]
+impl PluginInfra for ForgeWasmPlugins {
+    async fn plugin_tools(&self, plugin: &Plugin) -> anyhow::Result<Vec<ToolDefinition>> {
+        let (instance, mut store) = self.instantiate(plugin).await?;
+        let definitions = instance
+            .forge_tool_tool()
+            .call_definitions(&mut store)
+            .await?;
+
+        definitions
+            .into_iter()
+            .map(|definition| {
+                let input_schema =
+                    serde_json::from_str(&definition.input_schema).with_context(|| {
+                        format!("Invalid input schema for tool '{}'", definition.name)
+                    })?;
+                Ok(ToolDefinition::new(definition.name)
+                    .description(definition.description)
+                    .input_schema(input_schema))
+            })
+            .collect()
+    }
+
+    async fn call_plugin(
+        &self,
+        plugin: &Plugin,
+        tool_name: &ToolName,
+        input: serde_json::Value,
+    ) -> anyhow::Result<ToolOutput> {
+        let (instance, mut store) = self.instantiate(plugin).await?;
+        let output = instance
+            .forge_tool_tool()
+            .call_call(&mut store, tool_name.as_str(), &input.to_string())
+            .await?;
+
+        Ok(match output {
+            Ok(output) => ToolOutput::text(output),
+            Err(error) => ToolOutput::text(error).is_error(true),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_contained_dir() {
+        let fixture = tempfile::tempdir().unwrap();
+        let cwd = fixture.path().join("workspace");
+        std::fs::create_dir_all(cwd.join("data")).unwrap();
+        std::fs::create_dir_all(fixture.path().join("secrets")).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(fixture.path().join("secrets"), cwd.join("link")).unwrap();
+
+        let actual = ["data", "../secrets", "/etc", "link"]
+            .map(|directory| contained_dir(&cwd, Path::new(directory)).is_ok());
+
+        let expected = [true, false, false, false];
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use chrono::NaiveDate;
 use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
@@ -50,11 +51,68 @@ pub struct Cli {
     #[arg(long, short = 'e')]
     pub event: Option<String>,
 
+    /// Name of the profile from forge.yaml to activate.
+    ///
+    /// The profile stays active for subsequent sessions until another one is
+    /// selected.
+    #[arg(long)]
+    pub profile: Option<String>,
+
     /// Path to a file containing the conversation to execute.
     /// This file should be in JSON format.
     #[arg(long)]
     pub conversation: Option<PathBuf>,
 
+    /// Id or name of a saved session to resume, or `last` for the most recent
+    /// session of the working directory.
+    ///
+    /// Turns interrupted by a crash are resumed up to the last tool call that
+    /// completed.
+    #[arg(long, conflicts_with = "conversation")]
+    pub resume: Option<String>,
+
+    /// Tag the new session, e.g. `--tag bugfix`. Can be repeated.
+    #[arg(long = "tag")]
+    pub tags: Vec<String>,
+
+    /// Let other frontends attach to the new session with `forge attach`,
+    /// to watch it or to also send prompts in collaborative mode.
+    #[arg(long)]
+    pub share: Option<ShareMode>,
+
+    /// Append the lifecycle events of the turns to this file as JSON lines,
+    /// to follow the progress of the agents from another program.
+    #[arg(long)]
+    pub events: Option<PathBuf>,
+
+    /// Preview file changes and shell commands instead of running them.
+    ///
+    /// Can be toggled during a session with the `/dry-run` command.
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+
+    /// Start the new session in plan mode, where the agent only reads the
+    /// workspace and makes a plan, carried out once you approve it.
+    ///
+    /// Can be toggled during a session with the `/plan` command.
+    #[arg(long, default_value_t = false)]
+    pub plan: bool,
+
+    /// Abort a turn once the agent has made this many requests to the model,
+    /// overriding the `max_turns` of the agents.
+    #[arg(long)]
+    pub max_turns: Option<u64>,
+
+    /// Abort a turn once its requests cost more than this many US dollars,
+    /// overriding the `max_cost_usd` of the agents.
+    #[arg(long)]
+    pub max_cost: Option<f64>,
+
+    /// Abort a turn once it has run for this many seconds, overriding the
+    /// `max_duration` of the agents.
+    #[arg(long)]
+    pub max_duration: Option<u64>,
+
     /// Top-level subcommands
     #[command(subcommand)]
     pub subcommands: Option<TopLevelCommand>,
@@ -82,6 +140,279 @@ pub enum TopLevelCommand {
     Mcp(McpCommandGroup),
     /// Print information about the environment
     Info,
+    /// Export a saved session to markdown, HTML or JSON
+    Export(ExportArgs),
+    Sessions(SessionsCommandGroup),
+    /// Send the requests of a dump, a conversation saved with /dump or a
+    /// session again, possibly to another model, to compare the answers
+    Replay(ReplayArgs),
+    /// Import the conversations of a Claude Code session file or of a ChatGPT
+    /// export as sessions
+    Import(ImportArgs),
+    /// Remove the sessions, request dumps and snapshots beyond the retention
+    /// limits and report the space reclaimed
+    Gc(GcArgs),
+    /// Follow a session shared with `--share` by another forge process, and
+    /// send prompts to it when shared collaboratively
+    Attach(AttachArgs),
+    /// Run the schedules of forge.yaml without a user, on their cron
+    /// expressions or when the files they watch change, until stopped. Each
+    /// run is saved as a session.
+    Schedule(ScheduleArgs),
+    /// Run the steps of a pipeline of forge.yaml one after the other, without
+    /// a user. Each step is saved as a session.
+    Run(RunArgs),
+    Agent(AgentCommandGroup),
+    Memory(MemoryCommandGroup),
+}
+
+/// Group of commands managing the facts remembered about the project of the
+/// working directory
+#[derive(Parser, Debug, Clone)]
+pub struct MemoryCommandGroup {
+    /// Subcommands under `memory`
+    #[command(subcommand)]
+    pub command: MemoryCommand,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum MemoryCommand {
+    /// List the facts remembered about the project
+    List,
+
+    /// Forget a fact
+    Forget(MemoryForgetArgs),
+
+    /// Replace the text of a fact
+    Edit(MemoryEditArgs),
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct MemoryForgetArgs {
+    /// Id of the fact, as listed
+    pub id: u32,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct MemoryEditArgs {
+    /// Id of the fact, as listed
+    pub id: u32,
+
+    /// New text of the fact
+    pub fact: String,
+}
+
+/// Group of commands managing the agents of forge.yaml
+#[derive(Parser, Debug, Clone)]
+pub struct AgentCommandGroup {
+    /// Subcommands under `agent`
+    #[command(subcommand)]
+    pub command: AgentCommand,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum AgentCommand {
+    /// Add an agent to forge.yaml from a built-in template, with its prompts
+    /// and tools filled in
+    New(AgentNewArgs),
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct AgentNewArgs {
+    /// Template of the agent (code-reviewer, test-writer or
+    /// migration-assistant), chosen from a list when omitted
+    pub template: Option<forge_domain::AgentTemplate>,
+
+    /// Id of the agent, the name of the template when omitted
+    #[arg(long)]
+    pub id: Option<String>,
+
+    /// Model used by the agent, instead of the model of the workflow
+    #[arg(short = 'm', long = "model")]
+    pub model: Option<String>,
+
+    /// Rules the agent follows on top of its prompt, e.g. `--rules "Focus on
+    /// the SQL queries"`
+    #[arg(long)]
+    pub rules: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct RunArgs {
+    /// Name of the pipeline
+    pub pipeline: String,
+
+    /// Value of an input of the pipeline, e.g. `--input version=1.2.0`. Can be
+    /// repeated.
+    #[arg(long = "input", value_parser = parse_input)]
+    pub inputs: Vec<(String, String)>,
+}
+
+/// Parses an input given as `name=value`
+fn parse_input(input: &str) -> Result<(String, String), String> {
+    input
+        .split_once('=')
+        .map(|(name, value)| (name.trim().to_string(), value.to_string()))
+        .ok_or_else(|| format!("Invalid input '{input}', expected name=value"))
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ScheduleArgs {
+    /// Run the schedule with this name once, right away, instead
+    #[arg(long)]
+    pub run: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct AttachArgs {
+    /// Id or name of the session, the most recently shared session when
+    /// omitted
+    pub session: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct GcArgs {
+    /// Remove the data older than this number of days, instead of the
+    /// `max_age_days` of the retention in forge.yaml
+    #[arg(long = "max-age")]
+    pub max_age_days: Option<u64>,
+
+    /// Remove the oldest data until the rest takes at most this number of
+    /// megabytes, instead of the `max_disk_usage_mb` of the retention
+    #[arg(long = "max-size")]
+    pub max_disk_usage_mb: Option<u64>,
+
+    /// Report what would be removed without removing it
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ImportArgs {
+    /// Path to a Claude Code session file (`~/.claude/projects/*/*.jsonl`) or
+    /// to the `conversations.json` of a ChatGPT export
+    pub path: PathBuf,
+
+    /// Format of the file, detected from its content when omitted
+    #[arg(short = 'f', long = "format")]
+    pub format: Option<ImportFormat>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ReplayArgs {
+    /// Path to a request dump or to a conversation saved with /dump, or the id
+    /// or name of a session
+    pub source: String,
+
+    /// Model the requests are sent to, instead of the one they were sent to
+    #[arg(short = 'm', long = "model")]
+    pub model: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ExportArgs {
+    /// Id or name of a session, or path to a conversation saved with /dump
+    pub source: String,
+
+    /// Format of the export (md, html or json)
+    #[arg(short = 'f', long = "format", default_value = "md")]
+    pub format: forge_domain::ExportFormat,
+
+    /// File to write the export to, instead of the standard output
+    #[arg(short = 'o', long = "output")]
+    pub output: Option<PathBuf>,
+}
+
+/// Group of commands managing the saved sessions
+#[derive(Parser, Debug, Clone)]
+pub struct SessionsCommandGroup {
+    /// Subcommands under `sessions`
+    #[command(subcommand)]
+    pub command: SessionsCommand,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum SessionsCommand {
+    /// List the saved sessions, the most recent first
+    List(SessionsListArgs),
+
+    /// Name a session
+    Rename(SessionsRenameArgs),
+
+    /// Add tags to a session
+    Tag(SessionsTagArgs),
+
+    /// Remove tags from a session
+    Untag(SessionsTagArgs),
+
+    /// Delete a session
+    Delete(SessionsDeleteArgs),
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct SessionsListArgs {
+    /// Only list the sessions with this tag. Can be repeated.
+    #[arg(short = 't', long = "tag")]
+    pub tags: Vec<String>,
+
+    /// Only list the sessions started in this directory
+    #[arg(short = 'w', long = "workspace")]
+    pub workspace: Option<PathBuf>,
+
+    /// Only list the sessions using this model
+    #[arg(short = 'm', long = "model")]
+    pub model: Option<String>,
+
+    /// Only list the sessions active on or after this date (YYYY-MM-DD)
+    #[arg(long = "since")]
+    pub since: Option<NaiveDate>,
+
+    /// Only list the sessions started on or before this date (YYYY-MM-DD)
+    #[arg(long = "until")]
+    pub until: Option<NaiveDate>,
+}
+
+impl From<SessionsListArgs> for forge_domain::SessionFilter {
+    fn from(value: SessionsListArgs) -> Self {
+        Self {
+            tags: value.tags,
+            workspace: value.workspace,
+            model: value.model.map(forge_domain::ModelId::new),
+            since: value
+                .since
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|time| time.and_utc()),
+            until: value
+                .until
+                .and_then(|date| date.and_hms_opt(23, 59, 59))
+                .map(|time| time.and_utc()),
+        }
+    }
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct SessionsTagArgs {
+    /// Id or name of the session
+    pub session: String,
+
+    /// Tags, with or without their leading `#`
+    #[arg(required = true)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct SessionsRenameArgs {
+    /// Id or current name of the session
+    pub session: String,
+
+    /// New name of the session
+    pub name: String,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct SessionsDeleteArgs {
+    /// Id or name of the session
+    pub session: String,
 }
 
 /// Group of MCP-related commands
@@ -108,6 +439,16 @@ pub enum McpCommand {
 
     /// Add a server in JSON format
     AddJson(McpAddJsonArgs),
+
+    /// Serve the read, search, patch and shell tools of forge to other MCP
+    /// clients
+    Serve(McpServeArgs),
+
+    /// Authorize forge with a remote server in the browser
+    Login(McpLoginArgs),
+
+    /// Forget the token issued by a remote server
+    Logout(McpLogoutArgs),
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -116,7 +457,7 @@ pub struct McpAddArgs {
     #[arg(short = 's', long = "scope", default_value = "local")]
     pub scope: Scope,
 
-    /// Transport type (stdio or sse)
+    /// Transport type (stdio, sse or http)
     #[arg(short = 't', long = "transport", default_value = "stdio")]
     pub transport: Transport,
 
@@ -124,6 +465,11 @@ pub struct McpAddArgs {
     #[arg(short = 'e', long = "env")]
     pub env: Vec<String>,
 
+    /// Headers sent to sse and http servers, e.g. -H "Authorization: Bearer
+    /// ${TOKEN}"
+    #[arg(short = 'H', long = "header")]
+    pub headers: Vec<String>,
+
     /// Name of the server
     pub name: String,
 
@@ -164,6 +510,29 @@ pub struct McpAddJsonArgs {
     pub json: String,
 }
 
+#[derive(Parser, Debug, Clone)]
+pub struct McpServeArgs {
+    /// Transport type (stdio or sse)
+    #[arg(short = 't', long = "transport", default_value = "stdio")]
+    pub transport: Transport,
+
+    /// Port to listen on with the sse transport
+    #[arg(short = 'p', long = "port", default_value_t = 8811)]
+    pub port: u16,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct McpLoginArgs {
+    /// Name of the server to authorize forge with
+    pub name: String,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct McpLogoutArgs {
+    /// Name of the server whose token is forgotten
+    pub name: String,
+}
+
 #[derive(Copy, Clone, Debug, ValueEnum, Default)]
 pub enum Scope {
     #[default]
@@ -185,4 +554,37 @@ impl From<Scope> for forge_domain::Scope {
 pub enum Transport {
     Stdio,
     Sse,
+    Http,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+#[clap(rename_all = "kebab-case")]
+pub enum ShareMode {
+    ReadOnly,
+    Collaborative,
+}
+
+impl From<ShareMode> for forge_domain::ShareMode {
+    fn from(value: ShareMode) -> Self {
+        match value {
+            ShareMode::ReadOnly => forge_domain::ShareMode::ReadOnly,
+            ShareMode::Collaborative => forge_domain::ShareMode::Collaborative,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+#[clap(rename_all = "kebab-case")]
+pub enum ImportFormat {
+    ClaudeCode,
+    Openai,
+}
+
+impl From<ImportFormat> for forge_domain::ImportFormat {
+    fn from(value: ImportFormat) -> Self {
+        match value {
+            ImportFormat::ClaudeCode => forge_domain::ImportFormat::ClaudeCode,
+            ImportFormat::Openai => forge_domain::ImportFormat::OpenAi,
+        }
+    }
 }
@@ -3,8 +3,9 @@ use std::sync::Arc;
 use forge_api::Environment;
 use nu_ansi_term::{Color, Style};
 use reedline::{
-    ColumnarMenu, DefaultHinter, EditCommand, Emacs, FileBackedHistory, KeyCode, KeyModifiers,
-    MenuBuilder, Prompt, Reedline, ReedlineEvent, ReedlineMenu, Signal, default_emacs_keybindings,
+    ColumnarMenu, DefaultHinter, EditCommand, Emacs, ExternalPrinter, FileBackedHistory, KeyCode,
+    KeyModifiers, MenuBuilder, Prompt, Reedline, ReedlineEvent, ReedlineMenu, Signal,
+    default_emacs_keybindings,
 };
 
 use super::completer::InputCompleter;
@@ -63,7 +64,11 @@ impl ForgeEditor {
         keybindings
     }
 
-    pub fn new(env: Environment, manager: Arc<ForgeCommandManager>) -> Self {
+    pub fn new(
+        env: Environment,
+        manager: Arc<ForgeCommandManager>,
+        printer: ExternalPrinter<String>,
+    ) -> Self {
         // Store file history in system config directory
         let history_file = env.history_path();
 
@@ -90,6 +95,7 @@ impl ForgeEditor {
             .with_edit_mode(edit_mode)
             .with_quick_completions(true)
             .with_ansi_colors(true)
+            .with_external_printer(printer)
             .use_bracketed_paste(true);
         Self { editor }
     }
@@ -1,9 +1,12 @@
 use std::fmt;
 use std::path::Path;
-use std::time::Duration;
+use std::time::{Duration, UNIX_EPOCH};
 
 use colored::Colorize;
-use forge_api::{Environment, LoginInfo, UserUsage};
+use forge_api::{
+    Environment, LoginInfo, Memory, PruneReport, Session, SessionStats, Snapshot, ToolStats,
+    UserUsage,
+};
 use forge_tracker::VERSION;
 
 use crate::model::ForgeCommandManager;
@@ -123,6 +126,10 @@ pub fn get_usage(state: &UIState) -> Info {
         .add_key_value("Total Tokens", state.usage.total_tokens.to_string())
         .add_key_value("Cached Tokens", state.usage.cached_tokens.to_string());
 
+    if let Some(budget) = state.token_budget.as_ref() {
+        usage = usage.add_key_value("Context Window", budget.to_string());
+    }
+
     let is_forge_provider = state.provider.as_ref().is_some_and(|p| p.is_forge());
     if let Some(cost) = state.usage.cost.as_ref()
         && !is_forge_provider
@@ -298,6 +305,191 @@ impl From<&UserUsage> for Info {
     }
 }
 
+impl From<&[Snapshot]> for Info {
+    fn from(snapshots: &[Snapshot]) -> Self {
+        let mut info = Info::new().add_title("Snapshots");
+
+        // Most recent first, as that is the order in which they are undone
+        for snapshot in snapshots.iter().rev() {
+            let time = chrono::DateTime::<chrono::Local>::from(UNIX_EPOCH + snapshot.timestamp);
+            info = info.add_key_value(
+                snapshot.id.to_string(),
+                format!("{} {}", time.format("%Y-%m-%d %H:%M:%S"), snapshot.path),
+            );
+        }
+
+        info
+    }
+}
+
+impl From<&[Session]> for Info {
+    fn from(sessions: &[Session]) -> Self {
+        let mut info = Info::new().add_title("Sessions");
+
+        if sessions.is_empty() {
+            return info.add_key("No sessions saved yet");
+        }
+
+        for session in sessions {
+            let updated_at = session.updated_at.with_timezone(&chrono::Local);
+            let model = session
+                .model
+                .as_ref()
+                .map(|model| format!(" {model}"))
+                .unwrap_or_default();
+            let tags = if session.tags.is_empty() {
+                String::new()
+            } else {
+                format!(" {}", format_tags(&session.tags))
+            };
+            info = info.add_key_value(
+                session
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| session.id.to_string()),
+                format!(
+                    "{} {}{model}{tags}",
+                    updated_at.format("%Y-%m-%d %H:%M:%S"),
+                    session.workspace.display()
+                ),
+            );
+        }
+
+        info
+    }
+}
+
+impl From<&[Memory]> for Info {
+    fn from(memories: &[Memory]) -> Self {
+        let info = Info::new().add_title("Memories");
+
+        if memories.is_empty() {
+            return info.add_key("No facts remembered about this project yet");
+        }
+
+        memories.iter().fold(info, |info, memory| {
+            info.add_key_value(format!("#{}", memory.id), &memory.fact)
+        })
+    }
+}
+
+impl From<&SessionStats> for Info {
+    fn from(stats: &SessionStats) -> Self {
+        let mut info = Info::new().add_title("Session Stats");
+
+        if stats.requests == 0 {
+            return info.add_key("No requests sent yet");
+        }
+
+        info = info
+            .add_key_value("Requests", stats.requests)
+            .add_key_value(
+                "Tokens",
+                format!(
+                    "{} ({} prompt, {} completion, {} cached)",
+                    stats.total_tokens(),
+                    stats.prompt_tokens,
+                    stats.completion_tokens,
+                    stats.cached_tokens
+                ),
+            );
+        if let Some(cost) = stats.cost {
+            info = info.add_key_value("Cost", format!("${cost:.4}"));
+        }
+        info = info.add_key_value("Tool Calls", stats.total_tool_calls());
+        for (name, calls) in &stats.tool_calls {
+            info = info.add_key_value(name, calls);
+        }
+
+        info.add_key_value("Model Time", format_duration_ms(stats.model_duration_ms))
+            .add_key_value("Tool Time", format_duration_ms(stats.tool_duration_ms))
+            .add_key_value("Wall Clock", format_duration_ms(stats.wall_clock_ms))
+    }
+}
+
+impl From<&PruneReport> for Info {
+    fn from(report: &PruneReport) -> Self {
+        let title = if report.dry_run {
+            "Would Reclaim"
+        } else {
+            "Reclaimed"
+        };
+        let mut info = Info::new().add_title(title);
+
+        if report.is_empty() {
+            return info.add_key("Nothing beyond the retention limits");
+        }
+
+        for (kind, reclaimed) in &report.reclaimed {
+            info = info.add_key_value(
+                kind.to_string(),
+                format!(
+                    "{} ({})",
+                    reclaimed.count,
+                    format_bytes(reclaimed.bytes as usize)
+                ),
+            );
+        }
+        info.add_key_value("Total", format_bytes(report.total_bytes() as usize))
+    }
+}
+
+impl From<&[ToolStats]> for Info {
+    fn from(stats: &[ToolStats]) -> Self {
+        let mut info = Info::new().add_title("Tool Stats");
+
+        if stats.is_empty() {
+            return info.add_key("No tool calls recorded yet");
+        }
+
+        for stats in stats {
+            info = info.add_key_value(
+                stats.name.as_str(),
+                format!(
+                    "{} calls, {:.0}% failed, {} total, {} avg, {} max, {} avg output",
+                    stats.calls,
+                    stats.failure_rate() * 100.0,
+                    format_duration_ms(stats.total_duration_ms),
+                    format_duration_ms(stats.average_duration_ms()),
+                    format_duration_ms(stats.max_duration_ms),
+                    format_bytes(stats.average_output_bytes()),
+                ),
+            );
+        }
+
+        info
+    }
+}
+
+/// Tags prefixed with `#` and separated by spaces, or `no tags`
+pub fn format_tags(tags: &[String]) -> String {
+    if tags.is_empty() {
+        return "no tags".to_string();
+    }
+    tags.iter()
+        .map(|tag| format!("#{tag}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn format_duration_ms(duration_ms: u64) -> String {
+    if duration_ms < 1000 {
+        format!("{duration_ms}ms")
+    } else {
+        format!("{:.1}s", duration_ms as f64 / 1000.0)
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{bytes} B")
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
 pub fn create_progress_bar(current: u32, limit: u32, width: usize) -> String {
     if limit == 0 {
         return "N/A".to_string();
@@ -351,6 +543,7 @@ mod tests {
             stdout_max_line_length: 2000,
             max_read_size: 100,
             tool_timeout: 300,
+            max_concurrent_commands: 4,
             http: Default::default(),
             max_file_size: 1000,
         }
@@ -487,6 +680,23 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_format_duration_ms() {
+        let actual = (
+            super::format_duration_ms(250),
+            super::format_duration_ms(3400),
+        );
+        let expected = ("250ms".to_string(), "3.4s".to_string());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        let actual = (super::format_bytes(512), super::format_bytes(2048));
+        let expected = ("512 B".to_string(), "2.0 KB".to_string());
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn test_format_reset_time_zero() {
         let actual = super::format_reset_time(0);
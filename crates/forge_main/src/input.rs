@@ -3,6 +3,7 @@ use std::sync::{Arc, Mutex};
 
 use forge_api::Environment;
 use forge_display::TitleFormat;
+use reedline::ExternalPrinter;
 use tokio::fs;
 use tokio::task::block_in_place;
 
@@ -16,16 +17,22 @@ use crate::tracker;
 pub struct Console {
     env: Environment,
     command: Arc<ForgeCommandManager>,
+    printer: ExternalPrinter<String>,
 }
 
 impl Console {
     /// Creates a new instance of `Console`.
     pub fn new(env: Environment, command: Arc<ForgeCommandManager>) -> Self {
-        Self { env, command }
+        Self { env, command, printer: ExternalPrinter::default() }
     }
 }
 
 impl Console {
+    /// Prints lines above the prompt while the user is typing
+    pub fn printer(&self) -> ExternalPrinter<String> {
+        self.printer.clone()
+    }
+
     pub async fn upload<P: Into<PathBuf> + Send>(&self, path: P) -> anyhow::Result<Command> {
         let path = path.into();
         let content = fs::read_to_string(&path).await?.trim().to_string();
@@ -35,7 +42,11 @@ impl Console {
     }
 
     pub async fn prompt(&self, prompt: ForgePrompt) -> anyhow::Result<Command> {
-        let engine = Mutex::new(ForgeEditor::new(self.env.clone(), self.command.clone()));
+        let engine = Mutex::new(ForgeEditor::new(
+            self.env.clone(),
+            self.command.clone(),
+            self.printer.clone(),
+        ));
 
         loop {
             let user_input = block_in_place(|| engine.lock().unwrap().prompt(&prompt))?;
@@ -6,6 +6,7 @@ mod info;
 mod input;
 mod model;
 mod prompt;
+mod reasoning;
 mod sandbox;
 mod select;
 mod state;
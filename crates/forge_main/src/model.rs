@@ -1,6 +1,6 @@
 use std::sync::{Arc, Mutex};
 
-use forge_api::{Model, Workflow};
+use forge_api::{McpPrompt, Model, Workflow};
 use strum::{EnumProperty, IntoEnumIterator};
 use strum_macros::{EnumIter, EnumProperty};
 
@@ -51,12 +51,17 @@ impl From<&Workflow> for ForgeCommandManager {
 #[derive(Debug)]
 pub struct ForgeCommandManager {
     commands: Arc<Mutex<Vec<ForgeCommand>>>,
+    // Prompts of the MCP servers, which are loaded separately from the workflow
+    prompts: Arc<Mutex<Vec<McpPrompt>>>,
 }
 
 impl Default for ForgeCommandManager {
     fn default() -> Self {
         let commands = Self::default_commands();
-        ForgeCommandManager { commands: Arc::new(Mutex::new(commands)) }
+        ForgeCommandManager {
+            commands: Arc::new(Mutex::new(commands)),
+            prompts: Default::default(),
+        }
     }
 }
 
@@ -66,6 +71,7 @@ impl ForgeCommandManager {
             .filter(|command| !matches!(command, Command::Message(_)))
             .filter(|command| !matches!(command, Command::Custom(_)))
             .filter(|command| !matches!(command, Command::Shell(_)))
+            .filter(|command| !matches!(command, Command::McpPrompt(..)))
             .map(|command| ForgeCommand {
                 name: command.name().to_string(),
                 description: command.usage().to_string(),
@@ -92,6 +98,11 @@ impl ForgeCommandManager {
         *guard = commands;
     }
 
+    /// Registers the prompts of the MCP servers as commands
+    pub fn register_prompts(&self, prompts: Vec<McpPrompt>) {
+        *self.prompts.lock().unwrap() = prompts;
+    }
+
     /// Finds a command by name.
     fn find(&self, command: &str) -> Option<ForgeCommand> {
         self.commands
@@ -102,9 +113,34 @@ impl ForgeCommandManager {
             .cloned()
     }
 
+    /// Finds a prompt of an MCP server by its command.
+    fn find_prompt(&self, command: &str) -> Option<McpPrompt> {
+        self.prompts
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|prompt| prompt.command() == command)
+            .cloned()
+    }
+
     /// Lists all registered commands.
     pub fn list(&self) -> Vec<ForgeCommand> {
-        self.commands.lock().unwrap().clone()
+        let mut commands = self.commands.lock().unwrap().clone();
+        commands.extend(
+            self.prompts
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|prompt| ForgeCommand {
+                    name: prompt.command(),
+                    description: format!(
+                        "⚙ {}",
+                        prompt.description.as_deref().unwrap_or("MCP prompt")
+                    ),
+                    value: None,
+                }),
+        );
+        commands
     }
 
     /// Extracts the command value from the input parts
@@ -163,12 +199,19 @@ impl ForgeCommandManager {
             return Ok(Command::Message(input.to_string()));
         }
 
+        // Check if it's a prompt of an MCP server (e.g. /github:create-pr)
+        if let Some(prompt) = self.find_prompt(command) {
+            let value = Some(parameters.join(" ")).filter(|value| !value.is_empty());
+            return Ok(Command::McpPrompt(prompt, value));
+        }
+
         // TODO: Can leverage Clap to parse commands and provide correct error messages
         match command {
             "/compact" => Ok(Command::Compact),
             "/new" => Ok(Command::New),
             "/info" => Ok(Command::Info),
             "/usage" => Ok(Command::Usage),
+            "/stats" => Ok(Command::Stats),
             "/exit" => Ok(Command::Exit),
             "/update" => Ok(Command::Update),
             "/dump" => {
@@ -179,7 +222,7 @@ impl ForgeCommandManager {
                 }
             }
             "/act" | "/forge" => Ok(Command::Forge),
-            "/plan" | "/muse" => Ok(Command::Muse),
+            "/muse" => Ok(Command::Muse),
             "/help" => Ok(Command::Help),
             "/model" => Ok(Command::Model),
             "/tools" => Ok(Command::Tools),
@@ -187,6 +230,34 @@ impl ForgeCommandManager {
             "/login" => Ok(Command::Login),
             "/logout" => Ok(Command::Logout),
             "/retry" => Ok(Command::Retry),
+            "/profile" => Ok(Command::Profile(
+                parameters.first().map(|name| name.to_string()),
+            )),
+            "/dry-run" => Ok(Command::DryRun),
+            "/plan" => Ok(Command::Plan),
+            "/reasoning" => Ok(Command::Reasoning),
+            "/undo" => Ok(Command::Undo(
+                parameters.first().map(|value| value.to_string()),
+            )),
+            "/mcp" => Ok(Command::Mcp(
+                parameters.first().map(|value| value.to_string()),
+            )),
+            "/summarize" => Ok(Command::Summarize(
+                parameters.first().map(|value| value.to_string()),
+            )),
+            "/pin" => Ok(Command::Pin(
+                Some(parameters.join(" ")).filter(|path| !path.is_empty()),
+            )),
+            "/unpin" => Ok(Command::Unpin(
+                Some(parameters.join(" ")).filter(|path| !path.is_empty()),
+            )),
+            "/export" => Ok(Command::Export(
+                Some(parameters.join(" ")).filter(|path| !path.is_empty()),
+            )),
+            "/context" => Ok(Command::Context(
+                parameters.first().map(|value| value.to_string()),
+            )),
+            "/repomap" => Ok(Command::RepoMap),
             text => {
                 let parts = text.split_ascii_whitespace().collect::<Vec<&str>>();
 
@@ -236,6 +307,11 @@ pub enum Command {
     /// Display usage information (tokens & requests).
     #[strum(props(usage = "Shows usage information (tokens & requests)"))]
     Usage,
+    /// Display the tokens, cost and time spent in the session, followed by the
+    /// latency, failure rate and output size of each tool.
+    /// This can be triggered with the '/stats' command.
+    #[strum(props(usage = "Shows the cost of the session and where tool calls spend their time"))]
+    Stats,
     /// Exit the application without any further action.
     #[strum(props(usage = "Exit the application"))]
     Exit,
@@ -247,8 +323,11 @@ pub enum Command {
     #[strum(props(usage = "Enable implementation mode with code changes"))]
     Forge,
     /// Switch to "muse" agent.
-    /// This can be triggered with the '/must' command.
-    #[strum(props(usage = "Enable planning mode without code changes"))]
+    /// This can be triggered with the '/muse' command. '/plan' used to switch
+    /// to it too, it now toggles plan mode.
+    #[strum(props(
+        usage = "Switch to the muse agent, which plans without code changes (/plan toggles plan mode)"
+    ))]
     Muse,
     /// Switch to "help" mode.
     /// This can be triggered with the '/help' command.
@@ -267,6 +346,10 @@ pub enum Command {
     Tools,
     /// Handles custom command defined in workflow file.
     Custom(PartialEvent),
+    /// Sends a prompt of an MCP server, with the text typed after the command
+    /// as its first argument.
+    /// This can be triggered with the '/server:prompt' command.
+    McpPrompt(McpPrompt, Option<String>),
     /// Executes a native shell command.
     /// This can be triggered with commands starting with '!' character.
     #[strum(props(usage = "Execute a native shell command"))]
@@ -289,6 +372,81 @@ pub enum Command {
     /// Retry without modifying model context
     #[strum(props(usage = "Retry the last command"))]
     Retry,
+
+    /// Switch to a provider profile defined in the workflow.
+    /// This can be triggered with the '/profile [name]' command.
+    #[strum(props(usage = "Switch to a provider profile defined in forge.yaml"))]
+    Profile(Option<String>),
+
+    /// Toggle dry-run mode for the current conversation.
+    /// This can be triggered with the '/dry-run' command.
+    #[strum(props(usage = "Toggle previewing file changes and commands instead of running them"))]
+    DryRun,
+
+    /// Toggle plan mode for the current conversation.
+    /// This can be triggered with the '/plan' command.
+    #[strum(props(
+        usage = "Toggle planning the task without changing anything, then carrying out the approved plan"
+    ))]
+    Plan,
+
+    /// Toggle showing the reasoning of the model as it's streamed.
+    /// This can be triggered with the '/reasoning' command.
+    #[strum(props(usage = "Toggle showing the reasoning of the model apart from its answer"))]
+    Reasoning,
+
+    /// Undo a file change made by a tool.
+    /// This can be triggered with the '/undo [list|snapshot-id]' command.
+    #[strum(props(
+        usage = "Undo the last file change (use /undo list to see snapshots, /undo <id> to restore one)"
+    ))]
+    Undo(Option<String>),
+
+    /// Manage the connected MCP servers.
+    /// This can be triggered with the '/mcp reload' command.
+    #[strum(props(
+        usage = "Manage MCP servers (use /mcp reload to apply changes to their configuration)"
+    ))]
+    Mcp(Option<String>),
+
+    /// Summarize the conversation into decisions, open tasks and files touched.
+    /// This can be triggered with the '/summarize [replace]' command.
+    #[strum(props(
+        usage = "Summarize the conversation (use /summarize replace to continue from the summary)"
+    ))]
+    Summarize(Option<String>),
+
+    /// Pin a file or the last reply of the agent, so that compaction keeps it
+    /// in the context.
+    /// This can be triggered with the '/pin [path]' command.
+    #[strum(props(
+        usage = "Keep a file in the context through compaction (use /pin without a path to pin the last reply)"
+    ))]
+    Pin(Option<String>),
+
+    /// Unpin a file, or everything that's pinned when no path is given.
+    /// This can be triggered with the '/unpin [path]' command.
+    #[strum(props(usage = "Unpin a file (use /unpin without a path to unpin everything)"))]
+    Unpin(Option<String>),
+
+    /// Export the context of the conversation to a markdown, HTML or JSON
+    /// file. This can be triggered with the '/export [path]' command.
+    #[strum(props(
+        usage = "Save the context as a markdown transcript (use /export <path> to pick the file, ending with .html or .json for other formats)"
+    ))]
+    Export(Option<String>),
+
+    /// Debug the context sent to the provider.
+    /// This can be triggered with the '/context diff' command.
+    #[strum(props(
+        usage = "Debug the context (use /context diff to see what changed between the last two requests)"
+    ))]
+    Context(Option<String>),
+
+    /// Generate the repo map of the conversation again from the workspace as
+    /// it is now. This can be triggered with the '/repomap' command.
+    #[strum(props(usage = "Refresh the map of the files and definitions given to the agent"))]
+    RepoMap,
 }
 
 impl Command {
@@ -300,6 +458,7 @@ impl Command {
             Command::Update => "/update",
             Command::Info => "/info",
             Command::Usage => "/usage",
+            Command::Stats => "/stats",
             Command::Exit => "/exit",
             Command::Forge => "/forge",
             Command::Muse => "/muse",
@@ -308,11 +467,24 @@ impl Command {
             Command::Model => "/model",
             Command::Tools => "/tools",
             Command::Custom(event) => &event.name,
+            Command::McpPrompt(..) => "/mcp-prompt",
             Command::Shell(_) => "!shell",
             Command::Agent => "/agent",
             Command::Login => "/login",
             Command::Logout => "/logout",
             Command::Retry => "/retry",
+            Command::Profile(_) => "/profile",
+            Command::DryRun => "/dry-run",
+            Command::Plan => "/plan",
+            Command::Reasoning => "/reasoning",
+            Command::Undo(_) => "/undo",
+            Command::Mcp(_) => "/mcp",
+            Command::Summarize(_) => "/summarize",
+            Command::Pin(_) => "/pin",
+            Command::Unpin(_) => "/unpin",
+            Command::Export(_) => "/export",
+            Command::Context(_) => "/context",
+            Command::RepoMap => "/repomap",
         }
     }
 
@@ -353,6 +525,7 @@ mod tests {
                 description: String::from("Test command"),
                 value: Some(String::from("default_value")),
             }])),
+            ..Default::default()
         };
         let command = ForgeCommand {
             name: String::from("/test"),
@@ -377,6 +550,7 @@ mod tests {
                 description: String::from("Test command"),
                 value: Some(String::from("default_value")),
             }])),
+            ..Default::default()
         };
         let command = ForgeCommand {
             name: String::from("/test"),
@@ -401,6 +575,7 @@ mod tests {
                 description: String::from("Test command"),
                 value: Some(String::from("default_value")),
             }])),
+            ..Default::default()
         };
         let command = ForgeCommand {
             name: String::from("/test"),
@@ -425,6 +600,7 @@ mod tests {
                 description: String::from("Test command"),
                 value: None,
             }])),
+            ..Default::default()
         };
         let command = ForgeCommand {
             name: String::from("/test"),
@@ -449,6 +625,7 @@ mod tests {
                 description: String::from("Test command"),
                 value: Some(String::from("default_value")),
             }])),
+            ..Default::default()
         };
         let command = ForgeCommand {
             name: String::from("/test"),
@@ -463,6 +640,171 @@ mod tests {
         // Verify - provided value should override default
         assert_eq!(result, Some(String::from("provided_value")));
     }
+    #[test]
+    fn test_parse_profile_command() {
+        // Setup
+        let cmd_manager = ForgeCommandManager::default();
+
+        // Execute
+        let with_name = cmd_manager.parse("/profile work").unwrap();
+        let without_name = cmd_manager.parse("/profile").unwrap();
+
+        // Verify
+        assert_eq!(with_name, Command::Profile(Some("work".to_string())));
+        assert_eq!(without_name, Command::Profile(None));
+    }
+
+    #[test]
+    fn test_parse_dry_run_command() {
+        // Setup
+        let cmd_manager = ForgeCommandManager::default();
+
+        // Execute
+        let result = cmd_manager.parse("/dry-run").unwrap();
+
+        // Verify
+        assert_eq!(result, Command::DryRun);
+    }
+
+    #[test]
+    fn test_parse_plan_command() {
+        // Setup
+        let cmd_manager = ForgeCommandManager::default();
+
+        // Execute
+        let result = cmd_manager.parse("/plan").unwrap();
+
+        // Verify
+        assert_eq!(result, Command::Plan);
+    }
+
+    #[test]
+    fn test_parse_reasoning_command() {
+        // Setup
+        let cmd_manager = ForgeCommandManager::default();
+
+        // Execute
+        let result = cmd_manager.parse("/reasoning").unwrap();
+
+        // Verify
+        assert_eq!(result, Command::Reasoning);
+    }
+
+    #[test]
+    fn test_parse_undo_command() {
+        // Setup
+        let cmd_manager = ForgeCommandManager::default();
+
+        // Execute
+        let with_argument = cmd_manager.parse("/undo list").unwrap();
+        let without_argument = cmd_manager.parse("/undo").unwrap();
+
+        // Verify
+        assert_eq!(with_argument, Command::Undo(Some("list".to_string())));
+        assert_eq!(without_argument, Command::Undo(None));
+    }
+
+    #[test]
+    fn test_parse_mcp_command() {
+        let fixture = ForgeCommandManager::default();
+
+        let actual = fixture.parse("/mcp reload").unwrap();
+
+        let expected = Command::Mcp(Some("reload".to_string()));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_summarize_command() {
+        let fixture = ForgeCommandManager::default();
+
+        let actual = [
+            fixture.parse("/summarize replace").unwrap(),
+            fixture.parse("/summarize").unwrap(),
+        ];
+
+        let expected = [
+            Command::Summarize(Some("replace".to_string())),
+            Command::Summarize(None),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_pin_command() {
+        let fixture = ForgeCommandManager::default();
+
+        let actual = [
+            fixture.parse("/pin src/my file.rs").unwrap(),
+            fixture.parse("/pin").unwrap(),
+            fixture.parse("/unpin").unwrap(),
+        ];
+
+        let expected = [
+            Command::Pin(Some("src/my file.rs".to_string())),
+            Command::Pin(None),
+            Command::Unpin(None),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_export_command() {
+        let fixture = ForgeCommandManager::default();
+
+        let actual = [
+            fixture.parse("/export notes/context.md").unwrap(),
+            fixture.parse("/export").unwrap(),
+        ];
+
+        let expected = [
+            Command::Export(Some("notes/context.md".to_string())),
+            Command::Export(None),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_context_command() {
+        let fixture = ForgeCommandManager::default();
+
+        let actual = fixture.parse("/context diff").unwrap();
+
+        let expected = Command::Context(Some("diff".to_string()));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_repo_map_command() {
+        let fixture = ForgeCommandManager::default();
+
+        let actual = fixture.parse("/repomap").unwrap();
+
+        let expected = Command::RepoMap;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_mcp_prompt_command() {
+        // Setup
+        let cmd_manager = ForgeCommandManager::default();
+        let prompt = McpPrompt::default().server("github").name("create-pr");
+        cmd_manager.register_prompts(vec![prompt.clone()]);
+
+        // Execute
+        let with_value = cmd_manager
+            .parse("/github:create-pr fix the login bug")
+            .unwrap();
+        let without_value = cmd_manager.parse("/github:create-pr").unwrap();
+
+        // Verify
+        assert_eq!(
+            with_value,
+            Command::McpPrompt(prompt.clone(), Some("fix the login bug".to_string()))
+        );
+        assert_eq!(without_value, Command::McpPrompt(prompt, None));
+    }
+
     #[test]
     fn test_parse_shell_command() {
         // Setup
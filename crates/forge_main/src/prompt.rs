@@ -5,7 +5,7 @@ use std::process::Command;
 
 use convert_case::{Case, Casing};
 use derive_setters::Setters;
-use forge_api::{AgentId, ModelId, Usage};
+use forge_api::{AgentId, ModelId, TokenBudget, Usage};
 use forge_tracker::VERSION;
 use nu_ansi_term::{Color, Style};
 use reedline::{Prompt, PromptHistorySearchStatus};
@@ -20,6 +20,7 @@ const RIGHT_CHEVRON: &str = "❯";
 pub struct ForgePrompt {
     pub cwd: PathBuf,
     pub usage: Option<Usage>,
+    pub token_budget: Option<TokenBudget>,
     pub agent_id: AgentId,
     pub model: Option<ModelId>,
 }
@@ -87,6 +88,15 @@ impl Prompt for ForgePrompt {
             write!(result, "/{usage}").unwrap();
         }
 
+        // Share of the context window of the model that's used
+        if let Some(utilization) = self
+            .token_budget
+            .as_ref()
+            .and_then(TokenBudget::utilization)
+        {
+            write!(result, " {:.0}%", utilization * 100.0).unwrap();
+        }
+
         write!(result, "]").unwrap();
 
         // Apply styling once at the end
@@ -176,6 +186,7 @@ mod tests {
             ForgePrompt {
                 cwd: PathBuf::from("."),
                 usage: None,
+                token_budget: None,
                 agent_id: AgentId::default(),
                 model: None,
             }
@@ -229,6 +240,17 @@ mod tests {
         assert!(actual.contains("~30"));
     }
 
+    #[test]
+    fn test_render_prompt_right_with_token_budget() {
+        let budget =
+            forge_api::TokenBudget::new(forge_api::TokenCount::Actual(50_000)).limit(200_000u64);
+        let mut prompt = ForgePrompt::default();
+        let _ = prompt.token_budget(budget);
+
+        let actual = prompt.render_prompt_right();
+        assert!(actual.contains(" 25%]"));
+    }
+
     #[test]
     fn test_render_prompt_right_without_usage() {
         let prompt = ForgePrompt::default();
@@ -0,0 +1,49 @@
+/// Reasoning of the model as it's streamed, written a line at a time so that
+/// it can be printed above the spinner
+#[derive(Debug, Default)]
+pub struct ReasoningBuffer {
+    pending: String,
+}
+
+impl ReasoningBuffer {
+    /// Adds a part of the reasoning, returning the lines it completes
+    pub fn push(&mut self, part: &str) -> Vec<String> {
+        self.pending.push_str(part);
+        let mut lines = Vec::new();
+        while let Some(end) = self.pending.find('\n') {
+            let line = self.pending.drain(..=end).collect::<String>();
+            lines.push(line.trim_end().to_string());
+        }
+        lines
+    }
+
+    /// Takes the last line once the reasoning is over, if it's not empty
+    pub fn flush(&mut self) -> Option<String> {
+        let line = std::mem::take(&mut self.pending);
+        (!line.trim().is_empty()).then(|| line.trim_end().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_push() {
+        let mut fixture = ReasoningBuffer::default();
+
+        let actual =
+            ["The user wants", " a test.\n\nFirst I", "'ll read it"].map(|part| fixture.push(part));
+
+        let expected = [
+            vec![],
+            vec!["The user wants a test.".to_string(), "".to_string()],
+            vec![],
+        ];
+        assert_eq!(actual, expected);
+        assert_eq!(fixture.flush(), Some("First I'll read it".to_string()));
+        assert_eq!(fixture.flush(), None);
+    }
+}
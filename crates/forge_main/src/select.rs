@@ -1,6 +1,6 @@
 use anyhow::Result;
 use inquire::ui::{RenderConfig, Styled};
-use inquire::{Confirm, InquireError, Select};
+use inquire::{Confirm, InquireError, MultiSelect, Select, Text};
 
 /// Centralized inquire select functionality with consistent error handling
 pub struct ForgeSelect;
@@ -44,6 +44,29 @@ impl ForgeSelect {
         }
     }
 
+    /// Prompts for a free text answer
+    pub fn input(message: impl Into<String>) -> Result<Option<String>> {
+        let message = message.into();
+        let text = Text::new(&message)
+            .with_render_config(Self::default_render_config())
+            .with_help_message("Press Enter to submit, ESC to cancel");
+        Self::handle_inquire_error(text.prompt())
+    }
+
+    /// Prompts to select any number of options
+    pub fn select_many<T: std::fmt::Display>(
+        message: impl Into<String>,
+        options: Vec<T>,
+    ) -> Result<Option<Vec<T>>> {
+        let message = message.into();
+        let select = MultiSelect::new(&message, options)
+            .with_render_config(Self::default_render_config())
+            .with_help_message(
+                "Use arrow keys to navigate, Space to select/deselect, Enter to confirm, ESC to cancel",
+            );
+        Self::handle_inquire_error(select.prompt())
+    }
+
     /// Convenience method for confirm (yes/no)
     pub fn confirm(message: impl Into<String>) -> SelectBuilder<bool> {
         SelectBuilder {
@@ -1,7 +1,9 @@
 use std::path::PathBuf;
 
 use derive_setters::Setters;
-use forge_api::{AgentId, ConversationId, Environment, ModelId, Provider, Usage, Workflow};
+use forge_api::{
+    AgentId, ConversationId, Environment, ModelId, Provider, TokenBudget, Usage, Workflow,
+};
 
 use crate::prompt::ForgePrompt;
 
@@ -13,6 +15,8 @@ pub struct UIState {
     pub cwd: PathBuf,
     pub conversation_id: Option<ConversationId>,
     pub usage: Usage,
+    /// Share of the context window used, reported after each request
+    pub token_budget: Option<TokenBudget>,
     pub operating_agent: AgentId,
     pub is_first: bool,
     pub model: Option<ModelId>,
@@ -41,6 +45,7 @@ impl UIState {
             cwd: env.cwd,
             conversation_id: Default::default(),
             usage: Default::default(),
+            token_budget: Default::default(),
             is_first: true,
             model: workflow.model,
             operating_agent,
@@ -54,6 +59,7 @@ impl From<UIState> for ForgePrompt {
         ForgePrompt {
             cwd: state.cwd,
             usage: Some(state.usage),
+            token_budget: state.token_budget,
             model: state.model,
             agent_id: state.operating_agent,
         }
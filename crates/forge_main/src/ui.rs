@@ -1,28 +1,43 @@
 use std::collections::BTreeMap;
 use std::fmt::Display;
+use std::net::SocketAddr;
+use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use anyhow::{Context, Result};
 use colored::Colorize;
 use convert_case::{Case, Casing};
 use forge_api::{
     API, AgentId, AppConfig, ChatRequest, ChatResponse, Conversation, ConversationId, Event,
-    InterruptionReason, Model, ModelId, Workflow,
+    InterruptionReason, McpPrompt, McpServeTransport, Model, ModelId, Notification, PipelineEvent,
+    ScheduledRun, SnapshotId, StepRun, StepStatus, Workflow,
 };
 use forge_display::{MarkdownFormat, TitleFormat};
-use forge_domain::{McpConfig, McpServerConfig, Provider, Scope};
+use forge_domain::{
+    AgentTemplate, Approval, BackgroundRequest, ExportFormat, ImportFormat, ImportedConversation,
+    LiveEvent, McpConfig, McpServerConfig, McpServerEvent, Provider, RecordedRequest, Retention,
+    Scope, ShareMode, WorkItemStatus,
+};
 use forge_fs::ForgeFS;
 use forge_spinner::SpinnerManager;
 use forge_tracker::ToolCallPayload;
 use merge::Merge;
 use serde::Deserialize;
 use serde_json::Value;
+use strum::IntoEnumIterator;
+use tokio::io::AsyncWriteExt;
+use tokio::task::{JoinHandle, block_in_place};
 use tokio_stream::StreamExt;
 
-use crate::cli::{Cli, McpCommand, TopLevelCommand, Transport};
-use crate::info::{Info, get_usage};
+use crate::cli::{
+    AgentCommand, AgentNewArgs, AttachArgs, Cli, GcArgs, ImportArgs, McpCommand, MemoryCommand,
+    ReplayArgs, RunArgs, ScheduleArgs, SessionsCommand, TopLevelCommand, Transport,
+};
+use crate::info::{Info, format_tags, get_usage};
 use crate::input::Console;
 use crate::model::{Command, ForgeCommandManager};
+use crate::reasoning::ReasoningBuffer;
 use crate::select::ForgeSelect;
 use crate::state::UIState;
 use crate::update::on_update;
@@ -59,6 +74,13 @@ pub struct UI<A, F: Fn() -> A> {
     command: Arc<ForgeCommandManager>,
     cli: Cli,
     spinner: SpinnerManager,
+    /// Reasoning of the model not yet printed, as it's printed a line at a time
+    reasoning: ReasoningBuffer,
+    /// Whether the reasoning of the model is left out of the output
+    hide_reasoning: bool,
+    /// Whether a turn is running, during which Ctrl+C steers the agent rather
+    /// than stopping it
+    turn_running: Arc<AtomicBool>,
     #[allow(dead_code)] // The guard is kept alive by being held in the struct
     _guard: forge_tracker::Guard,
 }
@@ -83,9 +105,11 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
         self.api = Arc::new((self.new_api)());
         self.init_state(false).await?;
         self.cli.conversation = None;
+        self.cli.resume = None;
         banner::display()?;
         self.trace_user();
         self.hydrate_caches();
+        self.print_notifications();
         Ok(())
     }
 
@@ -157,6 +181,9 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
             cli,
             command,
             spinner: SpinnerManager::new(),
+            reasoning: ReasoningBuffer::default(),
+            hide_reasoning: false,
+            turn_running: Default::default(),
             markdown: MarkdownFormat::new(),
             _guard: forge_tracker::init_tracing(env.log_path(), TRACKER.clone())?,
         })
@@ -182,6 +209,14 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
             return self.handle_subcommands(mcp).await;
         }
 
+        if let Some(profile) = self.cli.profile.clone() {
+            self.api.activate_profile(&profile).await?;
+        }
+
+        if let Some(path) = self.cli.events.clone() {
+            self.record_events(&path).await?;
+        }
+
         // Check for dispatch flag first
         if let Some(dispatch_json) = self.cli.event.clone() {
             return self.handle_dispatch(dispatch_json).await;
@@ -202,6 +237,7 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
 
         // Hydrate the models cache
         self.hydrate_caches();
+        self.print_notifications();
 
         // Get initial input from file or prompt
         let mut command = match &self.cli.command {
@@ -210,8 +246,9 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
         };
 
         loop {
+            let turn_running = self.turn_running.clone();
             tokio::select! {
-                _ = tokio::signal::ctrl_c() => {
+                _ = interrupted(turn_running) => {
                     tracing::info!("User interrupted operation with Ctrl+C");
                 }
                 result = self.on_command(command) => {
@@ -231,7 +268,9 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
                 }
             }
 
+            self.turn_running.store(false, Ordering::SeqCst);
             self.spinner.stop(None)?;
+            self.answer_background_requests().await?;
 
             // Centralized prompt call at the end of the loop
             command = self.prompt().await?;
@@ -239,6 +278,31 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
     }
 
     // Improve startup time by hydrating caches
+    /// Appends the lifecycle events of the turns to the file as JSON lines
+    /// until forge exits
+    async fn record_events(&self, path: &Path) -> Result<()> {
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        let mut events = self.api.lifecycle_events();
+        tokio::spawn(async move {
+            while let Some(update) = events.next().await {
+                let Ok(mut line) = serde_json::to_string(&update) else {
+                    continue;
+                };
+                line.push('\n');
+                if let Err(error) = file.write_all(line.as_bytes()).await {
+                    tracing::warn!(error = ?error, "Failed to record a lifecycle event");
+                    break;
+                }
+            }
+        });
+        Ok(())
+    }
+
     fn hydrate_caches(&self) {
         let api = self.api.clone();
         tokio::spawn(async move { api.models().await });
@@ -259,7 +323,14 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
                             add.args.clone(),
                             Some(parse_env(add.env.clone())),
                         ),
-                        Transport::Sse => McpServerConfig::new_sse(add.command_or_url.clone()),
+                        Transport::Sse => McpServerConfig::new_sse(
+                            add.command_or_url.clone(),
+                            parse_headers(add.headers.clone()),
+                        ),
+                        Transport::Http => McpServerConfig::new_http(
+                            add.command_or_url.clone(),
+                            parse_headers(add.headers.clone()),
+                        ),
                     };
                     // Command/URL already set in the constructor
 
@@ -320,6 +391,50 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
                         add_json.name
                     )))?;
                 }
+                McpCommand::Serve(serve) => {
+                    let transport = match serve.transport {
+                        // Nothing else may be written to stdout, it carries the protocol
+                        Transport::Stdio => McpServeTransport::Stdio,
+                        Transport::Sse => {
+                            // The tools run commands on this machine, so only local clients
+                            // are served
+                            let address = SocketAddr::from(([127, 0, 0, 1], serve.port));
+                            let transport = McpServeTransport::sse(address);
+                            if let Some(url) = transport.url() {
+                                self.writeln(TitleFormat::info(format!("Serving MCP at {url}")))?;
+                            }
+                            transport
+                        }
+                        Transport::Http => {
+                            anyhow::bail!("Forge can only be served over stdio or sse")
+                        }
+                    };
+                    self.api.serve_mcp(transport).await?;
+                }
+                McpCommand::Login(login) => {
+                    let authorization = self.api.mcp_authorize(&login.name).await?;
+                    open::that(authorization.url.as_str()).ok();
+                    self.writeln(TitleFormat::info(format!(
+                        "Authorize here: {}",
+                        authorization.url
+                    )))?;
+                    self.spinner.start(Some("Waiting for authorization"))?;
+
+                    self.api.mcp_await_authorization(authorization).await?;
+
+                    self.spinner.stop(None)?;
+                    self.writeln(TitleFormat::info(format!(
+                        "Authorized with server: {}",
+                        login.name
+                    )))?;
+                }
+                McpCommand::Logout(logout) => {
+                    self.api.mcp_deauthorize(&logout.name).await?;
+                    self.writeln(TitleFormat::info(format!(
+                        "Logged out of server: {}",
+                        logout.name
+                    )))?;
+                }
             },
             TopLevelCommand::Info => {
                 // Make sure to init model
@@ -328,7 +443,397 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
                 self.on_info().await?;
                 return Ok(());
             }
+            TopLevelCommand::Export(export) => {
+                let path = Path::new(&export.source);
+                let conversation = if path.is_file() {
+                    serde_json::from_str(ForgeFS::read_utf8(path.as_os_str()).await?.as_str())
+                        .context("Failed to parse Conversation")?
+                } else {
+                    self.api.resume_session(&export.source).await?
+                };
+                let content = conversation.export(export.format);
+
+                match export.output {
+                    Some(path) => {
+                        tokio::fs::write(&path, content).await?;
+                        self.writeln(
+                            TitleFormat::action("Conversation exported")
+                                .sub_title(path.display().to_string()),
+                        )?;
+                    }
+                    None => self.writeln(content.trim_end())?,
+                }
+            }
+            TopLevelCommand::Replay(replay) => self.on_replay(replay).await?,
+            TopLevelCommand::Import(import) => self.on_import(import).await?,
+            TopLevelCommand::Gc(gc) => self.on_gc(gc).await?,
+            TopLevelCommand::Attach(attach) => self.on_attach(attach).await?,
+            TopLevelCommand::Schedule(schedule) => self.on_schedule(schedule).await?,
+            TopLevelCommand::Run(run) => self.on_run(run).await?,
+            TopLevelCommand::Agent(agent) => match agent.command {
+                AgentCommand::New(new) => self.on_agent_new(new).await?,
+            },
+            TopLevelCommand::Memory(memory) => match memory.command {
+                MemoryCommand::List => {
+                    let memories = self.api.memories().await?;
+                    self.writeln(Info::from(memories.as_slice()))?;
+                }
+                MemoryCommand::Forget(forget) => {
+                    let memory = self.api.forget_memory(forget.id).await?;
+                    self.writeln(TitleFormat::info(format!("Forgot: {}", memory.fact)))?;
+                }
+                MemoryCommand::Edit(edit) => {
+                    let memory = self.api.edit_memory(edit.id, &edit.fact).await?;
+                    self.writeln(TitleFormat::info(format!("Remembered: {}", memory.fact)))?;
+                }
+            },
+            TopLevelCommand::Sessions(sessions) => match sessions.command {
+                SessionsCommand::List(list) => {
+                    let sessions = self.api.sessions(list.into()).await?;
+                    self.writeln(Info::from(sessions.as_slice()))?;
+                }
+                SessionsCommand::Rename(rename) => {
+                    let session = self
+                        .api
+                        .rename_session(&rename.session, &rename.name)
+                        .await?;
+                    self.writeln(TitleFormat::info(format!(
+                        "Renamed session {} to {}",
+                        session.id, rename.name
+                    )))?;
+                }
+                SessionsCommand::Tag(tag) => {
+                    let session = self.api.tag_session(&tag.session, &tag.tags).await?;
+                    self.writeln(TitleFormat::info(format!(
+                        "Tagged session {} with {}",
+                        session.id,
+                        format_tags(&session.tags)
+                    )))?;
+                }
+                SessionsCommand::Untag(untag) => {
+                    let session = self.api.untag_session(&untag.session, &untag.tags).await?;
+                    self.writeln(TitleFormat::info(format!(
+                        "Session {} is tagged with {}",
+                        session.id,
+                        format_tags(&session.tags)
+                    )))?;
+                }
+                SessionsCommand::Delete(delete) => {
+                    let session = self.api.delete_session(&delete.session).await?;
+                    self.writeln(TitleFormat::info(format!("Deleted session {}", session.id)))?;
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Runs the schedules until stopped, or the one named once
+    async fn on_schedule(&mut self, schedule: ScheduleArgs) -> anyhow::Result<()> {
+        if let Some(name) = schedule.run {
+            self.spinner.start(Some("Running"))?;
+            let run = self.api.run_schedule(&name).await?;
+            self.spinner.stop(None)?;
+            return self.on_scheduled_run(run);
+        }
+
+        self.writeln(
+            TitleFormat::info("Waiting for the schedules to run").sub_title("Ctrl+C to stop"),
+        )?;
+        let mut runs = self.api.run_schedules().await?;
+        while let Some(run) = runs.next().await {
+            self.on_scheduled_run(run?)?;
+        }
+        Ok(())
+    }
+
+    fn on_scheduled_run(&mut self, run: ScheduledRun) -> anyhow::Result<()> {
+        let title = format!("{} ({})", run.schedule, run.trigger);
+        let session = run.conversation_id.to_string();
+        match run.error {
+            Some(error) => self
+                .writeln(TitleFormat::error(format!("{title} failed: {error}")).sub_title(session)),
+            None => {
+                self.writeln(TitleFormat::action(format!("{title} completed")).sub_title(session))?;
+                match run.summary {
+                    Some(summary) => {
+                        let summary = self.markdown.render(&summary);
+                        self.writeln(summary)
+                    }
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Runs the steps of the pipeline, showing each of them once it's done
+    /// and asking for the approvals and answers of their agents
+    async fn on_run(&mut self, run: RunArgs) -> anyhow::Result<()> {
+        let inputs = run.inputs.into_iter().collect();
+        let mut events = self.api.run_pipeline(&run.pipeline, inputs).await?;
+        self.spinner.start(Some("Running"))?;
+        while let Some(event) = events.next().await {
+            self.spinner.stop(None)?;
+            match event? {
+                PipelineEvent::Request(request) => self.handle_chat_response(request).await?,
+                PipelineEvent::Step(step) => self.on_step_run(step)?,
+            }
+            self.spinner.start(Some("Running"))?;
+        }
+        self.spinner.stop(None)?;
+        self.writeln(TitleFormat::completion(format!(
+            "Pipeline {} completed",
+            run.pipeline
+        )))
+    }
+
+    fn on_step_run(&mut self, run: StepRun) -> anyhow::Result<()> {
+        let session = run
+            .conversation_id
+            .map(|id| id.to_string())
+            .unwrap_or_default();
+        match run.status {
+            StepStatus::Skipped => {
+                self.writeln(TitleFormat::debug(format!("{} skipped", run.step)))
+            }
+            StepStatus::Failed => self.writeln(
+                TitleFormat::error(format!(
+                    "{} failed: {}",
+                    run.step,
+                    run.error.unwrap_or_default()
+                ))
+                .sub_title(session),
+            ),
+            StepStatus::Completed => {
+                self.writeln(
+                    TitleFormat::action(format!("{} completed", run.step)).sub_title(session),
+                )?;
+                match run.output {
+                    Some(output) => {
+                        let output = self.markdown.render(&output);
+                        self.writeln(output)
+                    }
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Shows the turns of the conversation shared by another forge process,
+    /// reading a prompt after each of them when it's shared collaboratively
+    async fn on_attach(&mut self, attach: AttachArgs) -> anyhow::Result<()> {
+        let mut client = self.api.attach_session(attach.session.as_deref()).await?;
+        let mut collaborative = false;
+
+        while let Some(event) = client.next_event().await? {
+            let idle = match event {
+                LiveEvent::Attached { conversation_id, mode, busy } => {
+                    collaborative = mode == ShareMode::Collaborative;
+                    let mode = if collaborative {
+                        "collaborative"
+                    } else {
+                        "read-only"
+                    };
+                    self.writeln(
+                        TitleFormat::info(format!("Attached to {conversation_id}")).sub_title(mode),
+                    )?;
+                    if busy {
+                        self.spinner.start(None)?;
+                    }
+                    !busy
+                }
+                LiveEvent::Prompt { content } => {
+                    self.spinner.stop(None)?;
+                    self.writeln(TitleFormat::action("Prompt").sub_title(content))?;
+                    self.spinner.start(None)?;
+                    false
+                }
+                LiveEvent::Waiting { message } => {
+                    self.spinner.stop(None)?;
+                    self.writeln(TitleFormat::info("Waiting for the host").sub_title(message))?;
+                    false
+                }
+                LiveEvent::Error { message } => {
+                    self.writeln(TitleFormat::error(message))?;
+                    false
+                }
+                LiveEvent::TurnEnd => {
+                    self.spinner.stop(None)?;
+                    true
+                }
+                event => {
+                    if let Some(response) = event.into_response() {
+                        self.handle_chat_response(response).await?;
+                    }
+                    false
+                }
+            };
+
+            // An empty prompt waits for the next turn of the host
+            if collaborative && idle {
+                match ForgeSelect::input("Prompt, or Enter to keep watching")? {
+                    Some(prompt) if !prompt.trim().is_empty() => client.prompt(prompt).await?,
+                    Some(_) => {}
+                    None => break,
+                }
+            }
         }
+
+        self.spinner.stop(None)?;
+        self.writeln(TitleFormat::info("Detached"))
+    }
+
+    async fn on_gc(&mut self, gc: GcArgs) -> anyhow::Result<()> {
+        let workflow = self.api.read_workflow(self.cli.workflow.as_deref()).await?;
+        let mut retention = workflow.retention.unwrap_or_default();
+        retention.max_age_days = gc.max_age_days.or(retention.max_age_days);
+        retention.max_disk_usage_mb = gc.max_disk_usage_mb.or(retention.max_disk_usage_mb);
+        if !retention.is_set() {
+            return self.writeln(TitleFormat::info(
+                "No retention limits, set them under `retention` in forge.yaml or pass --max-age or --max-size",
+            ));
+        }
+
+        let report = self
+            .api
+            .prune(&retention, self.state.conversation_id, gc.dry_run)
+            .await?;
+        self.writeln(Info::from(&report))
+    }
+
+    async fn on_agent_new(&mut self, args: AgentNewArgs) -> anyhow::Result<()> {
+        let template = match args.template {
+            Some(template) => template,
+            None => {
+                match ForgeSelect::select("Select a template:", AgentTemplate::iter().collect())
+                    .prompt()?
+                {
+                    Some(template) => template,
+                    None => return Ok(()),
+                }
+            }
+        };
+        let id = AgentId::new(args.id.unwrap_or_else(|| template.to_string()));
+
+        let workflow = self.api.read_workflow(self.cli.workflow.as_deref()).await?;
+        if workflow.agents.iter().any(|agent| agent.id == id) {
+            anyhow::bail!("Agent {id} already exists in forge.yaml, choose another id with --id");
+        }
+
+        let mut agent = template.agent(id.clone());
+        if let Some(model) = args.model {
+            agent = agent.model(ModelId::new(model));
+        }
+        if let Some(rules) = args.rules {
+            agent = agent.custom_rules(rules);
+        }
+        self.api
+            .update_workflow(self.cli.workflow.as_deref(), |workflow| {
+                workflow.agents.push(agent)
+            })
+            .await?;
+
+        self.writeln(
+            TitleFormat::action(format!("Added agent {id} to forge.yaml"))
+                .sub_title(template.to_string()),
+        )
+    }
+
+    async fn on_import(&mut self, import: ImportArgs) -> anyhow::Result<()> {
+        let content = ForgeFS::read_utf8(import.path.as_os_str()).await?;
+        let format = import
+            .format
+            .map(ImportFormat::from)
+            .unwrap_or_else(|| ImportFormat::detect(&content));
+        let imported = ImportedConversation::parse(&content, format)
+            .context("Failed to parse the conversations")?;
+        if imported.is_empty() {
+            return self.writeln(TitleFormat::info("No conversations to import"));
+        }
+
+        let workflow = self.init_state(false).await?;
+        for conversation in imported {
+            let mut session = self.api.init_conversation(workflow.clone()).await?;
+            session.context = Some(conversation.context);
+            session.tags = vec!["imported".to_string()];
+            self.api.save_session(&session).await?;
+
+            // Titles aren't unique, the session stays unnamed when the title
+            // is taken
+            let id = session.id.into_string();
+            let name = match conversation.title {
+                Some(title) => self
+                    .api
+                    .rename_session(&id, &title)
+                    .await
+                    .ok()
+                    .and_then(|session| session.name),
+                None => None,
+            };
+            self.writeln(TitleFormat::action("Imported").sub_title(name.unwrap_or(id)))?;
+        }
+
+        self.writeln(TitleFormat::info(
+            "Resume an imported session with forge --resume <id or name>",
+        ))
+    }
+
+    /// Sends the recorded requests again, showing the answer recorded for
+    /// each of them next to the new one
+    async fn on_replay(&mut self, replay: ReplayArgs) -> anyhow::Result<()> {
+        let path = Path::new(&replay.source);
+        let requests = if path.extension().is_some_and(|ext| ext == "jsonl") {
+            let dump = ForgeFS::read_utf8(path.as_os_str()).await?;
+            vec![RecordedRequest::from_dump(&dump).context("Failed to parse the dump")?]
+        } else if path.is_file() {
+            let conversation: Conversation =
+                serde_json::from_str(ForgeFS::read_utf8(path.as_os_str()).await?.as_str())
+                    .context("Failed to parse Conversation")?;
+            RecordedRequest::from_conversation(&conversation)
+        } else {
+            let conversation = self.api.resume_session(&replay.source).await?;
+            RecordedRequest::from_conversation(&conversation)
+        };
+
+        if requests.is_empty() {
+            return self.writeln(TitleFormat::info("No requests to replay"));
+        }
+
+        self.init_provider().await?;
+        let total = requests.len();
+        for (index, request) in requests.into_iter().enumerate() {
+            let model = replay
+                .model
+                .clone()
+                .map(ModelId::new)
+                .or(request.model)
+                .ok_or_else(|| anyhow::anyhow!("No model was recorded, pass one with --model"))?;
+
+            self.spinner.start(Some("Replaying"))?;
+            let answer = self.api.replay(&model, request.context).await;
+            self.spinner.stop(None)?;
+            let answer = answer?;
+
+            self.writeln(
+                TitleFormat::action(format!("Request {}/{total}", index + 1))
+                    .sub_title(model.as_str()),
+            )?;
+            if let Some(recorded) = request.answer {
+                self.writeln(TitleFormat::info("Recorded answer"))?;
+                self.writeln(recorded.trim())?;
+            }
+            self.writeln(TitleFormat::info("Replayed answer"))?;
+            self.writeln(answer.content.trim())?;
+            if !answer.tool_calls.is_empty() {
+                let tools = answer
+                    .tool_calls
+                    .iter()
+                    .map(|call| call.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                self.writeln(TitleFormat::info(format!("Tools called: {tools}")))?;
+            }
+        }
+
         Ok(())
     }
 
@@ -373,10 +878,26 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
             Command::Usage => {
                 self.on_usage().await?;
             }
-            Command::Message(ref content) => {
-                self.spinner.start(None)?;
-                self.on_message(Some(content.clone())).await?;
+            Command::Stats => {
+                self.spinner.start(Some("Loading Stats"))?;
+                let mut info = Info::new();
+                if let Some(id) = self.state.conversation_id {
+                    info = info.extend(Info::from(&self.api.session_stats(&id).await?));
+                }
+                let stats = self.api.tool_stats().await?;
+                self.writeln(info.extend(Info::from(stats.as_slice())))?;
+                self.spinner.stop(None)?;
             }
+            // A message ending with `&` is run in the background
+            Command::Message(ref content) => match content.trim_end().strip_suffix('&') {
+                Some(task) if !task.trim().is_empty() => {
+                    self.on_background_message(task.trim().to_string()).await?;
+                }
+                _ => {
+                    self.spinner.start(None)?;
+                    self.on_message(Some(content.clone())).await?;
+                }
+            },
             Command::Forge => {
                 self.on_agent_change(AgentId::FORGE).await?;
             }
@@ -406,6 +927,9 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
                 self.spinner.start(None)?;
                 self.on_custom_event(event.into()).await?;
             }
+            Command::McpPrompt(prompt, value) => {
+                self.on_mcp_prompt(prompt, value).await?;
+            }
             Command::Model => {
                 self.on_model_selection().await?;
             }
@@ -485,6 +1009,42 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
                 self.spinner.start(None)?;
                 self.on_message(None).await?;
             }
+            Command::Profile(name) => {
+                self.on_profile(name).await?;
+            }
+            Command::DryRun => {
+                self.on_dry_run().await?;
+            }
+            Command::Plan => {
+                self.on_plan().await?;
+            }
+            Command::Reasoning => {
+                self.on_reasoning()?;
+            }
+            Command::Undo(argument) => {
+                self.on_undo(argument).await?;
+            }
+            Command::Mcp(argument) => {
+                self.on_mcp(argument).await?;
+            }
+            Command::Summarize(argument) => {
+                self.on_summarize(argument).await?;
+            }
+            Command::Pin(path) => {
+                self.on_pin(path).await?;
+            }
+            Command::Unpin(path) => {
+                self.on_unpin(path).await?;
+            }
+            Command::Export(path) => {
+                self.on_export(path).await?;
+            }
+            Command::Context(argument) => {
+                self.on_context(argument).await?;
+            }
+            Command::RepoMap => {
+                self.on_repo_map().await?;
+            }
         }
 
         Ok(false)
@@ -571,6 +1131,315 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
         Ok(())
     }
 
+    async fn on_profile(&mut self, name: Option<String>) -> Result<()> {
+        let name = match name {
+            Some(name) => name,
+            None => {
+                let workflow = self.active_workflow().await?;
+                let mut profiles = workflow.profiles.into_keys().collect::<Vec<_>>();
+                if profiles.is_empty() {
+                    self.writeln(TitleFormat::error("No profiles defined in forge.yaml"))?;
+                    return Ok(());
+                }
+                profiles.sort();
+
+                match ForgeSelect::select("Select a profile:", profiles).prompt()? {
+                    Some(name) => name,
+                    None => return Ok(()),
+                }
+            }
+        };
+
+        let profile = self.api.activate_profile(&name).await?;
+        self.state.provider = Some(self.api.provider().await?);
+
+        // Switch the ongoing conversation to the model of the profile
+        if let Some(model) = profile.model {
+            if let Some(conversation_id) = self.state.conversation_id
+                && let Some(mut conversation) = self.api.conversation(&conversation_id).await?
+            {
+                conversation.set_model(&model)?;
+                self.api.upsert_conversation(conversation).await?;
+            }
+            self.update_model(model);
+        }
+
+        self.writeln(TitleFormat::action(format!("Switched to profile: {name}")))?;
+        Ok(())
+    }
+
+    /// Toggles dry-run mode for the current conversation
+    async fn on_dry_run(&mut self) -> Result<()> {
+        let conversation_id = self.init_conversation().await?;
+        if let Some(mut conversation) = self.api.conversation(&conversation_id).await? {
+            conversation.dry_run = !conversation.dry_run;
+            let status = if conversation.dry_run { "on" } else { "off" };
+            self.api.upsert_conversation(conversation).await?;
+            self.writeln(TitleFormat::action(format!("Dry-run mode is {status}")))?;
+        }
+        Ok(())
+    }
+
+    /// Toggles plan mode for the current conversation
+    async fn on_plan(&mut self) -> Result<()> {
+        let conversation_id = self.init_conversation().await?;
+        if let Some(mut conversation) = self.api.conversation(&conversation_id).await? {
+            conversation.plan_mode = !conversation.plan_mode;
+            let status = if conversation.plan_mode { "on" } else { "off" };
+            self.api.upsert_conversation(conversation).await?;
+            self.writeln(TitleFormat::action(format!("Plan mode is {status}")))?;
+        }
+        Ok(())
+    }
+
+    /// Toggles showing the reasoning of the model
+    fn on_reasoning(&mut self) -> Result<()> {
+        self.hide_reasoning = !self.hide_reasoning;
+        let status = if self.hide_reasoning {
+            "hidden"
+        } else {
+            "shown"
+        };
+        self.writeln(TitleFormat::action(format!("Reasoning is {status}")))
+    }
+
+    /// Shows a summary of the conversation, and continues the conversation from
+    /// it when the argument is "replace"
+    async fn on_summarize(&mut self, argument: Option<String>) -> Result<()> {
+        let replace = match argument.as_deref() {
+            None => false,
+            Some("replace") => true,
+            Some(_) => {
+                self.writeln(TitleFormat::error("Usage: /summarize [replace]"))?;
+                return Ok(());
+            }
+        };
+
+        let conversation_id = self.init_conversation().await?;
+        self.spinner.start(Some("Summarizing"))?;
+        let summary = self
+            .api
+            .summarize_conversation(&conversation_id, replace)
+            .await;
+        self.spinner.stop(None)?;
+
+        let Some(summary) = summary? else {
+            self.writeln(TitleFormat::error("Nothing to summarize yet"))?;
+            return Ok(());
+        };
+        let summary = self.markdown.render(&summary);
+        self.writeln(summary)?;
+        if replace {
+            self.writeln(TitleFormat::action(
+                "Replaced the history of the conversation with the summary",
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Pins the file at the path, or the last reply of the agent when no path
+    /// is given
+    async fn on_pin(&mut self, path: Option<String>) -> Result<()> {
+        let conversation_id = self.init_conversation().await?;
+        let Some(mut conversation) = self.api.conversation(&conversation_id).await? else {
+            return Ok(());
+        };
+
+        let status = match path {
+            Some(path) => {
+                let path = self.api.environment().cwd.join(path);
+                if !path.is_file() {
+                    self.writeln(TitleFormat::error(format!(
+                        "File not found: {}",
+                        path.display()
+                    )))?;
+                    return Ok(());
+                }
+                let path = path.display().to_string();
+                if !conversation.pins.pin_file(&path) {
+                    self.writeln(TitleFormat::info(format!("Already pinned: {path}")))?;
+                    return Ok(());
+                }
+                format!("Pinned {path}")
+            }
+            None => {
+                if !conversation.pin_last_reply() {
+                    self.writeln(TitleFormat::error("No reply of the agent to pin"))?;
+                    return Ok(());
+                }
+                "Pinned the last reply of the agent".to_string()
+            }
+        };
+
+        self.api.upsert_conversation(conversation).await?;
+        self.writeln(TitleFormat::action(status))?;
+        Ok(())
+    }
+
+    /// Unpins the file at the path, or everything that's pinned when no path is
+    /// given
+    async fn on_unpin(&mut self, path: Option<String>) -> Result<()> {
+        let conversation_id = self.init_conversation().await?;
+        let Some(mut conversation) = self.api.conversation(&conversation_id).await? else {
+            return Ok(());
+        };
+
+        let status = match path {
+            Some(path) => {
+                let path = self.api.environment().cwd.join(path).display().to_string();
+                if !conversation.pins.unpin_file(&path) {
+                    self.writeln(TitleFormat::error(format!("Not pinned: {path}")))?;
+                    return Ok(());
+                }
+                format!("Unpinned {path}")
+            }
+            None => {
+                conversation.pins = Default::default();
+                "Unpinned everything".to_string()
+            }
+        };
+
+        self.api.upsert_conversation(conversation).await?;
+        self.writeln(TitleFormat::action(status))?;
+        Ok(())
+    }
+
+    /// Writes the context of the conversation to a file, at the path when one
+    /// is given. The format follows the extension of the path, markdown by
+    /// default.
+    async fn on_export(&mut self, path: Option<String>) -> Result<()> {
+        let Some(conversation_id) = self.state.conversation_id else {
+            return Err(anyhow::anyhow!("No conversation initiated yet"))
+                .context("Could not export the context");
+        };
+
+        let path = path.unwrap_or_else(|| {
+            let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
+            format!("{timestamp}-context.md")
+        });
+        let path = self.api.environment().cwd.join(path);
+        let format = ExportFormat::from_path(&path).unwrap_or_default();
+        let content = self.api.export_context(&conversation_id, format).await?;
+        tokio::fs::write(&path, content).await?;
+
+        self.writeln(
+            TitleFormat::action("Context exported".to_string())
+                .sub_title(path.display().to_string()),
+        )?;
+        Ok(())
+    }
+
+    /// Shows which messages changed in the context between the last two
+    /// requests of the conversation
+    async fn on_context(&mut self, argument: Option<String>) -> Result<()> {
+        if argument.as_deref() != Some("diff") {
+            self.writeln(TitleFormat::error("Usage: /context diff"))?;
+            return Ok(());
+        }
+
+        let Some(conversation_id) = self.state.conversation_id else {
+            return Err(anyhow::anyhow!("No conversation initiated yet"))
+                .context("Could not diff the context");
+        };
+
+        match self.api.context_diff(&conversation_id).await? {
+            None => self.writeln(TitleFormat::info(
+                "Two requests are needed to diff the context",
+            ))?,
+            Some(diff) if diff.is_empty() => {
+                self.writeln(TitleFormat::info("Context is unchanged"))?
+            }
+            Some(diff) => {
+                self.writeln(TitleFormat::action("Context diff"))?;
+                self.writeln(diff.to_string())?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn on_repo_map(&mut self) -> Result<()> {
+        let conversation_id = self.init_conversation().await?;
+        self.spinner.start(Some("Mapping"))?;
+        let repo_map = self.api.refresh_repo_map(&conversation_id).await;
+        self.spinner.stop(None)?;
+
+        match repo_map? {
+            None => self.writeln(TitleFormat::error("No source file to map"))?,
+            Some(repo_map) => {
+                self.writeln(repo_map)?;
+                self.writeln(TitleFormat::action(
+                    "Refreshed the repo map given to the agent",
+                ))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Undoes the last file change, restores the given snapshot or lists the
+    /// snapshots when the argument is "list"
+    async fn on_undo(&mut self, argument: Option<String>) -> Result<()> {
+        let snapshot = match argument.as_deref() {
+            None => self.api.undo_last().await?,
+            Some("list") => {
+                let snapshots = self.api.snapshots().await?;
+                if snapshots.is_empty() {
+                    self.writeln(TitleFormat::error("No snapshots found"))?;
+                } else {
+                    self.writeln(Info::from(snapshots.as_slice()))?;
+                }
+                return Ok(());
+            }
+            Some(id) => {
+                let id = SnapshotId::parse(id)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid snapshot id: {id}"))?;
+                self.api.restore(&id).await?
+            }
+        };
+
+        let action = if snapshot.hash.is_some() {
+            "Restored"
+        } else {
+            "Removed"
+        };
+        self.writeln(TitleFormat::action(format!("{action} {}", snapshot.path)))?;
+        Ok(())
+    }
+
+    async fn on_mcp(&mut self, argument: Option<String>) -> Result<()> {
+        if argument.as_deref() != Some("reload") {
+            self.writeln(TitleFormat::error("Usage: /mcp reload"))?;
+            return Ok(());
+        }
+
+        self.spinner.start(Some("Reloading MCP servers"))?;
+        let reload = self.api.reload_mcp().await;
+        self.spinner.stop(None)?;
+        let reload = reload?;
+
+        // Prompts of the servers that were started or stopped change the commands
+        let prompts = self.api.mcp_prompts().await.unwrap_or_else(|error| {
+            tracing::warn!(error = ?error, "Failed to list MCP prompts");
+            Vec::new()
+        });
+        self.command.register_prompts(prompts);
+
+        if reload.is_empty() {
+            self.writeln(TitleFormat::info("MCP configuration is unchanged"))?;
+        }
+        for (action, servers) in [
+            ("Started", &reload.added),
+            ("Stopped", &reload.removed),
+            ("Restarted", &reload.restarted),
+        ] {
+            for server in servers {
+                self.writeln(TitleFormat::action(format!(
+                    "{action} MCP server: {server}"
+                )))?;
+            }
+        }
+        Ok(())
+    }
+
     // Handle dispatching events from the CLI
     async fn handle_dispatch(&mut self, json: String) -> Result<()> {
         // Initialize the conversation
@@ -604,23 +1473,60 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
                     self.update_model(conversation.main_model()?);
                     self.api.upsert_conversation(conversation).await?;
                     conversation_id
+                } else if let Some(ref session) = self.cli.resume {
+                    let conversation = self.api.resume_session(session).await?;
+
+                    let conversation_id = conversation.id;
+                    self.prune_in_background(workflow.retention.clone(), Some(conversation_id));
+                    self.state.conversation_id = Some(conversation_id);
+                    self.update_model(conversation.main_model()?);
+                    self.api.upsert_conversation(conversation).await?;
+                    conversation_id
                 } else {
-                    let conversation = self.api.init_conversation(workflow).await?;
+                    let mut conversation = self.api.init_conversation(workflow).await?;
+                    if !self.cli.tags.is_empty() || self.cli.plan {
+                        conversation.tags = self.cli.tags.clone();
+                        conversation.plan_mode = self.cli.plan;
+                        self.api.upsert_conversation(conversation.clone()).await?;
+                    }
                     self.state.conversation_id = Some(conversation.id);
                     self.update_model(conversation.main_model()?);
                     conversation.id
                 };
 
+                if let Some(mode) = self.cli.share {
+                    self.api.share_session(&id, mode.into()).await?;
+                    self.writeln(
+                        TitleFormat::info("Session shared")
+                            .sub_title(format!("attach with forge attach {id}")),
+                    )?;
+                }
+
                 Ok(id)
             }
         }
     }
 
+    /// Removes the stored data beyond the retention limits, except the session
+    /// kept, without holding up the start of the session
+    fn prune_in_background(&self, retention: Option<Retention>, keep: Option<ConversationId>) {
+        let Some(retention) = retention.filter(Retention::is_set) else {
+            return;
+        };
+        let api = self.api.clone();
+        tokio::spawn(async move {
+            if let Err(error) = api.prune(&retention, keep, false).await {
+                tracing::warn!(error = ?error, "Failed to prune the stored data");
+            }
+        });
+    }
+
     /// Initialize the state of the UI
     async fn init_state(&mut self, first: bool) -> Result<Workflow> {
         let provider = self.init_provider().await?;
         let mut workflow = self.api.read_workflow(self.cli.workflow.as_deref()).await?;
-        if workflow.model.is_none() {
+        let profile_model = self.profile_model(&workflow).await;
+        if workflow.model.is_none() && profile_model.is_none() {
             workflow.model = Some(
                 self.select_model()
                     .await?
@@ -632,16 +1538,59 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
         if first {
             // only call on_update if this is the first initialization
             on_update(self.api.clone(), base_workflow.updates.as_ref()).await;
+            // A resumed session is only known once loaded, so pruning waits
+            // for it
+            if self.cli.resume.is_none() {
+                self.prune_in_background(base_workflow.retention.clone(), None);
+            }
         }
         self.api
             .write_workflow(self.cli.workflow.as_deref(), &workflow)
             .await?;
 
+        // The model of the active profile takes precedence, without being
+        // persisted into the workflow
+        if let Some(model) = profile_model {
+            workflow.model = Some(model.clone());
+            base_workflow.model = Some(model);
+        }
+
+        // Nor is dry-run mode when it is enabled from the command line
+        if self.cli.dry_run {
+            workflow.dry_run = Some(true);
+            base_workflow.dry_run = Some(true);
+        }
+
+        // Nor are the hard limits given on the command line, which apply to all
+        // the agents
+        for agent in workflow.agents.iter_mut() {
+            if let Some(max_turns) = self.cli.max_turns {
+                agent.max_turns = Some(max_turns);
+            }
+            if let Some(max_cost) = self.cli.max_cost {
+                agent.max_cost_usd = Some(max_cost);
+            }
+            if let Some(max_duration) = self.cli.max_duration {
+                agent.max_duration = Some(max_duration);
+            }
+        }
+
         self.command.register_all(&base_workflow);
+        let prompts = self.api.mcp_prompts().await.unwrap_or_else(|error| {
+            tracing::warn!(error = ?error, "Failed to load the prompts of the MCP servers");
+            Vec::new()
+        });
+        self.command.register_prompts(prompts);
         self.state = UIState::new(self.api.environment(), base_workflow).provider(provider);
 
         Ok(workflow)
     }
+    /// Returns the model configured for the active profile, if any
+    async fn profile_model(&self, workflow: &Workflow) -> Option<ModelId> {
+        let name = self.api.app_config().await.ok()?.active_profile?;
+        workflow.profiles.get(&name)?.model.clone()
+    }
+
     async fn init_provider(&mut self) -> Result<Provider> {
         match self.api.provider().await {
             // Use the forge key if available in the config.
@@ -662,9 +1611,14 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
     }
     async fn login(&mut self) -> Result<()> {
         let auth = self.api.init_login().await?;
-        open::that(auth.auth_url.as_str()).ok();
+        let url = auth
+            .verification_uri_complete
+            .as_deref()
+            .unwrap_or(auth.verification_uri.as_str());
+        open::that(url).ok();
+        self.writeln(TitleFormat::info(format!("Login here: {url}").as_str()))?;
         self.writeln(TitleFormat::info(
-            format!("Login here: {}", auth.auth_url).as_str(),
+            format!("Enter code: {}", auth.user_code).as_str(),
         ))?;
         self.spinner.start(Some("Waiting for login to complete"))?;
 
@@ -694,24 +1648,142 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
         self.on_chat(chat).await
     }
 
+    /// Runs the task on a fork of the conversation, leaving the user free to
+    /// keep chatting until a notification tells them it's done
+    async fn on_background_message(&mut self, task: String) -> Result<()> {
+        let conversation_id = self.init_conversation().await?;
+        let agent = self.state.operating_agent.clone();
+        let task = self
+            .api
+            .chat_in_background(&conversation_id, agent, task)
+            .await?;
+
+        let running = self.api.background_tasks().len();
+        self.writeln(
+            TitleFormat::info("Running in the background")
+                .sub_title(format!("{} ({running} running)", task.task)),
+        )?;
+        Ok(())
+    }
+
+    /// Prints the notifications of the background tasks as soon as they are
+    /// sent, above the prompt if the user is typing
+    fn print_notifications(&self) {
+        let api = self.api.clone();
+        let printer = self.console.printer();
+        tokio::spawn(async move {
+            while let Some(notification) = api.next_notification().await {
+                let worktree = notification.task().worktree.display().to_string();
+                let mut lines = match &notification {
+                    Notification::TaskFailed { .. } => {
+                        TitleFormat::error(notification.to_string()).sub_title(worktree)
+                    }
+                    Notification::TaskCompleted { .. } => {
+                        TitleFormat::action(notification.to_string()).sub_title(worktree)
+                    }
+                    Notification::TaskWaiting { .. } => {
+                        TitleFormat::info(notification.to_string()).sub_title(worktree)
+                    }
+                }
+                .to_string();
+                if let Notification::TaskCompleted { summary: Some(summary), .. } = &notification {
+                    lines = format!("{lines}\n{}", MarkdownFormat::new().render(summary));
+                }
+                // The printer blocks while its queue is full, until the next prompt
+                if block_in_place(|| printer.print(lines)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Asks the user the approvals and questions the background tasks are
+    /// waiting for
+    async fn answer_background_requests(&mut self) -> Result<()> {
+        for BackgroundRequest { task, request } in self.api.background_requests() {
+            self.writeln(
+                TitleFormat::info(format!("Background task of {}", task.agent))
+                    .sub_title(task.task),
+            )?;
+            self.handle_chat_response(request).await?;
+        }
+        Ok(())
+    }
+
     async fn on_chat(&mut self, chat: ChatRequest) -> Result<()> {
+        let conversation_id = chat.conversation_id;
         let mut stream = self.api.chat(chat).await?;
 
-        while let Some(message) = stream.next().await {
-            match message {
-                Ok(message) => self.handle_chat_response(message).await?,
-                Err(err) => {
+        self.turn_running.store(true, Ordering::SeqCst);
+        // The instruction is typed in a blocking prompt of its own, while the
+        // responses of the agent keep being received and are held until it's
+        // done, so that they don't garble it
+        let mut steering: Option<JoinHandle<Result<Option<String>>>> = None;
+        let mut held = Vec::new();
+        let mut finished = false;
+        loop {
+            tokio::select! {
+                message = stream.next(), if !finished => match message {
+                    Some(Ok(message)) if steering.is_some() => held.push(message),
+                    Some(Ok(message)) => self.handle_chat_response(message).await?,
+                    Some(Err(err)) => {
+                        self.spinner.stop(None)?;
+                        return Err(err);
+                    }
+                    None if steering.is_some() => finished = true,
+                    None => break,
+                },
+                message = async { steering.as_mut().unwrap().await }, if steering.is_some() => {
+                    steering = None;
+                    let steered = self.on_steer(&conversation_id, message??, finished).await?;
+                    for message in held.drain(..) {
+                        self.handle_chat_response(message).await?;
+                    }
+                    if !steered || finished {
+                        break;
+                    }
+                }
+                _ = tokio::signal::ctrl_c(), if steering.is_none() => {
                     self.spinner.stop(None)?;
-                    return Err(err);
+                    steering = Some(tokio::task::spawn_blocking(|| {
+                        ForgeSelect::input("Steer the agent (leave empty to stop)")
+                    }));
                 }
             }
         }
+        self.turn_running.store(false, Ordering::SeqCst);
 
+        if let Some(line) = self.reasoning.flush() {
+            self.writeln(line.dimmed())?;
+        }
         self.spinner.stop(None)?;
 
         Ok(())
     }
 
+    /// Steers the agent with the instruction the user typed, returning false
+    /// to stop the turn when none is given. An instruction typed once the turn
+    /// is over can't be given to the agent anymore.
+    async fn on_steer(
+        &mut self,
+        conversation_id: &ConversationId,
+        message: Option<String>,
+        finished: bool,
+    ) -> Result<bool> {
+        let Some(message) = message.filter(|message| !message.trim().is_empty()) else {
+            tracing::info!("User interrupted operation with Ctrl+C");
+            return Ok(false);
+        };
+        if finished {
+            self.writeln(TitleFormat::error("Not steered, the agent was done").sub_title(message))?;
+            return Ok(false);
+        }
+
+        self.api.steer(conversation_id, message).await?;
+        self.spinner.start(None)?;
+        Ok(true)
+    }
+
     /// Modified version of handle_dump that supports HTML format
     async fn on_dump(&mut self, format: Option<String>) -> Result<()> {
         if let Some(conversation_id) = self.state.conversation_id {
@@ -759,6 +1831,13 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
     }
 
     async fn handle_chat_response(&mut self, message: ChatResponse) -> Result<()> {
+        // The reasoning is over once anything else is streamed
+        if !matches!(message, ChatResponse::Reasoning { .. })
+            && let Some(line) = self.reasoning.flush()
+        {
+            self.writeln(line.dimmed())?;
+        }
+
         match message {
             ChatResponse::Text { mut text, is_complete, is_md } => {
                 if is_complete && !text.trim().is_empty() {
@@ -798,6 +1877,8 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
                     return Ok(());
                 }
             }
+            // Already written to the terminal while the command ran
+            ChatResponse::CommandOutput(_) => {}
             ChatResponse::Usage(mut usage) => {
                 // accumulate the cost
                 usage.cost = usage
@@ -805,6 +1886,21 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
                     .map(|cost| cost + self.state.usage.cost.as_ref().map_or(0.0, |c| *c));
                 self.state.usage = usage;
             }
+            ChatResponse::TokenBudget(budget) => {
+                if let Some(compaction) = budget.compaction.as_ref() {
+                    self.writeln(
+                        TitleFormat::action(format!(
+                            "Context compacted from ~{} to ~{} tokens",
+                            compaction.before, compaction.after
+                        ))
+                        .sub_title(format!(
+                            "earlier messages were summarized as {}",
+                            compaction.trigger
+                        )),
+                    )?;
+                }
+                self.state.token_budget = Some(budget);
+            }
             ChatResponse::RetryAttempt { cause, duration: _ } => {
                 if !self.api.environment().retry_config.suppress_retry_errors {
                     self.spinner.start(Some("Retrying"))?;
@@ -821,16 +1917,85 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
                     InterruptionReason::MaxToolFailurePerTurnLimitReached { limit } => {
                         format!("Maximum tool failure limit ({limit}) reached for this turn")
                     }
+                    // Hard limits abort the turn, there's nothing to continue
+                    InterruptionReason::TurnLimitReached { limit, partial } => {
+                        self.writeln(
+                            TitleFormat::error(format!("Turn aborted, limit of {limit} reached"))
+                                .sub_title(partial.to_string()),
+                        )?;
+                        if let Some(message) = partial.last_message {
+                            self.writeln(message.dimmed())?;
+                        }
+                        return Ok(());
+                    }
                 };
 
                 self.writeln(TitleFormat::action(title))?;
                 self.should_continue().await?;
             }
             ChatResponse::Reasoning { content } => {
-                if !content.trim().is_empty() {
-                    self.writeln(content.dimmed())?;
+                if !self.hide_reasoning {
+                    for line in self.reasoning.push(&content) {
+                        self.writeln(line.dimmed())?;
+                    }
+                }
+            }
+            ChatResponse::ProviderWarning(warning) => {
+                self.writeln(TitleFormat::error(warning.to_string()))?;
+            }
+            ChatResponse::McpServer(event) => match event {
+                McpServerEvent::Unavailable { .. } => {
+                    self.writeln(TitleFormat::error(event.to_string()))?;
+                }
+                McpServerEvent::Restored { .. } => {
+                    self.writeln(TitleFormat::info(event.to_string()))?;
+                }
+            },
+            ChatResponse::ApprovalRequest { message, options, reply, .. } => {
+                self.spinner.stop(None)?;
+                let approval = ForgeSelect::select(message, options)
+                    .prompt()?
+                    .unwrap_or(Approval::Reject);
+                reply.send(approval);
+            }
+            ChatResponse::Question { question, options, multiple, reply } => {
+                self.spinner.stop(None)?;
+                let answer = if options.is_empty() {
+                    ForgeSelect::input(question)?
+                } else if multiple {
+                    ForgeSelect::select_many(question, options)?.map(|selected| selected.join(", "))
+                } else {
+                    ForgeSelect::select(question, options).prompt()?
+                };
+                match answer {
+                    Some(answer) => {
+                        reply.send(answer);
+                    }
+                    None => reply.cancel(),
                 }
             }
+            ChatResponse::Handoff { from, to } => {
+                self.spinner.stop(None)?;
+                // The next messages go to the agent the conversation was handed off to
+                self.state.operating_agent = to.clone();
+                self.writeln(TitleFormat::action(format!(
+                    "{} handed off to {}",
+                    from.as_str().to_case(Case::UpperSnake).bold(),
+                    to.as_str().to_case(Case::UpperSnake).bold()
+                )))?;
+                self.spinner.start(None)?;
+            }
+            ChatResponse::WorkItem(update) => {
+                self.spinner.stop(None)?;
+                let title = format!("[{}/{}] {}", update.index, update.total, update.item);
+                let title = match update.status {
+                    WorkItemStatus::Running => TitleFormat::debug(title),
+                    WorkItemStatus::Completed => TitleFormat::completion(title),
+                    _ => TitleFormat::error(title),
+                };
+                self.writeln(title.sub_title(update.status.to_string()))?;
+                self.spinner.start(None)?;
+            }
         }
         Ok(())
     }
@@ -853,6 +2018,48 @@ impl<A: API + 'static, F: Fn() -> A> UI<A, F> {
         self.state.model = Some(model);
     }
 
+    /// Asks for the arguments of the prompt, other than the first one when it
+    /// was typed after the command, and sends the prompt as the next message
+    async fn on_mcp_prompt(&mut self, prompt: McpPrompt, value: Option<String>) -> Result<()> {
+        let mut value = value;
+        let mut arguments = BTreeMap::new();
+        for argument in &prompt.arguments {
+            let answer = match value.take() {
+                Some(value) => Some(value),
+                None => {
+                    let description = argument
+                        .description
+                        .as_ref()
+                        .map(|description| format!(" ({description})"))
+                        .unwrap_or_default();
+                    ForgeSelect::input(format!("{}{}", argument.name, description))?
+                }
+            };
+            match answer.filter(|answer| !answer.trim().is_empty()) {
+                Some(answer) => {
+                    arguments.insert(argument.name.clone(), answer);
+                }
+                None if argument.required => {
+                    self.writeln(TitleFormat::error(format!(
+                        "{} is required by {}",
+                        argument.name,
+                        prompt.command()
+                    )))?;
+                    return Ok(());
+                }
+                None => {}
+            }
+        }
+
+        self.spinner.start(None)?;
+        let mut content = self.api.mcp_prompt(&prompt, arguments).await?;
+        // Prompts without arguments take the text as is, after their content
+        if let Some(value) = value {
+            content = format!("{content}\n\n{value}");
+        }
+        self.on_message(Some(content)).await
+    }
+
     async fn on_custom_event(&mut self, event: Event) -> Result<()> {
         let conversation_id = self.init_conversation().await?;
         let chat = ChatRequest::new(event, conversation_id);
@@ -904,6 +2111,28 @@ fn parse_env(env: Vec<String>) -> BTreeMap<String, String> {
         .collect()
 }
 
+/// Waits for Ctrl+C, unless a turn is running, as the turn takes it to let the
+/// user steer the agent
+async fn interrupted(turn_running: Arc<AtomicBool>) {
+    loop {
+        let _ = tokio::signal::ctrl_c().await;
+        if !turn_running.load(Ordering::SeqCst) {
+            return;
+        }
+    }
+}
+
+/// Parses headers given as `Name: value`
+fn parse_headers(headers: Vec<String>) -> BTreeMap<String, String> {
+    headers
+        .into_iter()
+        .filter_map(|header| {
+            let (name, value) = header.split_once(':')?;
+            Some((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
 struct CliModel(Model);
 
 impl Display for CliModel {
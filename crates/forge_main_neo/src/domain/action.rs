@@ -1,7 +1,7 @@
-use forge_api::{ChatResponse, ConversationId};
+use forge_api::{ChatResponse, ConversationId, LifecycleUpdate, TaskList, WorkspaceStats};
 use ratatui::crossterm::event::Event;
 
-use crate::domain::{CancelId, Timer};
+use crate::domain::{CancelId, FileChange, FileTree, Timer};
 
 /// Top-level application actions that wrap route-specific actions
 #[derive(Clone, Debug)]
@@ -13,8 +13,15 @@ pub enum Action {
         current_branch: Option<String>,
     },
     ChatResponse(ChatResponse),
+    /// Progress of the turn running on the conversation
+    Lifecycle(LifecycleUpdate),
     ConversationInitialized(ConversationId),
     IntervalTick(Timer),
     InterruptStream,
     StartStream(CancelId),
+    EndStream,
+    Tasks(TaskList),
+    WorkspaceStats(WorkspaceStats),
+    FileTree(FileTree),
+    Changes(Vec<FileChange>),
 }
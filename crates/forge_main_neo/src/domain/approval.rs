@@ -0,0 +1,114 @@
+use forge_api::{Approval, ApprovalReply, ChangeReview};
+
+/// Approval the agent is waiting for. When it is for a change of a file, each
+/// hunk of the change is accepted or rejected before answering.
+#[derive(Clone, Debug)]
+pub struct PendingApproval {
+    pub message: String,
+    reply: ApprovalReply,
+    pub review: Option<ChangeReview>,
+    /// Whether each hunk of the change is accepted, all of them at first
+    pub accepted: Vec<bool>,
+    pub selected: usize,
+}
+
+impl PendingApproval {
+    pub fn new(message: String, reply: ApprovalReply, review: Option<ChangeReview>) -> Self {
+        let hunks = review.as_ref().map_or(0, |review| review.hunks.len());
+        Self {
+            message,
+            reply,
+            review,
+            accepted: vec![true; hunks],
+            selected: 0,
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.accepted.len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Accepts the selected hunk if it was rejected, and rejects it otherwise
+    pub fn toggle_selected(&mut self) {
+        if let Some(accepted) = self.accepted.get_mut(self.selected) {
+            *accepted = !*accepted;
+        }
+    }
+
+    pub fn set_all(&mut self, accepted: bool) {
+        self.accepted.fill(accepted);
+    }
+
+    /// Answers with the hunks accepted, rejecting the change when none of them
+    /// is. An approval without a change is accepted.
+    pub fn submit(self) {
+        if let Some(review) = &self.review {
+            review.reply.send(self.accepted.clone());
+        }
+        if self.accepted.contains(&true) || self.review.is_none() {
+            self.reply.send(Approval::Accept);
+        } else {
+            self.reply.send(Approval::Reject);
+        }
+    }
+
+    pub fn reject(self) {
+        self.reply.send(Approval::Reject);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use forge_api::{Hunk, Reply};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_submit_sends_accepted_hunks_before_approval() {
+        let (reply, rx) = ApprovalReply::channel();
+        let (review_reply, mut review_rx) = Reply::channel();
+        let review = ChangeReview {
+            path: PathBuf::from("/project/main.rs"),
+            hunks: Hunk::split("a\nb\nc\n", "A\nb\nC\n"),
+            reply: review_reply,
+        };
+        let mut fixture = PendingApproval::new("Patch file".to_string(), reply, Some(review));
+
+        fixture.select_next();
+        fixture.select_next();
+        fixture.toggle_selected();
+        fixture.submit();
+
+        let actual = (rx.await.unwrap(), review_rx.try_recv().ok());
+        let expected = (Approval::Accept, Some(vec![true, false]));
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_submit_without_accepted_hunks_rejects() {
+        let (reply, rx) = ApprovalReply::channel();
+        let (review_reply, _review_rx) = Reply::channel();
+        let review = ChangeReview {
+            path: PathBuf::from("/project/main.rs"),
+            hunks: Hunk::split("a\n", "b\n"),
+            reply: review_reply,
+        };
+        let mut fixture = PendingApproval::new("Patch file".to_string(), reply, Some(review));
+
+        fixture.set_all(false);
+        fixture.submit();
+
+        let actual = rx.await.unwrap();
+        let expected = Approval::Reject;
+        assert_eq!(actual, expected);
+    }
+}
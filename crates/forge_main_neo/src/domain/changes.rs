@@ -0,0 +1,131 @@
+use std::path::Path;
+use std::time::Duration;
+
+use forge_api::Snapshot;
+use forge_display::DiffFormat;
+
+/// File the agent changed during the session, with the diff from the content
+/// it had before the first change
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileChange {
+    pub path: String,
+    pub diff: String,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+}
+
+impl FileChange {
+    /// Change of the file from `old` to `new`, `None` when the contents are
+    /// the same again, such as after the change was reverted by hand
+    pub fn new(path: impl Into<String>, old: &str, new: &str) -> Option<Self> {
+        if old == new {
+            return None;
+        }
+        let diff = DiffFormat::format(old, new);
+        Some(Self {
+            path: path.into(),
+            diff: diff.diff().to_string(),
+            lines_added: diff.lines_added(),
+            lines_removed: diff.lines_removed(),
+        })
+    }
+}
+
+/// Snapshots of the files under `cwd` changed since the session started, the
+/// oldest of each file, which holds its content from before the session. The
+/// snapshots of the files of other projects, changed by other sessions, are
+/// left out.
+pub fn session_snapshots(snapshots: Vec<Snapshot>, started: Duration, cwd: &Path) -> Vec<Snapshot> {
+    let mut first = Vec::<Snapshot>::new();
+    for snapshot in snapshots {
+        if snapshot.timestamp >= started
+            && Path::new(&snapshot.path).starts_with(cwd)
+            && !first.iter().any(|other| other.path == snapshot.path)
+        {
+            first.push(snapshot);
+        }
+    }
+    first
+}
+
+/// Diffs of the files changed during the session, scrolled through as one
+#[derive(Clone, Debug, Default)]
+pub struct Changes {
+    pub files: Vec<FileChange>,
+    pub scroll: u16,
+}
+
+impl Changes {
+    /// Lines of the diffs, with the line naming each file
+    pub fn line_count(&self) -> usize {
+        self.files
+            .iter()
+            .map(|change| 1 + change.diff.lines().count())
+            .sum()
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+
+    pub fn scroll_down(&mut self) {
+        if usize::from(self.scroll) + 1 < self.line_count() {
+            self.scroll = self.scroll.saturating_add(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn snapshot(path: &str, timestamp: u64) -> Snapshot {
+        Snapshot {
+            id: Default::default(),
+            timestamp: Duration::from_secs(timestamp),
+            path: path.to_string(),
+            hash: None,
+        }
+    }
+
+    #[test]
+    fn test_session_snapshots_keeps_first_of_each_file_of_the_project() {
+        let fixture = vec![
+            snapshot("/project/old.rs", 5),
+            snapshot("/project/main.rs", 10),
+            snapshot("/project/lib.rs", 11),
+            snapshot("/other/lib.rs", 11),
+            snapshot("/project/main.rs", 12),
+        ];
+
+        let actual = session_snapshots(fixture, Duration::from_secs(10), Path::new("/project"))
+            .into_iter()
+            .map(|snapshot| (snapshot.path, snapshot.timestamp.as_secs()))
+            .collect::<Vec<_>>();
+
+        let expected = vec![
+            ("/project/main.rs".to_string(), 10),
+            ("/project/lib.rs".to_string(), 11),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_file_change_counts_lines() {
+        let actual = FileChange::new("main.rs", "a\nb\n", "a\nc\nd\n")
+            .map(|change| (change.path, change.lines_added, change.lines_removed));
+
+        let expected = Some(("main.rs".to_string(), 2, 1));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_file_change_of_same_content_is_none() {
+        let actual = FileChange::new("main.rs", "a\n", "a\n");
+
+        let expected = None;
+        assert_eq!(actual, expected);
+    }
+}
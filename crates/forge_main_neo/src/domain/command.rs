@@ -13,6 +13,11 @@ use forge_api::{AgentId, ConversationId, ModelId};
 pub enum Command {
     // Application-level commands
     ReadWorkspace,
+    ReadTasks,
+    ReadWorkspaceStats,
+    ReadFileTree,
+    /// Reads the diffs of the files changed since the session started
+    ReadChanges,
     #[default]
     Empty,
     Exit,
@@ -23,6 +28,11 @@ pub enum Command {
         is_first: bool,
     },
     InterruptStream,
+    /// Sends an instruction to the turn running on the conversation
+    Steer {
+        message: String,
+        conversation_id: ConversationId,
+    },
     #[allow(unused)]
     Spotlight(SpotlightCommand),
     Interval {
@@ -0,0 +1,119 @@
+use forge_api::File;
+
+/// Line of the file tree, a file or a directory indented by its depth in the
+/// workspace
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileTreeEntry {
+    pub name: String,
+    pub depth: usize,
+    pub is_dir: bool,
+}
+
+/// Files of the workspace, listed with the contents of each directory under
+/// it
+#[derive(Clone, Debug, Default)]
+pub struct FileTree {
+    pub entries: Vec<FileTreeEntry>,
+    pub selected: usize,
+}
+
+impl FileTree {
+    /// Builds the tree from the files the walker found, whose paths are
+    /// relative to the workspace
+    pub fn new(files: Vec<File>) -> Self {
+        let mut paths = files
+            .iter()
+            .map(|file| {
+                file.path
+                    .split('/')
+                    .filter(|part| !part.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .zip(files.iter().map(|file| file.is_dir))
+            .filter(|(parts, _)| !parts.is_empty())
+            .collect::<Vec<_>>();
+        // A directory comes right before its contents, as its parts are a
+        // prefix of theirs
+        paths.sort();
+
+        let entries = paths
+            .into_iter()
+            .map(|(parts, is_dir)| FileTreeEntry {
+                name: parts
+                    .last()
+                    .map(|part| part.to_string())
+                    .unwrap_or_default(),
+                depth: parts.len() - 1,
+                is_dir,
+            })
+            .collect();
+        Self { entries, selected: 0 }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.entries.len() {
+            self.selected += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn file(path: &str, is_dir: bool) -> File {
+        File { path: path.to_string(), is_dir }
+    }
+
+    #[test]
+    fn test_file_tree_lists_directories_before_their_contents() {
+        let fixture = vec![
+            file("src/main.rs", false),
+            file("README.md", false),
+            file("src/", true),
+            file("src/widgets/", true),
+            file("src/widgets/app.rs", false),
+            file("src.rs", false),
+        ];
+
+        let actual = FileTree::new(fixture)
+            .entries
+            .into_iter()
+            .map(|entry| (entry.name, entry.depth, entry.is_dir))
+            .collect::<Vec<_>>();
+
+        let expected = vec![
+            ("README.md".to_string(), 0, false),
+            ("src".to_string(), 0, true),
+            ("main.rs".to_string(), 1, false),
+            ("widgets".to_string(), 1, true),
+            ("app.rs".to_string(), 2, false),
+            ("src.rs".to_string(), 0, false),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_file_tree_selection_stays_within_entries() {
+        let mut fixture = FileTree::new(vec![file("a.rs", false), file("b.rs", false)]);
+
+        let mut actual = Vec::new();
+        for select_next in [true, true, false, false] {
+            if select_next {
+                fixture.select_next();
+            } else {
+                fixture.select_previous();
+            }
+            actual.push(fixture.selected);
+        }
+
+        let expected = vec![1, 1, 0, 0];
+        assert_eq!(actual, expected);
+    }
+}
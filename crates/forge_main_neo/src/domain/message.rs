@@ -1,4 +1,7 @@
+use std::collections::HashMap;
+
 use forge_api::ChatResponse;
+use ratatui::text::Line;
 
 /// Enum to differentiate between user and assistant messages
 #[derive(Debug, Clone)]
@@ -6,3 +9,56 @@ pub enum Message {
     User(String),
     Assistant(ChatResponse),
 }
+
+/// Lines the messages were rendered to by their position, as rendering the
+/// markdown of a message and highlighting its code, or parsing the colors of
+/// the output of a command, again for every frame is slow. Messages are only
+/// ever appended, so a position keeps its lines.
+#[derive(Debug, Clone, Default)]
+pub struct RenderedMessages {
+    lines: HashMap<usize, Vec<Line<'static>>>,
+    // Whether the lines were rendered without their colors
+    strip_ansi: bool,
+}
+
+impl RenderedMessages {
+    /// Lines of the message at the position, rendered the first time they
+    /// are asked for and again once `strip_ansi` changes
+    pub fn lines(
+        &mut self,
+        position: usize,
+        strip_ansi: bool,
+        render: impl FnOnce() -> Vec<Line<'static>>,
+    ) -> Vec<Line<'static>> {
+        if self.strip_ansi != strip_ansi {
+            self.lines.clear();
+            self.strip_ansi = strip_ansi;
+        }
+        self.lines.entry(position).or_insert_with(render).clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_rendered_messages_are_kept() {
+        let mut fixture = RenderedMessages::default();
+        let mut renders = 0;
+        let mut render = |text: &'static str| {
+            renders += 1;
+            vec![Line::raw(text)]
+        };
+
+        fixture.lines(0, false, || render("first"));
+        let kept = fixture.lines(0, false, || render("again"));
+        let stripped = fixture.lines(0, true, || render("stripped"));
+
+        let actual = (kept, stripped, renders);
+        let expected = (vec![Line::raw("first")], vec![Line::raw("stripped")], 2);
+        assert_eq!(actual, expected);
+    }
+}
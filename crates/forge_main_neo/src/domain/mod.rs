@@ -1,8 +1,13 @@
 mod action;
+mod approval;
 mod cancel;
+mod changes;
 mod command;
 mod editor_helpers;
+mod file_tree;
 mod message;
+mod panes;
+mod question;
 mod slash_command;
 mod spotlight;
 mod state;
@@ -11,10 +16,15 @@ mod update_key_event;
 mod workspace;
 
 pub use action::*;
+pub use approval::*;
 pub use cancel::*;
+pub use changes::*;
 pub use command::*;
 pub use editor_helpers::*;
+pub use file_tree::*;
 pub use message::*;
+pub use panes::*;
+pub use question::*;
 pub use state::*;
 pub use update::*;
 pub use workspace::*;
@@ -0,0 +1,128 @@
+/// Panes of the screen from left to right. The chat is always shown, the
+/// others only once they are toggled on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pane {
+    Files,
+    #[default]
+    Chat,
+    Changes,
+}
+
+impl Pane {
+    const ALL: [Pane; 3] = [Pane::Files, Pane::Chat, Pane::Changes];
+}
+
+/// Panes shown next to the chat, and the one the keys go to
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Panes {
+    pub show_files: bool,
+    pub show_changes: bool,
+    pub focus: Pane,
+}
+
+impl Panes {
+    pub fn is_visible(&self, pane: Pane) -> bool {
+        match pane {
+            Pane::Files => self.show_files,
+            Pane::Chat => true,
+            Pane::Changes => self.show_changes,
+        }
+    }
+
+    pub fn is_focused(&self, pane: Pane) -> bool {
+        self.focus == pane
+    }
+
+    /// Shows or hides the pane, returning whether it is shown. The focus goes
+    /// back to the chat when the pane that had it is hidden.
+    pub fn toggle(&mut self, pane: Pane) -> bool {
+        match pane {
+            Pane::Files => self.show_files = !self.show_files,
+            Pane::Chat => {}
+            Pane::Changes => self.show_changes = !self.show_changes,
+        }
+        if !self.is_visible(self.focus) {
+            self.focus = Pane::Chat;
+        }
+        self.is_visible(pane)
+    }
+
+    /// Moves the focus to the next pane shown, from left to right and back to
+    /// the first one
+    pub fn focus_next(&mut self) {
+        self.focus = self.next_visible(Pane::ALL);
+    }
+
+    /// Moves the focus to the previous pane shown, from right to left and back
+    /// to the last one
+    pub fn focus_previous(&mut self) {
+        let mut panes = Pane::ALL;
+        panes.reverse();
+        self.focus = self.next_visible(panes);
+    }
+
+    /// First pane shown after the one with the focus in the order given
+    fn next_visible(&self, panes: [Pane; 3]) -> Pane {
+        let position = panes
+            .iter()
+            .position(|pane| *pane == self.focus)
+            .unwrap_or_default();
+        panes
+            .iter()
+            .cycle()
+            .skip(position + 1)
+            .take(panes.len())
+            .find(|pane| self.is_visible(**pane))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_focus_next_skips_hidden_panes() {
+        let mut fixture = Panes { show_changes: true, ..Default::default() };
+
+        let mut actual = Vec::new();
+        for _ in 0..3 {
+            fixture.focus_next();
+            actual.push(fixture.focus);
+        }
+
+        let expected = vec![Pane::Changes, Pane::Chat, Pane::Changes];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_focus_previous_goes_from_right_to_left() {
+        let mut fixture = Panes { show_files: true, show_changes: true, ..Default::default() };
+
+        let mut actual = Vec::new();
+        for _ in 0..3 {
+            fixture.focus_previous();
+            actual.push(fixture.focus);
+        }
+
+        let expected = vec![Pane::Files, Pane::Changes, Pane::Chat];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_toggle_hidden_pane_gives_focus_back_to_chat() {
+        let mut fixture = Panes::default();
+
+        let shown = fixture.toggle(Pane::Files);
+        fixture.focus_next();
+        let focused = fixture.focus;
+        let hidden = fixture.toggle(Pane::Files);
+
+        let actual = (shown, focused, hidden, fixture.focus);
+        let expected = (true, Pane::Files, false, Pane::Chat);
+        assert_eq!(actual, expected);
+    }
+}
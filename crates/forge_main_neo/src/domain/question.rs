@@ -0,0 +1,151 @@
+use forge_api::Reply;
+
+/// Question the agent is waiting for an answer to. The answer is picked from
+/// the options when there are some, several of them when `multiple` is set,
+/// and typed otherwise.
+#[derive(Clone, Debug)]
+pub struct PendingQuestion {
+    pub question: String,
+    pub options: Vec<String>,
+    pub multiple: bool,
+    reply: Reply<String>,
+    /// Whether each option is picked, when several of them can be
+    pub picked: Vec<bool>,
+    pub selected: usize,
+    /// Answer typed so far, when there are no options
+    pub typed: String,
+}
+
+impl PendingQuestion {
+    pub fn new(
+        question: String,
+        options: Vec<String>,
+        multiple: bool,
+        reply: Reply<String>,
+    ) -> Self {
+        let picked = vec![false; options.len()];
+        Self {
+            question,
+            options,
+            multiple,
+            reply,
+            picked,
+            selected: 0,
+            typed: String::new(),
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.options.len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Picks the selected option if it wasn't, and leaves it out otherwise
+    pub fn toggle_selected(&mut self) {
+        if let Some(picked) = self.picked.get_mut(self.selected) {
+            *picked = !*picked;
+        }
+    }
+
+    pub fn type_char(&mut self, c: char) {
+        self.typed.push(c);
+    }
+
+    pub fn delete_char(&mut self) {
+        self.typed.pop();
+    }
+
+    /// Answer given when submitting: the options picked, the selected one
+    /// when none is, or the text typed
+    pub fn answer(&self) -> String {
+        if self.options.is_empty() {
+            return self.typed.trim().to_string();
+        }
+        let picked = self
+            .options
+            .iter()
+            .zip(&self.picked)
+            .filter(|(_, picked)| **picked)
+            .map(|(option, _)| option.as_str())
+            .collect::<Vec<_>>();
+        if self.multiple && !picked.is_empty() {
+            picked.join(", ")
+        } else {
+            self.options[self.selected].clone()
+        }
+    }
+
+    pub fn submit(self) {
+        self.reply.send(self.answer());
+    }
+
+    /// Leaves the question unanswered
+    pub fn cancel(self) {
+        self.reply.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn fixture(options: &[&str], multiple: bool) -> PendingQuestion {
+        let (reply, _rx) = Reply::channel();
+        PendingQuestion::new(
+            "Which crates?".to_string(),
+            options.iter().map(|option| option.to_string()).collect(),
+            multiple,
+            reply,
+        )
+    }
+
+    #[test]
+    fn test_answer_picks_the_selected_option() {
+        let mut fixture = fixture(&["forge_api", "forge_app"], false);
+
+        fixture.select_next();
+        fixture.select_next();
+
+        let actual = fixture.answer();
+        let expected = "forge_app".to_string();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_answer_joins_the_options_picked() {
+        let mut fixture = fixture(&["forge_api", "forge_app", "forge_domain"], true);
+
+        fixture.toggle_selected();
+        fixture.select_next();
+        fixture.select_next();
+        fixture.toggle_selected();
+
+        let actual = fixture.answer();
+        let expected = "forge_api, forge_domain".to_string();
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_submit_sends_the_answer_typed() {
+        let (reply, rx) = Reply::channel();
+        let mut fixture = PendingQuestion::new("Which version?".to_string(), vec![], false, reply);
+
+        for c in "1.2.x".chars() {
+            fixture.type_char(c);
+        }
+        fixture.delete_char();
+        fixture.type_char('0');
+        fixture.submit();
+
+        let actual = rx.await.unwrap();
+        let expected = "1.2.0".to_string();
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,26 +1,48 @@
+use std::collections::VecDeque;
 use std::time::Duration;
 
 use chrono::{DateTime, Utc};
 use edtui::EditorState;
-use forge_api::{ChatResponse, ConversationId};
+use forge_api::{ChatResponse, ConversationId, TaskList, TokenBudget};
 use throbber_widgets_tui::ThrobberState;
 use tui_scrollview::ScrollViewState;
 
 use crate::domain::spotlight::SpotlightState;
-use crate::domain::{CancelId, EditorStateExt, Message, Workspace};
+use crate::domain::{
+    CancelId, Changes, EditorStateExt, FileTree, Message, Panes, PendingApproval, RenderedMessages,
+    Workspace,
+};
 
 #[derive(Clone)]
 pub struct State {
     pub workspace: Workspace,
     pub editor: EditorState,
     pub messages: Vec<Message>,
+    pub rendered_messages: RenderedMessages,
     pub spinner: ThrobberState,
     pub timer: Option<Timer>,
     pub show_spinner: bool,
+    /// What the agent is doing, shown next to the spinner
+    pub progress: Option<String>,
     pub spotlight: SpotlightState,
     pub conversation: ConversationState,
     pub chat_stream: Option<CancelId>,
     pub message_scroll_state: ScrollViewState,
+    pub tasks: TaskList,
+    pub token_budget: Option<TokenBudget>,
+    /// Whether the reasoning of the model is left out of the messages
+    pub hide_reasoning: bool,
+    /// Whether the output of the commands and the code of the messages are
+    /// shown without their colors
+    pub strip_ansi: bool,
+    pub panes: Panes,
+    pub file_tree: FileTree,
+    pub changes: Changes,
+    /// Approvals the agent is waiting for, the first one is shown
+    pub approvals: VecDeque<PendingApproval>,
+    /// Questions the agent is waiting for an answer to, the first one is
+    /// shown once there's no approval left
+    pub questions: VecDeque<PendingQuestion>,
 }
 
 impl Default for State {
@@ -31,13 +53,24 @@ impl Default for State {
             workspace: Default::default(),
             editor: prompt_editor,
             messages: Default::default(),
+            rendered_messages: Default::default(),
             spinner: Default::default(),
             timer: Default::default(),
             show_spinner: Default::default(),
+            progress: None,
             spotlight: Default::default(),
             conversation: Default::default(),
             chat_stream: None,
             message_scroll_state: ScrollViewState::default(),
+            tasks: Default::default(),
+            token_budget: None,
+            hide_reasoning: false,
+            strip_ansi: false,
+            panes: Default::default(),
+            file_tree: Default::default(),
+            changes: Default::default(),
+            approvals: Default::default(),
+            questions: Default::default(),
         }
     }
 }
@@ -72,7 +105,14 @@ impl State {
 
     /// Add an assistant message to the chat
     pub fn add_assistant_message(&mut self, message: ChatResponse) {
-        self.messages.push(Message::Assistant(message));
+        // Reasoning is streamed in parts, which are shown as one message
+        match (self.messages.last_mut(), message) {
+            (
+                Some(Message::Assistant(ChatResponse::Reasoning { content })),
+                ChatResponse::Reasoning { content: part },
+            ) => content.push_str(&part),
+            (_, message) => self.messages.push(Message::Assistant(message)),
+        }
         // Auto-scroll to bottom when new message is added
         self.message_scroll_state.scroll_to_bottom();
     }
@@ -1,14 +1,49 @@
 use edtui::EditorEventHandler;
-use forge_api::ChatResponse;
+use forge_api::{ChatResponse, LifecycleEventKind, LifecycleUpdate, ToolName, ToolsDiscriminants};
 use ratatui::crossterm::event::KeyEventKind;
 
 use crate::domain::update_key_event::handle_key_event;
-use crate::domain::{Action, Command, State};
+use crate::domain::{Action, Command, FileTree, PendingApproval, PendingQuestion, State};
+
+/// Tools whose changes to the files are kept in snapshots, and so show up in
+/// the diffs of the session
+const FILE_CHANGING_TOOLS: [ToolsDiscriminants; 7] = [
+    ToolsDiscriminants::ForgeToolFsCreate,
+    ToolsDiscriminants::ForgeToolFsPatch,
+    ToolsDiscriminants::ForgeToolFsApplyPatch,
+    ToolsDiscriminants::ForgeToolFsAstEdit,
+    ToolsDiscriminants::ForgeToolFsRemove,
+    ToolsDiscriminants::ForgeToolFsUndo,
+    ToolsDiscriminants::ForgeToolLspRenameSymbol,
+];
+
+/// Reads the panes shown again once the tool may have changed what they show.
+/// Any tool can create and remove files, such as the shell, a custom or an MCP
+/// tool, so the file tree is read after each of them, while the diffs only
+/// change with the tools taking snapshots.
+fn refresh_panes(state: &State, tool: &ToolName) -> Command {
+    let file_tree = if state.panes.show_files {
+        Command::ReadFileTree
+    } else {
+        Command::Empty
+    };
+    let changes_files = FILE_CHANGING_TOOLS
+        .iter()
+        .any(|changing| changing.name() == *tool);
+    let changes = if state.panes.show_changes && changes_files {
+        Command::ReadChanges
+    } else {
+        Command::Empty
+    };
+    file_tree.and(changes)
+}
 
 pub fn update(state: &mut State, action: impl Into<Action>) -> Command {
     let action = action.into();
     match action {
-        Action::Initialize => Command::ReadWorkspace,
+        Action::Initialize => Command::ReadWorkspace
+            .and(Command::ReadTasks)
+            .and(Command::ReadWorkspaceStats),
         Action::Workspace { current_dir, current_branch } => {
             // TODO: can simply get workspace object from the action
             state.workspace.current_dir = current_dir;
@@ -35,12 +70,45 @@ pub fn update(state: &mut State, action: impl Into<Action>) -> Command {
             ratatui::crossterm::event::Event::Resize(_, _) => Command::Empty,
         },
         Action::ChatResponse(response) => {
+            if let ChatResponse::ApprovalRequest { ref message, ref reply, ref review, .. } =
+                response
+            {
+                state.approvals.push_back(PendingApproval::new(
+                    message.clone(),
+                    reply.clone(),
+                    review.clone(),
+                ));
+            }
+            if let ChatResponse::Question { ref question, ref options, multiple, ref reply } =
+                response
+            {
+                state.questions.push_back(PendingQuestion::new(
+                    question.clone(),
+                    options.clone(),
+                    multiple,
+                    reply.clone(),
+                ));
+            }
+            if let ChatResponse::TokenBudget(ref budget) = response {
+                state.token_budget = Some(budget.clone());
+            }
             if let ChatResponse::Text { ref text, is_complete, .. } = response
                 && is_complete
                 && !text.trim().is_empty()
             {
                 state.show_spinner = false
             }
+            // The task list is kept in the workspace, so it is read again once a
+            // task tool changed it
+            let command = match response {
+                ChatResponse::ToolCallEnd(ref result)
+                    if result.name.as_str().starts_with("forge_tool_task_list") =>
+                {
+                    Command::ReadTasks.and(refresh_panes(state, &result.name))
+                }
+                ChatResponse::ToolCallEnd(ref result) => refresh_panes(state, &result.name),
+                _ => Command::Empty,
+            };
             state.add_assistant_message(response);
             if let Some(ref timer) = state.timer
                 && !state.show_spinner
@@ -48,6 +116,10 @@ pub fn update(state: &mut State, action: impl Into<Action>) -> Command {
                 timer.cancel.cancel();
                 state.timer = None;
             }
+            command
+        }
+        Action::Lifecycle(update) => {
+            state.progress = progress(update);
             Command::Empty
         }
         Action::ConversationInitialized(conversation_id) => {
@@ -73,6 +145,7 @@ pub fn update(state: &mut State, action: impl Into<Action>) -> Command {
                 timer.cancel.cancel();
                 state.timer = None;
             }
+            reject_requests(state);
             Command::Empty
         }
         Action::StartStream(cancel_id) => {
@@ -80,6 +153,72 @@ pub fn update(state: &mut State, action: impl Into<Action>) -> Command {
             state.chat_stream = Some(cancel_id);
             Command::Empty
         }
+        Action::EndStream => {
+            state.chat_stream = None;
+            state.progress = None;
+            reject_requests(state);
+            Command::Empty
+        }
+        Action::Tasks(tasks) => {
+            state.tasks = tasks;
+            Command::Empty
+        }
+        Action::WorkspaceStats(stats) => {
+            state.workspace.stats = Some(stats);
+            Command::Empty
+        }
+        Action::FileTree(file_tree) => {
+            // The selection stays where it was as files come and go
+            let selected = state
+                .file_tree
+                .selected
+                .min(file_tree.entries.len().saturating_sub(1));
+            state.file_tree = FileTree { selected, ..file_tree };
+            Command::Empty
+        }
+        Action::Changes(files) => {
+            state.changes.files = files;
+            let last_line = state.changes.line_count().saturating_sub(1);
+            state.changes.scroll = state
+                .changes
+                .scroll
+                .min(u16::try_from(last_line).unwrap_or(u16::MAX));
+            Command::Empty
+        }
+    }
+}
+
+/// What the agent is doing according to the update of the lifecycle of its
+/// turn, if it's worth showing
+fn progress(update: LifecycleUpdate) -> Option<String> {
+    let LifecycleUpdate::Event(event) = update else {
+        // Missing events, the progress shown may be over already
+        return None;
+    };
+    match event.kind {
+        LifecycleEventKind::ToolCallStart { call, .. } => Some(format!("Running {}", call.name)),
+        LifecycleEventKind::Retry { cause, delay, .. } => {
+            Some(format!("Retrying in {}s: {cause}", delay.as_secs()))
+        }
+        LifecycleEventKind::Compaction { compaction, .. } => Some(format!(
+            "Compacted the context from {} to {} tokens",
+            compaction.before, compaction.after
+        )),
+        LifecycleEventKind::TurnStart { .. }
+        | LifecycleEventKind::TurnEnd
+        | LifecycleEventKind::ToolCallEnd { .. }
+        | LifecycleEventKind::Error { .. } => None,
+    }
+}
+
+/// Rejects the approvals and leaves the questions still shown unanswered once
+/// the agent stopped, so that none of them is left over for the next message
+fn reject_requests(state: &mut State) {
+    for approval in state.approvals.drain(..) {
+        approval.reject();
+    }
+    for question in state.questions.drain(..) {
+        question.cancel();
     }
 }
 
@@ -90,7 +229,7 @@ mod tests {
     use tokio_util::sync::CancellationToken;
 
     use super::*;
-    use crate::domain::{CancelId, EditorStateExt};
+    use crate::domain::{CancelId, EditorStateExt, Message};
 
     #[test]
     fn test_update_processes_key_press_events() {
@@ -251,6 +390,18 @@ mod tests {
         assert!(fixture_state.chat_stream.is_some());
     }
 
+    #[test]
+    fn test_end_stream_action_clears_cancellation_token() {
+        let mut fixture_state = State::default();
+        fixture_state.chat_stream = Some(CancelId::new(CancellationToken::new()));
+
+        let actual_command = update(&mut fixture_state, Action::EndStream);
+        let expected_command = Command::Empty;
+
+        assert_eq!(actual_command, expected_command);
+        assert!(fixture_state.chat_stream.is_none());
+    }
+
     #[test]
     fn test_interrupt_stream_action_cancels_stream_token() {
         let mut fixture_state = State::default();
@@ -274,7 +425,9 @@ mod tests {
         let mut fixture_state = State::default();
 
         let actual_command = update(&mut fixture_state, Action::Initialize);
-        let expected_command = Command::ReadWorkspace;
+        let expected_command = Command::ReadWorkspace
+            .and(Command::ReadTasks)
+            .and(Command::ReadWorkspaceStats);
 
         assert_eq!(actual_command, expected_command);
     }
@@ -303,6 +456,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_task_tool_result_reads_tasks() {
+        let mut fixture_state = State::default();
+        let chat_response = forge_api::ChatResponse::ToolCallEnd(forge_api::ToolResult::new(
+            forge_api::ToolName::new("forge_tool_task_list_complete"),
+        ));
+
+        let actual = update(&mut fixture_state, Action::ChatResponse(chat_response));
+
+        let expected = Command::ReadTasks;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_file_tool_result_reads_panes_shown() {
+        let mut fixture_state = State::default();
+        fixture_state.panes.show_changes = true;
+        fixture_state.panes.show_files = true;
+
+        let actual = [
+            "forge_tool_fs_patch",
+            "forge_tool_process_shell",
+            "mcp_github_tool_create_file",
+        ]
+        .map(|name| {
+            let chat_response = forge_api::ChatResponse::ToolCallEnd(forge_api::ToolResult::new(
+                forge_api::ToolName::new(name),
+            ));
+            update(&mut fixture_state, Action::ChatResponse(chat_response))
+        });
+
+        let expected = [
+            Command::ReadFileTree.and(Command::ReadChanges),
+            Command::ReadFileTree,
+            Command::ReadFileTree,
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_file_tree_action_keeps_selection_within_entries() {
+        let mut fixture_state = State::default();
+        fixture_state.file_tree.selected = 5;
+        let file_tree = FileTree::new(vec![
+            forge_api::File { path: "a.rs".to_string(), is_dir: false },
+            forge_api::File { path: "b.rs".to_string(), is_dir: false },
+        ]);
+
+        update(&mut fixture_state, Action::FileTree(file_tree));
+
+        let actual = fixture_state.file_tree.selected;
+        let expected = 1;
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn test_chat_response_stops_spinner_when_complete() {
         let mut fixture_state = State::default();
@@ -356,6 +564,27 @@ mod tests {
         assert_eq!(fixture_state.timer, Some(timer));
     }
 
+    #[test]
+    fn test_chat_response_joins_reasoning_parts() {
+        let mut fixture_state = State::default();
+
+        for content in ["Checking ", "the tests"] {
+            let chat_response = forge_api::ChatResponse::Reasoning { content: content.to_string() };
+            update(&mut fixture_state, Action::ChatResponse(chat_response));
+        }
+
+        let actual = fixture_state
+            .messages
+            .iter()
+            .map(|message| match message {
+                Message::Assistant(ChatResponse::Reasoning { content }) => content.clone(),
+                _ => String::new(),
+            })
+            .collect::<Vec<_>>();
+        let expected = vec!["Checking the tests".to_string()];
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn test_conversation_initialized_updates_state() {
         let mut fixture_state = State::default();
@@ -423,4 +652,42 @@ mod tests {
         // Timer should be replaced with the new timer from the action
         assert_eq!(fixture_state.timer, Some(timer_2));
     }
+
+    #[test]
+    fn test_lifecycle_updates_show_the_progress() {
+        let mut fixture_state = State::default();
+        let conversation_id = forge_api::ConversationId::generate();
+        let agent_id = forge_api::AgentId::new("forge");
+        let fixture = [
+            LifecycleEventKind::ToolCallStart {
+                agent_id: agent_id.clone(),
+                call: forge_api::ToolCallFull::new("forge_tool_fs_read"),
+            },
+            LifecycleEventKind::Retry {
+                agent_id,
+                cause: "Rate limited".to_string(),
+                delay: std::time::Duration::from_secs(2),
+            },
+            LifecycleEventKind::TurnEnd,
+        ];
+
+        let actual = fixture
+            .into_iter()
+            .map(|kind| {
+                let event = forge_api::LifecycleEvent::new(conversation_id.clone(), kind);
+                update(
+                    &mut fixture_state,
+                    Action::Lifecycle(LifecycleUpdate::Event(event)),
+                );
+                fixture_state.progress.clone()
+            })
+            .collect::<Vec<_>>();
+
+        let expected = vec![
+            Some("Running forge_tool_fs_read".to_string()),
+            Some("Retrying in 2s: Rate limited".to_string()),
+            None,
+        ];
+        assert_eq!(actual, expected);
+    }
 }
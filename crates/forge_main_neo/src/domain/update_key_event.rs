@@ -7,7 +7,7 @@ use edtui::{EditorEventHandler, EditorMode};
 use ratatui::crossterm::event::{KeyCode, KeyModifiers};
 
 use crate::domain::spotlight::SpotlightState;
-use crate::domain::{Command, EditorStateExt, State};
+use crate::domain::{Command, EditorStateExt, Pane, State};
 
 fn handle_spotlight_input_change(state: &mut State) {
     // Reset selection index when input changes to ensure it's within bounds
@@ -143,6 +143,18 @@ fn handle_prompt_submit(
         let message = state.take_lines().join("\n");
         if message.trim().is_empty() {
             Command::Empty
+        } else if state.chat_stream.is_some()
+            && let Some(conversation_id) = state.conversation.conversation_id
+        {
+            // Messages sent while the agent is working steer it
+            state.add_user_message(message.clone());
+            state.show_spinner = true;
+            let steer_command = Command::Steer { message, conversation_id };
+            if state.timer.is_none() {
+                Command::Interval { duration: Duration::from_millis(100) }.and(steer_command)
+            } else {
+                steer_command
+            }
         } else {
             state.add_user_message(message.clone());
             state.show_spinner = true;
@@ -216,6 +228,136 @@ fn handle_message_scroll(
     }
 }
 
+/// Shows or hides the pane, reading what it shows when it is shown
+fn toggle_pane(state: &mut State, pane: Pane) -> Command {
+    if !state.panes.toggle(pane) {
+        return Command::Empty;
+    }
+    match pane {
+        Pane::Files => Command::ReadFileTree,
+        Pane::Changes => Command::ReadChanges,
+        Pane::Chat => Command::Empty,
+    }
+}
+
+/// Moves the focus between the panes with Tab and Shift+Tab, and the selection
+/// of the pane with the focus. The keys don't reach the editor while another
+/// pane has the focus, Esc gives it back to the chat.
+fn handle_pane_focus(
+    state: &mut State,
+    key_event: ratatui::crossterm::event::KeyEvent,
+) -> Option<Command> {
+    use ratatui::crossterm::event::KeyCode;
+
+    if state.spotlight.is_visible {
+        return None;
+    }
+
+    let pane = state.panes.focus;
+    match key_event.code {
+        // Tab is left to the editor in insert mode, where it's typed
+        KeyCode::Tab if pane != Pane::Chat || state.editor.mode == EditorMode::Normal => {
+            state.panes.focus_next();
+        }
+        KeyCode::BackTab if pane != Pane::Chat || state.editor.mode == EditorMode::Normal => {
+            state.panes.focus_previous();
+        }
+        _ if pane == Pane::Chat => return None,
+        KeyCode::Esc => state.panes.focus = Pane::Chat,
+        KeyCode::Up | KeyCode::Char('k') => match pane {
+            Pane::Files => state.file_tree.select_previous(),
+            Pane::Changes => state.changes.scroll_up(),
+            Pane::Chat => {}
+        },
+        KeyCode::Down | KeyCode::Char('j') => match pane {
+            Pane::Files => state.file_tree.select_next(),
+            Pane::Changes => state.changes.scroll_down(),
+            Pane::Chat => {}
+        },
+        _ => {}
+    }
+    Some(Command::Empty)
+}
+
+/// Answers the approval shown with the keys. The hunks of a change are
+/// selected with Up and Down and accepted or rejected with Space, or all at
+/// once with `a` and `r`. Enter answers with the hunks accepted, Esc rejects
+/// the whole change. Only the keys pressed without a modifier answer it.
+fn handle_approval(
+    state: &mut State,
+    key_event: ratatui::crossterm::event::KeyEvent,
+) -> Option<Command> {
+    use ratatui::crossterm::event::KeyCode;
+
+    let approval = state.approvals.front_mut()?;
+    // Keys pressed with a modifier, such as Ctrl+R, keep their own meaning
+    if !key_event.modifiers.is_empty() {
+        return None;
+    }
+    match key_event.code {
+        KeyCode::Up | KeyCode::Char('k') => approval.select_previous(),
+        KeyCode::Down | KeyCode::Char('j') => approval.select_next(),
+        KeyCode::Char(' ') => approval.toggle_selected(),
+        KeyCode::Char('a') => approval.set_all(true),
+        KeyCode::Char('r') => approval.set_all(false),
+        KeyCode::Enter | KeyCode::Char('y') => {
+            if let Some(approval) = state.approvals.pop_front() {
+                approval.submit();
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('n') => {
+            if let Some(approval) = state.approvals.pop_front() {
+                approval.reject();
+            }
+        }
+        _ => {}
+    }
+    Some(Command::Empty)
+}
+
+/// Answers the question shown with the keys. An option is selected with Up
+/// and Down, and picked with Space when several can be. Without options the
+/// answer is typed. Enter answers, Esc leaves the question unanswered. Only
+/// the keys pressed without a modifier, other than Shift, answer it.
+fn handle_question(
+    state: &mut State,
+    key_event: ratatui::crossterm::event::KeyEvent,
+) -> Option<Command> {
+    use ratatui::crossterm::event::KeyCode;
+
+    // The approvals are answered first
+    if !state.approvals.is_empty() {
+        return None;
+    }
+    let question = state.questions.front_mut()?;
+    if !key_event
+        .modifiers
+        .difference(KeyModifiers::SHIFT)
+        .is_empty()
+    {
+        return None;
+    }
+    match key_event.code {
+        KeyCode::Enter => {
+            if let Some(question) = state.questions.pop_front() {
+                question.submit();
+            }
+        }
+        KeyCode::Esc => {
+            if let Some(question) = state.questions.pop_front() {
+                question.cancel();
+            }
+        }
+        KeyCode::Char(c) if question.options.is_empty() => question.type_char(c),
+        KeyCode::Backspace => question.delete_char(),
+        KeyCode::Up | KeyCode::Char('k') => question.select_previous(),
+        KeyCode::Down | KeyCode::Char('j') => question.select_next(),
+        KeyCode::Char(' ') if question.multiple => question.toggle_selected(),
+        _ => {}
+    }
+    Some(Command::Empty)
+}
+
 fn handle_editor_default(
     editor: &mut edtui::EditorState,
     key_event: ratatui::crossterm::event::KeyEvent,
@@ -238,6 +380,31 @@ pub fn handle_key_event(
         return Command::InterruptStream;
     }
 
+    if let Some(command) = handle_approval(state, key_event) {
+        return command;
+    }
+
+    if let Some(command) = handle_question(state, key_event) {
+        return command;
+    }
+
+    if key_event.code == KeyCode::Char('r') && key_event.modifiers.contains(KeyModifiers::CONTROL) {
+        state.hide_reasoning = !state.hide_reasoning;
+        return Command::Empty;
+    }
+
+    if key_event.code == KeyCode::Char('t') && key_event.modifiers.contains(KeyModifiers::CONTROL) {
+        return toggle_pane(state, Pane::Files);
+    }
+
+    if key_event.code == KeyCode::Char('g') && key_event.modifiers.contains(KeyModifiers::CONTROL) {
+        return toggle_pane(state, Pane::Changes);
+    }
+
+    if let Some(command) = handle_pane_focus(state, key_event) {
+        return command;
+    }
+
     if state.spotlight.is_visible {
         // When spotlight is visible, route events to spotlight editor
         let cmd = handle_spotlight_toggle(state, key_event, state.editor.mode);
@@ -301,12 +468,14 @@ pub fn handle_key_event(
 #[cfg(test)]
 mod tests {
     use edtui::Index2;
+    use forge_api::ConversationId;
     use pretty_assertions::assert_eq;
     use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use tokio_util::sync::CancellationToken;
 
     use super::*;
-    use crate::domain::State;
     use crate::domain::slash_command::SlashCommand;
+    use crate::domain::{CancelId, State};
 
     fn create_test_state_with_text() -> State {
         let mut state = State::default();
@@ -504,6 +673,150 @@ mod tests {
         assert_eq!(actual_command, expected_command);
     }
 
+    #[test]
+    fn test_ctrl_r_toggles_reasoning() {
+        let mut state = create_test_state_with_text();
+        let key_event = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+
+        let actual = [
+            handle_key_event(&mut state, key_event) == Command::Empty && state.hide_reasoning,
+            handle_key_event(&mut state, key_event) == Command::Empty && state.hide_reasoning,
+        ];
+        let expected = [true, false];
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_ctrl_t_toggles_file_tree() {
+        let mut state = create_test_state_with_text();
+        let key_event = KeyEvent::new(KeyCode::Char('t'), KeyModifiers::CONTROL);
+
+        let actual = [
+            handle_key_event(&mut state, key_event),
+            handle_key_event(&mut state, key_event),
+        ];
+        let expected = [Command::ReadFileTree, Command::Empty];
+
+        assert_eq!(actual, expected);
+        assert!(!state.panes.show_files);
+    }
+
+    #[test]
+    fn test_tab_moves_focus_to_changes_pane_which_keeps_keys_from_editor() {
+        let mut state = create_test_state_with_text();
+        state.editor.mode = EditorMode::Normal;
+        state.panes.show_changes = true;
+        let initial_text = state.editor.get_text();
+
+        handle_key_event(&mut state, KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        let focused = state.panes.focus;
+        handle_key_event(
+            &mut state,
+            KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE),
+        );
+        handle_key_event(&mut state, KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+
+        let actual = (focused, state.editor.get_text(), state.panes.focus);
+        let expected = (Pane::Changes, initial_text, Pane::Chat);
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_approval_keys_answer_with_hunks_and_keep_editor() {
+        let mut state = create_test_state_with_text();
+        let initial_text = state.editor.get_text();
+        let (reply, rx) = forge_api::ApprovalReply::channel();
+        let (review_reply, mut review_rx) = forge_api::Reply::channel();
+        state
+            .approvals
+            .push_back(crate::domain::PendingApproval::new(
+                "Patch file".to_string(),
+                reply,
+                Some(forge_api::ChangeReview {
+                    path: "/project/main.rs".into(),
+                    hunks: forge_api::Hunk::split("a\nb\nc\n", "A\nb\nC\n"),
+                    reply: review_reply,
+                }),
+            ));
+
+        for code in [
+            KeyCode::Char('r'),
+            KeyCode::Down,
+            KeyCode::Char(' '),
+            KeyCode::Enter,
+        ] {
+            handle_key_event(&mut state, KeyEvent::new(code, KeyModifiers::NONE));
+        }
+
+        let actual = (
+            rx.await.unwrap(),
+            review_rx.try_recv().ok(),
+            state.approvals.is_empty(),
+            state.editor.get_text(),
+        );
+        let expected = (
+            forge_api::Approval::Accept,
+            Some(vec![false, true]),
+            true,
+            initial_text,
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_approval_leaves_keys_with_modifiers() {
+        let mut state = create_test_state_with_text();
+        let (reply, _rx) = forge_api::ApprovalReply::channel();
+        state
+            .approvals
+            .push_back(crate::domain::PendingApproval::new(
+                "Run command".to_string(),
+                reply,
+                None,
+            ));
+
+        handle_key_event(
+            &mut state,
+            KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL),
+        );
+
+        let actual = (state.hide_reasoning, state.approvals.len());
+        let expected = (true, 1);
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_question_keys_answer_with_the_text_typed() {
+        let mut state = create_test_state_with_text();
+        let initial_text = state.editor.get_text();
+        let (reply, rx) = forge_api::Reply::channel();
+        state
+            .questions
+            .push_back(crate::domain::PendingQuestion::new(
+                "Which version?".to_string(),
+                vec![],
+                false,
+                reply,
+            ));
+
+        for (code, modifiers) in [
+            (KeyCode::Char('V'), KeyModifiers::SHIFT),
+            (KeyCode::Char('2'), KeyModifiers::NONE),
+            (KeyCode::Enter, KeyModifiers::NONE),
+        ] {
+            handle_key_event(&mut state, KeyEvent::new(code, modifiers));
+        }
+
+        let actual = (
+            rx.await.unwrap(),
+            state.questions.is_empty(),
+            state.editor.get_text(),
+        );
+        let expected = ("V2".to_string(), true, initial_text);
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn test_spotlight_word_navigation() {
         let mut state = create_test_state_with_text();
@@ -685,4 +998,26 @@ mod tests {
         assert_eq!(fixture.messages.len(), 0);
         assert!(!fixture.show_spinner);
     }
+
+    #[test]
+    fn test_handle_prompt_submit_while_streaming_steers() {
+        let conversation_id = ConversationId::generate();
+        let mut fixture = State::default();
+        fixture.editor.mode = EditorMode::Normal;
+        fixture
+            .editor
+            .set_text_with_cursor_at_end("Use the v2 API".to_string());
+        fixture.conversation.conversation_id = Some(conversation_id);
+        fixture.chat_stream = Some(CancelId::new(CancellationToken::new()));
+
+        let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+
+        let actual = handle_prompt_submit(&mut fixture, key_event);
+        let expected = Command::Interval { duration: Duration::from_millis(100) }
+            .and(Command::Steer { message: "Use the v2 API".to_string(), conversation_id });
+
+        assert_eq!(actual, expected);
+        assert_eq!(fixture.messages.len(), 1);
+        assert!(fixture.show_spinner);
+    }
 }
@@ -1,5 +1,8 @@
+use forge_api::WorkspaceStats;
+
 #[derive(Clone, Default)]
 pub struct Workspace {
     pub current_branch: Option<String>,
     pub current_dir: Option<String>,
+    pub stats: Option<WorkspaceStats>,
 }
@@ -1,32 +1,44 @@
+use std::path::Path;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use chrono::Utc;
-use forge_api::{API, AgentId, ChatRequest, ConversationId, Event};
+use forge_api::{API, AgentId, ChatRequest, ConversationId, Event, LifecycleUpdate};
 use serde_json::Value;
 use tokio::sync::mpsc::{Receiver, Sender};
 use tokio_stream::StreamExt;
 use tokio_util::sync::CancellationToken;
 use tracing::error;
 
-use crate::domain::{Action, CancelId, Command, Timer};
+use crate::domain::{Action, CancelId, Command, FileChange, FileTree, Timer, session_snapshots};
 
 // Event type constants
 pub const EVENT_USER_TASK_INIT: &str = "user_task_init";
 pub const EVENT_USER_TASK_UPDATE: &str = "user_task_update";
 
+/// Files counted at most for the summary of the welcome screen, few enough not
+/// to slow down the start in large workspaces
+const WELCOME_MAX_FILES: usize = 2000;
+
 pub struct Executor<T> {
     api: Arc<T>,
+    // Time since the epoch the session started at, the changes to the files
+    // made after it are the ones shown
+    started: Duration,
 }
 
 impl<T> Clone for Executor<T> {
     fn clone(&self) -> Self {
-        Self { api: self.api.clone() }
+        Self { api: self.api.clone(), started: self.started }
     }
 }
 
 impl<T: API + 'static> Executor<T> {
     pub fn new(api: Arc<T>) -> Self {
-        Executor { api }
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Executor { api, started }
     }
 
     async fn execute_chat_message(
@@ -78,6 +90,8 @@ impl<T: API + 'static> Executor<T> {
         // Send StartStream action with the cancel_id
         tx.send(Ok(Action::StartStream(cancel_id.clone()))).await?;
 
+        // Subscribed before the turn starts, so that none of its events is missed
+        let mut events = self.api.lifecycle_events();
         match self.api.chat(chat_request).await {
             Ok(mut stream) => loop {
                 tokio::select! {
@@ -89,6 +103,15 @@ impl<T: API + 'static> Executor<T> {
                             None => break,
                         }
                     }
+                    Some(update) = events.next() => {
+                        let ours = match &update {
+                            LifecycleUpdate::Event(event) => event.conversation_id == conversation.id,
+                            LifecycleUpdate::Lagged { .. } => true,
+                        };
+                        if ours {
+                            tx.send(Ok(Action::Lifecycle(update))).await?;
+                        }
+                    }
                     _ = cancellation_token.cancelled() => {
                         break;
                     }
@@ -96,6 +119,7 @@ impl<T: API + 'static> Executor<T> {
             },
             Err(err) => return Err(err),
         }
+        tx.send(Ok(Action::EndStream)).await?;
         Ok(())
     }
 
@@ -147,6 +171,63 @@ impl<T: API + 'static> Executor<T> {
         Ok(())
     }
 
+    async fn execute_read_tasks(&self, tx: &Sender<anyhow::Result<Action>>) -> anyhow::Result<()> {
+        let tasks = self.api.tasks().await?;
+        tx.send(Ok(Action::Tasks(tasks))).await?;
+        Ok(())
+    }
+
+    async fn execute_read_workspace_stats(
+        &self,
+        tx: &Sender<anyhow::Result<Action>>,
+    ) -> anyhow::Result<()> {
+        let stats = self.api.workspace_stats(WELCOME_MAX_FILES).await?;
+        tx.send(Ok(Action::WorkspaceStats(stats))).await?;
+        Ok(())
+    }
+
+    async fn execute_read_file_tree(
+        &self,
+        tx: &Sender<anyhow::Result<Action>>,
+    ) -> anyhow::Result<()> {
+        let files = self.api.discover().await?;
+        tx.send(Ok(Action::FileTree(FileTree::new(files)))).await?;
+        Ok(())
+    }
+
+    /// Diffs each file changed during the session from its content before the
+    /// first change, which its oldest snapshot since then holds. The changes
+    /// that were undone have no snapshot left.
+    async fn execute_read_changes(
+        &self,
+        tx: &Sender<anyhow::Result<Action>>,
+    ) -> anyhow::Result<()> {
+        let cwd = self.api.environment().cwd;
+        let mut changes = Vec::new();
+        for snapshot in session_snapshots(self.api.snapshots().await?, self.started, &cwd) {
+            let old = self
+                .api
+                .snapshot_content(&snapshot)
+                .await?
+                .map(|content| String::from_utf8_lossy(&content).into_owned())
+                .unwrap_or_default();
+            // A file that was removed since is diffed as empty
+            let new = tokio::fs::read(&snapshot.path)
+                .await
+                .map(|content| String::from_utf8_lossy(&content).into_owned())
+                .unwrap_or_default();
+            let path = Path::new(&snapshot.path);
+            let path = path
+                .strip_prefix(&cwd)
+                .unwrap_or(path)
+                .display()
+                .to_string();
+            changes.extend(FileChange::new(path, &old, &new));
+        }
+        tx.send(Ok(Action::Changes(changes))).await?;
+        Ok(())
+    }
+
     async fn execute_empty(&self) -> anyhow::Result<()> {
         // Empty command doesn't send any action
         Ok(())
@@ -241,6 +322,18 @@ impl<T: API + 'static> Executor<T> {
             Command::ReadWorkspace => {
                 self.execute_read_workspace(&tx).await?;
             }
+            Command::ReadTasks => {
+                self.execute_read_tasks(&tx).await?;
+            }
+            Command::ReadWorkspaceStats => {
+                self.execute_read_workspace_stats(&tx).await?;
+            }
+            Command::ReadFileTree => {
+                self.execute_read_file_tree(&tx).await?;
+            }
+            Command::ReadChanges => {
+                self.execute_read_changes(&tx).await?;
+            }
             Command::Empty => {
                 self.execute_empty().await?;
             }
@@ -258,6 +351,9 @@ impl<T: API + 'static> Executor<T> {
                 // Send InterruptStream action to trigger state update
                 tx.send(Ok(Action::InterruptStream)).await?;
             }
+            Command::Steer { message, conversation_id } => {
+                self.api.steer(&conversation_id, message).await?;
+            }
         }
         Ok(())
     }
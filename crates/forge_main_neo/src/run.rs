@@ -17,8 +17,12 @@ pub async fn run(mut terminal: DefaultTerminal, cwd: PathBuf) -> anyhow::Result<
     let (action_tx, mut action_rx) = tokio::sync::mpsc::channel::<anyhow::Result<Action>>(1024);
     let (cmd_tx, cmd_rx) = tokio::sync::mpsc::channel::<Command>(1024);
 
-    let mut state = State::default();
-    let api = ForgeAPI::init(false, cwd);
+    // The colors of the command output and of the highlighted code are left
+    // out as https://no-color.org asks
+    let strip_ansi = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let mut state = State { strip_ansi, ..Default::default() };
+    // The output of the commands would be drawn over the TUI otherwise
+    let api = ForgeAPI::init_without_echo(false, cwd);
 
     // Initialize forge_tracker using the API instance
     let env = api.environment();
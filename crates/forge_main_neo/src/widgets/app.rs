@@ -1,7 +1,12 @@
-use ratatui::widgets::StatefulWidget;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::widgets::{StatefulWidget, Widget};
 
-use crate::domain::State;
+use crate::domain::{Pane, State};
+use crate::widgets::approval::ApprovalWidget;
+use crate::widgets::changes::ChangesWidget;
 use crate::widgets::chat::ChatWidget;
+use crate::widgets::file_tree::FileTreeWidget;
+use crate::widgets::question::QuestionWidget;
 
 #[derive(Clone, Default)]
 pub struct App;
@@ -16,6 +21,37 @@ impl StatefulWidget for App {
     ) where
         Self: Sized,
     {
-        ChatWidget.render(area, buf, state);
+        // The file tree on the left and the changes on the right of the chat,
+        // when they are shown
+        let show_files = state.panes.is_visible(Pane::Files);
+        let show_changes = state.panes.is_visible(Pane::Changes);
+        let [files_area, chat_area, changes_area] = Layout::horizontal([
+            Constraint::Length(if show_files { 32 } else { 0 }),
+            Constraint::Fill(1),
+            Constraint::Percentage(if show_changes { 45 } else { 0 }),
+        ])
+        .areas(area);
+
+        if show_files {
+            FileTreeWidget::new(&state.file_tree, state.panes.is_focused(Pane::Files))
+                .render(files_area, buf);
+        }
+        ChatWidget.render(chat_area, buf, state);
+        if show_changes {
+            ChangesWidget::new(
+                &state.changes,
+                state.strip_ansi,
+                state.panes.is_focused(Pane::Changes),
+            )
+            .render(changes_area, buf);
+        }
+
+        // The approval or question the agent is waiting for is shown over the
+        // panes
+        if let Some(approval) = state.approvals.front() {
+            ApprovalWidget::new(approval).render(area, buf);
+        } else if let Some(question) = state.questions.front() {
+            QuestionWidget::new(question).render(area, buf);
+        }
     }
 }
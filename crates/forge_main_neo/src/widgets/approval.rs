@@ -0,0 +1,129 @@
+use ratatui::layout::{Constraint, Flex, Layout};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Clear, Padding, Paragraph, Widget, Wrap};
+
+use crate::domain::PendingApproval;
+
+/// Popup asking for the approval the agent is waiting for. A change of a file
+/// is shown as a unified diff, hunk by hunk, with whether each is accepted.
+pub struct ApprovalWidget<'a> {
+    approval: &'a PendingApproval,
+}
+
+impl<'a> ApprovalWidget<'a> {
+    pub fn new(approval: &'a PendingApproval) -> Self {
+        Self { approval }
+    }
+
+    /// Lines of the hunks, along with the line where the selected one starts
+    fn hunk_lines(&self) -> (Vec<Line<'static>>, usize) {
+        let Some(review) = &self.approval.review else {
+            return (vec![], 0);
+        };
+
+        let mut lines = vec![Line::from(review.path.display().to_string()).bold()];
+        let mut selected_line = 0;
+        // Lines the hunks before add to the file, which move the ones after
+        let mut offset = 0isize;
+        for (index, hunk) in review.hunks.iter().enumerate() {
+            let accepted = self.approval.accepted.get(index).copied().unwrap_or(true);
+            let selected = index == self.approval.selected;
+            if selected {
+                selected_line = lines.len();
+            }
+
+            let marker = if selected { "▶ " } else { "  " };
+            let status = if accepted {
+                Span::from("[✓] ").green()
+            } else {
+                Span::from("[✗] ").red()
+            };
+            let new_start = hunk.old_start.saturating_add_signed(offset);
+            offset += hunk.added.len() as isize - hunk.removed.len() as isize;
+            let header = format!(
+                "@@ -{},{} +{},{} @@",
+                hunk.old_start + 1,
+                hunk.removed.len(),
+                new_start + 1,
+                hunk.added.len()
+            );
+            let header = if selected {
+                Span::from(header).cyan().bold()
+            } else {
+                Span::from(header).cyan()
+            };
+            lines.push(Line::from(vec![Span::from(marker), status, header]));
+
+            let removed = hunk
+                .removed
+                .iter()
+                .map(|line| ('-', line, Style::default().red()));
+            let added = hunk
+                .added
+                .iter()
+                .map(|line| ('+', line, Style::default().green()));
+            for (sign, line, style) in removed.chain(added) {
+                let text = format!("    {sign}{}", line.trim_end_matches(['\n', '\r']));
+                // Rejected hunks are dimmed, as they are left out of the change
+                let style = if accepted { style } else { style.dim() };
+                lines.push(Line::from(Span::styled(text, style)));
+            }
+        }
+        (lines, selected_line)
+    }
+}
+
+impl Widget for ApprovalWidget<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer)
+    where
+        Self: Sized,
+    {
+        let [area] = Layout::vertical([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        Clear.render(area, buf);
+
+        let keys = if self.approval.review.is_some() {
+            " ↑↓ SPACE toggle · a/r all · ENTER apply · ESC reject "
+        } else {
+            " ENTER accept · ESC reject "
+        };
+        let block = Block::bordered()
+            .title(" APPROVAL ")
+            .title_bottom(Line::from(keys).dim())
+            .padding(Padding::horizontal(1))
+            .border_style(Style::default().yellow());
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let message = Paragraph::new(Line::from(self.approval.message.clone()).yellow())
+            .wrap(Wrap { trim: false });
+        let message_width = self.approval.message.chars().count();
+        let message_height = u16::try_from(message_width.div_ceil(usize::from(inner.width.max(1))))
+            .unwrap_or(u16::MAX)
+            .min(inner.height);
+        let [message_area, hunks_area] = Layout::vertical([
+            Constraint::Length(message_height.saturating_add(1)),
+            Constraint::Fill(1),
+        ])
+        .areas(inner);
+        message.render(message_area, buf);
+
+        // The selected hunk is kept in view, starting near the top when the
+        // hunks don't fit
+        let (lines, selected_line) = self.hunk_lines();
+        let scroll = if lines.len() > usize::from(hunks_area.height) {
+            u16::try_from(selected_line.saturating_sub(1)).unwrap_or(u16::MAX)
+        } else {
+            0
+        };
+        Paragraph::new(lines)
+            .scroll((scroll, 0))
+            .render(hunks_area, buf);
+    }
+}
@@ -0,0 +1,61 @@
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Padding, Paragraph, Widget};
+
+use crate::domain::Changes;
+use crate::widgets::message_list::ansi_lines;
+
+/// Pane showing the diffs of the files the agent changed during the session
+pub struct ChangesWidget<'a> {
+    changes: &'a Changes,
+    strip_ansi: bool,
+    focused: bool,
+}
+
+impl<'a> ChangesWidget<'a> {
+    pub fn new(changes: &'a Changes, strip_ansi: bool, focused: bool) -> Self {
+        Self { changes, strip_ansi, focused }
+    }
+}
+
+impl Widget for ChangesWidget<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer)
+    where
+        Self: Sized,
+    {
+        let lines = if self.changes.files.is_empty() {
+            vec![Line::from(Span::styled(
+                "No files changed yet",
+                Style::default().dim(),
+            ))]
+        } else {
+            self.changes
+                .files
+                .iter()
+                .flat_map(|change| {
+                    let header = Line::from(vec![
+                        Span::from(change.path.clone()).bold(),
+                        Span::from(format!(" +{}", change.lines_added)).green(),
+                        Span::from(format!(" -{}", change.lines_removed)).red(),
+                    ]);
+                    std::iter::once(header).chain(ansi_lines(&change.diff, self.strip_ansi))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let border_style = if self.focused {
+            Style::default().cyan()
+        } else {
+            Style::default().dark_gray()
+        };
+        let block = Block::bordered()
+            .title(" CHANGES ")
+            .padding(Padding::horizontal(1))
+            .border_style(border_style);
+
+        Paragraph::new(lines)
+            .scroll((self.changes.scroll, 0))
+            .block(block)
+            .render(area, buf);
+    }
+}
@@ -7,6 +7,7 @@ use crate::domain::State;
 use crate::widgets::message_list::MessageList;
 use crate::widgets::spotlight::SpotlightWidget;
 use crate::widgets::status_bar::StatusBar;
+use crate::widgets::task_list::TaskListWidget;
 use crate::widgets::welcome::WelcomeWidget;
 
 /// Chat widget that handles the chat interface with editor and message list
@@ -30,6 +31,17 @@ impl StatefulWidget for ChatWidget {
         );
         let [messages_area, user_area] = chat_layout.areas(area);
 
+        // Show the tasks of the workspace in a sidebar next to the messages
+        let messages_area = if state.tasks.tasks().is_empty() {
+            messages_area
+        } else {
+            let [messages_area, tasks_area] =
+                Layout::horizontal([Constraint::Fill(1), Constraint::Length(32)])
+                    .areas(messages_area);
+            TaskListWidget::new(&state.tasks).render(tasks_area, buf);
+            messages_area
+        };
+
         // Messages area block (now at top)
         let message_block = Block::new();
 
@@ -52,6 +64,7 @@ impl StatefulWidget for ChatWidget {
                 "FORGE",
                 state.editor.mode.name(),
                 state.workspace.clone(),
+                state.token_budget.clone(),
             ));
 
         EditorView::new(&mut state.editor)
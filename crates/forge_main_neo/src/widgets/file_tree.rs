@@ -0,0 +1,63 @@
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Padding, Paragraph, Widget};
+
+use crate::domain::FileTree;
+
+/// Pane listing the files of the workspace as a tree
+pub struct FileTreeWidget<'a> {
+    file_tree: &'a FileTree,
+    focused: bool,
+}
+
+impl<'a> FileTreeWidget<'a> {
+    pub fn new(file_tree: &'a FileTree, focused: bool) -> Self {
+        Self { file_tree, focused }
+    }
+}
+
+impl Widget for FileTreeWidget<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer)
+    where
+        Self: Sized,
+    {
+        let lines = self
+            .file_tree
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let indent = "  ".repeat(entry.depth);
+                let span = if entry.is_dir {
+                    Span::from(format!("{indent}{}/", entry.name)).fg(Color::LightCyan)
+                } else {
+                    Span::from(format!("{indent}{}", entry.name))
+                };
+                if index == self.file_tree.selected && self.focused {
+                    Line::from(span.reversed())
+                } else {
+                    Line::from(span)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let border_style = if self.focused {
+            Style::default().cyan()
+        } else {
+            Style::default().dark_gray()
+        };
+        let block = Block::bordered()
+            .title(" FILES ")
+            .padding(Padding::horizontal(1))
+            .border_style(border_style);
+
+        // The selection is kept on screen, at the bottom once it goes past it
+        let height = usize::from(block.inner(area).height);
+        let offset = (self.file_tree.selected + 1).saturating_sub(height);
+
+        Paragraph::new(lines)
+            .scroll((u16::try_from(offset).unwrap_or(u16::MAX), 0))
+            .block(block)
+            .render(area, buf);
+    }
+}
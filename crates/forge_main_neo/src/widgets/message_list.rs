@@ -1,6 +1,6 @@
 use ansi_to_tui::IntoText;
 use color_eyre::owo_colors::OwoColorize;
-use forge_api::ChatResponse;
+use forge_api::{ChatResponse, CommandOutput};
 use ratatui::layout::Size;
 use ratatui::prelude::Widget;
 use ratatui::style::{Style, Stylize};
@@ -8,16 +8,60 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Paragraph, StatefulWidget, Wrap};
 use tui_scrollview::ScrollView;
 
-use crate::domain::{Message, State};
+use crate::domain::{Message, RenderedMessages, State};
 use crate::widgets::spinner::Spinner;
 
 #[derive(Default)]
 pub struct MessageList;
 
-fn messages_to_lines(messages: &[Message]) -> Vec<Line<'_>> {
+/// Lines of text holding ANSI escape codes, such as the colored output of
+/// cargo or git, styled the way the codes say. The codes are left out when
+/// `strip` is set or when they can't be parsed, instead of showing up as
+/// garbage.
+pub(crate) fn ansi_lines(text: &str, strip: bool) -> Vec<Line<'static>> {
+    match text.into_text() {
+        Ok(text) if !strip => text.lines,
+        _ => String::from_utf8_lossy(&strip_ansi_escapes::strip(text))
+            .lines()
+            .map(|line| Line::raw(line.to_string()))
+            .collect(),
+    }
+}
+
+/// Lines of the markdown rendered for the terminal, with its code
+/// highlighted unless the colors are stripped
+fn markdown_lines(markdown: &str, strip_ansi: bool) -> Vec<Line<'static>> {
+    let rendered = forge_display::MarkdownFormat::new()
+        .highlight_code(!strip_ansi)
+        .render(markdown);
+    ansi_lines(&rendered, strip_ansi)
+}
+
+/// Lines of the output of a command, the failed attempts of a retried one
+/// first, as the CLI shows them while the command runs
+fn command_output_lines(output: &CommandOutput, strip_ansi: bool) -> Vec<Line<'static>> {
+    output
+        .failed_attempts
+        .iter()
+        .chain(std::iter::once(output))
+        .flat_map(|attempt| {
+            ansi_lines(&attempt.stdout, strip_ansi)
+                .into_iter()
+                .chain(ansi_lines(&attempt.stderr, strip_ansi))
+        })
+        .collect()
+}
+
+fn messages_to_lines<'a>(
+    messages: &'a [Message],
+    hide_reasoning: bool,
+    strip_ansi: bool,
+    rendered: &mut RenderedMessages,
+) -> Vec<Line<'a>> {
     messages
         .iter()
-        .flat_map(|message| match message {
+        .enumerate()
+        .flat_map(|(position, message)| match message {
             Message::User(content) => vec![Line::from(vec![
                 Span::styled("❯ ", Style::default().green()),
                 Span::styled(content, Style::default().cyan().bold()),
@@ -27,16 +71,13 @@ fn messages_to_lines(messages: &[Message]) -> Vec<Line<'_>> {
                 ChatResponse::Text { text, is_complete, is_md } => {
                     if *is_complete {
                         if *is_md {
-                            let rendered_text = forge_display::MarkdownFormat::new().render(text);
-                            match rendered_text.into_text() {
-                                Ok(text) => text.lines.into_iter(),
-                                Err(_) => vec![Line::raw(rendered_text)].into_iter(),
-                            }
+                            rendered
+                                .lines(position, strip_ansi, || markdown_lines(text, strip_ansi))
+                                .into_iter()
                         } else {
-                            match text.clone().into_text() {
-                                Ok(text) => text.lines.into_iter(),
-                                Err(_) => vec![Line::raw(text.clone())].into_iter(),
-                            }
+                            rendered
+                                .lines(position, strip_ansi, || ansi_lines(text, strip_ansi))
+                                .into_iter()
                         }
                     } else {
                         vec![].into_iter()
@@ -44,28 +85,39 @@ fn messages_to_lines(messages: &[Message]) -> Vec<Line<'_>> {
                 }
                 ChatResponse::ToolCallStart(_) => vec![].into_iter(),
                 ChatResponse::ToolCallEnd(_) => vec![].into_iter(),
+                ChatResponse::CommandOutput(output) => rendered
+                    .lines(position, strip_ansi, || {
+                        command_output_lines(output, strip_ansi)
+                    })
+                    .into_iter(),
                 ChatResponse::Usage(_) => vec![].into_iter(),
+                ChatResponse::TokenBudget(budget) => match budget.compaction.as_ref() {
+                    Some(compaction) => vec![Line::from(Span::styled(
+                        format!(
+                            "Context compacted from ~{} to ~{} tokens as {}",
+                            compaction.before, compaction.after, compaction.trigger
+                        ),
+                        Style::default().dim(),
+                    ))]
+                    .into_iter(),
+                    None => vec![].into_iter(),
+                },
                 ChatResponse::Interrupt { reason: _ } => {
                     todo!()
                 }
                 ChatResponse::Reasoning { content } => {
-                    if !content.trim().is_empty() {
+                    if !hide_reasoning && !content.trim().is_empty() {
                         let dimmed_content = content.dimmed().to_string();
-                        match dimmed_content.into_text() {
-                            Ok(text) => text.lines.into_iter(),
-                            Err(_) => vec![Line::raw(dimmed_content)].into_iter(),
-                        }
+                        ansi_lines(&dimmed_content, false).into_iter()
                     } else {
                         vec![].into_iter()
                     }
                 }
                 ChatResponse::Summary { content } => {
                     if !content.trim().is_empty() {
-                        let rendered_text = forge_display::MarkdownFormat::new().render(content);
-                        match rendered_text.into_text() {
-                            Ok(text) => text.lines.into_iter(),
-                            Err(_) => vec![Line::raw(rendered_text)].into_iter(),
-                        }
+                        rendered
+                            .lines(position, strip_ansi, || markdown_lines(content, strip_ansi))
+                            .into_iter()
                     } else {
                         vec![].into_iter()
                     }
@@ -73,6 +125,39 @@ fn messages_to_lines(messages: &[Message]) -> Vec<Line<'_>> {
                 ChatResponse::RetryAttempt { cause: _, duration: _ } => {
                     todo!()
                 }
+                ChatResponse::ProviderWarning(warning) => vec![Line::from(Span::styled(
+                    format!("⚠ {warning}"),
+                    Style::default().yellow(),
+                ))]
+                .into_iter(),
+                ChatResponse::McpServer(event) => vec![Line::from(Span::styled(
+                    format!("⚠ {event}"),
+                    Style::default().yellow(),
+                ))]
+                .into_iter(),
+                ChatResponse::ApprovalRequest { message, .. } => vec![Line::from(Span::styled(
+                    format!("⚠ {message}"),
+                    Style::default().yellow(),
+                ))]
+                .into_iter(),
+                ChatResponse::Question { question, .. } => vec![Line::from(Span::styled(
+                    format!("? {question}"),
+                    Style::default().cyan(),
+                ))]
+                .into_iter(),
+                ChatResponse::Handoff { from, to } => vec![Line::from(Span::styled(
+                    format!("{from} handed off to {to}"),
+                    Style::default().cyan(),
+                ))]
+                .into_iter(),
+                ChatResponse::WorkItem(update) => vec![Line::from(Span::styled(
+                    format!(
+                        "[{}/{}] {} {}",
+                        update.index, update.total, update.item, update.status
+                    ),
+                    Style::default().dim(),
+                ))]
+                .into_iter(),
             },
         })
         .collect()
@@ -88,7 +173,12 @@ impl StatefulWidget for MessageList {
     ) where
         Self: Sized,
     {
-        let mut lines = messages_to_lines(&state.messages);
+        let mut lines = messages_to_lines(
+            &state.messages,
+            state.hide_reasoning,
+            state.strip_ansi,
+            &mut state.rendered_messages,
+        );
         let s = Spinner::default();
         if state.show_spinner {
             lines.push(s.to_line(state));
@@ -116,3 +206,73 @@ impl StatefulWidget for MessageList {
         scroll_view.render(area, buf, &mut state.message_scroll_state);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use ratatui::style::Color;
+
+    use super::*;
+
+    /// Text of each line, along with the colors of its spans
+    fn colored(lines: &[Line]) -> Vec<(String, Vec<Option<Color>>)> {
+        lines
+            .iter()
+            .map(|line| {
+                let colors = line
+                    .spans
+                    .iter()
+                    .filter(|span| !span.content.is_empty())
+                    .map(|span| span.style.fg)
+                    .collect();
+                (line.to_string(), colors)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ansi_lines() {
+        let fixture = "\x1b[32mok\x1b[0m\nplain";
+
+        let styled = colored(&ansi_lines(fixture, false));
+        let stripped = colored(&ansi_lines(fixture, true));
+
+        let actual = (styled[0].clone(), stripped);
+        let expected = (
+            ("ok".to_string(), vec![Some(Color::Green)]),
+            vec![
+                ("ok".to_string(), vec![None]),
+                ("plain".to_string(), vec![None]),
+            ],
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_command_output_lines_are_kept() {
+        let fixture = vec![Message::Assistant(ChatResponse::CommandOutput(
+            CommandOutput {
+                command: "cargo test".to_string(),
+                stdout: "running 1 test".to_string(),
+                stderr: "warning: unused".to_string(),
+                exit_code: Some(0),
+                failed_attempts: Vec::new(),
+                cwd: None,
+            },
+        ))];
+        let mut rendered = RenderedMessages::default();
+
+        let lines = messages_to_lines(&fixture, false, false, &mut rendered);
+        let kept = rendered.lines(0, false, Vec::new);
+
+        let actual = (
+            lines.iter().map(Line::to_string).collect::<Vec<_>>(),
+            kept.iter().map(Line::to_string).collect::<Vec<_>>(),
+        );
+        let expected = (
+            vec!["running 1 test".to_string(), "warning: unused".to_string()],
+            vec!["running 1 test".to_string(), "warning: unused".to_string()],
+        );
+        assert_eq!(actual, expected);
+    }
+}
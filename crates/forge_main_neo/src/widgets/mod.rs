@@ -1,10 +1,15 @@
 mod app;
+mod approval;
 // mod bordered_panel;
+mod changes;
 mod chat;
+mod file_tree;
 mod message_list;
+mod question;
 mod spinner;
 mod spotlight;
 mod status_bar;
+mod task_list;
 mod welcome;
 
 pub use app::App;
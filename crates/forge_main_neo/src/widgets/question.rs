@@ -0,0 +1,104 @@
+use ratatui::layout::{Constraint, Flex, Layout};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Clear, Padding, Paragraph, Widget, Wrap};
+
+use crate::domain::PendingQuestion;
+
+/// Popup asking the question the agent is waiting for an answer to, with its
+/// options or the answer typed so far
+pub struct QuestionWidget<'a> {
+    question: &'a PendingQuestion,
+}
+
+impl<'a> QuestionWidget<'a> {
+    pub fn new(question: &'a PendingQuestion) -> Self {
+        Self { question }
+    }
+
+    fn answer_lines(&self) -> Vec<Line<'static>> {
+        if self.question.options.is_empty() {
+            return vec![Line::from(vec![
+                Span::from("> ").cyan(),
+                Span::from(self.question.typed.clone()),
+                Span::from("█").dim(),
+            ])];
+        }
+        self.question
+            .options
+            .iter()
+            .enumerate()
+            .map(|(index, option)| {
+                let selected = index == self.question.selected;
+                let marker = if selected { "▶ " } else { "  " };
+                let picked = match self.question.picked.get(index) {
+                    Some(true) if self.question.multiple => Span::from("[✓] ").green(),
+                    _ if self.question.multiple => Span::from("[ ] ").dim(),
+                    _ => Span::from(""),
+                };
+                let option = if selected {
+                    Span::from(option.clone()).cyan().bold()
+                } else {
+                    Span::from(option.clone())
+                };
+                Line::from(vec![Span::from(marker), picked, option])
+            })
+            .collect()
+    }
+}
+
+impl Widget for QuestionWidget<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer)
+    where
+        Self: Sized,
+    {
+        let [area] = Layout::vertical([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        Clear.render(area, buf);
+
+        let keys = if self.question.options.is_empty() {
+            " ENTER answer · ESC skip "
+        } else if self.question.multiple {
+            " ↑↓ SPACE pick · ENTER answer · ESC skip "
+        } else {
+            " ↑↓ select · ENTER answer · ESC skip "
+        };
+        let block = Block::bordered()
+            .title(" QUESTION ")
+            .title_bottom(Line::from(keys).dim())
+            .padding(Padding::horizontal(1))
+            .border_style(Style::default().cyan());
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let question = Paragraph::new(Line::from(self.question.question.clone()).cyan())
+            .wrap(Wrap { trim: false });
+        let question_width = self.question.question.chars().count();
+        let question_height =
+            u16::try_from(question_width.div_ceil(usize::from(inner.width.max(1))))
+                .unwrap_or(u16::MAX)
+                .min(inner.height);
+        let [question_area, answer_area] = Layout::vertical([
+            Constraint::Length(question_height.saturating_add(1)),
+            Constraint::Fill(1),
+        ])
+        .areas(inner);
+        question.render(question_area, buf);
+
+        // The selected option is kept in view when the options don't fit
+        let scroll = if self.question.options.len() > usize::from(answer_area.height) {
+            u16::try_from(self.question.selected).unwrap_or(u16::MAX)
+        } else {
+            0
+        };
+        Paragraph::new(self.answer_lines())
+            .wrap(Wrap { trim: false })
+            .scroll((scroll, 0))
+            .render(answer_area, buf);
+    }
+}
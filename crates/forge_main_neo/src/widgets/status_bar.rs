@@ -1,3 +1,4 @@
+use forge_api::TokenBudget;
 use ratatui::layout::Alignment;
 use ratatui::style::{Color, Stylize};
 use ratatui::text::{Line, Span};
@@ -8,15 +9,22 @@ pub struct StatusBar {
     editor_status: Option<String>,
     agent: Option<String>,
     workspace: Workspace,
+    token_budget: Option<TokenBudget>,
 }
 
 impl StatusBar {
     /// Create a new StatusBar with all fields
-    pub fn new(agent: impl ToString, editor_status: impl ToString, workspace: Workspace) -> Self {
+    pub fn new(
+        agent: impl ToString,
+        editor_status: impl ToString,
+        workspace: Workspace,
+        token_budget: Option<TokenBudget>,
+    ) -> Self {
         Self {
             editor_status: Some(editor_status.to_string()),
             agent: Some(agent.to_string()),
             workspace,
+            token_budget,
         }
     }
 }
@@ -65,6 +73,16 @@ impl<'a> From<StatusBar> for Line<'a> {
             spans.push(Span::from(format!("{dir_name} ")).fg(Color::LightCyan));
         }
 
+        // Add the token meter, which turns red as the context window fills up
+        if let Some(budget) = value.token_budget {
+            let color = match budget.utilization() {
+                Some(utilization) if utilization >= 0.9 => Color::Red,
+                Some(utilization) if utilization >= 0.7 => Color::Yellow,
+                _ => Color::DarkGray,
+            };
+            spans.push(Span::from(format!("{budget} ")).fg(color));
+        }
+
         Line::from(spans).alignment(Alignment::Left).bold()
     }
 }
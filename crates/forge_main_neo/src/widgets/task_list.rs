@@ -0,0 +1,49 @@
+use forge_api::{Status, TaskList};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Padding, Paragraph, Widget, Wrap};
+
+/// Sidebar listing the tasks of the workspace with their status
+pub struct TaskListWidget<'a> {
+    tasks: &'a TaskList,
+}
+
+impl<'a> TaskListWidget<'a> {
+    pub fn new(tasks: &'a TaskList) -> Self {
+        Self { tasks }
+    }
+}
+
+impl Widget for TaskListWidget<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer)
+    where
+        Self: Sized,
+    {
+        let lines = self
+            .tasks
+            .tasks()
+            .iter()
+            .map(|task| {
+                let (glyph, style) = match task.status {
+                    Status::Pending => ("☐", Style::default()),
+                    Status::InProgress => ("▣", Style::default().fg(Color::Yellow)),
+                    Status::Done => ("◼", Style::default().fg(Color::DarkGray).crossed_out()),
+                };
+                Line::from(vec![
+                    Span::from(format!("{glyph} ")).style(style),
+                    Span::from(task.task.clone()).style(style),
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        let block = Block::bordered()
+            .title(" TASKS ")
+            .padding(Padding::horizontal(1))
+            .border_style(Style::default().dark_gray());
+
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(block)
+            .render(area, buf);
+    }
+}
@@ -1,3 +1,4 @@
+use forge_api::WorkspaceStats;
 use ratatui::layout::{Alignment, Constraint, Layout};
 use ratatui::style::{Color, Style, Stylize};
 use ratatui::text::{Line, Span};
@@ -5,8 +6,11 @@ use ratatui::widgets::{Paragraph, StatefulWidget, Widget, Wrap};
 
 use crate::domain::State;
 
-/// Welcome widget that displays the banner and keyboard shortcuts when no
-/// messages are present
+/// Languages shown in the summary of the workspace
+const SUMMARY_LANGUAGES: usize = 3;
+
+/// Welcome widget that displays the banner, the size of the workspace and
+/// keyboard shortcuts when no messages are present
 #[derive(Default)]
 pub struct WelcomeWidget;
 
@@ -16,7 +20,7 @@ impl StatefulWidget for WelcomeWidget {
         self,
         area: ratatui::prelude::Rect,
         buf: &mut ratatui::prelude::Buffer,
-        _state: &mut State,
+        state: &mut State,
     ) where
         Self: Sized,
     {
@@ -34,6 +38,12 @@ impl StatefulWidget for WelcomeWidget {
             banner_lines.push(Line::raw(line));
         }
 
+        // Summarize the workspace under the banner once it's counted
+        if let Some(stats) = &state.workspace.stats {
+            banner_lines.push(Line::raw(""));
+            banner_lines.push(Line::styled(summary(stats), Style::new().fg(Color::Gray)));
+        }
+
         // Render banner and welcome message in top section
         Paragraph::new(banner_lines)
             .style(Style::new().fg(Color::Yellow))
@@ -44,6 +54,9 @@ impl StatefulWidget for WelcomeWidget {
         // Create keyboard shortcuts for bottom section
         let shortcuts = vec![
             ("CTRL+D", "Exit application"),
+            ("CTRL+R", "Show or hide reasoning"),
+            ("CTRL+T", "Show or hide the file tree"),
+            ("CTRL+G", "Show or hide the changes"),
             ("TAB", "Navigate to next view"),
             ("SHIFT+TAB", "Navigate to previous view"),
             ("ENTER", "Submit message (in Chat mode)"),
@@ -81,3 +94,25 @@ impl StatefulWidget for WelcomeWidget {
             .render(right_layout, buf);
     }
 }
+
+/// Files and lines of the workspace with the share of its main languages, eg:
+/// `120 files · 8400 lines · Rust 80% · Markdown 15%`, the counts ending with
+/// a `+` when some files were left out
+fn summary(stats: &WorkspaceStats) -> String {
+    let more = if stats.truncated { "+" } else { "" };
+    let mut parts = vec![
+        format!("{}{more} files", stats.files),
+        format!("{}{more} lines", stats.lines),
+    ];
+    parts.extend(
+        stats
+            .languages
+            .iter()
+            .take(SUMMARY_LANGUAGES)
+            .filter_map(|language| {
+                let share = (language.lines * 100).checked_div(stats.lines)?;
+                Some(format!("{} {share}%", language.language))
+            }),
+    );
+    parts.join(" · ")
+}
@@ -10,10 +10,13 @@ pub struct ForgeConfigService<I> {
     infra: Arc<I>,
 }
 
-impl<I: FileReaderInfra + FileWriterInfra + EnvironmentInfra> ForgeConfigService<I> {
+impl<I> ForgeConfigService<I> {
     pub fn new(infra: Arc<I>) -> Self {
         Self { infra }
     }
+}
+
+impl<I: FileReaderInfra + FileWriterInfra + EnvironmentInfra> ForgeConfigService<I> {
     async fn read(&self) -> anyhow::Result<AppConfig> {
         let env = self.infra.get_environment();
         let config = self.infra.read(env.app_config().as_path()).await?;
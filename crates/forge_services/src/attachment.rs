@@ -78,7 +78,7 @@ impl<F: FileReaderInfra + EnvironmentInfra> AttachmentService for ForgeChatReque
 
 #[cfg(test)]
 pub mod tests {
-    use std::collections::{HashMap, HashSet};
+    use std::collections::{BTreeMap, HashMap, HashSet};
     use std::path::{Path, PathBuf};
     use std::sync::{Arc, Mutex};
 
@@ -86,17 +86,18 @@ pub mod tests {
     use bytes::Bytes;
     use forge_app::AttachmentService;
     use forge_app::domain::{
-        AttachmentContent, CommandOutput, Environment, ToolDefinition, ToolName, ToolOutput,
+        AttachmentContent, BackgroundProcess, CommandOutput, CommandSettings, Environment,
+        McpPrompt, ProcessLogs, ProjectEnv, ShellKind, ToolDefinition, ToolName, ToolOutput,
     };
-    use forge_snaps::Snapshot;
+    use forge_snaps::{Snapshot, SnapshotId};
     use serde_json::Value;
     use url::Url;
 
     use crate::attachment::ForgeChatRequest;
     use crate::{
-        CommandInfra, EnvironmentInfra, FileDirectoryInfra, FileInfoInfra, FileReaderInfra,
-        FileRemoverInfra, FileWriterInfra, McpClientInfra, McpServerInfra, SnapshotInfra,
-        UserInfra,
+        CommandInfra, EnvironmentInfra, FileDirectoryInfra, FileInfoInfra, FileLock,
+        FileReaderInfra, FileRemoverInfra, FileWriterInfra, McpClientInfra, McpServerInfra,
+        SnapshotInfra, UserInfra,
     };
 
     #[derive(Debug)]
@@ -122,6 +123,7 @@ pub mod tests {
                 stdout_max_line_length: 2000,
                 max_read_size: 2000,
                 tool_timeout: 300,
+                max_concurrent_commands: 4,
                 http: Default::default(),
                 max_file_size: 10_000_000,
                 forge_api_url: Url::parse("http://forgecode.dev/api").unwrap(),
@@ -133,6 +135,16 @@ pub mod tests {
         }
     }
 
+    impl EnvironmentInfra for MockFileService {
+        fn get_environment(&self) -> Environment {
+            MockEnvironmentInfra {}.get_environment()
+        }
+
+        fn get_env_var(&self, _key: &str) -> Option<String> {
+            None
+        }
+    }
+
     impl MockFileService {
         pub fn new() -> Self {
             let mut files = HashMap::new();
@@ -287,6 +299,21 @@ pub mod tests {
             Ok(())
         }
 
+        async fn append(&self, path: &Path, contents: Bytes) -> anyhow::Result<()> {
+            let mut files = self.files.lock().unwrap();
+            match files.iter_mut().find(|v| v.0 == path) {
+                Some((_, existing)) => {
+                    *existing = [existing.as_ref(), contents.as_ref()].concat().into();
+                }
+                None => files.push((path.to_path_buf(), contents)),
+            }
+            Ok(())
+        }
+
+        async fn replace(&self, path: &Path, contents: Bytes) -> anyhow::Result<()> {
+            self.write(path, contents, false).await
+        }
+
         async fn write_temp(&self, _: &str, _: &str, content: &str) -> anyhow::Result<PathBuf> {
             let temp_dir = crate::utils::TempDir::new().unwrap();
             let path = temp_dir.path();
@@ -295,6 +322,10 @@ pub mod tests {
 
             Ok(path)
         }
+
+        async fn lock(&self, _: &Path) -> anyhow::Result<FileLock> {
+            Ok(FileLock::default())
+        }
     }
 
     #[derive(Debug)]
@@ -310,6 +341,66 @@ pub mod tests {
         async fn undo_snapshot(&self, _: &Path) -> anyhow::Result<()> {
             unimplemented!()
         }
+
+        async fn list_snapshots(&self) -> anyhow::Result<Vec<Snapshot>> {
+            unimplemented!()
+        }
+
+        async fn undo_last_snapshot(&self) -> anyhow::Result<Snapshot> {
+            unimplemented!()
+        }
+
+        async fn restore_snapshot(&self, _: &SnapshotId) -> anyhow::Result<Snapshot> {
+            unimplemented!()
+        }
+
+        async fn snapshot_content(&self, _: &Snapshot) -> anyhow::Result<Option<Vec<u8>>> {
+            unimplemented!()
+        }
+
+        async fn snapshot_usage(&self) -> anyhow::Result<Vec<(Snapshot, u64)>> {
+            unimplemented!()
+        }
+
+        async fn remove_snapshots(&self, _: &[SnapshotId]) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+    }
+
+    /// Holds no snapshots
+    #[async_trait::async_trait]
+    impl SnapshotInfra for MockFileService {
+        async fn create_snapshot(&self, _: &Path) -> anyhow::Result<Snapshot> {
+            unimplemented!()
+        }
+
+        async fn undo_snapshot(&self, _: &Path) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        async fn list_snapshots(&self) -> anyhow::Result<Vec<Snapshot>> {
+            Ok(Vec::new())
+        }
+
+        async fn undo_last_snapshot(&self) -> anyhow::Result<Snapshot> {
+            unimplemented!()
+        }
+
+        async fn restore_snapshot(&self, _: &SnapshotId) -> anyhow::Result<Snapshot> {
+            unimplemented!()
+        }
+
+        async fn snapshot_content(&self, _: &Snapshot) -> anyhow::Result<Option<Vec<u8>>> {
+            unimplemented!()
+        }
+
+        async fn snapshot_usage(&self) -> anyhow::Result<Vec<(Snapshot, u64)>> {
+            Ok(Vec::new())
+        }
+
+        async fn remove_snapshots(&self, _: &[SnapshotId]) -> anyhow::Result<()> {
+            Ok(())
+        }
     }
 
     #[async_trait::async_trait]
@@ -355,6 +446,22 @@ pub mod tests {
         async fn call(&self, _: &ToolName, _: Value) -> anyhow::Result<ToolOutput> {
             Ok(ToolOutput::default())
         }
+
+        async fn prompts(&self) -> anyhow::Result<Vec<McpPrompt>> {
+            Ok(vec![])
+        }
+
+        async fn prompt(&self, _: &str, _: BTreeMap<String, String>) -> anyhow::Result<String> {
+            Ok(String::new())
+        }
+
+        async fn ping(&self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn set_roots(&self, _: Vec<PathBuf>) -> anyhow::Result<()> {
+            Ok(())
+        }
     }
 
     #[async_trait::async_trait]
@@ -375,6 +482,9 @@ pub mod tests {
             &self,
             command: String,
             working_dir: PathBuf,
+            _: Option<ShellKind>,
+            _: Option<String>,
+            _: &CommandSettings,
         ) -> anyhow::Result<CommandOutput> {
             // For test purposes, we'll create outputs that match what the shell tests
             // expect Check for common command patterns
@@ -386,6 +496,8 @@ pub mod tests {
                     stderr: "".to_string(),
                     command,
                     exit_code: Some(0),
+                    failed_attempts: Vec::new(),
+                    cwd: None,
                 });
             } else if command.contains("echo") {
                 if command.contains(">") && command.contains(">&2") {
@@ -405,6 +517,8 @@ pub mod tests {
                         stderr: stderr.to_string(),
                         command,
                         exit_code: Some(0),
+                        failed_attempts: Vec::new(),
+                        cwd: None,
                     });
                 } else if command.contains(">&2") {
                     // Command with only stderr
@@ -415,6 +529,8 @@ pub mod tests {
                         stderr: format!("{content}\n"),
                         command,
                         exit_code: Some(0),
+                        failed_attempts: Vec::new(),
+                        cwd: None,
                     });
                 } else {
                     // Standard echo command
@@ -443,6 +559,8 @@ pub mod tests {
                         stderr: "".to_string(),
                         command,
                         exit_code: Some(0),
+                        failed_attempts: Vec::new(),
+                        cwd: None,
                     });
                 }
             } else if command == "pwd" || command == "cd" {
@@ -452,6 +570,8 @@ pub mod tests {
                     stderr: "".to_string(),
                     command,
                     exit_code: Some(0),
+                    failed_attempts: Vec::new(),
+                    cwd: None,
                 });
             } else if command == "true" {
                 // true command returns success with no output
@@ -460,6 +580,8 @@ pub mod tests {
                     stderr: "".to_string(),
                     command,
                     exit_code: Some(0),
+                    failed_attempts: Vec::new(),
+                    cwd: None,
                 });
             } else if command.starts_with("/bin/ls") || command.contains("whoami") {
                 // Full path commands
@@ -468,6 +590,8 @@ pub mod tests {
                     stderr: "".to_string(),
                     command,
                     exit_code: Some(0),
+                    failed_attempts: Vec::new(),
+                    cwd: None,
                 });
             } else if command == "non_existent_command" {
                 // Command not found
@@ -476,6 +600,8 @@ pub mod tests {
                     stderr: "command not found: non_existent_command\n".to_string(),
                     command,
                     exit_code: Some(-1),
+                    failed_attempts: Vec::new(),
+                    cwd: None,
                 });
             }
 
@@ -485,6 +611,8 @@ pub mod tests {
                 stderr: "".to_string(),
                 command,
                 exit_code: Some(0),
+                failed_attempts: Vec::new(),
+                cwd: None,
             })
         }
 
@@ -492,9 +620,31 @@ pub mod tests {
             &self,
             _: &str,
             _: PathBuf,
+            _: &ProjectEnv,
         ) -> anyhow::Result<std::process::ExitStatus> {
             unimplemented!()
         }
+
+        async fn start_process(
+            &self,
+            _: String,
+            _: PathBuf,
+            _: &CommandSettings,
+        ) -> anyhow::Result<BackgroundProcess> {
+            unimplemented!()
+        }
+
+        async fn stop_process(&self, _: u32) -> anyhow::Result<BackgroundProcess> {
+            unimplemented!()
+        }
+
+        async fn process_logs(&self, _: u32, _: usize) -> anyhow::Result<ProcessLogs> {
+            unimplemented!()
+        }
+
+        async fn list_processes(&self) -> anyhow::Result<Vec<BackgroundProcess>> {
+            unimplemented!()
+        }
     }
 
     #[async_trait::async_trait]
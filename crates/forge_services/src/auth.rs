@@ -2,31 +2,54 @@ use std::sync::Arc;
 
 use anyhow::bail;
 use bytes::Bytes;
-use forge_app::dto::{InitAuth, LoginInfo};
+use chrono::Utc;
+use forge_app::dto::{AuthToken, InitAuth, LoginInfo};
 use forge_app::{AuthService, Error, User, UserUsage};
 use reqwest::Url;
 use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderValue};
+use serde::Deserialize;
 
 use crate::EnvironmentInfra;
 use crate::infra::HttpInfra;
 
-const AUTH_ROUTE: &str = "auth/sessions/";
+const DEVICE_CODE_ROUTE: &str = "auth/device/code";
+const TOKEN_ROUTE: &str = "auth/token";
 const USER_INFO_ROUTE: &str = "auth/user";
 const USER_USAGE_ROUTE: &str = "auth/usage";
 
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+const REFRESH_TOKEN_GRANT: &str = "refresh_token";
+
+/// Error response of the token endpoint as described in RFC 8628
+#[derive(Deserialize)]
+struct TokenError {
+    error: String,
+}
+
 #[derive(Default, Clone)]
 pub struct ForgeAuthService<I> {
     infra: Arc<I>,
 }
 
-impl<I: HttpInfra + EnvironmentInfra> ForgeAuthService<I> {
+impl<I> ForgeAuthService<I> {
     pub fn new(infra: Arc<I>) -> Self {
         Self { infra }
     }
+}
+
+impl<I: HttpInfra + EnvironmentInfra> ForgeAuthService<I> {
+    fn url(&self, route: &str) -> anyhow::Result<Url> {
+        Ok(Url::parse(&format!(
+            "{}{route}",
+            self.infra.get_environment().forge_api_url
+        ))?)
+    }
+
     async fn init(&self) -> anyhow::Result<InitAuth> {
-        let init_url = format!("{}{AUTH_ROUTE}", self.infra.get_environment().forge_api_url);
-        let init_url = Url::parse(&init_url)?;
-        let resp = self.infra.post(&init_url, Bytes::new()).await?;
+        let resp = self
+            .infra
+            .post(&self.url(DEVICE_CODE_ROUTE)?, None, Bytes::new())
+            .await?;
         if !resp.status().is_success() {
             bail!("Failed to initialize auth")
         }
@@ -35,26 +58,62 @@ impl<I: HttpInfra + EnvironmentInfra> ForgeAuthService<I> {
     }
 
     async fn login(&self, auth: &InitAuth) -> anyhow::Result<LoginInfo> {
-        let url = format!(
-            "{}{AUTH_ROUTE}{}",
-            self.infra.get_environment().forge_api_url,
-            auth.session_id
-        );
-        let url = Url::parse(&url)?;
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            AUTHORIZATION,
-            HeaderValue::from_str(&format!("Bearer {}", auth.token))?,
-        );
+        let body = serde_json::json!({
+            "grant_type": DEVICE_CODE_GRANT,
+            "device_code": auth.device_code,
+        });
+        let response = self
+            .infra
+            .post(
+                &self.url(TOKEN_ROUTE)?,
+                None,
+                serde_json::to_vec(&body)?.into(),
+            )
+            .await?;
+        let status = response.status().as_u16();
+        let bytes = response.bytes().await?;
+        if status == 200 {
+            let token = serde_json::from_slice::<AuthToken>(&bytes)?;
+            return Ok(LoginInfo::from_token(token, Utc::now()));
+        }
+
+        let error = serde_json::from_slice::<TokenError>(&bytes)
+            .map(|e| e.error)
+            .unwrap_or_default();
+        match error.as_str() {
+            "authorization_pending" | "slow_down" => Err(Error::AuthInProgress.into()),
+            "expired_token" => bail!("Login code expired, please try again"),
+            "access_denied" => bail!("Login was denied"),
+            _ => bail!("HTTP {}: Authentication failed", status),
+        }
+    }
 
-        let response = self.infra.get(&url, Some(headers)).await?;
-        match response.status().as_u16() {
-            200 => Ok(serde_json::from_slice::<LoginInfo>(
-                &response.bytes().await?,
-            )?),
-            202 => Err(Error::AuthInProgress.into()),
-            status => bail!("HTTP {}: Authentication failed", status),
+    async fn refresh(&self, refresh_token: &str) -> anyhow::Result<LoginInfo> {
+        let body = serde_json::json!({
+            "grant_type": REFRESH_TOKEN_GRANT,
+            "refresh_token": refresh_token,
+        });
+        let response = self
+            .infra
+            .post(
+                &self.url(TOKEN_ROUTE)?,
+                None,
+                serde_json::to_vec(&body)?.into(),
+            )
+            .await?;
+        if !response.status().is_success() {
+            bail!(
+                "HTTP {}: Failed to refresh the login, please run /login again",
+                response.status().as_u16()
+            )
         }
+
+        let mut token = serde_json::from_slice::<AuthToken>(&response.bytes().await?)?;
+        // Servers may omit the refresh token when it isn't rotated
+        token
+            .refresh_token
+            .get_or_insert_with(|| refresh_token.to_string());
+        Ok(LoginInfo::from_token(token, Utc::now()))
     }
 
     async fn user_info(&self, api_key: &str) -> anyhow::Result<User> {
@@ -110,6 +169,10 @@ impl<I: HttpInfra + EnvironmentInfra> AuthService for ForgeAuthService<I> {
         self.login(auth).await
     }
 
+    async fn refresh(&self, refresh_token: &str) -> anyhow::Result<LoginInfo> {
+        self.refresh(refresh_token).await
+    }
+
     async fn user_info(&self, api_key: &str) -> anyhow::Result<User> {
         self.user_info(api_key).await
     }
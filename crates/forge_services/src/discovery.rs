@@ -1,10 +1,14 @@
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::Result;
-use forge_app::domain::File;
+use forge_app::domain::{File, ProjectMemory};
 use forge_app::{FileDiscoveryService, Walker};
 
-use crate::{EnvironmentInfra, WalkerInfra};
+use crate::{DirectoryReaderInfra, EnvironmentInfra, FileInfoInfra, FileReaderInfra, WalkerInfra};
+
+/// Files of instructions read from each directory, ahead of `.forge/rules`
+const MEMORY_FILES: [&str; 2] = ["AGENTS.md", "FORGE.md"];
 
 pub struct ForgeDiscoveryService<F> {
     service: Arc<F>,
@@ -26,11 +30,145 @@ impl<F: EnvironmentInfra + WalkerInfra> ForgeDiscoveryService<F> {
     }
 }
 
+impl<F: FileReaderInfra + FileInfoInfra + DirectoryReaderInfra> ForgeDiscoveryService<F> {
+    /// Finds the root of the repository holding the directory
+    async fn repository_root<'a>(&self, cwd: &'a Path) -> Option<&'a Path> {
+        for dir in cwd.ancestors() {
+            if self
+                .service
+                .exists(&dir.join(".git"))
+                .await
+                .unwrap_or_default()
+            {
+                return Some(dir);
+            }
+        }
+        None
+    }
+
+    /// Finds the git directory of the repository holding the directory. In a
+    /// worktree `.git` is a file pointing to it.
+    async fn git_dir(&self, cwd: &Path) -> Option<PathBuf> {
+        let root = self.repository_root(cwd).await?;
+        let git = root.join(".git");
+        if !self.service.is_file(&git).await.unwrap_or_default() {
+            return Some(git);
+        }
+        let content = self.service.read_utf8(&git).await.ok()?;
+        let path = content.trim().strip_prefix("gitdir:")?.trim();
+        Some(root.join(path))
+    }
+
+    /// Reads the files of instructions kept in the directory
+    async fn memory_in(&self, dir: &Path) -> Vec<(PathBuf, String)> {
+        let mut memory = Vec::new();
+        for name in MEMORY_FILES {
+            let path = dir.join(name);
+            if self.service.is_file(&path).await.unwrap_or_default()
+                && let Ok(content) = self.service.read_utf8(&path).await
+            {
+                memory.push((path, content));
+            }
+        }
+
+        let mut rules = self
+            .service
+            .read_directory_files(&dir.join(".forge").join("rules"), Some("*.md"))
+            .await
+            .unwrap_or_default();
+        rules.sort_by(|a, b| a.0.cmp(&b.0));
+        memory.extend(rules);
+        memory
+    }
+}
+
+/// Branch named by the HEAD file of a repository, `None` when the HEAD is
+/// detached
+fn branch_from_head(head: &str) -> Option<String> {
+    head.trim()
+        .strip_prefix("ref: refs/heads/")
+        .map(|branch| branch.to_string())
+}
+
+/// Directories from the root down to the working directory, so that the
+/// instructions of nested directories come last
+fn memory_dirs<'a>(cwd: &'a Path, root: &Path) -> Vec<&'a Path> {
+    let mut dirs = cwd
+        .ancestors()
+        .take_while(|dir| dir.starts_with(root))
+        .collect::<Vec<_>>();
+    dirs.reverse();
+    dirs
+}
+
 #[async_trait::async_trait]
-impl<F: EnvironmentInfra + WalkerInfra + Send + Sync> FileDiscoveryService
-    for ForgeDiscoveryService<F>
+impl<
+    F: EnvironmentInfra
+        + WalkerInfra
+        + FileReaderInfra
+        + FileInfoInfra
+        + DirectoryReaderInfra
+        + Send
+        + Sync,
+> FileDiscoveryService for ForgeDiscoveryService<F>
 {
     async fn collect_files(&self, config: Walker) -> Result<Vec<File>> {
         self.discover_with_config(config).await
     }
+
+    async fn git_branch(&self, cwd: &Path) -> Option<String> {
+        let git_dir = self.git_dir(cwd).await?;
+        let head = self.service.read_utf8(&git_dir.join("HEAD")).await.ok()?;
+        branch_from_head(&head)
+    }
+
+    async fn project_memory(&self, cwd: &Path) -> Vec<ProjectMemory> {
+        // Outside of a repository only the working directory is looked at
+        let root = self.repository_root(cwd).await.unwrap_or(cwd);
+
+        let mut memory = Vec::new();
+        for dir in memory_dirs(cwd, root) {
+            memory.extend(
+                self.memory_in(dir)
+                    .await
+                    .into_iter()
+                    .filter(|(_, content)| !content.trim().is_empty())
+                    .map(|(path, content)| ProjectMemory {
+                        path: path.display().to_string(),
+                        content: content.trim().to_string(),
+                    }),
+            );
+        }
+        memory
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_branch_from_head() {
+        let actual = [
+            branch_from_head("ref: refs/heads/feature/login\n"),
+            branch_from_head("3f1c2a9e8b7d6c5f4e3a2b1c0d9e8f7a6b5c4d3e\n"),
+        ];
+
+        let expected = [Some("feature/login".to_string()), None];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_memory_dirs() {
+        let actual = memory_dirs(Path::new("/project/crates/app"), Path::new("/project"));
+
+        let expected = vec![
+            Path::new("/project"),
+            Path::new("/project/crates"),
+            Path::new("/project/crates/app"),
+        ];
+        assert_eq!(actual, expected);
+    }
 }
@@ -10,19 +10,33 @@ use crate::conversation::ForgeConversationService;
 use crate::discovery::ForgeDiscoveryService;
 use crate::env::ForgeEnvironmentService;
 use crate::infra::HttpInfra;
+use crate::lifecycle::ForgeLifecycle;
+use crate::mailbox::ForgeMailbox;
 use crate::mcp::{ForgeMcpManager, ForgeMcpService};
+use crate::memory::ForgeMemory;
+use crate::openapi::ForgeOpenApiService;
+use crate::plugin::ForgePluginService;
 use crate::policy::ForgePolicyService;
 use crate::provider::{ForgeProviderRegistry, ForgeProviderService};
+use crate::request_recorder::ForgeRequestRecorder;
+use crate::sessions::ForgeSessions;
+use crate::steering::ForgeSteering;
+use crate::storage::ForgeStorage;
+use crate::tasks::ForgeTasks;
 use crate::template::ForgeTemplateService;
+use crate::tool_audit::ForgeToolAudit;
 use crate::tool_services::{
-    ForgeFetch, ForgeFollowup, ForgeFsCreate, ForgeFsPatch, ForgeFsRead, ForgeFsRemove,
-    ForgeFsSearch, ForgeFsUndo, ForgePlanCreate, ForgeShell,
+    ForgeBrowser, ForgeFetch, ForgeFollowup, ForgeFsApplyPatch, ForgeFsAstEdit, ForgeFsCreate,
+    ForgeFsPatch, ForgeFsPreview, ForgeFsRead, ForgeFsRemove, ForgeFsSearch, ForgeFsTransaction,
+    ForgeFsUndo, ForgeLsp, ForgePlanCreate, ForgePython, ForgeShell, ForgeSymbolIndex,
+    ForgeWorkspaceStats,
 };
 use crate::workflow::ForgeWorkflowService;
+use crate::workspace_index::ForgeWorkspaceIndex;
 use crate::{
     CommandInfra, DirectoryReaderInfra, EnvironmentInfra, FileDirectoryInfra, FileInfoInfra,
-    FileReaderInfra, FileRemoverInfra, FileWriterInfra, McpServerInfra, SnapshotInfra, UserInfra,
-    WalkerInfra,
+    FileReaderInfra, FileRemoverInfra, FileWriterInfra, McpServerInfra, OAuthRedirectInfra,
+    PluginInfra, SnapshotInfra, UserInfra, WalkerInfra,
 };
 
 type McpService<F> = ForgeMcpService<ForgeMcpManager<F>, F, <F as McpServerInfra>::Client>;
@@ -49,17 +63,38 @@ pub struct ForgeServices<F: HttpInfra + EnvironmentInfra + McpServerInfra + Walk
     file_search_service: Arc<ForgeFsSearch<F>>,
     file_remove_service: Arc<ForgeFsRemove<F>>,
     file_patch_service: Arc<ForgeFsPatch<F>>,
+    file_apply_patch_service: Arc<ForgeFsApplyPatch<F>>,
+    file_ast_edit_service: Arc<ForgeFsAstEdit<F>>,
+    file_preview_service: Arc<ForgeFsPreview<F>>,
+    file_transaction_service: Arc<ForgeFsTransaction<F>>,
     file_undo_service: Arc<ForgeFsUndo<F>>,
     shell_service: Arc<ForgeShell<F>>,
+    python_service: Arc<ForgePython>,
     fetch_service: Arc<ForgeFetch>,
+    browser_service: Arc<ForgeBrowser>,
+    lsp_service: Arc<ForgeLsp<F>>,
+    workspace_index_service: Arc<ForgeWorkspaceIndex<F, ForgeProviderService<F>>>,
+    symbol_index_service: Arc<ForgeSymbolIndex<F>>,
+    workspace_stats_service: Arc<ForgeWorkspaceStats<F>>,
     followup_service: Arc<ForgeFollowup<F>>,
     mcp_service: Arc<McpService<F>>,
+    plugin_service: Arc<ForgePluginService<F>>,
+    openapi_service: Arc<ForgeOpenApiService<F>>,
     env_service: Arc<ForgeEnvironmentService<F>>,
     config_service: Arc<ForgeConfigService<F>>,
     auth_service: Arc<AuthService<F>>,
     provider_service: Arc<ForgeProviderRegistry<F>>,
     agent_loader_service: Arc<ForgeAgentLoaderService<F>>,
     policy_service: ForgePolicyService<F>,
+    tool_audit_service: Arc<ForgeToolAudit<F>>,
+    task_service: Arc<ForgeTasks<F>>,
+    memory_service: Arc<ForgeMemory<F>>,
+    request_recorder_service: Arc<ForgeRequestRecorder<F>>,
+    session_service: Arc<ForgeSessions<F>>,
+    storage_service: Arc<ForgeStorage<F>>,
+    mailbox_service: Arc<ForgeMailbox>,
+    steering_service: Arc<ForgeSteering>,
+    lifecycle_service: Arc<ForgeLifecycle>,
 }
 
 impl<
@@ -71,16 +106,23 @@ impl<
         + HttpInfra
         + WalkerInfra
         + DirectoryReaderInfra
-        + UserInfra,
+        + UserInfra
+        + PluginInfra
+        + OAuthRedirectInfra,
 > ForgeServices<F>
 {
     pub fn new(infra: Arc<F>) -> Self {
         let mcp_manager = Arc::new(ForgeMcpManager::new(infra.clone()));
         let mcp_service = Arc::new(ForgeMcpService::new(mcp_manager.clone(), infra.clone()));
+        let plugin_service = Arc::new(ForgePluginService::new(infra.clone()));
         let template_service = Arc::new(ForgeTemplateService::new(infra.clone()));
         let attachment_service = Arc::new(ForgeChatRequest::new(infra.clone()));
 
         let workflow_service = Arc::new(ForgeWorkflowService::new(infra.clone()));
+        let openapi_service = Arc::new(ForgeOpenApiService::new(
+            infra.clone(),
+            workflow_service.clone(),
+        ));
         let suggestion_service = Arc::new(ForgeDiscoveryService::new(infra.clone()));
         let conversation_service = Arc::new(ForgeConversationService::new(mcp_service.clone()));
         let config_service = Arc::new(ForgeConfigService::new(infra.clone()));
@@ -92,14 +134,39 @@ impl<
         let file_search_service = Arc::new(ForgeFsSearch::new(infra.clone()));
         let file_remove_service = Arc::new(ForgeFsRemove::new(infra.clone()));
         let file_patch_service = Arc::new(ForgeFsPatch::new(infra.clone()));
+        let file_apply_patch_service = Arc::new(ForgeFsApplyPatch::new(infra.clone()));
+        let file_ast_edit_service = Arc::new(ForgeFsAstEdit::new(infra.clone()));
+        let file_preview_service = Arc::new(ForgeFsPreview::new(infra.clone()));
+        let file_transaction_service = Arc::new(ForgeFsTransaction::new(infra.clone()));
         let file_undo_service = Arc::new(ForgeFsUndo::new(infra.clone()));
         let shell_service = Arc::new(ForgeShell::new(infra.clone()));
+        let python_service = Arc::new(ForgePython::new());
         let fetch_service = Arc::new(ForgeFetch::new());
+        let browser_service = Arc::new(ForgeBrowser::new());
+        let lsp_service = Arc::new(ForgeLsp::new(infra.clone()));
+        let workspace_index_service =
+            Arc::new(ForgeWorkspaceIndex::new(infra.clone(), chat_service.clone()));
+        let symbol_index_service = Arc::new(ForgeSymbolIndex::new(infra.clone()));
+        let workspace_stats_service = Arc::new(ForgeWorkspaceStats::new(infra.clone()));
         let followup_service = Arc::new(ForgeFollowup::new(infra.clone()));
-        let provider_service = Arc::new(ForgeProviderRegistry::new(infra.clone()));
+        let provider_service = Arc::new(ForgeProviderRegistry::new(
+            infra.clone(),
+            workflow_service.clone(),
+            auth_service.clone(),
+            config_service.clone(),
+        ));
         let env_service = Arc::new(ForgeEnvironmentService::new(infra.clone()));
         let agent_loader_service = Arc::new(ForgeAgentLoaderService::new(infra.clone()));
         let policy_service = ForgePolicyService::new(infra.clone());
+        let tool_audit_service = Arc::new(ForgeToolAudit::new(infra.clone()));
+        let task_service = Arc::new(ForgeTasks::new(infra.clone()));
+        let memory_service = Arc::new(ForgeMemory::new(infra.clone()));
+        let request_recorder_service = Arc::new(ForgeRequestRecorder::new(infra.clone()));
+        let session_service = Arc::new(ForgeSessions::new(infra.clone()));
+        let storage_service = Arc::new(ForgeStorage::new(infra.clone(), session_service.clone()));
+        let mailbox_service = Arc::new(ForgeMailbox::new());
+        let steering_service = Arc::new(ForgeSteering::new());
+        let lifecycle_service = Arc::new(ForgeLifecycle::new());
 
         Self {
             conversation_service,
@@ -114,11 +181,23 @@ impl<
             file_search_service,
             file_remove_service,
             file_patch_service,
+            file_apply_patch_service,
+            file_ast_edit_service,
+            file_preview_service,
+            file_transaction_service,
             file_undo_service,
             shell_service,
+            python_service,
             fetch_service,
+            browser_service,
+            lsp_service,
+            workspace_index_service,
+            symbol_index_service,
+            workspace_stats_service,
             followup_service,
             mcp_service,
+            plugin_service,
+            openapi_service,
             env_service,
             config_service,
             auth_service,
@@ -126,6 +205,15 @@ impl<
             provider_service,
             agent_loader_service,
             policy_service,
+            tool_audit_service,
+            task_service,
+            memory_service,
+            request_recorder_service,
+            session_service,
+            storage_service,
+            mailbox_service,
+            steering_service,
+            lifecycle_service,
         }
     }
 }
@@ -144,6 +232,8 @@ impl<
         + DirectoryReaderInfra
         + HttpInfra
         + WalkerInfra
+        + PluginInfra
+        + OAuthRedirectInfra
         + Clone,
 > Services for ForgeServices<F>
 {
@@ -158,19 +248,41 @@ impl<
     type FsCreateService = ForgeFsCreate<F>;
     type PlanCreateService = ForgePlanCreate<F>;
     type FsPatchService = ForgeFsPatch<F>;
+    type FsApplyPatchService = ForgeFsApplyPatch<F>;
+    type FsAstEditService = ForgeFsAstEdit<F>;
+    type FsPreviewService = ForgeFsPreview<F>;
+    type FsTransactionService = ForgeFsTransaction<F>;
     type FsReadService = ForgeFsRead<F>;
     type FsRemoveService = ForgeFsRemove<F>;
     type FsSearchService = ForgeFsSearch<F>;
     type FollowUpService = ForgeFollowup<F>;
     type FsUndoService = ForgeFsUndo<F>;
     type NetFetchService = ForgeFetch;
+    type NetRequestService = ForgeFetch;
+    type BrowserService = ForgeBrowser;
+    type LspService = ForgeLsp<F>;
+    type WorkspaceIndexService = ForgeWorkspaceIndex<F, ForgeProviderService<F>>;
+    type SymbolIndexService = ForgeSymbolIndex<F>;
+    type WorkspaceStatsService = ForgeWorkspaceStats<F>;
     type ShellService = ForgeShell<F>;
+    type PythonService = ForgePython;
     type McpService = McpService<F>;
+    type PluginService = ForgePluginService<F>;
+    type OpenApiService = ForgeOpenApiService<F>;
     type AppConfigService = ForgeConfigService<F>;
     type AuthService = AuthService<F>;
     type ProviderRegistry = ForgeProviderRegistry<F>;
     type AgentLoaderService = ForgeAgentLoaderService<F>;
     type PolicyService = ForgePolicyService<F>;
+    type ToolAuditService = ForgeToolAudit<F>;
+    type TaskService = ForgeTasks<F>;
+    type MemoryService = ForgeMemory<F>;
+    type RequestRecorderService = ForgeRequestRecorder<F>;
+    type SessionService = ForgeSessions<F>;
+    type StorageService = ForgeStorage<F>;
+    type MailboxService = ForgeMailbox;
+    type SteeringService = ForgeSteering;
+    type LifecycleService = ForgeLifecycle;
 
     fn provider_service(&self) -> &Self::ProviderService {
         &self.chat_service
@@ -216,6 +328,22 @@ impl<
         &self.file_patch_service
     }
 
+    fn fs_apply_patch_service(&self) -> &Self::FsApplyPatchService {
+        &self.file_apply_patch_service
+    }
+
+    fn fs_ast_edit_service(&self) -> &Self::FsAstEditService {
+        &self.file_ast_edit_service
+    }
+
+    fn fs_preview_service(&self) -> &Self::FsPreviewService {
+        &self.file_preview_service
+    }
+
+    fn fs_transaction_service(&self) -> &Self::FsTransactionService {
+        &self.file_transaction_service
+    }
+
     fn fs_read_service(&self) -> &Self::FsReadService {
         &self.file_read_service
     }
@@ -240,14 +368,50 @@ impl<
         &self.fetch_service
     }
 
+    fn net_request_service(&self) -> &Self::NetRequestService {
+        &self.fetch_service
+    }
+
+    fn browser_service(&self) -> &Self::BrowserService {
+        &self.browser_service
+    }
+
+    fn lsp_service(&self) -> &Self::LspService {
+        &self.lsp_service
+    }
+
+    fn workspace_index_service(&self) -> &Self::WorkspaceIndexService {
+        &self.workspace_index_service
+    }
+
+    fn symbol_index_service(&self) -> &Self::SymbolIndexService {
+        &self.symbol_index_service
+    }
+
+    fn workspace_stats_service(&self) -> &Self::WorkspaceStatsService {
+        &self.workspace_stats_service
+    }
+
     fn shell_service(&self) -> &Self::ShellService {
         &self.shell_service
     }
 
+    fn python_service(&self) -> &Self::PythonService {
+        &self.python_service
+    }
+
     fn mcp_service(&self) -> &Self::McpService {
         &self.mcp_service
     }
 
+    fn plugin_service(&self) -> &Self::PluginService {
+        &self.plugin_service
+    }
+
+    fn openapi_service(&self) -> &Self::OpenApiService {
+        &self.openapi_service
+    }
+
     fn auth_service(&self) -> &Self::AuthService {
         self.auth_service.as_ref()
     }
@@ -266,4 +430,40 @@ impl<
     fn policy_service(&self) -> &Self::PolicyService {
         &self.policy_service
     }
+
+    fn tool_audit_service(&self) -> &Self::ToolAuditService {
+        &self.tool_audit_service
+    }
+
+    fn task_service(&self) -> &Self::TaskService {
+        &self.task_service
+    }
+
+    fn memory_service(&self) -> &Self::MemoryService {
+        &self.memory_service
+    }
+
+    fn request_recorder_service(&self) -> &Self::RequestRecorderService {
+        &self.request_recorder_service
+    }
+
+    fn session_service(&self) -> &Self::SessionService {
+        &self.session_service
+    }
+
+    fn storage_service(&self) -> &Self::StorageService {
+        &self.storage_service
+    }
+
+    fn mailbox_service(&self) -> &Self::MailboxService {
+        &self.mailbox_service
+    }
+
+    fn steering_service(&self) -> &Self::SteeringService {
+        &self.steering_service
+    }
+
+    fn lifecycle_service(&self) -> &Self::LifecycleService {
+        &self.lifecycle_service
+    }
 }
@@ -22,8 +22,13 @@ impl<T: HttpInfra> HttpClientService for HttpClient<T> {
     async fn get(&self, url: &Url, headers: Option<HeaderMap>) -> anyhow::Result<Response> {
         self.0.get(url, headers).await
     }
-    async fn post(&self, url: &Url, body: bytes::Bytes) -> anyhow::Result<Response> {
-        self.0.post(url, body).await
+    async fn post(
+        &self,
+        url: &Url,
+        headers: Option<HeaderMap>,
+        body: bytes::Bytes,
+    ) -> anyhow::Result<Response> {
+        self.0.post(url, headers, body).await
     }
     async fn delete(&self, url: &Url) -> anyhow::Result<Response> {
         self.0.delete(url).await
@@ -1,15 +1,18 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use bytes::Bytes;
 use forge_app::domain::{
-    CommandOutput, Environment, McpServerConfig, ToolDefinition, ToolName, ToolOutput,
+    BackgroundProcess, CommandOutput, CommandSettings, Environment, McpPrompt, McpServerConfig,
+    Plugin, ProcessLogs, ProjectEnv, ShellKind, ToolDefinition, ToolName, ToolOutput,
 };
 use forge_app::{WalkedFile, Walker};
-use forge_snaps::Snapshot;
+use forge_snaps::{Snapshot, SnapshotId};
 use reqwest::Response;
 use reqwest::header::HeaderMap;
 use reqwest_eventsource::EventSource;
+use tokio::sync::oneshot;
 use url::Url;
 
 pub trait EnvironmentInfra: Send + Sync {
@@ -65,6 +68,15 @@ pub trait FileWriterInfra: Send + Sync {
         capture_snapshot: bool,
     ) -> anyhow::Result<()>;
 
+    /// Appends the content to the end of the file at the specified path,
+    /// creating the file if it doesn't exist.
+    async fn append(&self, path: &Path, contents: Bytes) -> anyhow::Result<()>;
+
+    /// Writes the content to a temporary file next to the file at the
+    /// specified path and renames it over the file, so that the file is never
+    /// left half written.
+    async fn replace(&self, path: &Path, contents: Bytes) -> anyhow::Result<()>;
+
     /// Writes content to a temporary file with the given prefix and extension,
     /// and returns its path. The file will be kept (not deleted) after
     /// creation.
@@ -74,6 +86,22 @@ pub trait FileWriterInfra: Send + Sync {
     /// * `ext` - File extension (e.g. ".txt", ".md")
     /// * `content` - Content to write to the file
     async fn write_temp(&self, prefix: &str, ext: &str, content: &str) -> anyhow::Result<PathBuf>;
+
+    /// Takes the lock of the file at the specified path, waiting while another
+    /// process of forge holds it, so that a file read, changed and written
+    /// back doesn't lose the changes of the other processes. The lock is
+    /// released when the returned guard is dropped.
+    async fn lock(&self, path: &Path) -> anyhow::Result<FileLock>;
+}
+
+/// Lock of a file taken with [`FileWriterInfra::lock`], released when dropped
+#[derive(Debug, Default)]
+pub struct FileLock(Option<std::fs::File>);
+
+impl FileLock {
+    pub fn new(file: std::fs::File) -> Self {
+        Self(Some(file))
+    }
 }
 
 #[async_trait::async_trait]
@@ -103,16 +131,43 @@ pub trait SnapshotInfra: Send + Sync {
 
     /// Restores the most recent snapshot for the given file path
     async fn undo_snapshot(&self, file_path: &Path) -> Result<()>;
+
+    /// Lists the snapshots from the oldest to the most recent
+    async fn list_snapshots(&self) -> Result<Vec<Snapshot>>;
+
+    /// Restores the file that was changed most recently from its snapshot
+    async fn undo_last_snapshot(&self) -> Result<Snapshot>;
+
+    /// Restores a file to the content it had when the snapshot was taken
+    async fn restore_snapshot(&self, id: &SnapshotId) -> Result<Snapshot>;
+
+    /// Reads the content the file had when the snapshot was taken, `None` when
+    /// the file didn't exist
+    async fn snapshot_content(&self, snapshot: &Snapshot) -> Result<Option<Vec<u8>>>;
+
+    /// Lists the snapshots from the oldest to the most recent, with the bytes
+    /// removing each of them frees
+    async fn snapshot_usage(&self) -> Result<Vec<(Snapshot, u64)>>;
+
+    /// Removes the snapshots without restoring them
+    async fn remove_snapshots(&self, ids: &[SnapshotId]) -> Result<()>;
 }
 
 /// Service for executing shell commands
 #[async_trait::async_trait]
 pub trait CommandInfra: Send + Sync {
-    /// Executes a shell command and returns the output
+    /// Executes a shell command with the shell, or the one of the environment
+    /// if not specified, and the variables of the project, and returns the
+    /// output. The command reads `stdin` as its input, or an empty input if
+    /// not specified. A command the policy refuses fails with a
+    /// `CommandViolation` without being run.
     async fn execute_command(
         &self,
         command: String,
         working_dir: PathBuf,
+        shell: Option<ShellKind>,
+        stdin: Option<String>,
+        settings: &CommandSettings,
     ) -> anyhow::Result<CommandOutput>;
 
     /// execute the shell command on present stdio.
@@ -120,7 +175,27 @@ pub trait CommandInfra: Send + Sync {
         &self,
         command: &str,
         working_dir: PathBuf,
+        env: &ProjectEnv,
     ) -> anyhow::Result<std::process::ExitStatus>;
+
+    /// Starts a shell command in the background and returns without waiting
+    /// for it, its output being kept to be read later
+    async fn start_process(
+        &self,
+        command: String,
+        working_dir: PathBuf,
+        settings: &CommandSettings,
+    ) -> anyhow::Result<BackgroundProcess>;
+
+    /// Stops a background process along with the processes it started
+    async fn stop_process(&self, id: u32) -> anyhow::Result<BackgroundProcess>;
+
+    /// Returns the last `lines` lines of each output stream of a background
+    /// process
+    async fn process_logs(&self, id: u32, lines: usize) -> anyhow::Result<ProcessLogs>;
+
+    /// Lists the background processes, including the ones that exited
+    async fn list_processes(&self) -> anyhow::Result<Vec<BackgroundProcess>>;
 }
 
 #[async_trait::async_trait]
@@ -166,6 +241,24 @@ pub trait McpClientInfra: Clone + Send + Sync + 'static {
         tool_name: &ToolName,
         input: serde_json::Value,
     ) -> anyhow::Result<ToolOutput>;
+
+    /// Lists the prompts advertised by the server
+    async fn prompts(&self) -> anyhow::Result<Vec<McpPrompt>>;
+
+    /// Fills in the prompt with the arguments and returns its text
+    async fn prompt(
+        &self,
+        name: &str,
+        arguments: BTreeMap<String, String>,
+    ) -> anyhow::Result<String>;
+
+    /// Checks that the server still responds, starting it again on the next
+    /// request when it doesn't
+    async fn ping(&self) -> anyhow::Result<()>;
+
+    /// Sets the directories the server can work in, telling it about the
+    /// change if it's running
+    async fn set_roots(&self, roots: Vec<PathBuf>) -> anyhow::Result<()>;
 }
 
 #[async_trait::async_trait]
@@ -173,6 +266,31 @@ pub trait McpServerInfra: Send + Sync + 'static {
     type Client: McpClientInfra;
     async fn connect(&self, config: McpServerConfig) -> anyhow::Result<Self::Client>;
 }
+
+/// Receives the redirect of the browser that completes an OAuth authorization
+#[async_trait::async_trait]
+pub trait OAuthRedirectInfra: Send + Sync + 'static {
+    /// Listens on a local port for a single redirect, returning the URI to
+    /// redirect to and the receiver of the query parameters of the redirect
+    async fn listen_redirect(
+        &self,
+    ) -> anyhow::Result<(Url, oneshot::Receiver<BTreeMap<String, String>>)>;
+}
+/// Loads and runs sandboxed WASM plugins
+#[async_trait::async_trait]
+pub trait PluginInfra: Send + Sync + 'static {
+    /// Lists the tools exported by the plugin
+    async fn plugin_tools(&self, plugin: &Plugin) -> anyhow::Result<Vec<ToolDefinition>>;
+
+    /// Calls a tool exported by the plugin with JSON encoded arguments
+    async fn call_plugin(
+        &self,
+        plugin: &Plugin,
+        tool_name: &ToolName,
+        input: serde_json::Value,
+    ) -> anyhow::Result<ToolOutput>;
+}
+
 /// Service for walking filesystem directories
 #[async_trait::async_trait]
 pub trait WalkerInfra: Send + Sync {
@@ -185,7 +303,12 @@ pub trait WalkerInfra: Send + Sync {
 #[async_trait::async_trait]
 pub trait HttpInfra: Send + Sync + 'static {
     async fn get(&self, url: &Url, headers: Option<HeaderMap>) -> anyhow::Result<Response>;
-    async fn post(&self, url: &Url, body: bytes::Bytes) -> anyhow::Result<Response>;
+    async fn post(
+        &self,
+        url: &Url,
+        headers: Option<HeaderMap>,
+        body: bytes::Bytes,
+    ) -> anyhow::Result<Response>;
     async fn delete(&self, url: &Url) -> anyhow::Result<Response>;
 
     /// Posts JSON data and returns a server-sent events stream
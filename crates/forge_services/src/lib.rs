@@ -10,14 +10,26 @@ mod env;
 mod forge_services;
 mod http;
 mod infra;
+mod lifecycle;
+mod mailbox;
 mod mcp;
+mod memory;
+mod openapi;
+mod plugin;
 mod policy;
 mod provider;
 mod range;
+mod request_recorder;
+mod sessions;
+mod steering;
+mod storage;
+mod tasks;
 mod template;
+mod tool_audit;
 mod tool_services;
 mod utils;
 mod workflow;
+mod workspace_index;
 
 pub use agent_loader::*;
 pub use clipper::*;
@@ -0,0 +1,66 @@
+use forge_app::LifecycleService;
+use forge_app::domain::LifecycleEvent;
+use tokio::sync::broadcast;
+
+/// Number of events kept for the subscribers that are behind, beyond which
+/// they miss the oldest ones
+const EVENTS_CAPACITY: usize = 256;
+
+/// Broadcasts the events of the turns to all the subscribers
+pub struct ForgeLifecycle {
+    events: broadcast::Sender<LifecycleEvent>,
+}
+
+impl Default for ForgeLifecycle {
+    fn default() -> Self {
+        Self { events: broadcast::channel(EVENTS_CAPACITY).0 }
+    }
+}
+
+impl ForgeLifecycle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl LifecycleService for ForgeLifecycle {
+    fn publish(&self, event: LifecycleEvent) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.events.send(event);
+    }
+
+    fn lifecycle_events(&self) -> broadcast::Receiver<LifecycleEvent> {
+        self.events.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_app::domain::{ConversationId, LifecycleEventKind};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_lifecycle_events() {
+        let fixture = ForgeLifecycle::new();
+        let conversation_id = ConversationId::generate();
+        fixture.publish(LifecycleEvent::new(
+            conversation_id,
+            LifecycleEventKind::TurnEnd,
+        ));
+        let mut events = fixture.lifecycle_events();
+
+        fixture.publish(LifecycleEvent::new(
+            conversation_id,
+            LifecycleEventKind::TurnStart { event: "forge/user_task_init".to_string() },
+        ));
+        let actual = events.recv().await.unwrap();
+
+        let expected = LifecycleEvent::new(
+            conversation_id,
+            LifecycleEventKind::TurnStart { event: "forge/user_task_init".to_string() },
+        );
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use forge_app::MailboxService;
+use forge_app::domain::{AgentId, AgentMessage, ConversationId, Mailbox};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+/// Longest an agent can wait for a message, so that one waiting for an agent
+/// that never answers doesn't hold the turn
+const MAX_WAIT: Duration = Duration::from_secs(5 * 60);
+
+/// Keeps the messages sent between agents in memory, in a mailbox per
+/// conversation, so that the agents running in this process at the same time
+/// can talk to each other without reaching the ones of other conversations.
+#[derive(Default)]
+pub struct ForgeMailbox {
+    mailboxes: Mutex<HashMap<ConversationId, Mailbox>>,
+    posted: Notify,
+}
+
+impl ForgeMailbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl MailboxService for ForgeMailbox {
+    async fn post_message(
+        &self,
+        conversation: &ConversationId,
+        message: AgentMessage,
+    ) -> anyhow::Result<()> {
+        self.mailboxes
+            .lock()
+            .unwrap()
+            .entry(*conversation)
+            .or_default()
+            .post(message)?;
+        self.posted.notify_waiters();
+        Ok(())
+    }
+
+    async fn take_messages(
+        &self,
+        conversation: &ConversationId,
+        agent: &AgentId,
+        wait: Option<Duration>,
+    ) -> anyhow::Result<Vec<AgentMessage>> {
+        let deadline = Instant::now() + wait.unwrap_or_default().min(MAX_WAIT);
+        loop {
+            // Listening before taking, so that a message posted in between
+            // isn't missed
+            let posted = self.posted.notified();
+            tokio::pin!(posted);
+            posted.as_mut().enable();
+
+            let messages = {
+                let mut mailboxes = self.mailboxes.lock().unwrap();
+                let messages = mailboxes
+                    .get_mut(conversation)
+                    .map(|mailbox| mailbox.take(agent))
+                    .unwrap_or_default();
+                // Mailboxes are dropped once emptied, so that the ones of
+                // finished conversations don't stay around
+                if mailboxes.get(conversation).is_some_and(Mailbox::is_empty) {
+                    mailboxes.remove(conversation);
+                }
+                messages
+            };
+            if !messages.is_empty() || Instant::now() >= deadline {
+                return Ok(messages);
+            }
+            let _ = tokio::time::timeout_at(deadline, posted).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn conversation() -> ConversationId {
+        ConversationId::parse("5af97419-0277-410a-8ca6-0e2a252152c5").unwrap()
+    }
+
+    fn message(to: &str) -> AgentMessage {
+        AgentMessage::new(
+            AgentId::new("reviewer"),
+            AgentId::new(to),
+            "Unused import",
+            "Remove `use std::fmt` from src/lib.rs",
+        )
+    }
+
+    #[tokio::test]
+    async fn test_take_messages() {
+        let fixture = ForgeMailbox::new();
+        fixture
+            .post_message(&conversation(), message("coder"))
+            .await
+            .unwrap();
+        fixture
+            .post_message(&conversation(), message("tester"))
+            .await
+            .unwrap();
+
+        let actual = fixture
+            .take_messages(&conversation(), &AgentId::new("coder"), None)
+            .await
+            .unwrap();
+
+        let expected = vec![message("coder")];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_take_messages_waits_for_message() {
+        let fixture = Arc::new(ForgeMailbox::new());
+        let sender = fixture.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            sender
+                .post_message(&conversation(), message("coder"))
+                .await
+                .unwrap();
+        });
+
+        let actual = fixture
+            .take_messages(
+                &conversation(),
+                &AgentId::new("coder"),
+                Some(Duration::from_secs(60)),
+            )
+            .await
+            .unwrap();
+
+        let expected = vec![message("coder")];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_take_messages_times_out() {
+        let fixture = ForgeMailbox::new();
+
+        let actual = fixture
+            .take_messages(
+                &conversation(),
+                &AgentId::new("coder"),
+                Some(Duration::from_secs(5)),
+            )
+            .await
+            .unwrap();
+
+        let expected: Vec<AgentMessage> = vec![];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_take_messages_of_conversation() {
+        let fixture = ForgeMailbox::new();
+        let other = ConversationId::generate();
+        fixture
+            .post_message(&other, message("coder"))
+            .await
+            .unwrap();
+
+        let actual = fixture
+            .take_messages(&conversation(), &AgentId::new("coder"), None)
+            .await
+            .unwrap();
+
+        let expected: Vec<AgentMessage> = vec![];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_take_messages_wait_is_capped() {
+        let fixture = ForgeMailbox::new();
+        let started = Instant::now();
+
+        fixture
+            .take_messages(
+                &conversation(),
+                &AgentId::new("coder"),
+                Some(Duration::from_secs(24 * 60 * 60)),
+            )
+            .await
+            .unwrap();
+
+        let actual = started.elapsed();
+        assert_eq!(actual, MAX_WAIT);
+    }
+
+    #[tokio::test]
+    async fn test_emptied_mailbox_is_dropped() {
+        let fixture = ForgeMailbox::new();
+        fixture
+            .post_message(&conversation(), message("coder"))
+            .await
+            .unwrap();
+
+        fixture
+            .take_messages(&conversation(), &AgentId::new("coder"), None)
+            .await
+            .unwrap();
+
+        let actual = fixture.mailboxes.lock().unwrap().len();
+        assert_eq!(actual, 0);
+    }
+}
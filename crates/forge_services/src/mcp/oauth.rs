@@ -0,0 +1,323 @@
+//! Authorization of forge by remote MCP servers. The authorization server is
+//! discovered from the metadata published by the MCP server, forge registers
+//! itself as a client of it, and the user approves forge in the browser, with
+//! PKCE protecting the code that is exchanged for a token.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, bail};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::Utc;
+use forge_app::dto::{AuthToken, McpAuthorization, McpCredential};
+use reqwest::header::{ACCEPT, CONTENT_TYPE, HeaderMap, HeaderValue};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use url::{Url, form_urlencoded};
+use uuid::Uuid;
+
+use crate::{HttpInfra, OAuthRedirectInfra};
+
+/// Time the user has to approve forge in the browser
+const AUTHORIZATION_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Metadata of an OAuth authorization server, as described in RFC 8414
+#[derive(Deserialize)]
+struct AuthorizationServer {
+    authorization_endpoint: String,
+    token_endpoint: String,
+    #[serde(default)]
+    registration_endpoint: Option<String>,
+}
+
+/// Metadata of a protected resource, as described in RFC 9728
+#[derive(Deserialize)]
+struct ProtectedResource {
+    #[serde(default)]
+    authorization_servers: Vec<String>,
+}
+
+/// Response of the dynamic client registration, as described in RFC 7591
+#[derive(Deserialize)]
+struct RegisteredClient {
+    client_id: String,
+    #[serde(default)]
+    client_secret: Option<String>,
+}
+
+pub struct ForgeMcpOAuth<I> {
+    infra: Arc<I>,
+}
+
+impl<I> ForgeMcpOAuth<I> {
+    pub fn new(infra: Arc<I>) -> Self {
+        Self { infra }
+    }
+}
+
+impl<I: HttpInfra + OAuthRedirectInfra> ForgeMcpOAuth<I> {
+    /// Registers forge with the authorization server of the MCP server at the
+    /// url and prepares the page on which the user approves it
+    pub async fn authorize(&self, server: &str, url: &Url) -> anyhow::Result<McpAuthorization> {
+        let metadata = self.discover(url).await;
+        let registration_endpoint =
+            metadata.registration_endpoint.as_deref().with_context(|| {
+                format!("The authorization server of {server} doesn't support client registration")
+            })?;
+
+        let (redirect_uri, redirect) = self.infra.listen_redirect().await?;
+        let client = self.register(registration_endpoint, &redirect_uri).await?;
+
+        let state = Uuid::new_v4().simple().to_string();
+        let code_verifier = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let mut authorization_url = Url::parse(&metadata.authorization_endpoint)?;
+        authorization_url
+            .query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &client.client_id)
+            .append_pair("redirect_uri", redirect_uri.as_str())
+            .append_pair("state", &state)
+            .append_pair("code_challenge", &code_challenge(&code_verifier))
+            .append_pair("code_challenge_method", "S256")
+            .append_pair("resource", url.as_str());
+
+        Ok(McpAuthorization {
+            server: server.to_string(),
+            url: authorization_url,
+            redirect_uri,
+            state,
+            code_verifier,
+            credential: McpCredential {
+                url: url.to_string(),
+                client_id: client.client_id,
+                client_secret: client.client_secret,
+                token_endpoint: metadata.token_endpoint,
+                access_token: String::new(),
+                refresh_token: None,
+                expires_at: None,
+            },
+            redirect,
+        })
+    }
+
+    /// Waits for the user to approve forge and exchanges the code carried by
+    /// the redirect for a token
+    pub async fn token(&self, authorization: McpAuthorization) -> anyhow::Result<McpCredential> {
+        let params = tokio::time::timeout(AUTHORIZATION_TIMEOUT, authorization.redirect)
+            .await
+            .context("Timed out waiting for the authorization")?
+            .context("The authorization was interrupted")?;
+
+        if let Some(error) = params.get("error") {
+            let description = params.get("error_description").unwrap_or(error);
+            bail!("Authorization failed: {description}")
+        }
+        if params.get("state") != Some(&authorization.state) {
+            bail!("Authorization failed: the redirect doesn't belong to this authorization")
+        }
+        let code = params
+            .get("code")
+            .context("Authorization failed: the redirect carries no code")?;
+
+        let credential = authorization.credential;
+        let token = self
+            .request_token(
+                &credential,
+                &[
+                    ("grant_type", "authorization_code"),
+                    ("code", code.as_str()),
+                    ("redirect_uri", authorization.redirect_uri.as_str()),
+                    ("code_verifier", authorization.code_verifier.as_str()),
+                    ("resource", credential.url.as_str()),
+                ],
+            )
+            .await?;
+        Ok(credential.token(token, Utc::now()))
+    }
+
+    /// Obtains a new token with the refresh token of the credential
+    pub async fn refresh(&self, credential: McpCredential) -> anyhow::Result<McpCredential> {
+        let refresh_token = credential
+            .refresh_token
+            .clone()
+            .context("The token expired and can't be refreshed")?;
+        let token = self
+            .request_token(
+                &credential,
+                &[
+                    ("grant_type", "refresh_token"),
+                    ("refresh_token", refresh_token.as_str()),
+                    ("resource", credential.url.as_str()),
+                ],
+            )
+            .await?;
+        Ok(credential.token(token, Utc::now()))
+    }
+
+    async fn request_token(
+        &self,
+        credential: &McpCredential,
+        params: &[(&str, &str)],
+    ) -> anyhow::Result<AuthToken> {
+        let mut form = form_urlencoded::Serializer::new(String::new());
+        form.extend_pairs(params)
+            .append_pair("client_id", &credential.client_id);
+        if let Some(secret) = &credential.client_secret {
+            form.append_pair("client_secret", secret);
+        }
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/x-www-form-urlencoded"),
+        );
+        headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+        let response = self
+            .infra
+            .post(
+                &Url::parse(&credential.token_endpoint)?,
+                Some(headers),
+                form.finish().into(),
+            )
+            .await
+            .context("Failed to obtain a token from the authorization server")?;
+        Ok(serde_json::from_slice(&response.bytes().await?)?)
+    }
+
+    async fn register(
+        &self,
+        endpoint: &str,
+        redirect_uri: &Url,
+    ) -> anyhow::Result<RegisteredClient> {
+        let body = serde_json::json!({
+            "client_name": "Forge",
+            "redirect_uris": [redirect_uri],
+            "grant_types": ["authorization_code", "refresh_token"],
+            "response_types": ["code"],
+            "token_endpoint_auth_method": "none",
+        });
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        let response = self
+            .infra
+            .post(
+                &Url::parse(endpoint)?,
+                Some(headers),
+                serde_json::to_vec(&body)?.into(),
+            )
+            .await
+            .context("Failed to register forge with the authorization server")?;
+        Ok(serde_json::from_slice(&response.bytes().await?)?)
+    }
+
+    /// Finds the authorization server of the MCP server, falling back to the
+    /// default endpoints of the MCP spec when no metadata is published
+    async fn discover(&self, url: &Url) -> AuthorizationServer {
+        let root = origin(url);
+        let mut issuer = None;
+        for resource in [url, &root] {
+            let metadata = self
+                .get_json::<ProtectedResource>(&well_known(resource, "oauth-protected-resource"))
+                .await;
+            issuer = metadata.ok().and_then(|metadata| {
+                metadata
+                    .authorization_servers
+                    .iter()
+                    .find_map(|server| Url::parse(server).ok())
+            });
+            if issuer.is_some() {
+                break;
+            }
+        }
+        // Servers that publish no resource metadata are their own authorization server
+        let issuer = issuer.unwrap_or(root);
+
+        match self
+            .get_json(&well_known(&issuer, "oauth-authorization-server"))
+            .await
+        {
+            Ok(metadata) => metadata,
+            Err(error) => {
+                tracing::debug!(error = ?error, %issuer, "No authorization server metadata");
+                let endpoint = |path: &str| {
+                    issuer
+                        .join(path)
+                        .map(|url| url.to_string())
+                        .unwrap_or_default()
+                };
+                AuthorizationServer {
+                    authorization_endpoint: endpoint("/authorize"),
+                    token_endpoint: endpoint("/token"),
+                    registration_endpoint: Some(endpoint("/register")),
+                }
+            }
+        }
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, url: &Url) -> anyhow::Result<T> {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+        let response = self.infra.get(url, Some(headers)).await?;
+        Ok(serde_json::from_slice(&response.bytes().await?)?)
+    }
+}
+
+/// Challenge of PKCE derived from the verifier with the S256 method
+fn code_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+fn origin(url: &Url) -> Url {
+    let mut origin = url.clone();
+    origin.set_path("/");
+    origin.set_query(None);
+    origin.set_fragment(None);
+    origin
+}
+
+/// Well-known URL of the metadata, with the path of the url appended to it as
+/// RFC 8414 requires
+fn well_known(url: &Url, name: &str) -> Url {
+    let mut well_known = origin(url);
+    well_known.set_path(&format!(
+        "/.well-known/{name}{}",
+        url.path().trim_end_matches('/')
+    ));
+    well_known
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_code_challenge() {
+        // Example of RFC 7636, appendix B
+        let fixture = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+
+        let actual = code_challenge(fixture);
+
+        let expected = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_well_known_keeps_path() {
+        let fixture = Url::parse("https://mcp.example.com/v1/mcp/?session=1").unwrap();
+
+        let actual = [
+            well_known(&fixture, "oauth-protected-resource").to_string(),
+            well_known(&origin(&fixture), "oauth-authorization-server").to_string(),
+        ];
+
+        let expected = [
+            "https://mcp.example.com/.well-known/oauth-protected-resource/v1/mcp".to_string(),
+            "https://mcp.example.com/.well-known/oauth-authorization-server".to_string(),
+        ];
+        assert_eq!(actual, expected);
+    }
+}
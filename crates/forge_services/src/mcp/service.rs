@@ -1,58 +1,99 @@
-use std::collections::HashMap;
-use std::hash::{DefaultHasher, Hash, Hasher};
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
 
-use anyhow::Context;
+use anyhow::{Context, bail};
+use chrono::Utc;
 use forge_app::domain::{
-    McpConfig, McpServerConfig, ToolCallFull, ToolDefinition, ToolName, ToolOutput,
+    McpConfig, McpPrompt, McpReload, McpServerConfig, McpServerEvent, ToolCallFull, ToolDefinition,
+    ToolName, ToolOutput, Workflow,
 };
-use forge_app::{McpConfigManager, McpService};
-use tokio::sync::{Mutex, RwLock};
+use forge_app::dto::McpAuthorization;
+use forge_app::{AppConfigService, McpConfigManager, McpService, WorkflowService};
+use merge::Merge;
+use tokio::sync::{Mutex, RwLock, broadcast};
+use tokio::task::JoinHandle;
+use url::Url;
 
+use crate::app_config::ForgeConfigService;
+use crate::mcp::oauth::ForgeMcpOAuth;
 use crate::mcp::tool::McpExecutor;
-use crate::{McpClientInfra, McpServerInfra};
+use crate::workflow::ForgeWorkflowService;
+use crate::{
+    EnvironmentInfra, FileReaderInfra, FileWriterInfra, HttpInfra, McpClientInfra, McpServerInfra,
+    OAuthRedirectInfra,
+};
+
+/// Time between two pings of a running server
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Time before the first restart of a failed server, doubled after every
+/// restart that fails
+const MIN_RESTART_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+
+/// Events that can be waiting to be received by a chat
+const EVENTS_CAPACITY: usize = 16;
 
 #[derive(Clone)]
 pub struct ForgeMcpService<M, I, C> {
-    tools: Arc<RwLock<HashMap<ToolName, ToolHolder<McpExecutor<C>>>>>,
-    previous_config_hash: Arc<Mutex<u64>>,
+    servers: Arc<Servers<C>>,
+    // Tasks pinging the servers and restarting the ones that fail, by the name
+    // of their server
+    supervisors: Arc<Mutex<BTreeMap<String, JoinHandle<()>>>>,
+    // Directory the agent works in, `None` until it leaves the one forge was
+    // started in
+    cwd: Arc<Mutex<Option<PathBuf>>>,
+    // Directories the servers were last told they can work in
+    roots: Arc<Mutex<Vec<PathBuf>>>,
+    // Configuration of the servers that were started
+    configs: Arc<Mutex<BTreeMap<String, McpServerConfig>>>,
     manager: Arc<M>,
     infra: Arc<I>,
+    workflow: Arc<ForgeWorkflowService<I>>,
+    app_config: Arc<ForgeConfigService<I>>,
+    oauth: Arc<ForgeMcpOAuth<I>>,
 }
 
 #[derive(Clone)]
 struct ToolHolder<T> {
+    server: String,
     definition: ToolDefinition,
     executable: T,
 }
 
-impl<M: McpConfigManager, I: McpServerInfra, C> ForgeMcpService<M, I, C>
-where
-    C: McpClientInfra + Clone,
-    C: From<<I as McpServerInfra>::Client>,
-{
-    pub fn new(manager: Arc<M>, infra: Arc<I>) -> Self {
+/// Tools and prompts of the servers that are up, and the reasons the others
+/// are down
+struct Servers<C> {
+    clients: RwLock<BTreeMap<String, Arc<C>>>,
+    tools: RwLock<HashMap<ToolName, ToolHolder<McpExecutor<C>>>>,
+    // Prompts by the name of their command, with the client of their server
+    prompts: RwLock<BTreeMap<String, (McpPrompt, Arc<C>)>>,
+    failures: RwLock<BTreeMap<String, String>>,
+    events: broadcast::Sender<McpServerEvent>,
+}
+
+impl<C> Default for Servers<C> {
+    fn default() -> Self {
         Self {
+            clients: Default::default(),
             tools: Default::default(),
-            previous_config_hash: Arc::new(Mutex::new(0)),
-            manager,
-            infra,
+            prompts: Default::default(),
+            failures: Default::default(),
+            events: broadcast::channel(EVENTS_CAPACITY).0,
         }
     }
+}
 
-    fn hash(config: &McpConfig) -> u64 {
-        let mut hasher = DefaultHasher::new();
-        config.hash(&mut hasher);
-        hasher.finish()
-    }
-    async fn is_config_modified(&self, config: &McpConfig) -> bool {
-        *self.previous_config_hash.lock().await != Self::hash(config)
-    }
-
-    async fn insert_clients(&self, server_name: &str, client: Arc<C>) -> anyhow::Result<()> {
+impl<C: McpClientInfra> Servers<C> {
+    /// Lists the tools and prompts of the server, replacing the ones it
+    /// provided before
+    async fn register(&self, server_name: &str, client: &Arc<C>) -> anyhow::Result<()> {
         let tools = client.list().await?;
 
         let mut tool_map = self.tools.write().await;
+        tool_map.retain(|_, tool| tool.server != server_name);
 
         for mut tool in tools.into_iter() {
             let actual_name = tool.name.clone();
@@ -68,52 +109,322 @@ where
 
             tool_map.insert(
                 generated_name,
-                ToolHolder { definition: tool, executable: server },
+                ToolHolder {
+                    server: server_name.to_string(),
+                    definition: tool,
+                    executable: server,
+                },
             );
         }
+        drop(tool_map);
+
+        // Prompts are optional, a server failing to list them still provides its tools
+        match client.prompts().await {
+            Ok(prompts) => {
+                let mut prompt_map = self.prompts.write().await;
+                prompt_map.retain(|_, (prompt, _)| prompt.server != server_name);
+                for prompt in prompts {
+                    let prompt = prompt.server(server_name);
+                    prompt_map.insert(prompt.command(), (prompt, client.clone()));
+                }
+            }
+            Err(error) => {
+                tracing::warn!(server = %server_name, error = ?error, "Failed to list MCP prompts")
+            }
+        }
 
         Ok(())
     }
 
+    /// Withdraws the tools and prompts of the server, telling the chats about
+    /// it unless the server was already down
+    async fn fail(&self, server_name: &str, error: &anyhow::Error) {
+        self.tools
+            .write()
+            .await
+            .retain(|_, tool| tool.server != server_name);
+        self.prompts
+            .write()
+            .await
+            .retain(|_, (prompt, _)| prompt.server != server_name);
+
+        let reason = format!("{error:#}");
+        let previous = self
+            .failures
+            .write()
+            .await
+            .insert(server_name.to_string(), reason.clone());
+        if previous.is_none() {
+            tracing::warn!(server = %server_name, error = ?error, "MCP server is unavailable");
+            // Fails only when no chat is listening
+            let _ = self
+                .events
+                .send(McpServerEvent::Unavailable { server: server_name.to_string(), reason });
+        }
+    }
+
+    async fn restore(&self, server_name: &str) {
+        if self.failures.write().await.remove(server_name).is_some() {
+            tracing::info!(server = %server_name, "MCP server is available again");
+            let _ = self
+                .events
+                .send(McpServerEvent::Restored { server: server_name.to_string() });
+        }
+    }
+
+    async fn is_failed(&self, server_name: &str) -> bool {
+        self.failures.read().await.contains_key(server_name)
+    }
+
+    async fn remove(&self, server_name: &str) {
+        self.clients.write().await.remove(server_name);
+        self.tools
+            .write()
+            .await
+            .retain(|_, tool| tool.server != server_name);
+        self.prompts
+            .write()
+            .await
+            .retain(|_, (prompt, _)| prompt.server != server_name);
+        self.failures.write().await.remove(server_name);
+    }
+}
+
+/// Pings the server while it is up and restarts it with an increasing delay
+/// while it is down. Stops once the service is dropped.
+async fn supervise<C: McpClientInfra>(
+    servers: Weak<Servers<C>>,
+    server_name: String,
+    client: Arc<C>,
+) {
+    let mut delay = MIN_RESTART_DELAY;
+    loop {
+        let failed = match servers.upgrade() {
+            Some(servers) => servers.is_failed(&server_name).await,
+            None => return,
+        };
+        tokio::time::sleep(if failed { delay } else { PING_INTERVAL }).await;
+        let Some(servers) = servers.upgrade() else {
+            return;
+        };
+
+        if failed {
+            // Listing the tools starts the server again
+            match servers.register(&server_name, &client).await {
+                Ok(()) => {
+                    servers.restore(&server_name).await;
+                    delay = MIN_RESTART_DELAY;
+                }
+                Err(error) => {
+                    servers.fail(&server_name, &error).await;
+                    delay = (delay * 2).min(MAX_RESTART_DELAY);
+                }
+            }
+        } else if let Err(error) = client.ping().await {
+            servers.fail(&server_name, &error).await;
+        }
+    }
+}
+
+impl<M: McpConfigManager, I, C> ForgeMcpService<M, I, C>
+where
+    I: McpServerInfra
+        + FileReaderInfra
+        + FileWriterInfra
+        + EnvironmentInfra
+        + HttpInfra
+        + OAuthRedirectInfra,
+    C: McpClientInfra + Clone,
+    C: From<<I as McpServerInfra>::Client>,
+{
+    pub fn new(manager: Arc<M>, infra: Arc<I>) -> Self {
+        Self {
+            servers: Default::default(),
+            supervisors: Default::default(),
+            cwd: Default::default(),
+            roots: Default::default(),
+            configs: Default::default(),
+            manager,
+            workflow: Arc::new(ForgeWorkflowService::new(infra.clone())),
+            app_config: Arc::new(ForgeConfigService::new(infra.clone())),
+            oauth: Arc::new(ForgeMcpOAuth::new(infra.clone())),
+            infra,
+        }
+    }
+
     async fn connect(&self, server_name: &str, config: McpServerConfig) -> anyhow::Result<()> {
         let client = self.infra.connect(config).await?;
         let client = Arc::new(C::from(client));
-        self.insert_clients(server_name, client).await?;
+        client.set_roots(self.roots.lock().await.clone()).await?;
+        self.servers
+            .clients
+            .write()
+            .await
+            .insert(server_name.to_string(), client.clone());
+        // A server that fails to start doesn't keep the others from being used,
+        // it's restarted in the background
+        if let Err(error) = self.servers.register(server_name, &client).await {
+            self.servers.fail(server_name, &error).await;
+        }
 
+        let supervisor = tokio::spawn(supervise(
+            Arc::downgrade(&self.servers),
+            server_name.to_string(),
+            client,
+        ));
+        self.supervisors
+            .lock()
+            .await
+            .insert(server_name.to_string(), supervisor);
         Ok(())
     }
 
-    async fn init_mcp(&self) -> anyhow::Result<()> {
-        let mcp = self.manager.read_mcp_config().await?;
+    /// Stops the server, withdrawing its tools and prompts
+    async fn disconnect(&self, server_name: &str) {
+        if let Some(supervisor) = self.supervisors.lock().await.remove(server_name) {
+            supervisor.abort();
+        }
+        // The server shuts down once the last of its clients is dropped
+        self.servers.remove(server_name).await;
+    }
+
+    async fn read_config(&self, workflow: &Workflow) -> anyhow::Result<McpConfig> {
+        // Servers of the .mcp.json files take precedence over the ones of forge.yaml
+        let mut mcp = McpConfig::from(workflow.mcp_servers.clone());
+        mcp.merge(self.manager.read_mcp_config().await?);
+        Ok(mcp)
+    }
+
+    /// Sends the tokens of the remote servers forge is authorized by with their
+    /// requests, refreshing the expired ones. A refreshed token changes the
+    /// config, so that the servers are reconnected with it.
+    async fn add_credentials(&self, mcp: &mut McpConfig) -> anyhow::Result<()> {
+        let mut config = self.app_config.read_app_config().await.unwrap_or_default();
+        let mut refreshed = false;
+        for (name, server) in mcp.mcp_servers.iter_mut() {
+            let McpServerConfig::Http(http) = server else {
+                continue;
+            };
+            // Headers configured by the user take precedence
+            if http
+                .headers
+                .keys()
+                .any(|header| header.eq_ignore_ascii_case("authorization"))
+            {
+                continue;
+            }
+            let Some(credential) = config
+                .mcp_credentials
+                .get_mut(name)
+                .filter(|credential| credential.url == http.url)
+            else {
+                continue;
+            };
 
-        // If config is unchanged, skip reinitialization
-        if !self.is_config_modified(&mcp).await {
-            return Ok(());
+            if credential.is_expired(Utc::now()) {
+                match self.oauth.refresh(credential.clone()).await {
+                    Ok(fresh) => {
+                        *credential = fresh;
+                        refreshed = true;
+                    }
+                    Err(error) => {
+                        tracing::warn!(server = %name, error = ?error, "Failed to refresh the MCP token");
+                        continue;
+                    }
+                }
+            }
+            http.headers.insert(
+                "Authorization".to_string(),
+                format!("Bearer {}", credential.access_token),
+            );
+        }
+
+        if refreshed {
+            self.app_config.write_app_config(&config).await?;
         }
+        Ok(())
+    }
+
+    /// Directories the servers can work in: the one the agent works in and the
+    /// `mcp_roots` of the workflow
+    async fn roots(&self, workflow: &Workflow) -> Vec<PathBuf> {
+        let cwd = self.cwd.lock().await.clone();
+        let cwd = cwd.unwrap_or_else(|| self.infra.get_environment().cwd);
+        workspace_roots(&cwd, &workflow.mcp_roots)
+    }
+
+    /// Tells the running servers about the directories they can work in when
+    /// they change
+    async fn update_roots(&self, roots: Vec<PathBuf>) {
+        let mut current = self.roots.lock().await;
+        if *current == roots {
+            return;
+        }
+        *current = roots.clone();
+        drop(current);
+
+        for (name, client) in self.servers.clients.read().await.iter() {
+            if let Err(error) = client.set_roots(roots.clone()).await {
+                tracing::warn!(server = %name, error = ?error, "Failed to update the MCP roots");
+            }
+        }
+    }
+
+    async fn init_mcp(&self) -> anyhow::Result<McpReload> {
+        let workflow = self.workflow.read_merged(None).await?;
+        let mut mcp = self.read_config(&workflow).await?;
+        self.add_credentials(&mut mcp).await?;
+        self.update_roots(self.roots(&workflow).await).await;
 
         self.update_mcp(mcp).await
     }
 
-    async fn update_mcp(&self, mcp: McpConfig) -> Result<(), anyhow::Error> {
-        // Update the hash with the new config
-        let new_hash = Self::hash(&mcp);
-        *self.previous_config_hash.lock().await = new_hash;
-        self.clear_tools().await;
+    /// Starts the servers that were added to the configuration, stops the ones
+    /// that were removed and restarts the ones whose configuration changed.
+    /// The others keep running.
+    async fn update_mcp(&self, mcp: McpConfig) -> anyhow::Result<McpReload> {
+        // Held until the servers are started, so that they're only started once
+        let mut configs = self.configs.lock().await;
+        let reload = diff_configs(&configs, &mcp);
 
-        futures::future::join_all(mcp.mcp_servers.iter().map(|(name, server)| async move {
-            self.connect(name, server.clone())
-                .await
-                .context(format!("Failed to initiate MCP server: {name}"))
-        }))
-        .await
-        .into_iter()
-        .collect::<anyhow::Result<Vec<_>>>()
-        .map(|_| ())
+        for name in reload.removed.iter().chain(&reload.restarted) {
+            self.disconnect(name).await;
+            configs.remove(name);
+        }
+
+        let started = futures::future::join_all(reload.added.iter().chain(&reload.restarted).map(
+            |name| async move {
+                let config = mcp.mcp_servers[name].clone();
+                self.connect(name, config.clone())
+                    .await
+                    .context(format!("Failed to initiate MCP server: {name}"))?;
+                Ok::<_, anyhow::Error>((name.clone(), config))
+            },
+        ))
+        .await;
+        // Servers that started are kept even when another one failed to
+        let mut error = None;
+        for result in started {
+            match result {
+                Ok((name, config)) => {
+                    configs.insert(name, config);
+                }
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+
+        match error {
+            Some(error) => Err(error),
+            None => Ok(reload),
+        }
     }
 
     async fn list(&self) -> anyhow::Result<Vec<ToolDefinition>> {
         self.init_mcp().await?;
         Ok(self
+            .servers
             .tools
             .read()
             .await
@@ -121,22 +432,109 @@ where
             .map(|tool| tool.definition.clone())
             .collect())
     }
-    async fn clear_tools(&self) {
-        self.tools.write().await.clear()
-    }
-
     async fn call(&self, call: ToolCallFull) -> anyhow::Result<ToolOutput> {
-        let tools = self.tools.read().await;
+        let tools = self.servers.tools.read().await;
 
-        let tool = tools.get(&call.name).context("Tool not found")?;
+        let Some(tool) = tools.get(&call.name) else {
+            // The tools of a server that is down are withdrawn until it's restarted
+            let failures = self.servers.failures.read().await;
+            if let Some((server, reason)) = failures.iter().find(|(server, _)| {
+                call.name
+                    .as_str()
+                    .starts_with(&format!("mcp_{server}_tool_"))
+            }) {
+                bail!("MCP server {server} is unavailable: {reason}")
+            }
+            bail!("Tool not found")
+        };
 
         tool.executable.call_tool(call.arguments).await
     }
+
+    async fn prompts(&self) -> anyhow::Result<Vec<McpPrompt>> {
+        self.init_mcp().await?;
+        Ok(self
+            .servers
+            .prompts
+            .read()
+            .await
+            .values()
+            .map(|(prompt, _)| prompt.clone())
+            .collect())
+    }
+
+    async fn prompt(
+        &self,
+        prompt: &McpPrompt,
+        arguments: BTreeMap<String, String>,
+    ) -> anyhow::Result<String> {
+        let prompts = self.servers.prompts.read().await;
+        let (_, client) = prompts.get(&prompt.command()).context("Prompt not found")?;
+        client.prompt(&prompt.name, arguments).await
+    }
+
+    async fn reload(&self) -> anyhow::Result<McpReload> {
+        self.init_mcp().await
+    }
+
+    async fn set_cwd(&self, cwd: Option<PathBuf>) -> anyhow::Result<()> {
+        {
+            let mut current = self.cwd.lock().await;
+            if *current == cwd {
+                return Ok(());
+            }
+            *current = cwd;
+        }
+        let workflow = self.workflow.read_merged(None).await?;
+        self.update_roots(self.roots(&workflow).await).await;
+        Ok(())
+    }
+
+    fn events(&self) -> broadcast::Receiver<McpServerEvent> {
+        self.servers.events.subscribe()
+    }
+
+    async fn failures(&self) -> BTreeMap<String, String> {
+        self.servers.failures.read().await.clone()
+    }
+
+    async fn authorize(&self, server: &str) -> anyhow::Result<McpAuthorization> {
+        let mcp = self
+            .read_config(&self.workflow.read_merged(None).await?)
+            .await?;
+        let Some(McpServerConfig::Http(http)) = mcp.mcp_servers.get(server) else {
+            bail!("{server} is not a remote MCP server")
+        };
+        self.oauth.authorize(server, &Url::parse(&http.url)?).await
+    }
+
+    async fn await_authorization(&self, authorization: McpAuthorization) -> anyhow::Result<()> {
+        let server = authorization.server.clone();
+        let credential = self.oauth.token(authorization).await?;
+
+        let mut config = self.app_config.read_app_config().await.unwrap_or_default();
+        config.mcp_credentials.insert(server, credential);
+        self.app_config.write_app_config(&config).await
+    }
+
+    async fn deauthorize(&self, server: &str) -> anyhow::Result<()> {
+        let mut config = self.app_config.read_app_config().await.unwrap_or_default();
+        if config.mcp_credentials.remove(server).is_none() {
+            bail!("Forge isn't authorized by {server}")
+        }
+        self.app_config.write_app_config(&config).await
+    }
 }
 
 #[async_trait::async_trait]
-impl<R: McpConfigManager, I: McpServerInfra, C> McpService for ForgeMcpService<R, I, C>
+impl<R: McpConfigManager, I, C> McpService for ForgeMcpService<R, I, C>
 where
+    I: McpServerInfra
+        + FileReaderInfra
+        + FileWriterInfra
+        + EnvironmentInfra
+        + HttpInfra
+        + OAuthRedirectInfra,
     C: McpClientInfra + Clone,
     C: From<<I as McpServerInfra>::Client>,
 {
@@ -147,4 +545,231 @@ where
     async fn call(&self, call: ToolCallFull) -> anyhow::Result<ToolOutput> {
         self.call(call).await
     }
+
+    async fn prompts(&self) -> anyhow::Result<Vec<McpPrompt>> {
+        self.prompts().await
+    }
+
+    async fn prompt(
+        &self,
+        prompt: &McpPrompt,
+        arguments: BTreeMap<String, String>,
+    ) -> anyhow::Result<String> {
+        self.prompt(prompt, arguments).await
+    }
+
+    async fn reload(&self) -> anyhow::Result<McpReload> {
+        self.reload().await
+    }
+
+    fn mcp_events(&self) -> broadcast::Receiver<McpServerEvent> {
+        self.events()
+    }
+
+    async fn mcp_failures(&self) -> BTreeMap<String, String> {
+        self.failures().await
+    }
+
+    async fn authorize(&self, server: &str) -> anyhow::Result<McpAuthorization> {
+        self.authorize(server).await
+    }
+
+    async fn await_authorization(&self, authorization: McpAuthorization) -> anyhow::Result<()> {
+        self.await_authorization(authorization).await
+    }
+
+    async fn deauthorize(&self, server: &str) -> anyhow::Result<()> {
+        self.deauthorize(server).await
+    }
+
+    async fn set_mcp_cwd(&self, cwd: Option<PathBuf>) -> anyhow::Result<()> {
+        self.set_cwd(cwd).await
+    }
+}
+
+/// Servers of the configuration that weren't started yet, the started ones it
+/// no longer has and the started ones whose configuration changed
+fn diff_configs(started: &BTreeMap<String, McpServerConfig>, mcp: &McpConfig) -> McpReload {
+    let mut reload = McpReload::default();
+    for (name, config) in started {
+        match mcp.mcp_servers.get(name) {
+            None => reload.removed.push(name.clone()),
+            Some(new_config) if new_config != config => reload.restarted.push(name.clone()),
+            Some(_) => {}
+        }
+    }
+    reload.added = mcp
+        .mcp_servers
+        .keys()
+        .filter(|name| !started.contains_key(*name))
+        .cloned()
+        .collect();
+    reload
+}
+
+/// Directories the servers can work in: the current one and the roots, relative
+/// to it, without the `.` and `..` of their paths and without duplicates
+fn workspace_roots(cwd: &Path, mcp_roots: &[String]) -> Vec<PathBuf> {
+    let mut roots = vec![normalize(cwd)];
+    for root in mcp_roots {
+        let root = normalize(&cwd.join(root));
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+    roots
+}
+
+/// Resolves the `.` and `..` of a path without touching the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::Value;
+
+    use super::*;
+
+    #[derive(Clone)]
+    struct Client;
+
+    #[async_trait::async_trait]
+    impl McpClientInfra for Client {
+        async fn list(&self) -> anyhow::Result<Vec<ToolDefinition>> {
+            Ok(vec![ToolDefinition::new("search")])
+        }
+
+        async fn call(&self, _: &ToolName, _: Value) -> anyhow::Result<ToolOutput> {
+            Ok(ToolOutput::default())
+        }
+
+        async fn prompts(&self) -> anyhow::Result<Vec<McpPrompt>> {
+            Ok(vec![])
+        }
+
+        async fn prompt(&self, _: &str, _: BTreeMap<String, String>) -> anyhow::Result<String> {
+            Ok(String::new())
+        }
+
+        async fn ping(&self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn set_roots(&self, _: Vec<PathBuf>) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_server_is_reported_once_until_restored() {
+        let fixture = Servers::default();
+        let mut events = fixture.events.subscribe();
+        fixture.register("docs", &Arc::new(Client)).await.unwrap();
+
+        fixture.fail("docs", &anyhow::anyhow!("crashed")).await;
+        fixture
+            .fail("docs", &anyhow::anyhow!("crashed again"))
+            .await;
+        let tools = fixture.tools.read().await.len();
+        fixture.register("docs", &Arc::new(Client)).await.unwrap();
+        fixture.restore("docs").await;
+
+        let actual = (
+            tools,
+            events.try_recv().unwrap(),
+            events.try_recv().unwrap(),
+            events.try_recv().is_err(),
+        );
+        let expected = (
+            0,
+            McpServerEvent::Unavailable {
+                server: "docs".to_string(),
+                reason: "crashed".to_string(),
+            },
+            McpServerEvent::Restored { server: "docs".to_string() },
+            true,
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_diff_configs() {
+        let docs = McpServerConfig::new_stdio("docs-server", vec![], None);
+        let search = McpServerConfig::new_http("https://search.example.com/mcp", BTreeMap::new());
+        let started = BTreeMap::from([
+            ("docs".to_string(), docs.clone()),
+            (
+                "git".to_string(),
+                McpServerConfig::new_stdio("git-server", vec![], None),
+            ),
+            ("search".to_string(), search),
+        ]);
+        let fixture = McpConfig {
+            mcp_servers: BTreeMap::from([
+                ("docs".to_string(), docs),
+                (
+                    "fetch".to_string(),
+                    McpServerConfig::new_stdio("fetch-server", vec![], None),
+                ),
+                (
+                    "search".to_string(),
+                    McpServerConfig::new_sse("https://search.example.com/sse", BTreeMap::new()),
+                ),
+            ]),
+        };
+
+        let actual = diff_configs(&started, &fixture);
+
+        let expected = McpReload {
+            added: vec!["fetch".to_string()],
+            removed: vec!["git".to_string()],
+            restarted: vec!["search".to_string()],
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_diff_unchanged_configs() {
+        let started = BTreeMap::from([(
+            "docs".to_string(),
+            McpServerConfig::new_stdio("docs-server", vec!["--stdio".to_string()], None),
+        )]);
+        let fixture = McpConfig { mcp_servers: started.clone() };
+
+        let actual = diff_configs(&started, &fixture);
+
+        assert!(actual.is_empty());
+    }
+
+    #[test]
+    fn test_workspace_roots_are_normalized() {
+        let fixture = [
+            "../shared".to_string(),
+            "./docs/../docs".to_string(),
+            "/opt/data/".to_string(),
+            "../app".to_string(),
+        ];
+
+        let actual = workspace_roots(Path::new("/work/./app"), &fixture);
+
+        let expected = vec![
+            PathBuf::from("/work/app"),
+            PathBuf::from("/work/shared"),
+            PathBuf::from("/work/app/docs"),
+            PathBuf::from("/opt/data"),
+        ];
+        assert_eq!(actual, expected);
+    }
 }
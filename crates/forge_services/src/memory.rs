@@ -0,0 +1,108 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Context;
+use bytes::Bytes;
+use forge_app::MemoryService;
+use forge_app::domain::MemoryStore;
+
+use crate::{EnvironmentInfra, FileInfoInfra, FileReaderInfra, FileWriterInfra};
+
+/// Keeps the remembered facts in a JSON file under the base path, so that they
+/// are shared by the sessions of every project.
+pub struct ForgeMemory<I> {
+    infra: Arc<I>,
+}
+
+impl<I> ForgeMemory<I> {
+    pub fn new(infra: Arc<I>) -> Self {
+        Self { infra }
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: EnvironmentInfra + FileInfoInfra + FileReaderInfra + FileWriterInfra> MemoryService
+    for ForgeMemory<I>
+{
+    async fn load_memories(&self) -> anyhow::Result<MemoryStore> {
+        let path = self.infra.get_environment().memory_path();
+        if !self.infra.exists(&path).await? {
+            return Ok(MemoryStore::default());
+        }
+
+        let content = self.infra.read_utf8(&path).await?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse the memories at {}", path.display()))
+    }
+
+    async fn update_memories<T: Send + 'static>(
+        &self,
+        update: Box<dyn FnOnce(&mut MemoryStore) -> T + Send>,
+    ) -> anyhow::Result<T> {
+        let path = self.infra.get_environment().memory_path();
+        let _lock = self.infra.lock(&path).await?;
+
+        let mut memories = self.load_memories().await?;
+        let output = update(&mut memories);
+        let content = serde_json::to_string_pretty(&memories)?;
+        self.infra.write(&path, Bytes::from(content), false).await?;
+        Ok(output)
+    }
+
+    async fn memory_project(&self, cwd: &Path) -> PathBuf {
+        let cwd = tokio::fs::canonicalize(cwd)
+            .await
+            .unwrap_or_else(|_| cwd.to_path_buf());
+        for dir in cwd.ancestors() {
+            if self
+                .infra
+                .exists(&dir.join(".git"))
+                .await
+                .unwrap_or_default()
+            {
+                return dir.to_path_buf();
+            }
+        }
+        cwd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::attachment::tests::MockFileService;
+
+    #[tokio::test]
+    async fn test_updated_memories_are_loaded() {
+        let fixture = ForgeMemory::new(Arc::new(MockFileService::new()));
+        let created_at = Utc::now();
+
+        fixture
+            .update_memories(Box::new(move |memories| {
+                memories.remember(Path::new("/project"), "Use tabs", created_at)
+            }))
+            .await
+            .unwrap();
+        let actual = fixture.load_memories().await.unwrap();
+
+        let mut expected = MemoryStore::default();
+        expected.remember(Path::new("/project"), "Use tabs", created_at);
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_memory_project_is_the_repository_root() {
+        let infra = MockFileService::new();
+        // A worktree or a submodule has a `.git` file instead of a directory
+        infra.add_file(PathBuf::from("/project/.git"), String::new());
+        let fixture = ForgeMemory::new(Arc::new(infra));
+
+        let actual = fixture.memory_project(Path::new("/project/src/app")).await;
+
+        let expected = PathBuf::from("/project");
+        assert_eq!(actual, expected);
+    }
+}
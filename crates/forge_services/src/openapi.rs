@@ -0,0 +1,268 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use anyhow::Context;
+use forge_app::domain::{OpenApi, OpenApiTool};
+use forge_app::{OpenApiService, WorkflowService};
+use tokio::sync::RwLock;
+
+use crate::workflow::ForgeWorkflowService;
+use crate::{EnvironmentInfra, FileReaderInfra, FileWriterInfra};
+
+/// Tools generated from the OpenAPI documents, along with the times forge.yaml
+/// and the documents were modified when they were read, so that they're only
+/// read again once one of them changes
+#[derive(Default)]
+struct GeneratedTools {
+    workflow_modified: Option<SystemTime>,
+    apis: Vec<OpenApi>,
+    specs_modified: Vec<Option<SystemTime>>,
+    tools: Vec<OpenApiTool>,
+}
+
+pub struct ForgeOpenApiService<F> {
+    infra: Arc<F>,
+    workflow: Arc<ForgeWorkflowService<F>>,
+    generated: RwLock<GeneratedTools>,
+}
+
+impl<F: EnvironmentInfra + FileReaderInfra + FileWriterInfra> ForgeOpenApiService<F> {
+    pub fn new(infra: Arc<F>, workflow: Arc<ForgeWorkflowService<F>>) -> Self {
+        Self { infra, workflow, generated: Default::default() }
+    }
+
+    fn spec_path(&self, api: &OpenApi) -> PathBuf {
+        self.infra.get_environment().cwd.join(&api.spec)
+    }
+
+    async fn load(&self, api: &OpenApi) -> anyhow::Result<Vec<OpenApiTool>> {
+        let path = self.spec_path(api);
+        let content = self.infra.read_utf8(&path).await?;
+        // JSON documents are valid YAML too
+        let spec: serde_json::Value = serde_yml::from_str(&content)
+            .with_context(|| format!("Failed to parse the OpenAPI document {}", path.display()))?;
+        OpenApiTool::from_spec(api, &spec)
+    }
+
+    /// Generates the tools of all the APIs, the first API keeping the name of
+    /// a tool that several of them define
+    async fn generate(&self, apis: &[OpenApi]) -> Vec<OpenApiTool> {
+        let mut tools: Vec<OpenApiTool> = Vec::new();
+        for api in apis {
+            // A broken document shouldn't make the tools of the other APIs unavailable
+            match self.load(api).await {
+                Ok(generated) => {
+                    for tool in generated {
+                        if !tools.iter().any(|existing| existing.name == tool.name) {
+                            tools.push(tool);
+                        }
+                    }
+                }
+                Err(error) => {
+                    tracing::warn!(api = %api.name, error = ?error, "Failed to load OpenAPI document")
+                }
+            }
+        }
+        tools
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: EnvironmentInfra + FileReaderInfra + FileWriterInfra> OpenApiService
+    for ForgeOpenApiService<F>
+{
+    async fn openapi_tools(&self) -> anyhow::Result<Vec<OpenApiTool>> {
+        let workflow_path = self
+            .workflow
+            .resolve_path(Some(PathBuf::from("forge.yaml")))
+            .await;
+        let workflow_modified = modified(&workflow_path).await;
+        let apis = {
+            let generated = self.generated.read().await;
+            is_unchanged(&[generated.workflow_modified], &[workflow_modified])
+                .then(|| generated.apis.clone())
+        };
+        let apis = match apis {
+            Some(apis) => apis,
+            None => self.workflow.read_merged(None).await?.openapi,
+        };
+
+        let mut specs_modified = Vec::new();
+        for api in &apis {
+            specs_modified.push(modified(&self.spec_path(api)).await);
+        }
+        {
+            let generated = self.generated.read().await;
+            if generated.apis == apis && is_unchanged(&generated.specs_modified, &specs_modified) {
+                return Ok(generated.tools.clone());
+            }
+        }
+
+        let tools = self.generate(&apis).await;
+        *self.generated.write().await = GeneratedTools {
+            workflow_modified,
+            apis,
+            specs_modified,
+            tools: tools.clone(),
+        };
+        Ok(tools)
+    }
+}
+
+/// Time the file was last modified, `None` when it doesn't exist or the
+/// platform doesn't tell
+async fn modified(path: &Path) -> Option<SystemTime> {
+    tokio::fs::metadata(path)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Whether the files were modified at the same times as when they were read.
+/// Files whose modification time isn't known are read again each time.
+fn is_unchanged(read: &[Option<SystemTime>], current: &[Option<SystemTime>]) -> bool {
+    read == current && current.iter().all(Option::is_some)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use forge_app::domain::Environment;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::attachment::tests::MockEnvironmentInfra;
+
+    /// Documents by their path relative to the workspace
+    #[derive(Default)]
+    struct Fixture {
+        files: Mutex<BTreeMap<String, String>>,
+    }
+
+    impl EnvironmentInfra for Fixture {
+        fn get_environment(&self) -> Environment {
+            MockEnvironmentInfra {}.get_environment()
+        }
+
+        fn get_env_var(&self, _key: &str) -> Option<String> {
+            None
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl FileReaderInfra for Fixture {
+        async fn read_utf8(&self, path: &Path) -> anyhow::Result<String> {
+            let path = path.strip_prefix("/test")?.to_string_lossy().to_string();
+            self.files
+                .lock()
+                .unwrap()
+                .get(&path)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("No file {path}"))
+        }
+
+        async fn read(&self, _path: &Path) -> anyhow::Result<Vec<u8>> {
+            unimplemented!()
+        }
+
+        async fn range_read_utf8(
+            &self,
+            _path: &Path,
+            _start_line: u64,
+            _end_line: u64,
+        ) -> anyhow::Result<(String, forge_fs::FileInfo)> {
+            unimplemented!()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl FileWriterInfra for Fixture {
+        async fn write(
+            &self,
+            _path: &Path,
+            _contents: Bytes,
+            _capture_snapshot: bool,
+        ) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        async fn append(&self, _path: &Path, _contents: Bytes) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        async fn replace(&self, _path: &Path, _contents: Bytes) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        async fn write_temp(
+            &self,
+            _prefix: &str,
+            _ext: &str,
+            _content: &str,
+        ) -> anyhow::Result<PathBuf> {
+            unimplemented!()
+        }
+
+        async fn lock(&self, _path: &Path) -> anyhow::Result<crate::FileLock> {
+            unimplemented!()
+        }
+    }
+
+    fn service(files: &[(&str, &str)]) -> ForgeOpenApiService<Fixture> {
+        let infra = Arc::new(Fixture::default());
+        infra.files.lock().unwrap().extend(
+            files
+                .iter()
+                .map(|(path, content)| (path.to_string(), content.to_string())),
+        );
+        ForgeOpenApiService::new(infra.clone(), Arc::new(ForgeWorkflowService::new(infra)))
+    }
+
+    const PETS: &str = r#"
+servers: [{ url: "https://pets.example.com" }]
+paths:
+  /pets:
+    get: { operationId: listPets }
+"#;
+
+    #[tokio::test]
+    async fn test_generate_skips_broken_documents() {
+        let fixture = service(&[("pets.yaml", PETS), ("broken.yaml", "paths: [")]);
+        let apis = [
+            OpenApi::new("broken", "broken.yaml"),
+            OpenApi::new("pets", "pets.yaml"),
+            OpenApi::new("missing", "missing.yaml"),
+        ];
+
+        let actual = fixture
+            .generate(&apis)
+            .await
+            .into_iter()
+            .map(|tool| tool.name.as_str().to_string())
+            .collect::<Vec<_>>();
+
+        let expected = vec!["pets_listPets".to_string()];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_is_unchanged() {
+        let read = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(60));
+        let later = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(61));
+
+        let actual = [
+            is_unchanged(&[read, read], &[read, read]),
+            is_unchanged(&[read, read], &[read, later]),
+            is_unchanged(&[read], &[read, read]),
+            is_unchanged(&[None], &[None]),
+        ];
+
+        let expected = [true, false, false, false];
+        assert_eq!(actual, expected);
+    }
+}
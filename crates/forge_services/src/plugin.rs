@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use forge_app::domain::{Plugin, ToolCallFull, ToolDefinition, ToolOutput};
+use forge_app::{PluginService, WorkflowService};
+use tokio::sync::RwLock;
+
+use crate::workflow::ForgeWorkflowService;
+use crate::{FileReaderInfra, FileWriterInfra, PluginInfra};
+
+/// Tools along with the plugin that provides them
+type PluginTools = Vec<(ToolDefinition, Plugin)>;
+
+pub struct ForgePluginService<F> {
+    infra: Arc<F>,
+    workflow: ForgeWorkflowService<F>,
+    // Tools are cached along with the plugin configuration they were loaded from, so that
+    // changes to forge.yaml are picked up without a restart.
+    tools: Arc<RwLock<Option<(Vec<Plugin>, PluginTools)>>>,
+}
+
+impl<F: PluginInfra + FileReaderInfra + FileWriterInfra> ForgePluginService<F> {
+    pub fn new(infra: Arc<F>) -> Self {
+        Self {
+            workflow: ForgeWorkflowService::new(infra.clone()),
+            infra,
+            tools: Default::default(),
+        }
+    }
+
+    async fn tools(&self) -> anyhow::Result<PluginTools> {
+        let plugins = self.workflow.read_merged(None).await?.plugins;
+        if let Some((cached, tools)) = self.tools.read().await.as_ref()
+            && *cached == plugins
+        {
+            return Ok(tools.clone());
+        }
+
+        let mut tools = PluginTools::new();
+        for plugin in &plugins {
+            // A broken plugin shouldn't make the tools of the other plugins unavailable
+            match self.infra.plugin_tools(plugin).await {
+                Ok(definitions) => {
+                    for definition in definitions {
+                        // The first plugin providing a tool wins
+                        if !tools.iter().any(|(tool, _)| tool.name == definition.name) {
+                            tools.push((definition, plugin.clone()));
+                        }
+                    }
+                }
+                Err(error) => {
+                    tracing::warn!(plugin = %plugin.name, error = ?error, "Failed to load plugin")
+                }
+            }
+        }
+
+        self.tools.write().await.replace((plugins, tools.clone()));
+        Ok(tools)
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: PluginInfra + FileReaderInfra + FileWriterInfra> PluginService for ForgePluginService<F> {
+    async fn plugin_tools(&self) -> anyhow::Result<Vec<ToolDefinition>> {
+        Ok(self
+            .tools()
+            .await?
+            .into_iter()
+            .map(|(definition, _)| definition)
+            .collect())
+    }
+
+    async fn call_plugin(&self, call: ToolCallFull) -> anyhow::Result<ToolOutput> {
+        let (_, plugin) = self
+            .tools()
+            .await?
+            .into_iter()
+            .find(|(definition, _)| definition.name == call.name)
+            .with_context(|| format!("No plugin provides the tool '{}'", call.name))?;
+
+        self.infra
+            .call_plugin(&plugin, &call.name, call.arguments)
+            .await
+            .with_context(|| format!("Plugin '{}' failed", plugin.name))
+    }
+}
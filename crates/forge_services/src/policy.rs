@@ -4,31 +4,16 @@ use std::sync::Arc;
 use anyhow::Context;
 use bytes::Bytes;
 use forge_app::domain::{
-    ExecuteRule, Fetch, Operation, Permission, Policy, PolicyConfig, PolicyEngine, ReadRule, Rule,
-    WriteRule,
+    ExecuteRule, Fetch, Operation, Permission, Policy, PolicyConfig, PolicyEngine, ReadRule,
+    RequestRule, Rule, ToolRule, WriteRule,
 };
-use forge_app::{PolicyDecision, PolicyService};
-use strum_macros::{Display, EnumIter};
+use forge_app::{PolicyDecision, PolicyService, WorkflowService};
 
+use crate::workflow::ForgeWorkflowService;
 use crate::{
     DirectoryReaderInfra, EnvironmentInfra, FileInfoInfra, FileReaderInfra, FileWriterInfra,
-    UserInfra,
 };
 
-/// User response for permission confirmation requests
-#[derive(Debug, Clone, PartialEq, Eq, Display, EnumIter, strum_macros::EnumString)]
-pub enum PolicyPermission {
-    /// Accept the operation
-    #[strum(to_string = "Accept")]
-    Accept,
-    /// Reject the operation
-    #[strum(to_string = "Reject")]
-    Reject,
-    /// Accept the operation and remember this choice for similar operations
-    #[strum(to_string = "Accept and Remember")]
-    AcceptAndRemember,
-}
-
 #[derive(Clone)]
 pub struct ForgePolicyService<I> {
     infra: Arc<I>,
@@ -58,10 +43,7 @@ where
     async fn add_policy_for_operation(
         &self,
         operation: &Operation,
-    ) -> anyhow::Result<Option<PathBuf>>
-    where
-        I: UserInfra,
-    {
+    ) -> anyhow::Result<Option<PathBuf>> {
         if let Some(new_policy) = create_policy_for_operation(operation, None) {
             // TODO: Can return a diff later
             self.modify_policy(new_policy).await?;
@@ -127,12 +109,9 @@ where
         Ok(())
     }
 
-    /// Get or create policies, prompting user if needed
+    /// Get or create policies
     #[async_recursion::async_recursion]
-    async fn get_or_create_policies(&self) -> anyhow::Result<(PolicyConfig, Option<PathBuf>)>
-    where
-        I: UserInfra,
-    {
+    async fn get_or_create_policies(&self) -> anyhow::Result<(PolicyConfig, Option<PathBuf>)> {
         if let Some(policies) = self.read_policies().await? {
             Ok((policies, None))
         } else {
@@ -146,60 +125,30 @@ where
 #[async_trait::async_trait]
 impl<I> PolicyService for ForgePolicyService<I>
 where
-    I: FileReaderInfra
-        + FileWriterInfra
-        + FileInfoInfra
-        + EnvironmentInfra
-        + DirectoryReaderInfra
-        + UserInfra,
+    I: FileReaderInfra + FileWriterInfra + FileInfoInfra + EnvironmentInfra + DirectoryReaderInfra,
 {
-    /// Check if an operation is allowed based on policies and handle user
-    /// confirmation
+    /// Check if an operation is allowed based on the policies in the
+    /// permissions file and in the workflow
     async fn check_operation_permission(
         &self,
         operation: &Operation,
     ) -> anyhow::Result<PolicyDecision> {
-        let (policies, path) = self.get_or_create_policies().await?;
+        let (mut policies, path) = self.get_or_create_policies().await?;
+        let workflow = ForgeWorkflowService::new(self.infra.clone())
+            .read_merged(None)
+            .await?;
+        for policy in workflow.policies {
+            policies = policies.add_policy(policy);
+        }
 
         let engine = PolicyEngine::new(&policies);
         let permission = engine.can_perform(operation);
 
-        match permission {
-            Permission::Deny => Ok(PolicyDecision { allowed: false, path }),
-            Permission::Allow => Ok(PolicyDecision { allowed: true, path }),
-            Permission::Confirm => {
-                // Request user confirmation using UserInfra
-                let confirmation_msg = match operation {
-                    Operation::Read { message, .. } => {
-                        format!("{message}. How would you like to proceed?")
-                    }
-                    Operation::Write { message, .. } => {
-                        format!("{message}. How would you like to proceed?")
-                    }
-                    Operation::Execute { message, .. } => {
-                        format!("{message}. How would you like to proceed?")
-                    }
-                    Operation::Fetch { message, .. } => {
-                        format!("{message}. How would you like to proceed?")
-                    }
-                };
-
-                match self
-                    .infra
-                    .select_one_enum::<PolicyPermission>(&confirmation_msg)
-                    .await?
-                {
-                    Some(PolicyPermission::Accept) => Ok(PolicyDecision { allowed: true, path }),
-                    Some(PolicyPermission::AcceptAndRemember) => {
-                        let update_path = self.add_policy_for_operation(operation).await?;
-                        Ok(PolicyDecision { allowed: true, path: update_path.or(path) })
-                    }
-                    Some(PolicyPermission::Reject) | None => {
-                        Ok(PolicyDecision { allowed: false, path })
-                    }
-                }
-            }
-        }
+        Ok(PolicyDecision { permission, path })
+    }
+
+    async fn remember_operation(&self, operation: &Operation) -> anyhow::Result<Option<PathBuf>> {
+        self.add_policy_for_operation(operation).await
     }
 }
 
@@ -238,6 +187,24 @@ fn create_policy_for_operation(operation: &Operation, dir: Option<String>) -> Op
                 })
             }
         }
+        Operation::Request { method, url, cwd: _, message: _ } => {
+            let request = match url::Url::parse(url) {
+                Ok(parsed_url) => format!("{}/*", parsed_url.origin().ascii_serialization()),
+                Err(_) => url.to_string(),
+            };
+            Some(Policy::Simple {
+                permission: Permission::Allow,
+                rule: Rule::Request(RequestRule {
+                    request,
+                    method: Some(method.clone()),
+                    dir: None,
+                }),
+            })
+        }
+        Operation::Tool { name, cwd: _, message: _ } => Some(Policy::Simple {
+            permission: Permission::Allow,
+            rule: Rule::Tool(ToolRule { tool: name.to_string(), dir: None }),
+        }),
         Operation::Execute { command, cwd: _, message: _ } => {
             let parts: Vec<&str> = command.split_whitespace().collect();
             match parts.as_slice() {
@@ -410,6 +377,47 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_create_policy_for_tool_operation() {
+        let operation = Operation::Tool {
+            name: forge_app::domain::ToolName::new("github_create_issue"),
+            cwd: std::path::PathBuf::from("/test/cwd"),
+            message: "Call tool: github_create_issue".to_string(),
+        };
+
+        let actual = create_policy_for_operation(&operation, None);
+
+        let expected = Some(Policy::Simple {
+            permission: Permission::Allow,
+            rule: Rule::Tool(ToolRule { tool: "github_create_issue".to_string(), dir: None }),
+        });
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_create_policy_for_request_operation() {
+        let operation = Operation::Request {
+            method: "POST".to_string(),
+            url: "http://localhost:8080/api/users?page=2".to_string(),
+            cwd: std::path::PathBuf::from("/test/cwd"),
+            message: "POST request to URL: http://localhost:8080/api/users?page=2".to_string(),
+        };
+
+        let actual = create_policy_for_operation(&operation, None);
+
+        let expected = Some(Policy::Simple {
+            permission: Permission::Allow,
+            rule: Rule::Request(RequestRule {
+                request: "http://localhost:8080/*".to_string(),
+                method: Some("POST".to_string()),
+                dir: None,
+            }),
+        });
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn test_create_policy_for_empty_execute_command() {
         let command = "".to_string();
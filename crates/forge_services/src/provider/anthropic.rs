@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use anyhow::Context as _;
@@ -19,21 +20,36 @@ pub struct Anthropic<T> {
     api_key: String,
     base_url: String,
     anthropic_version: String,
+    headers: HashMap<String, String>,
 }
 
 impl<H: HttpClientService> Anthropic<H> {
     pub fn new(http: Arc<H>, api_key: String, base_url: String, version: String) -> Self {
-        Self { http, api_key, base_url, anthropic_version: version }
+        Self {
+            http,
+            api_key,
+            base_url,
+            anthropic_version: version,
+            headers: HashMap::new(),
+        }
+    }
+
+    /// Sets additional headers sent with every request
+    pub fn headers(mut self, headers: HashMap<String, String>) -> Self {
+        self.headers = headers;
+        self
     }
 
     fn get_headers(&self) -> Vec<(String, String)> {
-        vec![
+        let mut headers = vec![
             ("x-api-key".to_string(), self.api_key.clone()),
             (
                 "anthropic-version".to_string(),
                 self.anthropic_version.clone(),
             ),
-        ]
+        ];
+        headers.extend(self.headers.iter().map(|(k, v)| (k.clone(), v.clone())));
+        headers
     }
 
     fn url(&self, path: &str) -> anyhow::Result<Url> {
@@ -152,7 +168,12 @@ mod tests {
             Ok(request.send().await?)
         }
 
-        async fn post(&self, _url: &Url, _body: Bytes) -> anyhow::Result<reqwest::Response> {
+        async fn post(
+            &self,
+            _url: &Url,
+            _headers: Option<HeaderMap>,
+            _body: Bytes,
+        ) -> anyhow::Result<reqwest::Response> {
             unimplemented!()
         }
 
@@ -231,6 +252,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_headers_includes_custom_headers() {
+        let fixture = create_anthropic("https://api.anthropic.com/v1/")
+            .unwrap()
+            .headers(HashMap::from([(
+                "X-Org-Id".to_string(),
+                "org-1".to_string(),
+            )]));
+
+        let actual = fixture.get_headers();
+
+        assert!(actual.contains(&("X-Org-Id".to_string(), "org-1".to_string())));
+        assert!(actual.contains(&("x-api-key".to_string(), "sk-test-key".to_string())));
+    }
+
     #[tokio::test]
     async fn test_request_conversion() {
         let model_id = ModelId::new("gpt-4");
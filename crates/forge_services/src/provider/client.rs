@@ -16,7 +16,7 @@ use tokio_stream::StreamExt;
 
 use crate::provider::anthropic::Anthropic;
 use crate::provider::openai::OpenAIProvider;
-use crate::provider::retry::into_retry;
+use crate::provider::retry::into_provider_error;
 
 #[derive(Setters)]
 #[setters(strip_option, into)]
@@ -45,19 +45,25 @@ impl ClientBuilder {
     /// Build the client with the configured settings.
     pub fn build<T: HttpClientService>(self, http: Arc<T>) -> Result<Client<T>> {
         let provider = self.provider;
-        let retry_config = self.retry_config;
+        let retry_config = match provider.retry() {
+            Some(retry) => Arc::new(retry.apply(&self.retry_config)),
+            None => self.retry_config,
+        };
 
         let inner = match &provider {
             Provider::OpenAI { .. } => {
                 InnerClient::OpenAICompat(OpenAIProvider::new(provider.clone(), http.clone()))
             }
 
-            Provider::Anthropic { key, .. } => InnerClient::Anthropic(Anthropic::new(
-                http.clone(),
-                key.to_string(),
-                provider.to_base_url().to_string(),
-                "2023-06-01".to_string(),
-            )),
+            Provider::Anthropic { key, headers, .. } => InnerClient::Anthropic(
+                Anthropic::new(
+                    http.clone(),
+                    key.to_string(),
+                    provider.to_base_url().to_string(),
+                    "2023-06-01".to_string(),
+                )
+                .headers(headers.clone()),
+            ),
         };
 
         Ok(Client {
@@ -92,7 +98,7 @@ enum InnerClient<T> {
 impl<T: HttpClientService> Client<T> {
     fn retry<A>(&self, result: anyhow::Result<A>) -> anyhow::Result<A> {
         let retry_config = &self.retry_config;
-        result.map_err(move |e| into_retry(e, retry_config))
+        result.map_err(move |e| into_provider_error(e, retry_config))
     }
 
     pub async fn refresh_models(&self) -> anyhow::Result<Vec<Model>> {
@@ -135,6 +141,19 @@ impl<T: HttpClientService> Client<T> {
         self.refresh_models().await
     }
 
+    pub async fn embed(
+        &self,
+        model: &ModelId,
+        input: Vec<String>,
+    ) -> anyhow::Result<Vec<Vec<f32>>> {
+        match self.inner.as_ref() {
+            InnerClient::OpenAICompat(provider) => self.retry(provider.embed(model, input).await),
+            InnerClient::Anthropic(_) => anyhow::bail!(
+                "Anthropic doesn't provide embeddings, use an OpenAI compatible provider for them"
+            ),
+        }
+    }
+
     #[allow(dead_code)]
     pub async fn model(&self, model: &ModelId) -> anyhow::Result<Option<Model>> {
         // First, check if the model is in the cache
@@ -185,7 +204,7 @@ mod tests {
 
     use bytes::Bytes;
     use forge_app::HttpClientService;
-    use forge_app::domain::Provider;
+    use forge_app::domain::{Provider, RetryOverrides};
     use reqwest::Url;
     use reqwest::header::HeaderMap;
     use reqwest_eventsource::EventSource;
@@ -205,7 +224,12 @@ mod tests {
             Err(anyhow::anyhow!("Mock HTTP client - no real requests"))
         }
 
-        async fn post(&self, _url: &Url, _body: Bytes) -> anyhow::Result<reqwest::Response> {
+        async fn post(
+            &self,
+            _url: &Url,
+            _headers: Option<HeaderMap>,
+            _body: Bytes,
+        ) -> anyhow::Result<reqwest::Response> {
             Err(anyhow::anyhow!("Mock HTTP client - no real requests"))
         }
 
@@ -228,6 +252,8 @@ mod tests {
         let provider = Provider::OpenAI {
             url: Url::parse("https://api.openai.com/v1/").unwrap(),
             key: Some("test-key".to_string()),
+            headers: Default::default(),
+            retry: None,
         };
         let client = ClientBuilder::new(provider, "dev")
             .build(Arc::new(MockHttpClient))
@@ -243,6 +269,8 @@ mod tests {
         let provider = Provider::OpenAI {
             url: Url::parse("https://api.openai.com/v1/").unwrap(),
             key: Some("test-key".to_string()),
+            headers: Default::default(),
+            retry: None,
         };
         let client = ClientBuilder::new(provider, "dev")
             .build(Arc::new(MockHttpClient))
@@ -260,6 +288,8 @@ mod tests {
         let provider = Provider::OpenAI {
             url: Url::parse("https://api.openai.com/v1/").unwrap(),
             key: Some("test-key".to_string()),
+            headers: Default::default(),
+            retry: None,
         };
 
         // Test the builder pattern API
@@ -275,11 +305,27 @@ mod tests {
         assert!(cache.is_empty());
     }
 
+    #[test]
+    fn test_builder_applies_provider_retry_overrides() {
+        let provider = Provider::openai("test-key")
+            .with_retry(Some(RetryOverrides::default().max_retry_attempts(1usize)));
+
+        let client = ClientBuilder::new(provider, "dev")
+            .retry_config(Arc::new(RetryConfig::default()))
+            .build(Arc::new(MockHttpClient))
+            .unwrap();
+
+        let expected = RetryConfig::default().max_retry_attempts(1usize);
+        assert_eq!(*client.retry_config, expected);
+    }
+
     #[tokio::test]
     async fn test_builder_with_defaults() {
         let provider = Provider::OpenAI {
             url: Url::parse("https://api.openai.com/v1/").unwrap(),
             key: Some("test-key".to_string()),
+            headers: Default::default(),
+            retry: None,
         };
 
         // Test that ClientBuilder::new works with minimal parameters
@@ -5,8 +5,10 @@ use forge_app::HttpClientService;
 use forge_app::domain::{
     ChatCompletionMessage, Context as ChatContext, ModelId, Provider, ResultStream, Transformer,
 };
-use forge_app::dto::openai::{ListModelResponse, ProviderPipeline, Request, Response};
-use reqwest::header::AUTHORIZATION;
+use forge_app::dto::openai::{
+    EmbeddingRequest, EmbeddingResponse, ListModelResponse, ProviderPipeline, Request, Response,
+};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
 use tracing::{debug, info};
 
 use crate::provider::client::{create_headers, join_url};
@@ -32,6 +34,12 @@ impl<H: HttpClientService> OpenAIProvider<H> {
         if let Some(ref api_key) = self.provider.key() {
             headers.push((AUTHORIZATION.to_string(), format!("Bearer {api_key}")));
         }
+        headers.extend(
+            self.provider
+                .headers()
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone())),
+        );
         headers
     }
 
@@ -87,6 +95,40 @@ impl<H: HttpClientService> OpenAIProvider<H> {
         }
     }
 
+    async fn inner_embed(&self, model: &ModelId, input: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        let url = join_url(self.provider.to_base_url().as_str(), "embeddings")?;
+        let mut headers = self.get_headers();
+        headers.push((CONTENT_TYPE.to_string(), "application/json".to_string()));
+        let headers = create_headers(headers);
+        debug!(url = %url, model = %model, inputs = input.len(), "Computing embeddings");
+
+        let request = EmbeddingRequest { model: model.clone(), input };
+        let body = serde_json::to_vec(&request).with_context(|| "Failed to serialize request")?;
+        let response = self
+            .http
+            .post(&url, Some(headers), body.into())
+            .await
+            .with_context(|| format_http_context(None, "POST", &url))?;
+
+        let status = response.status();
+        let ctx_message = format_http_context(Some(status), "POST", &url);
+        let response_text = response
+            .text()
+            .await
+            .with_context(|| ctx_message.clone())
+            .with_context(|| "Failed to decode response into text")?;
+        if !status.is_success() {
+            return Err(anyhow::anyhow!(response_text))
+                .with_context(|| ctx_message)
+                .with_context(|| "Failed to compute the embeddings");
+        }
+
+        let response: EmbeddingResponse = serde_json::from_str(&response_text)
+            .with_context(|| ctx_message)
+            .with_context(|| "Failed to deserialize embeddings response")?;
+        Ok(response.into_embeddings())
+    }
+
     async fn fetch_models(&self, url: &str) -> Result<String, anyhow::Error> {
         let headers = create_headers(self.get_headers());
         let url = join_url(url, "")?;
@@ -130,6 +172,10 @@ impl<T: HttpClientService> OpenAIProvider<T> {
     pub async fn models(&self) -> Result<Vec<forge_app::domain::Model>> {
         self.inner_models().await
     }
+
+    pub async fn embed(&self, model: &ModelId, input: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        self.inner_embed(model, input).await
+    }
 }
 
 #[cfg(test)]
@@ -138,6 +184,7 @@ mod tests {
     use anyhow::Context;
     use bytes::Bytes;
     use forge_app::HttpClientService;
+    use pretty_assertions::assert_eq;
     use reqwest::header::HeaderMap;
     use reqwest_eventsource::EventSource;
 
@@ -172,10 +219,15 @@ mod tests {
 
         async fn post(
             &self,
-            _url: &reqwest::Url,
-            _body: Bytes,
+            url: &reqwest::Url,
+            headers: Option<HeaderMap>,
+            body: Bytes,
         ) -> anyhow::Result<reqwest::Response> {
-            unimplemented!()
+            let mut request = self.client.post(url.clone()).body(body);
+            if let Some(headers) = headers {
+                request = request.headers(headers);
+            }
+            Ok(request.send().await?)
         }
 
         async fn delete(&self, _url: &reqwest::Url) -> anyhow::Result<reqwest::Response> {
@@ -196,6 +248,8 @@ mod tests {
         let provider = Provider::OpenAI {
             url: reqwest::Url::parse(base_url)?,
             key: Some("test-api-key".to_string()),
+            headers: Default::default(),
+            retry: None,
         };
 
         Ok(OpenAIProvider::new(
@@ -301,6 +355,35 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_embed_keeps_input_order() -> anyhow::Result<()> {
+        let mut fixture = MockServer::new().await;
+        let mock = fixture
+            .mock_embeddings(
+                serde_json::json!({
+                    "data": [
+                        { "index": 1, "embedding": [0.0, 1.0] },
+                        { "index": 0, "embedding": [1.0, 0.0] }
+                    ]
+                }),
+                200,
+            )
+            .await;
+
+        let provider = create_provider(&fixture.url())?;
+        let actual = provider
+            .embed(
+                &ModelId::new("text-embedding-3-small"),
+                vec!["first".to_string(), "second".to_string()],
+            )
+            .await?;
+
+        mock.assert_async().await;
+        let expected = vec![vec![1.0, 0.0], vec![0.0, 1.0]];
+        assert_eq!(actual, expected);
+        Ok(())
+    }
+
     #[test]
     fn test_error_deserialization() -> Result<()> {
         let content = serde_json::to_string(&serde_json::json!({
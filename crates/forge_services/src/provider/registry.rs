@@ -1,25 +1,91 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use anyhow::Context;
-use forge_app::ProviderRegistry;
-use forge_app::domain::{Provider, ProviderUrl};
+use chrono::{DateTime, Utc};
+use forge_app::domain::{Profile, Provider, ProviderDetails, ProviderUrl, ProviderWarning};
 use forge_app::dto::AppConfig;
+use forge_app::{AppConfigService, AuthService, ProviderRegistry, WorkflowService};
+use reqwest::header::{HeaderName, HeaderValue};
 use tokio::sync::RwLock;
+use url::Url;
 
-use crate::EnvironmentInfra;
+use crate::app_config::ForgeConfigService;
+use crate::auth::ForgeAuthService;
+use crate::workflow::ForgeWorkflowService;
+use crate::{EnvironmentInfra, FileReaderInfra, FileWriterInfra, HttpInfra};
 
-type ProviderSearch = (&'static str, Box<dyn FnOnce(&str) -> Provider>);
+/// Provider type, the environment variable holding its key and the provider
+/// constructor.
+type ProviderSearch = (&'static str, &'static str, fn(&str) -> Provider);
+
+const PROVIDERS: [ProviderSearch; 6] = [
+    ("forge", "FORGE_KEY", Provider::forge),
+    ("open_router", "OPENROUTER_API_KEY", Provider::open_router),
+    ("requesty", "REQUESTY_API_KEY", Provider::requesty),
+    ("xai", "XAI_API_KEY", Provider::xai),
+    ("openai", "OPENAI_API_KEY", Provider::openai),
+    ("anthropic", "ANTHROPIC_API_KEY", Provider::anthropic),
+];
+
+/// Active profile along with its provider details, used to detect changes to
+/// the provider configuration.
+type CacheKey = (Option<String>, Option<ProviderDetails>);
+
+/// Profiles of forge.yaml along with the time the file was last modified when
+/// they were read, so that it's only read again once it changes
+#[derive(Default)]
+struct ProfileCache {
+    modified: Option<SystemTime>,
+    profiles: HashMap<String, Profile>,
+}
+
+impl ProfileCache {
+    /// The profiles read from forge.yaml as it was when last modified at
+    /// `modified`, `None` when it changed since or can't be told
+    fn get(&self, modified: Option<SystemTime>) -> Option<&HashMap<String, Profile>> {
+        (modified.is_some() && self.modified == modified).then_some(&self.profiles)
+    }
+}
+
+/// Environment variable holding the keys for a provider along with the parsed
+/// key list and the provider constructor.
+struct ProviderKeys {
+    env: String,
+    keys: Vec<String>,
+    create: fn(&str) -> Provider,
+}
 
 pub struct ForgeProviderRegistry<F> {
     infra: Arc<F>,
+    workflow: Arc<ForgeWorkflowService<F>>,
+    auth: Arc<ForgeAuthService<F>>,
+    app_config: Arc<ForgeConfigService<F>>,
+    profiles: RwLock<ProfileCache>,
     // IMPORTANT: This cache is used to avoid logging out if the user has logged out from other
-    // session. This helps to keep the user logged in for current session.
-    cache: Arc<RwLock<Option<Provider>>>,
+    // session. This helps to keep the user logged in for current session. The cached provider is
+    // tagged with the profile configuration it was resolved from, so that edits to forge.yaml are
+    // picked up without a restart.
+    cache: Arc<RwLock<Option<(CacheKey, Provider)>>>,
 }
 
 impl<F: EnvironmentInfra> ForgeProviderRegistry<F> {
-    pub fn new(infra: Arc<F>) -> Self {
-        Self { infra, cache: Arc::new(Default::default()) }
+    pub fn new(
+        infra: Arc<F>,
+        workflow: Arc<ForgeWorkflowService<F>>,
+        auth: Arc<ForgeAuthService<F>>,
+        app_config: Arc<ForgeConfigService<F>>,
+    ) -> Self {
+        Self {
+            infra,
+            workflow,
+            auth,
+            app_config,
+            profiles: Default::default(),
+            cache: Arc::new(Default::default()),
+        }
     }
 
     fn provider_url(&self) -> Option<ProviderUrl> {
@@ -33,49 +99,348 @@ impl<F: EnvironmentInfra> ForgeProviderRegistry<F> {
         }
         None
     }
-    fn get_provider(&self, forge_config: AppConfig) -> Option<Provider> {
+
+    /// Resolves the provider, preferring the provider of the active profile
+    /// when one is configured.
+    fn get_provider(
+        &self,
+        forge_config: &AppConfig,
+        details: Option<&ProviderDetails>,
+    ) -> anyhow::Result<Provider> {
+        if let Some(details) = details {
+            return self.profile_provider(forge_config, details);
+        }
+
         if let Some(forge_key) = &forge_config.key_info {
             let provider = Provider::forge(forge_key.api_key.as_str());
-            return Some(override_url(provider, self.provider_url()));
+            return Ok(override_url(provider, self.provider_url()));
         }
-        resolve_env_provider(self.provider_url(), self.infra.as_ref())
+
+        let keys = self
+            .find_keys(None)?
+            .context("Failed to detect upstream provider")?;
+        Ok(override_url(keys.active(forge_config), self.provider_url()))
+    }
+
+    fn profile_provider(
+        &self,
+        config: &AppConfig,
+        details: &ProviderDetails,
+    ) -> anyhow::Result<Provider> {
+        let keys = self.find_keys(Some(details))?.with_context(|| {
+            format!("No API key found for provider '{}'", details.provider_type)
+        })?;
+        let provider = keys.active(config);
+
+        if let Some((name, reason)) = details
+            .headers
+            .iter()
+            .find_map(|(name, value)| Some((name, header_error(name, value)?)))
+        {
+            anyhow::bail!("Invalid header '{name}' for provider: {reason}");
+        }
+        let provider = provider
+            .with_headers(details.headers.clone())
+            .with_retry(details.retry.clone());
+
+        match &details.base_url {
+            Some(base_url) => {
+                Url::parse(base_url)
+                    .with_context(|| format!("Invalid base_url for provider: {base_url}"))?;
+                Ok(override_url(
+                    provider.clone(),
+                    Some(provider_url(&provider, base_url.clone())),
+                ))
+            }
+            None => Ok(provider),
+        }
+    }
+
+    /// Finds the environment variable that holds the provider keys. When
+    /// provider details are given, only the variable configured for them is
+    /// considered.
+    fn find_keys(&self, details: Option<&ProviderDetails>) -> anyhow::Result<Option<ProviderKeys>> {
+        let Some(details) = details else {
+            return Ok(PROVIDERS.into_iter().find_map(|(_, env, create)| {
+                let keys = parse_keys(&self.infra.get_env_var(env)?);
+                (!keys.is_empty()).then(|| ProviderKeys { env: env.to_string(), keys, create })
+            }));
+        };
+
+        let (_, default_env, create) = PROVIDERS
+            .into_iter()
+            .find(|(provider_type, _, _)| *provider_type == details.provider_type)
+            .with_context(|| format!("Unknown provider type: {}", details.provider_type))?;
+        let env = details
+            .api_key_env
+            .clone()
+            .unwrap_or_else(|| default_env.to_string());
+        let keys = parse_keys(&self.infra.get_env_var(&env).unwrap_or_default());
+
+        Ok((!keys.is_empty()).then_some(ProviderKeys { env, keys, create }))
+    }
+
+    /// Advances to the next key of the provider, recording it in `config`.
+    /// Returns the provider for the new key, or `None` if there is no other key
+    /// to rotate to.
+    fn next_key(
+        &self,
+        config: &mut AppConfig,
+        details: Option<&ProviderDetails>,
+    ) -> anyhow::Result<Option<Provider>> {
+        // Keys issued through login are not rotated
+        if details.is_none() && config.key_info.is_some() {
+            return Ok(None);
+        }
+
+        let Some(keys) = self.find_keys(details)? else {
+            return Ok(None);
+        };
+
+        if keys.keys.len() < 2 {
+            return Ok(None);
+        }
+
+        let current = config
+            .active_keys
+            .get(&keys.env)
+            .copied()
+            .unwrap_or_default();
+        let next = (current + 1) % keys.keys.len();
+        config.active_keys.insert(keys.env.clone(), next);
+
+        self.get_provider(config, details).map(Some)
+    }
+
+    /// Checks the provider details of a profile for problems that would
+    /// otherwise only surface once the profile is used.
+    fn validate(&self, profile: &str, details: &ProviderDetails) -> Vec<ProviderWarning> {
+        let mut warnings = Vec::new();
+
+        let default_env = PROVIDERS
+            .into_iter()
+            .find(|(provider_type, _, _)| *provider_type == details.provider_type)
+            .map(|(_, env, _)| env);
+        if default_env.is_none() {
+            warnings.push(ProviderWarning::UnknownProviderType {
+                profile: profile.to_string(),
+                provider_type: details.provider_type.clone(),
+            });
+        }
+
+        if let Some(base_url) = &details.base_url
+            && let Err(error) = Url::parse(base_url)
+        {
+            warnings.push(ProviderWarning::InvalidBaseUrl {
+                profile: profile.to_string(),
+                base_url: base_url.clone(),
+                reason: error.to_string(),
+            });
+        }
+
+        let mut headers = details.headers.iter().collect::<Vec<_>>();
+        headers.sort();
+        warnings.extend(headers.into_iter().filter_map(|(name, value)| {
+            Some(ProviderWarning::InvalidHeader {
+                profile: profile.to_string(),
+                header: name.clone(),
+                reason: header_error(name, value)?,
+            })
+        }));
+
+        if let Some(env) = details.api_key_env.as_deref().or(default_env)
+            && parse_keys(&self.infra.get_env_var(env).unwrap_or_default()).is_empty()
+        {
+            warnings.push(ProviderWarning::MissingApiKey {
+                profile: profile.to_string(),
+                env: env.to_string(),
+            });
+        }
+
+        warnings
+    }
+}
+
+impl<F: EnvironmentInfra + FileReaderInfra + FileWriterInfra + HttpInfra> ForgeProviderRegistry<F> {
+    /// Returns the provider details of the active profile, if any. forge.yaml
+    /// is only read again once it was modified.
+    async fn profile_details(&self, config: &AppConfig) -> anyhow::Result<Option<ProviderDetails>> {
+        let Some(name) = &config.active_profile else {
+            return Ok(None);
+        };
+
+        let path = self
+            .workflow
+            .resolve_path(Some(PathBuf::from("forge.yaml")))
+            .await;
+        let modified = tokio::fs::metadata(&path)
+            .await
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if let Some(profiles) = self.profiles.read().await.get(modified) {
+            return profile_provider(profiles, name);
+        }
+
+        let profiles = self.workflow.read_merged(None).await?.profiles;
+        let details = profile_provider(&profiles, name);
+        *self.profiles.write().await = ProfileCache { modified, profiles };
+        details
+    }
+
+    /// Refreshes the login token once it has expired and persists the new
+    /// token. Returns `true` if the token was refreshed.
+    async fn refresh_login(&self, config: &mut AppConfig) -> anyhow::Result<bool> {
+        let Some(refresh_token) = expired_refresh_token(config, Utc::now()) else {
+            return Ok(false);
+        };
+
+        let info = self
+            .auth
+            .refresh(&refresh_token)
+            .await
+            .context("Failed to refresh the login token")?;
+        config.key_info = Some(info);
+        self.app_config.write_app_config(config).await?;
+        Ok(true)
     }
 }
 
 #[async_trait::async_trait]
-impl<F: EnvironmentInfra> ProviderRegistry for ForgeProviderRegistry<F> {
-    async fn get_provider(&self, config: AppConfig) -> anyhow::Result<Provider> {
-        if let Some(provider) = self.cache.read().await.as_ref() {
+impl<F: EnvironmentInfra + FileReaderInfra + FileWriterInfra + HttpInfra> ProviderRegistry
+    for ForgeProviderRegistry<F>
+{
+    async fn get_provider(&self, mut config: AppConfig) -> anyhow::Result<Provider> {
+        // Changes to the profile in forge.yaml invalidate the cached provider
+        let details = self.profile_details(&config).await?;
+        let refreshed = details.is_none() && self.refresh_login(&mut config).await?;
+        let key = (config.active_profile.clone(), details);
+        if let Some((cached, provider)) = self.cache.read().await.as_ref()
+            && *cached == key
+            && !refreshed
+            && !(key.1.is_none() && is_stale_login(&config, provider))
+        {
             return Ok(provider.clone());
         }
 
-        let provider = self
-            .get_provider(config)
-            .context("Failed to detect upstream provider")?;
-        self.cache.write().await.replace(provider.clone());
+        let provider = self.get_provider(&config, key.1.as_ref())?;
+        self.cache.write().await.replace((key, provider.clone()));
         Ok(provider)
     }
+
+    async fn get_agent_provider(
+        &self,
+        config: AppConfig,
+        details: &ProviderDetails,
+    ) -> anyhow::Result<Provider> {
+        // Agent providers are cheap to resolve and aren't cached, so that
+        // switching between agents doesn't evict the active provider
+        self.profile_provider(&config, details)
+    }
+
+    async fn rotate_key(
+        &self,
+        config: &mut AppConfig,
+        details: Option<&ProviderDetails>,
+    ) -> anyhow::Result<bool> {
+        if let Some(details) = details {
+            if self.next_key(config, Some(details))?.is_none() {
+                return Ok(false);
+            }
+            // The active provider might share the rotated keys
+            self.cache.write().await.take();
+            return Ok(true);
+        }
+
+        let details = self.profile_details(config).await?;
+        let Some(provider) = self.next_key(config, details.as_ref())? else {
+            return Ok(false);
+        };
+
+        let key = (config.active_profile.clone(), details);
+        self.cache.write().await.replace((key, provider));
+        Ok(true)
+    }
+
+    async fn validate_providers(&self) -> anyhow::Result<Vec<ProviderWarning>> {
+        let workflow = self.workflow.read_merged(None).await?;
+        let mut profiles = workflow.profiles.into_iter().collect::<Vec<_>>();
+        profiles.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(profiles
+            .iter()
+            .filter_map(|(name, profile)| Some((name, profile.provider.as_ref()?)))
+            .flat_map(|(name, details)| self.validate(name, details))
+            .collect())
+    }
+}
+
+/// Provider details of the profile, failing when it isn't defined
+fn profile_provider(
+    profiles: &HashMap<String, Profile>,
+    name: &str,
+) -> anyhow::Result<Option<ProviderDetails>> {
+    let profile = profiles
+        .get(name)
+        .with_context(|| format!("Profile '{name}' is not defined in forge.yaml"))?;
+    Ok(profile.provider.clone())
+}
+
+/// Token to refresh the login with, once its key has expired at `now`
+fn expired_refresh_token(config: &AppConfig, now: DateTime<Utc>) -> Option<String> {
+    config
+        .key_info
+        .as_ref()
+        .filter(|info| info.is_expired(now))
+        .and_then(|info| info.refresh_token.clone())
+}
+
+/// Checks whether the login key differs from the key of the cached provider,
+/// eg: after the token was refreshed by another session.
+fn is_stale_login(config: &AppConfig, provider: &Provider) -> bool {
+    config
+        .key_info
+        .as_ref()
+        .is_some_and(|info| provider.key() != Some(info.api_key.as_str()))
+}
+
+impl ProviderKeys {
+    /// Creates the provider using the key that is currently active
+    fn active(&self, config: &AppConfig) -> Provider {
+        let index = config
+            .active_keys
+            .get(&self.env)
+            .copied()
+            .unwrap_or_default();
+        (self.create)(&self.keys[index % self.keys.len()])
+    }
 }
 
-fn resolve_env_provider<F: EnvironmentInfra>(
-    url: Option<ProviderUrl>,
-    env: &F,
-) -> Option<Provider> {
-    let keys: [ProviderSearch; 6] = [
-        ("FORGE_KEY", Box::new(Provider::forge)),
-        ("OPENROUTER_API_KEY", Box::new(Provider::open_router)),
-        ("REQUESTY_API_KEY", Box::new(Provider::requesty)),
-        ("XAI_API_KEY", Box::new(Provider::xai)),
-        ("OPENAI_API_KEY", Box::new(Provider::openai)),
-        ("ANTHROPIC_API_KEY", Box::new(Provider::anthropic)),
-    ];
+/// Parses a comma separated list of API keys, ignoring blank entries.
+fn parse_keys(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(String::from)
+        .collect()
+}
 
-    keys.into_iter().find_map(|(key, fun)| {
-        env.get_env_var(key).map(|key| {
-            let provider = fun(&key);
-            override_url(provider, url.clone())
-        })
-    })
+/// Returns the reason a custom header can't be sent, if any
+fn header_error(name: &str, value: &str) -> Option<String> {
+    if let Err(error) = HeaderName::from_bytes(name.as_bytes()) {
+        return Some(error.to_string());
+    }
+    HeaderValue::from_str(value)
+        .err()
+        .map(|error| error.to_string())
+}
+
+/// Wraps a base URL into the URL kind matching the provider
+fn provider_url(provider: &Provider, url: String) -> ProviderUrl {
+    match provider {
+        Provider::OpenAI { .. } => ProviderUrl::OpenAI(url),
+        Provider::Anthropic { .. } => ProviderUrl::Anthropic(url),
+    }
 }
 
 fn override_url(mut provider: Provider, url: Option<ProviderUrl>) -> Provider {
@@ -84,3 +449,436 @@ fn override_url(mut provider: Provider, url: Option<ProviderUrl>) -> Provider {
     }
     provider
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::Duration;
+    use forge_app::domain::{Environment, RetryOverrides};
+    use forge_app::dto::{AuthToken, LoginInfo};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    struct MockEnvironmentInfra(HashMap<String, String>);
+
+    impl MockEnvironmentInfra {
+        fn new(vars: &[(&str, &str)]) -> Self {
+            Self(
+                vars.iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            )
+        }
+    }
+
+    impl EnvironmentInfra for MockEnvironmentInfra {
+        fn get_environment(&self) -> Environment {
+            unimplemented!()
+        }
+
+        fn get_env_var(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+    }
+
+    // Resolving the providers reads only the environment, as long as no
+    // profile is active and the login doesn't expire
+
+    #[async_trait::async_trait]
+    impl FileReaderInfra for MockEnvironmentInfra {
+        async fn read_utf8(&self, _path: &Path) -> anyhow::Result<String> {
+            unimplemented!()
+        }
+
+        async fn read(&self, _path: &Path) -> anyhow::Result<Vec<u8>> {
+            unimplemented!()
+        }
+
+        async fn range_read_utf8(
+            &self,
+            _path: &Path,
+            _start_line: u64,
+            _end_line: u64,
+        ) -> anyhow::Result<(String, forge_fs::FileInfo)> {
+            unimplemented!()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl FileWriterInfra for MockEnvironmentInfra {
+        async fn write(
+            &self,
+            _path: &Path,
+            _contents: bytes::Bytes,
+            _capture_snapshot: bool,
+        ) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        async fn append(&self, _path: &Path, _contents: bytes::Bytes) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        async fn replace(&self, _path: &Path, _contents: bytes::Bytes) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        async fn write_temp(
+            &self,
+            _prefix: &str,
+            _ext: &str,
+            _content: &str,
+        ) -> anyhow::Result<PathBuf> {
+            unimplemented!()
+        }
+
+        async fn lock(&self, _path: &Path) -> anyhow::Result<crate::FileLock> {
+            unimplemented!()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl HttpInfra for MockEnvironmentInfra {
+        async fn get(
+            &self,
+            _url: &Url,
+            _headers: Option<reqwest::header::HeaderMap>,
+        ) -> anyhow::Result<reqwest::Response> {
+            unimplemented!()
+        }
+
+        async fn post(
+            &self,
+            _url: &Url,
+            _headers: Option<reqwest::header::HeaderMap>,
+            _body: bytes::Bytes,
+        ) -> anyhow::Result<reqwest::Response> {
+            unimplemented!()
+        }
+
+        async fn delete(&self, _url: &Url) -> anyhow::Result<reqwest::Response> {
+            unimplemented!()
+        }
+
+        async fn eventsource(
+            &self,
+            _url: &Url,
+            _headers: Option<reqwest::header::HeaderMap>,
+            _body: bytes::Bytes,
+        ) -> anyhow::Result<reqwest_eventsource::EventSource> {
+            unimplemented!()
+        }
+    }
+
+    fn registry(vars: &[(&str, &str)]) -> ForgeProviderRegistry<MockEnvironmentInfra> {
+        let infra = Arc::new(MockEnvironmentInfra::new(vars));
+        ForgeProviderRegistry::new(
+            infra.clone(),
+            Arc::new(ForgeWorkflowService::new(infra.clone())),
+            Arc::new(ForgeAuthService::new(infra.clone())),
+            Arc::new(ForgeConfigService::new(infra)),
+        )
+    }
+
+    fn login(api_key: &str, expires_in: Option<u64>, now: DateTime<Utc>) -> AppConfig {
+        let token = AuthToken {
+            access_token: api_key.to_string(),
+            refresh_token: Some("refresh".to_string()),
+            expires_in,
+        };
+        AppConfig {
+            key_info: Some(LoginInfo::from_token(token, now)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_profile_cache_until_forge_yaml_changes() {
+        let modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(60);
+        let fixture = ProfileCache {
+            modified: Some(modified),
+            profiles: HashMap::from([("work".to_string(), Profile::default())]),
+        };
+
+        let actual = [
+            Some(modified),
+            Some(modified + std::time::Duration::from_secs(1)),
+            None,
+        ]
+        .map(|modified| fixture.get(modified).is_some());
+
+        let expected = [true, false, false];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_profile_provider_of_undefined_profile() {
+        let fixture = HashMap::from([("work".to_string(), Profile::default())]);
+
+        assert_eq!(profile_provider(&fixture, "work").unwrap(), None);
+        assert!(profile_provider(&fixture, "home").is_err());
+    }
+
+    #[test]
+    fn test_expired_refresh_token() {
+        let now = Utc::now();
+
+        let actual = [
+            expired_refresh_token(&login("key", Some(3600), now), now),
+            expired_refresh_token(&login("key", Some(3600), now), now + Duration::hours(2)),
+            expired_refresh_token(&login("key", None, now), now + Duration::days(365)),
+            expired_refresh_token(&AppConfig::default(), now),
+        ];
+
+        let expected = [None, Some("refresh".to_string()), None, None];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_is_stale_login() {
+        let now = Utc::now();
+        let fixture = login("new-key", None, now);
+
+        let actual = [
+            is_stale_login(&fixture, &Provider::forge("old-key")),
+            is_stale_login(&fixture, &Provider::forge("new-key")),
+            is_stale_login(&AppConfig::default(), &Provider::forge("old-key")),
+        ];
+
+        let expected = [true, false, false];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_get_agent_provider_keeps_the_active_provider() {
+        let fixture = registry(&[("OPENAI_API_KEY", "key-a"), ("ANTHROPIC_API_KEY", "key-b")]);
+        let active = ProviderRegistry::get_provider(&fixture, AppConfig::default())
+            .await
+            .unwrap();
+
+        let actual = fixture
+            .get_agent_provider(AppConfig::default(), &ProviderDetails::new("anthropic"))
+            .await
+            .unwrap();
+
+        assert_eq!(actual, Provider::anthropic("key-b"));
+        let cached = fixture
+            .cache
+            .read()
+            .await
+            .clone()
+            .map(|(_, provider)| provider);
+        assert_eq!(cached, Some(active));
+    }
+
+    #[tokio::test]
+    async fn test_rotate_key_of_the_active_provider() {
+        let fixture = registry(&[("OPENAI_API_KEY", "key-a,key-b")]);
+        let mut config = AppConfig::default();
+        ProviderRegistry::get_provider(&fixture, config.clone())
+            .await
+            .unwrap();
+
+        let actual = fixture.rotate_key(&mut config, None).await.unwrap();
+
+        assert!(actual);
+        assert_eq!(config.active_keys.get("OPENAI_API_KEY"), Some(&1));
+        let provider = ProviderRegistry::get_provider(&fixture, config)
+            .await
+            .unwrap();
+        assert_eq!(provider, Provider::openai("key-b"));
+    }
+
+    #[tokio::test]
+    async fn test_rotate_key_of_an_agent_provider() {
+        let fixture = registry(&[("OPENAI_API_KEY", "key-a"), ("WORK_KEY", "key-b,key-c")]);
+        let details = ProviderDetails::new("anthropic").api_key_env("WORK_KEY");
+        let mut config = AppConfig::default();
+        ProviderRegistry::get_provider(&fixture, config.clone())
+            .await
+            .unwrap();
+
+        let actual = fixture
+            .rotate_key(&mut config, Some(&details))
+            .await
+            .unwrap();
+
+        assert!(actual);
+        assert!(fixture.cache.read().await.is_none());
+        let provider = fixture.get_agent_provider(config, &details).await.unwrap();
+        assert_eq!(provider, Provider::anthropic("key-c"));
+    }
+
+    #[tokio::test]
+    async fn test_rotate_key_with_a_single_key() {
+        let fixture = registry(&[("OPENAI_API_KEY", "key-a")]);
+        let mut config = AppConfig::default();
+
+        let actual = fixture.rotate_key(&mut config, None).await.unwrap();
+
+        assert!(!actual);
+        assert!(config.active_keys.is_empty());
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        let fixture = " key-a, ,key-b,";
+        let actual = parse_keys(fixture);
+        let expected = vec!["key-a".to_string(), "key-b".to_string()];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_get_provider_uses_active_key() {
+        let fixture = registry(&[("OPENAI_API_KEY", "key-a,key-b")]);
+        let mut config = AppConfig::default();
+        config.active_keys.insert("OPENAI_API_KEY".to_string(), 1);
+
+        let actual = fixture.get_provider(&config, None).unwrap();
+
+        let expected = Provider::openai("key-b");
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_next_key_advances_and_wraps() {
+        let fixture = registry(&[("OPENAI_API_KEY", "key-a,key-b")]);
+        let mut config = AppConfig::default();
+
+        let actual = fixture.next_key(&mut config, None).unwrap();
+        assert_eq!(actual, Some(Provider::openai("key-b")));
+
+        let actual = fixture.next_key(&mut config, None).unwrap();
+        assert_eq!(actual, Some(Provider::openai("key-a")));
+        assert_eq!(config.active_keys.get("OPENAI_API_KEY"), Some(&0));
+    }
+
+    #[test]
+    fn test_next_key_with_single_key() {
+        let fixture = registry(&[("OPENAI_API_KEY", "key-a")]);
+        let mut config = AppConfig::default();
+
+        let actual = fixture.next_key(&mut config, None).unwrap();
+
+        assert_eq!(actual, None);
+        assert!(config.active_keys.is_empty());
+    }
+
+    #[test]
+    fn test_get_provider_from_profile() {
+        let fixture = registry(&[
+            ("OPENAI_API_KEY", "personal-key"),
+            ("WORK_ANTHROPIC_KEY", "work-key"),
+        ]);
+        let details = ProviderDetails::new("anthropic")
+            .api_key_env("WORK_ANTHROPIC_KEY")
+            .base_url("https://gateway.example.com/v1");
+
+        let actual = fixture
+            .get_provider(&AppConfig::default(), Some(&details))
+            .unwrap();
+
+        let mut expected = Provider::anthropic("work-key");
+        expected.url(ProviderUrl::Anthropic(
+            "https://gateway.example.com/v1".to_string(),
+        ));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_get_provider_from_profile_with_headers() {
+        let fixture = registry(&[("OPENROUTER_API_KEY", "key")]);
+        let headers = HashMap::from([("X-Title".to_string(), "forge".to_string())]);
+        let details = ProviderDetails::new("open_router").headers(headers.clone());
+
+        let actual = fixture
+            .get_provider(&AppConfig::default(), Some(&details))
+            .unwrap();
+
+        let expected = Provider::open_router("key").with_headers(headers);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_get_provider_from_profile_with_retry_overrides() {
+        let fixture = registry(&[("OPENAI_API_KEY", "key")]);
+        let retry = RetryOverrides::default().max_retry_attempts(0usize);
+        let details = ProviderDetails::new("openai").retry(retry.clone());
+
+        let actual = fixture
+            .get_provider(&AppConfig::default(), Some(&details))
+            .unwrap();
+
+        let expected = Provider::openai("key").with_retry(Some(retry));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_get_provider_from_profile_with_invalid_header() {
+        let fixture = registry(&[("OPENAI_API_KEY", "key")]);
+        let details = ProviderDetails::new("openai")
+            .headers(HashMap::from([("X Org".to_string(), "org-1".to_string())]));
+
+        let actual = fixture.get_provider(&AppConfig::default(), Some(&details));
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn test_validate_with_valid_details() {
+        let fixture = registry(&[("ANTHROPIC_API_KEY", "key")]);
+        let details = ProviderDetails::new("anthropic").base_url("https://api.example.com/v1");
+
+        let actual = fixture.validate("work", &details);
+
+        assert_eq!(actual, vec![]);
+    }
+
+    #[test]
+    fn test_validate_reports_all_problems() {
+        let fixture = registry(&[]);
+        let details = ProviderDetails::new("unknown")
+            .base_url("api.example.com")
+            .api_key_env("WORK_KEY")
+            .headers(HashMap::from([(
+                "X-Org-Id".to_string(),
+                "org\n1".to_string(),
+            )]));
+
+        let actual = fixture.validate("work", &details);
+
+        let expected = vec![
+            ProviderWarning::UnknownProviderType {
+                profile: "work".to_string(),
+                provider_type: "unknown".to_string(),
+            },
+            ProviderWarning::InvalidBaseUrl {
+                profile: "work".to_string(),
+                base_url: "api.example.com".to_string(),
+                reason: "relative URL without a base".to_string(),
+            },
+            ProviderWarning::InvalidHeader {
+                profile: "work".to_string(),
+                header: "X-Org-Id".to_string(),
+                reason: "failed to parse header value".to_string(),
+            },
+            ProviderWarning::MissingApiKey {
+                profile: "work".to_string(),
+                env: "WORK_KEY".to_string(),
+            },
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_get_provider_from_profile_with_unknown_type() {
+        let fixture = registry(&[("OPENAI_API_KEY", "key")]);
+        let details = ProviderDetails::new("unknown");
+
+        let actual = fixture.get_provider(&AppConfig::default(), Some(&details));
+
+        assert!(actual.is_err());
+    }
+}
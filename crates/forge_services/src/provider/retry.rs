@@ -3,10 +3,25 @@ use forge_app::dto::openai::{Error, ErrorResponse};
 
 const TRANSPORT_ERROR_CODES: [&str; 3] = ["ERR_STREAM_PREMATURE_CLOSE", "ECONNRESET", "ETIMEDOUT"];
 
+/// Status codes that indicate the API key itself was rejected or exhausted,
+/// making it worthwhile to try another key.
+const KEY_REJECTED_STATUS_CODES: [u16; 2] = [401, 429];
+
+/// Marks errors caused by a rejected API key, keeping the retry classification
+/// of [`into_retry`] as the inner error.
+pub fn into_provider_error(error: anyhow::Error, retry_config: &RetryConfig) -> anyhow::Error {
+    let status = get_status_code(&error);
+    let error = into_retry(error, retry_config);
+    match status {
+        Some(status) if KEY_REJECTED_STATUS_CODES.contains(&status) => {
+            DomainError::KeyRejected { status, error }.into()
+        }
+        _ => error,
+    }
+}
+
 pub fn into_retry(error: anyhow::Error, retry_config: &RetryConfig) -> anyhow::Error {
-    if let Some(code) = get_req_status_code(&error)
-        .or(get_event_req_status_code(&error))
-        .or(get_api_status_code(&error))
+    if let Some(code) = get_status_code(&error)
         && retry_config.retry_status_codes.contains(&code)
     {
         return DomainError::Retryable(error).into();
@@ -23,6 +38,12 @@ pub fn into_retry(error: anyhow::Error, retry_config: &RetryConfig) -> anyhow::E
     error
 }
 
+fn get_status_code(error: &anyhow::Error) -> Option<u16> {
+    get_req_status_code(error)
+        .or(get_event_req_status_code(error))
+        .or(get_api_status_code(error))
+}
+
 fn get_api_status_code(error: &anyhow::Error) -> Option<u16> {
     error.downcast_ref::<Error>().and_then(|error| match error {
         Error::Response(error) => error
@@ -312,6 +333,38 @@ mod tests {
         assert!(is_retryable(actual));
     }
 
+    #[test]
+    fn test_into_provider_error_with_rejected_key() {
+        // Setup
+        let retry_config = RetryConfig::default().retry_status_codes(vec![429, 500, 503]);
+        let error = anyhow::Error::from(Error::InvalidStatusCode(429));
+
+        // Execute
+        let actual = into_provider_error(error, &retry_config);
+
+        // Verify - the inner error keeps its retry classification
+        match actual.downcast::<DomainError>().unwrap() {
+            DomainError::KeyRejected { status, error } => {
+                assert_eq!(status, 429);
+                assert!(is_retryable(error));
+            }
+            error => panic!("Expected KeyRejected, got {error:?}"),
+        }
+    }
+
+    #[test]
+    fn test_into_provider_error_with_other_status() {
+        // Setup
+        let retry_config = RetryConfig::default().retry_status_codes(vec![429, 500, 503]);
+        let error = anyhow::Error::from(Error::InvalidStatusCode(500));
+
+        // Execute
+        let actual = into_provider_error(error, &retry_config);
+
+        // Verify
+        assert!(is_retryable(actual));
+    }
+
     #[test]
     fn test_is_empty_error_with_default_error_response() {
         // Setup
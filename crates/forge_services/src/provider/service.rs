@@ -1,22 +1,103 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
 use forge_app::ProviderService;
 use forge_app::domain::{
     ChatCompletionMessage, Context as ChatContext, HttpConfig, Model, ModelId, Provider,
-    ResultStream, RetryConfig,
+    ResultStream, RetryConfig, RetryOverrides,
 };
 use tokio::sync::Mutex;
+use url::Url;
 
 use crate::EnvironmentInfra;
 use crate::http::HttpClient;
 use crate::infra::HttpInfra;
 use crate::provider::client::{Client, ClientBuilder};
+
+/// Identifies the provider a client was built for. The API key is only kept as
+/// a fingerprint, so that it doesn't end up in debug output.
+#[derive(Debug, Clone, PartialEq)]
+struct ClientKey {
+    provider_id: &'static str,
+    base_url: Url,
+    key_fingerprint: Option<u64>,
+    // Headers and retry overrides are baked into the client, so changes to them
+    // require a new client as well
+    headers: Vec<(String, String)>,
+    retry: Option<RetryOverrides>,
+}
+
+impl ClientKey {
+    fn new(provider: &Provider) -> Self {
+        let provider_id = match provider {
+            Provider::OpenAI { .. } => "openai",
+            Provider::Anthropic { .. } => "anthropic",
+        };
+        let mut headers = provider
+            .headers()
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect::<Vec<_>>();
+        headers.sort();
+
+        Self {
+            provider_id,
+            base_url: provider.to_base_url(),
+            key_fingerprint: provider.key().map(fingerprint),
+            headers,
+            retry: provider.retry().cloned(),
+        }
+    }
+}
+
+/// Providers whose clients and models are kept at once, such as the active one
+/// and the ones configured on agents
+const CACHED_PROVIDERS: usize = 4;
+
+/// Values kept for the last providers used, the one used least recently being
+/// dropped first
+struct ProviderCache<T> {
+    // Most recently used last
+    entries: Vec<(ClientKey, T)>,
+}
+
+impl<T> Default for ProviderCache<T> {
+    fn default() -> Self {
+        Self { entries: Vec::new() }
+    }
+}
+
+impl<T: Clone> ProviderCache<T> {
+    fn get(&mut self, key: &ClientKey) -> Option<T> {
+        let index = self.entries.iter().position(|(cached, _)| cached == key)?;
+        let entry = self.entries.remove(index);
+        let value = entry.1.clone();
+        self.entries.push(entry);
+        Some(value)
+    }
+
+    fn insert(&mut self, key: ClientKey, value: T) {
+        self.entries.retain(|(cached, _)| *cached != key);
+        if self.entries.len() >= CACHED_PROVIDERS {
+            self.entries.remove(0);
+        }
+        self.entries.push((key, value));
+    }
+}
+
+fn fingerprint(key: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
 #[derive(Clone)]
 pub struct ForgeProviderService<I: HttpInfra> {
     retry_config: Arc<RetryConfig>,
-    cached_client: Arc<Mutex<Option<Client<HttpClient<I>>>>>,
-    cached_models: Arc<Mutex<Option<Vec<Model>>>>,
+    cached_clients: Arc<Mutex<ProviderCache<Client<HttpClient<I>>>>>,
+    cached_models: Arc<Mutex<ProviderCache<Vec<Model>>>>,
     version: String,
     timeout_config: HttpConfig,
     http_infra: Arc<I>,
@@ -29,8 +110,8 @@ impl<I: EnvironmentInfra + HttpInfra> ForgeProviderService<I> {
         let retry_config = Arc::new(env.retry_config);
         Self {
             retry_config,
-            cached_client: Arc::new(Mutex::new(None)),
-            cached_models: Arc::new(Mutex::new(None)),
+            cached_clients: Default::default(),
+            cached_models: Default::default(),
             version,
             timeout_config: env.http,
             http_infra: infra,
@@ -38,23 +119,22 @@ impl<I: EnvironmentInfra + HttpInfra> ForgeProviderService<I> {
     }
 
     async fn client(&self, provider: Provider) -> Result<Client<HttpClient<I>>> {
-        let mut client_guard = self.cached_client.lock().await;
-
-        match client_guard.as_ref() {
-            Some(client) => Ok(client.clone()),
-            None => {
-                let infra = self.http_infra.clone();
-                let client = ClientBuilder::new(provider, &self.version)
-                    .retry_config(self.retry_config.clone())
-                    .timeout_config(self.timeout_config.clone())
-                    .use_hickory(false) // use native DNS resolver(GAI)
-                    .build(Arc::new(HttpClient::new(infra)))?;
-
-                // Cache the new client
-                *client_guard = Some(client.clone());
-                Ok(client)
-            }
+        let mut clients = self.cached_clients.lock().await;
+        let key = ClientKey::new(&provider);
+
+        // A new client is built whenever the provider changes, eg: after a key rotation
+        if let Some(client) = clients.get(&key) {
+            return Ok(client);
         }
+        let infra = self.http_infra.clone();
+        let client = ClientBuilder::new(provider.clone(), &self.version)
+            .retry_config(self.retry_config.clone())
+            .timeout_config(self.timeout_config.clone())
+            .use_hickory(false) // use native DNS resolver(GAI)
+            .build(Arc::new(HttpClient::new(infra)))?;
+
+        clients.insert(key, client.clone());
+        Ok(client)
     }
 }
 
@@ -75,24 +155,110 @@ impl<I: EnvironmentInfra + HttpInfra> ProviderService for ForgeProviderService<I
     }
 
     async fn models(&self, provider: Provider) -> Result<Vec<Model>> {
-        // Check cache first
-        {
-            let models_guard = self.cached_models.lock().await;
-            if let Some(cached_models) = models_guard.as_ref() {
-                return Ok(cached_models.clone());
-            }
+        // Models are kept for each provider, so that listing the ones of the
+        // agent providers doesn't evict the ones of the active provider
+        let key = ClientKey::new(&provider);
+        if let Some(models) = self.cached_models.lock().await.get(&key) {
+            return Ok(models);
         }
 
-        // Models not in cache, fetch from client
         let client = self.client(provider).await?;
         let models = client.models().await?;
+        self.cached_models.lock().await.insert(key, models.clone());
+
+        Ok(models)
+    }
 
-        // Cache the models
-        {
-            let mut models_guard = self.cached_models.lock().await;
-            *models_guard = Some(models.clone());
+    async fn embed(
+        &self,
+        model: &ModelId,
+        input: Vec<String>,
+        provider: Provider,
+    ) -> Result<Vec<Vec<f32>>> {
+        let client = self.client(provider).await?;
+        client
+            .embed(model, input)
+            .await
+            .with_context(|| format!("Failed to compute embeddings with model: {model}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use forge_app::domain::ProviderUrl;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_client_key_same_provider() {
+        let actual = ClientKey::new(&Provider::openai("key-a"));
+        let expected = ClientKey::new(&Provider::openai("key-a"));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_client_key_switch_provider() {
+        let openai = ClientKey::new(&Provider::openai("key-a"));
+        let anthropic = ClientKey::new(&Provider::anthropic("key-a"));
+        assert_ne!(openai, anthropic);
+    }
+
+    #[test]
+    fn test_client_key_switch_base_url() {
+        let mut fixture = Provider::openai("key-a");
+        let before = ClientKey::new(&fixture);
+
+        fixture.url(ProviderUrl::OpenAI(
+            "https://gateway.example.com/v1".to_string(),
+        ));
+        let after = ClientKey::new(&fixture);
+
+        assert_ne!(before, after);
+    }
+
+    #[test]
+    fn test_client_key_switch_api_key() {
+        let before = ClientKey::new(&Provider::openai("key-a"));
+        let after = ClientKey::new(&Provider::openai("key-b"));
+        assert_ne!(before, after);
+    }
+
+    #[test]
+    fn test_client_key_switch_headers() {
+        let fixture = Provider::openai("key-a");
+        let before = ClientKey::new(&fixture);
+        let after = ClientKey::new(&fixture.with_headers(HashMap::from([(
+            "X-Org-Id".to_string(),
+            "org-1".to_string(),
+        )])));
+        assert_ne!(before, after);
+    }
+
+    #[test]
+    fn test_client_key_does_not_hold_api_key() {
+        let fixture = ClientKey::new(&Provider::openai("secret-key"));
+        let actual = format!("{fixture:?}");
+        assert!(!actual.contains("secret-key"));
+    }
+
+    #[test]
+    fn test_provider_cache_keeps_several_providers() {
+        let mut fixture = ProviderCache::default();
+        let keys = ["key-a", "key-b", "key-c", "key-d", "key-e"]
+            .map(|key| ClientKey::new(&Provider::openai(key)));
+        for (index, key) in keys.iter().take(CACHED_PROVIDERS).enumerate() {
+            fixture.insert(key.clone(), index);
         }
 
-        Ok(models)
+        // Using the first provider keeps it over the second one
+        fixture.get(&keys[0]);
+        fixture.insert(keys[4].clone(), 4);
+
+        let actual = keys.map(|key| fixture.get(&key));
+        let expected = [Some(0), None, Some(2), Some(3), Some(4)];
+        assert_eq!(actual, expected);
     }
 }
@@ -0,0 +1,141 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use chrono::Utc;
+use forge_app::RequestRecorderService;
+use forge_app::domain::{ChatCompletionMessage, Context, DumpRecord, ModelId};
+
+use crate::{EnvironmentInfra, FileRemoverInfra, FileWriterInfra};
+
+/// Dumps each request sent to the model in a JSONL file under the base path,
+/// with the responses appended one per line as they are streamed.
+pub struct ForgeRequestRecorder<I> {
+    infra: Arc<I>,
+}
+
+impl<I> ForgeRequestRecorder<I> {
+    pub fn new(infra: Arc<I>) -> Self {
+        Self { infra }
+    }
+}
+
+impl<I: FileRemoverInfra> ForgeRequestRecorder<I> {
+    /// Removes the oldest dumps of the directory so that `keep` of them
+    /// remain once the next one is written
+    async fn rotate(&self, dir: &Path, keep: usize) -> anyhow::Result<()> {
+        let mut entries = match tokio::fs::read_dir(dir).await {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(error.into()),
+        };
+
+        let mut dumps = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            dumps.push(entry.path());
+        }
+        for dump in stale_dumps(dumps, keep.saturating_sub(1)) {
+            self.infra.remove(&dump).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Dumps beyond the `keep` most recent ones. Dumps are named after the time
+/// they were started, so sorting them by name sorts them by age.
+fn stale_dumps(mut dumps: Vec<PathBuf>, keep: usize) -> Vec<PathBuf> {
+    dumps.retain(|path| path.extension().is_some_and(|ext| ext == "jsonl"));
+    dumps.sort();
+    dumps.truncate(dumps.len().saturating_sub(keep));
+    dumps
+}
+
+#[async_trait::async_trait]
+impl<I: EnvironmentInfra + FileWriterInfra + FileRemoverInfra> RequestRecorderService
+    for ForgeRequestRecorder<I>
+{
+    async fn record_request(
+        &self,
+        model: &ModelId,
+        context: &Context,
+        keep: usize,
+    ) -> anyhow::Result<PathBuf> {
+        let dir = self.infra.get_environment().request_dump_path();
+        self.rotate(&dir, keep).await?;
+
+        let created_at = Utc::now();
+        let path = dir.join(format!(
+            "{}.jsonl",
+            created_at.format("%Y-%m-%dT%H-%M-%S%.6f")
+        ));
+        let record = DumpRecord::Request { model, created_at, context };
+        self.infra
+            .write(&path, Bytes::from(record.to_line()?), false)
+            .await?;
+        Ok(path)
+    }
+
+    async fn record_response(
+        &self,
+        dump: &Path,
+        response: Result<&ChatCompletionMessage, &anyhow::Error>,
+    ) -> anyhow::Result<()> {
+        let record = match response {
+            Ok(message) => DumpRecord::Response { message },
+            Err(error) => DumpRecord::Error { error: format!("{error:#}") },
+        };
+        self.infra
+            .append(dump, Bytes::from(record.to_line()?))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::FileReaderInfra;
+    use crate::attachment::tests::MockFileService;
+
+    #[test]
+    fn test_stale_dumps() {
+        let fixture = vec![
+            PathBuf::from("/requests/2025-01-03T10-00-00.000000.jsonl"),
+            PathBuf::from("/requests/2025-01-01T10-00-00.000000.jsonl"),
+            PathBuf::from("/requests/notes.txt"),
+            PathBuf::from("/requests/2025-01-02T10-00-00.000000.jsonl"),
+        ];
+
+        let actual = stale_dumps(fixture, 2);
+
+        let expected = vec![PathBuf::from("/requests/2025-01-01T10-00-00.000000.jsonl")];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_responses_are_appended_to_the_dump() {
+        let infra = Arc::new(MockFileService::new());
+        let fixture = ForgeRequestRecorder::new(infra.clone());
+        let context = Context::default();
+        let dump = fixture
+            .record_request(&ModelId::new("gpt-4"), &context, 5)
+            .await
+            .unwrap();
+
+        let message = ChatCompletionMessage::assistant("Hello");
+        fixture.record_response(&dump, Ok(&message)).await.unwrap();
+        fixture
+            .record_response(&dump, Err(&anyhow::anyhow!("Stream closed")))
+            .await
+            .unwrap();
+
+        let content = infra.read_utf8(&dump).await.unwrap();
+        let actual = content
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["type"].clone())
+            .collect::<Vec<_>>();
+        let expected = vec!["request", "response", "error"];
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,295 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Context;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use forge_app::SessionService;
+use forge_app::domain::{Conversation, ConversationId, Session, SessionEvent, SessionIndex};
+
+use crate::{EnvironmentInfra, FileInfoInfra, FileReaderInfra, FileRemoverInfra, FileWriterInfra};
+
+/// Saves each conversation as a JSON file under the base path, next to an
+/// index holding the metadata used to list, rename and delete them. The steps
+/// of a turn are appended to a journal until the conversation is saved again,
+/// so that a crash in the middle of a turn loses at most the step in progress.
+pub struct ForgeSessions<I> {
+    infra: Arc<I>,
+}
+
+impl<I> ForgeSessions<I> {
+    pub fn new(infra: Arc<I>) -> Self {
+        Self { infra }
+    }
+}
+
+impl<I: EnvironmentInfra + FileInfoInfra + FileReaderInfra + FileWriterInfra> ForgeSessions<I> {
+    fn index_path(&self) -> PathBuf {
+        self.infra
+            .get_environment()
+            .sessions_path()
+            .join("index.json")
+    }
+
+    fn conversation_path(&self, id: &ConversationId) -> PathBuf {
+        self.infra
+            .get_environment()
+            .sessions_path()
+            .join(format!("{id}.json"))
+    }
+
+    fn journal_path(&self, id: &ConversationId) -> PathBuf {
+        self.infra
+            .get_environment()
+            .sessions_path()
+            .join(format!("{id}.jsonl"))
+    }
+
+    /// Files holding the conversation of the session
+    pub(crate) fn session_files(&self, id: &ConversationId) -> [PathBuf; 2] {
+        [self.conversation_path(id), self.journal_path(id)]
+    }
+
+    /// Updates the index while holding its lock, so that forge processes
+    /// running side by side don't drop each other's changes
+    async fn update_index<T>(
+        &self,
+        update: impl FnOnce(&mut SessionIndex) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let path = self.index_path();
+        let _lock = self.infra.lock(&path).await?;
+        let mut index = self.read_index().await?;
+        let output = update(&mut index)?;
+        let content = serde_json::to_string_pretty(&index)?;
+        self.infra.write(&path, Bytes::from(content), false).await?;
+        Ok(output)
+    }
+
+    async fn read_index(&self) -> anyhow::Result<SessionIndex> {
+        let path = self.index_path();
+        if !self.infra.exists(&path).await? {
+            return Ok(SessionIndex::default());
+        }
+
+        let content = self.infra.read_utf8(&path).await?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse the sessions at {}", path.display()))
+    }
+}
+
+impl<I: EnvironmentInfra + FileInfoInfra + FileReaderInfra + FileWriterInfra + FileRemoverInfra>
+    ForgeSessions<I>
+{
+    /// Deletes the session unless it was saved after the given time, e.g. by
+    /// a forge process that resumed it since. The index stays locked until
+    /// the files are removed, so that the session can't be saved in between.
+    pub(crate) async fn delete_unchanged(
+        &self,
+        id: &ConversationId,
+        updated_at: DateTime<Utc>,
+    ) -> anyhow::Result<bool> {
+        let path = self.index_path();
+        let _lock = self.infra.lock(&path).await?;
+        let mut index = self.read_index().await?;
+        let key = id.into_string();
+        if index
+            .find(&key)
+            .is_none_or(|session| session.updated_at != updated_at)
+        {
+            return Ok(false);
+        }
+
+        index.remove(&key)?;
+        for path in self.session_files(id) {
+            if self.infra.exists(&path).await? {
+                self.infra.remove(&path).await?;
+            }
+        }
+        let content = serde_json::to_string_pretty(&index)?;
+        self.infra.write(&path, Bytes::from(content), false).await?;
+        Ok(true)
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: EnvironmentInfra + FileInfoInfra + FileReaderInfra + FileWriterInfra + FileRemoverInfra>
+    SessionService for ForgeSessions<I>
+{
+    async fn save_session(&self, conversation: &Conversation) -> anyhow::Result<()> {
+        let content = serde_json::to_string_pretty(conversation)?;
+        self.infra
+            .write(
+                &self.conversation_path(&conversation.id),
+                Bytes::from(content),
+                false,
+            )
+            .await?;
+
+        // The saved conversation includes the journaled steps
+        let journal = self.journal_path(&conversation.id);
+        if self.infra.exists(&journal).await? {
+            self.infra.remove(&journal).await?;
+        }
+
+        let cwd = self.infra.get_environment().cwd;
+        self.update_index(|index| {
+            index.touch(
+                conversation.id,
+                &cwd,
+                conversation.main_model().ok(),
+                &conversation.tags,
+                Utc::now(),
+            );
+            Ok(())
+        })
+        .await
+    }
+
+    async fn record_session_event(
+        &self,
+        id: &ConversationId,
+        event: &SessionEvent,
+    ) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+        self.infra
+            .append(&self.journal_path(id), Bytes::from(line))
+            .await
+    }
+
+    async fn load_sessions(&self) -> anyhow::Result<SessionIndex> {
+        self.read_index().await
+    }
+
+    async fn load_session(&self, key: &str) -> anyhow::Result<Conversation> {
+        let index = self.load_sessions().await?;
+        let session = if key == "last" {
+            index.latest(&self.infra.get_environment().cwd)
+        } else {
+            index.find(key)
+        }
+        .ok_or_else(|| anyhow::anyhow!("Session not found: {key}"))?;
+
+        let path = self.conversation_path(&session.id);
+        let content = self.infra.read_utf8(&path).await?;
+        let mut conversation: Conversation = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse the session at {}", path.display()))?;
+        // A resumed session keeps being saved
+        conversation.persist = true;
+
+        let journal = self.journal_path(&session.id);
+        if self.infra.exists(&journal).await? {
+            // The last line is cut short when forge is killed while writing it
+            let content = self.infra.read_utf8(&journal).await?;
+            conversation.recover(
+                content
+                    .lines()
+                    .map_while(|line| serde_json::from_str::<SessionEvent>(line).ok()),
+            );
+        }
+
+        Ok(conversation)
+    }
+
+    async fn rename_session(&self, key: &str, name: &str) -> anyhow::Result<Session> {
+        self.update_index(|index| index.rename(key, name)).await
+    }
+
+    async fn tag_session(&self, key: &str, tags: &[String]) -> anyhow::Result<Session> {
+        self.update_index(|index| index.tag(key, tags)).await
+    }
+
+    async fn untag_session(&self, key: &str, tags: &[String]) -> anyhow::Result<Session> {
+        self.update_index(|index| index.untag(key, tags)).await
+    }
+
+    async fn delete_session(&self, key: &str) -> anyhow::Result<Session> {
+        let session = self.update_index(|index| index.remove(key)).await?;
+        for path in [
+            self.conversation_path(&session.id),
+            self.journal_path(&session.id),
+        ] {
+            if self.infra.exists(&path).await? {
+                self.infra.remove(&path).await?;
+            }
+        }
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_app::domain::{Context, Workflow};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::attachment::tests::MockFileService;
+
+    #[tokio::test]
+    async fn test_saved_sessions_are_renamed_and_deleted() {
+        let infra = Arc::new(MockFileService::new());
+        let fixture = ForgeSessions::new(infra.clone());
+        let conversation = Conversation::new(
+            ConversationId::generate(),
+            Workflow::new(),
+            Default::default(),
+        );
+        fixture.save_session(&conversation).await.unwrap();
+        let id = conversation.id.into_string();
+
+        let renamed = fixture.rename_session(&id, "bugfix").await.unwrap();
+        let deleted = fixture.delete_session("bugfix").await.unwrap();
+
+        let actual = (renamed.name, deleted.id);
+        let expected = (Some("bugfix".to_string()), conversation.id);
+        assert_eq!(actual, expected);
+        assert!(fixture.load_sessions().await.unwrap().sessions().is_empty());
+        assert!(
+            !infra
+                .exists(&fixture.conversation_path(&conversation.id))
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_last_session_is_recovered_from_the_journal() {
+        let fixture = ForgeSessions::new(Arc::new(MockFileService::new()));
+        let conversation = Conversation::new(
+            ConversationId::generate(),
+            Workflow::new(),
+            Default::default(),
+        )
+        .context(Some(Context::default()));
+        fixture.save_session(&conversation).await.unwrap();
+        let event = SessionEvent::Assistant {
+            messages: 0,
+            content: "Done".to_string(),
+            tool_calls: vec![],
+        };
+        fixture
+            .record_session_event(&conversation.id, &event)
+            .await
+            .unwrap();
+
+        let actual = fixture.load_session("last").await.unwrap().context;
+
+        let expected = Some(Context::default().append_message("Done", None, vec![]));
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_resumed_session_keeps_being_saved() {
+        let fixture = ForgeSessions::new(Arc::new(MockFileService::new()));
+        let conversation = Conversation::new(
+            ConversationId::generate(),
+            Workflow::new(),
+            Default::default(),
+        );
+        fixture.save_session(&conversation).await.unwrap();
+
+        let actual = fixture.load_session("last").await.unwrap().persist;
+
+        assert!(actual);
+    }
+}
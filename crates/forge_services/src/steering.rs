@@ -0,0 +1,91 @@
+use std::sync::Mutex;
+
+use forge_app::SteeringService;
+use forge_app::domain::{ConversationId, SteeringQueue};
+use tokio::sync::Notify;
+
+/// Keeps the instructions the user sends during a turn in memory, until the
+/// agent running the turn takes them.
+#[derive(Default)]
+pub struct ForgeSteering {
+    queue: Mutex<SteeringQueue>,
+    posted: Notify,
+}
+
+impl ForgeSteering {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl SteeringService for ForgeSteering {
+    async fn steer(&self, conversation_id: &ConversationId, message: String) -> anyhow::Result<()> {
+        self.queue.lock().unwrap().post(*conversation_id, message);
+        self.posted.notify_waiters();
+        Ok(())
+    }
+
+    async fn take_steering(
+        &self,
+        conversation_id: &ConversationId,
+        wait: bool,
+    ) -> anyhow::Result<Vec<String>> {
+        loop {
+            // Listening before taking, so that an instruction posted in between
+            // isn't missed
+            let posted = self.posted.notified();
+            tokio::pin!(posted);
+            posted.as_mut().enable();
+
+            let messages = self.queue.lock().unwrap().take(conversation_id);
+            if !messages.is_empty() || !wait {
+                return Ok(messages);
+            }
+            posted.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_take_steering() {
+        let fixture = ForgeSteering::new();
+        let conversation_id = ConversationId::generate();
+
+        let actual = fixture
+            .take_steering(&conversation_id, false)
+            .await
+            .unwrap();
+
+        let expected: Vec<String> = vec![];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_take_steering_waits_for_instruction() {
+        let fixture = Arc::new(ForgeSteering::new());
+        let conversation_id = ConversationId::generate();
+        let sender = fixture.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            sender
+                .steer(&conversation_id, "Use the v2 API".to_string())
+                .await
+                .unwrap();
+        });
+
+        let actual = fixture.take_steering(&conversation_id, true).await.unwrap();
+
+        let expected = vec!["Use the v2 API".to_string()];
+        assert_eq!(actual, expected);
+    }
+}
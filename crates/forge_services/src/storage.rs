@@ -0,0 +1,175 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use chrono::{DateTime, Utc};
+use forge_app::domain::{ConversationId, StoredItem, StoredKind};
+use forge_app::{SessionService, StorageService};
+use forge_snaps::SnapshotId;
+
+use crate::sessions::ForgeSessions;
+use crate::{
+    EnvironmentInfra, FileInfoInfra, FileReaderInfra, FileRemoverInfra, FileWriterInfra,
+    SnapshotInfra,
+};
+
+/// Lists and removes the data stored under the base path: the saved sessions,
+/// the request dumps and the snapshots of the files changed by the tools
+pub struct ForgeStorage<I> {
+    infra: Arc<I>,
+    sessions: Arc<ForgeSessions<I>>,
+}
+
+impl<I> ForgeStorage<I> {
+    pub fn new(infra: Arc<I>, sessions: Arc<ForgeSessions<I>>) -> Self {
+        Self { infra, sessions }
+    }
+}
+
+impl<I: EnvironmentInfra> ForgeStorage<I> {
+    async fn request_dumps(&self) -> anyhow::Result<Vec<StoredItem>> {
+        let dir = self.infra.get_environment().request_dump_path();
+        let mut entries = match tokio::fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error.into()),
+        };
+
+        let mut dumps = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "jsonl") {
+                continue;
+            }
+            let metadata = entry.metadata().await?;
+            dumps.push(StoredItem {
+                kind: StoredKind::RequestDump,
+                id: path.display().to_string(),
+                size: metadata.len(),
+                modified_at: DateTime::<Utc>::from(metadata.modified()?),
+            });
+        }
+        Ok(dumps)
+    }
+}
+
+#[async_trait::async_trait]
+impl<
+    I: EnvironmentInfra
+        + FileInfoInfra
+        + FileReaderInfra
+        + FileWriterInfra
+        + FileRemoverInfra
+        + SnapshotInfra,
+> StorageService for ForgeStorage<I>
+{
+    async fn stored_items(&self) -> anyhow::Result<Vec<StoredItem>> {
+        let mut items = Vec::new();
+
+        for session in self.sessions.load_sessions().await?.sessions() {
+            let mut size = 0;
+            for path in self.sessions.session_files(&session.id) {
+                if self.infra.exists(&path).await? {
+                    size += self.infra.file_size(&path).await?;
+                }
+            }
+            items.push(StoredItem {
+                kind: StoredKind::Session,
+                id: session.id.into_string(),
+                size,
+                modified_at: session.updated_at,
+            });
+        }
+
+        items.extend(self.request_dumps().await?);
+
+        for (snapshot, size) in self.infra.snapshot_usage().await? {
+            items.push(StoredItem {
+                kind: StoredKind::Snapshot,
+                id: snapshot.id.to_string(),
+                size,
+                modified_at: DateTime::<Utc>::from(UNIX_EPOCH + snapshot.timestamp),
+            });
+        }
+
+        Ok(items)
+    }
+
+    async fn remove_stored(&self, items: &[StoredItem]) -> anyhow::Result<Vec<StoredItem>> {
+        let mut removed = Vec::new();
+        let mut snapshots = Vec::new();
+        for item in items {
+            match item.kind {
+                // The session may have been resumed and saved since it was listed
+                StoredKind::Session => {
+                    let id = ConversationId::parse(&item.id)?;
+                    if !self
+                        .sessions
+                        .delete_unchanged(&id, item.modified_at)
+                        .await?
+                    {
+                        continue;
+                    }
+                }
+                StoredKind::RequestDump => self.infra.remove(Path::new(&item.id)).await?,
+                StoredKind::Snapshot => match SnapshotId::parse(&item.id) {
+                    Some(id) => snapshots.push(id),
+                    None => continue,
+                },
+            }
+            removed.push(item.clone());
+        }
+
+        if !snapshots.is_empty() {
+            self.infra.remove_snapshots(&snapshots).await?;
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_app::SessionService;
+    use forge_app::domain::{Conversation, Workflow};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::attachment::tests::MockFileService;
+
+    fn conversation() -> Conversation {
+        Conversation::new(
+            ConversationId::generate(),
+            Workflow::new(),
+            Default::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_sessions_saved_since_listed_are_kept() {
+        let infra = Arc::new(MockFileService::new());
+        let sessions = Arc::new(ForgeSessions::new(infra.clone()));
+        let fixture = ForgeStorage::new(infra, sessions.clone());
+        let stale = conversation();
+        let resumed = conversation();
+        sessions.save_session(&stale).await.unwrap();
+        sessions.save_session(&resumed).await.unwrap();
+        let items = fixture.stored_items().await.unwrap();
+
+        sessions.save_session(&resumed).await.unwrap();
+        let removed = fixture.remove_stored(&items).await.unwrap();
+        let left = sessions.load_sessions().await.unwrap();
+
+        let actual = (
+            removed
+                .iter()
+                .map(|item| item.id.clone())
+                .collect::<Vec<_>>(),
+            left.sessions()
+                .iter()
+                .map(|session| session.id)
+                .collect::<Vec<_>>(),
+        );
+        let expected = (vec![stale.id.into_string()], vec![resumed.id]);
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use bytes::Bytes;
+use forge_app::TaskService;
+use forge_app::domain::TaskList;
+
+use crate::{EnvironmentInfra, FileInfoInfra, FileReaderInfra, FileWriterInfra};
+
+/// Keeps the task list in a JSON file of the workspace, so that it is shared
+/// by the conversations and survives restarts.
+pub struct ForgeTasks<I> {
+    infra: Arc<I>,
+}
+
+impl<I> ForgeTasks<I> {
+    pub fn new(infra: Arc<I>) -> Self {
+        Self { infra }
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: EnvironmentInfra + FileInfoInfra + FileReaderInfra + FileWriterInfra> TaskService
+    for ForgeTasks<I>
+{
+    async fn load_tasks(&self) -> anyhow::Result<TaskList> {
+        let path = self.infra.get_environment().tasks_path();
+        if !self.infra.exists(&path).await? {
+            return Ok(TaskList::new());
+        }
+
+        let content = self.infra.read_utf8(&path).await?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse the task list at {}", path.display()))
+    }
+
+    async fn save_tasks(&self, tasks: &TaskList) -> anyhow::Result<()> {
+        let path = self.infra.get_environment().tasks_path();
+        let content = serde_json::to_string_pretty(tasks)?;
+        self.infra.replace(&path, Bytes::from(content)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::attachment::tests::MockFileService;
+
+    #[tokio::test]
+    async fn test_load_tasks_without_file() {
+        let fixture = ForgeTasks::new(Arc::new(MockFileService::new()));
+
+        let actual = fixture.load_tasks().await.unwrap();
+
+        let expected = TaskList::new();
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_saved_tasks_are_loaded() {
+        let fixture = ForgeTasks::new(Arc::new(MockFileService::new()));
+        let mut tasks = TaskList::new();
+        tasks.append_multiple(vec!["Write the parser".into(), "Test the parser".into()]);
+        tasks.mark_done(1);
+
+        fixture.save_tasks(&tasks).await.unwrap();
+        let actual = fixture.load_tasks().await.unwrap();
+
+        let expected = tasks;
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+use forge_app::ToolAuditService;
+use forge_app::domain::{ToolCallQuery, ToolCallRecord};
+
+use crate::{EnvironmentInfra, FileInfoInfra, FileReaderInfra, FileWriterInfra};
+
+/// Keeps an append-only log of the tool calls in a JSONL file under the base
+/// path, one record per line.
+pub struct ForgeToolAudit<I> {
+    infra: Arc<I>,
+}
+
+impl<I> ForgeToolAudit<I> {
+    pub fn new(infra: Arc<I>) -> Self {
+        Self { infra }
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: EnvironmentInfra + FileInfoInfra + FileReaderInfra + FileWriterInfra> ToolAuditService
+    for ForgeToolAudit<I>
+{
+    async fn record_tool_call(&self, record: ToolCallRecord) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        let path = self.infra.get_environment().tool_calls_path();
+        self.infra.append(&path, Bytes::from(line)).await
+    }
+
+    async fn tool_calls(&self, query: ToolCallQuery) -> anyhow::Result<Vec<ToolCallRecord>> {
+        let path = self.infra.get_environment().tool_calls_path();
+        if !self.infra.exists(&path).await? {
+            return Ok(Vec::new());
+        }
+
+        let content = self.infra.read_utf8(&path).await?;
+        let mut records = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            // A line can be incomplete if forge was stopped while writing it
+            .filter_map(|line| match serde_json::from_str::<ToolCallRecord>(line) {
+                Ok(record) => Some(record),
+                Err(error) => {
+                    tracing::warn!(%error, "Skipping invalid tool call record");
+                    None
+                }
+            })
+            .filter(|record| query.matches(record))
+            .collect::<Vec<_>>();
+
+        if let Some(limit) = query.limit {
+            records.drain(..records.len().saturating_sub(limit));
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+    use forge_app::domain::{AgentId, ToolCallFull, ToolName, ToolOutput};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::attachment::tests::MockFileService;
+
+    fn record(name: &str, is_error: bool) -> ToolCallRecord {
+        ToolCallRecord::new(
+            AgentId::new("forge"),
+            &ToolCallFull::new(name),
+            &ToolOutput::text("output").is_error(is_error),
+            DateTime::<Utc>::default(),
+            10,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_without_log() {
+        let fixture = ForgeToolAudit::new(Arc::new(MockFileService::new()));
+
+        let actual = fixture.tool_calls(ToolCallQuery::default()).await.unwrap();
+
+        let expected: Vec<ToolCallRecord> = vec![];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_are_queried_from_the_log() {
+        let fixture = ForgeToolAudit::new(Arc::new(MockFileService::new()));
+        for record in [
+            record("forge_tool_fs_read", false),
+            record("forge_tool_process_shell", true),
+            record("forge_tool_fs_read", true),
+        ] {
+            fixture.record_tool_call(record).await.unwrap();
+        }
+
+        let actual = fixture
+            .tool_calls(
+                ToolCallQuery::default()
+                    .name(ToolName::new("forge_tool_fs_read"))
+                    .limit(1usize),
+            )
+            .await
+            .unwrap();
+
+        let expected = vec![record("forge_tool_fs_read", true)];
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,201 @@
+use anyhow::Context;
+use chromiumoxide::cdp::browser_protocol::page::CaptureScreenshotFormat;
+use chromiumoxide::page::ScreenshotParams;
+use chromiumoxide::{Browser, BrowserConfig, Page};
+use forge_app::domain::Image;
+use forge_app::{BrowserPage, BrowserService};
+use futures::StreamExt;
+use reqwest::Url;
+use tokio::sync::Mutex;
+
+struct Session {
+    browser: Browser,
+    page: Option<Page>,
+    /// URL of the page last returned, which the caller checked
+    seen: Option<String>,
+}
+
+/// Drives a headless Chrome or Chromium for pages that need JavaScript to
+/// render. The browser is launched on first use and the open page is kept
+/// between calls, so that the agent can navigate through a site.
+#[derive(Default)]
+pub struct ForgeBrowser {
+    session: Mutex<Option<Session>>,
+}
+
+impl ForgeBrowser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn launch() -> anyhow::Result<Session> {
+        let config = BrowserConfig::builder()
+            .build()
+            .map_err(anyhow::Error::msg)?;
+        let (browser, mut handler) = Browser::launch(config)
+            .await
+            .context("Failed to launch the browser, make sure Chrome or Chromium is installed")?;
+
+        // The handler drives the connection to the browser and has to be polled
+        // for as long as the browser is in use
+        tokio::spawn(async move { while handler.next().await.is_some() {} });
+
+        Ok(Session { browser, page: None, seen: None })
+    }
+
+    fn session(session: &mut Option<Session>) -> anyhow::Result<&mut Session> {
+        session
+            .as_mut()
+            .filter(|session| session.page.is_some())
+            .context("No page is open, use the 'open' action first")
+    }
+
+    /// Reads the open page, recording its URL as the one last seen
+    async fn read(session: &mut Session) -> anyhow::Result<BrowserPage> {
+        let page = session.page.as_ref().context("No page is open")?;
+        let url = page.url().await?.unwrap_or_default();
+        let title = page.get_title().await?.filter(|title| !title.is_empty());
+        let html = page.content().await?;
+        session.seen = Some(url.clone());
+        Ok(BrowserPage { url, title, content: html2md::parse_html(&html) })
+    }
+}
+
+#[async_trait::async_trait]
+impl BrowserService for ForgeBrowser {
+    async fn open(&self, url: String) -> anyhow::Result<BrowserPage> {
+        let url = Url::parse(&url).with_context(|| format!("Failed to parse URL: {url}"))?;
+
+        let mut guard = self.session.lock().await;
+        if guard.is_none() {
+            *guard = Some(Self::launch().await?);
+        }
+        let session = guard.as_mut().context("Browser is not running")?;
+
+        let page = match session.page.take() {
+            Some(page) => {
+                page.goto(url.as_str()).await?;
+                page
+            }
+            None => session.browser.new_page(url.as_str()).await?,
+        };
+        page.wait_for_navigation()
+            .await
+            .with_context(|| format!("Failed to load {url}"))?;
+        session.page = Some(page);
+        Self::read(session).await
+    }
+
+    async fn text(&self) -> anyhow::Result<BrowserPage> {
+        let mut guard = self.session.lock().await;
+        Self::read(Self::session(&mut guard)?).await
+    }
+
+    async fn click(&self, selector: String) -> anyhow::Result<BrowserPage> {
+        let mut guard = self.session.lock().await;
+        let session = Self::session(&mut guard)?;
+        let page = session.page.as_ref().context("No page is open")?;
+        page.find_element(selector.as_str())
+            .await
+            .with_context(|| format!("No element matches the selector '{selector}'"))?
+            .click()
+            .await?;
+        page.wait_for_navigation().await?;
+        Self::read(session).await
+    }
+
+    async fn screenshot(&self) -> anyhow::Result<Image> {
+        let mut guard = self.session.lock().await;
+        let session = Self::session(&mut guard)?;
+        let page = session.page.as_ref().context("No page is open")?;
+        let url = page.url().await?;
+        let bytes = page
+            .screenshot(
+                ScreenshotParams::builder()
+                    .format(CaptureScreenshotFormat::Png)
+                    .build(),
+            )
+            .await?;
+        session.seen = url;
+        Ok(Image::new_bytes(bytes, "image/png"))
+    }
+
+    async fn current_url(&self) -> anyhow::Result<Option<String>> {
+        let guard = self.session.lock().await;
+        match guard.as_ref().and_then(|session| session.page.as_ref()) {
+            Some(page) => Ok(page.url().await?),
+            None => Ok(None),
+        }
+    }
+
+    async fn seen_url(&self) -> anyhow::Result<Option<String>> {
+        let guard = self.session.lock().await;
+        Ok(guard.as_ref().and_then(|session| session.seen.clone()))
+    }
+
+    async fn close(&self) -> anyhow::Result<()> {
+        let page = self.session.lock().await.as_mut().and_then(|session| {
+            session.seen = None;
+            session.page.take()
+        });
+        if let Some(page) = page {
+            page.close().await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_actions_without_open_page() {
+        let fixture = ForgeBrowser::new();
+
+        let actual = (
+            fixture.text().await.unwrap_err().to_string(),
+            fixture
+                .click("a".to_string())
+                .await
+                .unwrap_err()
+                .to_string(),
+            fixture.screenshot().await.unwrap_err().to_string(),
+        );
+
+        let expected = "No page is open, use the 'open' action first".to_string();
+        assert_eq!(actual, (expected.clone(), expected.clone(), expected));
+    }
+
+    #[tokio::test]
+    async fn test_urls_without_open_page() {
+        let fixture = ForgeBrowser::new();
+
+        let actual = (
+            fixture.current_url().await.unwrap(),
+            fixture.seen_url().await.unwrap(),
+        );
+
+        assert_eq!(actual, (None, None));
+    }
+
+    #[tokio::test]
+    async fn test_close_without_open_page() {
+        let fixture = ForgeBrowser::new();
+
+        let actual = fixture.close().await;
+
+        assert!(actual.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_open_invalid_url() {
+        let fixture = ForgeBrowser::new();
+
+        let actual = fixture.open("not a url".to_string()).await.unwrap_err();
+
+        assert_eq!(actual.to_string(), "Failed to parse URL: not a url");
+    }
+}
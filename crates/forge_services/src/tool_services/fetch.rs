@@ -1,6 +1,13 @@
+use std::collections::BTreeMap;
+
 use anyhow::{Context, anyhow};
-use forge_app::{HttpResponse, NetFetchService, ResponseContext};
-use reqwest::{Client, Url};
+use forge_app::domain::HttpMethod;
+use forge_app::{HttpResponse, NetFetchService, NetRequestService, ResponseContext};
+use reqwest::{Client, Method, Response, Url, redirect};
+
+/// Responses of the request tool are cut off after this many bytes, so that a
+/// misbehaving API can't exhaust memory
+const MAX_RESPONSE_BYTES: usize = 10 * 1024 * 1024;
 
 /// Retrieves content from URLs as markdown or raw text. Enables access to
 /// current online information including websites, APIs and documentation. Use
@@ -22,11 +29,27 @@ impl Default for ForgeFetch {
 }
 
 impl ForgeFetch {
+    /// Redirects aren't followed, as the URL they lead to has to pass the
+    /// policies the requested one did. The agent is told where the response
+    /// redirects to and requests it itself.
     pub fn new() -> Self {
-        Self { client: Client::new() }
+        let client = Client::builder()
+            .redirect(redirect::Policy::none())
+            .build()
+            .expect("The HTTP client should build with the default settings");
+        Self { client }
     }
 }
 
+/// URL a redirect response points to, resolved against the requested one
+fn redirect_location(url: &Url, response: &Response) -> Option<Url> {
+    if !response.status().is_redirection() {
+        return None;
+    }
+    let location = response.headers().get("location")?.to_str().ok()?;
+    url.join(location).ok()
+}
+
 impl ForgeFetch {
     async fn check_robots_txt(&self, url: &Url) -> anyhow::Result<()> {
         let robots_url = format!("{}://{}/robots.txt", url.scheme(), url.authority());
@@ -73,6 +96,14 @@ impl ForgeFetch {
             .map_err(|e| anyhow!("Failed to fetch URL {}: {}", url, e))?;
         let code = response.status().as_u16();
 
+        if let Some(location) = redirect_location(url, &response) {
+            return Err(anyhow!(
+                "{} redirects to {} (status code {}), fetch that URL instead",
+                url,
+                location,
+                response.status()
+            ));
+        }
         if !response.status().is_success() {
             return Err(anyhow!(
                 "Failed to fetch {} - status code {}",
@@ -119,3 +150,135 @@ impl NetFetchService for ForgeFetch {
         self.fetch_url(&url, raw.unwrap_or(false)).await
     }
 }
+
+#[async_trait::async_trait]
+impl NetRequestService for ForgeFetch {
+    async fn request(
+        &self,
+        method: HttpMethod,
+        url: String,
+        headers: BTreeMap<String, String>,
+        body: Option<String>,
+    ) -> anyhow::Result<HttpResponse> {
+        let url = Url::parse(&url).with_context(|| format!("Failed to parse URL: {url}"))?;
+        let method = Method::from_bytes(method.as_ref().as_bytes())?;
+
+        let mut request = self.client.request(method, url.clone());
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        if let Some(body) = body {
+            request = request.body(body);
+        }
+
+        let mut response = request
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to send request to {}: {}", url, e))?;
+        let code = response.status().as_u16();
+        let content_type = response
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+        let location = redirect_location(&url, &response);
+
+        let mut bytes = Vec::new();
+        let mut truncated = false;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| anyhow!("Failed to read response content from {}: {}", url, e))?
+        {
+            bytes.extend_from_slice(&chunk);
+            if bytes.len() > MAX_RESPONSE_BYTES {
+                bytes.truncate(MAX_RESPONSE_BYTES);
+                truncated = true;
+                break;
+            }
+        }
+
+        let mut content = String::from_utf8_lossy(&bytes).into_owned();
+        if truncated {
+            content.push_str(&format!(
+                "\n[Response truncated after {MAX_RESPONSE_BYTES} bytes]"
+            ));
+        }
+        if let Some(location) = location {
+            content.push_str(&format!(
+                "\n[Redirects to {location}, send the request there to follow it]"
+            ));
+        }
+
+        Ok(HttpResponse { content, code, context: ResponseContext::Raw, content_type })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::Server;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fetch_does_not_follow_redirects() {
+        let mut server = Server::new_async().await;
+        let _robots = server
+            .mock("GET", "/robots.txt")
+            .with_status(404)
+            .create_async()
+            .await;
+        let _redirect = server
+            .mock("GET", "/docs")
+            .with_status(302)
+            .with_header("location", "http://169.254.169.254/latest/meta-data")
+            .create_async()
+            .await;
+        let fixture = ForgeFetch::new();
+
+        let actual = fixture
+            .fetch(format!("{}/docs", server.url()), None)
+            .await
+            .unwrap_err()
+            .to_string();
+
+        let expected = format!(
+            "{}/docs redirects to http://169.254.169.254/latest/meta-data (status code 302 Found), fetch that URL instead",
+            server.url()
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_request_does_not_follow_redirects() {
+        let mut server = Server::new_async().await;
+        let _redirect = server
+            .mock("POST", "/api")
+            .with_status(307)
+            .with_header("location", "/v2/api")
+            .create_async()
+            .await;
+        let fixture = ForgeFetch::new();
+
+        let actual = fixture
+            .request(
+                HttpMethod::Post,
+                format!("{}/api", server.url()),
+                BTreeMap::new(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let expected = (
+            307,
+            format!(
+                "\n[Redirects to {}/v2/api, send the request there to follow it]",
+                server.url()
+            ),
+        );
+        assert_eq!((actual.code, actual.content), expected);
+    }
+}
@@ -0,0 +1,349 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Context;
+use bytes::Bytes;
+use forge_app::domain::{FileDiff, Hunk, HunkLine, parse_unified_diff};
+use forge_app::{ApplyPatchOutput, FsApplyPatchService, PatchedFile};
+
+use crate::{
+    FileDirectoryInfra, FileInfoInfra, FileReaderInfra, FileRemoverInfra, FileWriterInfra,
+    tool_services,
+};
+
+/// How strictly the lines of a hunk have to match the file. Hunks are first
+/// matched exactly, then ignoring whitespace at the end of lines and finally
+/// ignoring indentation too, which covers most of the diffs models get wrong.
+const FUZZ_LEVELS: [fn(&str, &str) -> bool; 3] = [
+    |a, b| a == b,
+    |a, b| a.trim_end() == b.trim_end(),
+    |a, b| a.trim() == b.trim(),
+];
+
+/// Finds where the lines of a hunk are in the file, preferring the position
+/// closest to the expected one. Only positions from `min` on are considered,
+/// so that hunks don't overlap.
+fn find_hunk(lines: &[String], old: &[&str], expected: usize, min: usize) -> Option<usize> {
+    let max = lines.len().checked_sub(old.len())?;
+    if min > max {
+        return None;
+    }
+    let expected = expected.clamp(min, max);
+
+    FUZZ_LEVELS.iter().find_map(|matches| {
+        let is_match = |pos: usize| {
+            lines[pos..pos + old.len()]
+                .iter()
+                .zip(old)
+                .all(|(line, old)| matches(line, old))
+        };
+        (0..=max - min).find_map(|distance| {
+            [
+                expected.checked_sub(distance),
+                expected.checked_add(distance),
+            ]
+            .into_iter()
+            .flatten()
+            .filter(|pos| (min..=max).contains(pos))
+            .find(|pos| is_match(*pos))
+        })
+    })
+}
+
+/// Applies the hunks of a file diff to its content. Returns the headers of
+/// the hunks that couldn't be applied if any.
+fn apply_hunks(content: &str, hunks: &[Hunk]) -> Result<String, Vec<String>> {
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let mut failed = Vec::new();
+    // Lines added minus lines removed by the hunks applied so far
+    let mut delta: isize = 0;
+    let mut min = 0;
+
+    for hunk in hunks {
+        let old = hunk.old_lines();
+        let position = if old.is_empty() {
+            // Pure additions have no context to match, so they go where the header
+            // says: `-N,0` adds the lines after the line N
+            let expected = (hunk.old_start as isize + delta).max(0) as usize;
+            Some(expected.clamp(min, lines.len()))
+        } else {
+            let expected = (hunk.old_start.saturating_sub(1) as isize + delta).max(0) as usize;
+            find_hunk(&lines, &old, expected, min)
+        };
+        let Some(position) = position else {
+            failed.push(hunk.header.clone());
+            continue;
+        };
+
+        // Context lines are kept as they are in the file, since they may only
+        // match loosely
+        let mut original = lines[position..position + old.len()].iter();
+        let replacement: Vec<String> = hunk
+            .lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(_) => original.next().cloned(),
+                HunkLine::Remove(_) => {
+                    original.next();
+                    None
+                }
+                HunkLine::Add(text) => Some(text.clone()),
+            })
+            .collect();
+
+        min = position + replacement.len();
+        delta += replacement.len() as isize - old.len() as isize;
+        lines.splice(position..position + old.len(), replacement);
+    }
+
+    if !failed.is_empty() {
+        return Err(failed);
+    }
+
+    let mut output = lines.join("\n");
+    // Keep the trailing newline of the file, and add one to new files
+    if !output.is_empty() && (content.ends_with('\n') || content.is_empty()) {
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// A change to a single file that is ready to be written
+struct Change {
+    path: PathBuf,
+    // Set when the patch renames the file
+    renamed_from: Option<PathBuf>,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+fn into_output(changes: Vec<Change>) -> ApplyPatchOutput {
+    let files = changes
+        .into_iter()
+        .map(|change| PatchedFile {
+            warning: change
+                .after
+                .as_ref()
+                .and_then(|after| tool_services::syn::validate(&change.path, after))
+                .map(|warning| warning.to_string()),
+            path: change.path,
+            before: change.before,
+            after: change.after,
+        })
+        .collect();
+
+    ApplyPatchOutput { files }
+}
+
+/// Applies unified diffs across several files at once. All hunks are matched
+/// before anything is written, so that a patch with a failing hunk leaves the
+/// files untouched.
+pub struct ForgeFsApplyPatch<F>(Arc<F>);
+
+impl<F> ForgeFsApplyPatch<F> {
+    pub fn new(infra: Arc<F>) -> Self {
+        Self(infra)
+    }
+}
+
+impl<F: FileDirectoryInfra + FileInfoInfra + FileReaderInfra + FileRemoverInfra + FileWriterInfra>
+    ForgeFsApplyPatch<F>
+{
+    async fn prepare(&self, cwd: &Path, diff: &FileDiff) -> anyhow::Result<Result<Change, String>> {
+        let path = cwd.join(diff.path());
+        let before = match &diff.old_path {
+            Some(old_path) => {
+                let old_path = cwd.join(old_path);
+                if !self.0.is_file(&old_path).await? {
+                    return Ok(Err(format!("{}: file not found", old_path.display())));
+                }
+                Some(self.0.read_utf8(&old_path).await?)
+            }
+            None if self.0.exists(&path).await? => {
+                return Ok(Err(format!("{}: file already exists", path.display())));
+            }
+            None => None,
+        };
+
+        let after = match &diff.new_path {
+            Some(_) => match apply_hunks(before.as_deref().unwrap_or_default(), &diff.hunks) {
+                Ok(after) => Some(after),
+                Err(headers) => {
+                    let failures = headers
+                        .iter()
+                        .map(|header| format!("{}: hunk '{header}' doesn't match", path.display()))
+                        .collect::<Vec<_>>();
+                    return Ok(Err(failures.join("\n")));
+                }
+            },
+            None => None,
+        };
+
+        let renamed_from = diff
+            .old_path
+            .as_ref()
+            .filter(|old_path| diff.new_path.as_ref().is_some_and(|new| new != *old_path))
+            .map(|old_path| cwd.join(old_path));
+
+        Ok(Ok(Change { path, renamed_from, before, after }))
+    }
+
+    /// Matches the hunks of all the files in the patch. Fails without
+    /// changing anything when one of them doesn't match.
+    async fn prepare_all(&self, cwd: &Path, patch: &str) -> anyhow::Result<Vec<Change>> {
+        let diffs = parse_unified_diff(patch)?;
+
+        let mut changes = Vec::new();
+        let mut failures = Vec::new();
+        for diff in &diffs {
+            match self.prepare(cwd, diff).await? {
+                Ok(change) => changes.push(change),
+                Err(failure) => failures.push(failure),
+            }
+        }
+        if !failures.is_empty() {
+            anyhow::bail!(
+                "The patch was not applied, no files were changed. Fix the following and try again:\n{}",
+                failures.join("\n")
+            );
+        }
+        Ok(changes)
+    }
+
+    /// Returns the changes the patch would make, without writing anything
+    pub(crate) async fn preview(
+        &self,
+        cwd: &Path,
+        patch: &str,
+    ) -> anyhow::Result<ApplyPatchOutput> {
+        Ok(into_output(self.prepare_all(cwd, patch).await?))
+    }
+
+    async fn write(&self, change: &Change) -> anyhow::Result<()> {
+        match &change.after {
+            Some(after) => {
+                if let Some(parent) = change.path.parent() {
+                    self.0.create_dirs(parent).await?;
+                }
+                self.0
+                    .write(&change.path, Bytes::from(after.clone()), true)
+                    .await?;
+                if let Some(renamed_from) = &change.renamed_from {
+                    self.0.remove(renamed_from).await?;
+                }
+            }
+            None => self.0.remove(&change.path).await?,
+        }
+        Ok(())
+    }
+
+    /// Restores a file to its content before the patch
+    async fn revert(&self, change: &Change) -> anyhow::Result<()> {
+        let original = change.renamed_from.as_ref().unwrap_or(&change.path);
+        if change.renamed_from.is_some() && self.0.exists(&change.path).await? {
+            self.0.remove(&change.path).await?;
+        }
+        match &change.before {
+            Some(before) => {
+                self.0
+                    .write(original, Bytes::from(before.clone()), false)
+                    .await
+            }
+            None => self.0.remove(original).await,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: FileDirectoryInfra + FileInfoInfra + FileReaderInfra + FileRemoverInfra + FileWriterInfra>
+    FsApplyPatchService for ForgeFsApplyPatch<F>
+{
+    async fn apply_patch(&self, cwd: PathBuf, patch: String) -> anyhow::Result<ApplyPatchOutput> {
+        let changes = self.prepare_all(&cwd, &patch).await?;
+
+        for (index, change) in changes.iter().enumerate() {
+            if let Err(error) = self.write(change).await {
+                for change in changes[..=index].iter().rev() {
+                    if let Err(error) = self.revert(change).await {
+                        tracing::error!(path = %change.path.display(), error = ?error, "Failed to revert patched file");
+                    }
+                }
+                return Err(error).with_context(|| {
+                    format!(
+                        "Failed to write {}, the patch was reverted",
+                        change.path.display()
+                    )
+                });
+            }
+        }
+
+        Ok(into_output(changes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn hunks(patch: &str) -> Vec<Hunk> {
+        parse_unified_diff(patch).unwrap().remove(0).hunks
+    }
+
+    #[test]
+    fn test_apply_hunks_with_offset() {
+        let content = "header\nheader\na\nb\nc\n";
+        let fixture = hunks("--- a/f\n+++ b/f\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n");
+
+        let actual = apply_hunks(content, &fixture).unwrap();
+
+        let expected = "header\nheader\na\nB\nc\n";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_apply_hunks_ignores_indentation() {
+        let content = "fn main() {\n    let a = 1;\n    println!(\"{a}\");\n}\n";
+        let fixture = hunks(
+            "--- a/f\n+++ b/f\n@@ -1,4 +1,4 @@\n fn main() {\n-  let a = 1;\n+    let a = 2;\n   println!(\"{a}\");\n }\n",
+        );
+
+        let actual = apply_hunks(content, &fixture).unwrap();
+
+        let expected = "fn main() {\n    let a = 2;\n    println!(\"{a}\");\n}\n";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_apply_hunks_reports_failed_hunks() {
+        let content = "a\nb\nc\n";
+        let fixture = hunks("--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n a\n-b\n+B\n@@ -3 +3 @@\n-x\n+X\n");
+
+        let actual = apply_hunks(content, &fixture);
+
+        let expected = Err(vec!["@@ -3 +3 @@".to_string()]);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_apply_hunks_to_new_file() {
+        let fixture = hunks("--- /dev/null\n+++ b/f\n@@ -0,0 +1,2 @@\n+a\n+b\n");
+
+        let actual = apply_hunks("", &fixture).unwrap();
+
+        let expected = "a\nb\n";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_apply_hunks_inserts_after_the_line_of_the_header() {
+        let content = "a\nb\nc\n";
+        let fixture = hunks("--- a/f\n+++ b/f\n@@ -0,0 +1 @@\n+start\n@@ -2,0 +4 @@\n+after b\n");
+
+        let actual = apply_hunks(content, &fixture).unwrap();
+
+        let expected = "start\na\nb\nafter b\nc\n";
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,42 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use forge_app::domain::AstOperation;
+use forge_app::{FsAstEditService, PatchOutput};
+
+use crate::utils::assert_absolute_path;
+use crate::{FileReaderInfra, FileWriterInfra, tool_services};
+
+/// Edits source files through their syntax tree. Definitions are looked up by
+/// their symbol path, so edits don't depend on the exact text of the file.
+/// The file is left untouched when the edit would introduce syntax errors.
+pub struct ForgeFsAstEdit<F>(Arc<F>);
+
+impl<F> ForgeFsAstEdit<F> {
+    pub fn new(infra: Arc<F>) -> Self {
+        Self(infra)
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: FileReaderInfra + FileWriterInfra> FsAstEditService for ForgeFsAstEdit<F> {
+    async fn ast_edit(
+        &self,
+        input_path: String,
+        symbol: Option<String>,
+        operation: AstOperation,
+        content: String,
+    ) -> anyhow::Result<PatchOutput> {
+        let path = Path::new(&input_path);
+        assert_absolute_path(path)?;
+
+        let before = self.0.read_utf8(path).await?;
+        let after =
+            tool_services::syn::edit(path, &before, symbol.as_deref(), &operation, &content)?;
+
+        self.0.write(path, Bytes::from(after.clone()), true).await?;
+
+        Ok(PatchOutput { warning: None, before, after })
+    }
+}
@@ -52,7 +52,7 @@ impl From<Range> for std::ops::Range<usize> {
 // MatchSequence struct and implementation removed - we only use exact matching
 
 #[derive(Debug, Error)]
-enum Error {
+pub(crate) enum Error {
     #[error("Failed to read/write file: {0}")]
     FileOperation(#[from] std::io::Error),
     #[error("Could not find match for search text: {0}")]
@@ -65,7 +65,7 @@ enum Error {
     MultipleMatches(String),
 }
 
-fn apply_replacement(
+pub(crate) fn apply_replacement(
     haystack: String,
     search: Option<String>,
     operation: &PatchOperation,
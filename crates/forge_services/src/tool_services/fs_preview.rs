@@ -0,0 +1,158 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use forge_app::domain::Tools;
+use forge_app::{ApplyPatchOutput, FsPreviewService, PatchedFile};
+
+use super::fs_patch::apply_replacement;
+use crate::utils::assert_absolute_path;
+use crate::{
+    FileDirectoryInfra, FileInfoInfra, FileReaderInfra, FileRemoverInfra, FileWriterInfra,
+    ForgeFsApplyPatch, tool_services,
+};
+
+/// Works out the changes the file tools would make, without writing anything.
+/// Used in dry-run mode so that the changes can be reviewed before they are
+/// made.
+pub struct ForgeFsPreview<F>(Arc<F>);
+
+impl<F> ForgeFsPreview<F> {
+    pub fn new(infra: Arc<F>) -> Self {
+        Self(infra)
+    }
+}
+
+impl<F: FileInfoInfra + FileReaderInfra> ForgeFsPreview<F> {
+    async fn current(&self, path: &Path) -> anyhow::Result<Option<String>> {
+        assert_absolute_path(path)?;
+        if !self.0.is_file(path).await? {
+            return Ok(None);
+        }
+        Ok(Some(self.0.read_utf8(path).await?))
+    }
+
+    async fn read(&self, path: &Path) -> anyhow::Result<String> {
+        self.current(path)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("File not found: {}", path.display()))
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: FileDirectoryInfra + FileInfoInfra + FileReaderInfra + FileRemoverInfra + FileWriterInfra>
+    FsPreviewService for ForgeFsPreview<F>
+{
+    async fn preview(&self, tool: Tools, cwd: PathBuf) -> anyhow::Result<ApplyPatchOutput> {
+        let (path, before, after) = match tool {
+            Tools::ForgeToolFsCreate(input) => {
+                let path = PathBuf::from(input.path);
+                let before = self.current(&path).await?;
+                if before.is_some() && !input.overwrite {
+                    anyhow::bail!("Cannot overwrite existing file: overwrite flag not set.");
+                }
+                (path, before, Some(input.content))
+            }
+            Tools::ForgeToolFsPatch(input) => {
+                let path = PathBuf::from(input.path);
+                let before = self.read(&path).await?;
+                let after = apply_replacement(
+                    before.clone(),
+                    input.search,
+                    &input.operation,
+                    &input.content,
+                )?;
+                (path, Some(before), Some(after))
+            }
+            Tools::ForgeToolFsAstEdit(input) => {
+                let path = PathBuf::from(input.path);
+                let before = self.read(&path).await?;
+                let after = tool_services::syn::edit(
+                    &path,
+                    &before,
+                    input.symbol.as_deref(),
+                    &input.operation,
+                    &input.content,
+                )?;
+                (path, Some(before), Some(after))
+            }
+            Tools::ForgeToolFsRemove(input) => {
+                let path = PathBuf::from(input.path);
+                let before = self.read(&path).await?;
+                (path, Some(before), None)
+            }
+            Tools::ForgeToolFsApplyPatch(input) => {
+                return ForgeFsApplyPatch::new(self.0.clone())
+                    .preview(&cwd, &input.patch)
+                    .await;
+            }
+            tool => anyhow::bail!("{} can't be previewed in dry-run mode", tool.name()),
+        };
+
+        let warning = after
+            .as_ref()
+            .and_then(|after| tool_services::syn::validate(&path, after))
+            .map(|warning| warning.to_string());
+        Ok(ApplyPatchOutput { files: vec![PatchedFile { path, before, after, warning }] })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_app::domain::{FSRemove, FSWrite};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::attachment::tests::MockFileService;
+
+    #[tokio::test]
+    async fn test_preview_create_leaves_file_untouched() {
+        let infra = Arc::new(MockFileService::new());
+        infra.add_file(PathBuf::from("/test/a.txt"), "old".to_string());
+        let fixture = ForgeFsPreview::new(infra.clone());
+
+        let actual = fixture
+            .preview(
+                Tools::ForgeToolFsCreate(FSWrite {
+                    path: "/test/a.txt".to_string(),
+                    content: "new".to_string(),
+                    overwrite: true,
+                    explanation: None,
+                }),
+                PathBuf::from("/test"),
+            )
+            .await
+            .unwrap()
+            .files
+            .into_iter()
+            .map(|file| (file.path, file.before, file.after))
+            .collect::<Vec<_>>();
+
+        let expected = vec![(
+            PathBuf::from("/test/a.txt"),
+            Some("old".to_string()),
+            Some("new".to_string()),
+        )];
+        assert_eq!(actual, expected);
+        assert_eq!(
+            infra.read_utf8(Path::new("/test/a.txt")).await.unwrap(),
+            "old"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_preview_remove_missing_file() {
+        let fixture = ForgeFsPreview::new(Arc::new(MockFileService::new()));
+
+        let actual = fixture
+            .preview(
+                Tools::ForgeToolFsRemove(FSRemove {
+                    path: "/test/missing.txt".to_string(),
+                    explanation: None,
+                }),
+                PathBuf::from("/test"),
+            )
+            .await;
+
+        assert!(actual.is_err());
+    }
+}
@@ -3,12 +3,13 @@ use std::path::Path;
 use std::sync::Arc;
 
 use anyhow::Context;
+use forge_app::domain::WalkerFilter;
 use forge_app::{FsSearchService, Match, MatchResult, SearchResult, Walker};
-use grep_searcher::sinks::UTF8;
+use grep_searcher::{Searcher, SearcherBuilder, Sink, SinkContext, SinkMatch};
 
 use crate::infra::WalkerInfra;
 use crate::utils::assert_absolute_path;
-use crate::{FileInfoInfra, FileReaderInfra};
+use crate::{EnvironmentInfra, FileInfoInfra, FileReaderInfra};
 
 // Using FSSearchInput from forge_domain
 
@@ -79,12 +80,16 @@ impl<W> ForgeFsSearch<W> {
 }
 
 #[async_trait::async_trait]
-impl<W: WalkerInfra + FileReaderInfra + FileInfoInfra> FsSearchService for ForgeFsSearch<W> {
+impl<W: EnvironmentInfra + WalkerInfra + FileReaderInfra + FileInfoInfra> FsSearchService
+    for ForgeFsSearch<W>
+{
     async fn search(
         &self,
         input_path: String,
         input_regex: Option<String>,
         file_pattern: Option<String>,
+        context_lines: usize,
+        filter: &WalkerFilter,
     ) -> anyhow::Result<Option<SearchResult>> {
         let helper = FSSearchHelper {
             path: &input_path,
@@ -106,7 +111,7 @@ impl<W: WalkerInfra + FileReaderInfra + FileInfoInfra> FsSearchService for Forge
             }
             None => None,
         };
-        let paths = self.retrieve_file_paths(path).await?;
+        let paths = self.retrieve_file_paths(path, filter).await?;
 
         let mut matches = Vec::new();
 
@@ -128,7 +133,10 @@ impl<W: WalkerInfra + FileReaderInfra + FileInfoInfra> FsSearchService for Forge
 
             // Process the file line by line to find content matches
             if let Some(regex) = &content_pattern {
-                let mut searcher = grep_searcher::Searcher::new();
+                let mut searcher = SearcherBuilder::new()
+                    .before_context(context_lines)
+                    .after_context(context_lines)
+                    .build();
                 let path_string = path.to_string_lossy().to_string();
 
                 let content = self
@@ -136,31 +144,11 @@ impl<W: WalkerInfra + FileReaderInfra + FileInfoInfra> FsSearchService for Forge
                     .read(&path)
                     .await
                     .map(|v| String::from_utf8_lossy(&v).to_string())?;
-                let mut found_match = false;
                 searcher.search_slice(
                     regex,
                     content.as_bytes(),
-                    UTF8(|line_num, line| {
-                        found_match = true;
-                        matches.push(Match {
-                            path: path_string.clone(),
-                            result: Some(MatchResult::Found {
-                                line_number: line_num as usize,    /* grep_searcher already
-                                                                    * returns
-                                                                    * 1-based line numbers */
-                                line: line.trim_end().to_string(), // Remove trailing newline
-                            }),
-                        });
-
-                        Ok(true)
-                    }),
+                    MatchSink { path: &path_string, matches: &mut matches },
                 )?;
-
-                // If no matches found in content but we're looking for content,
-                // don't add this file to matches
-                if !found_match && helper.regex().is_some() {
-                    continue;
-                }
             }
         }
         if matches.is_empty() {
@@ -171,14 +159,66 @@ impl<W: WalkerInfra + FileReaderInfra + FileInfoInfra> FsSearchService for Forge
     }
 }
 
-impl<W: WalkerInfra + FileInfoInfra> ForgeFsSearch<W> {
-    async fn retrieve_file_paths(&self, dir: &Path) -> anyhow::Result<Vec<std::path::PathBuf>> {
+/// Collects the matching lines of a file along with the lines of context
+/// around them. Context lines are only reported next to a match, so files
+/// without matches don't add anything.
+struct MatchSink<'a> {
+    path: &'a str,
+    matches: &'a mut Vec<Match>,
+}
+
+impl MatchSink<'_> {
+    fn line(bytes: &[u8]) -> String {
+        // Remove trailing newline
+        String::from_utf8_lossy(bytes).trim_end().to_string()
+    }
+}
+
+impl Sink for MatchSink<'_> {
+    type Error = std::io::Error;
+
+    fn matched(&mut self, _: &Searcher, mat: &SinkMatch<'_>) -> Result<bool, Self::Error> {
+        self.matches.push(Match {
+            path: self.path.to_string(),
+            result: Some(MatchResult::Found {
+                // grep_searcher already returns 1-based line numbers
+                line_number: mat.line_number().unwrap_or_default() as usize,
+                line: Self::line(mat.bytes()),
+            }),
+        });
+        Ok(true)
+    }
+
+    fn context(&mut self, _: &Searcher, context: &SinkContext<'_>) -> Result<bool, Self::Error> {
+        self.matches.push(Match {
+            path: self.path.to_string(),
+            result: Some(MatchResult::Context {
+                line_number: context.line_number().unwrap_or_default() as usize,
+                line: Self::line(context.bytes()),
+            }),
+        });
+        Ok(true)
+    }
+}
+
+impl<W: EnvironmentInfra + WalkerInfra + FileInfoInfra> ForgeFsSearch<W> {
+    async fn retrieve_file_paths(
+        &self,
+        dir: &Path,
+        filter: &WalkerFilter,
+    ) -> anyhow::Result<Vec<std::path::PathBuf>> {
         if !self.infra.is_file(dir).await? {
+            let cwd = self.infra.get_environment().cwd;
+            let mut walker = Walker::unlimited().cwd(dir.to_path_buf());
+            // The filter scopes the workspace, its patterns being relative to it
+            if dir.starts_with(&cwd) {
+                walker = walker.filter(filter).root(cwd);
+            }
             // note: Paths needs mutable to avoid flaky tests.
             #[allow(unused_mut)]
             let mut paths = self
                 .infra
-                .walk(Walker::unlimited().cwd(dir.to_path_buf()))
+                .walk(walker)
                 .await
                 .with_context(|| format!("Failed to walk directory '{}'", dir.display()))?
                 .into_iter()
@@ -206,6 +246,7 @@ mod test {
     use tokio::fs;
 
     use super::*;
+    use crate::attachment::tests::MockEnvironmentInfra;
     use crate::utils::TempDir;
 
     // Mock WalkerInfra for testing
@@ -273,6 +314,16 @@ mod test {
         }
     }
 
+    impl EnvironmentInfra for MockInfra {
+        fn get_environment(&self) -> forge_app::domain::Environment {
+            MockEnvironmentInfra {}.get_environment()
+        }
+
+        fn get_env_var(&self, _key: &str) -> Option<String> {
+            None
+        }
+    }
+
     #[async_trait::async_trait]
     impl WalkerInfra for MockInfra {
         async fn walk(&self, config: Walker) -> anyhow::Result<Vec<WalkedFile>> {
@@ -288,9 +339,15 @@ mod test {
                         .to_string_lossy()
                         .to_string();
                     let file_name = path.file_name().map(|n| n.to_string_lossy().to_string());
-                    let size = entry.metadata().await?.len();
-
-                    files.push(WalkedFile { path: relative_path, file_name, size });
+                    let metadata = entry.metadata().await?;
+
+                    files.push(WalkedFile {
+                        path: relative_path,
+                        file_name,
+                        size: metadata.len(),
+                        modified: metadata.modified().ok(),
+                        generated: None,
+                    });
                 }
             }
             Ok(files)
@@ -315,6 +372,8 @@ mod test {
                 fixture.path().to_string_lossy().to_string(),
                 Some("test".to_string()),
                 None,
+                0,
+                &WalkerFilter::default(),
             )
             .await
             .unwrap();
@@ -330,6 +389,8 @@ mod test {
                 fixture.path().to_string_lossy().to_string(),
                 None,
                 Some("*.rs".to_string()),
+                0,
+                &WalkerFilter::default(),
             )
             .await
             .unwrap();
@@ -348,6 +409,8 @@ mod test {
                 fixture.path().to_string_lossy().to_string(),
                 Some("test".to_string()),
                 Some("*.rs".to_string()),
+                0,
+                &WalkerFilter::default(),
             )
             .await
             .unwrap();
@@ -367,6 +430,8 @@ mod test {
                 file_path.to_string_lossy().to_string(),
                 Some("hello".to_string()),
                 None,
+                0,
+                &WalkerFilter::default(),
             )
             .await
             .unwrap();
@@ -382,6 +447,8 @@ mod test {
                 fixture.path().to_string_lossy().to_string(),
                 Some("nonexistent".to_string()),
                 None,
+                0,
+                &WalkerFilter::default(),
             )
             .await
             .unwrap();
@@ -397,6 +464,8 @@ mod test {
                 fixture.path().to_string_lossy().to_string(),
                 None,
                 Some("*.cpp".to_string()),
+                0,
+                &WalkerFilter::default(),
             )
             .await
             .unwrap();
@@ -411,6 +480,8 @@ mod test {
                 "/nonexistent/path".to_string(),
                 Some("test".to_string()),
                 None,
+                0,
+                &WalkerFilter::default(),
             )
             .await;
 
@@ -420,7 +491,13 @@ mod test {
     #[tokio::test]
     async fn test_search_relative_path_error() {
         let result = ForgeFsSearch::new(Arc::new(MockInfra::default()))
-            .search("relative/path".to_string(), Some("test".to_string()), None)
+            .search(
+                "relative/path".to_string(),
+                Some("test".to_string()),
+                None,
+                0,
+                &WalkerFilter::default(),
+            )
             .await;
 
         assert!(result.is_err());
@@ -445,6 +522,8 @@ mod test {
                 fixture.path().to_string_lossy().to_string(),
                 Some("Hello".to_string()),
                 None,
+                0,
+                &WalkerFilter::default(),
             )
             .await
             .unwrap();
@@ -475,6 +554,8 @@ mod test {
                 fixture.path().to_string_lossy().to_string(),
                 None,
                 Some("*.exe".to_string()),
+                0,
+                &WalkerFilter::default(),
             )
             .await
             .unwrap();
@@ -510,6 +591,8 @@ mod test {
                 fixture.path().to_string_lossy().to_string(),
                 Some("Hello".to_string()),
                 Some("*.exe".to_string()),
+                0,
+                &WalkerFilter::default(),
             )
             .await
             .unwrap();
@@ -517,4 +600,42 @@ mod test {
         // Should be an empty file
         assert!(actual.is_none());
     }
+
+    #[tokio::test]
+    async fn test_search_with_context_lines() {
+        let fixture = TempDir::new().unwrap();
+        tokio::fs::write(
+            fixture.path().join("lines.txt"),
+            "one\ntwo\nthree\nfour\nfive\n",
+        )
+        .await
+        .unwrap();
+
+        let actual = ForgeFsSearch::new(Arc::new(MockInfra::default()))
+            .search(
+                fixture.path().to_string_lossy().to_string(),
+                Some("three".to_string()),
+                None,
+                1,
+                &WalkerFilter::default(),
+            )
+            .await
+            .unwrap()
+            .unwrap()
+            .matches
+            .into_iter()
+            .filter_map(|matched| match matched.result {
+                Some(MatchResult::Found { line_number, line }) => {
+                    Some(format!("{line_number}:{line}"))
+                }
+                Some(MatchResult::Context { line_number, line }) => {
+                    Some(format!("{line_number}-{line}"))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let expected = vec!["2-two", "3:three", "4-four"];
+        assert_eq!(actual, expected);
+    }
 }
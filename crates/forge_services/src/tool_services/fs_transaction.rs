@@ -0,0 +1,65 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use forge_app::FsTransactionService;
+
+use crate::{
+    FileDirectoryInfra, FileInfoInfra, FileReaderInfra, FileRemoverInfra, FileWriterInfra,
+};
+
+/// Reads and restores files on behalf of the transactions that group the file
+/// writes of a batch of tool calls.
+pub struct ForgeFsTransaction<F>(Arc<F>);
+
+impl<F> ForgeFsTransaction<F> {
+    pub fn new(infra: Arc<F>) -> Self {
+        Self(infra)
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: FileDirectoryInfra + FileInfoInfra + FileReaderInfra + FileRemoverInfra + FileWriterInfra>
+    FsTransactionService for ForgeFsTransaction<F>
+{
+    async fn current(&self, path: &Path) -> anyhow::Result<Option<Vec<u8>>> {
+        if !self.0.is_file(path).await? {
+            return Ok(None);
+        }
+        Ok(Some(self.0.read(path).await?))
+    }
+
+    async fn restore(&self, path: &Path, content: Option<Vec<u8>>) -> anyhow::Result<()> {
+        match content {
+            Some(content) => {
+                if let Some(parent) = path.parent() {
+                    self.0.create_dirs(parent).await?;
+                }
+                self.0.write(path, Bytes::from(content), false).await
+            }
+            None if self.0.exists(path).await? => self.0.remove(path).await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::attachment::tests::MockFileService;
+
+    #[tokio::test]
+    async fn test_restore_keeps_bytes_that_are_not_utf8() {
+        let fixture = ForgeFsTransaction::new(Arc::new(MockFileService::new()));
+        let path = Path::new("/test/latin1.txt");
+        let original = vec![b'c', b'a', b'f', 0xe9];
+        fixture.restore(path, Some(original.clone())).await.unwrap();
+
+        let actual = fixture.current(path).await.unwrap();
+
+        let expected = Some(original);
+        assert_eq!(actual, expected);
+    }
+}
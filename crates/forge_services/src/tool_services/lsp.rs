@@ -0,0 +1,657 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, anyhow, bail};
+use forge_app::{ApplyPatchOutput, LspService, PatchedFile, SymbolReference};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::oneshot;
+use url::Url;
+
+use crate::FileReaderInfra;
+use crate::utils::assert_absolute_path;
+
+/// Returned while the server is still loading the workspace, the request can
+/// be retried
+const CONTENT_MODIFIED: i64 = -32801;
+const SERVER_CANCELLED: i64 = -32802;
+const MAX_RETRIES: usize = 5;
+
+/// A language server along with the language id of the files it handles
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LanguageServer {
+    command: &'static str,
+    args: &'static [&'static str],
+    language_id: &'static str,
+}
+
+impl LanguageServer {
+    const fn new(
+        command: &'static str,
+        args: &'static [&'static str],
+        language_id: &'static str,
+    ) -> Self {
+        Self { command, args, language_id }
+    }
+
+    /// Picks the language server for a file from its extension
+    fn for_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?;
+        Some(match extension {
+            "rs" => Self::new("rust-analyzer", &[], "rust"),
+            "ts" => Self::new("typescript-language-server", &["--stdio"], "typescript"),
+            "tsx" => Self::new(
+                "typescript-language-server",
+                &["--stdio"],
+                "typescriptreact",
+            ),
+            "js" | "mjs" | "cjs" => {
+                Self::new("typescript-language-server", &["--stdio"], "javascript")
+            }
+            "jsx" => Self::new(
+                "typescript-language-server",
+                &["--stdio"],
+                "javascriptreact",
+            ),
+            "py" => Self::new("pyright-langserver", &["--stdio"], "python"),
+            "go" => Self::new("gopls", &[], "go"),
+            "c" | "h" => Self::new("clangd", &[], "c"),
+            "cc" | "cpp" | "cxx" | "hpp" | "hh" => Self::new("clangd", &[], "cpp"),
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct Position {
+    line: u32,
+    character: u32,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct Range {
+    start: Position,
+    end: Position,
+}
+
+#[derive(Debug, Deserialize)]
+struct Location {
+    uri: String,
+    range: Range,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TextEdit {
+    range: Range,
+    new_text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TextDocumentIdentifier {
+    uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TextDocumentEdit {
+    text_document: TextDocumentIdentifier,
+    edits: Vec<TextEdit>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WorkspaceEdit {
+    #[serde(default)]
+    changes: Option<HashMap<String, Vec<TextEdit>>>,
+    // Either text document edits or file operations, which are told apart by
+    // their `kind`
+    #[serde(default)]
+    document_changes: Option<Vec<Value>>,
+}
+
+type Pending = Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, Value>>>>>;
+
+/// Reads a message framed with a `Content-Length` header. Returns `None` once
+/// the stream is closed.
+async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> anyhow::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = Some(value.trim().parse::<usize>()?);
+        }
+    }
+
+    let length = length.context("Missing Content-Length header")?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+async fn write_message(
+    stdin: &tokio::sync::Mutex<ChildStdin>,
+    message: Value,
+) -> anyhow::Result<()> {
+    let body = serde_json::to_string(&message)?;
+    let mut stdin = stdin.lock().await;
+    stdin
+        .write_all(format!("Content-Length: {}\r\n\r\n{body}", body.len()).as_bytes())
+        .await?;
+    stdin.flush().await?;
+    Ok(())
+}
+
+/// A running language server, spoken to with JSON-RPC over its stdio
+struct LspClient {
+    stdin: Arc<tokio::sync::Mutex<ChildStdin>>,
+    pending: Pending,
+    next_id: AtomicI64,
+    exited: Arc<AtomicBool>,
+    opened: tokio::sync::Mutex<HashSet<String>>,
+    // Kills the server once the client is dropped
+    _child: Child,
+}
+
+impl LspClient {
+    async fn start(server: LanguageServer, root: &Path) -> anyhow::Result<Self> {
+        let mut child = Command::new(server.command)
+            .args(server.args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| {
+                format!(
+                    "Failed to start the language server '{}', make sure it is installed and on the PATH",
+                    server.command
+                )
+            })?;
+        let stdin = Arc::new(tokio::sync::Mutex::new(
+            child
+                .stdin
+                .take()
+                .context("Language server stdin unavailable")?,
+        ));
+        let stdout = child
+            .stdout
+            .take()
+            .context("Language server stdout unavailable")?;
+
+        let pending = Pending::default();
+        let exited = Arc::new(AtomicBool::new(false));
+        tokio::spawn(Self::dispatch(
+            BufReader::new(stdout),
+            stdin.clone(),
+            pending.clone(),
+            exited.clone(),
+        ));
+
+        let client = Self {
+            stdin,
+            pending,
+            next_id: AtomicI64::new(1),
+            exited,
+            opened: Default::default(),
+            _child: child,
+        };
+
+        let root_uri = file_uri(root)?;
+        client
+            .request(
+                "initialize",
+                json!({
+                    "processId": std::process::id(),
+                    "rootUri": root_uri,
+                    "workspaceFolders": [{ "uri": root_uri, "name": "workspace" }],
+                    "capabilities": {
+                        "workspace": {
+                            "workspaceEdit": { "documentChanges": true },
+                            "configuration": true,
+                            "workspaceFolders": true
+                        },
+                        "textDocument": {
+                            "references": {},
+                            "rename": { "prepareSupport": false }
+                        }
+                    }
+                }),
+            )
+            .await?;
+        client.notify("initialized", json!({})).await?;
+        Ok(client)
+    }
+
+    /// Routes the responses of the server to the pending requests and answers
+    /// the requests of the server, which expects replies to configuration and
+    /// progress requests before going on.
+    async fn dispatch<R: AsyncBufRead + Unpin>(
+        mut reader: R,
+        stdin: Arc<tokio::sync::Mutex<ChildStdin>>,
+        pending: Pending,
+        exited: Arc<AtomicBool>,
+    ) {
+        while let Ok(Some(message)) = read_message(&mut reader).await {
+            let id = message.get("id").cloned();
+            match (id, message.get("method").and_then(Value::as_str)) {
+                (Some(id), Some(method)) => {
+                    let result = match method {
+                        "workspace/configuration" => {
+                            let items = message["params"]["items"].as_array().map_or(0, Vec::len);
+                            Value::Array(vec![Value::Null; items])
+                        }
+                        _ => Value::Null,
+                    };
+                    let reply = json!({ "jsonrpc": "2.0", "id": id, "result": result });
+                    if write_message(&stdin, reply).await.is_err() {
+                        break;
+                    }
+                }
+                (Some(id), None) => {
+                    let sender = id.as_i64().and_then(|id| pending.lock().ok()?.remove(&id));
+                    if let Some(sender) = sender {
+                        let result = match message.get("error") {
+                            Some(error) => Err(error.clone()),
+                            None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                        };
+                        let _ = sender.send(result);
+                    }
+                }
+                // Notifications, eg: diagnostics or progress, aren't needed
+                _ => {}
+            }
+        }
+
+        exited.store(true, Ordering::SeqCst);
+        // Fails the requests still waiting for a response
+        if let Ok(mut pending) = pending.lock() {
+            pending.clear();
+        }
+    }
+
+    fn has_exited(&self) -> bool {
+        self.exited.load(Ordering::SeqCst)
+    }
+
+    async fn request(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        let mut attempt = 0;
+        loop {
+            let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+            let (sender, receiver) = oneshot::channel();
+            self.pending
+                .lock()
+                .map_err(|_| anyhow!("Language server state poisoned"))?
+                .insert(id, sender);
+            write_message(
+                &self.stdin,
+                json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }),
+            )
+            .await?;
+
+            let error = match receiver.await.context("The language server exited")? {
+                Ok(result) => return Ok(result),
+                Err(error) => error,
+            };
+            let code = error["code"].as_i64();
+            if matches!(code, Some(CONTENT_MODIFIED | SERVER_CANCELLED)) && attempt < MAX_RETRIES {
+                attempt += 1;
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+            bail!(
+                "The language server failed to handle {method}: {}",
+                error["message"].as_str().unwrap_or("unknown error")
+            );
+        }
+    }
+
+    async fn notify(&self, method: &str, params: Value) -> anyhow::Result<()> {
+        write_message(
+            &self.stdin,
+            json!({ "jsonrpc": "2.0", "method": method, "params": params }),
+        )
+        .await
+    }
+
+    /// Opens the document with its current content, reopening it when it was
+    /// already open so that the server doesn't work on an outdated version.
+    async fn open(&self, uri: &str, language_id: &str, text: &str) -> anyhow::Result<()> {
+        let mut opened = self.opened.lock().await;
+        if opened.contains(uri) {
+            self.notify(
+                "textDocument/didClose",
+                json!({ "textDocument": { "uri": uri } }),
+            )
+            .await?;
+        }
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": uri, "languageId": language_id, "version": 1, "text": text }
+            }),
+        )
+        .await?;
+        opened.insert(uri.to_string());
+        Ok(())
+    }
+}
+
+fn file_uri(path: &Path) -> anyhow::Result<String> {
+    Url::from_file_path(path)
+        .map(|url| url.to_string())
+        .map_err(|_| anyhow!("Invalid path: {}", path.display()))
+}
+
+fn uri_path(uri: &str) -> anyhow::Result<PathBuf> {
+    Url::parse(uri)?
+        .to_file_path()
+        .map_err(|_| anyhow!("Unsupported document URI: {uri}"))
+}
+
+/// Finds the column of the symbol on the line (1-based), in UTF-16 code units
+/// as expected by language servers. Only whole words match, so that `id`
+/// isn't found inside `width`.
+fn symbol_column(content: &str, line: u64, symbol: &str) -> anyhow::Result<u32> {
+    let text = line
+        .checked_sub(1)
+        .and_then(|index| content.lines().nth(index as usize))
+        .with_context(|| format!("Line {line} is out of range"))?;
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let column = text
+        .match_indices(symbol)
+        .map(|(index, _)| index)
+        .find(|&index| {
+            !text[..index].chars().next_back().is_some_and(is_word)
+                && !text[index + symbol.len()..]
+                    .chars()
+                    .next()
+                    .is_some_and(is_word)
+        })
+        .with_context(|| format!("Symbol '{symbol}' not found on line {line}"))?;
+    Ok(text[..column].encode_utf16().count() as u32)
+}
+
+/// Converts a position of the language server into a byte offset of the
+/// content. Positions past the end of a line or of the content are clamped.
+fn offset(content: &str, position: Position) -> usize {
+    let line_start: usize = content
+        .split_inclusive('\n')
+        .take(position.line as usize)
+        .map(str::len)
+        .sum();
+    let line = content[line_start..].split('\n').next().unwrap_or_default();
+
+    let mut units = 0;
+    for (index, c) in line.char_indices() {
+        if units >= position.character {
+            return line_start + index;
+        }
+        units += c.len_utf16() as u32;
+    }
+    line_start + line.len()
+}
+
+/// Applies the edits of a file. Edits never overlap, so they are applied from
+/// the end of the file to keep the offsets of the others valid.
+fn apply_edits(content: &str, edits: &[TextEdit]) -> String {
+    let mut ranges: Vec<_> = edits
+        .iter()
+        .map(|edit| {
+            (
+                offset(content, edit.range.start),
+                offset(content, edit.range.end),
+                edit.new_text.as_str(),
+            )
+        })
+        .collect();
+    ranges.sort_by_key(|(start, end, _)| std::cmp::Reverse((*start, *end)));
+
+    let mut result = content.to_string();
+    for (start, end, text) in ranges {
+        result.replace_range(start..end, text);
+    }
+    result
+}
+
+/// Gathers the edits of every file from a workspace edit, refusing the file
+/// operations some renames need, eg: renaming a module file.
+fn collect_edits(edit: WorkspaceEdit) -> anyhow::Result<BTreeMap<String, Vec<TextEdit>>> {
+    let mut edits: BTreeMap<String, Vec<TextEdit>> =
+        edit.changes.unwrap_or_default().into_iter().collect();
+    for change in edit.document_changes.unwrap_or_default() {
+        if let Some(kind) = change.get("kind").and_then(Value::as_str) {
+            bail!("The rename needs to {kind} files, which isn't supported");
+        }
+        let change: TextDocumentEdit = serde_json::from_value(change)?;
+        edits
+            .entry(change.text_document.uri)
+            .or_default()
+            .extend(change.edits);
+    }
+    Ok(edits)
+}
+
+/// Language server backed code navigation. A server is started on the first
+/// request for each language and workspace, and kept running for the
+/// following ones since loading a workspace is slow.
+pub struct ForgeLsp<F> {
+    infra: Arc<F>,
+    clients: tokio::sync::Mutex<HashMap<(PathBuf, &'static str), Arc<LspClient>>>,
+}
+
+impl<F> ForgeLsp<F> {
+    pub fn new(infra: Arc<F>) -> Self {
+        Self { infra, clients: Default::default() }
+    }
+
+    async fn client(&self, root: &Path, server: LanguageServer) -> anyhow::Result<Arc<LspClient>> {
+        let mut clients = self.clients.lock().await;
+        let key = (root.to_path_buf(), server.command);
+        if let Some(client) = clients.get(&key)
+            && !client.has_exited()
+        {
+            return Ok(client.clone());
+        }
+
+        let client = Arc::new(LspClient::start(server, root).await?);
+        clients.insert(key, client.clone());
+        Ok(client)
+    }
+}
+
+impl<F: FileReaderInfra> ForgeLsp<F> {
+    /// Opens the file in its language server and returns the parameters
+    /// identifying the position of the symbol.
+    async fn locate(
+        &self,
+        cwd: &Path,
+        path: &Path,
+        line: u64,
+        symbol: &str,
+    ) -> anyhow::Result<(Arc<LspClient>, Value)> {
+        assert_absolute_path(path)?;
+        let server = LanguageServer::for_path(path)
+            .with_context(|| format!("No language server is known for {}", path.display()))?;
+        let content = self.infra.read_utf8(path).await?;
+        let character = symbol_column(&content, line, symbol)?;
+
+        let client = self.client(cwd, server).await?;
+        let uri = file_uri(path)?;
+        client.open(&uri, server.language_id, &content).await?;
+
+        let params = json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": line - 1, "character": character }
+        });
+        Ok((client, params))
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: FileReaderInfra> LspService for ForgeLsp<F> {
+    async fn find_references(
+        &self,
+        cwd: PathBuf,
+        path: PathBuf,
+        line: u64,
+        symbol: String,
+    ) -> anyhow::Result<Vec<SymbolReference>> {
+        let (client, mut params) = self.locate(&cwd, &path, line, &symbol).await?;
+        params["context"] = json!({ "includeDeclaration": true });
+        let locations: Option<Vec<Location>> =
+            serde_json::from_value(client.request("textDocument/references", params).await?)?;
+
+        let mut contents = HashMap::new();
+        let mut references = Vec::new();
+        for location in locations.unwrap_or_default() {
+            let path = uri_path(&location.uri)?;
+            if !contents.contains_key(&path) {
+                let content = self.infra.read_utf8(&path).await?;
+                contents.insert(path.clone(), content);
+            }
+            let line = location.range.start.line as usize;
+            let text = contents[&path]
+                .lines()
+                .nth(line)
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+            references.push(SymbolReference { path, line: line as u64 + 1, text });
+        }
+        references.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+        references.dedup_by(|a, b| a.path == b.path && a.line == b.line);
+        Ok(references)
+    }
+
+    async fn rename_symbol(
+        &self,
+        cwd: PathBuf,
+        path: PathBuf,
+        line: u64,
+        symbol: String,
+        new_name: String,
+    ) -> anyhow::Result<ApplyPatchOutput> {
+        let (client, mut params) = self.locate(&cwd, &path, line, &symbol).await?;
+        params["newName"] = json!(new_name);
+        let edit: Option<WorkspaceEdit> =
+            serde_json::from_value(client.request("textDocument/rename", params).await?)?;
+        let edit = edit.with_context(|| format!("The language server can't rename '{symbol}'"))?;
+
+        let mut files = Vec::new();
+        for (uri, edits) in collect_edits(edit)? {
+            let path = uri_path(&uri)?;
+            let before = self.infra.read_utf8(&path).await?;
+            let after = apply_edits(&before, &edits);
+            if before != after {
+                files.push(PatchedFile {
+                    path,
+                    before: Some(before),
+                    after: Some(after),
+                    warning: None,
+                });
+            }
+        }
+        if files.is_empty() {
+            bail!("Renaming '{symbol}' to '{new_name}' doesn't change any file");
+        }
+        Ok(ApplyPatchOutput { files })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn edit(start: (u32, u32), end: (u32, u32), new_text: &str) -> TextEdit {
+        TextEdit {
+            range: Range {
+                start: Position { line: start.0, character: start.1 },
+                end: Position { line: end.0, character: end.1 },
+            },
+            new_text: new_text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_symbol_column_matches_whole_words() {
+        let fixture = "fn main() {\n    let width = id(id_map, id);\n}";
+
+        let actual = symbol_column(fixture, 2, "id").unwrap();
+
+        let expected = 16;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_symbol_column_counts_utf16_units() {
+        let fixture = "let é𝄞 = value;";
+
+        let actual = symbol_column(fixture, 1, "value").unwrap();
+
+        let expected = 10;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_symbol_column_missing_symbol() {
+        let fixture = "let width = 1;";
+
+        let actual = symbol_column(fixture, 1, "id").is_err();
+
+        assert!(actual);
+    }
+
+    #[test]
+    fn test_apply_edits() {
+        let fixture = "fn old() {}\n\nfn main() {\n    old();\n}\n";
+        let edits = vec![edit((3, 4), (3, 7), "new"), edit((0, 3), (0, 6), "new")];
+
+        let actual = apply_edits(fixture, &edits);
+
+        let expected = "fn new() {}\n\nfn main() {\n    new();\n}\n";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_collect_edits_refuses_file_operations() {
+        let fixture: WorkspaceEdit = serde_json::from_value(json!({
+            "documentChanges": [
+                { "kind": "rename", "oldUri": "file:///a.rs", "newUri": "file:///b.rs" }
+            ]
+        }))
+        .unwrap();
+
+        let actual = collect_edits(fixture).is_err();
+
+        assert!(actual);
+    }
+
+    #[tokio::test]
+    async fn test_read_message() {
+        let body = r#"{"jsonrpc":"2.0","id":1,"result":null}"#;
+        let fixture = format!("Content-Length: {}\r\n\r\n{body}", body.len());
+        let mut reader = fixture.as_bytes();
+
+        let actual = read_message(&mut reader).await.unwrap();
+
+        let expected = Some(json!({ "jsonrpc": "2.0", "id": 1, "result": null }));
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,22 +1,40 @@
+mod browser;
 mod fetch;
 mod followup;
+mod fs_apply_patch;
+mod fs_ast_edit;
 mod fs_create;
 mod fs_patch;
+mod fs_preview;
 mod fs_read;
 mod fs_remove;
 mod fs_search;
+mod fs_transaction;
 mod fs_undo;
+mod lsp;
 mod plan_create;
+mod python;
 mod shell;
+mod symbol_index;
 mod syn;
+mod workspace_stats;
 
+pub use browser::*;
 pub use fetch::*;
 pub use followup::*;
+pub use fs_apply_patch::*;
+pub use fs_ast_edit::*;
 pub use fs_create::*;
 pub use fs_patch::*;
+pub use fs_preview::*;
 pub use fs_read::*;
 pub use fs_remove::*;
 pub use fs_search::*;
+pub use fs_transaction::*;
 pub use fs_undo::*;
+pub use lsp::*;
 pub use plan_create::*;
+pub use python::*;
 pub use shell::*;
+pub use symbol_index::*;
+pub use workspace_stats::*;
@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex as SyncMutex, Once, Weak};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use forge_app::domain::ConversationId;
+use forge_app::{PythonOutput, PythonService};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+
+/// Script run by the interpreter to execute the code it is sent
+const KERNEL: &str = include_str!("python_kernel.py");
+
+/// Time given to the interpreter to report that the code was interrupted
+/// before it is killed
+const GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Interpreters not used for this long are stopped, freeing their memory
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// How often the interpreters are checked for being idle or exited
+const REAP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Output written directly to the file descriptors of the interpreter, eg: by
+/// a subprocess, kept until the next response at most
+const MAX_FD_OUTPUT: usize = 64 * 1024;
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    stdout: String,
+    stderr: String,
+    result: Option<String>,
+    error: Option<String>,
+}
+
+impl From<Response> for PythonOutput {
+    fn from(response: Response) -> Self {
+        Self {
+            stdout: response.stdout,
+            stderr: response.stderr,
+            result: response.result,
+            error: response.error,
+        }
+    }
+}
+
+/// A running Python interpreter, which is sent code to run as JSON lines on
+/// its stdin and answers with JSON lines on its stdout
+struct Kernel {
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    // Output the interpreter writes to its stderr outside of the captured
+    // output of the code, such as the one of subprocesses
+    fd_output: Arc<SyncMutex<String>>,
+    // Set while a request is waiting for its response, so that an interpreter
+    // whose request was abandoned isn't reused
+    busy: bool,
+    last_used: Instant,
+    // Kills the interpreter once the kernel is dropped
+    child: Child,
+}
+
+impl Kernel {
+    async fn start(cwd: &Path) -> anyhow::Result<Self> {
+        let python = if cfg!(target_os = "windows") {
+            "python"
+        } else {
+            "python3"
+        };
+        let mut child = Command::new(python)
+            .args(["-u", "-c", KERNEL])
+            .current_dir(cwd)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| {
+                format!("Failed to start '{python}', make sure Python is installed and on the PATH")
+            })?;
+        let stdin = child.stdin.take().context("Python stdin unavailable")?;
+        let stdout = child.stdout.take().context("Python stdout unavailable")?;
+        let stderr = child.stderr.take().context("Python stderr unavailable")?;
+
+        let fd_output = Arc::new(SyncMutex::new(String::new()));
+        tokio::spawn(collect(stderr, fd_output.clone()));
+
+        Ok(Self {
+            stdin,
+            stdout: BufReader::new(stdout).lines(),
+            fd_output,
+            busy: false,
+            last_used: Instant::now(),
+            child,
+        })
+    }
+
+    /// Whether the interpreter exited or wasn't used for a while
+    fn is_stale(&mut self) -> bool {
+        self.last_used.elapsed() > IDLE_TIMEOUT || !matches!(self.child.try_wait(), Ok(None))
+    }
+
+    async fn run(&mut self, code: &str, timeout: Duration) -> anyhow::Result<Response> {
+        self.busy = true;
+        let request = json!({ "code": code, "timeout": timeout.as_secs_f64() });
+        self.stdin
+            .write_all(format!("{request}\n").as_bytes())
+            .await?;
+        self.stdin.flush().await?;
+
+        let response = self
+            .stdout
+            .next_line()
+            .await?
+            .context("The Python interpreter exited, its state is lost")?;
+        self.busy = false;
+        self.last_used = Instant::now();
+        let mut response: Response = serde_json::from_str(&response)?;
+        response
+            .stderr
+            .push_str(&std::mem::take(&mut *self.fd_output.lock().unwrap()));
+        Ok(response)
+    }
+}
+
+/// Reads what the interpreter writes to its stderr into the output, dropping
+/// what goes over [`MAX_FD_OUTPUT`]
+async fn collect(mut stderr: ChildStderr, output: Arc<SyncMutex<String>>) {
+    let mut buffer = [0; 1024];
+    while let Ok(n) = stderr.read(&mut buffer).await
+        && n > 0
+    {
+        let mut output = output.lock().unwrap();
+        if output.len() < MAX_FD_OUTPUT {
+            output.push_str(&String::from_utf8_lossy(&buffer[..n]));
+        }
+    }
+}
+
+/// The interpreter of a conversation, if it was started
+type KernelSlot = Arc<Mutex<Option<Kernel>>>;
+
+type Kernels = Mutex<HashMap<Option<ConversationId>, KernelSlot>>;
+
+/// Runs Python code in an interpreter per conversation. The interpreter is
+/// started on the first call and kept running so that the following calls
+/// can use what the earlier ones defined, until it exits or is idle for
+/// [`IDLE_TIMEOUT`].
+#[derive(Default)]
+pub struct ForgePython {
+    kernels: Arc<Kernels>,
+    reaper: Once,
+}
+
+impl ForgePython {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops the interpreters that are idle and forgets the ones that exited,
+    /// leaving alone the ones a call is using
+    async fn evict(kernels: &Kernels) {
+        let mut kernels = kernels.lock().await;
+        kernels.retain(|_, slot| {
+            if Arc::strong_count(slot) > 1 {
+                return true;
+            }
+            match slot.try_lock() {
+                Ok(mut kernel) => kernel.as_mut().is_some_and(|kernel| !kernel.is_stale()),
+                Err(_) => true,
+            }
+        });
+    }
+
+    /// Evicts the interpreters periodically, for as long as the service is
+    /// alive
+    fn start_reaper(&self) {
+        let kernels: Weak<Kernels> = Arc::downgrade(&self.kernels);
+        self.reaper.call_once(|| {
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(REAP_INTERVAL).await;
+                    let Some(kernels) = kernels.upgrade() else {
+                        break;
+                    };
+                    Self::evict(&kernels).await;
+                }
+            });
+        });
+    }
+}
+
+#[async_trait::async_trait]
+impl PythonService for ForgePython {
+    async fn run_python(
+        &self,
+        conversation_id: Option<ConversationId>,
+        code: String,
+        cwd: PathBuf,
+        timeout: Duration,
+        restart: bool,
+    ) -> anyhow::Result<PythonOutput> {
+        self.start_reaper();
+        let kernel = self
+            .kernels
+            .lock()
+            .await
+            .entry(conversation_id)
+            .or_default()
+            .clone();
+        let mut kernel = kernel.lock().await;
+
+        if restart || kernel.as_ref().is_some_and(|kernel| kernel.busy) {
+            *kernel = None;
+        }
+        let running = match kernel.as_mut() {
+            Some(running) => running,
+            None => kernel.insert(Kernel::start(&cwd).await?),
+        };
+
+        // The interpreter interrupts the code itself once the timeout elapses,
+        // it is only killed when that didn't work
+        let response =
+            tokio::time::timeout(timeout + GRACE_PERIOD, running.run(&code, timeout)).await;
+        match response {
+            Ok(Ok(response)) => Ok(response.into()),
+            Ok(Err(error)) => {
+                *kernel = None;
+                Err(error)
+            }
+            Err(_) => {
+                *kernel = None;
+                anyhow::bail!(
+                    "The code couldn't be interrupted after {} seconds, the Python interpreter was restarted and its state is lost",
+                    timeout.as_secs()
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    async fn run(fixture: &ForgePython, code: &str) -> PythonOutput {
+        fixture
+            .run_python(
+                None,
+                code.to_string(),
+                std::env::temp_dir(),
+                Duration::from_secs(5),
+                false,
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_state_is_kept_between_calls() {
+        let fixture = ForgePython::new();
+        run(&fixture, "x = 40").await;
+
+        let actual = run(&fixture, "print('hello')\nx + 2").await;
+
+        let expected = PythonOutput {
+            stdout: "hello\n".to_string(),
+            result: Some("42".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_error_is_reported_with_traceback() {
+        let fixture = ForgePython::new();
+
+        let actual = run(&fixture, "raise ValueError('boom')")
+            .await
+            .error
+            .unwrap();
+
+        let expected = "Traceback (most recent call last):\n  File \"<cell 1>\", line 1, in <module>\n    raise ValueError('boom')\nValueError: boom\n";
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_subprocess_output_is_kept() {
+        let fixture = ForgePython::new();
+
+        let actual = run(&fixture, "import os\nos.system('echo from-subprocess')")
+            .await
+            .stderr;
+
+        // The output may not have been read yet when the response arrives
+        let actual = actual + &run(&fixture, "pass").await.stderr;
+        assert_eq!(actual, "from-subprocess\n");
+    }
+
+    #[tokio::test]
+    async fn test_exited_interpreter_is_evicted() {
+        let fixture = ForgePython::new();
+        let _ = fixture
+            .run_python(
+                None,
+                "import os\nos._exit(0)".to_string(),
+                std::env::temp_dir(),
+                Duration::from_secs(5),
+                false,
+            )
+            .await;
+
+        ForgePython::evict(&fixture.kernels).await;
+
+        let actual = fixture.kernels.lock().await.len();
+        assert_eq!(actual, 0);
+    }
+}
@@ -0,0 +1,86 @@
+# Runs the code sent by forge in a namespace that is kept between requests.
+# Each request is a JSON line on stdin and is answered with a JSON line on the
+# original stdout. The output of the code is captured, and anything written
+# directly to the stdout file descriptor, eg: by a subprocess, goes to stderr
+# so that it can't be mistaken for a response.
+import ast
+import contextlib
+import io
+import itertools
+import json
+import linecache
+import os
+import signal
+import sys
+import traceback
+
+responses = os.fdopen(os.dup(1), "w")
+os.dup2(2, 1)
+requests = sys.stdin
+sys.stdin = io.StringIO()
+
+namespace = {"__name__": "__main__"}
+cells = itertools.count(1)
+
+
+class Timeout(BaseException):
+    pass
+
+
+def on_alarm(signum, frame):
+    raise Timeout()
+
+
+def run(code):
+    # Each cell gets its own name, registered so that tracebacks show its lines
+    name = "<cell %d>" % next(cells)
+    linecache.cache[name] = (len(code), None, code.splitlines(True), name)
+    # Like a notebook cell, the value of a trailing expression is returned
+    tree = ast.parse(code, name, "exec")
+    last = None
+    if tree.body and isinstance(tree.body[-1], ast.Expr):
+        last = ast.Expression(tree.body.pop().value)
+    exec(compile(tree, name, "exec"), namespace)
+    if last is not None:
+        value = eval(compile(last, name, "eval"), namespace)
+        if value is not None:
+            namespace["_"] = value
+            return repr(value)
+    return None
+
+
+def format_error(error):
+    # Skips the frames of this driver and of the parser, so that the traceback
+    # starts in the code
+    tb = error.__traceback__
+    while tb is not None and not tb.tb_frame.f_code.co_filename.startswith("<cell"):
+        tb = tb.tb_next
+    return "".join(traceback.format_exception(type(error), error, tb))
+
+
+for line in requests:
+    request = json.loads(line)
+    stdout, stderr = io.StringIO(), io.StringIO()
+    response = {"result": None, "error": None}
+    timer = hasattr(signal, "setitimer")
+    try:
+        try:
+            if timer:
+                signal.signal(signal.SIGALRM, on_alarm)
+                signal.setitimer(signal.ITIMER_REAL, request["timeout"])
+            with contextlib.redirect_stdout(stdout), contextlib.redirect_stderr(stderr):
+                response["result"] = run(request["code"])
+        finally:
+            if timer:
+                signal.setitimer(signal.ITIMER_REAL, 0)
+    except Timeout:
+        response["error"] = (
+            "Interrupted after %g seconds, everything defined before that is kept"
+            % request["timeout"]
+        )
+    except BaseException as error:
+        response["error"] = format_error(error)
+    response["stdout"] = stdout.getvalue()
+    response["stderr"] = stderr.getvalue()
+    responses.write(json.dumps(response) + "\n")
+    responses.flush()
@@ -2,7 +2,7 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use anyhow::bail;
-use forge_app::domain::Environment;
+use forge_app::domain::{BackgroundProcess, CommandSettings, Environment, ProcessLogs, ShellKind};
 use forge_app::{ShellOutput, ShellService};
 use strip_ansi_escapes::strip;
 
@@ -46,17 +46,50 @@ impl<I: CommandInfra + EnvironmentInfra> ShellService for ForgeShell<I> {
         &self,
         command: String,
         cwd: PathBuf,
+        shell: Option<ShellKind>,
         keep_ansi: bool,
+        stdin: Option<String>,
+        settings: &CommandSettings,
     ) -> anyhow::Result<ShellOutput> {
         Self::validate_command(&command)?;
 
-        let mut output = self.infra.execute_command(command, cwd).await?;
+        let mut output = self
+            .infra
+            .execute_command(command, cwd, shell, stdin, settings)
+            .await?;
 
         if !keep_ansi {
             output.stdout = strip_ansi(output.stdout);
             output.stderr = strip_ansi(output.stderr);
         }
 
-        Ok(ShellOutput { output, shell: self.env.shell.clone() })
+        let shell = shell.map_or_else(|| self.env.shell.clone(), |shell| shell.to_string());
+        Ok(ShellOutput { output, shell })
+    }
+
+    async fn start_process(
+        &self,
+        command: String,
+        cwd: PathBuf,
+        settings: &CommandSettings,
+    ) -> anyhow::Result<BackgroundProcess> {
+        Self::validate_command(&command)?;
+        self.infra.start_process(command, cwd, settings).await
+    }
+
+    async fn stop_process(&self, id: u32) -> anyhow::Result<BackgroundProcess> {
+        self.infra.stop_process(id).await
+    }
+
+    async fn process_logs(&self, id: u32, lines: usize) -> anyhow::Result<ProcessLogs> {
+        let mut logs = self.infra.process_logs(id, lines).await?;
+        // Dev servers color their output, as the commands are run with colors forced
+        logs.stdout = strip_ansi(logs.stdout);
+        logs.stderr = strip_ansi(logs.stderr);
+        Ok(logs)
+    }
+
+    async fn list_processes(&self) -> anyhow::Result<Vec<BackgroundProcess>> {
+        self.infra.list_processes().await
     }
 }
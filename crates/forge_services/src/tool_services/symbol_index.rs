@@ -0,0 +1,514 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use forge_app::domain::{GeneratedFiles, RepoMapFile, WalkerFilter};
+use forge_app::{Symbol, SymbolIndexService, SymbolMatch, WalkedFile, Walker};
+use futures::StreamExt;
+
+use crate::utils::assert_absolute_path;
+use crate::{EnvironmentInfra, FileReaderInfra, WalkerInfra, tool_services};
+
+/// Files larger than this are left out of the index, as they're mostly
+/// generated code or data
+const MAX_FILE_SIZE: u64 = 512 * 1024;
+
+/// Files indexed at most in a workspace
+const MAX_FILES: usize = 10000;
+
+/// Files read and outlined at once
+const OUTLINE_CONCURRENCY: usize = 16;
+
+/// Identifiers shorter than this are too common to tell which definition a
+/// file refers to
+const MIN_IDENTIFIER_LENGTH: usize = 3;
+
+/// Outlines of the files of the workspace, by their path relative to it
+type Index = BTreeMap<String, Arc<IndexedFile>>;
+
+#[derive(Debug, Default)]
+struct IndexedFile {
+    /// Size and time of the last modification of the file when it was
+    /// outlined
+    size: u64,
+    modified: Option<SystemTime>,
+    symbols: Vec<Symbol>,
+    /// Identifiers mentioned in the file, to find the files it refers to
+    identifiers: HashSet<String>,
+    /// Whether the file is generated, its definitions not being the ones
+    /// looked for
+    generated: bool,
+}
+
+/// Outlines the source files of the workspace with tree-sitter as they're
+/// walked and keeps the outlines in memory. Only the files whose size or
+/// modification time changed since the last lookup are read and outlined
+/// again.
+pub struct ForgeSymbolIndex<F> {
+    infra: Arc<F>,
+    // Only held to take or replace the index, so that lookups don't wait for
+    // one another
+    index: Arc<Mutex<Arc<Index>>>,
+    /// Whether the workspace was outlined once
+    ready: Arc<AtomicBool>,
+    /// Whether the index is being updated in the background
+    updating: Arc<AtomicBool>,
+}
+
+impl<F> Clone for ForgeSymbolIndex<F> {
+    fn clone(&self) -> Self {
+        Self {
+            infra: self.infra.clone(),
+            index: self.index.clone(),
+            ready: self.ready.clone(),
+            updating: self.updating.clone(),
+        }
+    }
+}
+
+impl<F> ForgeSymbolIndex<F> {
+    pub fn new(infra: Arc<F>) -> Self {
+        Self {
+            infra,
+            index: Default::default(),
+            ready: Default::default(),
+            updating: Default::default(),
+        }
+    }
+}
+
+impl<F: EnvironmentInfra + WalkerInfra + FileReaderInfra> ForgeSymbolIndex<F> {
+    /// Outlines the walked files that were added or changed since the index
+    /// was updated, drops the removed ones and returns the updated index
+    async fn update(
+        &self,
+        cwd: &Path,
+        filter: &WalkerFilter,
+        generated: &GeneratedFiles,
+    ) -> anyhow::Result<Arc<Index>> {
+        let walker = Walker::unlimited()
+            .cwd(cwd.to_path_buf())
+            .max_file_size(MAX_FILE_SIZE)
+            .max_files(MAX_FILES)
+            .skip_binary(true)
+            .filter(filter)
+            .generated(generated.clone());
+        let walked = self.infra.walk(walker).await?;
+
+        let previous = self.index.lock().unwrap().clone();
+        let mut files = Index::new();
+        let mut changed = Vec::new();
+        for file in walked {
+            if file.is_dir()
+                || file.generated.is_some()
+                || !tool_services::syn::is_outlined(&file.path)
+            {
+                continue;
+            }
+            match previous.get(&file.path) {
+                Some(indexed)
+                    if file.modified.is_some()
+                        && indexed.modified == file.modified
+                        && indexed.size == file.size =>
+                {
+                    files.insert(file.path, indexed.clone());
+                }
+                _ => changed.push(file),
+            }
+        }
+
+        let mut outlined = futures::stream::iter(changed)
+            .map(|file| self.outline(cwd, file, generated))
+            .buffer_unordered(OUTLINE_CONCURRENCY);
+        while let Some(outlined) = outlined.next().await {
+            if let Some((path, indexed)) = outlined {
+                files.insert(path, Arc::new(indexed));
+            }
+        }
+
+        // A lookup running at the same time may replace it with an index as
+        // recent
+        let files = Arc::new(files);
+        *self.index.lock().unwrap() = files.clone();
+        self.ready.store(true, Ordering::SeqCst);
+        Ok(files)
+    }
+
+    /// Reads and outlines a walked file, `None` when it can't be read or
+    /// parsed
+    async fn outline(
+        &self,
+        cwd: &Path,
+        file: WalkedFile,
+        generated: &GeneratedFiles,
+    ) -> Option<(String, IndexedFile)> {
+        let content = self.infra.read_utf8(&cwd.join(&file.path)).await.ok()?;
+        let mut indexed = IndexedFile {
+            size: file.size,
+            modified: file.modified,
+            ..Default::default()
+        };
+        if generated.detect(Path::new(&file.path), &content).is_some() {
+            indexed.generated = true;
+        } else {
+            indexed.symbols = tool_services::syn::outline(&file.path, &content).ok()?;
+            indexed.identifiers = identifiers(&content);
+        }
+        Some((file.path, indexed))
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: EnvironmentInfra + WalkerInfra + FileReaderInfra + 'static> SymbolIndexService
+    for ForgeSymbolIndex<F>
+{
+    async fn outline_file(&self, path: PathBuf) -> anyhow::Result<Vec<Symbol>> {
+        assert_absolute_path(&path)?;
+        let content = self.infra.read_utf8(&path).await?;
+        Ok(tool_services::syn::outline(&path, &content)?)
+    }
+
+    async fn find_symbol(
+        &self,
+        symbol: String,
+        filter: &WalkerFilter,
+        generated: &GeneratedFiles,
+    ) -> anyhow::Result<Vec<SymbolMatch>> {
+        let names = symbol_names(&symbol);
+        if names.is_empty() {
+            anyhow::bail!("The symbol to find can't be empty");
+        }
+
+        let cwd = self.infra.get_environment().cwd;
+        let index = self.update(&cwd, filter, generated).await?;
+
+        Ok(index
+            .iter()
+            .flat_map(|(path, file)| {
+                file.symbols
+                    .iter()
+                    .filter(|definition| symbol_names(&definition.path).ends_with(&names))
+                    .map(|definition| SymbolMatch {
+                        path: cwd.join(path),
+                        symbol: definition.clone(),
+                    })
+            })
+            .collect())
+    }
+
+    async fn map_files(
+        &self,
+        filter: &WalkerFilter,
+        generated: &GeneratedFiles,
+    ) -> anyhow::Result<Vec<RepoMapFile>> {
+        let cwd = self.infra.get_environment().cwd;
+        let index = self.update(&cwd, filter, generated).await?;
+        Ok(repo_map_files(&index))
+    }
+
+    async fn try_map_files(
+        &self,
+        filter: &WalkerFilter,
+        generated: &GeneratedFiles,
+    ) -> Option<Vec<RepoMapFile>> {
+        if !self.updating.swap(true, Ordering::SeqCst) {
+            let index = self.clone();
+            let cwd = self.infra.get_environment().cwd;
+            let filter = filter.clone();
+            let generated = generated.clone();
+            tokio::spawn(async move {
+                if let Err(error) = index.update(&cwd, &filter, &generated).await {
+                    tracing::warn!(error = ?error, "Failed to index the workspace");
+                }
+                index.updating.store(false, Ordering::SeqCst);
+            });
+        }
+
+        if !self.ready.load(Ordering::SeqCst) {
+            return None;
+        }
+        let index = self.index.lock().unwrap().clone();
+        Some(repo_map_files(&index))
+    }
+}
+
+/// Files of the index for the repo map, leaving out the generated ones
+fn repo_map_files(index: &Index) -> Vec<RepoMapFile> {
+    let files = || index.iter().filter(|(_, file)| !file.generated);
+
+    // Files defining each top level name
+    let mut definitions: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (path, file) in files() {
+        for symbol in file.symbols.iter().filter(|symbol| symbol.depth() == 0) {
+            definitions.entry(&symbol.path).or_default().push(path);
+        }
+    }
+
+    // Other files mentioning the definitions of each file
+    let mut referrers: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for (path, file) in files() {
+        for identifier in &file.identifiers {
+            for &defined_in in definitions.get(identifier.as_str()).into_iter().flatten() {
+                if defined_in != path.as_str() {
+                    referrers
+                        .entry(defined_in)
+                        .or_default()
+                        .insert(path.as_str());
+                }
+            }
+        }
+    }
+
+    files()
+        .map(|(path, file)| RepoMapFile {
+            path: path.clone(),
+            symbols: file
+                .symbols
+                .iter()
+                .map(|symbol| format!("{}{}", "  ".repeat(symbol.depth()), symbol.signature))
+                .collect(),
+            references: referrers.get(path.as_str()).map_or(0, HashSet::len),
+            modified: file.modified,
+        })
+        .collect()
+}
+
+/// Identifiers mentioned in a source file, leaving out the shortest ones
+fn identifiers(content: &str) -> HashSet<String> {
+    content
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| word.len() >= MIN_IDENTIFIER_LENGTH)
+        .filter(|word| !word.starts_with(|c: char| c.is_ascii_digit()))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Names making up a symbol path, separated by `::` or `.`
+fn symbol_names(symbol: &str) -> Vec<&str> {
+    symbol
+        .split("::")
+        .flat_map(|part| part.split('.'))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::time::Duration;
+
+    use forge_app::domain::Environment;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::attachment::tests::MockEnvironmentInfra;
+
+    /// Workspace of files by path, with their content and modification time,
+    /// recording the files read
+    #[derive(Default)]
+    struct Fixture {
+        files: Mutex<BTreeMap<String, (String, SystemTime)>>,
+        reads: Mutex<Vec<String>>,
+    }
+
+    impl Fixture {
+        fn write(&self, path: &str, content: &str, modified: u64) {
+            let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(modified);
+            self.files
+                .lock()
+                .unwrap()
+                .insert(path.to_string(), (content.to_string(), modified));
+        }
+
+        fn reads(&self) -> Vec<String> {
+            std::mem::take(&mut *self.reads.lock().unwrap())
+        }
+    }
+
+    impl EnvironmentInfra for Fixture {
+        fn get_environment(&self) -> Environment {
+            MockEnvironmentInfra {}.get_environment()
+        }
+
+        fn get_env_var(&self, _key: &str) -> Option<String> {
+            None
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl WalkerInfra for Fixture {
+        async fn walk(&self, _config: Walker) -> anyhow::Result<Vec<WalkedFile>> {
+            Ok(self
+                .files
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(path, (content, modified))| WalkedFile {
+                    path: path.clone(),
+                    file_name: None,
+                    size: content.len() as u64,
+                    modified: Some(*modified),
+                    generated: None,
+                })
+                .collect())
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl FileReaderInfra for Fixture {
+        async fn read_utf8(&self, path: &Path) -> anyhow::Result<String> {
+            let path = path.strip_prefix("/test")?.to_string_lossy().to_string();
+            self.reads.lock().unwrap().push(path.clone());
+            self.files
+                .lock()
+                .unwrap()
+                .get(&path)
+                .map(|(content, _)| content.clone())
+                .ok_or_else(|| anyhow::anyhow!("No file {path}"))
+        }
+
+        async fn read(&self, _path: &Path) -> anyhow::Result<Vec<u8>> {
+            unimplemented!()
+        }
+
+        async fn range_read_utf8(
+            &self,
+            _path: &Path,
+            _start_line: u64,
+            _end_line: u64,
+        ) -> anyhow::Result<(String, forge_fs::FileInfo)> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_symbol() {
+        let infra = Arc::new(Fixture::default());
+        infra.write(
+            "src/parser.rs",
+            "struct Parser;\nimpl Parser { fn parse() {} }",
+            1,
+        );
+        infra.write("src/lexer.rs", "fn parse() {}", 1);
+        infra.write("README.md", "# Parser::parse", 1);
+        let fixture = ForgeSymbolIndex::new(infra.clone());
+
+        let actual = fixture
+            .find_symbol(
+                "Parser::parse".to_string(),
+                &WalkerFilter::default(),
+                &GeneratedFiles::default(),
+            )
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|found| (found.path, found.symbol.path))
+            .collect::<Vec<_>>();
+
+        let expected = vec![(
+            PathBuf::from("/test/src/parser.rs"),
+            "Parser::parse".to_string(),
+        )];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_only_changed_files_are_outlined_again() {
+        let infra = Arc::new(Fixture::default());
+        infra.write("src/parser.rs", "struct Parser;", 1);
+        infra.write("src/lexer.rs", "struct Lexer;", 1);
+        infra.write("src/token.rs", "struct Token;", 1);
+        infra.write("README.md", "# Parser", 1);
+        let fixture = ForgeSymbolIndex::new(infra.clone());
+        fixture
+            .map_files(&WalkerFilter::default(), &GeneratedFiles::default())
+            .await
+            .unwrap();
+        infra.reads();
+
+        infra.write("src/lexer.rs", "struct Lexer;\nstruct Cursor;", 2);
+        infra.files.lock().unwrap().remove("src/token.rs");
+        let files = fixture
+            .map_files(&WalkerFilter::default(), &GeneratedFiles::default())
+            .await
+            .unwrap();
+
+        let actual = (
+            infra.reads(),
+            files
+                .into_iter()
+                .map(|file| (file.path, file.symbols))
+                .collect::<Vec<_>>(),
+        );
+        let expected = (
+            vec!["src/lexer.rs".to_string()],
+            vec![
+                (
+                    "src/lexer.rs".to_string(),
+                    vec!["struct Lexer;".to_string(), "struct Cursor;".to_string()],
+                ),
+                (
+                    "src/parser.rs".to_string(),
+                    vec!["struct Parser;".to_string()],
+                ),
+            ],
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_try_map_files_outlines_the_workspace_in_the_background() {
+        let infra = Arc::new(Fixture::default());
+        infra.write("src/parser.rs", "struct Parser;", 1);
+        let fixture = ForgeSymbolIndex::new(infra.clone());
+        let filter = WalkerFilter::default();
+        let generated = GeneratedFiles::default();
+
+        let before = fixture.try_map_files(&filter, &generated).await;
+        let after = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Some(files) = fixture.try_map_files(&filter, &generated).await {
+                    break files;
+                }
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let actual = (
+            before.is_none(),
+            after.into_iter().map(|file| file.path).collect::<Vec<_>>(),
+        );
+        let expected = (true, vec!["src/parser.rs".to_string()]);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_symbol_names() {
+        let actual = ["Parser::parse", "Shape.area", " parse ", ""].map(symbol_names);
+
+        let expected = [
+            vec!["Parser", "parse"],
+            vec!["Shape", "area"],
+            vec!["parse"],
+            vec![],
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_identifiers() {
+        let fixture = "let parser = Parser::new(&config.max_depth, 42, 0x1f);";
+
+        let actual: BTreeSet<_> = identifiers(fixture).into_iter().collect();
+
+        let expected = ["Parser", "config", "let", "max_depth", "new", "parser"]
+            .map(str::to_string)
+            .into();
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,614 @@
+use std::ops::Range;
+use std::path::Path;
+
+use forge_app::domain::AstOperation;
+use thiserror::Error;
+use tree_sitter::{LanguageError, Node, Parser, Tree};
+
+use super::validate::extension;
+
+/// Placeholder of the `wrap` operation replaced by the wrapped definition
+const PLACEHOLDER: &str = "$SYMBOL";
+
+/// Indentation used for a new body when it can't be derived from the file
+const INDENT: &str = "    ";
+
+/// Represents possible errors that can occur during an AST edit
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    /// The language of the file isn't supported
+    #[error("AST edits are not supported for file '{0}'")]
+    Unsupported(String),
+    /// Failed to initialize the parser with the specified language
+    #[error("Parser initialization error: {0}")]
+    Language(#[from] LanguageError),
+    /// Failed to parse the content
+    #[error("Failed to parse file '{0}'")]
+    Parse(String),
+    /// The operation needs a symbol but none was given
+    #[error("A symbol is required for every operation but 'insert_import'")]
+    MissingSymbol,
+    /// No definition matches the symbol path
+    #[error("Could not find a definition for symbol '{0}'")]
+    NotFound(String),
+    /// Several definitions match the symbol path
+    #[error(
+        "Symbol '{symbol}' matches {count} definitions. Prefix it with the names of its enclosing definitions to pick one of them."
+    )]
+    Ambiguous { symbol: String, count: usize },
+    /// The definition doesn't have a body to replace
+    #[error("Symbol '{0}' has no body, use the 'replace' operation instead")]
+    NoBody(String),
+    /// The content of a `wrap` operation doesn't say where the definition goes
+    #[error("The content of the 'wrap' operation must contain the {PLACEHOLDER} placeholder")]
+    MissingPlaceholder,
+    /// The edited content doesn't parse anymore
+    #[error("The edit would introduce syntax errors in file '{0}', the file was not changed")]
+    Syntax(String),
+}
+
+/// Applies an AST edit to the content of a source file and returns the new
+/// content.
+///
+/// # Arguments
+/// * `path` - The path of the file being edited (used to determine language)
+/// * `source` - The current content of the file
+/// * `symbol` - The symbol path of the definition to edit, eg: `Parser::parse`
+/// * `operation` - The operation to perform on the definition
+/// * `content` - The content used by the operation
+///
+/// # Note
+/// Edits that turn a valid file into an invalid one are rejected. Files that
+/// already have syntax errors can still be edited.
+pub fn edit(
+    path: impl AsRef<Path>,
+    source: &str,
+    symbol: Option<&str>,
+    operation: &AstOperation,
+    content: &str,
+) -> Result<String, Error> {
+    let path = path.as_ref();
+    let language = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(extension)
+        .ok_or_else(|| Error::Unsupported(path.display().to_string()))?;
+
+    let mut parser = Parser::new();
+    parser.set_language(&language)?;
+    let parse = |parser: &mut Parser, source: &str| -> Result<Tree, Error> {
+        parser
+            .parse(source, None)
+            .ok_or_else(|| Error::Parse(path.display().to_string()))
+    };
+
+    let tree = parse(&mut parser, source)?;
+    let root = tree.root_node();
+
+    let (range, replacement) = match operation {
+        AstOperation::InsertImport => match insert_import(root, source, content) {
+            Some(change) => change,
+            None => return Ok(source.to_string()),
+        },
+        operation => {
+            let symbol = symbol
+                .filter(|symbol| !symbol.trim().is_empty())
+                .ok_or(Error::MissingSymbol)?;
+            let node = resolve(root, source, symbol)?;
+            match operation {
+                AstOperation::ReplaceBody => replace_body(node, source, symbol, content)?,
+                AstOperation::Replace => {
+                    let range = definition_range(node);
+                    let indent = line_indent(source, range.start);
+                    (range, indent_rest(&dedent(content), indent))
+                }
+                AstOperation::InsertBefore => {
+                    let range = definition_range(node);
+                    let indent = line_indent(source, range.start);
+                    let text = format!(
+                        "{}{}{indent}",
+                        indent_rest(&dedent(content), indent),
+                        separator(source, &range)
+                    );
+                    (range.start..range.start, text)
+                }
+                AstOperation::InsertAfter => {
+                    let range = definition_range(node);
+                    let indent = line_indent(source, range.start);
+                    let text = format!(
+                        "{}{indent}{}",
+                        separator(source, &range),
+                        indent_rest(&dedent(content), indent)
+                    );
+                    (range.end..range.end, text)
+                }
+                AstOperation::Wrap => wrap(node, source, content)?,
+                AstOperation::InsertImport => unreachable!("imports are handled above"),
+            }
+        }
+    };
+
+    let mut edited = source.to_string();
+    edited.replace_range(range, &replacement);
+
+    let edited_root = parse(&mut parser, &edited)?;
+    if !root.has_error() && edited_root.root_node().has_error() {
+        return Err(Error::Syntax(path.display().to_string()));
+    }
+    Ok(edited)
+}
+
+/// Finds the definition a symbol path points to. Every name of the path is
+/// looked up among the definitions nested in the previous one, at any depth,
+/// so enclosing names can be omitted.
+fn resolve<'t>(root: Node<'t>, source: &str, symbol: &str) -> Result<Node<'t>, Error> {
+    let mut candidates = vec![root];
+    for name in symbol
+        .split("::")
+        .flat_map(|part| part.split('.'))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        let mut found = Vec::new();
+        for candidate in candidates {
+            find_definitions(candidate, source, name, &mut found);
+        }
+        if found.is_empty() {
+            return Err(Error::NotFound(symbol.to_string()));
+        }
+        candidates = found;
+    }
+
+    // Impl blocks share the name of their type but are only used to look up
+    // their items
+    if candidates.iter().any(|node| node.kind() != "impl_item") {
+        candidates.retain(|node| node.kind() != "impl_item");
+    }
+
+    match candidates.as_slice() {
+        [node] => Ok(*node),
+        nodes => Err(Error::Ambiguous { symbol: symbol.to_string(), count: nodes.len() }),
+    }
+}
+
+/// Collects the outermost definitions named `name` below `node`
+fn find_definitions<'t>(node: Node<'t>, source: &str, name: &str, found: &mut Vec<Node<'t>>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if definition_name(child, source) == Some(name) {
+            found.push(child);
+        } else {
+            find_definitions(child, source, name, found);
+        }
+    }
+}
+
+/// Name of a definition node, `None` for any other node. Impl blocks are
+/// named after the type they implement.
+pub(super) fn definition_name<'s>(node: Node<'_>, source: &'s str) -> Option<&'s str> {
+    let kind = node.kind();
+    let is_definition = kind.ends_with("_item")
+        || kind.ends_with("_definition")
+        || kind.ends_with("_declaration")
+        || kind.ends_with("_specifier")
+        || matches!(
+            kind,
+            "type_spec"
+                | "variable_declarator"
+                | "method"
+                | "singleton_method"
+                | "class"
+                | "module"
+        );
+    if !is_definition {
+        return None;
+    }
+
+    let name = match kind {
+        "impl_item" => node.child_by_field_name("type").map(|ty| {
+            // `impl<T> Parser<T>` is named `Parser`
+            ty.child_by_field_name("type").unwrap_or(ty)
+        }),
+        _ => node.child_by_field_name("name"),
+    }?;
+    name.utf8_text(source.as_bytes()).ok()
+}
+
+/// Byte range of a definition including the nodes that belong to it: the
+/// decorators or `export` keyword wrapping it and the attributes preceding it.
+fn definition_range(node: Node<'_>) -> Range<usize> {
+    let mut node = node;
+    while let Some(parent) = node
+        .parent()
+        .filter(|parent| matches!(parent.kind(), "decorated_definition" | "export_statement"))
+    {
+        node = parent;
+    }
+
+    let mut start = node.start_byte();
+    let mut previous = node.prev_named_sibling();
+    while let Some(attribute) = previous.filter(|sibling| sibling.kind() == "attribute_item") {
+        start = attribute.start_byte();
+        previous = attribute.prev_named_sibling();
+    }
+    start..node.end_byte()
+}
+
+fn replace_body(
+    node: Node<'_>,
+    source: &str,
+    symbol: &str,
+    content: &str,
+) -> Result<(Range<usize>, String), Error> {
+    let body = node
+        .child_by_field_name("body")
+        .ok_or_else(|| Error::NoBody(symbol.to_string()))?;
+    let range = body.byte_range();
+    let text = &source[range.clone()];
+    let content = dedent(content);
+
+    if text.starts_with('{') && text.ends_with('}') {
+        // Keep the braces and indent the content one level deeper than the
+        // definition, like the existing body if it spans several lines
+        let indent = line_indent(source, node.start_byte());
+        let inner = text
+            .lines()
+            .skip(1)
+            .find(|line| !line.trim().is_empty() && line.trim() != "}")
+            .map(|line| &line[..line.len() - line.trim_start().len()])
+            .filter(|inner| inner.len() > indent.len())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{indent}{INDENT}"));
+        let replacement = if content.trim().is_empty() {
+            "{}".to_string()
+        } else {
+            format!("{{\n{}\n{indent}}}", indent_all(&content, &inner))
+        };
+        Ok((range, replacement))
+    } else {
+        // Indentation based bodies, like Python blocks, start at their first
+        // statement
+        let indent = line_indent(source, range.start);
+        let indent = if indent.len() > line_indent(source, node.start_byte()).len() {
+            indent.to_string()
+        } else {
+            format!("{}{INDENT}", line_indent(source, node.start_byte()))
+        };
+        let start = source[..range.start].rfind('\n').map_or(0, |i| i + 1);
+        let start = if start > node.start_byte() {
+            start
+        } else {
+            range.start
+        };
+        let replacement = if start == range.start {
+            format!("\n{}", indent_all(&content, &indent))
+        } else {
+            indent_all(&content, &indent)
+        };
+        Ok((start..range.end, replacement))
+    }
+}
+
+fn wrap(node: Node<'_>, source: &str, content: &str) -> Result<(Range<usize>, String), Error> {
+    let content = dedent(content);
+    if !content.contains(PLACEHOLDER) {
+        return Err(Error::MissingPlaceholder);
+    }
+
+    let range = definition_range(node);
+    let indent = line_indent(source, range.start);
+    // The lines of the definition relative to its own indentation
+    let definition = source[range.clone()]
+        .split('\n')
+        .enumerate()
+        .map(|(i, line)| match i {
+            0 => line,
+            _ => line.strip_prefix(indent).unwrap_or(line),
+        })
+        .collect::<Vec<_>>();
+
+    let wrapped = content
+        .split('\n')
+        .map(|line| match line.find(PLACEHOLDER) {
+            Some(position) => {
+                let prefix = &line[..position];
+                let inner = &line[..prefix.len() - prefix.trim_start().len()];
+                let definition = definition
+                    .iter()
+                    .enumerate()
+                    .map(|(i, line)| match i {
+                        0 => line.to_string(),
+                        _ if line.is_empty() => String::new(),
+                        _ => format!("{inner}{line}"),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                line.replacen(PLACEHOLDER, &definition, 1)
+            }
+            None => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok((range, indent_rest(&wrapped, indent)))
+}
+
+/// Returns where to insert an import and the text to insert, or `None` when
+/// the file already has it
+fn insert_import(root: Node<'_>, source: &str, content: &str) -> Option<(Range<usize>, String)> {
+    let content = dedent(content);
+    let content = content.trim();
+    let mut cursor = root.walk();
+    let children = root.named_children(&mut cursor).collect::<Vec<_>>();
+
+    let imports = children
+        .iter()
+        .filter(|node| {
+            matches!(
+                node.kind(),
+                "use_declaration"
+                    | "extern_crate_declaration"
+                    | "import_statement"
+                    | "import_from_statement"
+                    | "future_import_statement"
+                    | "import_declaration"
+                    | "preproc_include"
+            )
+        })
+        .collect::<Vec<_>>();
+
+    if imports
+        .iter()
+        .any(|node| source[node.byte_range()].trim() == content)
+    {
+        return None;
+    }
+
+    if let Some(last) = imports.last() {
+        return Some((last.end_byte()..last.end_byte(), format!("\n{content}")));
+    }
+
+    // Without imports, the import goes after the package declaration and the
+    // comments heading the file
+    let header = children
+        .iter()
+        .take_while(|node| {
+            matches!(
+                node.kind(),
+                "package_clause"
+                    | "package_declaration"
+                    | "comment"
+                    | "line_comment"
+                    | "block_comment"
+                    | "inner_attribute_item"
+            )
+        })
+        .last();
+    Some(match header {
+        Some(node) => (node.end_byte()..node.end_byte(), format!("\n\n{content}")),
+        None => (0..0, format!("{content}\n\n")),
+    })
+}
+
+/// Separator between a definition and the code inserted next to it: a blank
+/// line for definitions spanning several lines, a line break otherwise.
+fn separator(source: &str, range: &Range<usize>) -> &'static str {
+    if source[range.clone()].contains('\n') {
+        "\n\n"
+    } else {
+        "\n"
+    }
+}
+
+/// Leading whitespace of the line containing `offset`
+fn line_indent(source: &str, offset: usize) -> &str {
+    let start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line = &source[start..];
+    let end = line
+        .find(|c: char| !c.is_whitespace() || c == '\n')
+        .unwrap_or(line.len());
+    &line[..end]
+}
+
+/// Removes the indentation common to all the non-empty lines along with the
+/// leading and trailing empty lines
+fn dedent(content: &str) -> String {
+    let lines = content
+        .lines()
+        .skip_while(|line| line.trim().is_empty())
+        .collect::<Vec<_>>();
+    let end = lines
+        .iter()
+        .rposition(|line| !line.trim().is_empty())
+        .map_or(0, |i| i + 1);
+    let lines = &lines[..end];
+    let common = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or_default();
+    lines
+        .iter()
+        .map(|line| line.get(common..).unwrap_or_default().trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Indents all the non-empty lines
+fn indent_all(content: &str, indent: &str) -> String {
+    content
+        .split('\n')
+        .map(|line| match line.is_empty() {
+            true => String::new(),
+            false => format!("{indent}{line}"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Indents the non-empty lines after the first one, for content inserted in
+/// the middle of an already indented line
+fn indent_rest(content: &str, indent: &str) -> String {
+    match content.split_once('\n') {
+        Some((first, rest)) => format!("{first}\n{}", indent_all(rest, indent)),
+        None => content.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const RUST: &str = r#"use std::fmt;
+
+struct Parser {
+    input: String,
+}
+
+impl Parser {
+    fn parse(&self) -> usize {
+        self.input.len()
+    }
+}
+
+fn parse() {}
+"#;
+
+    const PYTHON: &str = r#"import os
+
+
+class Shape:
+    @property
+    def area(self):
+        return 0
+"#;
+
+    #[test]
+    fn test_replace_body_of_method() {
+        let actual = edit(
+            "lib.rs",
+            RUST,
+            Some("Parser::parse"),
+            &AstOperation::ReplaceBody,
+            "let count = self.input.lines().count();\ncount",
+        )
+        .unwrap();
+
+        let expected = RUST.replace(
+            "        self.input.len()\n",
+            "        let count = self.input.lines().count();\n        count\n",
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_replace_body_of_python_method() {
+        let actual = edit(
+            "shape.py",
+            PYTHON,
+            Some("Shape.area"),
+            &AstOperation::ReplaceBody,
+            "    width = 2\n    return width * width",
+        )
+        .unwrap();
+
+        let expected = PYTHON.replace(
+            "        return 0\n",
+            "        width = 2\n        return width * width\n",
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_ambiguous_symbol() {
+        let actual = edit("lib.rs", RUST, Some("parse"), &AstOperation::Replace, "").unwrap_err();
+
+        let expected = Error::Ambiguous { symbol: "parse".to_string(), count: 2 };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_insert_after_and_wrap() {
+        let fixture = edit(
+            "lib.rs",
+            RUST,
+            Some("Parser::parse"),
+            &AstOperation::InsertAfter,
+            "fn reset(&mut self) {\n    self.input.clear();\n}",
+        )
+        .unwrap();
+
+        let actual = edit(
+            "lib.rs",
+            &fixture,
+            Some("Parser"),
+            &AstOperation::Wrap,
+            "mod parser {\n    $SYMBOL\n}",
+        )
+        .unwrap();
+
+        let expected = r#"use std::fmt;
+
+mod parser {
+    struct Parser {
+        input: String,
+    }
+}
+
+impl Parser {
+    fn parse(&self) -> usize {
+        self.input.len()
+    }
+
+    fn reset(&mut self) {
+        self.input.clear();
+    }
+}
+
+fn parse() {}
+"#;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_insert_import() {
+        let fixture = edit(
+            "shape.py",
+            PYTHON,
+            None,
+            &AstOperation::InsertImport,
+            "import math",
+        )
+        .unwrap();
+
+        let actual = edit(
+            "shape.py",
+            &fixture,
+            None,
+            &AstOperation::InsertImport,
+            "import math",
+        )
+        .unwrap();
+
+        let expected = PYTHON.replace("import os\n", "import os\nimport math\n");
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_reject_syntax_errors() {
+        let actual = edit(
+            "lib.rs",
+            RUST,
+            Some("Parser::parse"),
+            &AstOperation::ReplaceBody,
+            "self.input.len(",
+        )
+        .unwrap_err();
+
+        let expected = Error::Syntax("lib.rs".to_string());
+        assert_eq!(actual, expected);
+    }
+}
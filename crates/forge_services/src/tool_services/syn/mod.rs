@@ -1,3 +1,7 @@
+mod edit;
+mod outline;
 mod validate;
 
+pub use edit::edit;
+pub use outline::{is_outlined, outline};
 pub use validate::validate;
@@ -0,0 +1,247 @@
+use std::path::Path;
+
+use forge_app::Symbol;
+use thiserror::Error;
+use tree_sitter::{LanguageError, Node, Parser};
+
+use super::edit::definition_name;
+use super::validate::extension;
+
+/// Represents possible errors that can occur while outlining a file
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    /// The language of the file isn't supported
+    #[error("Outlines are not supported for file '{0}'")]
+    Unsupported(String),
+    /// Failed to initialize the parser with the specified language
+    #[error("Parser initialization error: {0}")]
+    Language(#[from] LanguageError),
+    /// Failed to parse the content
+    #[error("Failed to parse file '{0}'")]
+    Parse(String),
+}
+
+/// Kinds of definitions making up an outline, named after the node kinds of
+/// the grammars without their suffix
+const OUTLINE_KINDS: &[&str] = &[
+    "function",
+    "method",
+    "constructor",
+    "struct",
+    "enum",
+    "union",
+    "trait",
+    "impl",
+    "interface",
+    "record",
+    "class",
+    "object",
+    "module",
+    "namespace",
+    "type",
+    "const",
+    "static",
+    "macro",
+    "variable",
+];
+
+/// Kinds of definitions whose bodies aren't outlined, as they only hold local
+/// definitions
+const LEAF_KINDS: &[&str] = &[
+    "function",
+    "method",
+    "constructor",
+    "const",
+    "static",
+    "variable",
+];
+
+/// Whether the language of the file can be outlined, from its extension
+pub fn is_outlined(path: impl AsRef<Path>) -> bool {
+    path.as_ref()
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(extension)
+        .is_some()
+}
+
+/// Outlines the definitions of a source file: its functions, types, impl
+/// blocks, classes and modules, in the order they appear. Nested definitions
+/// follow the one enclosing them, with the names of the enclosing definitions
+/// in their symbol path.
+///
+/// # Arguments
+/// * `path` - The path of the file (used to determine language)
+/// * `source` - The content of the file
+pub fn outline(path: impl AsRef<Path>, source: &str) -> Result<Vec<Symbol>, Error> {
+    let path = path.as_ref();
+    let language = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(extension)
+        .ok_or_else(|| Error::Unsupported(path.display().to_string()))?;
+
+    let mut parser = Parser::new();
+    parser.set_language(&language)?;
+    let tree = parser
+        .parse(source, None)
+        .ok_or_else(|| Error::Parse(path.display().to_string()))?;
+
+    // Rust and C++ address the items of a type with `::`, the other languages
+    // with `.`
+    let separator = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("rs" | "cpp" | "cc" | "cxx" | "c++") => "::",
+        _ => ".",
+    };
+
+    let mut symbols = Vec::new();
+    collect(tree.root_node(), source, None, separator, &mut symbols);
+    Ok(symbols)
+}
+
+fn collect(
+    node: Node<'_>,
+    source: &str,
+    parent: Option<&str>,
+    separator: &str,
+    symbols: &mut Vec<Symbol>,
+) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        let kind = symbol_kind(child.kind());
+        match definition_name(child, source).filter(|_| OUTLINE_KINDS.contains(&kind)) {
+            Some(name) => {
+                let path = match parent {
+                    Some(parent) => format!("{parent}{separator}{name}"),
+                    None => name.to_string(),
+                };
+                symbols.push(Symbol {
+                    path: path.clone(),
+                    kind: kind.to_string(),
+                    signature: signature(child, source),
+                    start_line: child.start_position().row as u64 + 1,
+                    end_line: child.end_position().row as u64 + 1,
+                });
+                if !LEAF_KINDS.contains(&kind) {
+                    collect(child, source, Some(&path), separator, symbols);
+                }
+            }
+            None => collect(child, source, parent, separator, symbols),
+        }
+    }
+}
+
+/// Kind of a definition node without the suffix of its grammar, eg:
+/// `function` for `function_item` or `function_definition`
+fn symbol_kind(kind: &str) -> &str {
+    let kind = [
+        "_item",
+        "_definition",
+        "_declaration",
+        "_specifier",
+        "_declarator",
+        "_spec",
+    ]
+    .iter()
+    .find_map(|suffix| kind.strip_suffix(suffix))
+    .unwrap_or(kind);
+    match kind {
+        "function_signature" => "function",
+        "singleton_method" => "method",
+        "mod" => "module",
+        "type_alias" => "type",
+        "abstract_class" => "class",
+        kind => kind,
+    }
+}
+
+/// First line of a definition, without its indentation and the opening of
+/// its body
+fn signature(node: Node<'_>, source: &str) -> String {
+    let text = &source[node.byte_range()];
+    let line = text.lines().next().unwrap_or_default().trim();
+    line.strip_suffix('{')
+        .or_else(|| line.strip_suffix(':'))
+        .unwrap_or(line)
+        .trim_end()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn symbol(path: &str, kind: &str, signature: &str, lines: (u64, u64)) -> Symbol {
+        Symbol {
+            path: path.to_string(),
+            kind: kind.to_string(),
+            signature: signature.to_string(),
+            start_line: lines.0,
+            end_line: lines.1,
+        }
+    }
+
+    #[test]
+    fn test_outline_rust() {
+        let fixture = r#"use std::fmt;
+
+pub struct Parser {
+    input: String,
+}
+
+impl Parser {
+    pub fn parse(&self) -> usize {
+        let count = self.input.len();
+        count
+    }
+}
+
+fn main() {}
+"#;
+
+        let actual = outline("lib.rs", fixture).unwrap();
+
+        let expected = vec![
+            symbol("Parser", "struct", "pub struct Parser", (3, 5)),
+            symbol("Parser", "impl", "impl Parser", (7, 12)),
+            symbol(
+                "Parser::parse",
+                "function",
+                "pub fn parse(&self) -> usize",
+                (8, 11),
+            ),
+            symbol("main", "function", "fn main() {}", (14, 14)),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_outline_python() {
+        let fixture = r#"import os
+
+
+class Shape:
+    def area(self):
+        total = 0
+        return total
+"#;
+
+        let actual = outline("shapes.py", fixture).unwrap();
+
+        let expected = vec![
+            symbol("Shape", "class", "class Shape", (4, 7)),
+            symbol("Shape.area", "function", "def area(self)", (5, 7)),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_outline_unsupported() {
+        let actual = outline("notes.txt", "Some notes");
+
+        let expected = Err(Error::Unsupported("notes.txt".to_string()));
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,253 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use forge_app::domain::{GeneratedFiles, WalkerFilter, WorkspaceFile, WorkspaceStats};
+use forge_app::{WalkedFile, Walker, WorkspaceStatsService};
+use futures::StreamExt;
+
+use crate::{EnvironmentInfra, FileReaderInfra, WalkerInfra};
+
+/// Files larger than this aren't read to count their lines, and leave the
+/// statistics truncated
+const MAX_FILE_SIZE: u64 = 512 * 1024;
+
+/// Files read at once to count their lines
+const READ_CONCURRENCY: usize = 16;
+
+/// Largest files listed in the statistics
+const LARGEST_FILES: usize = 10;
+
+/// Counts the text files of the workspace and their lines by language
+pub struct ForgeWorkspaceStats<F> {
+    infra: Arc<F>,
+}
+
+impl<F> ForgeWorkspaceStats<F> {
+    pub fn new(infra: Arc<F>) -> Self {
+        Self { infra }
+    }
+}
+
+impl<F: FileReaderInfra> ForgeWorkspaceStats<F> {
+    /// Reads the file to count its lines, `None` when it can't be read or its
+    /// content tells it's generated
+    async fn count(
+        &self,
+        cwd: &Path,
+        file: WalkedFile,
+        generated: &GeneratedFiles,
+    ) -> Option<WorkspaceFile> {
+        let content = self.infra.read_utf8(&cwd.join(&file.path)).await.ok()?;
+        if generated.detect(Path::new(&file.path), &content).is_some() {
+            return None;
+        }
+        Some(WorkspaceFile {
+            path: file.path,
+            size: file.size,
+            lines: content.lines().count() as u64,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: EnvironmentInfra + WalkerInfra + FileReaderInfra> WorkspaceStatsService
+    for ForgeWorkspaceStats<F>
+{
+    async fn workspace_stats(
+        &self,
+        filter: &WalkerFilter,
+        generated: &GeneratedFiles,
+        max_files: usize,
+    ) -> anyhow::Result<WorkspaceStats> {
+        let cwd = self.infra.get_environment().cwd;
+        // One file more than counted tells whether some were left out
+        let walker = Walker::unlimited()
+            .cwd(cwd.clone())
+            .max_files(max_files.saturating_add(1))
+            .skip_binary(true)
+            .filter(filter)
+            .generated(generated.clone());
+        let mut walked = self.infra.walk(walker).await?;
+        walked.retain(|file| !file.is_dir());
+        let mut truncated = walked.len() > max_files;
+        walked.truncate(max_files);
+
+        // Lockfiles and generated code aren't written in the workspace
+        let mut written = Vec::new();
+        for file in walked {
+            if file.generated.is_some() {
+                continue;
+            }
+            if file.size > MAX_FILE_SIZE {
+                truncated = true;
+                continue;
+            }
+            written.push(file);
+        }
+
+        let files = futures::stream::iter(written)
+            .map(|file| self.count(&cwd, file, generated))
+            .buffer_unordered(READ_CONCURRENCY)
+            .filter_map(futures::future::ready)
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut stats = WorkspaceStats::new(files, LARGEST_FILES);
+        stats.truncated = truncated;
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::Mutex;
+
+    use forge_app::domain::{Environment, LanguageStats};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::attachment::tests::MockEnvironmentInfra;
+
+    /// Workspace of files by path with their content, walked in the order of
+    /// their paths
+    #[derive(Default)]
+    struct Fixture {
+        files: Mutex<BTreeMap<String, String>>,
+    }
+
+    impl Fixture {
+        fn write(&self, path: &str, content: &str) {
+            self.files
+                .lock()
+                .unwrap()
+                .insert(path.to_string(), content.to_string());
+        }
+    }
+
+    impl EnvironmentInfra for Fixture {
+        fn get_environment(&self) -> Environment {
+            MockEnvironmentInfra {}.get_environment()
+        }
+
+        fn get_env_var(&self, _key: &str) -> Option<String> {
+            None
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl WalkerInfra for Fixture {
+        async fn walk(&self, config: Walker) -> anyhow::Result<Vec<WalkedFile>> {
+            Ok(self
+                .files
+                .lock()
+                .unwrap()
+                .iter()
+                .take(config.max_files.unwrap_or(usize::MAX))
+                .map(|(path, content)| WalkedFile {
+                    path: path.clone(),
+                    file_name: None,
+                    size: content.len() as u64,
+                    modified: None,
+                    generated: config.generated.detect_path(Path::new(path)),
+                })
+                .collect())
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl FileReaderInfra for Fixture {
+        async fn read_utf8(&self, path: &Path) -> anyhow::Result<String> {
+            let path = path.strip_prefix("/test")?.to_string_lossy().to_string();
+            self.files
+                .lock()
+                .unwrap()
+                .get(&path)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("No file {path}"))
+        }
+
+        async fn read(&self, _path: &Path) -> anyhow::Result<Vec<u8>> {
+            unimplemented!()
+        }
+
+        async fn range_read_utf8(
+            &self,
+            _path: &Path,
+            _start_line: u64,
+            _end_line: u64,
+        ) -> anyhow::Result<(String, forge_fs::FileInfo)> {
+            unimplemented!()
+        }
+    }
+
+    fn fixture() -> ForgeWorkspaceStats<Fixture> {
+        let infra = Fixture::default();
+        infra.write(
+            "Cargo.lock",
+            "# This file is automatically @generated by Cargo.\n",
+        );
+        infra.write(
+            "src/lib.rs",
+            "// @generated by prost\npub struct Message;\n",
+        );
+        infra.write("src/main.rs", "fn main() {\n}\n");
+        infra.write("web/app.ts", "export {};\n");
+        ForgeWorkspaceStats::new(Arc::new(infra))
+    }
+
+    #[tokio::test]
+    async fn test_workspace_stats_leave_out_generated_files() {
+        let fixture = fixture();
+        let generated = GeneratedFiles::new(&[]);
+
+        let actual = fixture
+            .workspace_stats(&WalkerFilter::default(), &generated, 10)
+            .await
+            .unwrap();
+
+        let expected = WorkspaceStats {
+            files: 2,
+            lines: 3,
+            languages: vec![
+                LanguageStats { language: "Rust".to_string(), files: 1, lines: 2 },
+                LanguageStats { language: "TypeScript".to_string(), files: 1, lines: 1 },
+            ],
+            largest_files: vec![
+                WorkspaceFile { path: "src/main.rs".to_string(), size: 14, lines: 2 },
+                WorkspaceFile { path: "web/app.ts".to_string(), size: 11, lines: 1 },
+            ],
+            truncated: false,
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_workspace_stats_truncated_beyond_max_files() {
+        let fixture = fixture();
+        let generated = GeneratedFiles::new(&[]);
+
+        let actual = fixture
+            .workspace_stats(&WalkerFilter::default(), &generated, 3)
+            .await
+            .unwrap();
+
+        assert_eq!((actual.files, actual.truncated), (1, true));
+    }
+
+    #[tokio::test]
+    async fn test_workspace_stats_truncated_by_large_files() {
+        let fixture = fixture();
+        fixture
+            .infra
+            .write("data/dump.sql", &"INSERT;\n".repeat(MAX_FILE_SIZE as usize));
+        let generated = GeneratedFiles::new(&[]);
+
+        let actual = fixture
+            .workspace_stats(&WalkerFilter::default(), &generated, 10)
+            .await
+            .unwrap();
+
+        assert_eq!((actual.files, actual.truncated), (2, true));
+    }
+}
@@ -0,0 +1,348 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use anyhow::Context;
+use bytes::Bytes;
+use forge_app::domain::{
+    GeneratedFiles, ModelId, Point, Provider, Query, WalkerFilter, WorkspaceChunk, is_secret_file,
+};
+use forge_app::{ChunkMatch, ProviderService, Walker, WorkspaceIndexService};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+
+use crate::{EnvironmentInfra, FileInfoInfra, FileReaderInfra, FileWriterInfra, WalkerInfra};
+
+/// Files larger than this are left out of the index, as they're mostly
+/// generated code or data
+const MAX_FILE_SIZE: u64 = 256 * 1024;
+
+/// Files indexed at most in a workspace
+const MAX_FILES: usize = 5000;
+
+/// Chunks embedded with a single request
+const BATCH_SIZE: usize = 64;
+
+/// Embeddings of the chunks of the files of a workspace
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WorkspaceIndex {
+    /// Model the embeddings were computed with, they can't be compared with
+    /// the ones of another model
+    model: Option<ModelId>,
+    files: BTreeMap<String, IndexedFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexedFile {
+    /// Hash of the content the chunks were split from
+    hash: String,
+    /// Size and modification time of the file when it was indexed, a file
+    /// with the same ones isn't read again
+    #[serde(default)]
+    size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    modified: Option<SystemTime>,
+    points: Vec<Point<WorkspaceChunk>>,
+}
+
+impl WorkspaceIndex {
+    /// Returns the chunks most similar to the query, the most similar first
+    fn search(&self, query: &Query) -> Vec<ChunkMatch> {
+        let mut matches = self
+            .files
+            .values()
+            .flat_map(|file| &file.points)
+            .map(|point| (point, point.similarity(query)))
+            .filter(|(_, score)| {
+                query
+                    .distance
+                    .is_none_or(|distance| 1.0 - score <= distance)
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        if let Some(limit) = query.limit {
+            matches.truncate(limit as usize);
+        }
+        matches
+            .into_iter()
+            .map(|(point, score)| ChunkMatch { chunk: point.content.clone(), score })
+            .collect()
+    }
+}
+
+/// Indexes the files of the workspace with the embeddings of their chunks,
+/// kept in a JSON file under the base path. Only the files that changed since
+/// the last search are read and embedded again, and the files holding secrets
+/// are left out.
+pub struct ForgeWorkspaceIndex<I, P> {
+    infra: Arc<I>,
+    provider_service: Arc<P>,
+    // Loaded on the first search. The lock also keeps concurrent searches from
+    // embedding the same files.
+    index: Mutex<Option<WorkspaceIndex>>,
+}
+
+impl<I, P> ForgeWorkspaceIndex<I, P> {
+    pub fn new(infra: Arc<I>, provider_service: Arc<P>) -> Self {
+        Self { infra, provider_service, index: Mutex::new(None) }
+    }
+}
+
+impl<I: EnvironmentInfra + WalkerInfra + FileReaderInfra + FileWriterInfra + FileInfoInfra, P>
+    ForgeWorkspaceIndex<I, P>
+where
+    P: ProviderService,
+{
+    /// Index of the workspace, named after the hash of its path
+    fn index_path(&self) -> PathBuf {
+        let env = self.infra.get_environment();
+        let key = format!("{:x}", Sha256::digest(env.cwd.display().to_string()));
+        env.workspace_indexes_path().join(format!("{key}.json"))
+    }
+
+    async fn load(&self, path: &Path) -> WorkspaceIndex {
+        if !self.infra.exists(path).await.unwrap_or_default() {
+            return WorkspaceIndex::default();
+        }
+        let index = self
+            .infra
+            .read_utf8(path)
+            .await
+            .and_then(|content| Ok(serde_json::from_str(&content)?));
+        match index {
+            Ok(index) => index,
+            Err(error) => {
+                // The index can always be rebuilt from the files
+                tracing::warn!(error = ?error, path = %path.display(), "Discarding invalid workspace index");
+                WorkspaceIndex::default()
+            }
+        }
+    }
+
+    /// Embeds the chunks of the files that were added or changed since the
+    /// index was updated and drops the ones of removed files
+    async fn update(
+        &self,
+        index: &mut WorkspaceIndex,
+        provider: &Provider,
+        model: &ModelId,
+        filter: &WalkerFilter,
+        generated: &GeneratedFiles,
+    ) -> anyhow::Result<()> {
+        if index.model.as_ref() != Some(model) {
+            *index = WorkspaceIndex { model: Some(model.clone()), files: BTreeMap::new() };
+        }
+
+        let env = self.infra.get_environment();
+        let walker = Walker::unlimited()
+            .cwd(env.cwd.clone())
+            .max_file_size(MAX_FILE_SIZE)
+            .max_files(MAX_FILES)
+            .skip_binary(true)
+            .filter(filter)
+            .generated(generated.clone());
+        let walked = self.infra.walk(walker).await?;
+
+        let mut files = BTreeMap::new();
+        let mut pending = Vec::new();
+        // Set when only the size or the modification time of files changed
+        let mut touched = false;
+        // Lockfiles, minified and generated files would only crowd out the code
+        // that was written
+        for file in walked.into_iter().filter(|file| !file.is_dir()) {
+            if file.generated.is_some() || is_secret_file(Path::new(&file.path)) {
+                continue;
+            }
+            let indexed = match index.files.remove(&file.path) {
+                Some(indexed)
+                    if file.modified.is_some()
+                        && indexed.modified == file.modified
+                        && indexed.size == file.size =>
+                {
+                    files.insert(file.path, indexed);
+                    continue;
+                }
+                indexed => indexed,
+            };
+
+            let Ok(content) = self.infra.read_utf8(&env.cwd.join(&file.path)).await else {
+                continue;
+            };
+            if generated.detect(Path::new(&file.path), &content).is_some() {
+                continue;
+            }
+            let hash = format!("{:x}", Sha256::digest(content.as_bytes()));
+            match indexed {
+                Some(mut indexed) if indexed.hash == hash => {
+                    indexed.size = file.size;
+                    indexed.modified = file.modified;
+                    touched = true;
+                    files.insert(file.path, indexed);
+                }
+                _ => {
+                    let chunks = WorkspaceChunk::split(&file.path, &content);
+                    pending.push((file, hash, chunks));
+                }
+            }
+        }
+        // Whatever is left in the index was removed from the workspace
+        let changed = touched || !pending.is_empty() || !index.files.is_empty();
+
+        let inputs = pending
+            .iter()
+            .flat_map(|(_, _, chunks)| chunks.iter().map(WorkspaceChunk::embedding_input))
+            .collect::<Vec<_>>();
+        let mut embeddings = Vec::with_capacity(inputs.len());
+        for batch in inputs.chunks(BATCH_SIZE) {
+            let batch_embeddings = self
+                .provider_service
+                .embed(model, batch.to_vec(), provider.clone())
+                .await?;
+            if batch_embeddings.len() != batch.len() {
+                anyhow::bail!(
+                    "Expected {} embeddings from {model}, received {}",
+                    batch.len(),
+                    batch_embeddings.len()
+                )
+            }
+            embeddings.extend(batch_embeddings);
+        }
+
+        let mut embeddings = embeddings.into_iter();
+        for (file, hash, chunks) in pending {
+            let points = chunks
+                .into_iter()
+                .zip(embeddings.by_ref())
+                .map(|(chunk, embedding)| Point::new(chunk, embedding))
+                .collect();
+            files.insert(
+                file.path,
+                IndexedFile { hash, size: file.size, modified: file.modified, points },
+            );
+        }
+        index.files = files;
+
+        if changed {
+            let path = self.index_path();
+            let content = serde_json::to_string(&*index)?;
+            self.infra
+                .write(&path, Bytes::from(content), false)
+                .await
+                .with_context(|| {
+                    format!("Failed to save the workspace index at {}", path.display())
+                })?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<I: EnvironmentInfra + WalkerInfra + FileReaderInfra + FileWriterInfra + FileInfoInfra, P>
+    WorkspaceIndexService for ForgeWorkspaceIndex<I, P>
+where
+    P: ProviderService,
+{
+    async fn semantic_search(
+        &self,
+        provider: Provider,
+        model: ModelId,
+        query: String,
+        limit: usize,
+        filter: &WalkerFilter,
+        generated: &GeneratedFiles,
+    ) -> anyhow::Result<Vec<ChunkMatch>> {
+        let mut guard = self.index.lock().await;
+        let index = match guard.take() {
+            Some(index) => index,
+            None => self.load(&self.index_path()).await,
+        };
+        let index = guard.insert(index);
+        if let Err(error) = self
+            .update(index, &provider, &model, filter, generated)
+            .await
+        {
+            // The index may be partially updated, it's loaded from the file again
+            *guard = None;
+            return Err(error);
+        }
+
+        let embedding = self
+            .provider_service
+            .embed(&model, vec![query], provider)
+            .await?
+            .into_iter()
+            .next()
+            .context("No embedding was computed for the query")?;
+        Ok(index.search(&Query::new(embedding).limit(limit as u64)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn point(path: &str, embedding: Vec<f32>) -> Point<WorkspaceChunk> {
+        Point::new(
+            WorkspaceChunk {
+                path: path.to_string(),
+                start_line: 1,
+                end_line: 1,
+                total_lines: 1,
+                content: String::new(),
+            },
+            embedding,
+        )
+    }
+
+    fn fixture_index() -> WorkspaceIndex {
+        let file = |points| IndexedFile { hash: String::new(), size: 0, modified: None, points };
+        WorkspaceIndex {
+            model: Some(ModelId::new("text-embedding-3-small")),
+            files: BTreeMap::from([
+                (
+                    "src/a.rs".to_string(),
+                    file(vec![point("src/a.rs", vec![0.0, 1.0])]),
+                ),
+                (
+                    "src/b.rs".to_string(),
+                    file(vec![
+                        point("src/b.rs", vec![1.0, 0.0]),
+                        point("src/b.rs", vec![3.0, 4.0]),
+                    ]),
+                ),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_search_ranks_by_similarity() {
+        let fixture = fixture_index();
+
+        let actual = fixture
+            .search(&Query::new(vec![1.0, 0.0]).limit(2u64))
+            .into_iter()
+            .map(|matched| (matched.chunk.path, matched.score))
+            .collect::<Vec<_>>();
+
+        let expected = vec![("src/b.rs".to_string(), 1.0), ("src/b.rs".to_string(), 0.6)];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_search_within_distance() {
+        let fixture = fixture_index();
+
+        let actual = fixture
+            .search(&Query::new(vec![0.0, 1.0]).distance(0.5f32))
+            .into_iter()
+            .map(|matched| (matched.chunk.path, matched.score))
+            .collect::<Vec<_>>();
+
+        let expected = vec![("src/a.rs".to_string(), 1.0), ("src/b.rs".to_string(), 0.8)];
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,85 +1,288 @@
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use forge_fs::ForgeFS;
+use tokio::sync::Mutex;
 
-use crate::snapshot::Snapshot;
+use crate::snapshot::{Snapshot, SnapshotId, content_hash};
 
-/// Implementation of the SnapshotService
+/// Stores the content of files before they are changed, so that the changes
+/// can be undone. Contents are stored once under their SHA-256 hash in
+/// `objects`, and each workspace has an index under `indexes` listing its
+/// snapshots from the oldest to the most recent.
 #[derive(Debug)]
 pub struct SnapshotService {
     /// Base directory for storing snapshots
     snapshots_directory: PathBuf,
+    /// Workspace whose snapshots are listed, undone and restored
+    workspace: PathBuf,
+    /// Serializes the updates of the indexes
+    lock: Mutex<()>,
 }
 
 impl SnapshotService {
-    /// Create a new FileSystemSnapshotService with a specific home path
-    pub fn new(snapshot_base_dir: PathBuf) -> Self {
-        Self { snapshots_directory: snapshot_base_dir }
+    /// Create a new FileSystemSnapshotService with a specific home path, for
+    /// the snapshots of the given workspace
+    pub fn new(snapshot_base_dir: PathBuf, workspace: PathBuf) -> Self {
+        let workspace = workspace.canonicalize().unwrap_or(workspace);
+        Self {
+            snapshots_directory: snapshot_base_dir,
+            workspace,
+            lock: Mutex::new(()),
+        }
     }
 }
 
 impl SnapshotService {
-    pub async fn create_snapshot(&self, path: PathBuf) -> Result<Snapshot> {
-        let snapshot = Snapshot::create(path)?;
+    fn indexes_directory(&self) -> PathBuf {
+        self.snapshots_directory.join("indexes")
+    }
 
-        // Create intermediary directories if they don't exist
-        let snapshot_path = snapshot.snapshot_path(Some(self.snapshots_directory.clone()));
-        if let Some(parent) = PathBuf::from(&snapshot_path).parent() {
-            ForgeFS::create_dir_all(parent).await?;
+    /// Index of the workspace, named after the hash of its path
+    fn index_path(&self) -> PathBuf {
+        let key = content_hash(self.workspace.display().to_string().as_bytes());
+        self.indexes_directory().join(format!("{key}.jsonl"))
+    }
+
+    /// Indexes of every workspace, along with the single index used before
+    /// they were split by workspace
+    async fn index_paths(&self) -> Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        let legacy = self.snapshots_directory.join("index.jsonl");
+        if ForgeFS::exists(&legacy) {
+            paths.push(legacy);
         }
 
-        snapshot
-            .save(Some(self.snapshots_directory.clone()))
-            .await?;
+        let directory = self.indexes_directory();
+        if ForgeFS::exists(&directory) {
+            let mut entries = ForgeFS::read_dir(&directory).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                if path
+                    .extension()
+                    .is_some_and(|extension| extension == "jsonl")
+                {
+                    paths.push(path);
+                }
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
 
-        Ok(snapshot)
+    fn object_path(&self, hash: &str) -> PathBuf {
+        self.snapshots_directory.join("objects").join(hash)
     }
 
-    /// Find the most recent snapshot for a given path based on filename
-    /// timestamp
-    async fn find_recent_snapshot(snapshot_dir: &PathBuf) -> Result<Option<PathBuf>> {
-        let mut latest_path = None;
-        let mut latest_filename = None;
-        let mut dir = ForgeFS::read_dir(&snapshot_dir).await?;
+    async fn read_index(path: &Path) -> Result<Vec<Snapshot>> {
+        if !ForgeFS::exists(path) {
+            return Ok(Vec::new());
+        }
+        ForgeFS::read_utf8(path)
+            .await?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).context("Failed to parse snapshot index"))
+            .collect()
+    }
 
-        while let Some(entry) = dir.next_entry().await? {
-            let filename = entry.file_name().to_string_lossy().to_string();
-            if filename.ends_with(".snap")
-                && (latest_filename.is_none() || filename > latest_filename.clone().unwrap())
+    async fn write_index(path: &Path, snapshots: &[Snapshot]) -> Result<()> {
+        let mut content = String::new();
+        for snapshot in snapshots {
+            content.push_str(&serde_json::to_string(snapshot)?);
+            content.push('\n');
+        }
+        if let Some(parent) = path.parent() {
+            ForgeFS::create_dir_all(parent).await?;
+        }
+        ForgeFS::write(path, content).await
+    }
+
+    /// Removes the contents of the snapshots that no snapshot of any
+    /// workspace uses anymore, as contents are shared between them
+    async fn remove_unused(&self, removed: Vec<Snapshot>) -> Result<()> {
+        let mut used = HashSet::new();
+        for path in self.index_paths().await? {
+            used.extend(
+                Self::read_index(&path)
+                    .await?
+                    .into_iter()
+                    .filter_map(|snapshot| snapshot.hash),
+            );
+        }
+
+        for removed in removed {
+            if let Some(hash) = removed.hash
+                && !used.contains(&hash)
+                && ForgeFS::exists(self.object_path(&hash))
             {
-                latest_filename = Some(filename);
-                latest_path = Some(entry.path());
+                ForgeFS::remove_file(self.object_path(&hash)).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Captures the current content of a file. A file that doesn't exist is
+    /// recorded too, so that undoing its creation removes it.
+    pub async fn create_snapshot(&self, path: PathBuf) -> Result<Snapshot> {
+        let mut snapshot = Snapshot::create(path)?;
+
+        if ForgeFS::is_file(&snapshot.path) {
+            let content = ForgeFS::read(&snapshot.path).await?;
+            let hash = content_hash(&content);
+            let object = self.object_path(&hash);
+            if !ForgeFS::exists(&object) {
+                if let Some(parent) = object.parent() {
+                    ForgeFS::create_dir_all(parent).await?;
+                }
+                ForgeFS::write(&object, content).await?;
             }
+            snapshot.hash = Some(hash);
         }
 
-        Ok(latest_path)
+        let _guard = self.lock.lock().await;
+        let mut line = serde_json::to_string(&snapshot)?;
+        line.push('\n');
+        ForgeFS::create_dir_all(self.indexes_directory()).await?;
+        ForgeFS::append(self.index_path(), line).await?;
+
+        Ok(snapshot)
+    }
+
+    /// Lists the snapshots of the workspace from the oldest to the most recent
+    pub async fn list(&self) -> Result<Vec<Snapshot>> {
+        let _guard = self.lock.lock().await;
+        Self::read_index(&self.index_path()).await
     }
 
-    pub async fn undo_snapshot(&self, path: PathBuf) -> Result<()> {
-        let snapshot = Snapshot::create(path.clone())?;
+    /// Reads the content the file had when the snapshot was taken, `None` when
+    /// the file didn't exist
+    pub async fn content(&self, snapshot: &Snapshot) -> Result<Option<Vec<u8>>> {
+        let Some(hash) = &snapshot.hash else {
+            return Ok(None);
+        };
+        let content = ForgeFS::read(self.object_path(hash))
+            .await
+            .with_context(|| format!("Content of snapshot {} is missing", snapshot.id))?;
+        Ok(Some(content))
+    }
 
-        // All the snaps for `path` are stored in `snapshot.path_hash()` directory.
-        let snapshot_dir = self.snapshots_directory.join(snapshot.path_hash());
+    /// Lists the snapshots of every workspace from the oldest to the most
+    /// recent, with the bytes that removing each of them frees. A content
+    /// shared by several snapshots is counted for the most recent one, the
+    /// last to be removed.
+    pub async fn usage(&self) -> Result<Vec<(Snapshot, u64)>> {
+        let _guard = self.lock.lock().await;
+        let mut snapshots = Vec::new();
+        for path in self.index_paths().await? {
+            snapshots.extend(Self::read_index(&path).await?);
+        }
+        snapshots.sort_by_key(|snapshot| snapshot.timestamp);
+
+        let mut usage = Vec::with_capacity(snapshots.len());
+        for (index, snapshot) in snapshots.iter().enumerate() {
+            let size = match &snapshot.hash {
+                Some(hash)
+                    if !snapshots[index + 1..]
+                        .iter()
+                        .any(|other| other.hash.as_ref() == Some(hash)) =>
+                {
+                    tokio::fs::metadata(self.object_path(hash))
+                        .await
+                        .map(|metadata| metadata.len())
+                        .unwrap_or_default()
+                }
+                _ => 0,
+            };
+            usage.push((snapshot.clone(), size));
+        }
+        Ok(usage)
+    }
 
-        // Check if the `snapshot_dir` exists
-        if !ForgeFS::exists(&snapshot_dir) {
-            return Err(anyhow::anyhow!("No snapshots found for {:?}", path));
+    /// Removes the snapshots of any workspace, without restoring them, along
+    /// with the contents no other snapshot uses
+    pub async fn remove(&self, ids: &[SnapshotId]) -> Result<()> {
+        let _guard = self.lock.lock().await;
+        let mut removed = Vec::new();
+        for path in self.index_paths().await? {
+            let (matching, kept): (Vec<_>, Vec<_>) = Self::read_index(&path)
+                .await?
+                .into_iter()
+                .partition(|snapshot| ids.contains(&snapshot.id));
+            if !matching.is_empty() {
+                Self::write_index(&path, &kept).await?;
+                removed.extend(matching);
+            }
         }
+        self.remove_unused(removed).await
+    }
 
-        // Retrieve the latest snapshot path
-        let snapshot_path = Self::find_recent_snapshot(&snapshot_dir)
+    /// Restores a file from its most recent snapshot
+    pub async fn undo_snapshot(&self, path: PathBuf) -> Result<()> {
+        let path = Snapshot::create(path.clone())?.path;
+        self.restore_where(|snapshot| snapshot.path == path)
             .await?
-            .context(format!("No valid snapshots found for {path:?}"))?;
+            .context(format!("No snapshots found for {path:?}"))?;
+        Ok(())
+    }
 
-        // Restore the content
-        let content = ForgeFS::read(&snapshot_path).await?;
-        ForgeFS::write(&path, content).await?;
+    /// Restores the file of the workspace that was changed most recently from
+    /// its snapshot
+    pub async fn undo_last(&self) -> Result<Snapshot> {
+        self.restore_where(|_| true)
+            .await?
+            .context("No snapshots found")
+    }
 
-        // Remove the used snapshot
-        ForgeFS::remove_file(&snapshot_path).await?;
+    /// Restores a file to the content it had when the snapshot was taken.
+    /// The more recent snapshots of the file are discarded.
+    pub async fn restore(&self, id: &SnapshotId) -> Result<Snapshot> {
+        self.restore_where(|snapshot| snapshot.id == *id)
+            .await?
+            .context(format!("Snapshot {id} not found"))
+    }
 
-        Ok(())
+    /// Restores the most recent snapshot of the workspace matching the
+    /// predicate and removes it from the index, along with the more recent
+    /// snapshots of the same file
+    async fn restore_where(
+        &self,
+        predicate: impl Fn(&Snapshot) -> bool,
+    ) -> Result<Option<Snapshot>> {
+        let _guard = self.lock.lock().await;
+        let index_path = self.index_path();
+        let snapshots = Self::read_index(&index_path).await?;
+        let Some(position) = snapshots.iter().rposition(predicate) else {
+            return Ok(None);
+        };
+        let snapshot = snapshots[position].clone();
+
+        let path = Path::new(&snapshot.path);
+        match &snapshot.hash {
+            Some(hash) => {
+                let content = ForgeFS::read(self.object_path(hash))
+                    .await
+                    .with_context(|| format!("Content of snapshot {} is missing", snapshot.id))?;
+                if let Some(parent) = path.parent() {
+                    ForgeFS::create_dir_all(parent).await?;
+                }
+                ForgeFS::write(path, content).await?;
+            }
+            None if ForgeFS::exists(path) => ForgeFS::remove_file(path).await?,
+            None => {}
+        }
+
+        let (removed, kept): (Vec<_>, Vec<_>) = snapshots
+            .into_iter()
+            .enumerate()
+            .partition(|(index, other)| *index >= position && other.path == snapshot.path);
+        let kept = kept.into_iter().map(|(_, other)| other).collect::<Vec<_>>();
+        Self::write_index(&index_path, &kept).await?;
+        self.remove_unused(removed.into_iter().map(|(_, removed)| removed).collect())
+            .await?;
+
+        Ok(Some(snapshot))
     }
 }
 
@@ -107,7 +310,7 @@ mod tests {
                 .canonicalize()
                 .unwrap_or_else(|_| temp_dir.path().to_path_buf());
             let test_file = temp_path.join("test.txt");
-            let service = SnapshotService::new(snapshots_dir.clone());
+            let service = SnapshotService::new(snapshots_dir.clone(), temp_path);
 
             Ok(Self {
                 _temp_dir: temp_dir,
@@ -146,7 +349,8 @@ mod tests {
         let snapshot = ctx.create_snapshot().await?;
 
         // Assert
-        let snapshot_content = ForgeFS::read(&snapshot.path).await?;
+        let object = ctx.service.object_path(snapshot.hash.as_deref().unwrap());
+        let snapshot_content = ForgeFS::read(&object).await?;
         assert_eq!(String::from_utf8(snapshot_content)?, test_content);
 
         Ok(())
@@ -252,4 +456,143 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_undo_snapshot_of_created_file() -> Result<()> {
+        // Arrange
+        let ctx = TestContext::new().await?;
+
+        // Act
+        ctx.create_snapshot().await?;
+        ctx.write_content("New file").await?;
+        ctx.undo_snapshot().await?;
+
+        // Assert
+        assert!(!ForgeFS::exists(&ctx.test_file));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_undo_last_across_files() -> Result<()> {
+        // Arrange
+        let ctx = TestContext::new().await?;
+        let other_file = ctx.test_file.with_file_name("other.txt");
+        ctx.write_content("Initial content").await?;
+        ForgeFS::write(&other_file, "Other content").await?;
+
+        // Act
+        ctx.create_snapshot().await?;
+        ctx.write_content("Modified content").await?;
+        ctx.service.create_snapshot(other_file.clone()).await?;
+        ForgeFS::write(&other_file, "Modified other content").await?;
+        let actual = ctx.service.undo_last().await?;
+
+        // Assert
+        assert_eq!(actual.path, other_file.display().to_string());
+        assert_eq!(ForgeFS::read_utf8(&other_file).await?, "Other content");
+        assert_eq!(ctx.read_content().await?, "Modified content");
+        assert_eq!(ctx.service.list().await?.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_restore_discards_more_recent_snapshots() -> Result<()> {
+        // Arrange
+        let ctx = TestContext::new().await?;
+
+        // Act
+        ctx.write_content("Initial content").await?;
+        let first = ctx.create_snapshot().await?;
+        ctx.write_content("Second content").await?;
+        ctx.create_snapshot().await?;
+        ctx.write_content("Final content").await?;
+        ctx.service.restore(&first.id).await?;
+
+        // Assert
+        assert_eq!(ctx.read_content().await?, "Initial content");
+        assert!(ctx.service.list().await?.is_empty());
+        assert!(ctx.service.undo_last().await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_content_of_snapshots() -> Result<()> {
+        // Arrange
+        let ctx = TestContext::new().await?;
+
+        // Act
+        let created = ctx.create_snapshot().await?;
+        ctx.write_content("Initial content").await?;
+        let modified = ctx.create_snapshot().await?;
+        ctx.write_content("Modified content").await?;
+
+        // Assert
+        assert_eq!(ctx.service.content(&created).await?, None);
+        assert_eq!(
+            ctx.service.content(&modified).await?,
+            Some(b"Initial content".to_vec())
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_remove_frees_contents_no_longer_used() -> Result<()> {
+        // Arrange
+        let ctx = TestContext::new().await?;
+
+        // Act
+        ctx.write_content("Shared content").await?;
+        let first = ctx.create_snapshot().await?;
+        ctx.create_snapshot().await?;
+        ctx.write_content("Other content").await?;
+        let third = ctx.create_snapshot().await?;
+        let usage = ctx
+            .service
+            .usage()
+            .await?
+            .into_iter()
+            .map(|(_, size)| size)
+            .collect::<Vec<_>>();
+        ctx.service.remove(&[first.id, third.id]).await?;
+
+        // Assert
+        assert_eq!(usage, vec![0, 14, 13]);
+        assert_eq!(ctx.service.list().await?.len(), 1);
+        assert!(ForgeFS::exists(
+            ctx.service.object_path(first.hash.as_deref().unwrap())
+        ));
+        assert!(!ForgeFS::exists(
+            ctx.service.object_path(third.hash.as_deref().unwrap())
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_undo_last_is_scoped_to_the_workspace() -> Result<()> {
+        // Arrange
+        let ctx = TestContext::new().await?;
+        let other = SnapshotService::new(ctx._snapshots_dir.clone(), PathBuf::from("/elsewhere"));
+        ctx.write_content("Shared content").await?;
+
+        // Act
+        let snapshot = ctx.create_snapshot().await?;
+        other.create_snapshot(ctx.test_file.clone()).await?;
+        ctx.write_content("Modified content").await?;
+        ctx.service.undo_last().await?;
+
+        // Assert
+        assert_eq!(ctx.read_content().await?, "Shared content");
+        assert!(ctx.service.undo_last().await.is_err());
+        assert_eq!(other.list().await?.len(), 1);
+        assert!(ForgeFS::exists(
+            ctx.service.object_path(snapshot.hash.as_deref().unwrap())
+        ));
+
+        Ok(())
+    }
 }
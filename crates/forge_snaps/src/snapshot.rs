@@ -1,10 +1,9 @@
 use std::fmt::{Display, Formatter};
-use std::hash::Hasher;
 use std::path::PathBuf;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use forge_fs::ForgeFS;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
 /// A newtype for snapshot IDs, internally using UUID
@@ -60,6 +59,11 @@ pub struct Snapshot {
 
     /// Original file path that is being processed
     pub path: String,
+
+    /// Hash of the content of the file, under which the content is stored.
+    /// Not set when the file didn't exist, restoring the snapshot removes it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
 }
 
 impl Snapshot {
@@ -82,40 +86,14 @@ impl Snapshot {
             id: SnapshotId::new(),
             timestamp,
             path: path.display().to_string(),
+            hash: None,
         })
     }
+}
 
-    /// Create a hash of a file path for storage
-    pub fn path_hash(&self) -> String {
-        let mut hasher = fnv_rs::Fnv64::default();
-        hasher.write(self.path.as_bytes());
-        format!("{:x}", hasher.finish())
-    }
-
-    /// Create a snapshot filename from a path and timestamp
-    pub fn snapshot_path(&self, cwd: Option<PathBuf>) -> PathBuf {
-        // Convert Duration to SystemTime then to a formatted string
-        let datetime = UNIX_EPOCH + self.timestamp;
-        // Format: YYYY-MM-DD_HH-MM-SS-nnnnnnnnn (including nanoseconds)
-        let formatted_time = chrono::DateTime::<chrono::Utc>::from(datetime)
-            .format("%Y-%m-%d_%H-%M-%S-%9f")
-            .to_string();
-
-        let filename = format!("{formatted_time}.snap");
-        let path = PathBuf::from(self.path_hash()).join(PathBuf::from(filename));
-        if let Some(cwd) = cwd {
-            cwd.join(path)
-        } else {
-            path
-        }
-    }
-
-    pub async fn save(&self, path: Option<PathBuf>) -> anyhow::Result<()> {
-        let content = ForgeFS::read(&self.path).await?;
-        let path = self.snapshot_path(path);
-        ForgeFS::write(path, content).await?;
-        Ok(())
-    }
+/// Hashes the content of a file, to address it in the snapshot store
+pub(crate) fn content_hash(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
 }
 
 #[cfg(test)]
@@ -132,6 +110,13 @@ mod tests {
         assert!(snapshot.timestamp.as_secs() > 0);
         // Should use the original absolute path since canonicalize fails
         assert_eq!(snapshot.path, nonexistent_path.display().to_string());
+        assert_eq!(snapshot.hash, None);
+    }
+
+    #[test]
+    fn test_content_hash_is_stable() {
+        assert_eq!(content_hash(b"hello"), content_hash(b"hello"));
+        assert_ne!(content_hash(b"hello"), content_hash(b"world"));
     }
 
     #[test]
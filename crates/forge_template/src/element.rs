@@ -37,6 +37,13 @@ impl Element {
         self
     }
 
+    /// Sets content that is already HTML, which is rendered without being
+    /// escaped
+    pub fn html(mut self, html: impl ToString) -> Self {
+        self.text = Some(html.to_string());
+        self
+    }
+
     pub fn cdata(mut self, text: impl ToString) -> Self {
         self.text = Some(format!("<![CDATA[{}]]>", text.to_string()));
         self
@@ -214,6 +221,13 @@ mod test {
         assert_eq!(actual, expected);
     }
     #[test]
+    fn test_element_with_html() {
+        let html = Element::new("pre").html("<span>fn</span> main");
+        let actual = html.render();
+        let expected = "<pre><span>fn</span> main</pre>";
+        assert_eq!(actual, expected);
+    }
+    #[test]
     fn test_element_with_css_style_classes() {
         let html = Element::new("div.foo.bar");
         let actual = html.render();
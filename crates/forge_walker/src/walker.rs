@@ -1,9 +1,14 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::SystemTime;
 
 use anyhow::{Context, Result};
 use derive_setters::Setters;
-use ignore::WalkBuilder;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{DirEntry, ParallelVisitor, ParallelVisitorBuilder, WalkBuilder, WalkState};
 use tokio::task::spawn_blocking;
 
 #[derive(Clone, Debug)]
@@ -11,6 +16,8 @@ pub struct File {
     pub path: String,
     pub file_name: Option<String>,
     pub size: u64,
+    /// Time of the last modification, when the platform records it
+    pub modified: Option<SystemTime>,
 }
 
 impl File {
@@ -41,14 +48,43 @@ pub struct Walker {
 
     /// Whether to skip binary files
     skip_binary: bool,
+
+    /// Patterns of the only files to walk, written like the lines of a
+    /// `.gitignore`. Every file is walked when empty.
+    include: Vec<String>,
+
+    /// Patterns of the files and directories to skip, written like the lines
+    /// of a `.gitignore`
+    exclude: Vec<String>,
+
+    /// Whether to follow symbolic links
+    follow_symlinks: bool,
+
+    /// Directory the include and exclude patterns are relative to, the base
+    /// directory by default or when it's outside of it
+    #[setters(strip_option)]
+    root: Option<PathBuf>,
 }
 
+/// Ignore file with the patterns of the paths that only Forge should skip,
+/// written like a `.gitignore`
+const FORGE_IGNORE_FILE: &str = ".forgeignore";
+
 const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024; // 1MB
 const DEFAULT_MAX_FILES: usize = 100;
 const DEFAULT_MAX_TOTAL_SIZE: u64 = 10 * 1024 * 1024; // 10MB
 const DEFAULT_MAX_DEPTH: usize = 5;
 const DEFAULT_MAX_BREADTH: usize = 10;
 
+/// Bytes at the start of a file looked at to tell whether it's binary, as git
+/// does
+const BINARY_SNIFF_SIZE: u64 = 8000;
+
+/// How many times the limits on the files and their total size the walk
+/// accepts before it stops, so that the files kept in the order of their
+/// paths are mostly the same whatever order the threads visit them in
+const LIMIT_MARGIN: u64 = 2;
+
 impl Walker {
     /// Creates a new Walker instance with all settings set to conservative
     /// values.
@@ -61,6 +97,10 @@ impl Walker {
             max_files: DEFAULT_MAX_FILES,
             max_total_size: DEFAULT_MAX_TOTAL_SIZE,
             skip_binary: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            follow_symlinks: false,
+            root: None,
         }
     }
 
@@ -76,6 +116,10 @@ impl Walker {
             max_files: usize::MAX,
             max_total_size: u64::MAX,
             skip_binary: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            follow_symlinks: false,
+            root: None,
         }
     }
 }
@@ -104,101 +148,280 @@ impl Walker {
         }
     }
 
+    /// Checks the start of a file for a NUL byte, which text files don't
+    /// have, for the binary files whose extension isn't known
+    fn has_binary_content(path: &std::path::Path) -> bool {
+        let mut sample = Vec::new();
+        std::fs::File::open(path)
+            .and_then(|file| file.take(BINARY_SNIFF_SIZE).read_to_end(&mut sample))
+            .is_ok_and(|_| sample.contains(&0))
+    }
+
+    /// Matcher of patterns written like the lines of a `.gitignore`, relative
+    /// to the root directory
+    fn matcher(&self, patterns: &[String]) -> Result<Gitignore> {
+        let root = self
+            .root
+            .as_ref()
+            .filter(|root| self.cwd.starts_with(root))
+            .unwrap_or(&self.cwd);
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in patterns {
+            builder
+                .add_line(None, pattern)
+                .with_context(|| format!("Invalid walker pattern: {pattern}"))?;
+        }
+        Ok(builder.build()?)
+    }
+
     /// Blocking function to scan filesystem. Use this when you already have
-    /// a runtime or want to avoid spawning a new one.
+    /// a runtime or want to avoid spawning a new one. The directories are
+    /// walked in parallel until the files accepted pass the limits on their
+    /// number or total size by a margin, and the limits are then applied to
+    /// the files in the order of their paths. They're sorted by path.
     pub fn get_blocking(&self) -> Result<Vec<File>> {
-        let mut files = Vec::new();
-        let mut total_size = 0u64;
-        let mut dir_entries: HashMap<String, usize> = HashMap::new();
-        let mut file_count = 0;
+        let include = self.matcher(&self.include)?;
+        let exclude = self.matcher(&self.exclude)?;
 
-        // TODO: Convert to async and return a stream
         let walk = WalkBuilder::new(&self.cwd)
             .standard_filters(true) // use standard ignore filters.
+            // honor .gitignore and the global git excludes outside of git repositories too
+            .require_git(false)
+            .add_custom_ignore_filename(FORGE_IGNORE_FILE)
             .max_depth(Some(self.max_depth))
-            // TODO: use build_parallel() for better performance
-            .build();
+            .follow_links(self.follow_symlinks)
+            // skip the excluded directories without walking their content
+            .filter_entry(move |entry| {
+                let is_dir = entry
+                    .file_type()
+                    .is_some_and(|file_type| file_type.is_dir());
+                !exclude.matched(entry.path(), is_dir).is_ignore()
+            })
+            .build_parallel();
+
+        let collected = Mutex::new(Collected::default());
+        let accepted = Accepted::default();
+        walk.visit(&mut Visitors {
+            walker: self,
+            include: &include,
+            collected: &collected,
+            accepted: &accepted,
+        });
+
+        let collected = collected
+            .into_inner()
+            .map_err(|_| anyhow::anyhow!("A walker thread panicked"))?;
+        if let Some(error) = collected.error {
+            return Err(error);
+        }
+        let mut files = collected.files;
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        let mut files = self.limit(files);
+
+        // Keep only the directories leading to an included file, the root
+        // directory having an empty path
+        if !include.is_empty() {
+            let included = files
+                .iter()
+                .filter(|file| !file.is_dir())
+                .map(|file| file.path.clone())
+                .collect::<Vec<_>>();
+            files.retain(|file| {
+                let dir = file.path.trim_start_matches('/');
+                !file.is_dir() || included.iter().any(|path| path.starts_with(dir))
+            });
+        }
 
-        'walk_loop: for entry in walk.flatten() {
-            let path = entry.path();
+        Ok(files)
+    }
 
-            // Calculate depth relative to base directory
-            let depth = path
-                .strip_prefix(&self.cwd)
-                .map(|p| p.components().count())
-                .unwrap_or(0);
+    /// Turns an entry of the walk into a file unless it's filtered out
+    fn visit(&self, path: &Path, include: &Gitignore) -> Result<Option<File>> {
+        // Calculate depth relative to base directory
+        let depth = path
+            .strip_prefix(&self.cwd)
+            .map(|p| p.components().count())
+            .unwrap_or(0);
 
-            if depth > self.max_depth {
-                continue;
-            }
+        if depth > self.max_depth {
+            return Ok(None);
+        }
 
-            // Handle breadth limit
-            if let Some(parent) = path.parent() {
-                let parent_path = parent.to_string_lossy().to_string();
-                let entry_count = dir_entries.entry(parent_path).or_insert(0);
-                *entry_count += 1;
+        let is_dir = path.is_dir();
 
-                if *entry_count > self.max_breadth {
-                    continue;
-                }
-            }
+        if !is_dir
+            && !include.is_empty()
+            && !include.matched_path_or_any_parents(path, false).is_ignore()
+        {
+            return Ok(None);
+        }
 
-            let is_dir = path.is_dir();
+        // Skip binary files if configured
+        if self.skip_binary
+            && !is_dir
+            && (Self::is_likely_binary(path) || Self::has_binary_content(path))
+        {
+            return Ok(None);
+        }
 
-            // Skip binary files if configured
-            if self.skip_binary && !is_dir && Self::is_likely_binary(path) {
-                continue;
-            }
+        let metadata = match path.metadata() {
+            Ok(meta) => meta,
+            Err(_) => return Ok(None), // Skip files we can't read metadata for
+        };
 
-            let metadata = match path.metadata() {
-                Ok(meta) => meta,
-                Err(_) => continue, // Skip files we can't read metadata for
-            };
+        let file_size = metadata.len();
 
-            let file_size = metadata.len();
+        // Skip files that exceed size limit
+        if !is_dir && file_size > self.max_file_size {
+            return Ok(None);
+        }
 
-            // Skip files that exceed size limit
-            if !is_dir && file_size > self.max_file_size {
-                continue;
-            }
+        let relative_path = path
+            .strip_prefix(&self.cwd)
+            .with_context(|| format!("Failed to strip prefix from path: {}", path.display()))?;
+        let path_string = relative_path.to_string_lossy().to_string();
 
-            // Check total size limit
-            if total_size + file_size > self.max_total_size {
-                break 'walk_loop;
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string());
+
+        // Ensure directory paths end with '/' for is_dir() function
+        let path_string = if is_dir {
+            format!("{path_string}/")
+        } else {
+            path_string
+        };
+
+        Ok(Some(File {
+            path: path_string,
+            file_name,
+            size: file_size,
+            modified: metadata.modified().ok(),
+        }))
+    }
+
+    /// Keeps the files sorted by path up to the limits on the entries of a
+    /// directory, the number of files and their total size
+    fn limit(&self, files: Vec<File>) -> Vec<File> {
+        let mut dir_entries: HashMap<PathBuf, usize> = HashMap::new();
+        let mut total_size = 0;
+        let mut file_count = 0;
+        let mut limited = Vec::new();
+        for file in files {
+            // Handle breadth limit
+            if let Some(parent) = Path::new(file.path.trim_end_matches('/')).parent() {
+                let entry_count = dir_entries.entry(parent.to_path_buf()).or_insert(0);
+                *entry_count += 1;
+                if *entry_count > self.max_breadth {
+                    continue;
+                }
             }
 
-            // Check if we've hit the file count limit (only count non-directories)
-            if !is_dir {
+            if !file.is_dir() {
                 file_count += 1;
-                if file_count > self.max_files {
-                    break 'walk_loop;
+                total_size += file.size;
+                if file_count > self.max_files || total_size > self.max_total_size {
+                    break;
                 }
             }
+            limited.push(file);
+        }
+        limited
+    }
+}
 
-            let relative_path = path
-                .strip_prefix(&self.cwd)
-                .with_context(|| format!("Failed to strip prefix from path: {}", path.display()))?;
-            let path_string = relative_path.to_string_lossy().to_string();
+/// Entries accepted by the threads of a walk
+#[derive(Default)]
+struct Collected {
+    files: Vec<File>,
+    error: Option<anyhow::Error>,
+}
 
-            let file_name = path
-                .file_name()
-                .map(|name| name.to_string_lossy().to_string());
+/// Number and total size of the files accepted by all the threads of a walk
+#[derive(Default)]
+struct Accepted {
+    files: AtomicUsize,
+    size: AtomicU64,
+}
 
-            // Ensure directory paths end with '/' for is_dir() function
-            let path_string = if is_dir {
-                format!("{path_string}/")
-            } else {
-                path_string
-            };
+impl Accepted {
+    /// Counts a file accepted, returning whether the walk is past the limits
+    /// of the walker by the margin
+    fn add(&self, file: &File, walker: &Walker) -> bool {
+        let files = self.files.fetch_add(1, Ordering::Relaxed) + 1;
+        let size = self.size.fetch_add(file.size, Ordering::Relaxed) + file.size;
+        let max_files = walker
+            .max_files
+            .saturating_mul(usize::try_from(LIMIT_MARGIN).unwrap_or(usize::MAX));
+        let max_total_size = walker.max_total_size.saturating_mul(LIMIT_MARGIN);
+        files > max_files || size > max_total_size
+    }
+}
 
-            files.push(File { path: path_string, file_name, size: file_size });
+/// Builds the visitor of each thread of a walk
+struct Visitors<'s> {
+    walker: &'s Walker,
+    include: &'s Gitignore,
+    collected: &'s Mutex<Collected>,
+    accepted: &'s Accepted,
+}
+
+impl<'s> ParallelVisitorBuilder<'s> for Visitors<'s> {
+    fn build(&mut self) -> Box<dyn ParallelVisitor + 's> {
+        Box::new(Visitor {
+            walker: self.walker,
+            include: self.include,
+            collected: self.collected,
+            accepted: self.accepted,
+            files: Vec::new(),
+            error: None,
+        })
+    }
+}
+
+/// Visitor of a thread of a walk, keeping the entries it accepts to itself
+/// until the walk is over so that the threads don't wait for one another
+struct Visitor<'s> {
+    walker: &'s Walker,
+    include: &'s Gitignore,
+    collected: &'s Mutex<Collected>,
+    accepted: &'s Accepted,
+    files: Vec<File>,
+    error: Option<anyhow::Error>,
+}
 
-            if !is_dir {
-                total_size += file_size;
+impl ParallelVisitor for Visitor<'_> {
+    fn visit(&mut self, entry: Result<DirEntry, ignore::Error>) -> WalkState {
+        let Ok(entry) = entry else {
+            return WalkState::Continue;
+        };
+        match self.walker.visit(entry.path(), self.include) {
+            Ok(Some(file)) => {
+                let past_limits = !file.is_dir() && self.accepted.add(&file, self.walker);
+                self.files.push(file);
+                if past_limits {
+                    WalkState::Quit
+                } else {
+                    WalkState::Continue
+                }
+            }
+            Ok(None) => WalkState::Continue,
+            Err(error) => {
+                self.error = Some(error);
+                WalkState::Quit
             }
         }
+    }
+}
 
-        Ok(files)
+impl Drop for Visitor<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut collected) = self.collected.lock() {
+            collected.files.append(&mut self.files);
+            if let Some(error) = self.error.take() {
+                collected.error = Some(error);
+            }
+        }
     }
 }
 
@@ -331,6 +554,29 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_walker_filters_binary_content() {
+        let fixture = fixtures::Fixture::default();
+        fixture.add_file("notes", "plain text").unwrap();
+        fixture.add_file("image.dat", "\u{0}\u{1}\u{2}").unwrap();
+
+        let actual = Walker::min_all()
+            .cwd(fixture.as_path().to_path_buf())
+            .skip_binary(true)
+            .get()
+            .await
+            .unwrap();
+
+        let expected = vec!["notes"];
+        let actual_files: Vec<_> = actual
+            .iter()
+            .filter(|f| !f.is_dir())
+            .map(|f| f.path.as_str())
+            .collect();
+
+        assert_eq!(actual_files, expected);
+    }
+
     #[tokio::test]
     async fn test_walker_enforces_directory_breadth_limit() {
         let (fixture, _) =
@@ -354,6 +600,87 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_walker_stops_at_file_limit() {
+        let (fixture, _) = fixtures::create_file_collection(50, "file").unwrap();
+
+        let actual = Walker::max_all()
+            .cwd(fixture.path().to_path_buf())
+            .max_files(20usize)
+            .get()
+            .await
+            .unwrap();
+
+        let expected = 20;
+        assert_eq!(actual.iter().filter(|f| !f.is_dir()).count(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_walker_keeps_the_first_files_by_path_at_the_limit() {
+        let fixture = fixtures::Fixture::default();
+        for name in ["d.rs", "b.rs", "src/c.rs", "a.rs", "src/a.rs"] {
+            fixture.add_file(name, "").unwrap();
+        }
+
+        let actual = Walker::max_all()
+            .cwd(fixture.as_path().to_path_buf())
+            .max_files(3usize)
+            .get()
+            .await
+            .unwrap();
+
+        let expected = vec!["/", "a.rs", "b.rs", "d.rs", "src/"];
+        let actual: Vec<_> = actual.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_walk_stops_past_the_limits_by_the_margin() {
+        let walker = Walker::max_all().max_files(2usize);
+        let fixture = Accepted::default();
+        let file = File {
+            path: "a.rs".to_string(),
+            file_name: None,
+            size: 1,
+            modified: None,
+        };
+
+        let actual = (0..5)
+            .map(|_| fixture.add(&file, &walker))
+            .collect::<Vec<_>>();
+
+        let expected = vec![false, false, false, false, true];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_walker_sorts_files_by_path() {
+        let fixture = fixtures::Fixture::default();
+        fixture.add_file("src/lib.rs", "").unwrap();
+        fixture.add_file("src/bin/main.rs", "").unwrap();
+        fixture.add_file("README.md", "").unwrap();
+        fixture.add_file("docs/guide.md", "").unwrap();
+
+        let actual = Walker::max_all()
+            .cwd(fixture.as_path().to_path_buf())
+            .get()
+            .await
+            .unwrap();
+
+        let expected = vec![
+            "/",
+            "README.md",
+            "docs/",
+            "docs/guide.md",
+            "src/",
+            "src/bin/",
+            "src/bin/main.rs",
+            "src/lib.rs",
+        ];
+        let actual: Vec<_> = actual.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(actual, expected);
+    }
+
     #[tokio::test]
     async fn test_walker_enforces_directory_depth_limit() {
         let fixture = fixtures::create_directory_tree(DEFAULT_MAX_DEPTH + 3, "test.txt").unwrap();
@@ -456,6 +783,115 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_walker_respects_gitignore_and_forgeignore() {
+        let fixture = fixtures::Fixture::default();
+        fixture.add_file("src/main.rs", "fn main() {}").unwrap();
+        fixture
+            .add_file("target/debug/main.d", "target/debug/main")
+            .unwrap();
+        fixture
+            .add_file("node_modules/left-pad/index.js", "module.exports = {}")
+            .unwrap();
+        fixture
+            .add_file("fixtures/large.json", "{\"data\": []}")
+            .unwrap();
+        fixture
+            .add_file(".gitignore", "target/\nnode_modules/")
+            .unwrap();
+        fixture.add_file(FORGE_IGNORE_FILE, "fixtures/").unwrap();
+
+        let actual = Walker::max_all()
+            .cwd(fixture.as_path().to_path_buf())
+            .get()
+            .await
+            .unwrap();
+
+        let expected = vec!["src/main.rs"];
+        let actual_files: Vec<_> = actual
+            .iter()
+            .filter(|f| !f.is_dir())
+            .map(|f| f.path.as_str())
+            .collect();
+
+        assert_eq!(
+            actual_files, expected,
+            "Walker should exclude files listed in .gitignore and .forgeignore"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_walker_include_and_exclude() {
+        let fixture = fixtures::Fixture::default();
+        fixture.add_file("README.md", "# Monorepo").unwrap();
+        fixture
+            .add_file("services/api/src/main.rs", "fn main() {}")
+            .unwrap();
+        fixture
+            .add_file("services/api/vendor/lib.rs", "pub fn lib() {}")
+            .unwrap();
+        fixture
+            .add_file("services/web/index.ts", "export {}")
+            .unwrap();
+
+        let actual = Walker::max_all()
+            .cwd(fixture.as_path().to_path_buf())
+            .include(vec!["services/api/".to_string()])
+            .exclude(vec!["vendor/".to_string()])
+            .get()
+            .await
+            .unwrap();
+
+        let mut expected = vec![
+            "/",
+            "services/",
+            "services/api/",
+            "services/api/src/",
+            "services/api/src/main.rs",
+        ];
+        expected.sort();
+        let mut actual: Vec<_> = actual.iter().map(|f| f.path.as_str()).collect();
+        actual.sort();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_walker_patterns_relative_to_root() {
+        let fixture = fixtures::Fixture::default();
+        fixture
+            .add_file("services/api/src/main.rs", "fn main() {}")
+            .unwrap();
+        fixture
+            .add_file("services/api/vendor/lib.rs", "pub fn lib() {}")
+            .unwrap();
+
+        let actual = Walker::max_all()
+            .cwd(fixture.as_path().join("services"))
+            .root(fixture.as_path().to_path_buf())
+            .exclude(vec!["/services/api/vendor/".to_string()])
+            .get()
+            .await
+            .unwrap();
+
+        let actual: Vec<_> = actual.iter().map(|f| f.path.as_str()).collect();
+        let expected = vec!["/", "api/", "api/src/", "api/src/main.rs"];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_walker_invalid_pattern() {
+        let fixture = fixtures::Fixture::default();
+
+        let actual = Walker::max_all()
+            .cwd(fixture.as_path().to_path_buf())
+            .exclude(vec!["src/[".to_string()])
+            .get()
+            .await;
+
+        assert!(actual.is_err());
+    }
+
     #[test]
     fn test_is_likely_binary_detects_binary_files() {
         use std::path::Path;